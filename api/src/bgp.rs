@@ -0,0 +1,454 @@
+//! Matching of [basic graph patterns] against [graphs](crate::graph::Graph)
+//! and [datasets](crate::dataset::Dataset).
+//!
+//! A basic graph pattern (BGP) is a list of triple (or quad) patterns,
+//! i.e. triples (or quads) where some terms are [variables](crate::term::VarName).
+//! Matching a BGP against a graph produces all the ways of binding its variables to terms
+//! such that every pattern, once its variables are replaced, is in the graph.
+//! Each of these ways is called a [solution](BgpSolution).
+//!
+//! This covers the most common needs of querying a graph without a full-fledged SPARQL engine.
+//! See [`Graph::match_bgp`](crate::graph::Graph::match_bgp)
+//! and [`Dataset::match_bgp`](crate::dataset::Dataset::match_bgp).
+//!
+//! # Join order
+//!
+//! Patterns are not evaluated in the order in which they are given.
+//! Instead, each time a pattern must be chosen, the one with the lowest estimated number of matches
+//! (given the variables bound so far) is evaluated first.
+//! Estimates are provided by [`Graph::estimate_matching`](crate::graph::Graph::estimate_matching)
+//! (resp. [`Dataset::estimate_matching`](crate::dataset::Dataset::estimate_matching)),
+//! which indexed implementations are expected to override.
+//! When no estimate is available, patterns with more bound terms are preferred,
+//! subjects being considered more selective than objects, and objects more than predicates.
+//!
+//! [basic graph patterns]: https://www.w3.org/TR/sparql11-query/#BasicGraphPatterns
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::term::matcher::{GraphNameMatcher, TermMatcher};
+use crate::term::{FromTerm, GraphName, SimpleTerm, Term};
+
+/// A solution of a basic graph pattern, mapping each variable name to a term.
+pub type BgpSolution<T> = HashMap<Arc<str>, T>;
+
+/// The fallible iterator of triples or quads matching a grounded pattern,
+/// each represented as an array of (optional) terms.
+pub(crate) type Matches<'a, U, E, const N: usize> =
+    Box<dyn Iterator<Item = Result<[Option<U>; N], E>> + 'a>;
+
+/// A position of a compiled pattern.
+#[derive(Clone, Debug)]
+enum Slot<'a> {
+    Const(SimpleTerm<'a>),
+    Var(usize),
+    DefaultGraph,
+}
+
+/// A position of a pattern, where bound variables have been replaced by their value.
+///
+/// As a [`TermMatcher`], [`Ground::Any`] matches any term.
+/// As a [`GraphNameMatcher`], [`Ground::Any`] matches any *named* graph
+/// (since variables can not be bound to the default graph).
+#[derive(Clone, Debug)]
+pub(crate) enum Ground<'a> {
+    Any,
+    Term(SimpleTerm<'a>),
+    DefaultGraph,
+}
+
+impl<'a> Ground<'a> {
+    /// The term of this position, if known.
+    pub(crate) fn term(&self) -> Option<&SimpleTerm<'a>> {
+        match self {
+            Ground::Term(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// The graph name of this position, if known.
+    pub(crate) fn graph_name(&self) -> Option<GraphName<&SimpleTerm<'a>>> {
+        match self {
+            Ground::Any => None,
+            Ground::Term(t) => Some(Some(t)),
+            Ground::DefaultGraph => Some(None),
+        }
+    }
+}
+
+impl<'a> TermMatcher for Ground<'a> {
+    type Term = SimpleTerm<'a>;
+
+    fn matches<T2: Term + ?Sized>(&self, term: &T2) -> bool {
+        match self {
+            Ground::Any => true,
+            Ground::Term(t) => Term::eq(t, term.borrow_term()),
+            Ground::DefaultGraph => false,
+        }
+    }
+
+    fn constant(&self) -> Option<&SimpleTerm<'a>> {
+        self.term()
+    }
+}
+
+impl<'a> GraphNameMatcher for Ground<'a> {
+    type Term = SimpleTerm<'a>;
+
+    fn matches<T2: Term + ?Sized>(&self, graph_name: GraphName<&T2>) -> bool {
+        match (self, graph_name) {
+            (Ground::Any, Some(_)) => true,
+            (Ground::Term(t), Some(g)) => Term::eq(t, g.borrow_term()),
+            (Ground::DefaultGraph, None) => true,
+            _ => false,
+        }
+    }
+
+    fn constant(&self) -> Option<GraphName<&SimpleTerm<'a>>> {
+        match self {
+            Ground::Any => None,
+            other => other.graph_name(),
+        }
+    }
+}
+
+/// A basic graph pattern where variables have been replaced by their index in `variables`.
+#[derive(Clone, Debug)]
+pub(crate) struct CompiledBgp<'a, const N: usize> {
+    variables: Vec<Arc<str>>,
+    patterns: Vec<[Slot<'a>; N]>,
+}
+
+impl<'a, const N: usize> CompiledBgp<'a, N> {
+    /// Compile the given patterns, where `None` stands for the default graph.
+    pub(crate) fn new<T, I>(patterns: I) -> Self
+    where
+        T: Term + 'a,
+        I: IntoIterator<Item = [Option<&'a T>; N]>,
+    {
+        let mut variables: Vec<Arc<str>> = vec![];
+        let mut compiled = vec![];
+        for pattern in patterns {
+            compiled.push(pattern.map(|t| match t {
+                None => Slot::DefaultGraph,
+                Some(t) => match t.variable() {
+                    None => Slot::Const(SimpleTerm::from_term_ref(t)),
+                    Some(name) => {
+                        let name = name.as_str();
+                        match variables.iter().position(|v| &v[..] == name) {
+                            Some(i) => Slot::Var(i),
+                            None => {
+                                variables.push(name.into());
+                                Slot::Var(variables.len() - 1)
+                            }
+                        }
+                    }
+                },
+            }));
+        }
+        CompiledBgp {
+            variables,
+            patterns: compiled,
+        }
+    }
+
+    /// Replace the bound variables of the given pattern, borrowing the values from `row`.
+    fn ground<'s, U: Term>(&'s self, pattern: usize, row: &'s [Option<U>]) -> [Ground<'s>; N] {
+        self.patterns[pattern].each_ref().map(|slot| match slot {
+            Slot::Const(t) => Ground::Term(t.as_simple()),
+            Slot::Var(i) => row[*i]
+                .as_ref()
+                .map_or(Ground::Any, |v| Ground::Term(v.as_simple())),
+            Slot::DefaultGraph => Ground::DefaultGraph,
+        })
+    }
+
+    /// Replace the bound variables of the given pattern, copying the values from `row`.
+    fn ground_owned<U: Term>(&self, pattern: usize, row: &[Option<U>]) -> [Ground<'a>; N] {
+        self.patterns[pattern].each_ref().map(|slot| match slot {
+            Slot::Const(t) => Ground::Term(t.clone()),
+            Slot::Var(i) => row[*i].as_ref().map_or(Ground::Any, |v| {
+                Ground::Term(SimpleTerm::from_term(v.borrow_term()))
+            }),
+            Slot::DefaultGraph => Ground::DefaultGraph,
+        })
+    }
+}
+
+/// Fallback estimate used when the underlying graph or dataset provides none.
+fn heuristic<const N: usize>(ground: &[Ground; N]) -> usize {
+    // bits removed from the estimate when s, p, o or g is bound, respectively
+    const SELECTIVITY: [u32; 4] = [20, 8, 16, 4];
+    let shift: u32 = ground
+        .iter()
+        .zip(SELECTIVITY)
+        .filter(|(g, _)| !matches!(g, Ground::Any))
+        .map(|(_, bits)| bits)
+        .sum();
+    (u32::MAX as usize) >> shift.min(31)
+}
+
+/// A partial solution, and the iterator of matches for the next pattern to join with it.
+struct Frame<'a, U, E, const N: usize> {
+    pattern: usize,
+    remaining: Vec<usize>,
+    row: Vec<Option<U>>,
+    matches: Matches<'a, U, E, N>,
+}
+
+/// The iterator returned by `match_bgp`.
+///
+/// It performs a depth-first nested-loop join,
+/// choosing the next pattern to evaluate for each partial solution.
+pub(crate) struct BgpMatcher<'a, U, E, F, G, const N: usize> {
+    bgp: CompiledBgp<'a, N>,
+    source: F,
+    estimate: G,
+    stack: Vec<Frame<'a, U, E, N>>,
+    empty_solution: bool,
+}
+
+impl<'a, U, E, F, G, const N: usize> BgpMatcher<'a, U, E, F, G, N>
+where
+    U: Term + Clone,
+    F: Fn([Ground<'a>; N]) -> Matches<'a, U, E, N>,
+    G: Fn(&[Ground<'_>; N]) -> Option<usize>,
+{
+    /// `source` returns the triples or quads matching a grounded pattern,
+    /// and `estimate` estimates their number (if possible).
+    pub(crate) fn new(bgp: CompiledBgp<'a, N>, source: F, estimate: G) -> Self {
+        let n = bgp.patterns.len();
+        let row = vec![None; bgp.variables.len()];
+        let mut ret = BgpMatcher {
+            bgp,
+            source,
+            estimate,
+            stack: vec![],
+            empty_solution: n == 0,
+        };
+        if n > 0 {
+            ret.push(row, (0..n).collect());
+        }
+        ret
+    }
+
+    /// Choose the next pattern to join with `row` among `remaining`, and push the corresponding frame.
+    fn push(&mut self, row: Vec<Option<U>>, mut remaining: Vec<usize>) {
+        let best = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, pattern)| {
+                let ground = self.bgp.ground(**pattern, &row);
+                (self.estimate)(&ground).unwrap_or_else(|| heuristic(&ground))
+            })
+            .map(|(i, _)| i)
+            .unwrap();
+        let pattern = remaining.swap_remove(best);
+        let matches = (self.source)(self.bgp.ground_owned(pattern, &row));
+        self.stack.push(Frame {
+            pattern,
+            remaining,
+            row,
+            matches,
+        });
+    }
+}
+
+impl<'a, U, E, F, G, const N: usize> Iterator for BgpMatcher<'a, U, E, F, G, N>
+where
+    U: Term + Clone,
+    F: Fn([Ground<'a>; N]) -> Matches<'a, U, E, N>,
+    G: Fn(&[Ground<'_>; N]) -> Option<usize>,
+{
+    type Item = Result<BgpSolution<U>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty_solution {
+            self.empty_solution = false;
+            return Some(Ok(BgpSolution::new()));
+        }
+        loop {
+            let frame = self.stack.last_mut()?;
+            let terms = match frame.matches.next() {
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+                Some(Err(err)) => {
+                    self.stack.clear();
+                    return Some(Err(err));
+                }
+                Some(Ok(terms)) => terms,
+            };
+            let mut row = frame.row.clone();
+            if !bind(&self.bgp.patterns[frame.pattern], terms, &mut row) {
+                continue;
+            }
+            if frame.remaining.is_empty() {
+                let solution = self
+                    .bgp
+                    .variables
+                    .iter()
+                    .cloned()
+                    .zip(row)
+                    .filter_map(|(var, value)| Some((var, value?)))
+                    .collect();
+                return Some(Ok(solution));
+            }
+            let remaining = frame.remaining.clone();
+            self.push(row, remaining);
+        }
+    }
+}
+
+/// Bind the variables of `pattern` to the corresponding `terms` in `row`.
+///
+/// Return false if a variable is already bound to a different term
+/// (which happens when a variable occurs several times in the same pattern).
+fn bind<U: Term, const N: usize>(
+    pattern: &[Slot; N],
+    terms: [Option<U>; N],
+    row: &mut [Option<U>],
+) -> bool {
+    for (slot, term) in pattern.iter().zip(terms) {
+        if let Slot::Var(i) = slot {
+            let Some(term) = term else {
+                return false;
+            };
+            match &row[*i] {
+                Some(value) => {
+                    if !Term::eq(value, term.borrow_term()) {
+                        return false;
+                    }
+                }
+                None => row[*i] = Some(term),
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::Dataset;
+    use crate::graph::Graph;
+    use crate::ns::rdf;
+    use crate::term::{IriRef, VarName};
+    use mownstr::MownStr;
+
+    const EX: &str = "http://example.org/";
+
+    fn iri(suffix: &str) -> SimpleTerm<'static> {
+        IriRef::new_unchecked(MownStr::from(format!("{EX}{suffix}"))).into_term()
+    }
+
+    fn var(name: &str) -> SimpleTerm<'static> {
+        VarName::new_unchecked(MownStr::from(name.to_string())).into_term()
+    }
+
+    fn graph() -> Vec<[SimpleTerm<'static>; 3]> {
+        vec![
+            [iri("alice"), rdf::type_.into_term(), iri("Person")],
+            [iri("bob"), rdf::type_.into_term(), iri("Person")],
+            [iri("carol"), rdf::type_.into_term(), iri("Robot")],
+            [iri("alice"), iri("knows"), iri("bob")],
+            [iri("alice"), iri("knows"), iri("carol")],
+            [iri("bob"), iri("knows"), iri("bob")],
+        ]
+    }
+
+    fn sorted_names<T: Term>(solutions: impl Iterator<Item = BgpSolution<T>>) -> Vec<String> {
+        let mut names: Vec<_> = solutions
+            .map(|s| {
+                let mut pairs: Vec<_> = s
+                    .iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "{k}={}",
+                            v.iri().unwrap().as_str().strip_prefix(EX).unwrap()
+                        )
+                    })
+                    .collect();
+                pairs.sort();
+                pairs.join(",")
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn join() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let bgp = [
+            [var("x"), iri("knows"), var("y")],
+            [var("y"), rdf::type_.into_term(), iri("Person")],
+        ];
+        let solutions = g.match_bgp(&bgp).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            sorted_names(solutions.into_iter()),
+            vec!["x=alice,y=bob", "x=bob,y=bob"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_variable() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let bgp = [[var("x"), iri("knows"), var("x")]];
+        let solutions = g.match_bgp(&bgp).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(sorted_names(solutions.into_iter()), vec!["x=bob"]);
+        Ok(())
+    }
+
+    #[test]
+    fn no_match() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let bgp = [
+            [var("x"), iri("knows"), var("y")],
+            [var("y"), iri("knows"), iri("alice")],
+        ];
+        assert_eq!(g.match_bgp(&bgp).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_bgp() -> Result<(), Box<dyn std::error::Error>> {
+        let g = graph();
+        let bgp: [[SimpleTerm; 3]; 0] = [];
+        let solutions = g.match_bgp(&bgp).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn dataset() -> Result<(), Box<dyn std::error::Error>> {
+        let d: Vec<([SimpleTerm; 3], GraphName<SimpleTerm>)> = vec![
+            ([iri("alice"), iri("knows"), iri("bob")], None),
+            ([iri("alice"), iri("knows"), iri("carol")], Some(iri("g1"))),
+            ([iri("bob"), iri("knows"), iri("carol")], Some(iri("g2"))),
+        ];
+        let in_default = [([var("x"), iri("knows"), var("y")], None)];
+        let solutions = d.match_bgp(&in_default).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(sorted_names(solutions.into_iter()), vec!["x=alice,y=bob"]);
+
+        let in_named = [
+            ([var("x"), iri("knows"), iri("carol")], Some(var("g"))),
+            ([iri("alice"), iri("knows"), var("x")], None),
+        ];
+        let solutions = d.match_bgp(&in_named).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(sorted_names(solutions.into_iter()), vec!["g=g2,x=bob"]);
+        Ok(())
+    }
+
+    #[test]
+    fn heuristic_prefers_bound_subjects() {
+        let s = [Ground::Term(iri("a")), Ground::Any, Ground::Any];
+        let p = [Ground::Any, Ground::Term(iri("a")), Ground::Any];
+        let po = [Ground::Any, Ground::Term(iri("a")), Ground::Term(iri("b"))];
+        assert!(heuristic(&s) < heuristic(&p));
+        assert!(heuristic(&po) < heuristic(&s));
+    }
+}
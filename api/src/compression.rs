@@ -0,0 +1,403 @@
+//! Transparent decompression and compression of RDF data.
+//!
+//! [`decompress`] wraps a [`BufRead`], detects the compression of its content from its first bytes,
+//! and decompresses it on the fly.
+//! It is used, for example, by the `parse_bufread_decompressed` functions of the parser modules.
+//! [`compress`] wraps a [`Write`], and compresses on the fly everything written to it.
+//!
+//! Each codec is enabled by a cargo feature:
+//! `gzip`, `bzip2` and `zstd`.
+//! When the feature of a codec is disabled,
+//! compressed data is [detected](Compression::from_magic) but can not be decompressed,
+//! and reading it fails with an [`Unsupported`](io::ErrorKind::Unsupported) error.
+//!
+//! ```
+//! # use sophia_api::compression::{compress, decompress, Compression};
+//! # use std::io::{Read, Write};
+//! let mut write = compress(vec![], None)?;
+//! write.write_all(b"<tag:s> <tag:p> <tag:o> .\n")?;
+//! let data = write.finish()?;
+//!
+//! let mut txt = String::new();
+//! decompress(&data[..]).read_to_string(&mut txt)?;
+//! assert_eq!(txt, "<tag:s> <tag:p> <tag:o> .\n");
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io::{self, BufRead, Chain, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A compression format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// [gzip](https://www.rfc-editor.org/rfc/rfc1952), requires the `gzip` feature
+    Gzip,
+    /// [bzip2](https://sourceware.org/bzip2/), requires the `bzip2` feature
+    Bzip2,
+    /// [Zstandard](https://www.rfc-editor.org/rfc/rfc8878), requires the `zstd` feature
+    Zstd,
+}
+
+impl Compression {
+    /// The maximum length of the magic bytes identifying a compression format.
+    const MAGIC_LEN: usize = 4;
+
+    /// Identify the compression format of some data, from its first bytes.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Identify a compression format from a file extension (ignoring case, with or without the leading dot).
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        [Compression::Gzip, Compression::Bzip2, Compression::Zstd]
+            .into_iter()
+            .find(|c| c.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Identify the compression format of a file from its extension,
+    /// and return it together with the path stripped from that extension.
+    ///
+    /// For example, `data.nt.gz` gives [`Gzip`](Compression::Gzip) and `data.nt`.
+    pub fn from_path(path: &Path) -> (Option<Self>, PathBuf) {
+        let compression = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension);
+        match compression {
+            Some(_) => (compression, path.with_extension("")),
+            None => (None, path.to_path_buf()),
+        }
+    }
+
+    /// The usual file extension of this compression format (without the leading dot).
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Bzip2 => "bz2",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Whether this compression format is supported (i.e. whether its feature is enabled).
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Bzip2 => cfg!(feature = "bzip2"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    fn unsupported(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{self:?} compression is not supported (enable the corresponding feature)"),
+        )
+    }
+}
+
+/// Wrap `read` so that its content is transparently decompressed,
+/// if it starts with the magic bytes of a [`Compression`] format.
+///
+/// Detection is performed lazily, on the first read.
+pub fn decompress<B: BufRead>(read: B) -> Decompress<B> {
+    Decompress {
+        state: DecompressState::Pending(Some(read)),
+    }
+}
+
+/// A [`BufRead`] decompressing the content of the underlying reader, if required.
+///
+/// See [`decompress`].
+pub struct Decompress<B> {
+    state: DecompressState<B>,
+}
+
+/// The underlying reader, prefixed with the bytes consumed to detect its compression.
+type Sniffed<B> = Chain<Cursor<Vec<u8>>, B>;
+
+enum DecompressState<B> {
+    Pending(Option<B>),
+    Plain(Sniffed<B>),
+    #[cfg(feature = "gzip")]
+    Gzip(io::BufReader<flate2::bufread::MultiGzDecoder<Sniffed<B>>>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(io::BufReader<bzip2::bufread::MultiBzDecoder<Sniffed<B>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(io::BufReader<zstd::stream::read::Decoder<'static, Sniffed<B>>>),
+}
+
+impl<B: BufRead> Decompress<B> {
+    /// The compression detected in the underlying reader, if any.
+    ///
+    /// Returns `None` before the first read.
+    pub fn compression(&self) -> Option<Compression> {
+        match self.state {
+            DecompressState::Pending(_) | DecompressState::Plain(_) => None,
+            #[cfg(feature = "gzip")]
+            DecompressState::Gzip(_) => Some(Compression::Gzip),
+            #[cfg(feature = "bzip2")]
+            DecompressState::Bzip2(_) => Some(Compression::Bzip2),
+            #[cfg(feature = "zstd")]
+            DecompressState::Zstd(_) => Some(Compression::Zstd),
+        }
+    }
+
+    /// The reader to read from, after detecting the compression if needed.
+    fn reader(&mut self) -> io::Result<&mut dyn BufRead> {
+        if let DecompressState::Pending(read) = &mut self.state {
+            let Some(mut read) = read.take() else {
+                return Err(io::Error::other("compression detection previously failed"));
+            };
+            let mut magic = vec![0; Compression::MAGIC_LEN];
+            let mut len = 0;
+            while len < magic.len() {
+                match read.read(&mut magic[len..]) {
+                    Ok(0) => break,
+                    Ok(n) => len += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            magic.truncate(len);
+            let compression = Compression::from_magic(&magic);
+            let sniffed = Cursor::new(magic).chain(read);
+            self.state = match compression {
+                None => DecompressState::Plain(sniffed),
+                #[cfg(feature = "gzip")]
+                Some(Compression::Gzip) => DecompressState::Gzip(io::BufReader::new(
+                    flate2::bufread::MultiGzDecoder::new(sniffed),
+                )),
+                #[cfg(feature = "bzip2")]
+                Some(Compression::Bzip2) => DecompressState::Bzip2(io::BufReader::new(
+                    bzip2::bufread::MultiBzDecoder::new(sniffed),
+                )),
+                #[cfg(feature = "zstd")]
+                Some(Compression::Zstd) => DecompressState::Zstd(io::BufReader::new(
+                    zstd::stream::read::Decoder::with_buffer(sniffed)?,
+                )),
+                #[allow(unreachable_patterns)]
+                Some(compression) => return Err(compression.unsupported()),
+            };
+        }
+        Ok(match &mut self.state {
+            DecompressState::Pending(_) => unreachable!(),
+            DecompressState::Plain(read) => read,
+            #[cfg(feature = "gzip")]
+            DecompressState::Gzip(read) => read,
+            #[cfg(feature = "bzip2")]
+            DecompressState::Bzip2(read) => read,
+            #[cfg(feature = "zstd")]
+            DecompressState::Zstd(read) => read,
+        })
+    }
+}
+
+impl<B: BufRead> Read for Decompress<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader()?.read(buf)
+    }
+}
+
+impl<B: BufRead> BufRead for Decompress<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader()?.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(read) = self.reader() {
+            read.consume(amt)
+        }
+    }
+}
+
+/// Wrap `write` so that everything written to it is compressed with the given format
+/// (or left uncompressed if `compression` is `None`).
+///
+/// The compressed stream is terminated by [`Compress::finish`], or when the wrapper is dropped
+/// (in which case errors are ignored).
+///
+/// Fails if the given compression format is not [supported](Compression::is_supported).
+pub fn compress<W: Write>(write: W, compression: Option<Compression>) -> io::Result<Compress<W>> {
+    let encoder = match compression {
+        None => Encoder::Plain(write),
+        #[cfg(feature = "gzip")]
+        Some(Compression::Gzip) => Encoder::Gzip(flate2::write::GzEncoder::new(
+            write,
+            flate2::Compression::default(),
+        )),
+        #[cfg(feature = "bzip2")]
+        Some(Compression::Bzip2) => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+            write,
+            bzip2::Compression::default(),
+        )),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Encoder::Zstd(zstd::stream::write::Encoder::new(write, 0)?),
+        #[allow(unreachable_patterns)]
+        Some(compression) => return Err(compression.unsupported()),
+    };
+    Ok(Compress {
+        encoder: Some(encoder),
+    })
+}
+
+/// A [`Write`] compressing everything written to it.
+///
+/// See [`compress`].
+pub struct Compress<W: Write> {
+    // only None after `finish` has been called
+    encoder: Option<Encoder<W>>,
+}
+
+enum Encoder<W: Write> {
+    Plain(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Compress<W> {
+    /// Terminate the compressed stream, and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        #[allow(clippy::infallible_destructuring_match)] // when no compression feature is enabled
+        let mut write = match self.encoder.take().unwrap() {
+            Encoder::Plain(write) => write,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?,
+        };
+        write.flush()?;
+        Ok(write)
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self.encoder.as_mut().unwrap() {
+            Encoder::Plain(write) => write,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(write) => write,
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(write) => write,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(write) => write,
+        }
+    }
+}
+
+impl<W: Write> Write for Compress<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl<W: Write> Drop for Compress<W> {
+    fn drop(&mut self) {
+        match &mut self.encoder {
+            None | Some(Encoder::Plain(_)) => {}
+            #[cfg(feature = "gzip")]
+            Some(Encoder::Gzip(encoder)) => {
+                let _ = encoder.try_finish();
+            }
+            #[cfg(feature = "bzip2")]
+            Some(Encoder::Bzip2(encoder)) => {
+                let _ = encoder.try_finish();
+            }
+            #[cfg(feature = "zstd")]
+            Some(Encoder::Zstd(encoder)) => {
+                let _ = encoder.do_finish();
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::BufReader;
+    use test_case::test_case;
+
+    const TXT: &str = "<tag:s> <tag:p> \"some text, repeated: some text\" .\n";
+
+    #[test_case(None)]
+    #[test_case(Some(Compression::Gzip))]
+    #[test_case(Some(Compression::Bzip2))]
+    #[test_case(Some(Compression::Zstd))]
+    fn roundtrip(compression: Option<Compression>) -> io::Result<()> {
+        let mut write = match compress(vec![], compression) {
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => {
+                assert!(!compression.unwrap().is_supported());
+                return Ok(());
+            }
+            res => res?,
+        };
+        write.write_all(TXT.as_bytes())?;
+        let data = write.finish()?;
+        assert_eq!(Compression::from_magic(&data), compression);
+
+        // one byte at a time, to check that detection does not rely on fill_buf
+        let mut read = decompress(BufReader::with_capacity(1, &data[..]));
+        let mut got = String::new();
+        read.read_to_string(&mut got)?;
+        assert_eq!(got, TXT);
+        assert_eq!(read.compression(), compression);
+        Ok(())
+    }
+
+    #[test]
+    fn short_plain() -> io::Result<()> {
+        for txt in ["", "a", "ab", "abc", "\x1f"] {
+            let mut got = String::new();
+            decompress(txt.as_bytes()).read_to_string(&mut got)?;
+            assert_eq!(got, txt);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        for (compression, magic) in [
+            (Compression::Gzip, &b"\x1f\x8b\x08\x00"[..]),
+            (Compression::Bzip2, &b"BZh9"[..]),
+            (Compression::Zstd, &b"\x28\xb5\x2f\xfd"[..]),
+        ] {
+            assert_eq!(Compression::from_magic(magic), Some(compression));
+            if !compression.is_supported() {
+                let err = decompress(magic).read_to_end(&mut vec![]).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+            }
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        let (c, path) = Compression::from_path(Path::new("/tmp/data.nt.GZ"));
+        assert_eq!(c, Some(Compression::Gzip));
+        assert_eq!(path, Path::new("/tmp/data.nt"));
+        let (c, path) = Compression::from_path(Path::new("/tmp/data.nq"));
+        assert_eq!(c, None);
+        assert_eq!(path, Path::new("/tmp/data.nq"));
+        assert_eq!(Compression::from_extension(".zst"), Some(Compression::Zstd));
+        assert_eq!(Compression::from_extension("bz2"), Some(Compression::Bzip2));
+    }
+}
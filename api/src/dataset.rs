@@ -7,8 +7,10 @@
 //! for different kinds of datasets,
 //! as well as a few implementations for them.
 
+use crate::bgp::{BgpMatcher, BgpSolution, CompiledBgp, Ground, Matches};
 use crate::graph::adapter::{DatasetGraph, PartialUnionGraph, UnionGraph};
-use crate::quad::{iter_spog, Quad};
+use patch::Patch;
+use crate::quad::{iter_spog, Quad, Spog};
 use crate::source::{IntoSource, QuadSource, StreamResult};
 use crate::term::matcher::{GraphNameMatcher, TermMatcher};
 use crate::term::{GraphName, SimpleTerm, Term};
@@ -17,6 +19,7 @@ use std::error::Error;
 
 mod _foreign_impl;
 pub mod adapter;
+pub mod patch;
 #[cfg(any(test, feature = "test_macro"))]
 #[macro_use]
 pub mod test;
@@ -196,6 +199,62 @@ pub trait Dataset {
             .map(|o| o.is_some())
     }
 
+    /// Estimate the number of quads matching the given subject, predicate, object and graph name,
+    /// where `None` matches any term (resp. any graph name).
+    ///
+    /// This is used by [`match_bgp`](Dataset::match_bgp) to choose the order in which patterns are evaluated,
+    /// so it must be cheap to compute, but only needs to be roughly accurate.
+    ///
+    /// The default implementation returns `None` (no estimate available);
+    /// indexed implementations are encouraged to override it.
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+        g: Option<GraphName<T>>,
+    ) -> Option<usize> {
+        let _ = (s, p, o, g);
+        None
+    }
+
+    /// An iterator visiting all the solutions of the given [basic graph pattern](crate::bgp) in this dataset.
+    ///
+    /// `bgp` is a list of quad patterns, where [variables](crate::term::VarName) may occur in any position.
+    /// Each solution maps every variable of `bgp` to a term,
+    /// in such a way that all quad patterns are in this dataset.
+    /// Note that a variable in the graph name position only matches named graphs,
+    /// while `None` in that position matches the default graph only.
+    /// Variables occurring inside quoted triples are not supported
+    /// (such quoted triples are treated as constants).
+    ///
+    /// See also [`Graph::match_bgp`](crate::graph::Graph::match_bgp).
+    fn match_bgp<'s, T>(
+        &'s self,
+        bgp: &'s [Spog<T>],
+    ) -> impl Iterator<Item = DResult<Self, BgpSolution<DTerm<'s, Self>>>> + 's
+    where
+        T: Term,
+        DTerm<'s, Self>: Clone,
+    {
+        let bgp = CompiledBgp::new(
+            bgp.iter()
+                .map(|([s, p, o], g)| [Some(s), Some(p), Some(o), g.as_ref()]),
+        );
+        BgpMatcher::new(
+            bgp,
+            move |[s, p, o, g]: [Ground<'s>; 4]| -> Matches<'s, DTerm<'s, Self>, Self::Error, 4> {
+                Box::new(self.quads_matching(s, p, o, g).map_ok(|q| {
+                    let ([s, p, o], g) = q.to_spog();
+                    [Some(s), Some(p), Some(o), g]
+                }))
+            },
+            move |[s, p, o, g]: &[Ground; 4]| {
+                self.estimate_matching(s.term(), p.term(), o.term(), g.graph_name())
+            },
+        )
+    }
+
     /// Build a fallible iterator of all the terms used as subject in this Dataset.
     ///
     /// NB: implementations SHOULD avoid yielding the same term multiple times, but MAY do so.
@@ -584,6 +643,23 @@ pub trait MutableDataset: Dataset {
             .map_err(|err| err.unwrap_sink_error())?;
         Ok(())
     }
+
+    /// Apply the given [`Patch`] to this dataset.
+    ///
+    /// Operations are applied in order.
+    /// Operations enclosed in a transaction (`TX`) are only applied when the transaction is committed (`TC`),
+    /// and are discarded if the transaction is aborted (`TA`) or never committed.
+    /// Headers and prefix operations are ignored.
+    ///
+    /// # Return value
+    /// The `usize` value returned in case of success is
+    /// **not significant unless** this dataset also implements [`SetDataset`].
+    ///
+    /// If it does,
+    /// the number of quads that were *actually* inserted or removed is returned.
+    fn apply_patch(&mut self, patch: &Patch) -> MdResult<Self, usize> {
+        patch::apply_patch(self, patch)
+    }
 }
 
 /// Marker trait constraining the semantics of
@@ -0,0 +1,190 @@
+//! I define [`Patch`], a sequence of changes to be applied to a [`MutableDataset`],
+//! following the data model of the [RDF Patch] format.
+//!
+//! Patches can be applied to any [`MutableDataset`] with [`MutableDataset::apply_patch`].
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+use super::{MdResult, MutableDataset};
+use crate::prefix::Prefix;
+use crate::quad::Spog;
+use crate::term::SimpleTerm;
+use sophia_iri::Iri;
+
+/// A single operation of a [`Patch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    /// A header, i.e. metadata about the patch (`H`)
+    Header(Box<str>, SimpleTerm<'static>),
+    /// The beginning of a transaction (`TX`)
+    TxBegin,
+    /// The commit of the current transaction (`TC`)
+    TxCommit,
+    /// The abortion of the current transaction (`TA`)
+    TxAbort,
+    /// The addition of a prefix declaration (`PA`)
+    AddPrefix(Prefix<Box<str>>, Iri<Box<str>>),
+    /// The deletion of a prefix declaration (`PD`)
+    DeletePrefix(Prefix<Box<str>>),
+    /// The addition of a quad (`A`)
+    Add(Spog<SimpleTerm<'static>>),
+    /// The deletion of a quad (`D`)
+    Delete(Spog<SimpleTerm<'static>>),
+}
+
+/// A sequence of [`PatchOp`]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    /// Build an empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `op` to this patch.
+    pub fn push(&mut self, op: PatchOp) {
+        self.ops.push(op);
+    }
+
+    /// The operations of this patch.
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.ops
+    }
+
+    /// Whether this patch contains no operation.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// An iterator over the quads added by this patch.
+    ///
+    /// NB: this includes quads in aborted transactions.
+    pub fn added(&self) -> impl Iterator<Item = &Spog<SimpleTerm<'static>>> {
+        self.ops.iter().filter_map(|op| match op {
+            PatchOp::Add(q) => Some(q),
+            _ => None,
+        })
+    }
+
+    /// An iterator over the quads deleted by this patch.
+    ///
+    /// NB: this includes quads in aborted transactions.
+    pub fn deleted(&self) -> impl Iterator<Item = &Spog<SimpleTerm<'static>>> {
+        self.ops.iter().filter_map(|op| match op {
+            PatchOp::Delete(q) => Some(q),
+            _ => None,
+        })
+    }
+}
+
+impl From<Vec<PatchOp>> for Patch {
+    fn from(ops: Vec<PatchOp>) -> Self {
+        Patch { ops }
+    }
+}
+
+impl FromIterator<PatchOp> for Patch {
+    fn from_iter<I: IntoIterator<Item = PatchOp>>(iter: I) -> Self {
+        Patch {
+            ops: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<PatchOp> for Patch {
+    fn extend<I: IntoIterator<Item = PatchOp>>(&mut self, iter: I) {
+        self.ops.extend(iter);
+    }
+}
+
+impl IntoIterator for Patch {
+    type Item = PatchOp;
+    type IntoIter = std::vec::IntoIter<PatchOp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Patch {
+    type Item = &'a PatchOp;
+    type IntoIter = std::slice::Iter<'a, PatchOp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.iter()
+    }
+}
+
+/// Implementation of [`MutableDataset::apply_patch`].
+pub(super) fn apply_patch<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    patch: &Patch,
+) -> MdResult<D, usize> {
+    let mut count = 0;
+    let mut pending: Option<Vec<&PatchOp>> = None;
+    for op in patch {
+        match op {
+            PatchOp::TxBegin => {
+                pending.get_or_insert_with(Vec::new);
+            }
+            PatchOp::TxCommit => {
+                for op in pending.take().unwrap_or_default() {
+                    count += apply_op(dataset, op)?;
+                }
+            }
+            PatchOp::TxAbort => pending = None,
+            _ => match pending.as_mut() {
+                Some(ops) => ops.push(op),
+                None => count += apply_op(dataset, op)?,
+            },
+        }
+    }
+    Ok(count)
+}
+
+fn apply_op<D: MutableDataset + ?Sized>(dataset: &mut D, op: &PatchOp) -> MdResult<D, usize> {
+    let changed = match op {
+        PatchOp::Add(([s, p, o], g)) => dataset.insert(s, p, o, g.as_ref())?,
+        PatchOp::Delete(([s, p, o], g)) => dataset.remove(s, p, o, g.as_ref())?,
+        _ => false,
+    };
+    Ok(changed as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::term::IriRef;
+
+    fn quad(s: &str, g: Option<&str>) -> Spog<SimpleTerm<'static>> {
+        let iri = |s: &str| SimpleTerm::Iri(IriRef::new_unchecked(format!("tag:{s}").into()));
+        ([iri(s), iri("p"), iri("o")], g.map(iri))
+    }
+
+    #[test]
+    fn apply_patch() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d: Vec<Spog<SimpleTerm<'static>>> = vec![quad("a", None)];
+        let patch: Patch = vec![
+            PatchOp::Header("id".into(), quad("patch", None).0[0].clone()),
+            PatchOp::Add(quad("b", None)),
+            PatchOp::TxBegin,
+            PatchOp::Add(quad("c", Some("g"))),
+            PatchOp::Delete(quad("a", None)),
+            PatchOp::TxCommit,
+            PatchOp::TxBegin,
+            PatchOp::Add(quad("d", None)),
+            PatchOp::TxAbort,
+            PatchOp::TxBegin,
+            PatchOp::Add(quad("e", None)),
+        ]
+        .into();
+        assert_eq!(d.apply_patch(&patch)?, 3);
+        d.sort();
+        assert_eq!(d, vec![quad("b", None), quad("c", Some("g"))]);
+        assert_eq!(patch.added().count(), 4);
+        assert_eq!(patch.deleted().count(), 1);
+        Ok(())
+    }
+}
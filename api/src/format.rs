@@ -0,0 +1,502 @@
+//! A registry of RDF concrete syntaxes ([formats](Format)),
+//! indexed by media type and file extension.
+//!
+//! This module only provides the machinery of the registry;
+//! the formats themselves are provided by the crates implementing them
+//! (e.g. `sophia_turtle::format`),
+//! and the `sophia` crate provides a registry pre-populated with all the formats it supports.
+//!
+//! Since [`TripleParser`](crate::parser::TripleParser), [`QuadParser`](crate::parser::QuadParser)
+//! and serializers have different types for each format,
+//! the registry hides them behind type-erased [factories](ParserFactory):
+//! every format is parsed into a [`BoxQuadSource`],
+//! and triple-only formats are seen as datasets with only a default graph.
+
+use crate::compression::Compression;
+use crate::quad::{Quad, Spog};
+use crate::source::{QuadSource, Source, StreamError, StreamResult, TripleSource};
+use crate::term::{SimpleTerm, Term};
+use crate::triple::Triple;
+use sophia_iri::Iri;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// A type-erased error, as produced by the parsers and serializers of a [`FormatRegistry`].
+pub type DynError = Box<dyn Error + Send + Sync + 'static>;
+
+/// A type-erased [`QuadSource`], as produced by the parsers of a [`FormatRegistry`].
+pub type BoxQuadSource<'a> =
+    Box<dyn Iterator<Item = Result<Spog<SimpleTerm<'static>>, FormatError>> + 'a>;
+
+/// Builds a [`BoxQuadSource`] parsing the given data, using the given base IRI (if any).
+pub type ParserFactory =
+    for<'a> fn(Box<dyn BufRead + 'a>, Option<Iri<String>>) -> BoxQuadSource<'a>;
+
+/// Serializes the given quads into the given writer.
+///
+/// Serializers of triple-only formats only serialize the default graph.
+pub type SerializerFactory =
+    for<'a> fn(&mut dyn Write, BoxQuadSource<'a>) -> Result<(), FormatError>;
+
+/// The description of an RDF concrete syntax, as stored in a [`FormatRegistry`].
+#[derive(Clone, Copy, Debug)]
+pub struct Format {
+    /// The human-readable name of this format.
+    pub name: &'static str,
+    /// The media types of this format, the first one being the preferred one.
+    ///
+    /// They must be lowercase.
+    pub media_types: &'static [&'static str],
+    /// The file extensions of this format (without the leading dot), the first one being the preferred one.
+    ///
+    /// They must be lowercase.
+    pub extensions: &'static [&'static str],
+    /// Whether this format supports named graphs.
+    pub dataset: bool,
+    /// The parser factory for this format, if any.
+    pub parser: Option<ParserFactory>,
+    /// The serializer factory for this format, if any.
+    pub serializer: Option<SerializerFactory>,
+}
+
+impl Format {
+    /// The preferred media type of this format.
+    pub fn media_type(&self) -> &'static str {
+        self.media_types[0]
+    }
+
+    /// Whether `media_type` is one of the media types of this format
+    /// (ignoring case and parameters).
+    pub fn has_media_type(&self, media_type: &str) -> bool {
+        let media_type = essence(media_type);
+        self.media_types
+            .iter()
+            .any(|mt| mt.eq_ignore_ascii_case(media_type))
+    }
+
+    /// Whether `extension` is one of the file extensions of this format
+    /// (ignoring case, with or without the leading dot).
+    pub fn has_extension(&self, extension: &str) -> bool {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        self.extensions
+            .iter()
+            .any(|ext| ext.eq_ignore_ascii_case(extension))
+    }
+
+    /// Parse `read` with this format's parser, using `base` to resolve relative IRIs.
+    pub fn parse<'a, R: BufRead + 'a>(
+        &self,
+        read: R,
+        base: Option<Iri<String>>,
+    ) -> Result<BoxQuadSource<'a>, FormatError> {
+        let parser = self.parser.ok_or(FormatError::NoParser(self.name))?;
+        Ok(parser(Box::new(read), base))
+    }
+
+    /// Serialize `source` into `write` with this format's serializer.
+    ///
+    /// If this format does not [support named graphs](Format::dataset),
+    /// only the default graph of `source` is serialized.
+    pub fn serialize<W: Write, S: QuadSource>(
+        &self,
+        mut write: W,
+        source: S,
+    ) -> Result<(), FormatError>
+    where
+        S::Error: Send + Sync,
+    {
+        let serializer = self
+            .serializer
+            .ok_or(FormatError::NoSerializer(self.name))?;
+        serializer(&mut write, box_quads(source))
+    }
+}
+
+/// A collection of [formats](Format),
+/// that can be looked up by media type, file extension or HTTP `Accept` header.
+///
+/// When several formats match, the one registered first is returned.
+#[derive(Clone, Debug, Default)]
+pub struct FormatRegistry {
+    formats: Vec<Format>,
+}
+
+impl FormatRegistry {
+    /// Build an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a format to this registry.
+    pub fn register(&mut self, format: Format) -> &mut Self {
+        self.formats.push(format);
+        self
+    }
+
+    /// Iterate over the formats of this registry, in registration order.
+    pub fn formats(&self) -> impl Iterator<Item = &Format> + '_ {
+        self.formats.iter()
+    }
+
+    /// Find the format with the given media type (ignoring case and parameters).
+    pub fn by_media_type(&self, media_type: &str) -> Option<&Format> {
+        self.formats.iter().find(|f| f.has_media_type(media_type))
+    }
+
+    /// Find the format with the given file extension (ignoring case, with or without the leading dot).
+    pub fn by_extension(&self, extension: &str) -> Option<&Format> {
+        self.formats.iter().find(|f| f.has_extension(extension))
+    }
+
+    /// Find the format of the given file, based on its extension.
+    ///
+    /// The extension of a [compression format](Compression) is ignored
+    /// (e.g. `data.nt.gz` is recognized as N-Triples).
+    pub fn by_path<P: AsRef<Path>>(&self, path: P) -> Option<&Format> {
+        let (_, path) = Compression::from_path(path.as_ref());
+        self.by_extension(path.extension()?.to_str()?)
+    }
+
+    /// Find the format (with a serializer) best matching the given HTTP `Accept` header.
+    ///
+    /// Media ranges (such as `text/*` or `*/*`) and quality values (`q` parameter) are supported.
+    /// Among acceptable formats with the same quality, the one registered first is returned.
+    /// Returns `None` if no format is acceptable.
+    pub fn negotiate(&self, accept: &str) -> Option<&Format> {
+        let ranges: Vec<_> = accept.split(',').filter_map(media_range).collect();
+        let mut best = None;
+        let mut best_q = 0.0;
+        for format in self.formats.iter().filter(|f| f.serializer.is_some()) {
+            let q = format
+                .media_types
+                .iter()
+                .filter_map(|mt| quality(&ranges, mt))
+                .fold(0.0, f32::max);
+            if q > best_q {
+                best = Some(format);
+                best_q = q;
+            }
+        }
+        best
+    }
+
+    /// Parse `read` with the format identified by `media_type`,
+    /// using `base` to resolve relative IRIs.
+    pub fn parse_any<'a, R: BufRead + 'a>(
+        &self,
+        read: R,
+        media_type: &str,
+        base: Option<Iri<String>>,
+    ) -> Result<BoxQuadSource<'a>, FormatError> {
+        self.by_media_type(media_type)
+            .ok_or_else(|| FormatError::Unknown(media_type.into()))?
+            .parse(read, base)
+    }
+
+    /// Serialize `source` into `write` with the format identified by `media_type`.
+    ///
+    /// If this format does not [support named graphs](Format::dataset),
+    /// only the default graph of `source` is serialized.
+    pub fn serialize_any<W: Write, S: QuadSource>(
+        &self,
+        write: W,
+        source: S,
+        media_type: &str,
+    ) -> Result<(), FormatError>
+    where
+        S::Error: Send + Sync,
+    {
+        self.by_media_type(media_type)
+            .ok_or_else(|| FormatError::Unknown(media_type.into()))?
+            .serialize(write, source)
+    }
+}
+
+/// An error raised by a [`FormatRegistry`] or a [`Format`].
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    /// No registered format matches the given media type
+    #[error("unknown format {0:?}")]
+    Unknown(String),
+    /// The format has no parser
+    #[error("no parser available for {0}")]
+    NoParser(&'static str),
+    /// The format has no serializer
+    #[error("no serializer available for {0}")]
+    NoSerializer(&'static str),
+    /// The data could not be parsed
+    #[error("parse error: {0}")]
+    Parse(#[source] DynError),
+    /// The data could not be serialized
+    #[error("serialization error: {0}")]
+    Serialize(#[source] DynError),
+}
+
+/// Convert any [`QuadSource`] into a [`BoxQuadSource`].
+///
+/// This is a helper for implementing [`ParserFactory`].
+pub fn box_quads<'a, S>(source: S) -> BoxQuadSource<'a>
+where
+    S: QuadSource + 'a,
+    S::Error: Send + Sync,
+{
+    Box::new(Buffered::<S, Quads>::new(source))
+}
+
+/// Convert any [`TripleSource`] into a [`BoxQuadSource`], with all its triples in the default graph.
+///
+/// This is a helper for implementing [`ParserFactory`].
+pub fn box_triples<'a, S>(source: S) -> BoxQuadSource<'a>
+where
+    S: TripleSource + 'a,
+    S::Error: Send + Sync,
+{
+    Box::new(Buffered::<S, Triples>::new(source))
+}
+
+/// Convert the [`SourceError`](StreamError::SourceError) or [`SinkError`](StreamError::SinkError)
+/// of a serializer into a [`FormatError`].
+///
+/// This is a helper for implementing [`SerializerFactory`].
+pub fn serializer_error<T, E>(res: StreamResult<T, FormatError, E>) -> Result<(), FormatError>
+where
+    E: Error + Send + Sync + 'static,
+{
+    match res {
+        Ok(_) => Ok(()),
+        Err(StreamError::SourceError(err)) => Err(err),
+        Err(StreamError::SinkError(err)) => Err(FormatError::Serialize(Box::new(err))),
+    }
+}
+
+/// An iterator over the triples or quads (depending on `K`) of a source, converted to owned quads.
+struct Buffered<S, K> {
+    source: S,
+    buffer: VecDeque<Spog<SimpleTerm<'static>>>,
+    _kind: PhantomData<K>,
+}
+
+struct Quads;
+struct Triples;
+
+impl<S: Source, K> Buffered<S, K>
+where
+    S::Error: Send + Sync,
+{
+    fn new(source: S) -> Self {
+        Buffered {
+            source,
+            buffer: VecDeque::new(),
+            _kind: PhantomData,
+        }
+    }
+
+    /// Pop the next quad from the buffer, refilling it with `fill` as long as it is empty.
+    fn next_with<F>(
+        &mut self,
+        mut fill: F,
+    ) -> Option<Result<Spog<SimpleTerm<'static>>, FormatError>>
+    where
+        F: FnMut(&mut S, &mut VecDeque<Spog<SimpleTerm<'static>>>) -> Result<bool, S::Error>,
+    {
+        while self.buffer.is_empty() {
+            match fill(&mut self.source, &mut self.buffer) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => return Some(Err(FormatError::Parse(Box::new(err)))),
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+impl<S: QuadSource> Iterator for Buffered<S, Quads>
+where
+    S::Error: Send + Sync,
+{
+    type Item = Result<Spog<SimpleTerm<'static>>, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(|source, buffer| {
+            source.for_some_quad(|q| {
+                let (spo, g) = q.to_spog();
+                buffer.push_back((spo.map(Term::into_term), g.map(Term::into_term)));
+            })
+        })
+    }
+}
+
+impl<S: TripleSource> Iterator for Buffered<S, Triples>
+where
+    S::Error: Send + Sync,
+{
+    type Item = Result<Spog<SimpleTerm<'static>>, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(|source, buffer| {
+            source.for_some_triple(|t| buffer.push_back((t.to_spo().map(Term::into_term), None)))
+        })
+    }
+}
+
+/// The media type of `txt`, without its parameters.
+fn essence(txt: &str) -> &str {
+    txt.split(';').next().unwrap_or_default().trim()
+}
+
+/// Parse a media range of an `Accept` header into its type, subtype and quality.
+fn media_range(txt: &str) -> Option<(&str, &str, f32)> {
+    let mut parts = txt.split(';');
+    let (typ, subtype) = parts.next()?.trim().split_once('/')?;
+    let q = parts
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+        .map_or(Some(1.0), |(_, val)| val.trim().parse().ok())?;
+    Some((typ.trim(), subtype.trim(), q))
+}
+
+/// The quality of `media_type` according to the most specific matching range, if any.
+fn quality(ranges: &[(&str, &str, f32)], media_type: &str) -> Option<f32> {
+    let (typ, subtype) = media_type.split_once('/')?;
+    ranges
+        .iter()
+        .filter_map(|(rtyp, rsubtype, q)| {
+            let specificity = if rtyp.eq_ignore_ascii_case(typ) {
+                if rsubtype.eq_ignore_ascii_case(subtype) {
+                    2
+                } else if *rsubtype == "*" {
+                    1
+                } else {
+                    return None;
+                }
+            } else if *rtyp == "*" && *rsubtype == "*" {
+                0
+            } else {
+                return None;
+            };
+            Some((specificity, *q))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, q)| q)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::source::QuadSource;
+
+    /// A toy format, where each line is an IRI, used as subject, predicate and object
+    fn parse_toy(read: Box<dyn BufRead + '_>, _: Option<Iri<String>>) -> BoxQuadSource<'_> {
+        box_triples(read.lines().map(|line| {
+            line.map(|iri| {
+                let iri = SimpleTerm::Iri(crate::term::IriRef::new_unchecked(iri.into()));
+                [iri.clone(), iri.clone(), iri]
+            })
+        }))
+    }
+
+    fn serialize_toy(write: &mut dyn Write, mut quads: BoxQuadSource) -> Result<(), FormatError> {
+        let res = quads.try_for_each_quad(|q| writeln!(write, "{}", q.s().iri().unwrap()));
+        serializer_error(res)
+    }
+
+    const TOY: Format = Format {
+        name: "Toy",
+        media_types: &["text/x-toy", "application/x-toy"],
+        extensions: &["toy"],
+        dataset: false,
+        parser: Some(parse_toy),
+        serializer: Some(serialize_toy),
+    };
+
+    const TOY_RO: Format = Format {
+        name: "Read-only toy",
+        media_types: &["text/x-toy-ro"],
+        extensions: &["tro"],
+        dataset: false,
+        parser: Some(parse_toy),
+        serializer: None,
+    };
+
+    fn registry() -> FormatRegistry {
+        let mut reg = FormatRegistry::new();
+        reg.register(TOY_RO).register(TOY);
+        reg
+    }
+
+    #[test]
+    fn lookup() {
+        let reg = registry();
+        assert_eq!(reg.by_media_type("text/x-toy").unwrap().name, "Toy");
+        assert_eq!(
+            reg.by_media_type("Application/X-Toy; charset=utf-8")
+                .unwrap()
+                .name,
+            "Toy"
+        );
+        assert!(reg.by_media_type("text/turtle").is_none());
+        assert_eq!(reg.by_extension("tro").unwrap().name, "Read-only toy");
+        assert_eq!(reg.by_extension(".TOY").unwrap().name, "Toy");
+        assert_eq!(reg.by_path("/tmp/foo.bar.toy").unwrap().name, "Toy");
+        assert_eq!(reg.by_path("/tmp/foo.toy.gz").unwrap().name, "Toy");
+        assert!(reg.by_path("/tmp/toy").is_none());
+        assert!(reg.by_path("/tmp/toy.gz").is_none());
+    }
+
+    #[test]
+    fn negotiate() {
+        let reg = registry();
+        for (accept, expected) in [
+            ("text/x-toy", Some("Toy")),
+            ("application/x-toy;q=0.5, text/html", Some("Toy")),
+            ("text/*", Some("Toy")),
+            ("*/*", Some("Toy")),
+            ("text/x-toy-ro", None),
+            ("text/html, */*;q=0", None),
+            ("text/x-toy;q=0, */*", Some("Toy")),
+            ("text/*;q=0, application/x-toy", Some("Toy")),
+            ("", None),
+        ] {
+            assert_eq!(reg.negotiate(accept).map(|f| f.name), expected, "{accept}");
+        }
+    }
+
+    #[test]
+    fn parse_and_serialize() -> Result<(), Box<dyn Error>> {
+        let reg = registry();
+        let quads: Vec<_> = reg
+            .parse_any(&b"tag:a\ntag:b\n"[..], "text/x-toy-ro", None)?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(quads.len(), 2);
+        assert!(quads[0].1.is_none());
+
+        let mut out = vec![];
+        reg.serialize_any(
+            &mut out,
+            quads.into_iter().map(Ok::<_, FormatError>),
+            "text/x-toy",
+        )?;
+        assert_eq!(out, b"tag:a\ntag:b\n");
+
+        assert!(matches!(
+            reg.parse_any(&b""[..], "text/turtle", None),
+            Err(FormatError::Unknown(_))
+        ));
+        assert!(matches!(
+            reg.serialize_any(
+                vec![],
+                std::iter::empty::<Result<Spog<SimpleTerm>, FormatError>>(),
+                "text/x-toy-ro"
+            ),
+            Err(FormatError::NoSerializer(_))
+        ));
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@
 //! for different kinds of graph,
 //! as well as a few implementations for them.
 
+use crate::bgp::{BgpMatcher, BgpSolution, CompiledBgp, Ground, Matches};
 use crate::dataset::adapter::GraphAsDataset;
 use crate::source::{IntoSource, StreamResult, TripleSource};
 use crate::term::{matcher::TermMatcher, SimpleTerm, Term};
@@ -180,6 +181,78 @@ pub trait Graph {
             .map(|o| o.is_some())
     }
 
+    /// Estimate the number of triples matching the given subject, predicate and object,
+    /// where `None` matches any term.
+    ///
+    /// This is used by [`match_bgp`](Graph::match_bgp) to choose the order in which patterns are evaluated,
+    /// so it must be cheap to compute, but only needs to be roughly accurate.
+    ///
+    /// The default implementation returns `None` (no estimate available);
+    /// indexed implementations are encouraged to override it.
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+    ) -> Option<usize> {
+        let _ = (s, p, o);
+        None
+    }
+
+    /// An iterator visiting all the solutions of the given [basic graph pattern](crate::bgp) in this graph.
+    ///
+    /// `bgp` is a list of triple patterns, where [variables](crate::term::VarName) may occur in any position.
+    /// Each solution maps every variable of `bgp` to a term,
+    /// in such a way that all triple patterns are in this graph.
+    /// Variables occurring inside quoted triples are not supported
+    /// (such quoted triples are treated as constants).
+    ///
+    /// # Example
+    /// ```
+    /// # use sophia_api::prelude::*;
+    /// # use sophia_api::graph::GTerm;
+    /// # use sophia_api::ns::{rdf, Namespace};
+    /// # use sophia_api::term::{SimpleTerm, VarName};
+    /// # fn test<G: Graph>(graph: &G) -> Result<(), Box<dyn std::error::Error>>
+    /// # where
+    /// #     for<'x> GTerm<'x, G>: Clone,
+    /// # {
+    /// let s = Namespace::new("http://schema.org/")?;
+    /// let x: SimpleTerm = VarName::new_unchecked("x").into_term();
+    /// let name: SimpleTerm = VarName::new_unchecked("name").into_term();
+    /// let bgp = [
+    ///     [x.clone(), rdf::type_.into_term(), s.get("Person")?.into_term()],
+    ///     [x, s.get("name")?.into_term(), name],
+    /// ];
+    /// for solution in graph.match_bgp(&bgp) {
+    ///     let solution = solution?;
+    ///     println!("{:?} is named {:?}", solution["x"], solution["name"]);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// See the [`bgp`](crate::bgp) module for how the join order is chosen.
+    fn match_bgp<'s, T>(
+        &'s self,
+        bgp: &'s [[T; 3]],
+    ) -> impl Iterator<Item = GResult<Self, BgpSolution<GTerm<'s, Self>>>> + 's
+    where
+        T: Term,
+        GTerm<'s, Self>: Clone,
+    {
+        let bgp = CompiledBgp::new(bgp.iter().map(|t| t.each_ref().map(Some)));
+        BgpMatcher::new(
+            bgp,
+            move |[s, p, o]: [Ground<'s>; 3]| -> Matches<'s, GTerm<'s, Self>, Self::Error, 3> {
+                Box::new(
+                    self.triples_matching(s, p, o)
+                        .map_ok(|t| t.to_spo().map(Some)),
+                )
+            },
+            move |[s, p, o]: &[Ground; 3]| self.estimate_matching(s.term(), p.term(), o.term()),
+        )
+    }
+
     /// Build a fallible iterator of all the terms used as subject in this Graph.
     ///
     /// NB: implementations SHOULD avoid yielding the same term multiple times, but MAY do so.
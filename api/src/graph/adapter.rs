@@ -1,11 +1,13 @@
 //! I define adapters for the [`graph`](super) related traits.
 use super::*;
 use crate::dataset::{DTerm, Dataset, MutableDataset, SetDataset};
+use crate::ns::{rdf, rdfs};
 use crate::quad::Quad;
 use crate::term::{
     matcher::{Any, GraphNameMatcher},
-    GraphName,
+    FromTerm, GraphName,
 };
+use std::collections::HashMap;
 
 /// I wrap a [`Dataset`] as a [`Graph`]
 /// corresponding to the union of all graphs (default and named)
@@ -27,7 +29,10 @@ impl<T: Dataset> UnionGraph<T> {
 }
 
 impl<T: Dataset> Graph for UnionGraph<T> {
-    type Triple<'x> = [DTerm<'x, T>; 3] where Self: 'x;
+    type Triple<'x>
+        = [DTerm<'x, T>; 3]
+    where
+        Self: 'x;
     type Error = T::Error;
 
     fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
@@ -115,7 +120,10 @@ impl<D: Dataset, M: GraphNameMatcher + Copy> PartialUnionGraph<D, M> {
 }
 
 impl<D: Dataset, M: GraphNameMatcher + Copy> Graph for PartialUnionGraph<D, M> {
-    type Triple<'x> = [DTerm<'x, D>; 3] where Self: 'x;
+    type Triple<'x>
+        = [DTerm<'x, D>; 3]
+    where
+        Self: 'x;
     type Error = D::Error;
 
     fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
@@ -180,7 +188,10 @@ impl<D: Dataset, G: Term> DatasetGraph<D, G> {
 }
 
 impl<D: Dataset, G: Term> Graph for DatasetGraph<D, G> {
-    type Triple<'x> = [DTerm<'x, D>; 3] where Self: 'x;
+    type Triple<'x>
+        = [DTerm<'x, D>; 3]
+    where
+        Self: 'x;
     type Error = D::Error;
 
     fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
@@ -234,6 +245,311 @@ impl<D: MutableDataset, G: Term> MutableGraph for DatasetGraph<D, G> {
     }
 }
 
+//
+
+/// I wrap a [`Graph`] as a [`Graph`] containing, in addition to the original triples,
+/// all the triples entailed by them under (a subset of) the [RDFS entailment rules].
+///
+/// More precisely, the following rules are applied until a fixpoint is reached:
+/// - `rdfs2`: `p rdfs:domain c . s p o` ⊢ `s rdf:type c`
+/// - `rdfs3`: `p rdfs:range c . s p o` ⊢ `o rdf:type c` (unless `o` is a literal)
+/// - `rdfs5`: transitivity of `rdfs:subPropertyOf`
+/// - `rdfs7`: `p rdfs:subPropertyOf q . s p o` ⊢ `s q o`
+/// - `rdfs9`: `c rdfs:subClassOf d . s rdf:type c` ⊢ `s rdf:type d`
+/// - `rdfs11`: transitivity of `rdfs:subClassOf`
+///
+/// The other rules (and the axiomatic triples) are not applied,
+/// as they produce a lot of triples that are true of any graph,
+/// and hence of little practical interest.
+///
+/// The entailed triples are computed once and for all by [`RdfsGraph::new`],
+/// so later changes to the wrapped graph (if any) are not reflected by this graph.
+/// Only the entailed triples that are not in the wrapped graph are stored,
+/// so that this graph is a [`SetGraph`] whenever the wrapped graph is.
+///
+/// [RDFS entailment rules]: https://www.w3.org/TR/rdf11-mt/#patterns-of-rdfs-entailment-informative
+#[derive(Clone, Debug)]
+pub struct RdfsGraph<G: Graph> {
+    graph: G,
+    inferred: TripleIndex,
+}
+
+impl<G: Graph> RdfsGraph<G> {
+    /// Wrap the given graph, computing all the triples entailed by it.
+    pub fn new(graph: G) -> Result<Self, G::Error> {
+        let inferred = RdfsClosure::compute(&graph)?;
+        Ok(RdfsGraph { graph, inferred })
+    }
+
+    /// Unwrap the inner [`Graph`].
+    pub fn unwrap(self) -> G {
+        self.graph
+    }
+
+    /// An iterator visiting the entailed triples that are not in the wrapped graph.
+    pub fn inferred_triples(&self) -> impl Iterator<Item = &[SimpleTerm<'static>; 3]> + '_ {
+        self.inferred.iter()
+    }
+
+    /// Insert all the triples of this graph (asserted and entailed) into `target`.
+    ///
+    /// Return the number of triples actually inserted.
+    pub fn materialize_into<M: MutableGraph>(
+        &self,
+        target: &mut M,
+    ) -> StreamResult<usize, G::Error, M::MutationError> {
+        target.insert_all(self.triples())
+    }
+}
+
+impl<G: Graph> Graph for RdfsGraph<G> {
+    type Triple<'x>
+        = [SimpleTerm<'x>; 3]
+    where
+        Self: 'x;
+    type Error = G::Error;
+
+    fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
+        self.graph
+            .triples()
+            .map_ok(|t| t.to_spo().map(SimpleTerm::from_term))
+            .chain(
+                self.inferred
+                    .iter()
+                    .map(|t| Ok(t.each_ref().map(Term::as_simple))),
+            )
+    }
+
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        sm: S,
+        pm: P,
+        om: O,
+    ) -> impl Iterator<Item = GResult<Self, Self::Triple<'s>>> + 's
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+    {
+        let inferred: Vec<_> = self
+            .inferred
+            .matching(&sm, &pm, &om)
+            .map(|t| Ok(t.each_ref().map(Term::as_simple)))
+            .collect();
+        self.graph
+            .triples_matching(sm, pm, om)
+            .map_ok(|t| t.to_spo().map(SimpleTerm::from_term))
+            .chain(inferred)
+    }
+}
+
+impl<G: SetGraph> SetGraph for RdfsGraph<G> {}
+
+/// The state of the fixpoint computation performed by [`RdfsGraph::new`].
+struct RdfsClosure<'g, G> {
+    graph: &'g G,
+    inferred: TripleIndex,
+    queue: Vec<[SimpleTerm<'static>; 3]>,
+}
+
+impl<'g, G: Graph> RdfsClosure<'g, G> {
+    fn compute(graph: &'g G) -> Result<TripleIndex, G::Error> {
+        let mut closure = RdfsClosure {
+            graph,
+            inferred: TripleIndex::default(),
+            queue: vec![],
+        };
+        let v = Vocabulary::new();
+        for t in graph.triples() {
+            closure.apply_rules(t?.to_spo().map(SimpleTerm::from_term), &v)?;
+            while let Some(t) = closure.queue.pop() {
+                closure.apply_rules(t, &v)?;
+            }
+        }
+        Ok(closure.inferred)
+    }
+
+    /// Apply all rules having `t` as one of their premises,
+    /// looking up the other premise in the wrapped graph and the triples inferred so far.
+    ///
+    /// Since every triple is passed to this method eventually,
+    /// each pair of premises is considered at least once (when the latter is passed).
+    fn apply_rules(&mut self, t: [SimpleTerm<'static>; 3], v: &Vocabulary) -> Result<(), G::Error> {
+        let [s, p, o] = &t;
+        for [_, _, q] in self.matching([p], [&v.sub_property_of], Any)? {
+            self.add([s.clone(), q, o.clone()])?;
+        }
+        for [_, _, c] in self.matching([p], [&v.domain], Any)? {
+            self.add([s.clone(), v.type_.clone(), c])?;
+        }
+        if !o.is_literal() {
+            for [_, _, c] in self.matching([p], [&v.range], Any)? {
+                self.add([o.clone(), v.type_.clone(), c])?;
+            }
+        }
+        if Term::eq(p, &v.type_) {
+            for [_, _, d] in self.matching([o], [&v.sub_class_of], Any)? {
+                self.add([s.clone(), v.type_.clone(), d])?;
+            }
+        } else if Term::eq(p, &v.sub_property_of) || Term::eq(p, &v.sub_class_of) {
+            for [x, _, _] in self.matching(Any, [p], [s])? {
+                self.add([x, p.clone(), o.clone()])?;
+            }
+            for [_, _, y] in self.matching([o], [p], Any)? {
+                self.add([s.clone(), p.clone(), y])?;
+            }
+            if Term::eq(p, &v.sub_property_of) {
+                for [x, _, y] in self.matching(Any, [s], Any)? {
+                    self.add([x, o.clone(), y])?;
+                }
+            } else {
+                for [x, _, _] in self.matching(Any, [&v.type_], [s])? {
+                    self.add([x, v.type_.clone(), o.clone()])?;
+                }
+            }
+        } else if Term::eq(p, &v.domain) {
+            for [x, _, _] in self.matching(Any, [s], Any)? {
+                self.add([x, v.type_.clone(), o.clone()])?;
+            }
+        } else if Term::eq(p, &v.range) {
+            for [_, _, y] in self.matching(Any, [s], Any)? {
+                if !y.is_literal() {
+                    self.add([y, v.type_.clone(), o.clone()])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the triples matching the given matchers,
+    /// either in the wrapped graph or among the triples inferred so far.
+    fn matching<S, P, O>(
+        &self,
+        sm: S,
+        pm: P,
+        om: O,
+    ) -> Result<Vec<[SimpleTerm<'static>; 3]>, G::Error>
+    where
+        S: TermMatcher,
+        P: TermMatcher,
+        O: TermMatcher,
+    {
+        let mut ret = self
+            .graph
+            .triples_matching(sm.matcher_ref(), pm.matcher_ref(), om.matcher_ref())
+            .map_ok(|t| t.to_spo().map(SimpleTerm::from_term))
+            .collect::<Result<Vec<_>, _>>()?;
+        ret.extend(self.inferred.matching(&sm, &pm, &om).cloned());
+        Ok(ret)
+    }
+
+    /// Add `t` to the inferred triples, unless it is already known.
+    fn add(&mut self, t: [SimpleTerm<'static>; 3]) -> Result<(), G::Error> {
+        if !self.inferred.contains(&t) && !self.graph.contains(&t[0], &t[1], &t[2])? {
+            self.inferred.insert(t.clone());
+            self.queue.push(t);
+        }
+        Ok(())
+    }
+}
+
+/// The terms used by the rules of [`RdfsClosure`],
+/// converted once and for all into [`SimpleTerm`]s, which are cheaper to compare than [`NsTerm`](crate::ns::NsTerm)s.
+struct Vocabulary {
+    type_: SimpleTerm<'static>,
+    sub_class_of: SimpleTerm<'static>,
+    sub_property_of: SimpleTerm<'static>,
+    domain: SimpleTerm<'static>,
+    range: SimpleTerm<'static>,
+}
+
+impl Vocabulary {
+    fn new() -> Self {
+        Vocabulary {
+            type_: rdf::type_.into_term(),
+            sub_class_of: rdfs::subClassOf.into_term(),
+            sub_property_of: rdfs::subPropertyOf.into_term(),
+            domain: rdfs::domain.into_term(),
+            range: rdfs::range.into_term(),
+        }
+    }
+}
+
+/// A set of triples, indexed by subject, predicate and object,
+/// used by [`RdfsGraph`] to store and look up the entailed triples.
+#[derive(Clone, Debug, Default)]
+struct TripleIndex {
+    triples: Vec<[SimpleTerm<'static>; 3]>,
+    /// For each position (subject, predicate, object),
+    /// the indices in `triples` of the triples having a given term at that position
+    positions: [HashMap<SimpleTerm<'static>, Vec<usize>>; 3],
+}
+
+impl TripleIndex {
+    fn iter(&self) -> impl Iterator<Item = &[SimpleTerm<'static>; 3]> + '_ {
+        self.triples.iter()
+    }
+
+    fn contains(&self, t: &[SimpleTerm<'static>; 3]) -> bool {
+        self.matching(&[&t[0]], &[&t[1]], &[&t[2]]).next().is_some()
+    }
+
+    /// Insert `t`, unless it is already present.
+    fn insert(&mut self, t: [SimpleTerm<'static>; 3]) {
+        if self.contains(&t) {
+            return;
+        }
+        let i = self.triples.len();
+        for (term, index) in t.iter().zip(&mut self.positions) {
+            index.entry(term.clone()).or_default().push(i);
+        }
+        self.triples.push(t);
+    }
+
+    /// Iterate over the triples matching the given matchers,
+    /// using the position index of the most selective constant matcher (if any).
+    fn matching<'s, 'm, S, P, O>(
+        &'s self,
+        sm: &'m S,
+        pm: &'m P,
+        om: &'m O,
+    ) -> impl Iterator<Item = &'s [SimpleTerm<'static>; 3]> + 'm
+    where
+        's: 'm,
+        S: TermMatcher,
+        P: TermMatcher,
+        O: TermMatcher,
+    {
+        let constants = [
+            sm.constant().map(Term::as_simple),
+            pm.constant().map(Term::as_simple),
+            om.constant().map(Term::as_simple),
+        ];
+        let candidates = constants
+            .iter()
+            .zip(&self.positions)
+            .filter_map(|(c, index)| Some(lookup(index, c.as_ref()?)))
+            .min_by_key(|ids| ids.len());
+        let (ids, all) = match candidates {
+            Some(ids) => (ids, None),
+            None => (&[][..], Some(0..self.triples.len())),
+        };
+        ids.iter()
+            .copied()
+            .chain(all.into_iter().flatten())
+            .map(|i| &self.triples[i])
+            .filter(|[s, p, o]| sm.matches(s) && pm.matches(p) && om.matches(o))
+    }
+}
+
+/// The indices associated to `term` in `index`.
+fn lookup<'a>(
+    index: &'a HashMap<SimpleTerm<'static>, Vec<usize>>,
+    term: &SimpleTerm<'a>,
+) -> &'a [usize] {
+    index.get(term).map(Vec::as_slice).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -318,6 +634,87 @@ mod test {
     }
     crate::test_graph_impl!(dataset_graph, MyDG, true, true, collect_dataset_graph);
 
+    #[test]
+    fn rdfs_graph() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::graph::test::*;
+        use crate::ns::{rdf, rdfs};
+
+        let knows: MyTerm = NS.get("knows")?.into_term();
+        let friend: MyTerm = NS.get("friend")?.into_term();
+        let alice: MyTerm = NS.get("alice")?.into_term();
+        let bob: MyTerm = NS.get("bob")?.into_term();
+        let person: MyTerm = NS.get("Person")?.into_term();
+        let agent: MyTerm = NS.get("Agent")?.into_term();
+        let thing: MyTerm = NS.get("Thing")?.into_term();
+        let name: MyTerm = NS.get("name")?.into_term();
+        let lit: MyTerm = "Alice".into_term();
+        let g: BTreeSet<[MyTerm; 3]> = [
+            [
+                friend.clone(),
+                rdfs::subPropertyOf.into_term(),
+                knows.clone(),
+            ],
+            [knows.clone(), rdfs::domain.into_term(), person.clone()],
+            [name.clone(), rdfs::range.into_term(), thing.clone()],
+            [person.clone(), rdfs::subClassOf.into_term(), agent.clone()],
+            [agent.clone(), rdfs::subClassOf.into_term(), thing.clone()],
+            [alice.clone(), friend.clone(), bob.clone()],
+            [alice.clone(), name.clone(), lit],
+        ]
+        .into_iter()
+        .collect();
+        let r = RdfsGraph::new(&g)?;
+
+        for [s, p, o] in [
+            [&alice, &knows, &bob],
+            [&alice, &rdf::type_.into_term(), &person],
+            [&alice, &rdf::type_.into_term(), &agent],
+            [&alice, &rdf::type_.into_term(), &thing],
+            [&person, &rdfs::subClassOf.into_term(), &thing],
+        ] {
+            assert!(r.contains(s, p, o)?);
+            assert!(!Graph::contains(&g, s, p, o)?);
+        }
+        // rdfs:range does not apply to literals
+        assert_eq!(r.triples_matching(Any, [rdf::type_], Any).count(), 3);
+        // no rule makes bob the subject of anything
+        assert_eq!(r.triples_matching([&bob], Any, Any).count(), 0);
+        assert_eq!(r.inferred_triples().count(), 5);
+        assert_eq!(r.triples().count(), g.len() + 5);
+
+        let mut m = BTreeSet::<[MyTerm; 3]>::new();
+        assert_eq!(r.materialize_into(&mut m)?, g.len() + 5);
+        assert!(m.contains(&[alice, rdf::type_.into_term(), thing]));
+        Ok(())
+    }
+
+    #[test]
+    fn rdfs_graph_many_instances() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::graph::test::*;
+        use crate::ns::{rdf, rdfs};
+
+        let person: MyTerm = NS.get("Person")?.into_term();
+        let agent: MyTerm = NS.get("Agent")?.into_term();
+        let thing: MyTerm = NS.get("Thing")?.into_term();
+        let mut g: BTreeSet<[MyTerm; 3]> = [
+            [person.clone(), rdfs::subClassOf.into_term(), agent.clone()],
+            [agent.clone(), rdfs::subClassOf.into_term(), thing.clone()],
+        ]
+        .into_iter()
+        .collect();
+        let n = 100;
+        for i in 0..n {
+            let x: MyTerm = NS.get(&format!("x{i}"))?.into_term();
+            g.insert([x, rdf::type_.into_term(), person.clone()]);
+        }
+        let r = RdfsGraph::new(&g)?;
+        assert_eq!(r.inferred_triples().count(), 2 * n + 1);
+        let x42: MyTerm = NS.get("x42")?.into_term();
+        assert_eq!(r.triples_matching([&x42], Any, Any).count(), 3);
+        assert_eq!(r.triples_matching(Any, Any, [&thing]).count(), n + 2);
+        Ok(())
+    }
+
     #[allow(dead_code)] // just check this compiles
     fn check_trait_impls() {
         let mut ds = MyDS::new();
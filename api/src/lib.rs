@@ -50,7 +50,10 @@
 //! [Notation3]: https://www.w3.org/TeamSubmission/n3/
 #![deny(missing_docs)]
 
+pub mod bgp;
+pub mod compression;
 pub mod dataset;
+pub mod format;
 pub mod graph;
 pub mod ns;
 pub mod parser;
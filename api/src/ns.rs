@@ -74,6 +74,7 @@ pub mod rdf {
         language,
         object,
         predicate,
+        reifies,
         rest,
         subject,
         value,
@@ -198,6 +199,7 @@ pub mod owl {
         // Classes
         AllDifferent,
         AllDisjointClasses,
+        AsymmetricProperty,
         AnnotationProperty,
         Class,
         DatatypeProperty,
@@ -216,6 +218,7 @@ pub mod owl {
         distinctMembers,
         equivalentClass,
         equivalentProperty,
+        hasValue,
         intersectionOf,
         inverseOf,
         maxCardinality,
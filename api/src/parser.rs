@@ -1,6 +1,6 @@
 //! API for parsing RDF syntaxes.
 
-use crate::source::{QuadSource, TripleSource};
+use crate::source::{AsyncQuadSource, AsyncTripleSource, QuadSource, TripleSource};
 
 /// A parser takes some data of type `T`,
 /// and returns a [`TripleSource`].
@@ -52,6 +52,28 @@ pub trait QuadParser<T> {
     }
 }
 
+/// An asynchronous parser takes some data of type `T`
+/// (typically an asynchronous reader),
+/// and returns an [`AsyncTripleSource`].
+pub trait AsyncTripleParser<T> {
+    /// The source produced by this parser
+    type Source: AsyncTripleSource;
+
+    /// Parses data into an asynchronous triple source.
+    fn parse_async(&self, data: T) -> Self::Source;
+}
+
+/// An asynchronous parser takes some data of type `T`
+/// (typically an asynchronous reader),
+/// and returns an [`AsyncQuadSource`].
+pub trait AsyncQuadParser<T> {
+    /// The source produced by this parser
+    type Source: AsyncQuadSource;
+
+    /// Parses data into an asynchronous quad source.
+    fn parse_async(&self, data: T) -> Self::Source;
+}
+
 /// Utility trait to support [`TripleParser::parse_str`] and [`QuadParser::parse_str`].
 pub trait IntoParsable {
     /// The parsable type this type can be converted to.
@@ -77,6 +99,18 @@ macro_rules! def_mod_functions_for_bufread_parser {
             $parser_type::default().parse(bufread)
         }
 
+        /// Convenience function for parsing a possibly compressed BufRead with the default parser.
+        ///
+        /// Compressed data is transparently decompressed
+        /// (see [`decompress`]($crate::compression::decompress)).
+        pub fn parse_bufread_decompressed<B: std::io::BufRead>(
+            bufread: B,
+        ) -> <$parser_type as $crate::parser::$parser_trait<
+            $crate::compression::Decompress<B>,
+        >>::Source {
+            $parser_type::default().parse($crate::compression::decompress(bufread))
+        }
+
         /// Convenience function for parsing a str with the default parser.
         pub fn parse_str(
             txt: &str,
@@ -1,6 +1,8 @@
 //! RDF-related languages (e.g. Turtle, SPARQL) often use prefixes to shorten IRIs.
 //! This crate provides generic traits and types to handle prefix maps.
 
+mod _discovery;
+pub use _discovery::*;
 mod _error;
 pub use _error::*;
 mod _prefix_map;
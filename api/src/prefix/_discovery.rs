@@ -0,0 +1,347 @@
+//! Automatic discovery of the prefixes suitable for serializing some data.
+use super::{Prefix, PrefixMap, PrefixMapPair};
+use crate::dataset::Dataset;
+use crate::graph::Graph;
+use crate::ns::{rdf, xsd};
+use crate::quad::Quad;
+use crate::term::{Term, TermKind};
+use crate::triple::Triple;
+use sophia_iri::Iri;
+use std::collections::HashMap;
+
+/// Well-known prefixes, in the spirit of <https://prefix.cc/>,
+/// used by [`PrefixDiscovery`] to name the namespaces it finds.
+pub const WELL_KNOWN_PREFIXES: &[(&str, &str)] = &[
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("sh", "http://www.w3.org/ns/shacl#"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("org", "http://www.w3.org/ns/org#"),
+    ("ldp", "http://www.w3.org/ns/ldp#"),
+    ("as", "https://www.w3.org/ns/activitystreams#"),
+    ("sosa", "http://www.w3.org/ns/sosa/"),
+    ("ssn", "http://www.w3.org/ns/ssn/"),
+    ("time", "http://www.w3.org/2006/time#"),
+    ("vcard", "http://www.w3.org/2006/vcard/ns#"),
+    ("geo", "http://www.w3.org/2003/01/geo/wgs84_pos#"),
+    ("qb", "http://purl.org/linked-data/cube#"),
+    ("odrl", "http://www.w3.org/ns/odrl/2/"),
+    ("earl", "http://www.w3.org/ns/earl#"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("schema", "https://schema.org/"),
+    ("schema", "http://schema.org/"),
+    ("void", "http://rdfs.org/ns/void#"),
+    ("sioc", "http://rdfs.org/sioc/ns#"),
+    ("doap", "http://usefulinc.com/ns/doap#"),
+    ("bibo", "http://purl.org/ontology/bibo/"),
+    ("cc", "http://creativecommons.org/ns#"),
+    ("gr", "http://purl.org/goodrelations/v1#"),
+    ("geosparql", "http://www.opengis.net/ont/geosparql#"),
+    ("dbo", "http://dbpedia.org/ontology/"),
+    ("dbr", "http://dbpedia.org/resource/"),
+    ("dbp", "http://dbpedia.org/property/"),
+    ("wd", "http://www.wikidata.org/entity/"),
+    ("wdt", "http://www.wikidata.org/prop/direct/"),
+];
+
+/// Discover the namespaces used in some RDF data,
+/// in order to build a prefix map for serializing it.
+///
+/// Every IRI (including datatypes, and IRIs nested in triple terms)
+/// is split after its last `#` or `/`,
+/// and the most frequent namespaces are kept.
+/// They are named after the [known prefixes](PrefixDiscovery::with_known_prefixes) if any,
+/// then after the [`WELL_KNOWN_PREFIXES`],
+/// and otherwise `ns1`, `ns2`, etc.
+///
+/// ```
+/// # use sophia_api::prefix::{PrefixDiscovery, PrefixMap};
+/// # use sophia_api::term::{SimpleTerm, Term};
+/// # use sophia_iri::Iri;
+/// let alice = Iri::new_unchecked("https://example.org/people/alice");
+/// let name = Iri::new_unchecked("http://xmlns.com/foaf/0.1/name");
+/// let graph: Vec<[SimpleTerm; 3]> = vec![[alice.into_term(), name.into_term(), "Alice".into_term()]];
+/// let prefixes = PrefixDiscovery::new().graph(&graph)?;
+/// assert_eq!(prefixes.get_namespace("foaf").unwrap().as_str(), "http://xmlns.com/foaf/0.1/");
+/// assert_eq!(prefixes.get_namespace("ns1").unwrap().as_str(), "https://example.org/people/");
+/// # Ok::<(), std::convert::Infallible>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrefixDiscovery {
+    max_prefixes: usize,
+    min_count: usize,
+    known: Vec<PrefixMapPair>,
+    counts: HashMap<Box<str>, usize>,
+}
+
+impl PrefixDiscovery {
+    /// The maximum number of prefixes returned by default.
+    pub const DEFAULT_MAX_PREFIXES: usize = 20;
+
+    /// Build a new [`PrefixDiscovery`] with the default configuration.
+    pub fn new() -> Self {
+        PrefixDiscovery {
+            max_prefixes: Self::DEFAULT_MAX_PREFIXES,
+            min_count: 1,
+            known: vec![],
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Transform a [`PrefixDiscovery`] by setting the maximum number of prefixes it returns
+    /// (defaults to [`DEFAULT_MAX_PREFIXES`](Self::DEFAULT_MAX_PREFIXES)).
+    pub fn with_max_prefixes(mut self, max_prefixes: usize) -> Self {
+        self.max_prefixes = max_prefixes;
+        self
+    }
+
+    /// Transform a [`PrefixDiscovery`] by setting the number of occurrences
+    /// that a namespace must reach to be returned (defaults to 1).
+    pub fn with_min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    /// Transform a [`PrefixDiscovery`] by setting prefixes
+    /// that take precedence over the [`WELL_KNOWN_PREFIXES`]
+    /// (copying `pm` using [`PrefixMap::to_vec`]).
+    pub fn with_known_prefixes<P: PrefixMap + ?Sized>(mut self, pm: &P) -> Self {
+        self.known = pm.to_vec();
+        self
+    }
+
+    /// Count the namespaces of all the triples of `graph`,
+    /// and return the resulting [prefixes](PrefixDiscovery::prefixes).
+    pub fn graph<G: Graph + ?Sized>(mut self, graph: &G) -> Result<Vec<PrefixMapPair>, G::Error> {
+        for t in graph.triples() {
+            self.add_triple(t?);
+        }
+        Ok(self.prefixes())
+    }
+
+    /// Count the namespaces of all the quads of `dataset`,
+    /// and return the resulting [prefixes](PrefixDiscovery::prefixes).
+    pub fn dataset<D: Dataset + ?Sized>(
+        mut self,
+        dataset: &D,
+    ) -> Result<Vec<PrefixMapPair>, D::Error> {
+        for q in dataset.quads() {
+            self.add_quad(q?);
+        }
+        Ok(self.prefixes())
+    }
+
+    /// Count the namespaces of the terms of `triple`.
+    pub fn add_triple<T: Triple>(&mut self, triple: T) {
+        for t in triple.to_spo() {
+            self.add_term(t);
+        }
+    }
+
+    /// Count the namespaces of the terms of `quad`.
+    pub fn add_quad<Q: Quad>(&mut self, quad: Q) {
+        let (spo, g) = quad.to_spog();
+        for t in spo.into_iter().chain(g) {
+            self.add_term(t);
+        }
+    }
+
+    /// Count the namespace of `term` (or of the terms it contains).
+    pub fn add_term<T: Term>(&mut self, term: T) {
+        match term.kind() {
+            TermKind::Iri => self.add_iri(term.iri().unwrap().as_str()),
+            TermKind::Literal => {
+                let datatype = term.datatype().unwrap();
+                // xsd:string and rdf:langString are never written explicitly
+                if xsd::string != datatype && rdf::langString != datatype {
+                    self.add_iri(datatype.as_str());
+                }
+            }
+            TermKind::Triple => self.add_triple(term.to_triple().unwrap()),
+            _ => {}
+        }
+    }
+
+    fn add_iri(&mut self, iri: &str) {
+        let Some(ns) = namespace(iri) else {
+            return;
+        };
+        match self.counts.get_mut(ns) {
+            Some(count) => *count += 1,
+            None => {
+                // relative IRI references (in generalized RDF) have no usable namespace
+                if Iri::new(ns).is_ok() {
+                    self.counts.insert(ns.into(), 1);
+                }
+            }
+        }
+    }
+
+    /// The prefixes of the most frequent namespaces counted so far,
+    /// from the most to the least frequent.
+    pub fn prefixes(&self) -> Vec<PrefixMapPair> {
+        let mut namespaces: Vec<_> = self
+            .counts
+            .iter()
+            .filter(|(_, count)| **count >= self.min_count)
+            .collect();
+        namespaces.sort_by(|(ns1, c1), (ns2, c2)| c2.cmp(c1).then(ns1.cmp(ns2)));
+        namespaces.truncate(self.max_prefixes);
+
+        let mut ret: Vec<PrefixMapPair> = Vec::with_capacity(namespaces.len());
+        let is_free =
+            |ret: &[PrefixMapPair], prefix: &str| ret.iter().all(|(p, _)| p.as_str() != prefix);
+        let mut next_generated = 1;
+        for (ns, _) in namespaces {
+            let known = self
+                .known
+                .iter()
+                .map(|(p, n)| (p.as_str(), n.as_str()))
+                .chain(WELL_KNOWN_PREFIXES.iter().copied())
+                .find(|(p, n)| *n == &ns[..] && is_free(&ret, p));
+            let prefix = match known {
+                Some((prefix, _)) => prefix.into(),
+                None => loop {
+                    let prefix = format!("ns{next_generated}");
+                    next_generated += 1;
+                    if is_free(&ret, &prefix) && self.is_unreserved(&prefix) {
+                        break prefix.into();
+                    }
+                },
+            };
+            ret.push((
+                Prefix::new_unchecked(prefix),
+                Iri::new_unchecked(ns.clone()),
+            ));
+        }
+        ret
+    }
+
+    /// Whether `prefix` is not the name of a known prefix,
+    /// so that generated prefixes are not confusing.
+    fn is_unreserved(&self, prefix: &str) -> bool {
+        self.known.iter().all(|(p, _)| p.as_str() != prefix)
+    }
+}
+
+impl Default for PrefixDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The namespace of `iri`, i.e. its prefix up to its last `#` or `/`,
+/// provided that it is not the end of the scheme (as in `http://`).
+fn namespace(iri: &str) -> Option<&str> {
+    let end = iri.rfind(['#', '/'])? + 1;
+    let ns = &iri[..end];
+    if ns.ends_with("//") && !ns[..end - 2].contains('/') {
+        None
+    } else {
+        Some(ns)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::term::SimpleTerm;
+
+    #[test]
+    fn namespaces() {
+        assert_eq!(namespace("http://ex.org/a/b"), Some("http://ex.org/a/"));
+        assert_eq!(namespace("http://ex.org/a#b"), Some("http://ex.org/a#"));
+        assert_eq!(namespace("http://ex.org/a/#b"), Some("http://ex.org/a/#"));
+        assert_eq!(namespace("http://ex.org/"), Some("http://ex.org/"));
+        assert_eq!(namespace("http://ex.org"), None);
+        assert_eq!(namespace("tag:foo"), None);
+        assert_eq!(namespace("file:///tmp/x"), Some("file:///tmp/"));
+    }
+
+    fn iri(txt: &'static str) -> SimpleTerm<'static> {
+        SimpleTerm::Iri(crate::term::IriRef::new_unchecked(txt.into()))
+    }
+
+    #[test]
+    fn discover() {
+        let graph = vec![
+            [
+                iri("tag:a/x"),
+                iri("http://xmlns.com/foaf/0.1/knows"),
+                iri("tag:a/y"),
+            ],
+            [
+                iri("tag:a/x"),
+                iri("http://xmlns.com/foaf/0.1/age"),
+                42.into_term(),
+            ],
+            [iri("tag:a/y"), rdf::type_.into_term(), iri("tag:b#C")],
+            [iri("tag:a/y"), iri("tag:c/p"), "foo".into_term()],
+        ];
+        let prefixes = PrefixDiscovery::new().graph(&graph).unwrap();
+        let got: Vec<_> = prefixes
+            .iter()
+            .map(|(p, n)| (p.as_str(), n.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("ns1", "tag:a/"),
+                ("foaf", "http://xmlns.com/foaf/0.1/"),
+                ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+                ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+                ("ns2", "tag:b#"),
+                ("ns3", "tag:c/"),
+            ]
+        );
+
+        let known = [(Prefix::new_unchecked("ns2"), Iri::new_unchecked("tag:c/"))];
+        let prefixes = PrefixDiscovery::new()
+            .with_known_prefixes(&known[..])
+            .with_min_count(2)
+            .graph(&graph)
+            .unwrap();
+        let got: Vec<_> = prefixes
+            .iter()
+            .map(|(p, n)| (p.as_str(), n.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![("ns1", "tag:a/"), ("foaf", "http://xmlns.com/foaf/0.1/")]
+        );
+
+        let prefixes = PrefixDiscovery::new()
+            .with_known_prefixes(&known[..])
+            .with_max_prefixes(6)
+            .graph(&graph)
+            .unwrap();
+        let got: Vec<_> = prefixes.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(got, vec!["ns1", "foaf", "rdf", "xsd", "ns3", "ns2"]);
+    }
+
+    #[test]
+    fn schema_variants() {
+        let graph = vec![[
+            iri("https://schema.org/a"),
+            iri("https://schema.org/b"),
+            iri("http://schema.org/c"),
+        ]];
+        let prefixes = PrefixDiscovery::new().graph(&graph).unwrap();
+        let got: Vec<_> = prefixes.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(got, vec!["schema", "ns1"]);
+    }
+
+    #[test]
+    fn relative_iris() {
+        let graph = vec![[iri("a/b"), iri("tag:a/p"), iri("../c#d")]];
+        let prefixes = PrefixDiscovery::new().graph(&graph).unwrap();
+        let got: Vec<_> = prefixes.iter().map(|(_, ns)| ns.as_str()).collect();
+        assert_eq!(got, vec!["tag:a/"]);
+    }
+}
@@ -14,6 +14,7 @@
 use crate::dataset::*;
 use crate::graph::*;
 use crate::source::*;
+use std::future::Future;
 
 /// A triple serializer writes triples according to a given format.
 pub trait TripleSerializer {
@@ -76,6 +77,80 @@ pub trait QuadSerializer {
     }
 }
 
+/// An asynchronous triple serializer writes triples according to a given format,
+/// typically to an asynchronous writer.
+///
+/// This is the asynchronous counterpart of [`TripleSerializer`].
+pub trait AsyncTripleSerializer {
+    /// The error type that may be raised during serialization.
+    type Error: 'static + std::error::Error;
+
+    /// Serialize all triples from the given [`AsyncTripleSource`].
+    ///
+    /// Synchronous [`TripleSource`]s can be passed by wrapping them in a [`ReadySource`].
+    fn serialize_triples_async<TS>(
+        &mut self,
+        source: TS,
+    ) -> impl Future<Output = StreamResult<&mut Self, TS::Error, Self::Error>>
+    where
+        TS: AsyncTripleSource,
+        Self: Sized;
+
+    /// Serialize a whole [`Graph`].
+    ///
+    /// While this method has a default implementation based on
+    /// [`serialize_triples_async`](Self::serialize_triples_async),
+    /// some implementations may override it in order to better use the structure of the Graph.
+    #[inline]
+    fn serialize_graph_async<G>(
+        &mut self,
+        graph: &G,
+    ) -> impl Future<Output = StreamResult<&mut Self, G::Error, Self::Error>>
+    where
+        G: Graph,
+        Self: Sized,
+    {
+        self.serialize_triples_async(ReadySource(graph.triples()))
+    }
+}
+
+/// An asynchronous quad serializer writes quads according to a given format,
+/// typically to an asynchronous writer.
+///
+/// This is the asynchronous counterpart of [`QuadSerializer`].
+pub trait AsyncQuadSerializer {
+    /// The error type that may be raised during serialization.
+    type Error: 'static + std::error::Error;
+
+    /// Serialize all quads from the given [`AsyncQuadSource`].
+    ///
+    /// Synchronous [`QuadSource`]s can be passed by wrapping them in a [`ReadySource`].
+    fn serialize_quads_async<QS>(
+        &mut self,
+        source: QS,
+    ) -> impl Future<Output = StreamResult<&mut Self, QS::Error, Self::Error>>
+    where
+        QS: AsyncQuadSource,
+        Self: Sized;
+
+    /// Serialize a whole [`Dataset`].
+    ///
+    /// While this method has a default implementation based on
+    /// [`serialize_quads_async`](Self::serialize_quads_async),
+    /// some implementations may override it in order to better use the structure of the Dataset.
+    #[inline]
+    fn serialize_dataset_async<D>(
+        &mut self,
+        dataset: &D,
+    ) -> impl Future<Output = StreamResult<&mut Self, D::Error, Self::Error>>
+    where
+        D: Dataset,
+        Self: Sized,
+    {
+        self.serialize_quads_async(ReadySource(dataset.quads()))
+    }
+}
+
 /// A stringifier is special kind of [`TripleSerializer`] or [`QuadSerializer`]:
 ///
 /// + it uses a text-based format encoded in UTF8;
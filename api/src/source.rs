@@ -7,6 +7,9 @@
 //! [`TripleSource`] and [`QuadSource`] provides specialized alternative
 //! (e.g. [`for_each_triple`] and [`for_each_quad`], respectively).
 //!
+//! [`AsyncSource`], [`AsyncTripleSource`] and [`AsyncQuadSource`] are their asynchronous counterparts,
+//! for sources that have to wait for their data (e.g. parsers reading from the network).
+//!
 //! # Rationale (or Why not simply use `Iterator`?)
 //!
 //! The [`Iterator`] trait is designed in such a way that items must live at least as long as the iterator itself.
@@ -63,6 +66,8 @@ pub mod filter;
 pub mod filter_map;
 pub mod map;
 
+mod _async;
+pub use _async::*;
 mod _quad;
 pub use _quad::*;
 mod _stream_error;
@@ -0,0 +1,430 @@
+use super::*;
+use crate::dataset::MutableDataset;
+use crate::graph::MutableGraph;
+use crate::quad::Quad;
+use crate::triple::Triple;
+use std::future::Future;
+
+/// An asynchronous source produces [items](AsyncSource::Item), and may also fail in the process.
+///
+/// This is the asynchronous counterpart of [`Source`],
+/// for sources waiting for their data (e.g. parsers reading from the network).
+/// Items are still passed to a synchronous closure,
+/// only the retrieval of the data is asynchronous.
+///
+/// It comes with two specialized kinds of asynchronous sources,
+/// [`AsyncTripleSource`] and [`AsyncQuadSource`],
+/// which, like their synchronous counterparts, need not be implemented explicitly.
+pub trait AsyncSource {
+    /// The type of items this source yields.
+    type Item<'x>;
+    /// The type of errors produced by this source.
+    type Error: Error + 'static;
+
+    /// Call f for some item(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `Ok(false)` if there are no more items in this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    fn try_for_some_item<E, F>(
+        &mut self,
+        f: F,
+    ) -> impl Future<Output = StreamResult<bool, Self::Error, E>>
+    where
+        E: Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>;
+
+    /// Call f for all items from this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    #[inline]
+    fn try_for_each_item<F, E>(
+        &mut self,
+        mut f: F,
+    ) -> impl Future<Output = StreamResult<(), Self::Error, E>>
+    where
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+        E: Error,
+    {
+        async move {
+            while self.try_for_some_item(&mut f).await? {}
+            Ok(())
+        }
+    }
+
+    /// Call f for some item(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `false` if there are no more items in this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_some_item<F>(&mut self, mut f: F) -> impl Future<Output = Result<bool, Self::Error>>
+    where
+        F: FnMut(Self::Item<'_>),
+    {
+        async move {
+            self.try_for_some_item(|t| -> Result<(), Self::Error> {
+                f(t);
+                Ok(())
+            })
+            .await
+            .map_err(StreamError::inner_into)
+        }
+    }
+
+    /// Call f for all items from this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_each_item<F>(&mut self, mut f: F) -> impl Future<Output = Result<(), Self::Error>>
+    where
+        F: FnMut(Self::Item<'_>),
+    {
+        async move {
+            while self.for_some_item(&mut f).await? {}
+            Ok(())
+        }
+    }
+}
+
+/// An asynchronous triple source is an [`AsyncSource`] producing [triples](Triple).
+///
+/// It does not need to be explicitly implemented:
+/// any [`AsyncSource`] implementation producing [triples](Triple)
+/// will automatically implement [`AsyncTripleSource`].
+///
+/// See also [`ATSTriple`].
+pub trait AsyncTripleSource: AsyncSource + IsAsyncTripleSource {
+    /// Call f for some triple(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `Ok(false)` if there are no more triples in this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    #[inline]
+    fn try_for_some_triple<E, F>(
+        &mut self,
+        mut f: F,
+    ) -> impl Future<Output = StreamResult<bool, Self::Error, E>>
+    where
+        E: Error,
+        F: FnMut(ATSTriple<Self>) -> Result<(), E>,
+    {
+        self.try_for_some_item(move |i| f(Self::i2t(i)))
+    }
+
+    /// Call f for all triples from this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    #[inline]
+    fn try_for_each_triple<F, E>(
+        &mut self,
+        mut f: F,
+    ) -> impl Future<Output = StreamResult<(), Self::Error, E>>
+    where
+        F: FnMut(ATSTriple<Self>) -> Result<(), E>,
+        E: Error,
+    {
+        self.try_for_each_item(move |i| f(Self::i2t(i)))
+    }
+
+    /// Call f for some triple(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `false` if there are no more triples in this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_some_triple<F>(&mut self, mut f: F) -> impl Future<Output = Result<bool, Self::Error>>
+    where
+        F: FnMut(ATSTriple<Self>),
+    {
+        self.for_some_item(move |i| f(Self::i2t(i)))
+    }
+
+    /// Call f for all triples from this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_each_triple<F>(&mut self, mut f: F) -> impl Future<Output = Result<(), Self::Error>>
+    where
+        F: FnMut(ATSTriple<Self>),
+    {
+        self.for_each_item(move |i| f(Self::i2t(i)))
+    }
+
+    /// Insert all triples from this source into the given [MutableGraph].
+    ///
+    /// Stop on the first error (in the source or in the graph).
+    /// See [`MutableGraph::insert_all`] for the meaning of the returned `usize`.
+    #[inline]
+    fn add_to_graph<G: MutableGraph>(
+        mut self,
+        graph: &mut G,
+    ) -> impl Future<Output = StreamResult<usize, Self::Error, <G as MutableGraph>::MutationError>>
+    where
+        Self: Sized,
+    {
+        async move {
+            let mut c = 0;
+            self.try_for_each_triple(|t| {
+                if graph.insert_triple(t.spo())? {
+                    c += 1;
+                }
+                Ok(())
+            })
+            .await?;
+            Ok(c)
+        }
+    }
+}
+
+/// Ensures that AsyncTripleSource acts as an type alias for any AsyncSource satisfying the conditions.
+impl<T> AsyncTripleSource for T where T: AsyncSource + IsAsyncTripleSource {}
+
+/// Type alias to denote the type of triples yielded by an [`AsyncTripleSource`].
+///
+/// See [`TSTriple`] for the rationale of this type alias.
+pub type ATSTriple<'a, TS> = <TS as IsAsyncTripleSource>::Triple<'a>;
+
+/// An asynchronous quad source is an [`AsyncSource`] producing [quads](Quad).
+///
+/// It does not need to be explicitly implemented:
+/// any [`AsyncSource`] implementation producing [quads](Quad)
+/// will automatically implement [`AsyncQuadSource`].
+///
+/// See also [`AQSQuad`].
+pub trait AsyncQuadSource: AsyncSource + IsAsyncQuadSource {
+    /// Call f for some quad(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `Ok(false)` if there are no more quads in this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    #[inline]
+    fn try_for_some_quad<E, F>(
+        &mut self,
+        mut f: F,
+    ) -> impl Future<Output = StreamResult<bool, Self::Error, E>>
+    where
+        E: Error,
+        F: FnMut(AQSQuad<Self>) -> Result<(), E>,
+    {
+        self.try_for_some_item(move |i| f(Self::i2q(i)))
+    }
+
+    /// Call f for all quads from this source.
+    ///
+    /// Resolve to an error if either the source or `f` errs.
+    #[inline]
+    fn try_for_each_quad<F, E>(
+        &mut self,
+        mut f: F,
+    ) -> impl Future<Output = StreamResult<(), Self::Error, E>>
+    where
+        F: FnMut(AQSQuad<Self>) -> Result<(), E>,
+        E: Error,
+    {
+        self.try_for_each_item(move |i| f(Self::i2q(i)))
+    }
+
+    /// Call f for some quad(s) (possibly zero) from this source, if any.
+    ///
+    /// Resolve to `false` if there are no more quads in this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_some_quad<F>(&mut self, mut f: F) -> impl Future<Output = Result<bool, Self::Error>>
+    where
+        F: FnMut(AQSQuad<Self>),
+    {
+        self.for_some_item(move |i| f(Self::i2q(i)))
+    }
+
+    /// Call f for all quads from this source.
+    ///
+    /// Resolve to an error if the source errs.
+    #[inline]
+    fn for_each_quad<F>(&mut self, mut f: F) -> impl Future<Output = Result<(), Self::Error>>
+    where
+        F: FnMut(AQSQuad<Self>),
+    {
+        self.for_each_item(move |i| f(Self::i2q(i)))
+    }
+
+    /// Insert all quads from this source into the given [MutableDataset].
+    ///
+    /// Stop on the first error (in the source or in the dataset).
+    /// See [`MutableDataset::insert_all`] for the meaning of the returned `usize`.
+    #[inline]
+    fn add_to_dataset<D: MutableDataset>(
+        mut self,
+        dataset: &mut D,
+    ) -> impl Future<Output = StreamResult<usize, Self::Error, <D as MutableDataset>::MutationError>>
+    where
+        Self: Sized,
+    {
+        async move {
+            let mut c = 0;
+            self.try_for_each_quad(|q| {
+                if dataset.insert_quad(q.spog())? {
+                    c += 1;
+                }
+                Ok(())
+            })
+            .await?;
+            Ok(c)
+        }
+    }
+}
+
+/// Ensures that AsyncQuadSource acts as an type alias for any AsyncSource satisfying the conditions.
+impl<T> AsyncQuadSource for T where T: AsyncSource + IsAsyncQuadSource {}
+
+/// Type alias to denote the type of quads yielded by an [`AsyncQuadSource`].
+///
+/// See [`QSQuad`] for the rationale of this type alias.
+pub type AQSQuad<'a, TS> = <TS as IsAsyncQuadSource>::Quad<'a>;
+
+/// Wraps a synchronous [`Source`] into an [`AsyncSource`],
+/// whose futures are always immediately ready.
+///
+/// This makes it possible to pass in-memory data
+/// (e.g. the [triples](crate::graph::Graph::triples) of a graph)
+/// to APIs expecting an [`AsyncSource`].
+#[derive(Clone, Copy, Debug)]
+pub struct ReadySource<S>(pub S);
+
+impl<S: Source> AsyncSource for ReadySource<S> {
+    type Item<'x> = S::Item<'x>;
+    type Error = S::Error;
+
+    async fn try_for_some_item<E, F>(&mut self, f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        self.0.try_for_some_item(f)
+    }
+}
+
+mod sealed {
+    use super::*;
+
+    pub trait IsAsyncTripleSource: AsyncSource {
+        type Triple<'x>: Triple;
+        fn i2t(i: Self::Item<'_>) -> Self::Triple<'_>;
+    }
+
+    impl<TS> IsAsyncTripleSource for TS
+    where
+        TS: AsyncSource,
+        for<'x> TS::Item<'x>: Triple,
+    {
+        type Triple<'x> = Self::Item<'x>;
+
+        fn i2t(i: Self::Item<'_>) -> Self::Triple<'_> {
+            i
+        }
+    }
+
+    pub trait IsAsyncQuadSource: AsyncSource {
+        type Quad<'x>: Quad;
+        fn i2q(i: Self::Item<'_>) -> Self::Quad<'_>;
+    }
+
+    impl<QS> IsAsyncQuadSource for QS
+    where
+        QS: AsyncSource,
+        for<'x> QS::Item<'x>: Quad,
+    {
+        type Quad<'x> = Self::Item<'x>;
+
+        fn i2q(i: Self::Item<'_>) -> Self::Quad<'_> {
+            i
+        }
+    }
+}
+use sealed::{IsAsyncQuadSource, IsAsyncTripleSource};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::term::SimpleTerm;
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    /// A source yielding the triples of a vector, pretending to wait before each of them.
+    struct Lazy(VecDeque<[SimpleTerm<'static>; 3]>);
+
+    /// A future that is pending once, then ready.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    impl AsyncSource for Lazy {
+        type Item<'x> = [SimpleTerm<'static>; 3];
+        type Error = Infallible;
+
+        async fn try_for_some_item<E, F>(&mut self, mut f: F) -> StreamResult<bool, Infallible, E>
+        where
+            E: Error,
+            F: FnMut(Self::Item<'_>) -> Result<(), E>,
+        {
+            YieldNow(false).await;
+            match self.0.pop_front() {
+                Some(t) => f(t).map_err(SinkError).map(|_| true),
+                None => Ok(false),
+            }
+        }
+    }
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<T>(fut: impl Future<Output = T>) -> T {
+        let waker = Arc::new(Noop).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = std::pin::pin!(fut);
+        loop {
+            if let Poll::Ready(t) = fut.as_mut().poll(&mut cx) {
+                return t;
+            }
+        }
+    }
+
+    #[test]
+    fn add_to_graph() {
+        let iri = |x: &'static str| SimpleTerm::Iri(crate::term::IriRef::new_unchecked(x.into()));
+        let t = |s| [iri(s), iri("tag:p"), iri("tag:o")];
+        let mut g: Vec<[SimpleTerm<'static>; 3]> = vec![];
+        let source = Lazy(vec![t("tag:a"), t("tag:b"), t("tag:c")].into());
+        assert_eq!(block_on(source.add_to_graph(&mut g)).unwrap(), 3);
+        assert_eq!(g, vec![t("tag:a"), t("tag:b"), t("tag:c")]);
+    }
+
+    #[test]
+    fn for_each_triple() {
+        let iri = |x: &'static str| SimpleTerm::Iri(crate::term::IriRef::new_unchecked(x.into()));
+        let t = |s| [iri(s), iri("tag:p"), iri("tag:o")];
+        let mut source = Lazy(vec![t("tag:a"), t("tag:b")].into());
+        let mut subjects = vec![];
+        block_on(source.for_each_triple(|t| subjects.push(t.s().clone()))).unwrap();
+        assert_eq!(subjects, vec![iri("tag:a"), iri("tag:b")]);
+    }
+}
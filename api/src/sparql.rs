@@ -1,4 +1,5 @@
-//! Common traits for working with [SPARQL](https://www.w3.org/TR/sparql11-query/).
+//! Common traits for working with [SPARQL](https://www.w3.org/TR/sparql11-query/)
+//! and [SPARQL Update](https://www.w3.org/TR/sparql11-update/).
 //!
 //! # Design rationale
 //!
@@ -15,23 +16,34 @@
 //!
 //! # Extension point
 //!
-//! A possible way to extend these traits with additional functionalities
+//! These traits can be extended with additional functionalities
 //! (such as the ones described above)
-//! would be to define subtraits of `Query` with additional methods
-//! (*e.g.*`set_base`, `bind_variables`...).
-//! Implementation could then express requirements as trait bound, e.g.:
+//! by subtraits of `Query` with additional methods.
+//! Implementation can then express requirements as trait bound, e.g.:
 //! ```ignore
 //!     D: SparqlDataset,
-//!     D::Query: Clone + BindVariable,
+//!     D::Query: Clone + BindVariables,
 //! ```
 //!
-//! Sophia may define such traits in the future.
+//! Sophia defines the following subtraits:
+//! - [`ParseWithDefaults`] for default `BASE` and `PREFIX` directives,
+//! - [`SetQueryDataset`] for default `FROM` and `FROM NAMED` clauses,
+//! - [`BindVariables`] for pre-binding variables.
+//!
+//! # Results formats
+//!
+//! The [`results`] module defines traits for serializing and parsing
+//! the results of SELECT and ASK queries in the standard formats.
 
+use crate::prefix::PrefixMap;
 use crate::source::TripleSource;
 use crate::term::Term;
+use sophia_iri::Iri;
 use std::borrow::Borrow;
 use std::error::Error;
 
+pub mod results;
+
 /// A dataset that can be queried with SPARQL.
 pub trait SparqlDataset {
     /// The type of terms that SELECT queries will return.
@@ -74,11 +86,43 @@ pub trait SparqlDataset {
     }
 }
 
+/// A dataset that can be modified with [SPARQL Update](https://www.w3.org/TR/sparql11-update/).
+///
+/// Implementations must apply each update request atomically:
+/// if any of its operations fails, the dataset must be left unchanged.
+pub trait SparqlUpdatable {
+    /// The type of errors that processing SPARQL update requests may raise.
+    type UpdateError: Error + 'static;
+    /// The type representing pre-processed update requests.
+    ///
+    /// See [`prepare_update`](#method.prepare_update) for more detail.
+    type Update: Query<Error = Self::UpdateError>;
+
+    /// Parse and immediately apply `update`.
+    ///
+    /// `update` is usually either a `&str` that will be parsed on the fly,
+    /// or a `Self::Update` that was earlier prepared by the [`prepare_update`] method.
+    ///
+    /// [`prepare_update`]: #method.prepare_update
+    fn update<U>(&mut self, update: U) -> Result<(), Self::UpdateError>
+    where
+        U: IntoQuery<Self::Update>;
+
+    /// Prepare an update request for multiple future executions.
+    ///
+    /// Update requests are parsed through the [`Query`] trait,
+    /// just like queries (see [`SparqlDataset::prepare_query`]).
+    fn prepare_update(&self, update_string: &str) -> Result<Self::Update, Self::UpdateError> {
+        Self::Update::parse(update_string)
+    }
+}
+
 /// Preprocessed query, ready for execution.
 ///
 /// This trait exist to allow *some* implementations of [`SparqlDataset`]
 /// to mutualize the parsing of queries in the
 /// [`prepare_query`](SparqlDataset::prepare_query) method.
+/// It is also used for update requests (see [`SparqlUpdatable`]).
 pub trait Query: Sized {
     /// The error type that might be raised when parsing a query.
     type Error: Error + 'static;
@@ -93,6 +137,57 @@ impl Query for String {
     }
 }
 
+/// A [`Query`] that can be parsed with a default base IRI and default prefix declarations.
+///
+/// These defaults apply unless they are overridden by `BASE` or `PREFIX` directives in the query string.
+pub trait ParseWithDefaults: Query {
+    /// Parse the given text into a [`Query`], with the given defaults.
+    fn parse_with_defaults<P>(
+        query_source: &str,
+        base: Option<Iri<&str>>,
+        prefixes: &P,
+    ) -> Result<Self, Self::Error>
+    where
+        P: PrefixMap + ?Sized;
+}
+
+/// A [`Query`] whose RDF dataset can be set before execution.
+pub trait SetQueryDataset: Query {
+    /// Use the merge of `default_graphs` as the default graph of this query,
+    /// and `named_graphs` as its named graphs,
+    /// unless the query specifies its own dataset with `FROM` or `FROM NAMED` clauses.
+    fn set_default_dataset<T: Term>(
+        &mut self,
+        default_graphs: &[T],
+        named_graphs: &[T],
+    ) -> Result<(), Self::Error>;
+}
+
+/// A [`Query`] whose variables can be bound to given values before execution.
+///
+/// This allows to run the same query with different parameters,
+/// without building query strings by interpolation (which is prone to injections).
+pub trait BindVariables: Query {
+    /// Bind the variable named `name` (without the leading `?` or `$`) to `value`,
+    /// replacing any previous binding of that variable.
+    fn bind_variable<T: Term>(&mut self, name: &str, value: T) -> Result<(), Self::Error>;
+
+    /// Remove the binding of the variable named `name`, if any.
+    fn unbind_variable(&mut self, name: &str);
+
+    /// Bind several variables at once (see [`bind_variable`](BindVariables::bind_variable)).
+    fn bind_variables<'a, T, I>(&mut self, bindings: I) -> Result<(), Self::Error>
+    where
+        T: Term,
+        I: IntoIterator<Item = (&'a str, T)>,
+    {
+        for (name, value) in bindings {
+            self.bind_variable(name, value)?;
+        }
+        Ok(())
+    }
+}
+
 /// A utility trait to allow [`SparqlDataset::query`]
 /// to accept either `&str` or [`Self::Query`](SparqlDataset::Query).
 pub trait IntoQuery<Q: Query> {
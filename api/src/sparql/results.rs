@@ -0,0 +1,144 @@
+//! API for serializing and parsing the results of SPARQL queries.
+//!
+//! This module specifies, through dedicated traits,
+//! how the results of SELECT and ASK queries are written to
+//! (and read from) the standard result formats,
+//! such as [SPARQL 1.1 Query Results JSON](https://www.w3.org/TR/sparql11-results-json/),
+//! [XML](https://www.w3.org/TR/rdf-sparql-XMLres/)
+//! or [CSV and TSV](https://www.w3.org/TR/sparql11-results-csv-tsv/).
+//!
+//! Like [serializers](crate::serializer),
+//! each results serializer has a specific “target” associated to it.
+//! The results of CONSTRUCT and DESCRIBE queries are plain triples,
+//! which are serialized with a [`TripleSerializer`](crate::serializer::TripleSerializer).
+
+use std::convert::Infallible;
+use std::error::Error;
+
+use super::{SparqlBindings, SparqlDataset};
+use crate::parser::IntoParsable;
+use crate::source::{StreamError, StreamResult};
+use crate::term::Term;
+
+/// A results serializer writes the results of SELECT and ASK queries according to a given format.
+pub trait ResultsSerializer {
+    /// The error type that may be raised during serialization.
+    type Error: 'static + Error;
+
+    /// Serialize the solutions of a SELECT query.
+    ///
+    /// Each row must contain the value of every variable, in the same order as `variables`,
+    /// `None` indicating that the variable is not bound.
+    fn serialize_bindings<I, R, T, E>(
+        &mut self,
+        variables: &[&str],
+        rows: I,
+    ) -> StreamResult<&mut Self, E, Self::Error>
+    where
+        I: IntoIterator<Item = Result<R, E>>,
+        R: AsRef<[Option<T>]>,
+        T: Term,
+        E: 'static + Error,
+        Self: Sized;
+
+    /// Serialize the result of an ASK query.
+    fn serialize_boolean(&mut self, value: bool) -> Result<&mut Self, Self::Error>
+    where
+        Self: Sized;
+
+    /// Serialize the [bindings](SparqlBindings) returned by a [`SparqlDataset`].
+    fn serialize_sparql_bindings<D, B>(
+        &mut self,
+        bindings: B,
+    ) -> StreamResult<&mut Self, D::SparqlError, Self::Error>
+    where
+        D: SparqlDataset + ?Sized,
+        B: SparqlBindings<D>,
+        Self: Sized,
+    {
+        let variables: Vec<String> = bindings.variables().into_iter().map(String::from).collect();
+        let variables: Vec<&str> = variables.iter().map(String::as_str).collect();
+        self.serialize_bindings(&variables, bindings)
+    }
+
+    /// Serialize [`QueryResults`], typically produced by a [`ResultsParser`].
+    fn serialize_results<T>(&mut self, results: &QueryResults<T>) -> Result<&mut Self, Self::Error>
+    where
+        T: Term,
+        Self: Sized,
+    {
+        match results {
+            QueryResults::Bindings { variables, rows } => {
+                let variables: Vec<&str> = variables.iter().map(String::as_str).collect();
+                self.serialize_bindings(&variables, rows.iter().map(Ok::<_, Infallible>))
+                    .map_err(StreamError::unwrap_sink_error)
+            }
+            QueryResults::Boolean(value) => self.serialize_boolean(*value),
+        }
+    }
+}
+
+/// A results parser takes some data of type `T`,
+/// and returns the [`QueryResults`] it contains.
+pub trait ResultsParser<T> {
+    /// The type of terms produced by this parser.
+    type Term: Term;
+    /// The error type that may be raised during parsing.
+    type Error: 'static + Error;
+
+    /// Parses data into query results.
+    fn parse_results(&self, data: T) -> Result<QueryResults<Self::Term>, Self::Error>;
+
+    /// Convenient shortcut method for parsing strings.
+    ///
+    /// It may not be available on some exotic parsers,
+    /// but will be automatically supported for parsers supporting any
+    /// [`BufRead`] or [`Read`].
+    ///
+    /// [`BufRead`]: std::io::BufRead
+    /// [`Read`]: std::io::Read
+    fn parse_results_str<'t>(&self, txt: &'t str) -> Result<QueryResults<Self::Term>, Self::Error>
+    where
+        &'t str: IntoParsable<Target = T>,
+    {
+        self.parse_results(txt.into_parsable())
+    }
+}
+
+/// The results of a SELECT or ASK query, as produced by a [`ResultsParser`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryResults<T> {
+    /// The solutions of a SELECT query
+    Bindings {
+        /// The names of the SELECTed variables
+        variables: Vec<String>,
+        /// The values of the variables (in the order of `variables`) for each solution
+        rows: Vec<Vec<Option<T>>>,
+    },
+    /// The result of an ASK query
+    Boolean(bool),
+}
+
+impl<T> QueryResults<T> {
+    /// Get the variables and rows of these results.
+    ///
+    /// # Panics
+    /// This will panic if `self` is actually a `Boolean`.
+    pub fn into_bindings(self) -> (Vec<String>, Vec<Vec<Option<T>>>) {
+        match self {
+            QueryResults::Bindings { variables, rows } => (variables, rows),
+            _ => panic!("This QueryResults is not a Bindings"),
+        }
+    }
+
+    /// Get these results as a `Boolean`.
+    ///
+    /// # Panics
+    /// This will panic if `self` is actually a `Bindings`.
+    pub fn into_boolean(self) -> bool {
+        match self {
+            QueryResults::Boolean(b) => b,
+            _ => panic!("This QueryResults is not a Boolean"),
+        }
+    }
+}
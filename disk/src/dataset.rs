@@ -0,0 +1,631 @@
+//! Persistent implementation of [`Dataset`]
+use std::error::Error;
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::Arc;
+
+use redb::backends::FileBackend;
+use redb::{Database, ReadableTableMetadata, Table, TableDefinition, WriteTransaction};
+use sophia_api::dataset::{CollectibleDataset, DResult, MdResult, SetDataset};
+use sophia_api::prelude::*;
+use sophia_api::quad::Gspo;
+use sophia_api::source::{StreamError, StreamError::SinkError, StreamResult};
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
+use sophia_api::term::GraphName;
+
+use crate::error::DiskError;
+use crate::index::{DiskTermIndex, RedbTermIndex};
+
+/// The key of a quad in one of the quad tables
+type Key = (u64, u64, u64, u64);
+
+/// The number of keys read at once when iterating over a quad table.
+const CHUNK_SIZE: usize = 1024;
+
+/// The maximum value returned by `estimate_matching`,
+/// ensuring that estimates are cheap to compute.
+const ESTIMATE_CAP: usize = 1024;
+
+/// A dataset stored in a file, with four quad indexes (GSPO, SPOG, POSG, OSPG).
+///
+/// Quads are stored as keys of an embedded [`redb`] database,
+/// where each term is represented by its index in a [`DiskTermIndex`],
+/// which stores the terms in the same database.
+/// Quads are read lazily, so that the dataset does not need to fit in memory.
+///
+/// # Transactions
+///
+/// Every call to [`insert`](MutableDataset::insert) or [`remove`](MutableDataset::remove)
+/// opens and commits its own write transaction,
+/// which is flushed to disk before the method returns.
+/// Inserting or removing quads one by one is therefore *slow*,
+/// and should be avoided for more than a handful of quads.
+///
+/// [`insert_all`](MutableDataset::insert_all) and [`remove_all`](MutableDataset::remove_all),
+/// on the other hand, commit a single transaction for all the quads of their source,
+/// and are therefore both much faster and atomic.
+pub struct GenericDiskDataset<TI: DiskTermIndex> {
+    db: Arc<Database>,
+    terms: TI,
+}
+
+/// A [`GenericDiskDataset`] reading its terms lazily from the database.
+pub type DiskDataset = GenericDiskDataset<RedbTermIndex>;
+
+impl<TI: DiskTermIndex> GenericDiskDataset<TI> {
+    /// Open the dataset stored in the file at `path`, creating it if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DiskError> {
+        Self::from_database(Database::create(path)?)
+    }
+
+    /// Create an empty dataset in an anonymous temporary file,
+    /// which is deleted when the dataset is dropped.
+    pub fn temporary() -> Result<Self, DiskError> {
+        let file = tempfile::tempfile().map_err(redb::StorageError::Io)?;
+        let db = Database::builder().create_with_backend(FileBackend::new(file)?)?;
+        Self::from_database(db)
+    }
+
+    fn from_database(db: Database) -> Result<Self, DiskError> {
+        let txn = db.begin_write()?;
+        open_quad_tables(&txn)?;
+        txn.commit()?;
+        let db = Arc::new(db);
+        let terms = TI::open(db.clone())?;
+        Ok(Self { db, terms })
+    }
+
+    /// Run `f` in a new write transaction, which is committed if `f` succeeds, and aborted otherwise.
+    fn write<R, E: Error>(
+        &mut self,
+        f: impl FnOnce(&mut TI, &mut QuadTables) -> Result<R, StreamError<E, DiskError>>,
+    ) -> Result<R, StreamError<E, DiskError>> {
+        let txn = self.db.begin_write().map_err(|e| SinkError(e.into()))?;
+        let res = open_quad_tables(&txn)
+            .map_err(|e| SinkError(e.into()))
+            .and_then(|mut quads| f(&mut self.terms, &mut quads))
+            .and_then(|r| {
+                self.terms.save(&txn).map_err(SinkError)?;
+                txn.commit().map_err(|e| SinkError(e.into()))?;
+                Ok(r)
+            });
+        let ended = self.terms.end_transaction(res.is_ok());
+        let r = res?;
+        ended.map_err(SinkError)?;
+        Ok(r)
+    }
+
+    /// Convert the constant terms of a pattern into identifiers.
+    ///
+    /// Return `None` if one of the terms is not in the dataset (so that nothing can match).
+    fn pattern<T: Term>(
+        &self,
+        spo: [Option<T>; 3],
+        g: Option<GraphName<T>>,
+    ) -> Result<Option<[Option<u64>; 4]>, DiskError> {
+        let mut pattern = [None; 4];
+        for (i, t) in spo.into_iter().enumerate() {
+            if let Some(t) = t {
+                let Some(id) = self.terms.try_get_index(t)? else {
+                    return Ok(None);
+                };
+                pattern[i + 1] = Some(id);
+            }
+        }
+        match g {
+            None => {}
+            Some(None) => pattern[0] = Some(self.terms.get_default_graph_index()),
+            Some(Some(g)) => {
+                let Some(id) = self.terms.try_get_index(g)? else {
+                    return Ok(None);
+                };
+                pattern[0] = Some(id);
+            }
+        }
+        Ok(Some(pattern))
+    }
+
+    fn quad(
+        &self,
+        [g, s, p, o]: [u64; 4],
+    ) -> Result<Gspo<<TI::Term as Term>::BorrowTerm<'_>>, DiskError> {
+        let g = if g == self.terms.get_default_graph_index() {
+            None
+        } else {
+            Some(self.terms.try_get_term(g)?)
+        };
+        Ok((
+            g,
+            [
+                self.terms.try_get_term(s)?,
+                self.terms.try_get_term(p)?,
+                self.terms.try_get_term(o)?,
+            ],
+        ))
+    }
+
+    /// Iterate over the quads matching `pattern` (in GSPO order), using the most appropriate index.
+    ///
+    /// NB: the returned quads may not all match `pattern`, only those matching it are guaranteed to be returned.
+    fn scan(&self, pattern: [Option<u64>; 4]) -> Scan<'_, TI> {
+        let (index, prefix) = QuadIndex::choose(pattern);
+        let (start, end) = index.range(pattern, prefix);
+        Scan {
+            dataset: self,
+            index,
+            start: Bound::Included(start),
+            end,
+            buffer: vec![].into_iter(),
+            exhausted: false,
+        }
+    }
+}
+
+impl<TI: DiskTermIndex> Dataset for GenericDiskDataset<TI> {
+    type Quad<'x>
+        = Gspo<<TI::Term as Term>::BorrowTerm<'x>>
+    where
+        Self: 'x;
+    type Error = DiskError;
+
+    fn quads(&self) -> impl Iterator<Item = DResult<Self, Self::Quad<'_>>> + '_ {
+        self.scan([None; 4])
+    }
+
+    #[allow(refining_impl_trait)]
+    fn quads_matching<'s, S, P, O, G>(
+        &'s self,
+        sm: S,
+        pm: P,
+        om: O,
+        gm: G,
+    ) -> Box<dyn Iterator<Item = DResult<Self, Self::Quad<'s>>> + 's>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+        G: GraphNameMatcher + 's,
+    {
+        let spo = [
+            sm.constant().map(Term::as_simple),
+            pm.constant().map(Term::as_simple),
+            om.constant().map(Term::as_simple),
+        ];
+        let g = gm.constant().map(|g| g.map(Term::as_simple));
+        let pattern = match self.pattern(spo, g) {
+            Ok(Some(pattern)) => pattern,
+            Ok(None) => return Box::new(std::iter::empty()),
+            Err(err) => return Box::new(std::iter::once(Err(err))),
+        };
+        Box::new(self.scan(pattern).filter(move |res| match res {
+            Ok((g, [s, p, o])) => {
+                sm.matches(s) && pm.matches(p) && om.matches(o) && gm.matches(g.as_ref())
+            }
+            Err(_) => true,
+        }))
+    }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+        g: Option<GraphName<T>>,
+    ) -> Option<usize> {
+        let Some(pattern) = self.pattern([s, p, o], g).ok()? else {
+            return Some(0);
+        };
+        let (index, prefix) = QuadIndex::choose(pattern);
+        let txn = self.db.begin_read().ok()?;
+        let table = txn.open_table(index.table()).ok()?;
+        if prefix == 0 {
+            return usize::try_from(table.len().ok()?).ok();
+        }
+        let (start, end) = index.range(pattern, prefix);
+        Some(table.range(start..=end).ok()?.take(ESTIMATE_CAP).count())
+    }
+}
+
+impl<TI: DiskTermIndex> MutableDataset for GenericDiskDataset<TI> {
+    type MutationError = DiskError;
+
+    /// Insert the given quad, in its own transaction (see [`GenericDiskDataset`]).
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        self.write(
+            |terms, quads| -> StreamResult<_, std::convert::Infallible, _> {
+                let key = ensure_key(terms, s, p, o, g).map_err(SinkError)?;
+                insert_key(quads, key).map_err(|e| SinkError(e.into()))
+            },
+        )
+        .map_err(StreamError::unwrap_sink_error)
+    }
+
+    /// Remove the given quad, in its own transaction (see [`GenericDiskDataset`]).
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        let Some(key) = get_key(&self.terms, s, p, o, g)? else {
+            return Ok(false);
+        };
+        self.write(|_, quads| -> StreamResult<_, std::convert::Infallible, _> {
+            remove_key(quads, key).map_err(|e| SinkError(e.into()))
+        })
+        .map_err(StreamError::unwrap_sink_error)
+    }
+
+    fn insert_all<TS: QuadSource>(
+        &mut self,
+        mut src: TS,
+    ) -> StreamResult<usize, TS::Error, Self::MutationError> {
+        let mut c = 0;
+        self.write(|terms, quads| {
+            src.try_for_each_quad(|q| -> MdResult<Self, ()> {
+                let key = ensure_key(terms, q.s(), q.p(), q.o(), q.g())?;
+                if insert_key(quads, key)? {
+                    c += 1;
+                }
+                Ok(())
+            })
+        })?;
+        Ok(c)
+    }
+
+    fn remove_all<TS: QuadSource>(
+        &mut self,
+        mut src: TS,
+    ) -> StreamResult<usize, TS::Error, Self::MutationError> {
+        let mut c = 0;
+        self.write(|terms, quads| {
+            src.try_for_each_quad(|q| -> MdResult<Self, ()> {
+                if let Some(key) = get_key(terms, q.s(), q.p(), q.o(), q.g())? {
+                    if remove_key(quads, key)? {
+                        c += 1;
+                    }
+                }
+                Ok(())
+            })
+        })?;
+        Ok(c)
+    }
+}
+
+impl<TI: DiskTermIndex> CollectibleDataset for GenericDiskDataset<TI> {
+    /// Collect `quads` into a [temporary](GenericDiskDataset::temporary) dataset.
+    fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Self::Error> {
+        let mut d = Self::temporary().map_err(SinkError)?;
+        d.insert_all(quads)?;
+        Ok(d)
+    }
+}
+
+impl<TI: DiskTermIndex> SetDataset for GenericDiskDataset<TI> {}
+
+//
+
+/// Get the key of the given quad, adding its terms to `terms` if necessary.
+fn ensure_key<TI, TS, TP, TO, TG>(
+    terms: &mut TI,
+    s: TS,
+    p: TP,
+    o: TO,
+    g: GraphName<TG>,
+) -> Result<[u64; 4], DiskError>
+where
+    TI: DiskTermIndex,
+    TS: Term,
+    TP: Term,
+    TO: Term,
+    TG: Term,
+{
+    let ig = match g {
+        None => terms.get_default_graph_index(),
+        Some(g) => terms.ensure_index(g)?,
+    };
+    Ok([
+        ig,
+        terms.ensure_index(s)?,
+        terms.ensure_index(p)?,
+        terms.ensure_index(o)?,
+    ])
+}
+
+/// Get the key of the given quad, if all its terms are in `terms`.
+fn get_key<TI, TS, TP, TO, TG>(
+    terms: &TI,
+    s: TS,
+    p: TP,
+    o: TO,
+    g: GraphName<TG>,
+) -> Result<Option<[u64; 4]>, DiskError>
+where
+    TI: DiskTermIndex,
+    TS: Term,
+    TP: Term,
+    TO: Term,
+    TG: Term,
+{
+    let ig = match g {
+        None => Some(terms.get_default_graph_index()),
+        Some(g) => terms.try_get_index(g)?,
+    };
+    let (Some(ig), Some(is), Some(ip), Some(io)) = (
+        ig,
+        terms.try_get_index(s)?,
+        terms.try_get_index(p)?,
+        terms.try_get_index(o)?,
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some([ig, is, ip, io]))
+}
+
+/// The quad indexes, each stored in its own table.
+#[derive(Clone, Copy, Debug)]
+enum QuadIndex {
+    Gspo,
+    Spog,
+    Posg,
+    Ospg,
+}
+
+impl QuadIndex {
+    const ALL: [QuadIndex; 4] = [Self::Gspo, Self::Spog, Self::Posg, Self::Ospg];
+
+    fn table(self) -> TableDefinition<'static, Key, ()> {
+        match self {
+            Self::Gspo => TableDefinition::new("gspo"),
+            Self::Spog => TableDefinition::new("spog"),
+            Self::Posg => TableDefinition::new("posg"),
+            Self::Ospg => TableDefinition::new("ospg"),
+        }
+    }
+
+    /// The position, in GSPO order, of each component of the keys of this index.
+    fn order(self) -> [usize; 4] {
+        match self {
+            Self::Gspo => [0, 1, 2, 3],
+            Self::Spog => [1, 2, 3, 0],
+            Self::Posg => [2, 3, 1, 0],
+            Self::Ospg => [3, 1, 2, 0],
+        }
+    }
+
+    /// Convert `gspo` into a key of this index.
+    fn key(self, gspo: [u64; 4]) -> Key {
+        let [a, b, c, d] = self.order().map(|i| gspo[i]);
+        (a, b, c, d)
+    }
+
+    /// Convert a key of this index into GSPO order.
+    fn gspo(self, (a, b, c, d): Key) -> [u64; 4] {
+        let mut gspo = [0; 4];
+        for (i, v) in self.order().into_iter().zip([a, b, c, d]) {
+            gspo[i] = v;
+        }
+        gspo
+    }
+
+    /// Choose the index where the most components of `pattern` form a prefix,
+    /// and return it together with the length of that prefix.
+    fn choose(pattern: [Option<u64>; 4]) -> (Self, usize) {
+        Self::ALL
+            .into_iter()
+            .rev() // so that max_by_key returns the first index in case of a tie
+            .map(|index| {
+                let prefix = index
+                    .order()
+                    .into_iter()
+                    .take_while(|i| pattern[*i].is_some())
+                    .count();
+                (index, prefix)
+            })
+            .max_by_key(|(_, prefix)| *prefix)
+            .unwrap()
+    }
+
+    /// The range of keys of this index starting with the first `prefix` components of `pattern`.
+    fn range(self, pattern: [Option<u64>; 4], prefix: usize) -> (Key, Key) {
+        let mut start = [0; 4];
+        let mut end = [u64::MAX; 4];
+        for i in self.order().into_iter().take(prefix) {
+            start[i] = pattern[i].unwrap();
+            end[i] = pattern[i].unwrap();
+        }
+        (self.key(start), self.key(end))
+    }
+}
+
+type QuadTables<'t> = [Table<'t, Key, ()>; 4];
+
+fn open_quad_tables(txn: &WriteTransaction) -> Result<QuadTables<'_>, redb::TableError> {
+    let [a, b, c, d] = QuadIndex::ALL.map(|index| txn.open_table(index.table()));
+    Ok([a?, b?, c?, d?])
+}
+
+/// Insert the quad `gspo` in all the quad tables, and return whether it was absent.
+fn insert_key(tables: &mut QuadTables, gspo: [u64; 4]) -> Result<bool, redb::StorageError> {
+    let [gspo_table, others @ ..] = tables;
+    if gspo_table.insert(QuadIndex::Gspo.key(gspo), ())?.is_some() {
+        return Ok(false);
+    }
+    for (table, index) in others.iter_mut().zip(&QuadIndex::ALL[1..]) {
+        table.insert(index.key(gspo), ())?;
+    }
+    Ok(true)
+}
+
+/// Remove the quad `gspo` from all the quad tables, and return whether it was present.
+fn remove_key(tables: &mut QuadTables, gspo: [u64; 4]) -> Result<bool, redb::StorageError> {
+    let [gspo_table, others @ ..] = tables;
+    if gspo_table.remove(QuadIndex::Gspo.key(gspo))?.is_none() {
+        return Ok(false);
+    }
+    for (table, index) in others.iter_mut().zip(&QuadIndex::ALL[1..]) {
+        table.remove(index.key(gspo))?;
+    }
+    Ok(true)
+}
+
+/// An iterator over a range of a quad table, yielding quads in GSPO order.
+///
+/// Keys are read by chunks of [`CHUNK_SIZE`], each in its own read transaction,
+/// so that the iterator does not borrow any transaction.
+struct Scan<'a, TI: DiskTermIndex> {
+    dataset: &'a GenericDiskDataset<TI>,
+    index: QuadIndex,
+    start: Bound<Key>,
+    end: Key,
+    /// Keys in GSPO order
+    buffer: std::vec::IntoIter<[u64; 4]>,
+    exhausted: bool,
+}
+
+impl<TI: DiskTermIndex> Scan<'_, TI> {
+    fn fetch(&mut self) -> Result<(), DiskError> {
+        let txn = self.dataset.db.begin_read()?;
+        let table = txn.open_table(self.index.table())?;
+        let mut keys = Vec::with_capacity(CHUNK_SIZE);
+        for entry in table
+            .range::<Key>((self.start, Bound::Included(self.end)))?
+            .take(CHUNK_SIZE)
+        {
+            keys.push(self.index.gspo(entry?.0.value()));
+        }
+        self.exhausted = keys.len() < CHUNK_SIZE;
+        if let Some(last) = keys.last() {
+            self.start = Bound::Excluded(self.index.key(*last));
+        }
+        self.buffer = keys.into_iter();
+        Ok(())
+    }
+}
+
+impl<'a, TI: DiskTermIndex> Iterator for Scan<'a, TI> {
+    type Item = Result<Gspo<<TI::Term as Term>::BorrowTerm<'a>>, DiskError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(key) = self.buffer.next() {
+                return Some(self.dataset.quad(key));
+            }
+            if self.exhausted {
+                return None;
+            }
+            if let Err(err) = self.fetch() {
+                self.exhausted = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::rdf;
+    use sophia_api::term::{FromTerm, SimpleTerm};
+
+    sophia_api::test_dataset_impl!(disk_dataset, DiskDataset);
+
+    #[test]
+    fn choose_index() {
+        let (a, b) = (Some(1), Some(2));
+        assert!(matches!(QuadIndex::choose([None; 4]), (QuadIndex::Gspo, 0)));
+        assert!(matches!(
+            QuadIndex::choose([a, b, None, None]),
+            (QuadIndex::Gspo, 2)
+        ));
+        assert!(matches!(
+            QuadIndex::choose([None, a, None, b]),
+            (QuadIndex::Ospg, 2)
+        ));
+        assert!(matches!(
+            QuadIndex::choose([None, None, a, None]),
+            (QuadIndex::Posg, 1)
+        ));
+        for index in QuadIndex::ALL {
+            assert_eq!(index.gspo(index.key([1, 2, 3, 4])), [1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn many_quads() -> Result<(), Box<dyn std::error::Error>> {
+        // more quads than CHUNK_SIZE, to check that scans are correctly resumed
+        let n = 2 * CHUNK_SIZE as i32 + 10;
+        let mut d = DiskDataset::temporary()?;
+        let quads =
+            (0..n).map(|i| Ok::<_, std::convert::Infallible>(([i, i + 1, i + 2], None::<i32>)));
+        assert_eq!(d.insert_all(quads)?, n as usize);
+        assert_eq!(d.quads().count(), n as usize);
+        assert_eq!(d.quads_matching(Any, Any, Any, Any).count(), n as usize);
+        assert_eq!(d.quads_matching([42], Any, Any, Any).count(), 1);
+        assert_eq!(d.quads_matching(Any, [43, 44], Any, Any).count(), 2);
+        assert_eq!(d.estimate_matching(Some(42), None, None, None), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_insert_all() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Debug, thiserror::Error)]
+        #[error("interrupted source")]
+        struct Interrupted;
+
+        let mut d = DiskDataset::temporary()?;
+        let quads = vec![Ok(([1, 2, 3], None)), Err(Interrupted)];
+        assert!(d.insert_all(quads.into_iter()).is_err());
+        assert_eq!(d.quads().count(), 0);
+        assert!(d.insert(4, 5, 6, None as Option<i32>)?);
+        assert!(d.insert(1, 2, 3, None as Option<i32>)?);
+        let got: Vec<_> = d.quads().collect::<Result<_, _>>()?;
+        assert_eq!(got.len(), 2);
+        assert!(d.contains(1, 2, 3, None as Option<i32>)?);
+        Ok(())
+    }
+
+    #[test]
+    fn persistence() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("test.redb");
+        let s = SimpleTerm::from_term(rdf::type_);
+        let lit = SimpleTerm::from_term("hello");
+        let g = SimpleTerm::from_term(rdf::Property);
+        {
+            let mut d = DiskDataset::open(&path)?;
+            assert!(d.insert(&s, &s, &lit, Some(&g))?);
+            assert!(d.insert(&s, &s, &s, None as Option<&SimpleTerm>)?);
+            assert!(d.remove(&s, &s, &s, None as Option<&SimpleTerm>)?);
+        }
+        let mut d = DiskDataset::open(&path)?;
+        assert_eq!(d.quads().count(), 1);
+        assert!(d.contains(&s, &s, &lit, Some(&g))?);
+        assert!(!d.insert(&s, &s, &lit, Some(&g))?);
+        assert!(d.insert(&g, &s, &lit, None as Option<&SimpleTerm>)?);
+        drop(d);
+        let d = DiskDataset::open(&path)?;
+        assert_eq!(d.quads().count(), 2);
+        assert_eq!(d.quads_matching([&g], Any, Any, Any).count(), 1);
+        Ok(())
+    }
+}
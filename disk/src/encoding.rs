@@ -0,0 +1,145 @@
+//! Binary encoding of terms in the term table.
+//!
+//! Each term is encoded as a one-byte tag, followed by its components.
+//! Strings are encoded as their length (u32, little endian) followed by their UTF-8 bytes.
+//! Quoted triples are encoded as the concatenation of the encoding of their three terms.
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, SimpleTerm, Term, TermKind, VarName};
+
+const IRI: u8 = b'I';
+const BNODE: u8 = b'B';
+const LITERAL_DT: u8 = b'D';
+const LITERAL_LANG: u8 = b'L';
+const TRIPLE: u8 = b'T';
+const VARIABLE: u8 = b'V';
+
+/// Append the encoding of `t` to `buf`.
+pub(crate) fn encode_term<T: Term>(t: T, buf: &mut Vec<u8>) {
+    match t.kind() {
+        TermKind::Iri => {
+            buf.push(IRI);
+            encode_str(&t.iri().unwrap(), buf);
+        }
+        TermKind::BlankNode => {
+            buf.push(BNODE);
+            encode_str(&t.bnode_id().unwrap(), buf);
+        }
+        TermKind::Literal => {
+            let lex = t.lexical_form().unwrap();
+            if let Some(tag) = t.language_tag() {
+                buf.push(LITERAL_LANG);
+                encode_str(&lex, buf);
+                encode_str(&tag, buf);
+            } else {
+                buf.push(LITERAL_DT);
+                encode_str(&lex, buf);
+                encode_str(&t.datatype().unwrap(), buf);
+            }
+        }
+        TermKind::Triple => {
+            buf.push(TRIPLE);
+            for t in t.triple().unwrap() {
+                encode_term(t, buf);
+            }
+        }
+        TermKind::Variable => {
+            buf.push(VARIABLE);
+            encode_str(&t.variable().unwrap(), buf);
+        }
+    }
+}
+
+fn encode_str(s: &str, buf: &mut Vec<u8>) {
+    let len = u32::try_from(s.len()).expect("term too long to be stored");
+    buf.extend_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Decode a term produced by [`encode_term`].
+///
+/// Return `None` if `bytes` is not a valid encoding.
+pub(crate) fn decode_term(bytes: &[u8]) -> Option<SimpleTerm<'static>> {
+    let (t, rest) = decode_prefix(bytes)?;
+    rest.is_empty().then_some(t)
+}
+
+fn decode_prefix(bytes: &[u8]) -> Option<(SimpleTerm<'static>, &[u8])> {
+    let (tag, rest) = bytes.split_first()?;
+    match *tag {
+        IRI => {
+            let (iri, rest) = decode_str(rest)?;
+            let iri = IriRef::new(iri.into()).ok()?;
+            Some((SimpleTerm::Iri(iri), rest))
+        }
+        BNODE => {
+            let (id, rest) = decode_str(rest)?;
+            let id = BnodeId::new(id.into()).ok()?;
+            Some((SimpleTerm::BlankNode(id), rest))
+        }
+        LITERAL_DT => {
+            let (lex, rest) = decode_str(rest)?;
+            let (dt, rest) = decode_str(rest)?;
+            let dt = IriRef::new(dt.into()).ok()?;
+            Some((SimpleTerm::LiteralDatatype(lex.into(), dt), rest))
+        }
+        LITERAL_LANG => {
+            let (lex, rest) = decode_str(rest)?;
+            let (tag, rest) = decode_str(rest)?;
+            let tag = LanguageTag::new(tag.into()).ok()?;
+            Some((SimpleTerm::LiteralLanguage(lex.into(), tag), rest))
+        }
+        TRIPLE => {
+            let (s, rest) = decode_prefix(rest)?;
+            let (p, rest) = decode_prefix(rest)?;
+            let (o, rest) = decode_prefix(rest)?;
+            Some((SimpleTerm::Triple(Box::new([s, p, o])), rest))
+        }
+        VARIABLE => {
+            let (name, rest) = decode_str(rest)?;
+            let name = VarName::new(name.into()).ok()?;
+            Some((SimpleTerm::Variable(name), rest))
+        }
+        _ => None,
+    }
+}
+
+fn decode_str(bytes: &[u8]) -> Option<(String, &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (s, rest) = rest.split_at(len);
+    Some((String::from_utf8(s.to_vec()).ok()?, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::{rdf, xsd};
+
+    #[test]
+    fn roundtrip() {
+        let iri = rdf::type_.into_term::<SimpleTerm>();
+        let terms: Vec<SimpleTerm<'static>> = vec![
+            iri.clone(),
+            BnodeId::new_unchecked("b1").into_term(),
+            "hello\u{0}world".into_term(),
+            42.into_term(),
+            SimpleTerm::LiteralLanguage("chat".into(), LanguageTag::new_unchecked("fr".into())),
+            VarName::new_unchecked("x").into_term(),
+            SimpleTerm::Triple(Box::new([
+                iri.clone(),
+                xsd::string.into_term(),
+                SimpleTerm::Triple(Box::new([iri.clone(), iri.clone(), iri])),
+            ])),
+        ];
+        for t in terms {
+            let mut buf = vec![];
+            encode_term(&t, &mut buf);
+            assert_eq!(decode_term(&buf), Some(t.clone()));
+            assert_eq!(decode_term(&buf[..buf.len() - 1]), None);
+            buf.push(0);
+            assert_eq!(decode_term(&buf), None);
+        }
+    }
+}
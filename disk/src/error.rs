@@ -0,0 +1,33 @@
+//! Errors raised by [`DiskDataset`](crate::DiskDataset).
+
+/// An error raised by a [`DiskDataset`](crate::DiskDataset).
+#[derive(Debug, thiserror::Error)]
+pub enum DiskError {
+    /// An error raised by the underlying storage
+    #[error("storage error: {0}")]
+    Storage(#[source] Box<redb::Error>),
+    /// The stored data is not valid
+    #[error("corrupted dataset: {0}")]
+    Corrupted(String),
+}
+
+macro_rules! impl_from_redb_error {
+    ($($err: ident),*) => {
+        $(
+            impl From<redb::$err> for DiskError {
+                fn from(value: redb::$err) -> Self {
+                    DiskError::Storage(Box::new(value.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_redb_error!(
+    Error,
+    DatabaseError,
+    TransactionError,
+    TableError,
+    StorageError,
+    CommitError
+);
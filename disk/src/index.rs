@@ -0,0 +1,253 @@
+//! [Term indexes](TermIndex) storing their terms in the database of a [`GenericDiskDataset`](crate::GenericDiskDataset).
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use redb::{Database, ReadOnlyTable, ReadableTable, TableDefinition, WriteTransaction};
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_inmem::index::{GraphNameIndex, TermIndex};
+
+use crate::encoding::{decode_term, encode_term};
+use crate::error::DiskError;
+
+/// The table associating term identifiers with the encoding of terms
+const TERMS: TableDefinition<u64, &[u8]> = TableDefinition::new("terms");
+
+/// The table associating the encoding of terms with their identifier
+const TERM_IDS: TableDefinition<&[u8], u64> = TableDefinition::new("term_ids");
+
+/// The identifier standing for the default graph, which is never used for a term.
+const DEFAULT_GRAPH: u64 = 0;
+
+/// A [`GraphNameIndex`] whose terms are stored in the database of a [`GenericDiskDataset`](crate::GenericDiskDataset).
+///
+/// Terms added with [`ensure_index`](TermIndex::ensure_index)
+/// are only [saved](DiskTermIndex::save) in the write transaction where the quads using them are inserted,
+/// so that they are discarded if that transaction is aborted.
+pub trait DiskTermIndex: GraphNameIndex<Index = u64, Error = DiskError> + Sized {
+    /// Open the term index stored in `db`, creating its tables if necessary.
+    fn open(db: Arc<Database>) -> Result<Self, DiskError>;
+
+    /// Same as [`get_index`](TermIndex::get_index), but reporting storage errors.
+    fn try_get_index<T: Term>(&self, t: T) -> Result<Option<u64>, DiskError> {
+        Ok(self.get_index(t))
+    }
+
+    /// Same as [`get_term`](TermIndex::get_term), but reporting storage errors.
+    fn try_get_term(&self, i: u64) -> Result<<Self::Term as Term>::BorrowTerm<'_>, DiskError> {
+        Ok(self.get_term(i))
+    }
+
+    /// Save, as part of `txn`, the terms added since the last transaction.
+    fn save(&mut self, txn: &WriteTransaction) -> Result<(), DiskError>;
+
+    /// Notify this index that the last write transaction was committed (or aborted, if `committed` is false).
+    fn end_transaction(&mut self, committed: bool) -> Result<(), DiskError>;
+}
+
+/// A [`DiskTermIndex`] reading terms lazily from the term tables of the database,
+/// so that they do not need to fit in memory.
+///
+/// Terms returned by [`get_term`](TermIndex::get_term) are borrowed from the index,
+/// so they are kept in memory until the end of the next write transaction.
+///
+/// # Panics
+/// The methods of [`TermIndex`] panic if the database can not be read;
+/// [`try_get_index`](DiskTermIndex::try_get_index) and [`try_get_term`](DiskTermIndex::try_get_term)
+/// report such errors instead.
+pub struct RedbTermIndex {
+    db: Arc<Database>,
+    /// The term tables, as of the last committed transaction
+    terms: ReadOnlyTable<u64, &'static [u8]>,
+    ids: ReadOnlyTable<&'static [u8], u64>,
+    /// The identifier of the first term which is not in `terms`
+    next: u64,
+    /// The encoding of the terms added since the last committed transaction,
+    /// identified from `next` onwards
+    unsaved: Vec<Box<[u8]>>,
+    unsaved_ids: HashMap<Box<[u8]>, u64>,
+    /// The terms returned by `get_term`
+    decoded: Mutex<HashMap<u64, Box<SimpleTerm<'static>>>>,
+}
+
+impl RedbTermIndex {
+    /// Get the identifier of the term encoded as `bytes`, if any.
+    fn find(&self, bytes: &[u8]) -> Result<Option<u64>, DiskError> {
+        if let Some(i) = self.unsaved_ids.get(bytes) {
+            return Ok(Some(*i));
+        }
+        Ok(self.ids.get(bytes)?.map(|i| i.value()))
+    }
+
+    /// Read the term tables as of the last committed transaction.
+    fn refresh(&mut self) -> Result<(), DiskError> {
+        let txn = self.db.begin_read()?;
+        self.terms = txn.open_table(TERMS)?;
+        self.ids = txn.open_table(TERM_IDS)?;
+        Ok(())
+    }
+}
+
+impl DiskTermIndex for RedbTermIndex {
+    fn open(db: Arc<Database>) -> Result<Self, DiskError> {
+        let txn = db.begin_write()?;
+        txn.open_table(TERMS)?;
+        txn.open_table(TERM_IDS)?;
+        txn.commit()?;
+        let txn = db.begin_read()?;
+        let terms = txn.open_table(TERMS)?;
+        let ids = txn.open_table(TERM_IDS)?;
+        let next = match terms.last()? {
+            Some((last, _)) => last.value() + 1,
+            None => DEFAULT_GRAPH + 1,
+        };
+        Ok(RedbTermIndex {
+            db,
+            terms,
+            ids,
+            next,
+            unsaved: vec![],
+            unsaved_ids: HashMap::new(),
+            decoded: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn try_get_index<T: Term>(&self, t: T) -> Result<Option<u64>, DiskError> {
+        let mut buf = vec![];
+        encode_term(t, &mut buf);
+        self.find(&buf)
+    }
+
+    fn try_get_term(&self, i: u64) -> Result<&SimpleTerm<'static>, DiskError> {
+        // the cache is always in a consistent state, so poisoning can be ignored
+        let mut decoded = self.decoded.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(t) = decoded.get(&i) {
+            let t: &SimpleTerm<'static> = t;
+            // the following is safe,
+            // because t is boxed, and will not be moved nor dropped as long as self is borrowed
+            // (decoded is only cleared by end_transaction, which borrows self mutably).
+            return Ok(unsafe { std::mem::transmute::<&SimpleTerm, &SimpleTerm>(t) });
+        }
+        let t = match i.checked_sub(self.next) {
+            Some(j) => self
+                .unsaved
+                .get(j as usize)
+                .and_then(|bytes| decode_term(bytes)),
+            None => {
+                let bytes = self
+                    .terms
+                    .get(i)?
+                    .ok_or_else(|| DiskError::Corrupted(format!("missing term #{i}")))?;
+                decode_term(bytes.value())
+            }
+        }
+        .ok_or_else(|| DiskError::Corrupted(format!("invalid term #{i}")))?;
+        let t: &SimpleTerm<'static> = decoded.entry(i).or_insert(Box::new(t));
+        // safe for the same reason as above
+        Ok(unsafe { std::mem::transmute::<&SimpleTerm, &SimpleTerm>(t) })
+    }
+
+    fn save(&mut self, txn: &WriteTransaction) -> Result<(), DiskError> {
+        let mut terms = txn.open_table(TERMS)?;
+        let mut ids = txn.open_table(TERM_IDS)?;
+        for (i, bytes) in (self.next..).zip(&self.unsaved) {
+            terms.insert(i, &bytes[..])?;
+            ids.insert(&bytes[..], i)?;
+        }
+        Ok(())
+    }
+
+    fn end_transaction(&mut self, committed: bool) -> Result<(), DiskError> {
+        self.decoded
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+        self.unsaved_ids.clear();
+        let unsaved = std::mem::take(&mut self.unsaved);
+        if committed && !unsaved.is_empty() {
+            self.next += unsaved.len() as u64;
+            self.refresh()?;
+        }
+        Ok(())
+    }
+}
+
+impl TermIndex for RedbTermIndex {
+    type Term = SimpleTerm<'static>;
+    type Index = u64;
+    type Error = DiskError;
+
+    fn get_index<T: Term>(&self, t: T) -> Option<u64> {
+        self.try_get_index(t).unwrap()
+    }
+
+    fn ensure_index<T: Term>(&mut self, t: T) -> Result<u64, DiskError> {
+        let mut buf = vec![];
+        encode_term(t, &mut buf);
+        if let Some(i) = self.find(&buf)? {
+            return Ok(i);
+        }
+        let i = self.next + self.unsaved.len() as u64;
+        let buf: Box<[u8]> = buf.into();
+        self.unsaved.push(buf.clone());
+        self.unsaved_ids.insert(buf, i);
+        Ok(i)
+    }
+
+    fn get_term(&self, i: u64) -> &SimpleTerm<'static> {
+        self.try_get_term(i).unwrap()
+    }
+}
+
+impl GraphNameIndex for RedbTermIndex {
+    fn get_default_graph_index(&self) -> u64 {
+        DEFAULT_GRAPH
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use redb::backends::FileBackend;
+
+    fn temporary_db() -> Result<Arc<Database>, Box<dyn std::error::Error>> {
+        let file = tempfile::tempfile()?;
+        Ok(Arc::new(
+            Database::builder().create_with_backend(FileBackend::new(file)?)?,
+        ))
+    }
+
+    #[test]
+    fn redb_term_index() -> Result<(), Box<dyn std::error::Error>> {
+        let db = temporary_db()?;
+        let mut ti = RedbTermIndex::open(db.clone())?;
+        assert_eq!(ti.get_default_graph_index(), DEFAULT_GRAPH);
+        assert_eq!(ti.get_index("a"), None);
+        assert_eq!(ti.ensure_index("a")?, 1);
+        assert_eq!(ti.ensure_index("b")?, 2);
+        assert_eq!(ti.ensure_index("a")?, 1);
+        assert!(Term::eq(ti.get_term(2), "b"));
+
+        // terms are discarded if the transaction is aborted...
+        ti.end_transaction(false)?;
+        assert_eq!(ti.get_index("a"), None);
+        assert_eq!(ti.ensure_index("b")?, 1);
+
+        // ... and saved if it is committed
+        let txn = db.begin_write()?;
+        ti.save(&txn)?;
+        txn.commit()?;
+        ti.end_transaction(true)?;
+        assert_eq!(ti.get_index("b"), Some(1));
+        assert_eq!(ti.ensure_index("c")?, 2);
+        assert!(Term::eq(ti.get_term(1), "b"));
+        assert!(Term::eq(ti.get_term(2), "c"));
+        ti.end_transaction(false)?;
+
+        let ti = RedbTermIndex::open(db)?;
+        assert_eq!(ti.get_index("b"), Some(1));
+        assert_eq!(ti.get_index("c"), None);
+        assert!(Term::eq(ti.get_term(1), "b"));
+        assert!(ti.try_get_term(2).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides a persistent implementation of datasets,
+//! stored on disk in an embedded [`redb`] database,
+//! so that neither their quads nor their terms need to fit in memory.
+//!
+//! # Example
+//! ```
+//! # use sophia_api::dataset::{Dataset, MutableDataset};
+//! # use sophia_api::ns::rdf;
+//! # use sophia_api::term::SimpleTerm;
+//! # use sophia_disk::DiskDataset;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let dir = tempfile::tempdir()?;
+//! let path = dir.path().join("dataset.redb");
+//! let mut d = DiskDataset::open(&path)?;
+//! d.insert(rdf::type_, rdf::type_, rdf::Property, None as Option<SimpleTerm>)?;
+//! drop(d);
+//!
+//! let d = DiskDataset::open(&path)?;
+//! assert_eq!(d.quads().count(), 1);
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+#![deny(missing_docs)]
+
+mod dataset;
+pub use dataset::*;
+mod encoding;
+mod error;
+pub use error::*;
+pub mod index;
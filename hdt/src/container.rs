@@ -0,0 +1,509 @@
+//! Low-level building blocks of the HDT binary format:
+//! variable-length integers, control information, bitmaps and log-arrays.
+//!
+//! All the structures defined here only store *offsets* into the HDT data,
+//! and must be passed the data they were read from in order to be queried.
+use crate::error::{format_error, HdtError};
+use crc::{Crc, CRC_16_ARC, CRC_32_ISCSI, CRC_8_SMBUS};
+
+type Result<T> = std::result::Result<T, HdtError>;
+
+const CRC8: Crc<u8> = Crc::<u8>::new(&CRC_8_SMBUS);
+const CRC16: Crc<u16> = Crc::<u16>::new(&CRC_16_ARC);
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+
+const COOKIE: &[u8] = b"$HDT";
+
+/// Type of the control information preceding the whole file
+pub(crate) const GLOBAL: u8 = 1;
+/// Type of the control information preceding the header
+pub(crate) const HEADER: u8 = 2;
+/// Type of the control information preceding the dictionary
+pub(crate) const DICTIONARY: u8 = 3;
+/// Type of the control information preceding the triples
+pub(crate) const TRIPLES: u8 = 4;
+
+const TYPE_BITMAP_PLAIN: u8 = 1;
+const TYPE_SEQLOG: u8 = 1;
+
+/// A cursor over HDT data.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn byte(&mut self) -> Result<u8> {
+        let Some(b) = self.data.get(self.pos) else {
+            return format_error("unexpected end of data");
+        };
+        self.pos += 1;
+        Ok(*b)
+    }
+
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let Some(bytes) = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.pos..end))
+        else {
+            return format_error("unexpected end of data");
+        };
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    pub fn vbyte(&mut self) -> Result<usize> {
+        read_vbyte(self.data, &mut self.pos)
+    }
+
+    /// Read a null-terminated string, and return it without its terminator.
+    pub fn cstr(&mut self) -> Result<&'a [u8]> {
+        let rest = &self.data[self.pos..];
+        let Some(len) = rest.iter().position(|b| *b == 0) else {
+            return format_error("unterminated string");
+        };
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+
+    /// Check that the next byte is the CRC8 of the data since `start`.
+    pub fn check_crc8(&mut self, start: usize) -> Result<()> {
+        let expected = CRC8.checksum(&self.data[start..self.pos]);
+        if self.byte()? != expected {
+            return format_error("CRC8 mismatch");
+        }
+        Ok(())
+    }
+
+    /// Check that the next two bytes are the CRC16 of the data since `start`.
+    fn check_crc16(&mut self, start: usize) -> Result<()> {
+        let expected = CRC16.checksum(&self.data[start..self.pos]);
+        if self.take(2)? != expected.to_le_bytes() {
+            return format_error("CRC16 mismatch");
+        }
+        Ok(())
+    }
+
+    /// Check that the next four bytes are the CRC32C of the data since `start`.
+    pub fn check_crc32(&mut self, start: usize) -> Result<()> {
+        let expected = CRC32.checksum(&self.data[start..self.pos]);
+        if self.take(4)? != expected.to_le_bytes() {
+            return format_error("CRC32 mismatch");
+        }
+        Ok(())
+    }
+}
+
+/// Read a variable-length integer from `data` at `pos`, and advance `pos` accordingly.
+///
+/// HDT integers are encoded by groups of 7 bits, least significant group first;
+/// the most significant bit of a byte is set if and only if it is the last one.
+pub(crate) fn read_vbyte(data: &[u8], pos: &mut usize) -> Result<usize> {
+    let mut value = 0_usize;
+    let mut shift = 0;
+    loop {
+        let Some(b) = data.get(*pos) else {
+            return format_error("unexpected end of data");
+        };
+        *pos += 1;
+        if shift >= usize::BITS {
+            return format_error("integer overflow");
+        }
+        value |= ((b & 0x7f) as usize) << shift;
+        if b & 0x80 != 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Append the CRC8 of `out[start..]` to `out`.
+pub(crate) fn write_crc8(out: &mut Vec<u8>, start: usize) {
+    out.push(CRC8.checksum(&out[start..]));
+}
+
+/// Append the CRC32C of `out[start..]` to `out`.
+pub(crate) fn write_crc32(out: &mut Vec<u8>, start: usize) {
+    let crc = CRC32.checksum(&out[start..]);
+    out.extend_from_slice(&crc.to_le_bytes());
+}
+
+/// Append the variable-length encoding of `value` to `out` (see [`read_vbyte`]).
+pub(crate) fn write_vbyte(out: &mut Vec<u8>, mut value: usize) {
+    while value > 0x7f {
+        out.push((value & 0x7f) as u8);
+        value >>= 7;
+    }
+    out.push(value as u8 | 0x80);
+}
+
+//
+
+/// The control information preceding each part of an HDT file.
+#[derive(Clone, Debug)]
+pub(crate) struct ControlInfo {
+    pub kind: u8,
+    pub format: String,
+    properties: Vec<(String, String)>,
+}
+
+impl ControlInfo {
+    pub fn new(kind: u8, format: &str) -> Self {
+        ControlInfo {
+            kind,
+            format: format.into(),
+            properties: vec![],
+        }
+    }
+
+    pub fn with(mut self, key: &str, value: impl ToString) -> Self {
+        self.properties.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Read control information of the given `kind`.
+    pub fn read(r: &mut Reader, kind: u8) -> Result<Self> {
+        let start = r.pos();
+        if r.take(COOKIE.len())? != COOKIE {
+            return format_error("missing $HDT cookie");
+        }
+        let actual_kind = r.byte()?;
+        if actual_kind != kind {
+            return format_error(format!(
+                "expected control information of type {kind}, got {actual_kind}"
+            ));
+        }
+        let format = utf8(r.cstr()?)?.to_string();
+        let properties = utf8(r.cstr()?)?
+            .split(';')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (k, v) = p.split_once('=').unwrap_or((p, ""));
+                (k.to_string(), v.to_string())
+            })
+            .collect();
+        r.check_crc16(start)?;
+        Ok(ControlInfo {
+            kind,
+            format,
+            properties,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_usize(&self, key: &str) -> Result<Option<usize>> {
+        self.get(key)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| HdtError::Format(format!("invalid value for {key}: {v}")))
+            })
+            .transpose()
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(COOKIE);
+        out.push(self.kind);
+        out.extend_from_slice(self.format.as_bytes());
+        out.push(0);
+        for (k, v) in &self.properties {
+            out.extend_from_slice(k.as_bytes());
+            out.push(b'=');
+            out.extend_from_slice(v.as_bytes());
+            out.push(b';');
+        }
+        out.push(0);
+        let crc = CRC16.checksum(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+}
+
+pub(crate) fn utf8(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes).or_else(|_| format_error("invalid UTF-8"))
+}
+
+//
+
+/// The number of bytes covered by each entry of [`Bitmap::ranks`].
+const RANK_BLOCK: usize = 64;
+
+/// A sequence of bits, supporting `select1` queries.
+#[derive(Clone, Debug)]
+pub(crate) struct Bitmap {
+    start: usize,
+    len: usize,
+    ones: usize,
+    /// The number of ones preceding each block of [`RANK_BLOCK`] bytes
+    ranks: Vec<usize>,
+}
+
+impl Bitmap {
+    pub fn read(r: &mut Reader) -> Result<Self> {
+        let header = r.pos();
+        let kind = r.byte()?;
+        if kind != TYPE_BITMAP_PLAIN {
+            return Err(HdtError::Unsupported(format!("bitmap type {kind}")));
+        }
+        let len = r.vbyte()?;
+        r.check_crc8(header)?;
+        let start = r.pos();
+        let bytes = r.take(len.div_ceil(8))?;
+        r.check_crc32(start)?;
+        let mut ones = 0;
+        let ranks = bytes
+            .chunks(RANK_BLOCK)
+            .map(|chunk| {
+                let rank = ones;
+                ones += chunk.iter().map(|b| b.count_ones() as usize).sum::<usize>();
+                rank
+            })
+            .collect();
+        Ok(Bitmap {
+            start,
+            len,
+            ones,
+            ranks,
+        })
+    }
+
+    /// The number of bits in this bitmap
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bits set to one in this bitmap
+    pub fn ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Get the `i`-th bit of this bitmap.
+    pub fn get(&self, data: &[u8], i: usize) -> bool {
+        debug_assert!(i < self.len);
+        data[self.start + i / 8] & (1 << (i % 8)) != 0
+    }
+
+    /// The position of the `k`-th bit set to one (starting from 0).
+    pub fn select1(&self, data: &[u8], k: usize) -> Option<usize> {
+        if k >= self.ones {
+            return None;
+        }
+        let block = self.ranks.partition_point(|r| *r <= k) - 1;
+        let mut remaining = k - self.ranks[block];
+        let first = block * RANK_BLOCK;
+        let end = self.len.div_ceil(8);
+        for (i, byte) in data[self.start + first..self.start + end]
+            .iter()
+            .enumerate()
+        {
+            let count = byte.count_ones() as usize;
+            if remaining < count {
+                let mut byte = *byte;
+                for _ in 0..remaining {
+                    byte &= byte - 1; // clear lowest bit
+                }
+                return Some((first + i) * 8 + byte.trailing_zeros() as usize);
+            }
+            remaining -= count;
+        }
+        None
+    }
+}
+
+/// Append a bitmap containing `bits` to `out`.
+pub(crate) fn write_bitmap(out: &mut Vec<u8>, bits: &[bool]) {
+    let header = out.len();
+    out.push(TYPE_BITMAP_PLAIN);
+    write_vbyte(out, bits.len());
+    write_crc8(out, header);
+    let start = out.len();
+    out.extend(bits.chunks(8).map(|chunk| {
+        chunk
+            .iter()
+            .enumerate()
+            .fold(0_u8, |acc, (i, bit)| acc | ((*bit as u8) << i))
+    }));
+    write_crc32(out, start);
+}
+
+//
+
+/// A sequence of integers, all stored on the same number of bits.
+#[derive(Clone, Debug)]
+pub(crate) struct LogArray {
+    start: usize,
+    bits: usize,
+    len: usize,
+}
+
+impl LogArray {
+    pub fn read(r: &mut Reader) -> Result<Self> {
+        let header = r.pos();
+        let kind = r.byte()?;
+        if kind != TYPE_SEQLOG {
+            return Err(HdtError::Unsupported(format!("sequence type {kind}")));
+        }
+        let bits = r.byte()? as usize;
+        if bits > 64 {
+            return format_error(format!("invalid number of bits: {bits}"));
+        }
+        let len = r.vbyte()?;
+        r.check_crc8(header)?;
+        let start = r.pos();
+        let Some(nbits) = bits.checked_mul(len) else {
+            return format_error("log-array too big");
+        };
+        r.take(nbits.div_ceil(8))?;
+        r.check_crc32(start)?;
+        Ok(LogArray { start, bits, len })
+    }
+
+    /// The number of integers in this array
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the `i`-th integer of this array.
+    pub fn get(&self, data: &[u8], i: usize) -> usize {
+        debug_assert!(i < self.len);
+        if self.bits == 0 {
+            return 0;
+        }
+        let bitpos = i * self.bits;
+        let first = self.start + bitpos / 8;
+        let last = self.start + (bitpos + self.bits).div_ceil(8);
+        let mut buf = [0; 16];
+        buf[..last - first].copy_from_slice(&data[first..last]);
+        let word = u128::from_le_bytes(buf) >> (bitpos % 8);
+        (word & ((1 << self.bits) - 1)) as usize
+    }
+
+    /// Find the position of `value` in the sorted range `from..to` of this array.
+    pub fn binary_search(
+        &self,
+        data: &[u8],
+        from: usize,
+        to: usize,
+        value: usize,
+    ) -> Option<usize> {
+        let (mut lo, mut hi) = (from, to);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.get(data, mid).cmp(&value) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+/// Append a log-array containing `values` to `out`.
+pub(crate) fn write_log_array(out: &mut Vec<u8>, values: &[usize]) {
+    let max = values.iter().copied().max().unwrap_or(0);
+    let bits = (usize::BITS - max.leading_zeros()) as usize;
+    let header = out.len();
+    out.push(TYPE_SEQLOG);
+    out.push(bits as u8);
+    write_vbyte(out, values.len());
+    write_crc8(out, header);
+    let start = out.len();
+    let mut acc = 0_u128;
+    let mut acc_bits = 0;
+    for v in values {
+        acc |= (*v as u128) << acc_bits;
+        acc_bits += bits;
+        while acc_bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            acc_bits -= 8;
+        }
+    }
+    if acc_bits > 0 {
+        out.push(acc as u8);
+    }
+    write_crc32(out, start);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vbyte() -> Result<()> {
+        for value in [0, 1, 127, 128, 300, 1 << 20, usize::MAX] {
+            let mut buf = vec![];
+            write_vbyte(&mut buf, value);
+            let mut pos = 0;
+            assert_eq!(read_vbyte(&buf, &mut pos)?, value);
+            assert_eq!(pos, buf.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn control_info() -> Result<()> {
+        let mut buf = vec![];
+        ControlInfo::new(HEADER, "ntriples")
+            .with("length", 42)
+            .write(&mut buf);
+        let ci = ControlInfo::read(&mut Reader::new(&buf), HEADER)?;
+        assert_eq!(ci.format, "ntriples");
+        assert_eq!(ci.get_usize("length")?, Some(42));
+        assert_eq!(ci.get("missing"), None);
+
+        assert!(ControlInfo::read(&mut Reader::new(&buf), TRIPLES).is_err());
+        *buf.last_mut().unwrap() ^= 1;
+        assert!(ControlInfo::read(&mut Reader::new(&buf), HEADER).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bitmap() -> Result<()> {
+        let bits: Vec<bool> = (0..2000).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut buf = vec![];
+        write_bitmap(&mut buf, &bits);
+        let bitmap = Bitmap::read(&mut Reader::new(&buf))?;
+        assert_eq!(bitmap.len(), bits.len());
+        let positions: Vec<usize> = (0..bits.len()).filter(|i| bits[*i]).collect();
+        assert_eq!(bitmap.ones(), positions.len());
+        for (i, bit) in bits.iter().enumerate() {
+            assert_eq!(bitmap.get(&buf, i), *bit);
+        }
+        for (k, pos) in positions.iter().enumerate() {
+            assert_eq!(bitmap.select1(&buf, k), Some(*pos));
+        }
+        assert_eq!(bitmap.select1(&buf, positions.len()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn log_array() -> Result<()> {
+        for max in [0, 1, 5, 1000, u32::MAX as usize, usize::MAX] {
+            let values: Vec<usize> = (0..100).map(|i| max / 100 * i).chain([max]).collect();
+            let mut buf = vec![];
+            write_log_array(&mut buf, &values);
+            let array = LogArray::read(&mut Reader::new(&buf))?;
+            assert_eq!(array.len(), values.len());
+            for (i, v) in values.iter().enumerate() {
+                assert_eq!(array.get(&buf, i), *v);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+//! The four-section dictionary of HDT,
+//! a bidirectional association of terms with integer identifiers.
+//!
+//! Terms are split in four sections
+//! (shared subjects-objects, subjects, predicates, objects),
+//! each of them being a sorted list of strings, compressed with plain front-coding (PFC):
+//! strings are grouped in blocks, the first string of each block is stored in full,
+//! and each following string only stores its suffix after the prefix it shares with the previous one.
+//!
+//! Identifiers start at 1.
+//! Subjects and objects share the identifiers of the first section,
+//! then subject-only (resp. object-only) identifiers follow.
+use crate::container::*;
+use crate::error::{format_error, HdtError};
+use sophia_api::ns::xsd;
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, SimpleTerm, Term, TermKind};
+use sophia_inmem::index::TermIndex;
+use std::collections::{BTreeMap, BTreeSet};
+
+type Result<T> = std::result::Result<T, HdtError>;
+
+pub(crate) const FOUR_SECTION: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+const TYPE_PFC: u8 = 2;
+const BLOCK_SIZE: usize = 16;
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// The position of a term in a triple
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Role {
+    Subject,
+    Predicate,
+    Object,
+}
+
+/// A section of the dictionary.
+#[derive(Clone, Debug)]
+struct Pfc {
+    len: usize,
+    block_size: usize,
+    blocks: LogArray,
+    text_start: usize,
+    text_len: usize,
+}
+
+impl Pfc {
+    fn read(r: &mut Reader) -> Result<Self> {
+        let header = r.pos();
+        let kind = r.byte()?;
+        if kind != TYPE_PFC {
+            return Err(HdtError::Unsupported(format!(
+                "dictionary section type {kind}"
+            )));
+        }
+        let len = r.vbyte()?;
+        let text_len = r.vbyte()?;
+        let block_size = r.vbyte()?;
+        r.check_crc8(header)?;
+        let blocks = LogArray::read(r)?;
+        if block_size == 0 || blocks.len() != len.div_ceil(block_size) + 1 {
+            return format_error("inconsistent dictionary section");
+        }
+        let text_start = r.pos();
+        r.take(text_len)?;
+        r.check_crc32(text_start)?;
+        Ok(Pfc {
+            len,
+            block_size,
+            blocks,
+            text_start,
+            text_len,
+        })
+    }
+
+    fn text<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.text_start..self.text_start + self.text_len]
+    }
+
+    /// Get the first string of the given block,
+    /// and the position of the next string in the text.
+    fn block_head<'a>(&self, data: &'a [u8], block: usize) -> Result<(&'a [u8], usize)> {
+        cstr(self.text(data), self.blocks.get(data, block))
+    }
+
+    /// Get the string with the given identifier (starting at 1).
+    fn extract(&self, data: &[u8], id: usize) -> Result<Vec<u8>> {
+        if id == 0 || id > self.len {
+            return format_error(format!("identifier {id} out of range"));
+        }
+        let text = self.text(data);
+        let (block, rank) = ((id - 1) / self.block_size, (id - 1) % self.block_size);
+        let (head, mut pos) = self.block_head(data, block)?;
+        let mut current = head.to_vec();
+        for _ in 0..rank {
+            pos = next_string(text, pos, &mut current)?;
+        }
+        Ok(current)
+    }
+
+    /// Get the identifier (starting at 1) of the given string, if present.
+    fn locate(&self, data: &[u8], s: &[u8]) -> Option<usize> {
+        let text = self.text(data);
+        let nb_blocks = self.len.div_ceil(self.block_size);
+        // find the last block starting with a string lower or equal to s
+        let (mut lo, mut hi) = (0, nb_blocks);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.block_head(data, mid).ok()?.0 <= s {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let block = lo.checked_sub(1)?;
+        let (head, mut pos) = self.block_head(data, block).ok()?;
+        let mut current = head.to_vec();
+        let first = block * self.block_size + 1;
+        let last = (first + self.block_size - 1).min(self.len);
+        for id in first..=last {
+            if id > first {
+                pos = next_string(text, pos, &mut current).ok()?;
+            }
+            match current.as_slice().cmp(s) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => return Some(id),
+                std::cmp::Ordering::Greater => return None,
+            }
+        }
+        None
+    }
+}
+
+/// Read a null-terminated string in `text` at `pos`,
+/// and return it with the position following it.
+fn cstr(text: &[u8], pos: usize) -> Result<(&[u8], usize)> {
+    let Some(rest) = text.get(pos..) else {
+        return format_error("dictionary offset out of range");
+    };
+    let Some(len) = rest.iter().position(|b| *b == 0) else {
+        return format_error("unterminated string in dictionary");
+    };
+    Ok((&rest[..len], pos + len + 1))
+}
+
+/// Replace `current` with the front-coded string at `pos` in `text`,
+/// and return the position following it.
+fn next_string(text: &[u8], mut pos: usize, current: &mut Vec<u8>) -> Result<usize> {
+    let prefix = read_vbyte(text, &mut pos)?;
+    if prefix > current.len() {
+        return format_error("invalid prefix length in dictionary");
+    }
+    let (suffix, pos) = cstr(text, pos)?;
+    current.truncate(prefix);
+    current.extend_from_slice(suffix);
+    Ok(pos)
+}
+
+/// Append a PFC section containing `strings` (which must be sorted) to `out`.
+fn write_pfc<S: AsRef<[u8]>>(out: &mut Vec<u8>, strings: &[S]) {
+    let mut text = vec![];
+    let mut blocks = vec![];
+    let mut previous: &[u8] = &[];
+    for (i, s) in strings.iter().enumerate() {
+        let s = s.as_ref();
+        if i % BLOCK_SIZE == 0 {
+            blocks.push(text.len());
+            text.extend_from_slice(s);
+        } else {
+            let prefix = previous.iter().zip(s).take_while(|(a, b)| a == b).count();
+            write_vbyte(&mut text, prefix);
+            text.extend_from_slice(&s[prefix..]);
+        }
+        text.push(0);
+        previous = s;
+    }
+    blocks.push(text.len());
+
+    let header = out.len();
+    out.push(TYPE_PFC);
+    write_vbyte(out, strings.len());
+    write_vbyte(out, text.len());
+    write_vbyte(out, BLOCK_SIZE);
+    write_crc8(out, header);
+    write_log_array(out, &blocks);
+    let start = out.len();
+    out.extend_from_slice(&text);
+    write_crc32(out, start);
+}
+
+//
+
+/// A four-section dictionary.
+#[derive(Clone, Debug)]
+pub(crate) struct Dictionary {
+    shared: Pfc,
+    subjects: Pfc,
+    predicates: Pfc,
+    objects: Pfc,
+}
+
+impl Dictionary {
+    pub fn read(r: &mut Reader) -> Result<Self> {
+        let ci = ControlInfo::read(r, DICTIONARY)?;
+        if ci.format != FOUR_SECTION {
+            return Err(HdtError::Unsupported(format!(
+                "dictionary format {}",
+                ci.format
+            )));
+        }
+        if let Some(mapping) = ci.get_usize("mapping")? {
+            if mapping != 1 {
+                return Err(HdtError::Unsupported(format!(
+                    "dictionary mapping {mapping}"
+                )));
+            }
+        }
+        Ok(Dictionary {
+            shared: Pfc::read(r)?,
+            subjects: Pfc::read(r)?,
+            predicates: Pfc::read(r)?,
+            objects: Pfc::read(r)?,
+        })
+    }
+
+    /// The number of distinct subjects
+    pub fn nb_subjects(&self) -> usize {
+        self.shared.len + self.subjects.len
+    }
+
+    /// Get the identifier of `t` in the given role, if present.
+    pub fn id<T: Term>(&self, data: &[u8], t: T, role: Role) -> Option<usize> {
+        let s = term_to_string(t)?;
+        let s = s.as_bytes();
+        let own = match role {
+            Role::Predicate => return self.predicates.locate(data, s),
+            Role::Subject => &self.subjects,
+            Role::Object => &self.objects,
+        };
+        self.shared
+            .locate(data, s)
+            .or_else(|| Some(self.shared.len + own.locate(data, s)?))
+    }
+
+    /// Get the term with identifier `id` in the given role.
+    pub fn term(&self, data: &[u8], id: usize, role: Role) -> Result<SimpleTerm<'static>> {
+        let bytes = match role {
+            Role::Predicate => self.predicates.extract(data, id)?,
+            _ if id <= self.shared.len => self.shared.extract(data, id)?,
+            Role::Subject => self.subjects.extract(data, id - self.shared.len)?,
+            Role::Object => self.objects.extract(data, id - self.shared.len)?,
+        };
+        string_to_term(&bytes)
+    }
+}
+
+/// The identifiers assigned by [`write_dictionary`] to the terms of a [`TermIndex`].
+pub(crate) struct DictionaryIds<I> {
+    /// Identifiers of subjects and objects
+    pub so: BTreeMap<I, usize>,
+    /// Identifiers of predicates
+    pub p: BTreeMap<I, usize>,
+    /// Number of distinct subjects
+    pub nb_subjects: usize,
+    /// Number of distinct objects
+    pub nb_objects: usize,
+}
+
+/// Append to `out` the dictionary of all the terms from `terms` used in `triples`,
+/// and return the identifiers assigned to them.
+pub(crate) fn write_dictionary<TI: TermIndex>(
+    out: &mut Vec<u8>,
+    terms: &TI,
+    triples: &[[TI::Index; 3]],
+) -> Result<DictionaryIds<TI::Index>> {
+    const SUBJECT: u8 = 1;
+    const OBJECT: u8 = 2;
+    let mut roles = BTreeMap::new();
+    let mut predicates = BTreeSet::new();
+    for [s, p, o] in triples {
+        *roles.entry(*s).or_insert(0) |= SUBJECT;
+        *roles.entry(*o).or_insert(0) |= OBJECT;
+        predicates.insert(*p);
+    }
+    let to_string = |i: TI::Index| {
+        let t = terms.get_term(i);
+        term_to_string(t.borrow_term()).ok_or_else(|| {
+            HdtError::Unsupported(format!("term {:?} can not be stored in HDT", t.as_simple()))
+        })
+    };
+
+    // shared, subjects, objects
+    let mut sections: [Vec<(String, TI::Index)>; 3] = Default::default();
+    for (i, role) in roles {
+        let section = match role {
+            SUBJECT => 1,
+            OBJECT => 2,
+            _ => 0,
+        };
+        sections[section].push((to_string(i)?, i));
+    }
+    let mut predicates = predicates
+        .into_iter()
+        .map(|i| Ok((to_string(i)?, i)))
+        .collect::<Result<Vec<_>>>()?;
+    for section in sections.iter_mut().chain([&mut predicates]) {
+        section.sort_unstable();
+    }
+    let [shared, subjects, objects] = sections;
+
+    let size_strings: usize = [&shared, &subjects, &predicates, &objects]
+        .into_iter()
+        .flatten()
+        .map(|(s, _)| s.len())
+        .sum();
+    ControlInfo::new(DICTIONARY, FOUR_SECTION)
+        .with("mapping", 1)
+        .with("sizeStrings", size_strings)
+        .write(out);
+    let strings = |section: &[(String, TI::Index)]| {
+        section.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>()
+    };
+    write_pfc(out, &strings(&shared));
+    write_pfc(out, &strings(&subjects));
+    write_pfc(out, &strings(&predicates));
+    write_pfc(out, &strings(&objects));
+
+    fn numbered<I: Copy>(
+        section: &[(String, I)],
+        offset: usize,
+    ) -> impl Iterator<Item = (I, usize)> + '_ {
+        section
+            .iter()
+            .enumerate()
+            .map(move |(n, (_, i))| (*i, offset + n + 1))
+    }
+    let nb_shared = shared.len();
+    Ok(DictionaryIds {
+        so: numbered(&shared, 0)
+            .chain(numbered(&subjects, nb_shared))
+            .chain(numbered(&objects, nb_shared))
+            .collect(),
+        p: numbered(&predicates, 0).collect(),
+        nb_subjects: nb_shared + subjects.len(),
+        nb_objects: nb_shared + objects.len(),
+    })
+}
+
+//
+
+/// Convert a term into its representation in an HDT dictionary.
+///
+/// Return `None` for quoted triples and variables, which can not be represented in HDT,
+/// and for terms containing a null character.
+fn term_to_string<T: Term>(t: T) -> Option<String> {
+    let s = match t.kind() {
+        TermKind::Iri => t.iri().unwrap().as_str().to_string(),
+        TermKind::BlankNode => format!("_:{}", t.bnode_id().unwrap().as_str()),
+        TermKind::Literal => {
+            let lex = t.lexical_form().unwrap();
+            if let Some(tag) = t.language_tag() {
+                format!("\"{lex}\"@{}", tag.as_str())
+            } else {
+                let dt = t.datatype().unwrap();
+                if Term::eq(&dt, xsd::string) {
+                    format!("\"{lex}\"")
+                } else {
+                    format!("\"{lex}\"^^<{}>", dt.as_str())
+                }
+            }
+        }
+        TermKind::Triple | TermKind::Variable => return None,
+    };
+    (!s.contains('\0')).then_some(s)
+}
+
+/// Convert the representation of a term in an HDT dictionary into a term.
+fn string_to_term(bytes: &[u8]) -> Result<SimpleTerm<'static>> {
+    let s = utf8(bytes)?;
+    let invalid = || format_error(format!("invalid term {s:?}"));
+    if let Some(rest) = s.strip_prefix('"') {
+        let Some(quote) = rest.rfind('"') else {
+            return invalid();
+        };
+        let (lex, suffix) = (rest[..quote].to_string().into(), &rest[quote + 1..]);
+        if suffix.is_empty() {
+            Ok(SimpleTerm::LiteralDatatype(
+                lex,
+                IriRef::new_unchecked(XSD_STRING.into()),
+            ))
+        } else if let Some(tag) = suffix.strip_prefix('@') {
+            let Ok(tag) = LanguageTag::new(tag.to_string().into()) else {
+                return invalid();
+            };
+            Ok(SimpleTerm::LiteralLanguage(lex, tag))
+        } else if let Some(dt) = suffix
+            .strip_prefix("^^<")
+            .and_then(|dt| dt.strip_suffix('>'))
+        {
+            let Ok(dt) = IriRef::new(dt.to_string().into()) else {
+                return invalid();
+            };
+            Ok(SimpleTerm::LiteralDatatype(lex, dt))
+        } else {
+            invalid()
+        }
+    } else if let Some(id) = s.strip_prefix("_:") {
+        let Ok(id) = BnodeId::new(id.to_string().into()) else {
+            return invalid();
+        };
+        Ok(SimpleTerm::BlankNode(id))
+    } else {
+        let Ok(iri) = IriRef::new(s.to_string().into()) else {
+            return invalid();
+        };
+        Ok(SimpleTerm::Iri(iri))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::rdf;
+
+    #[test]
+    fn term_strings() -> Result<()> {
+        let terms: Vec<SimpleTerm<'static>> = vec![
+            rdf::type_.into_term(),
+            BnodeId::new_unchecked("b1").into_term(),
+            "hello \"world\"".into_term(),
+            42.into_term(),
+            SimpleTerm::LiteralLanguage("chat".into(), LanguageTag::new_unchecked("fr".into())),
+        ];
+        let strings = [
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "_:b1",
+            "\"hello \"world\"\"",
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+            "\"chat\"@fr",
+        ];
+        for (t, s) in terms.iter().zip(strings) {
+            assert_eq!(term_to_string(t).as_deref(), Some(s));
+            assert_eq!(&string_to_term(s.as_bytes())?, t);
+        }
+        assert_eq!(term_to_string("a\0b"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn pfc() -> Result<()> {
+        let mut strings: Vec<String> = (0..100)
+            .map(|i| format!("http://example.org/{i}"))
+            .collect();
+        strings.sort();
+        let mut buf = vec![];
+        write_pfc(&mut buf, &strings);
+        let pfc = Pfc::read(&mut Reader::new(&buf))?;
+        for (i, s) in strings.iter().enumerate() {
+            assert_eq!(&pfc.extract(&buf, i + 1)?, s.as_bytes());
+            assert_eq!(pfc.locate(&buf, s.as_bytes()), Some(i + 1));
+        }
+        for missing in ["", "a", "http://example.org/", "http://example.org/5a", "z"] {
+            assert_eq!(pfc.locate(&buf, missing.as_bytes()), None);
+        }
+        assert!(pfc.extract(&buf, 0).is_err());
+        assert!(pfc.extract(&buf, 101).is_err());
+        Ok(())
+    }
+}
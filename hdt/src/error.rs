@@ -0,0 +1,20 @@
+//! Errors raised when reading or writing HDT.
+
+/// An error raised when reading or writing HDT.
+#[derive(Debug, thiserror::Error)]
+pub enum HdtError {
+    /// An I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The data is not valid HDT
+    #[error("invalid HDT: {0}")]
+    Format(String),
+    /// The data uses a feature of HDT that is not supported by this crate,
+    /// or contains terms that can not be represented in HDT
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+}
+
+pub(crate) fn format_error<T>(msg: impl Into<String>) -> Result<T, HdtError> {
+    Err(HdtError::Format(msg.into()))
+}
@@ -0,0 +1,202 @@
+//! A read-only [`Graph`] backed by HDT data.
+use crate::container::*;
+use crate::dictionary::{Dictionary, Role};
+use crate::error::{format_error, HdtError};
+use crate::triples::{BitmapTriples, IdIter};
+use memmap2::Mmap;
+use sophia_api::graph::{GResult, Graph, SetGraph};
+use sophia_api::term::matcher::TermMatcher;
+use sophia_api::term::{SimpleTerm, Term};
+use std::fs::File;
+use std::iter::empty;
+use std::ops::Range;
+use std::path::Path;
+
+pub(crate) const HDT_V1: &str = "<http://purl.org/HDT/hdt#HDTv1>";
+
+/// A read-only [`Graph`] backed by HDT data,
+/// typically a memory-mapped file (see [`HdtGraph::open`]).
+///
+/// Only the HDT header and a few offsets are read when the graph is created;
+/// terms and triples are decoded on demand,
+/// and [`triples_matching`](Graph::triples_matching) is served by the bitmap triples index
+/// when the subject is constant.
+pub struct HdtGraph<D> {
+    data: D,
+    header: Range<usize>,
+    dictionary: Dictionary,
+    triples: BitmapTriples,
+}
+
+impl HdtGraph<Mmap> {
+    /// Memory-map the HDT file at `path`.
+    ///
+    /// The file must not be modified as long as the returned graph is in use.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, HdtError> {
+        let file = File::open(path)?;
+        // SAFETY: the file is only read, and the caller is responsible for not modifying it
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::new(mmap)
+    }
+}
+
+impl<D: AsRef<[u8]>> HdtGraph<D> {
+    /// Build a graph from the given HDT data.
+    pub fn new(data: D) -> Result<Self, HdtError> {
+        let bytes = data.as_ref();
+        let mut r = Reader::new(bytes);
+        let global = ControlInfo::read(&mut r, GLOBAL)?;
+        if global.format != HDT_V1 {
+            return Err(HdtError::Unsupported(format!(
+                "HDT format {}",
+                global.format
+            )));
+        }
+        let header_ci = ControlInfo::read(&mut r, HEADER)?;
+        let Some(header_len) = header_ci.get_usize("length")? else {
+            return format_error("missing header length");
+        };
+        let header_start = r.pos();
+        utf8(r.take(header_len)?)?;
+        let dictionary = Dictionary::read(&mut r)?;
+        let triples = BitmapTriples::read(&mut r, bytes, dictionary.nb_subjects())?;
+        Ok(HdtGraph {
+            header: header_start..header_start + header_len,
+            dictionary,
+            triples,
+            data,
+        })
+    }
+
+    /// The header of the HDT data, describing its content,
+    /// usually in N-Triples.
+    pub fn header(&self) -> &str {
+        // the header has been checked to be valid UTF-8 in `new`
+        std::str::from_utf8(&self.data.as_ref()[self.header.clone()]).unwrap()
+    }
+
+    /// The number of triples in this graph
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Whether this graph is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Consume this graph and return the underlying data.
+    pub fn into_inner(self) -> D {
+        self.data
+    }
+
+    /// Decode the triples identified by `ids`.
+    fn decode<'a>(
+        &'a self,
+        ids: IdIter<'a>,
+    ) -> impl Iterator<Item = Result<[SimpleTerm<'static>; 3], HdtError>> + 'a {
+        let data = self.data.as_ref();
+        // consecutive triples often share their subject
+        let mut last_subject: Option<(usize, SimpleTerm<'static>)> = None;
+        ids.map(move |[s, p, o]| {
+            let s = match &last_subject {
+                Some((id, t)) if *id == s => t.clone(),
+                _ => {
+                    let t = self.dictionary.term(data, s, Role::Subject)?;
+                    last_subject = Some((s, t.clone()));
+                    t
+                }
+            };
+            Ok([
+                s,
+                self.dictionary.term(data, p, Role::Predicate)?,
+                self.dictionary.term(data, o, Role::Object)?,
+            ])
+        })
+    }
+}
+
+impl<D: AsRef<[u8]>> Graph for HdtGraph<D> {
+    type Triple<'x>
+        = [SimpleTerm<'static>; 3]
+    where
+        Self: 'x;
+    type Error = HdtError;
+
+    fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
+        self.decode(self.triples.matching(self.data.as_ref(), [None; 3]))
+    }
+
+    #[allow(refining_impl_trait)]
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        sm: S,
+        pm: P,
+        om: O,
+    ) -> Box<dyn Iterator<Item = GResult<Self, Self::Triple<'s>>> + 's>
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+    {
+        let data = self.data.as_ref();
+        let mut pattern = [None; 3];
+        let ids = [
+            sm.constant()
+                .map(|t| self.dictionary.id(data, t.borrow_term(), Role::Subject)),
+            pm.constant()
+                .map(|t| self.dictionary.id(data, t.borrow_term(), Role::Predicate)),
+            om.constant()
+                .map(|t| self.dictionary.id(data, t.borrow_term(), Role::Object)),
+        ];
+        for (i, id) in ids.into_iter().enumerate() {
+            match id {
+                None => {}
+                Some(None) => return Box::new(empty()),
+                Some(Some(id)) => pattern[i] = Some(id),
+            }
+        }
+        Box::new(
+            self.decode(self.triples.matching(data, pattern))
+                .filter(move |res| match res {
+                    Ok([s, p, o]) => sm.matches(s) && pm.matches(p) && om.matches(o),
+                    Err(_) => true,
+                }),
+        )
+    }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+    ) -> Option<usize> {
+        let data = self.data.as_ref();
+        let mut pattern = [None; 3];
+        let roles = [Role::Subject, Role::Predicate, Role::Object];
+        for (i, (t, role)) in [s, p, o].into_iter().zip(roles).enumerate() {
+            if let Some(t) = t {
+                let Some(id) = self.dictionary.id(data, t, role) else {
+                    return Some(0);
+                };
+                pattern[i] = Some(id);
+            }
+        }
+        match pattern {
+            [None, None, None] => Some(self.len()),
+            // the bitmap index gives the exact range of triples with a given subject
+            [Some(_), _, _] => self.triples.matching(data, pattern).size_hint().1,
+            _ => None,
+        }
+    }
+}
+
+impl<D: AsRef<[u8]>> SetGraph for HdtGraph<D> {}
+
+impl<D> std::fmt::Debug for HdtGraph<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HdtGraph")
+            .field("triples", &self.triples)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,225 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides support for [HDT], a compact binary format for RDF graphs:
+//! [`HdtGraph`] exposes HDT data (typically a memory-mapped file) as a read-only [`Graph`],
+//! and [`HdtSerializer`] writes any [`Graph`] as HDT.
+//!
+//! # Example
+//! ```
+//! # use sophia_api::graph::Graph;
+//! # use sophia_api::ns::rdf;
+//! # use sophia_api::serializer::TripleSerializer;
+//! # use sophia_api::term::matcher::Any;
+//! # use sophia_hdt::{HdtGraph, HdtSerializer};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let dir = tempfile::tempdir()?;
+//! let path = dir.path().join("graph.hdt");
+//! let g = vec![
+//!     [rdf::type_, rdf::type_, rdf::Property],
+//!     [rdf::value, rdf::type_, rdf::Property],
+//! ];
+//! HdtSerializer::new(std::fs::File::create(&path)?).serialize_graph(&g)?;
+//!
+//! let hdt = HdtGraph::open(&path)?;
+//! assert_eq!(hdt.triples_matching([rdf::value], Any, Any).count(), 1);
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+//! [HDT]: https://www.rdfhdt.org/
+//! [`Graph`]: sophia_api::graph::Graph
+#![deny(missing_docs)]
+
+mod container;
+mod dictionary;
+mod error;
+pub use error::*;
+mod graph;
+pub use graph::*;
+mod serializer;
+pub use serializer::*;
+mod triples;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::graph::Graph;
+    use sophia_api::ns::{rdf, xsd, Namespace};
+    use sophia_api::serializer::TripleSerializer;
+    use sophia_api::source::TripleSource;
+    use sophia_api::term::matcher::Any;
+    use sophia_api::term::{BnodeId, FromTerm, LanguageTag, SimpleTerm, Term, VarName};
+    use sophia_isomorphism::isomorphic_graphs;
+
+    type TestGraph = HdtGraph<Vec<u8>>;
+
+    fn collect_hdt<TS: TripleSource>(ts: TS) -> Result<TestGraph, Box<dyn std::error::Error>> {
+        let mut ser = HdtSerializer::new(vec![]);
+        ser.serialize_triples(ts)?;
+        Ok(HdtGraph::new(ser.into_inner())?)
+    }
+
+    sophia_api::test_graph_impl!(hdt_graph, TestGraph, true, false, collect_hdt, {});
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let b1 = BnodeId::new_unchecked("b1");
+        let g: Vec<[SimpleTerm; 3]> = (0..100)
+            .map(|i| [b1.into_term(), rdf::value.into_term(), i.into_term()])
+            .chain([
+                [
+                    rdf::type_.into_term(),
+                    rdf::type_.into_term(),
+                    rdf::Property.into_term(),
+                ],
+                [
+                    rdf::type_.into_term(),
+                    rdf::value.into_term(),
+                    b1.into_term(),
+                ],
+                [
+                    rdf::Property.into_term(),
+                    rdf::value.into_term(),
+                    "lit\n\"quoted\"".into_term(),
+                ],
+            ])
+            .collect();
+        let hdt = collect_hdt(g.triples())?;
+        assert_eq!(hdt.len(), g.len());
+        assert!(isomorphic_graphs(&g, &hdt)?);
+        assert!(hdt
+            .header()
+            .contains("<http://rdfs.org/ns/void#triples> \"103\""));
+
+        assert_eq!(hdt.estimate_matching(Some(b1), None, None), Some(100));
+        assert_eq!(
+            hdt.estimate_matching(None::<SimpleTerm>, None, None),
+            Some(103)
+        );
+        assert_eq!(
+            hdt.estimate_matching(Some(xsd::integer), None, None),
+            Some(0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_terms() {
+        let v = VarName::new_unchecked("x");
+        for t in [
+            [
+                v.into_term(),
+                rdf::type_.into_term(),
+                rdf::Property.into_term(),
+            ],
+            [
+                rdf::type_.into_term(),
+                rdf::type_.into_term(),
+                v.into_term(),
+            ],
+            [
+                "lit".into_term(),
+                rdf::type_.into_term(),
+                rdf::Property.into_term(),
+            ],
+        ] {
+            let g: Vec<[SimpleTerm; 3]> = vec![t];
+            assert!(collect_hdt(g.triples()).is_err());
+        }
+    }
+
+    #[test]
+    fn corrupted() -> Result<(), Box<dyn std::error::Error>> {
+        let g = vec![[rdf::type_, rdf::type_, rdf::Property]];
+        let mut ser = HdtSerializer::new(vec![]);
+        ser.serialize_graph(&g)?;
+        let mut data = ser.into_inner();
+        assert!(HdtGraph::new(&data[..]).is_ok());
+        assert!(HdtGraph::new(&data[..data.len() - 1]).is_err());
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(HdtGraph::new(&data[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn open() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("test.hdt");
+        let g = vec![[rdf::type_, rdf::type_, rdf::Property]];
+        HdtSerializer::new(std::fs::File::create(&path)?).serialize_graph(&g)?;
+        let hdt = HdtGraph::open(&path)?;
+        assert!(isomorphic_graphs(&g, &hdt)?);
+        assert!(HdtGraph::open(dir.path().join("missing.hdt")).is_err());
+        Ok(())
+    }
+
+    /// `test/sample.hdt` was not written by [`HdtSerializer`],
+    /// but assembled independently, following the layout of the files written by hdt-cpp
+    /// (sorted control information properties, dictionary sections ending with a NUL byte...).
+    #[test]
+    fn sample_file() -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new_unchecked("http://example.org/");
+        let foaf = Namespace::new_unchecked("http://xmlns.com/foaf/0.1/");
+        let (alice, bob, list) = (ex.get("alice")?, ex.get("bob")?, ex.get("list")?);
+        let (name, knows) = (foaf.get("name")?, foaf.get("knows")?);
+        let item = ex.get("item")?;
+        let b1 = BnodeId::new_unchecked("b1");
+        let bob_en =
+            SimpleTerm::LiteralLanguage("Bob".into(), LanguageTag::new_unchecked("en".into()));
+        let mut expected: Vec<[SimpleTerm; 3]> = vec![
+            [
+                alice.into_term(),
+                rdf::type_.into_term(),
+                foaf.get("Person")?.into_term(),
+            ],
+            [alice.into_term(), name.into_term(), "Alice".into_term()],
+            [alice.into_term(), knows.into_term(), bob.into_term()],
+            [alice.into_term(), knows.into_term(), b1.into_term()],
+            [
+                alice.into_term(),
+                ex.get("age")?.into_term(),
+                42.into_term(),
+            ],
+            [
+                bob.into_term(),
+                rdf::type_.into_term(),
+                foaf.get("Person")?.into_term(),
+            ],
+            [bob.into_term(), name.into_term(), bob_en.clone()],
+            [b1.into_term(), name.into_term(), "Anonymous".into_term()],
+        ];
+        for i in 0..20 {
+            expected.push([
+                list.into_term(),
+                item.into_term(),
+                SimpleTerm::from_term(i.to_string().as_str()),
+            ]);
+        }
+
+        let hdt = HdtGraph::open("test/sample.hdt")?;
+        assert_eq!(hdt.len(), 28);
+        assert!(hdt
+            .header()
+            .contains("<http://rdfs.org/ns/void#triples> \"28\""));
+        assert!(isomorphic_graphs(&expected, &hdt)?);
+
+        assert_eq!(hdt.triples_matching([alice], Any, Any).count(), 5);
+        assert_eq!(hdt.triples_matching([alice], [knows], Any).count(), 2);
+        assert_eq!(hdt.triples_matching(Any, [name], Any).count(), 3);
+        assert_eq!(hdt.triples_matching(Any, Any, [bob]).count(), 1);
+        assert_eq!(hdt.triples_matching(Any, [item], Any).count(), 20);
+        // "9" is in the second block of the object section
+        assert_eq!(hdt.triples_matching([list], [item], ["9"]).count(), 1);
+        assert_eq!(hdt.triples_matching([list], [item], ["20"]).count(), 0);
+        let t = hdt.triples_matching(Any, Any, [bob_en]).next().unwrap()?;
+        assert!(Term::eq(&t[0], bob));
+        assert!(Term::eq(&t[1], name));
+        assert!(hdt.contains(alice, ex.get("age")?, 42)?);
+        assert!(!hdt.contains(bob, ex.get("age")?, 42)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! Serializer for the [HDT] binary format.
+//!
+//! [HDT]: https://www.rdfhdt.org/hdt-binary-format/
+use crate::container::*;
+use crate::dictionary::write_dictionary;
+use crate::error::HdtError;
+use crate::graph::HDT_V1;
+use crate::triples::write_triples;
+use sophia_api::serializer::TripleSerializer;
+use sophia_api::source::{StreamError::SinkError, StreamResult, TripleSource};
+use sophia_api::term::{Term, TermKind};
+use sophia_api::triple::Triple;
+use sophia_inmem::index::{SimpleTermIndex, TermIndex};
+use std::io;
+
+/// HDT serializer.
+///
+/// As HDT is not a streaming format,
+/// all the triples are collected in memory before the HDT data is written.
+/// Each call to [`serialize_triples`](TripleSerializer::serialize_triples)
+/// writes a complete HDT document.
+pub struct HdtSerializer<W> {
+    write: W,
+}
+
+impl<W> HdtSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new HDT serializer writing to `write`.
+    #[inline]
+    pub fn new(write: W) -> HdtSerializer<W> {
+        HdtSerializer { write }
+    }
+
+    /// Consume this serializer and return its target.
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+}
+
+impl<W> TripleSerializer for HdtSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = HdtError;
+
+    fn serialize_triples<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+    {
+        let mut terms = SimpleTermIndex::<u32>::new();
+        let mut triples = vec![];
+        source.try_for_each_triple(|t| {
+            let [s, p, o] = t.spo();
+            let valid = matches!(s.kind(), TermKind::Iri | TermKind::BlankNode)
+                && p.kind() == TermKind::Iri
+                && matches!(
+                    o.kind(),
+                    TermKind::Iri | TermKind::BlankNode | TermKind::Literal
+                );
+            if !valid {
+                return Err(HdtError::Unsupported(format!(
+                    "triple {:?} can not be stored in HDT",
+                    [s.as_simple(), p.as_simple(), o.as_simple()]
+                )));
+            }
+            let mut index = |t| {
+                terms
+                    .ensure_index(t)
+                    .map_err(|e| HdtError::Unsupported(e.to_string()))
+            };
+            triples.push([index(s)?, index(p)?, index(o)?]);
+            Ok(())
+        })?;
+        let mut out = vec![];
+        write_hdt(&mut out, &terms, &triples).map_err(SinkError)?;
+        self.write
+            .write_all(&out)
+            .map_err(|e| SinkError(e.into()))?;
+        Ok(self)
+    }
+}
+
+/// Append to `out` the HDT representation of `triples`, whose terms are stored in `terms`.
+fn write_hdt<TI: TermIndex>(
+    out: &mut Vec<u8>,
+    terms: &TI,
+    triples: &[[TI::Index; 3]],
+) -> Result<(), HdtError> {
+    ControlInfo::new(GLOBAL, HDT_V1)
+        .with("Software", "sophia_hdt")
+        .write(out);
+
+    let mut dictionary = vec![];
+    let ids = write_dictionary(&mut dictionary, terms, triples)?;
+    let mut triples: Vec<[usize; 3]> = triples
+        .iter()
+        .map(|[s, p, o]| [ids.so[s], ids.p[p], ids.so[o]])
+        .collect();
+    triples.sort_unstable();
+    triples.dedup();
+
+    let header = [
+        (
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "<http://purl.org/HDT/hdt#Dataset>".to_string(),
+        ),
+        (
+            "http://rdfs.org/ns/void#triples",
+            format!("\"{}\"", triples.len()),
+        ),
+        (
+            "http://rdfs.org/ns/void#properties",
+            format!("\"{}\"", ids.p.len()),
+        ),
+        (
+            "http://rdfs.org/ns/void#distinctSubjects",
+            format!("\"{}\"", ids.nb_subjects),
+        ),
+        (
+            "http://rdfs.org/ns/void#distinctObjects",
+            format!("\"{}\"", ids.nb_objects),
+        ),
+    ]
+    .into_iter()
+    .map(|(p, o)| format!("_:dataset <{p}> {o} .\n"))
+    .collect::<String>();
+    ControlInfo::new(HEADER, "ntriples")
+        .with("length", header.len())
+        .write(out);
+    out.extend_from_slice(header.as_bytes());
+
+    out.extend_from_slice(&dictionary);
+    write_triples(out, &triples);
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! The bitmap triples index of HDT.
+//!
+//! Triples are sorted in subject-predicate-object order, and stored as the identifiers of their terms.
+//! Subjects are implicit (they range from 1 to the number of subjects),
+//! `array_y` contains the predicates of each subject, and `array_z` the objects of each subject-predicate pair.
+//! `bitmap_y` (resp. `bitmap_z`) has a one at the position of the last predicate of each subject
+//! (resp. the last object of each subject-predicate pair).
+use crate::container::*;
+use crate::error::{format_error, HdtError};
+use std::ops::Range;
+
+type Result<T> = std::result::Result<T, HdtError>;
+
+pub(crate) const BITMAP_TRIPLES: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+const ORDER_SPO: usize = 1;
+
+/// A bitmap triples index.
+#[derive(Clone, Debug)]
+pub(crate) struct BitmapTriples {
+    bitmap_y: Bitmap,
+    bitmap_z: Bitmap,
+    array_y: LogArray,
+    array_z: LogArray,
+}
+
+impl BitmapTriples {
+    pub fn read(r: &mut Reader, data: &[u8], nb_subjects: usize) -> Result<Self> {
+        let ci = ControlInfo::read(r, TRIPLES)?;
+        if ci.format != BITMAP_TRIPLES {
+            return Err(HdtError::Unsupported(format!(
+                "triples format {}",
+                ci.format
+            )));
+        }
+        if ci.get_usize("order")? != Some(ORDER_SPO) {
+            return Err(HdtError::Unsupported("triple order other than SPO".into()));
+        }
+        let triples = BitmapTriples {
+            bitmap_y: Bitmap::read(r)?,
+            bitmap_z: Bitmap::read(r)?,
+            array_y: LogArray::read(r)?,
+            array_z: LogArray::read(r)?,
+        };
+        let ends_with_one = |b: &Bitmap| b.len() == 0 || b.get(data, b.len() - 1);
+        if triples.bitmap_y.len() != triples.array_y.len()
+            || triples.bitmap_z.len() != triples.array_z.len()
+            || triples.bitmap_y.ones() != nb_subjects
+            || triples.bitmap_z.ones() != triples.array_y.len()
+            || !ends_with_one(&triples.bitmap_y)
+            || !ends_with_one(&triples.bitmap_z)
+        {
+            return format_error("inconsistent bitmap triples");
+        }
+        Ok(triples)
+    }
+
+    /// The number of triples
+    pub fn len(&self) -> usize {
+        self.array_z.len()
+    }
+
+    /// Iterate over the triples matching `pattern`, where `None` matches any identifier.
+    pub fn matching<'a>(&'a self, data: &'a [u8], pattern: [Option<usize>; 3]) -> IdIter<'a> {
+        let mut iter = IdIter {
+            triples: self,
+            data,
+            pattern,
+            s: 1,
+            y: 0,
+            z: 0..self.len(),
+        };
+        let Some(s) = pattern[0] else {
+            return iter;
+        };
+        iter.s = s;
+        iter.z = 0..0;
+        if s == 0 || s > self.bitmap_y.ones() {
+            return iter;
+        }
+        let mut y = self.y_range(data, s);
+        if let Some(p) = pattern[1] {
+            match self.array_y.binary_search(data, y.start, y.end, p) {
+                Some(j) => y = j..j + 1,
+                None => return iter,
+            }
+        }
+        iter.y = y.start;
+        iter.z = self.z_range(data, y.start).start..self.z_range(data, y.end - 1).end;
+        if let (Some(_), Some(o)) = (pattern[1], pattern[2]) {
+            match self
+                .array_z
+                .binary_search(data, iter.z.start, iter.z.end, o)
+            {
+                Some(k) => iter.z = k..k + 1,
+                None => iter.z = 0..0,
+            }
+        }
+        iter
+    }
+
+    /// The positions in `array_y` of the predicates of subject `s` (starting at 1).
+    fn y_range(&self, data: &[u8], s: usize) -> Range<usize> {
+        self.range(&self.bitmap_y, data, s - 1)
+    }
+
+    /// The positions in `array_z` of the objects of the `j`-th subject-predicate pair.
+    fn z_range(&self, data: &[u8], j: usize) -> Range<usize> {
+        self.range(&self.bitmap_z, data, j)
+    }
+
+    fn range(&self, bitmap: &Bitmap, data: &[u8], i: usize) -> Range<usize> {
+        let start = match i {
+            0 => 0,
+            _ => bitmap.select1(data, i - 1).unwrap() + 1,
+        };
+        start..bitmap.select1(data, i).unwrap() + 1
+    }
+}
+
+/// An iterator over the identifiers of the triples of a [`BitmapTriples`] matching a pattern.
+pub(crate) struct IdIter<'a> {
+    triples: &'a BitmapTriples,
+    data: &'a [u8],
+    pattern: [Option<usize>; 3],
+    /// The current subject
+    s: usize,
+    /// The position of the current predicate in `array_y`
+    y: usize,
+    /// The remaining positions in `array_z`
+    z: Range<usize>,
+}
+
+impl Iterator for IdIter<'_> {
+    type Item = [usize; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let t = self.triples;
+        for k in self.z.by_ref() {
+            let ids = [
+                self.s,
+                t.array_y.get(self.data, self.y),
+                t.array_z.get(self.data, k),
+            ];
+            if t.bitmap_z.get(self.data, k) {
+                if t.bitmap_y.get(self.data, self.y) {
+                    self.s += 1;
+                }
+                self.y += 1;
+            }
+            if ids
+                .iter()
+                .zip(self.pattern)
+                .all(|(id, pat)| pat.is_none() || pat == Some(*id))
+            {
+                return Some(ids);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.z.len()))
+    }
+}
+
+/// Append bitmap triples containing `triples` (which must be sorted and deduplicated) to `out`.
+///
+/// Subject identifiers must range from 1 to the number of distinct subjects.
+pub(crate) fn write_triples(out: &mut Vec<u8>, triples: &[[usize; 3]]) {
+    ControlInfo::new(TRIPLES, BITMAP_TRIPLES)
+        .with("order", ORDER_SPO)
+        .with("numTriples", triples.len())
+        .write(out);
+    let (mut bitmap_y, mut bitmap_z, mut array_y, mut array_z) = (vec![], vec![], vec![], vec![]);
+    for (i, [s, p, o]) in triples.iter().enumerate() {
+        let next = triples.get(i + 1);
+        let last_object = next.is_none_or(|[ns, np, _]| ns != s || np != p);
+        array_z.push(*o);
+        bitmap_z.push(last_object);
+        if last_object {
+            array_y.push(*p);
+            bitmap_y.push(next.is_none_or(|[ns, _, _]| ns != s));
+        }
+    }
+    write_bitmap(out, &bitmap_y);
+    write_bitmap(out, &bitmap_z);
+    write_log_array(out, &array_y);
+    write_log_array(out, &array_z);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matching() -> Result<()> {
+        let triples = [
+            [1, 1, 1],
+            [1, 1, 2],
+            [1, 2, 3],
+            [2, 2, 1],
+            [3, 1, 2],
+            [3, 2, 2],
+            [3, 2, 3],
+        ];
+        let mut buf = vec![];
+        write_triples(&mut buf, &triples);
+        let bt = BitmapTriples::read(&mut Reader::new(&buf), &buf, 3)?;
+        assert_eq!(bt.len(), triples.len());
+
+        let values = [None, Some(1), Some(2), Some(3), Some(4)];
+        for s in values {
+            for p in values {
+                for o in values {
+                    let pattern = [s, p, o];
+                    let expected: Vec<_> = triples
+                        .iter()
+                        .copied()
+                        .filter(|t| {
+                            t.iter()
+                                .zip(pattern)
+                                .all(|(i, pat)| pat.is_none() || pat == Some(*i))
+                        })
+                        .collect();
+                    let iter = bt.matching(&buf, pattern);
+                    assert!(iter.size_hint().1.unwrap() >= expected.len());
+                    assert_eq!(iter.collect::<Vec<_>>(), expected, "{pattern:?}");
+                }
+            }
+        }
+
+        assert!(BitmapTriples::read(&mut Reader::new(&buf), &buf, 4).is_err());
+        Ok(())
+    }
+}
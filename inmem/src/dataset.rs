@@ -8,6 +8,7 @@ use sophia_api::quad::Gspo;
 use sophia_api::term::GraphName;
 
 use crate::index::*;
+use crate::transaction::sealed::{Keyed, KeyedDataset};
 
 mod _iter;
 use _iter::*;
@@ -31,7 +32,10 @@ impl<TI: GraphNameIndex + Default> GenericLightDataset<TI> {
 }
 
 impl<TI: GraphNameIndex> Dataset for GenericLightDataset<TI> {
-    type Quad<'x> = Gspo<<TI::Term as Term>::BorrowTerm<'x>> where Self: 'x;
+    type Quad<'x>
+        = Gspo<<TI::Term as Term>::BorrowTerm<'x>>
+    where
+        Self: 'x;
     type Error = TI::Error;
 
     fn quads(&self) -> impl Iterator<Item = DResult<Self, Self::Quad<'_>>> + '_ {
@@ -121,6 +125,38 @@ impl<TI: GraphNameIndex> Dataset for GenericLightDataset<TI> {
             GspoMatchingIterator::boxed(&self.terms, self.quads.iter(), gm, sm, pm, om)
         }
     }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+        g: Option<GraphName<T>>,
+    ) -> Option<usize> {
+        let Some([si, pi, oi]) = get_indexes(&self.terms, [s, p, o]) else {
+            return Some(0);
+        };
+        let gi = match g.map(|g| self.terms.get_graph_name_index(g)) {
+            None => None,
+            Some(None) => return Some(0),
+            Some(Some(i)) => Some(i),
+        };
+        let (z, m) = (TI::Index::ZERO, TI::Index::MAX);
+        // without a graph name, quads_matching has to scan the whole dataset
+        Some(match (gi, si, pi, oi) {
+            (Some(gi), Some(si), Some(pi), Some(oi)) => {
+                usize::from(self.quads.contains(&[gi, si, pi, oi]))
+            }
+            (Some(gi), Some(si), Some(pi), None) => {
+                capped_count(self.quads.range([gi, si, pi, z]..=[gi, si, pi, m]))
+            }
+            (Some(gi), Some(si), None, _) => {
+                capped_count(self.quads.range([gi, si, z, z]..=[gi, si, m, m]))
+            }
+            (Some(gi), None, _, _) => capped_count(self.quads.range([gi, z, z, z]..=[gi, m, m, m])),
+            (None, _, _, _) => self.quads.len(),
+        })
+    }
 }
 
 impl<TI: GraphNameIndex> MutableDataset for GenericLightDataset<TI> {
@@ -139,14 +175,8 @@ impl<TI: GraphNameIndex> MutableDataset for GenericLightDataset<TI> {
         TO: Term,
         TG: Term,
     {
-        let is = self.terms.ensure_index(s)?;
-        let ip = self.terms.ensure_index(p)?;
-        let io = self.terms.ensure_index(o)?;
-        let ig = match g {
-            None => self.terms.get_default_graph_index(),
-            Some(gn) => self.terms.ensure_index(gn)?,
-        };
-        Ok(self.quads.insert([ig, is, ip, io]))
+        let key = self.ensure_key(s, p, o, g)?;
+        Ok(self.insert_key(key))
     }
 
     fn remove<TS, TP, TO, TG>(
@@ -162,19 +192,63 @@ impl<TI: GraphNameIndex> MutableDataset for GenericLightDataset<TI> {
         TO: Term,
         TG: Term,
     {
-        let Some(is) = self.terms.get_index(s) else {
-            return Ok(false);
-        };
-        let Some(ip) = self.terms.get_index(p) else {
-            return Ok(false);
-        };
-        let Some(io) = self.terms.get_index(o) else {
+        let Some(key) = self.get_key(s, p, o, g) else {
             return Ok(false);
         };
-        let Some(ig) = self.terms.get_graph_name_index(g) else {
-            return Ok(false);
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<TI: GraphNameIndex> Keyed for GenericLightDataset<TI> {
+    type Key = [TI::Index; 4];
+    type KeyError = TI::Error;
+
+    fn insert_key(&mut self, key: Self::Key) -> bool {
+        self.quads.insert(key)
+    }
+
+    fn remove_key(&mut self, key: Self::Key) -> bool {
+        self.quads.remove(&key)
+    }
+}
+
+impl<TI: GraphNameIndex> KeyedDataset for GenericLightDataset<TI> {
+    fn ensure_key<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> Result<Self::Key, TI::Error>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        let is = self.terms.ensure_index(s)?;
+        let ip = self.terms.ensure_index(p)?;
+        let io = self.terms.ensure_index(o)?;
+        let ig = match g {
+            None => self.terms.get_default_graph_index(),
+            Some(gn) => self.terms.ensure_index(gn)?,
         };
-        Ok(self.quads.remove(&[ig, is, ip, io]))
+        Ok([ig, is, ip, io])
+    }
+
+    fn get_key<TS, TP, TO, TG>(&self, s: TS, p: TP, o: TO, g: GraphName<TG>) -> Option<Self::Key>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        Some([
+            self.terms.get_graph_name_index(g)?,
+            self.terms.get_index(s)?,
+            self.terms.get_index(p)?,
+            self.terms.get_index(o)?,
+        ])
     }
 }
 
@@ -220,8 +294,45 @@ impl<TI: GraphNameIndex + Default> GenericFastDataset<TI> {
     }
 }
 
+impl<TI: GraphNameIndex> GenericFastDataset<TI>
+where
+    TI::Index: Send + Sync,
+{
+    /// Construct a dataset from a term index and the keys of its quads (possibly with duplicates),
+    /// building each quad index in bulk (in parallel).
+    pub(crate) fn from_keys(terms: TI, keys: Vec<[TI::Index; 4]>) -> Self {
+        let keys = &keys;
+        let index = |order: fn([TI::Index; 4]) -> [TI::Index; 4]| {
+            move || keys.iter().map(|&k| order(k)).collect::<BTreeSet<_>>()
+        };
+        let [gspo, gpos, gosp, spog, posg, ospg] = std::thread::scope(|scope| {
+            let handles = [
+                scope.spawn(index(|[g, s, p, o]| [g, s, p, o])),
+                scope.spawn(index(|[g, s, p, o]| [g, p, o, s])),
+                scope.spawn(index(|[g, s, p, o]| [g, o, s, p])),
+                scope.spawn(index(|[g, s, p, o]| [s, p, o, g])),
+                scope.spawn(index(|[g, s, p, o]| [p, o, s, g])),
+                scope.spawn(index(|[g, s, p, o]| [o, s, p, g])),
+            ];
+            handles.map(|h| h.join().unwrap())
+        });
+        Self {
+            terms,
+            gspo,
+            gpos,
+            gosp,
+            spog,
+            posg,
+            ospg,
+        }
+    }
+}
+
 impl<TI: GraphNameIndex> Dataset for GenericFastDataset<TI> {
-    type Quad<'x> = Gspo<<TI::Term as Term>::BorrowTerm<'x>> where Self: 'x;
+    type Quad<'x>
+        = Gspo<<TI::Term as Term>::BorrowTerm<'x>>
+    where
+        Self: 'x;
     type Error = TI::Error;
 
     fn quads(&self) -> impl Iterator<Item = DResult<Self, Self::Quad<'_>>> + '_ {
@@ -447,6 +558,47 @@ impl<TI: GraphNameIndex> Dataset for GenericFastDataset<TI> {
             }
         }
     }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+        g: Option<GraphName<T>>,
+    ) -> Option<usize> {
+        let Some([si, pi, oi]) = get_indexes(&self.terms, [s, p, o]) else {
+            return Some(0);
+        };
+        let gi = match g.map(|g| self.terms.get_graph_name_index(g)) {
+            None => None,
+            Some(None) => return Some(0),
+            Some(Some(i)) => Some(i),
+        };
+        let (z, m) = (TI::Index::ZERO, TI::Index::MAX);
+        let r3 = |a, b, c| [a, b, c, z]..=[a, b, c, m];
+        let r2 = |a, b| [a, b, z, z]..=[a, b, m, m];
+        let r1 = |a| [a, z, z, z]..=[a, m, m, m];
+        Some(match (gi, si, pi, oi) {
+            (Some(gi), Some(si), Some(pi), Some(oi)) => {
+                usize::from(self.gspo.contains(&[gi, si, pi, oi]))
+            }
+            (Some(gi), Some(si), Some(pi), None) => capped_count(self.gspo.range(r3(gi, si, pi))),
+            (Some(gi), Some(si), None, Some(oi)) => capped_count(self.gosp.range(r3(gi, oi, si))),
+            (Some(gi), Some(si), None, None) => capped_count(self.gspo.range(r2(gi, si))),
+            (Some(gi), None, Some(pi), Some(oi)) => capped_count(self.gpos.range(r3(gi, pi, oi))),
+            (Some(gi), None, Some(pi), None) => capped_count(self.gpos.range(r2(gi, pi))),
+            (Some(gi), None, None, Some(oi)) => capped_count(self.gosp.range(r2(gi, oi))),
+            (Some(gi), None, None, None) => capped_count(self.gspo.range(r1(gi))),
+            (None, Some(si), Some(pi), Some(oi)) => capped_count(self.spog.range(r3(si, pi, oi))),
+            (None, Some(si), Some(pi), None) => capped_count(self.spog.range(r2(si, pi))),
+            (None, Some(si), None, Some(oi)) => capped_count(self.ospg.range(r2(oi, si))),
+            (None, Some(si), None, None) => capped_count(self.spog.range(r1(si))),
+            (None, None, Some(pi), Some(oi)) => capped_count(self.posg.range(r2(pi, oi))),
+            (None, None, Some(pi), None) => capped_count(self.posg.range(r1(pi))),
+            (None, None, None, Some(oi)) => capped_count(self.ospg.range(r1(oi))),
+            (None, None, None, None) => self.gspo.len(),
+        })
+    }
 }
 
 impl<TI: GraphNameIndex> MutableDataset for GenericFastDataset<TI> {
@@ -465,28 +617,8 @@ impl<TI: GraphNameIndex> MutableDataset for GenericFastDataset<TI> {
         TO: Term,
         TG: Term,
     {
-        let is = self.terms.ensure_index(s)?;
-        let ip = self.terms.ensure_index(p)?;
-        let io = self.terms.ensure_index(o)?;
-        let ig = match g {
-            None => self.terms.get_default_graph_index(),
-            Some(gn) => self.terms.ensure_index(gn)?,
-        };
-        if self.gspo.insert([ig, is, ip, io]) {
-            let i = self.gpos.insert([ig, ip, io, is]);
-            debug_assert!(i);
-            let i = self.gosp.insert([ig, io, is, ip]);
-            debug_assert!(i);
-            let i = self.spog.insert([is, ip, io, ig]);
-            debug_assert!(i);
-            let i = self.posg.insert([ip, io, is, ig]);
-            debug_assert!(i);
-            let i = self.ospg.insert([io, is, ip, ig]);
-            debug_assert!(i);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        let key = self.ensure_key(s, p, o, g)?;
+        Ok(self.insert_key(key))
     }
 
     fn remove<TS, TP, TO, TG>(
@@ -502,18 +634,36 @@ impl<TI: GraphNameIndex> MutableDataset for GenericFastDataset<TI> {
         TO: Term,
         TG: Term,
     {
-        let Some(is) = self.terms.get_index(s) else {
-            return Ok(false);
-        };
-        let Some(ip) = self.terms.get_index(p) else {
-            return Ok(false);
-        };
-        let Some(io) = self.terms.get_index(o) else {
-            return Ok(false);
-        };
-        let Some(ig) = self.terms.get_graph_name_index(g) else {
+        let Some(key) = self.get_key(s, p, o, g) else {
             return Ok(false);
         };
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<TI: GraphNameIndex> Keyed for GenericFastDataset<TI> {
+    type Key = [TI::Index; 4];
+    type KeyError = TI::Error;
+
+    fn insert_key(&mut self, [ig, is, ip, io]: Self::Key) -> bool {
+        if self.gspo.insert([ig, is, ip, io]) {
+            let i = self.gpos.insert([ig, ip, io, is]);
+            debug_assert!(i);
+            let i = self.gosp.insert([ig, io, is, ip]);
+            debug_assert!(i);
+            let i = self.spog.insert([is, ip, io, ig]);
+            debug_assert!(i);
+            let i = self.posg.insert([ip, io, is, ig]);
+            debug_assert!(i);
+            let i = self.ospg.insert([io, is, ip, ig]);
+            debug_assert!(i);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remove_key(&mut self, [ig, is, ip, io]: Self::Key) -> bool {
         if self.gspo.remove(&[ig, is, ip, io]) {
             let i = self.gpos.remove(&[ig, ip, io, is]);
             debug_assert!(i);
@@ -525,13 +675,53 @@ impl<TI: GraphNameIndex> MutableDataset for GenericFastDataset<TI> {
             debug_assert!(i);
             let i = self.ospg.remove(&[io, is, ip, ig]);
             debug_assert!(i);
-            Ok(true)
+            true
         } else {
-            Ok(false)
+            false
         }
     }
 }
 
+impl<TI: GraphNameIndex> KeyedDataset for GenericFastDataset<TI> {
+    fn ensure_key<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> Result<Self::Key, TI::Error>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        let is = self.terms.ensure_index(s)?;
+        let ip = self.terms.ensure_index(p)?;
+        let io = self.terms.ensure_index(o)?;
+        let ig = match g {
+            None => self.terms.get_default_graph_index(),
+            Some(gn) => self.terms.ensure_index(gn)?,
+        };
+        Ok([ig, is, ip, io])
+    }
+
+    fn get_key<TS, TP, TO, TG>(&self, s: TS, p: TP, o: TO, g: GraphName<TG>) -> Option<Self::Key>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        Some([
+            self.terms.get_graph_name_index(g)?,
+            self.terms.get_index(s)?,
+            self.terms.get_index(p)?,
+            self.terms.get_index(o)?,
+        ])
+    }
+}
+
 impl<TI: GraphNameIndex + Default> CollectibleDataset for GenericFastDataset<TI> {
     fn from_quad_source<QS: QuadSource>(
         mut quads: QS,
@@ -6,6 +6,7 @@ use sophia_api::graph::{CollectibleGraph, GResult, SetGraph};
 use sophia_api::prelude::*;
 
 use crate::index::*;
+use crate::transaction::sealed::{Keyed, KeyedGraph};
 
 mod _iter;
 pub(crate) use _iter::TermData;
@@ -30,7 +31,10 @@ impl<TI: TermIndex + Default> GenericLightGraph<TI> {
 }
 
 impl<TI: TermIndex> Graph for GenericLightGraph<TI> {
-    type Triple<'x> = [<TI::Term as Term>::BorrowTerm<'x>; 3] where Self: 'x;
+    type Triple<'x>
+        = [<TI::Term as Term>::BorrowTerm<'x>; 3]
+    where
+        Self: 'x;
     type Error = TI::Error;
 
     fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
@@ -91,6 +95,27 @@ impl<TI: TermIndex> Graph for GenericLightGraph<TI> {
             SpoMatchingIterator::boxed(&self.terms, self.triples.iter(), sm, pm, om)
         }
     }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+    ) -> Option<usize> {
+        let Some(indexes) = get_indexes(&self.terms, [s, p, o]) else {
+            return Some(0);
+        };
+        let (z, m) = (TI::Index::ZERO, TI::Index::MAX);
+        // without a subject, triples_matching has to scan the whole graph
+        Some(match indexes {
+            [Some(si), Some(pi), Some(oi)] => usize::from(self.triples.contains(&[si, pi, oi])),
+            [Some(si), Some(pi), None] => {
+                capped_count(self.triples.range([si, pi, z]..=[si, pi, m]))
+            }
+            [Some(si), None, _] => capped_count(self.triples.range([si, z, z]..=[si, m, m])),
+            [None, _, _] => self.triples.len(),
+        })
+    }
 }
 
 impl<TI: TermIndex> MutableGraph for GenericLightGraph<TI> {
@@ -102,10 +127,8 @@ impl<TI: TermIndex> MutableGraph for GenericLightGraph<TI> {
         TP: Term,
         TO: Term,
     {
-        let is = self.terms.ensure_index(s)?;
-        let ip = self.terms.ensure_index(p)?;
-        let io = self.terms.ensure_index(o)?;
-        Ok(self.triples.insert([is, ip, io]))
+        let key = self.ensure_key(s, p, o)?;
+        Ok(self.insert_key(key))
     }
 
     fn remove<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> sophia_api::graph::MgResult<Self, bool>
@@ -114,16 +137,50 @@ impl<TI: TermIndex> MutableGraph for GenericLightGraph<TI> {
         TP: Term,
         TO: Term,
     {
-        let Some(is) = self.terms.get_index(s) else {
-            return Ok(false);
-        };
-        let Some(ip) = self.terms.get_index(p) else {
+        let Some(key) = self.get_key(s, p, o) else {
             return Ok(false);
         };
-        let Some(io) = self.terms.get_index(o) else {
-            return Ok(false);
-        };
-        Ok(self.triples.remove(&[is, ip, io]))
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<TI: TermIndex> Keyed for GenericLightGraph<TI> {
+    type Key = [TI::Index; 3];
+    type KeyError = TI::Error;
+
+    fn insert_key(&mut self, key: Self::Key) -> bool {
+        self.triples.insert(key)
+    }
+
+    fn remove_key(&mut self, key: Self::Key) -> bool {
+        self.triples.remove(&key)
+    }
+}
+
+impl<TI: TermIndex> KeyedGraph for GenericLightGraph<TI> {
+    fn ensure_key<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> Result<Self::Key, TI::Error>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let is = self.terms.ensure_index(s)?;
+        let ip = self.terms.ensure_index(p)?;
+        let io = self.terms.ensure_index(o)?;
+        Ok([is, ip, io])
+    }
+
+    fn get_key<TS, TP, TO>(&self, s: TS, p: TP, o: TO) -> Option<Self::Key>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        Some([
+            self.terms.get_index(s)?,
+            self.terms.get_index(p)?,
+            self.terms.get_index(o)?,
+        ])
     }
 }
 
@@ -163,8 +220,33 @@ impl<TI: TermIndex + Default> GenericFastGraph<TI> {
     }
 }
 
+impl<TI: TermIndex> GenericFastGraph<TI>
+where
+    TI::Index: Send + Sync,
+{
+    /// Construct a graph from a term index and the keys of its triples (possibly with duplicates),
+    /// building each triple index in bulk (in parallel).
+    pub(crate) fn from_keys(terms: TI, keys: Vec<[TI::Index; 3]>) -> Self {
+        let (spo, pos, osp) = std::thread::scope(|scope| {
+            let pos = scope.spawn(|| keys.iter().map(|&[s, p, o]| [p, o, s]).collect());
+            let osp = scope.spawn(|| keys.iter().map(|&[s, p, o]| [o, s, p]).collect());
+            let spo = keys.iter().copied().collect();
+            (spo, pos.join().unwrap(), osp.join().unwrap())
+        });
+        Self {
+            terms,
+            spo,
+            pos,
+            osp,
+        }
+    }
+}
+
 impl<TI: TermIndex> Graph for GenericFastGraph<TI> {
-    type Triple<'x> = [<TI::Term as Term>::BorrowTerm<'x>; 3] where Self: 'x;
+    type Triple<'x>
+        = [<TI::Term as Term>::BorrowTerm<'x>; 3]
+    where
+        Self: 'x;
     type Error = TI::Error;
 
     fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
@@ -266,6 +348,28 @@ impl<TI: TermIndex> Graph for GenericFastGraph<TI> {
             }
         }
     }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+    ) -> Option<usize> {
+        let Some(indexes) = get_indexes(&self.terms, [s, p, o]) else {
+            return Some(0);
+        };
+        let (z, m) = (TI::Index::ZERO, TI::Index::MAX);
+        Some(match indexes {
+            [Some(si), Some(pi), Some(oi)] => usize::from(self.spo.contains(&[si, pi, oi])),
+            [Some(si), Some(pi), None] => capped_count(self.spo.range([si, pi, z]..=[si, pi, m])),
+            [Some(si), None, Some(oi)] => capped_count(self.osp.range([oi, si, z]..=[oi, si, m])),
+            [Some(si), None, None] => capped_count(self.spo.range([si, z, z]..=[si, m, m])),
+            [None, Some(pi), Some(oi)] => capped_count(self.pos.range([pi, oi, z]..=[pi, oi, m])),
+            [None, Some(pi), None] => capped_count(self.pos.range([pi, z, z]..=[pi, m, m])),
+            [None, None, Some(oi)] => capped_count(self.osp.range([oi, z, z]..=[oi, m, m])),
+            [None, None, None] => self.spo.len(),
+        })
+    }
 }
 
 impl<TI: TermIndex> MutableGraph for GenericFastGraph<TI> {
@@ -277,18 +381,8 @@ impl<TI: TermIndex> MutableGraph for GenericFastGraph<TI> {
         TP: Term,
         TO: Term,
     {
-        let is = self.terms.ensure_index(s)?;
-        let ip = self.terms.ensure_index(p)?;
-        let io = self.terms.ensure_index(o)?;
-        if self.spo.insert([is, ip, io]) {
-            let i = self.pos.insert([ip, io, is]);
-            debug_assert!(i);
-            let i = self.osp.insert([io, is, ip]);
-            debug_assert!(i);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        let key = self.ensure_key(s, p, o)?;
+        Ok(self.insert_key(key))
     }
 
     fn remove<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> sophia_api::graph::MgResult<Self, bool>
@@ -297,27 +391,69 @@ impl<TI: TermIndex> MutableGraph for GenericFastGraph<TI> {
         TP: Term,
         TO: Term,
     {
-        let Some(is) = self.terms.get_index(s) else {
-            return Ok(false);
-        };
-        let Some(ip) = self.terms.get_index(p) else {
-            return Ok(false);
-        };
-        let Some(io) = self.terms.get_index(o) else {
+        let Some(key) = self.get_key(s, p, o) else {
             return Ok(false);
         };
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<TI: TermIndex> Keyed for GenericFastGraph<TI> {
+    type Key = [TI::Index; 3];
+    type KeyError = TI::Error;
+
+    fn insert_key(&mut self, [is, ip, io]: Self::Key) -> bool {
+        if self.spo.insert([is, ip, io]) {
+            let i = self.pos.insert([ip, io, is]);
+            debug_assert!(i);
+            let i = self.osp.insert([io, is, ip]);
+            debug_assert!(i);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remove_key(&mut self, [is, ip, io]: Self::Key) -> bool {
         if self.spo.remove(&[is, ip, io]) {
             let i = self.pos.remove(&[ip, io, is]);
             debug_assert!(i);
             let i = self.osp.remove(&[io, is, ip]);
             debug_assert!(i);
-            Ok(true)
+            true
         } else {
-            Ok(false)
+            false
         }
     }
 }
 
+impl<TI: TermIndex> KeyedGraph for GenericFastGraph<TI> {
+    fn ensure_key<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> Result<Self::Key, TI::Error>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let is = self.terms.ensure_index(s)?;
+        let ip = self.terms.ensure_index(p)?;
+        let io = self.terms.ensure_index(o)?;
+        Ok([is, ip, io])
+    }
+
+    fn get_key<TS, TP, TO>(&self, s: TS, p: TP, o: TO) -> Option<Self::Key>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        Some([
+            self.terms.get_index(s)?,
+            self.terms.get_index(p)?,
+            self.terms.get_index(o)?,
+        ])
+    }
+}
+
 impl<TI: TermIndex + Default> CollectibleGraph for GenericFastGraph<TI> {
     fn from_triple_source<TS: TripleSource>(
         mut triples: TS,
@@ -345,6 +481,11 @@ pub type FastGraph = GenericFastGraph<SimpleTermIndex<u32>>;
 #[cfg(test)]
 mod test {
     use super::{FastGraph, LightGraph};
+    use sophia_api::graph::{CollectibleGraph, GTerm};
+    use sophia_api::ns::{rdf, Namespace};
+    use sophia_api::prelude::*;
+    use sophia_api::source::IntoSource;
+    use sophia_api::term::{SimpleTerm, VarName};
 
     sophia_api::test_graph_impl!(light_graph, LightGraph);
     sophia_api::test_graph_impl!(fast_graph, FastGraph);
@@ -356,6 +497,53 @@ mod test {
         let _ = FastGraph::new();
         let _ = LightGraph::new();
     }
+
+    #[test]
+    fn match_bgp() -> Result<(), Box<dyn std::error::Error>> {
+        check_match_bgp::<LightGraph>()?;
+        check_match_bgp::<FastGraph>()
+    }
+
+    fn check_match_bgp<G>() -> Result<(), Box<dyn std::error::Error>>
+    where
+        G: CollectibleGraph,
+        for<'x> GTerm<'x, G>: Clone,
+    {
+        let ex = Namespace::new_unchecked("http://example.org/");
+        let (alice, bob, carol, knows) = (
+            ex.get("alice")?,
+            ex.get("bob")?,
+            ex.get("carol")?,
+            ex.get("knows")?,
+        );
+        let triples = vec![
+            [alice, knows, bob],
+            [bob, knows, carol],
+            [carol, knows, alice],
+            [alice, rdf::type_, ex.get("Person")?],
+        ];
+        let g: G = triples.into_iter().into_source().collect_triples()?;
+        assert_eq!(g.estimate_matching(Some(alice), None, None), Some(2));
+        assert_eq!(
+            g.estimate_matching(Some(ex.get("dave")?), None, None),
+            Some(0)
+        );
+
+        let [x, y, z]: [SimpleTerm; 3] =
+            ["x", "y", "z"].map(|n| VarName::new_unchecked(n).into_term());
+        let bgp = [
+            [x.clone(), knows.into_term(), y.clone()],
+            [y.clone(), knows.into_term(), z.clone()],
+            [z, knows.into_term(), x.clone()],
+            [x, rdf::type_.into_term(), ex.get("Person")?.into_term()],
+        ];
+        let solutions = g.match_bgp(&bgp).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(solutions.len(), 1);
+        assert!(Term::eq(&solutions[0]["x"], alice));
+        assert!(Term::eq(&solutions[0]["y"], bob));
+        assert!(Term::eq(&solutions[0]["z"], carol));
+        Ok(())
+    }
 }
 
 /// Flavors of Graph implementations with a smaller memory-footprint.
@@ -27,6 +27,19 @@ impl Index for usize {
     }
 }
 
+impl Index for u64 {
+    const ZERO: Self = 0;
+    const MAX: Self = u64::MAX;
+    fn from_usize(other: usize) -> Self {
+        other as u64
+    }
+    fn into_usize(self) -> usize {
+        self.try_into()
+            .map_err(|_| ())
+            .expect("u64 too big to be converted to usize")
+    }
+}
+
 impl Index for u32 {
     const ZERO: Self = 0;
     const MAX: Self = u32::MAX;
@@ -139,6 +152,13 @@ impl<I: Index> SimpleTermIndex<I> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Consume this index, and iterate over its terms and their indices (in no particular order).
+    pub(crate) fn into_entries(self) -> impl Iterator<Item = (SimpleTerm<'static>, I)> {
+        // i2t borrows the keys of t2i, so it must be dropped first
+        drop(self.i2t);
+        self.t2i.into_iter()
+    }
 }
 
 impl<I: Index> TermIndex for SimpleTermIndex<I> {
@@ -188,6 +208,31 @@ impl<I: Index> GraphNameIndex for SimpleTermIndex<I> {
 #[error("This TermIndex can not contain more terms")]
 pub struct TermIndexFullError();
 
+/// The maximum value returned by `estimate_matching`,
+/// ensuring that estimates are cheap to compute.
+pub(crate) const ESTIMATE_CAP: usize = 1024;
+
+/// Count the items of `it`, up to [`ESTIMATE_CAP`].
+pub(crate) fn capped_count<I: Iterator>(it: I) -> usize {
+    it.take(ESTIMATE_CAP).count()
+}
+
+/// Get the index of each term in `ts` (`None` standing for any term).
+///
+/// Return `None` if any of the terms is not in `terms` (and therefore can not be matched).
+pub(crate) fn get_indexes<TI: TermIndex, T: Term, const N: usize>(
+    terms: &TI,
+    ts: [Option<T>; N],
+) -> Option<[Option<TI::Index>; N]> {
+    let mut ret = [None; N];
+    for (i, t) in ts.into_iter().enumerate() {
+        if let Some(t) = t {
+            ret[i] = Some(terms.get_index(t)?);
+        }
+    }
+    Some(ret)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
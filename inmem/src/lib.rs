@@ -1,7 +1,8 @@
 //! This crate is part of [Sophia],
 //! an [RDF] and [Linked Data] toolkit in Rust.
 //!
-//! It provides in-memory implementations of graphs and datasets.
+//! It provides in-memory implementations of graphs and datasets,
+//! supporting [transactions](transaction).
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
@@ -11,3 +12,5 @@
 pub mod dataset;
 pub mod graph;
 pub mod index;
+pub mod loader;
+pub mod transaction;
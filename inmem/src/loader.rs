@@ -0,0 +1,471 @@
+//! Parallel bulk loading of line-oriented RDF syntaxes
+//! into [`GenericFastGraph`] and [`GenericFastDataset`].
+//!
+//! In line-oriented syntaxes, such as [N-Triples] and [N-Quads],
+//! each line can be parsed independently of the rest of the document.
+//! The [`ParallelLoader`] takes advantage of that to split its input in chunks on line boundaries,
+//! parse them on several threads, and merge the terms of each chunk into a single [`TermIndex`].
+//! The triple (or quad) indexes of the graph (or dataset) are then built in bulk,
+//! which is faster than inserting triples (or quads) one at a time.
+//!
+//! ```
+//! # use sophia_api::graph::Graph;
+//! # use sophia_api::parser::TripleParser;
+//! # use sophia_api::source::{Source, TripleSource};
+//! # use sophia_api::term::SimpleTerm;
+//! # use sophia_inmem::graph::FastGraph;
+//! # use sophia_inmem::loader::ParallelLoader;
+//! # use sophia_inmem::loader::Chunk;
+//! # use std::io::{BufRead, Cursor};
+//! # /// A minimal parser for the subset of N-Triples where all terms are IRIs
+//! # #[derive(Default)]
+//! # struct IriTriplesParser;
+//! # impl TripleParser<Chunk> for IriTriplesParser {
+//! #     type Source = Box<dyn Iterator<Item = std::io::Result<[SimpleTerm<'static>; 3]>>>;
+//! #     fn parse(&self, data: Chunk) -> Self::Source {
+//! #         Box::new(data.lines().map(|line| {
+//! #             let line = line?;
+//! #             let mut iris = line.split(['<', '>', ' ', '.']).filter(|txt| !txt.is_empty());
+//! #             Ok([(); 3].map(|_| {
+//! #                 let iri = iris.next().unwrap().to_string();
+//! #                 SimpleTerm::Iri(sophia_api::term::IriRef::new_unchecked(iri.into()))
+//! #             }))
+//! #         }))
+//! #     }
+//! # }
+//! # let nt = "<tag:a> <tag:b> <tag:c> .\n<tag:a> <tag:b> <tag:d> .\n";
+//! # let file = Cursor::new(nt.as_bytes());
+//! // e.g. with sophia_turtle::parser::nt::NTriplesParser
+//! let loader = ParallelLoader::new(IriTriplesParser::default());
+//! let graph: FastGraph = loader.load_graph(file)?;
+//! # assert_eq!(graph.triples().count(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [N-Triples]: https://www.w3.org/TR/rdf12-n-triples/
+//! [N-Quads]: https://www.w3.org/TR/rdf12-n-quads/
+
+use std::error::Error;
+use std::io::{self, Cursor, Read};
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::Quad;
+use sophia_api::source::{QuadSource, TripleSource};
+use sophia_api::triple::Triple;
+
+use crate::dataset::GenericFastDataset;
+use crate::graph::GenericFastGraph;
+use crate::index::{GraphNameIndex, Index, SimpleTermIndex, TermIndex, TermIndexFullError};
+
+/// The type of data passed to the parser of a [`ParallelLoader`], for each chunk of its input.
+pub type Chunk = Cursor<Vec<u8>>;
+
+/// Loads graphs and datasets in parallel,
+/// from a line-oriented syntax such as N-Triples or N-Quads.
+///
+/// Each chunk of the input is parsed independently, with parser `P`.
+/// Therefore,
+/// `P` must only be used for syntaxes where each line can be parsed independently
+/// (which excludes Turtle, TriG, or N-Triples with multi-line statements),
+/// and must map blank node labels to the same blank nodes regardless of where they occur in the document
+/// (which is the case of the N-Triples and N-Quads parsers of `sophia_turtle`).
+///
+/// Note also that, if a parse error occurs,
+/// the position it reports (if any) is relative to the start of the chunk containing the error.
+///
+/// See the [module documentation](self) for more details.
+#[derive(Clone, Debug)]
+pub struct ParallelLoader<P> {
+    parser: P,
+    threads: usize,
+    chunk_size: usize,
+}
+
+impl<P> ParallelLoader<P> {
+    /// The default size of the chunks parsed by each thread.
+    pub const DEFAULT_CHUNK_SIZE: usize = 1 << 22;
+
+    /// Build a new loader using `parser`,
+    /// with as many threads as the [available parallelism](thread::available_parallelism).
+    pub fn new(parser: P) -> Self {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        ParallelLoader {
+            parser,
+            threads,
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Transform this loader by setting the number of threads used for parsing.
+    ///
+    /// # Precondition
+    /// `threads` must be strictly positive, otherwise this method will panic.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0);
+        self.threads = threads;
+        self
+    }
+
+    /// Transform this loader by setting the approximate size (in bytes) of the chunks parsed by each thread
+    /// (defaults to [`DEFAULT_CHUNK_SIZE`](Self::DEFAULT_CHUNK_SIZE)).
+    ///
+    /// Chunks may be bigger if they contain a line longer than `chunk_size`.
+    ///
+    /// # Precondition
+    /// `chunk_size` must be strictly positive, otherwise this method will panic.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0);
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Load all triples from `read` into a new [`GenericFastGraph`].
+    pub fn load_graph<TI, R, E>(
+        &self,
+        read: R,
+    ) -> Result<GenericFastGraph<TI>, LoaderError<E, TI::Error>>
+    where
+        P: TripleParser<Chunk> + Sync,
+        P::Source: TripleSource<Error = E>,
+        E: Error + Send + 'static,
+        TI: TermIndex + Default,
+        TI::Index: Send + Sync,
+        R: Read + Send,
+    {
+        let (terms, keys) = self.load(read, parse_triples::<P, E>, |local, mapping, _| {
+            local.map(|i| mapping[i])
+        })?;
+        Ok(GenericFastGraph::from_keys(terms, keys))
+    }
+
+    /// Load all quads from `read` into a new [`GenericFastDataset`].
+    pub fn load_dataset<TI, R, E>(
+        &self,
+        read: R,
+    ) -> Result<GenericFastDataset<TI>, LoaderError<E, TI::Error>>
+    where
+        P: QuadParser<Chunk> + Sync,
+        P::Source: QuadSource<Error = E>,
+        E: Error + Send + 'static,
+        TI: GraphNameIndex + Default,
+        TI::Index: Send + Sync,
+        R: Read + Send,
+    {
+        let (terms, keys) =
+            self.load(read, parse_quads::<P, E>, |local, mapping, terms: &TI| {
+                let default = terms.get_default_graph_index();
+                local.map(|i| if i == usize::MAX { default } else { mapping[i] })
+            })?;
+        Ok(GenericFastDataset::from_keys(terms, keys))
+    }
+
+    /// Parse the chunks of `read` in parallel with `parse`,
+    /// and merge their terms and keys, converting each local key with `convert`.
+    #[allow(clippy::type_complexity)]
+    fn load<TI, R, E, const N: usize>(
+        &self,
+        read: R,
+        parse: fn(&P, Chunk) -> Result<ParsedChunk<N>, E>,
+        convert: fn([usize; N], &[TI::Index], &TI) -> [TI::Index; N],
+    ) -> Result<(TI, Vec<[TI::Index; N]>), LoaderError<E, TI::Error>>
+    where
+        P: Sync,
+        E: Error + Send + 'static,
+        TI: TermIndex + Default,
+        R: Read + Send,
+    {
+        let mut terms = TI::default();
+        let mut keys = vec![];
+        thread::scope(|scope| {
+            let (chunk_tx, chunk_rx) = sync_channel::<Vec<u8>>(self.threads);
+            let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+            let (parsed_tx, parsed_rx) = sync_channel(self.threads);
+            for _ in 0..self.threads {
+                let chunk_rx = chunk_rx.clone();
+                let parsed_tx = parsed_tx.clone();
+                scope.spawn(move || {
+                    while let Some(chunk) = next_chunk(&chunk_rx) {
+                        let parsed = parse(&self.parser, Cursor::new(chunk));
+                        if parsed_tx.send(parsed).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(parsed_tx);
+            let reader = scope.spawn(move || {
+                let mut chunks = Chunks::new(read, self.chunk_size);
+                while let Some(chunk) = chunks.next_chunk()? {
+                    // an error means that the loading was interrupted
+                    if chunk_tx.send(chunk).is_err() {
+                        break;
+                    }
+                }
+                Ok::<_, io::Error>(())
+            });
+
+            for parsed in parsed_rx {
+                let ParsedChunk {
+                    terms: local_terms,
+                    keys: local_keys,
+                } = parsed.map_err(LoaderError::Parse)?;
+                let mut mapping = vec![TI::Index::ZERO; local_terms.len()];
+                for (t, i) in local_terms.into_entries() {
+                    mapping[i] = terms.ensure_index(t).map_err(LoaderError::TermIndex)?;
+                }
+                keys.extend(local_keys.into_iter().map(|k| convert(k, &mapping, &terms)));
+            }
+            reader.join().unwrap()?;
+            Ok::<_, LoaderError<E, TI::Error>>(())
+        })?;
+        Ok((terms, keys))
+    }
+}
+
+/// An error raised by a [`ParallelLoader`].
+#[derive(Debug, thiserror::Error)]
+pub enum LoaderError<E: Error + 'static, F: Error + 'static> {
+    /// The input could not be read
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    /// The input could not be parsed
+    #[error("parse error: {0}")]
+    Parse(#[source] E),
+    /// A term could not be added to the term index
+    #[error("term index error: {0}")]
+    TermIndex(#[source] F),
+}
+
+/// The terms and keys of the triples or quads of a chunk,
+/// using a chunk-local term index (`usize::MAX` standing for the default graph).
+struct ParsedChunk<const N: usize> {
+    terms: SimpleTermIndex<usize>,
+    keys: Vec<[usize; N]>,
+}
+
+fn parse_triples<P, E>(parser: &P, chunk: Chunk) -> Result<ParsedChunk<3>, E>
+where
+    P: TripleParser<Chunk>,
+    P::Source: TripleSource<Error = E>,
+    E: Error,
+{
+    let mut terms = SimpleTermIndex::new();
+    let mut keys = vec![];
+    parser
+        .parse(chunk)
+        .try_for_each_triple(|t| {
+            let [s, p, o] = t.to_spo();
+            let key = [
+                terms.ensure_index(s)?,
+                terms.ensure_index(p)?,
+                terms.ensure_index(o)?,
+            ];
+            keys.push(key);
+            Ok::<_, TermIndexFullError>(())
+        })
+        .map_err(|err| err.unwrap_source_error())?;
+    Ok(ParsedChunk { terms, keys })
+}
+
+fn parse_quads<P, E>(parser: &P, chunk: Chunk) -> Result<ParsedChunk<4>, E>
+where
+    P: QuadParser<Chunk>,
+    P::Source: QuadSource<Error = E>,
+    E: Error,
+{
+    let mut terms = SimpleTermIndex::new();
+    let mut keys = vec![];
+    parser
+        .parse(chunk)
+        .try_for_each_quad(|q| {
+            let ([s, p, o], g) = q.to_spog();
+            let g = match g {
+                Some(g) => terms.ensure_index(g)?,
+                None => usize::MAX,
+            };
+            let key = [
+                g,
+                terms.ensure_index(s)?,
+                terms.ensure_index(p)?,
+                terms.ensure_index(o)?,
+            ];
+            keys.push(key);
+            Ok::<_, TermIndexFullError>(())
+        })
+        .map_err(|err| err.unwrap_source_error())?;
+    Ok(ParsedChunk { terms, keys })
+}
+
+/// Receive the next chunk to parse, if any.
+fn next_chunk(chunk_rx: &Mutex<Receiver<Vec<u8>>>) -> Option<Vec<u8>> {
+    chunk_rx
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .recv()
+        .ok()
+}
+
+/// Splits a reader into chunks ending on line boundaries.
+struct Chunks<R> {
+    read: R,
+    size: usize,
+    /// The beginning of the next chunk, read while looking for the end of the previous one
+    rest: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> Chunks<R> {
+    fn new(read: R, size: usize) -> Self {
+        Chunks {
+            read,
+            size,
+            rest: vec![],
+            eof: false,
+        }
+    }
+
+    /// Read the next chunk, of approximately `size` bytes, ending with a line feed or at the end of the input.
+    fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut chunk = std::mem::take(&mut self.rest);
+        let mut target = self.size;
+        loop {
+            while chunk.len() < target && !self.eof {
+                let len = chunk.len();
+                chunk.resize(target, 0);
+                match self.read.read(&mut chunk[len..]) {
+                    Ok(n) => {
+                        chunk.truncate(len + n);
+                        self.eof = n == 0;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => chunk.truncate(len),
+                    Err(err) => return Err(err),
+                }
+            }
+            if self.eof {
+                return Ok((!chunk.is_empty()).then_some(chunk));
+            }
+            if let Some(i) = chunk.iter().rposition(|b| *b == b'\n') {
+                self.rest = chunk.split_off(i + 1);
+                return Ok(Some(chunk));
+            }
+            // the chunk contains a single, incomplete line
+            target *= 2;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::FastDataset;
+    use crate::graph::FastGraph;
+    use sophia_api::dataset::Dataset;
+    use sophia_api::graph::Graph;
+    use sophia_api::quad::Spog;
+    use sophia_api::term::{IriRef, SimpleTerm};
+
+    #[test]
+    fn chunks() -> io::Result<()> {
+        let txt = "a\nbb\n\nccccccc\ndd\ne";
+        let mut chunks = Chunks::new(txt.as_bytes(), 3);
+        let mut got = vec![];
+        while let Some(chunk) = chunks.next_chunk()? {
+            got.push(String::from_utf8(chunk).unwrap());
+        }
+        assert_eq!(got, ["a\n", "bb\n", "\n", "ccccccc\ndd\n", "e"]);
+        Ok(())
+    }
+
+    /// A minimal parser for the subset of N-Quads where all terms are IRIs
+    struct IriQuadsParser;
+
+    impl QuadParser<Chunk> for IriQuadsParser {
+        type Source = Box<dyn Iterator<Item = io::Result<Spog<SimpleTerm<'static>>>>>;
+
+        fn parse(&self, data: Chunk) -> Self::Source {
+            Box::new(io::BufRead::lines(data).map(|line| {
+                let mut iris = line?
+                    .split(['<', '>', ' ', '.'])
+                    .filter(|txt| !txt.is_empty())
+                    .map(|iri| SimpleTerm::Iri(IriRef::new_unchecked(iri.to_string().into())))
+                    .collect::<Vec<_>>();
+                let g = (iris.len() > 3).then(|| iris.pop().unwrap());
+                let Ok(spo) = iris.try_into() else {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "bad line"));
+                };
+                Ok((spo, g))
+            }))
+        }
+    }
+
+    impl TripleParser<Chunk> for IriQuadsParser {
+        type Source = Box<dyn Iterator<Item = io::Result<[SimpleTerm<'static>; 3]>>>;
+
+        fn parse(&self, data: Chunk) -> Self::Source {
+            Box::new(QuadParser::parse(self, data).map(|res| res.map(|(spo, _)| spo)))
+        }
+    }
+
+    fn nquads(n: usize) -> String {
+        (0..n)
+            .map(|i| {
+                let g = match i % 3 {
+                    0 => String::new(),
+                    j => format!(" <tag:g{j}>"),
+                };
+                format!("<tag:s{}> <tag:p{}> <tag:o{i}>{g} .\n", i % 7, i % 5)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn load_dataset() -> Result<(), Box<dyn std::error::Error>> {
+        let txt = nquads(1000);
+        let expected: FastDataset =
+            QuadParser::parse(&IriQuadsParser, Cursor::new(txt.clone().into_bytes()))
+                .collect_quads()?;
+        for (threads, chunk_size) in [(1, 1), (2, 16), (4, 100), (3, 1 << 20)] {
+            let got: FastDataset = ParallelLoader::new(IriQuadsParser)
+                .with_threads(threads)
+                .with_chunk_size(chunk_size)
+                .load_dataset(txt.as_bytes())?;
+            assert_eq!(got.quads().count(), 1000);
+            for q in expected.quads() {
+                let ([s, p, o], g) = q?.spog();
+                assert!(got.contains(s, p, o, g)?);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn load_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let txt = nquads(1000);
+        let got: FastGraph = ParallelLoader::new(IriQuadsParser)
+            .with_threads(3)
+            .with_chunk_size(64)
+            .load_graph(txt.as_bytes())?;
+        assert_eq!(got.triples().count(), 1000);
+        let [s, p, o] = ["tag:s5", "tag:p4", "tag:o999"].map(IriRef::new_unchecked);
+        assert!(got.contains(s, p, o)?);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error() {
+        let txt = nquads(100) + "<tag:a> <tag:b> .\n" + &nquads(100);
+        let res: Result<FastDataset, _> = ParallelLoader::new(IriQuadsParser)
+            .with_threads(2)
+            .with_chunk_size(64)
+            .load_dataset(txt.as_bytes());
+        assert!(matches!(res, Err(LoaderError::Parse(_))));
+    }
+}
@@ -0,0 +1,472 @@
+//! Transactions on the in-memory graphs and datasets of this crate.
+//!
+//! Calling [`begin`](Transactional::begin) on a graph or a dataset returns a [`Transaction`],
+//! which is itself a [`MutableGraph`] (resp. [`MutableDataset`]).
+//! All the changes made through the transaction are recorded in an undo log,
+//! and are reverted if the transaction is [rolled back](Transaction::rollback) or dropped
+//! without having been [committed](Transaction::commit).
+//!
+//! This makes it possible to load a [`TripleSource`](sophia_api::source::TripleSource) atomically:
+//! ```
+//! # use sophia_api::graph::{Graph, MutableGraph};
+//! # use sophia_api::ns::rdf;
+//! # use sophia_inmem::graph::FastGraph;
+//! # use sophia_inmem::transaction::Transactional;
+//! # #[derive(Debug, thiserror::Error)]
+//! # #[error("oops")]
+//! # struct MyError;
+//! let mut g = FastGraph::new();
+//! let source = vec![
+//!     Ok([rdf::type_, rdf::type_, rdf::Property]),
+//!     Err(MyError),
+//! ];
+//! let mut tx = g.begin();
+//! assert!(tx.insert_all(source.into_iter()).is_err());
+//! drop(tx); // the transaction is rolled back
+//! assert_eq!(g.triples().count(), 0);
+//! ```
+//!
+//! NB: terms added to the [term index](crate::index::TermIndex) of the graph or dataset
+//! are not removed on rollback; this does not change its content, only its memory footprint.
+use sophia_api::dataset::{DResult, MdResult, SetDataset};
+use sophia_api::graph::{GResult, MgResult, SetGraph};
+use sophia_api::prelude::*;
+use sophia_api::term::matcher::{GraphNameMatcher, TermMatcher};
+use sophia_api::term::GraphName;
+
+/// A graph or dataset whose changes can be recorded in a [`Transaction`].
+///
+/// This trait is sealed: it is implemented by the graphs and datasets of this crate,
+/// which record changes as the keys identifying a triple (resp. quad) in their indexes.
+pub trait Transactional: sealed::Keyed {
+    /// Start a new [`Transaction`] on this graph or dataset.
+    fn begin(&mut self) -> Transaction<'_, Self>
+    where
+        Self: Sized,
+    {
+        Transaction {
+            target: self,
+            log: vec![],
+        }
+    }
+}
+
+impl<T: sealed::Keyed> Transactional for T {}
+
+/// A [`Transactional`] graph.
+pub trait TransactionalGraph: Graph + Transactional + sealed::KeyedGraph {}
+
+impl<G: Graph + sealed::KeyedGraph> TransactionalGraph for G {}
+
+/// A [`Transactional`] dataset.
+pub trait TransactionalDataset: Dataset + Transactional + sealed::KeyedDataset {}
+
+impl<D: Dataset + sealed::KeyedDataset> TransactionalDataset for D {}
+
+pub(crate) mod sealed {
+    use sophia_api::term::{GraphName, Term};
+    use std::fmt::Debug;
+
+    /// A graph or dataset whose triples (resp. quads) are identified by keys.
+    pub trait Keyed {
+        /// The key identifying a triple or a quad in this graph or dataset
+        type Key: Copy + Debug;
+        /// The error raised when a key can not be computed for new terms
+        type KeyError: std::error::Error + 'static;
+
+        /// Insert the triple or quad identified by `key`.
+        ///
+        /// Return `true` if it was not already present.
+        fn insert_key(&mut self, key: Self::Key) -> bool;
+
+        /// Remove the triple or quad identified by `key`.
+        ///
+        /// Return `true` if it was present.
+        fn remove_key(&mut self, key: Self::Key) -> bool;
+    }
+
+    /// A [`Keyed`] graph.
+    pub trait KeyedGraph: Keyed {
+        /// Get the key identifying the given triple, adding its terms in the term index if necessary.
+        fn ensure_key<TS, TP, TO>(
+            &mut self,
+            s: TS,
+            p: TP,
+            o: TO,
+        ) -> Result<Self::Key, Self::KeyError>
+        where
+            TS: Term,
+            TP: Term,
+            TO: Term;
+
+        /// Get the key identifying the given triple, if all its terms are in the term index.
+        fn get_key<TS, TP, TO>(&self, s: TS, p: TP, o: TO) -> Option<Self::Key>
+        where
+            TS: Term,
+            TP: Term,
+            TO: Term;
+    }
+
+    /// A [`Keyed`] dataset.
+    pub trait KeyedDataset: Keyed {
+        /// Get the key identifying the given quad, adding its terms in the term index if necessary.
+        fn ensure_key<TS, TP, TO, TG>(
+            &mut self,
+            s: TS,
+            p: TP,
+            o: TO,
+            g: GraphName<TG>,
+        ) -> Result<Self::Key, Self::KeyError>
+        where
+            TS: Term,
+            TP: Term,
+            TO: Term,
+            TG: Term;
+
+        /// Get the key identifying the given quad, if all its terms are in the term index.
+        fn get_key<TS, TP, TO, TG>(
+            &self,
+            s: TS,
+            p: TP,
+            o: TO,
+            g: GraphName<TG>,
+        ) -> Option<Self::Key>
+        where
+            TS: Term,
+            TP: Term,
+            TO: Term,
+            TG: Term;
+    }
+}
+
+/// A guard recording the changes made to a [`Transactional`] graph or dataset.
+///
+/// The changes are kept if the transaction is [committed](Transaction::commit),
+/// and undone if it is [rolled back](Transaction::rollback) or dropped.
+#[derive(Debug)]
+pub struct Transaction<'a, T: Transactional> {
+    target: &'a mut T,
+    log: Vec<Change<T::Key>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Change<K> {
+    Inserted(K),
+    Removed(K),
+}
+
+impl<T: Transactional> Transaction<'_, T> {
+    /// Keep all the changes made in this transaction.
+    pub fn commit(mut self) {
+        self.log.clear();
+    }
+
+    /// Undo all the changes made in this transaction.
+    pub fn rollback(self) {
+        // rollback is performed by Drop
+    }
+
+    /// The number of changes (insertions or removals) made in this transaction so far.
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Whether no change has been made in this transaction so far.
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    fn insert_key(&mut self, key: T::Key) -> bool {
+        let inserted = self.target.insert_key(key);
+        if inserted {
+            self.log.push(Change::Inserted(key));
+        }
+        inserted
+    }
+
+    fn remove_key(&mut self, key: T::Key) -> bool {
+        let removed = self.target.remove_key(key);
+        if removed {
+            self.log.push(Change::Removed(key));
+        }
+        removed
+    }
+}
+
+impl<T: Transactional> Drop for Transaction<'_, T> {
+    fn drop(&mut self) {
+        while let Some(change) = self.log.pop() {
+            let undone = match change {
+                Change::Inserted(key) => self.target.remove_key(key),
+                Change::Removed(key) => self.target.insert_key(key),
+            };
+            debug_assert!(undone, "{change:?} could not be undone");
+        }
+    }
+}
+
+impl<G: TransactionalGraph> Graph for Transaction<'_, G> {
+    type Triple<'x>
+        = G::Triple<'x>
+    where
+        Self: 'x;
+    type Error = G::Error;
+
+    fn triples(&self) -> impl Iterator<Item = GResult<Self, Self::Triple<'_>>> + '_ {
+        self.target.triples()
+    }
+
+    fn triples_matching<'s, S, P, O>(
+        &'s self,
+        sm: S,
+        pm: P,
+        om: O,
+    ) -> impl Iterator<Item = GResult<Self, Self::Triple<'s>>> + 's
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+    {
+        self.target.triples_matching(sm, pm, om)
+    }
+
+    fn contains<TS, TP, TO>(&self, s: TS, p: TP, o: TO) -> GResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        self.target.contains(s, p, o)
+    }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+    ) -> Option<usize> {
+        self.target.estimate_matching(s, p, o)
+    }
+}
+
+impl<G: TransactionalGraph> MutableGraph for Transaction<'_, G> {
+    type MutationError = G::KeyError;
+
+    fn insert<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> MgResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let key = self.target.ensure_key(s, p, o)?;
+        Ok(self.insert_key(key))
+    }
+
+    fn remove<TS, TP, TO>(&mut self, s: TS, p: TP, o: TO) -> MgResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+    {
+        let Some(key) = self.target.get_key(s, p, o) else {
+            return Ok(false);
+        };
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<G: TransactionalGraph + SetGraph> SetGraph for Transaction<'_, G> {}
+
+impl<D: TransactionalDataset> Dataset for Transaction<'_, D> {
+    type Quad<'x>
+        = D::Quad<'x>
+    where
+        Self: 'x;
+    type Error = D::Error;
+
+    fn quads(&self) -> impl Iterator<Item = DResult<Self, Self::Quad<'_>>> + '_ {
+        self.target.quads()
+    }
+
+    fn quads_matching<'s, S, P, O, G>(
+        &'s self,
+        sm: S,
+        pm: P,
+        om: O,
+        gm: G,
+    ) -> impl Iterator<Item = DResult<Self, Self::Quad<'s>>> + 's
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+        G: GraphNameMatcher + 's,
+    {
+        self.target.quads_matching(sm, pm, om, gm)
+    }
+
+    fn contains<TS, TP, TO, TG>(&self, s: TS, p: TP, o: TO, g: GraphName<TG>) -> DResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        self.target.contains(s, p, o, g)
+    }
+
+    fn estimate_matching<T: Term>(
+        &self,
+        s: Option<T>,
+        p: Option<T>,
+        o: Option<T>,
+        g: Option<GraphName<T>>,
+    ) -> Option<usize> {
+        self.target.estimate_matching(s, p, o, g)
+    }
+}
+
+impl<D: TransactionalDataset> MutableDataset for Transaction<'_, D> {
+    type MutationError = D::KeyError;
+
+    fn insert<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        let key = self.target.ensure_key(s, p, o, g)?;
+        Ok(self.insert_key(key))
+    }
+
+    fn remove<TS, TP, TO, TG>(
+        &mut self,
+        s: TS,
+        p: TP,
+        o: TO,
+        g: GraphName<TG>,
+    ) -> MdResult<Self, bool>
+    where
+        TS: Term,
+        TP: Term,
+        TO: Term,
+        TG: Term,
+    {
+        let Some(key) = self.target.get_key(s, p, o, g) else {
+            return Ok(false);
+        };
+        Ok(self.remove_key(key))
+    }
+}
+
+impl<D: TransactionalDataset + SetDataset> SetDataset for Transaction<'_, D> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::{FastDataset, LightDataset};
+    use crate::graph::{FastGraph, LightGraph};
+    use sophia_api::graph::CollectibleGraph;
+    use sophia_api::ns::Namespace;
+    use sophia_api::source::IntoSource;
+    use sophia_api::term::SimpleTerm;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("interrupted source")]
+    struct Interrupted;
+
+    fn check_graph<G: TransactionalGraph + CollectibleGraph + MutableGraph>(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let ex = Namespace::new_unchecked("http://example.org/");
+        let [a, b, c, p] = ["a", "b", "c", "p"].map(|n| ex.get(n).unwrap());
+        let mut g: G = [[a, p, b]].into_iter().into_source().collect_triples()?;
+
+        // commit
+        let mut tx = g.begin();
+        assert!(tx.insert(b, p, c)?);
+        assert!(!tx.insert(b, p, c)?);
+        assert!(tx.remove(a, p, b)?);
+        assert!(!tx.remove(a, p, c)?);
+        assert!(tx.contains(b, p, c)?);
+        assert_eq!(tx.len(), 2);
+        tx.commit();
+        assert!(!g.contains(a, p, b)?);
+        assert!(g.contains(b, p, c)?);
+
+        // rollback
+        let mut tx = g.begin();
+        tx.insert(a, p, b)?;
+        tx.remove(b, p, c)?;
+        tx.insert(b, p, c)?;
+        tx.insert(c, p, a)?;
+        tx.rollback();
+        assert!(!g.contains(a, p, b)?);
+        assert!(g.contains(b, p, c)?);
+        assert!(!g.contains(c, p, a)?);
+        assert_eq!(g.triples().count(), 1);
+
+        // failing insert_all
+        let mut tx = g.begin();
+        let source = vec![Ok([a, p, a]), Ok([b, p, b]), Err(Interrupted)];
+        assert!(tx.insert_all(source.into_iter()).is_err());
+        assert_eq!(tx.triples().count(), 3);
+        drop(tx);
+        assert_eq!(g.triples().count(), 1);
+        assert_eq!(g.triples_matching(Any, [p], Any).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn light_graph() -> Result<(), Box<dyn std::error::Error>> {
+        check_graph::<LightGraph>()
+    }
+
+    #[test]
+    fn fast_graph() -> Result<(), Box<dyn std::error::Error>> {
+        check_graph::<FastGraph>()
+    }
+
+    fn check_dataset<D>() -> Result<(), Box<dyn std::error::Error>>
+    where
+        D: TransactionalDataset + MutableDataset + Default,
+        D::KeyError: From<D::Error>,
+    {
+        let ex = Namespace::new_unchecked("http://example.org/");
+        let [a, b, p, g1] = ["a", "b", "p", "g1"].map(|n| ex.get(n).unwrap());
+        let mut d = D::default();
+        d.insert(a, p, b, None as Option<&SimpleTerm>)?;
+
+        let mut tx = d.begin();
+        tx.insert(a, p, b, Some(g1))?;
+        tx.remove(a, p, b, None as Option<&SimpleTerm>)?;
+        tx.commit();
+        assert!(d.contains(a, p, b, Some(g1))?);
+        assert_eq!(d.quads().count(), 1);
+
+        let mut tx = d.begin();
+        tx.remove_matching(Any, Any, Any, Any)?;
+        tx.insert(b, p, a, None as Option<&SimpleTerm>)?;
+        assert_eq!(tx.quads().count(), 1);
+        drop(tx);
+        assert!(d.contains(a, p, b, Some(g1))?);
+        assert_eq!(d.quads().count(), 1);
+        assert_eq!(d.quads_matching(Any, Any, Any, [Some(g1)]).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn light_dataset() -> Result<(), Box<dyn std::error::Error>> {
+        check_dataset::<LightDataset>()
+    }
+
+    #[test]
+    fn fast_dataset() -> Result<(), Box<dyn std::error::Error>> {
+        check_dataset::<FastDataset>()
+    }
+}
@@ -44,6 +44,37 @@ impl<T: Deref<Target = str>> BaseIri<T> {
     ) -> R::OutputAbs {
         R::output_abs(self.0.resolve_into(iri.borrow(), buf).map(|_| &buf[..]))
     }
+
+    /// Computes a relative IRI reference which, resolved against this `BaseIri`,
+    /// gives back exactly `iri`.
+    ///
+    /// Returns `None` if `iri` does not share the scheme and authority of this `BaseIri`,
+    /// or if no such relative IRI reference exists
+    /// (e.g. because the path of `iri` contains `.` or `..` segments).
+    ///
+    /// ```
+    /// # use sophia_iri::{Iri, resolve::BaseIri};
+    /// let base = BaseIri::new("https://example.org/people/alice").unwrap();
+    /// let iri = Iri::new("https://example.org/people/alice#me").unwrap();
+    /// assert_eq!(base.relativize(iri).unwrap().as_str(), "#me");
+    /// let iri = Iri::new("https://example.org/people/bob").unwrap();
+    /// assert_eq!(base.relativize(iri).unwrap().as_str(), "bob");
+    /// let iri = Iri::new("https://example.com/").unwrap();
+    /// assert!(base.relativize(iri).is_none());
+    /// ```
+    pub fn relativize<U: IsIri>(&self, iri: U) -> Option<IriRef<String>> {
+        let abs = Oxiri::parse(iri.borrow()).ok()?;
+        let rel = self.0.relativize(&abs).ok()?;
+        if rel.scheme().is_some() || rel.authority().is_some() {
+            return None;
+        }
+        let rel = rel.into_inner();
+        // guard against any discrepancy between relativization and resolution
+        if self.0.resolve(&rel).ok()?.as_str() != abs.as_str() {
+            return None;
+        }
+        Some(IriRef::new_unchecked(rel))
+    }
 }
 
 impl<T: Deref<Target = str>> Borrow<str> for BaseIri<T> {
@@ -259,6 +290,59 @@ mod test {
         }
     }
 
+    #[test]
+    fn relativize() {
+        let base = BaseIri::new("http://a/b/c/d;p?q").unwrap();
+        for (_, abs) in RELATIVE_IRIS {
+            let abs = Iri::new(*abs).unwrap();
+            if let Some(rel) = base.relativize(abs) {
+                assert!(Iri::new(rel.as_str()).is_err(), "<{abs}> → <{rel}>");
+                assert_eq!(base.resolve(rel.as_ref()), abs, "<{abs}> → <{rel}>");
+            } else {
+                let parsed = abs.as_base();
+                assert!(
+                    parsed.scheme() != "http" || parsed.authority() != Some("a"),
+                    "<{abs}> → None"
+                );
+            }
+        }
+        for (abs, parsed) in POSITIVE_IRIS {
+            if !parsed.0 {
+                continue;
+            }
+            let abs = Iri::new(*abs).unwrap();
+            let base = abs.as_base();
+            for (other, parsed) in POSITIVE_IRIS {
+                if !parsed.0 {
+                    continue;
+                }
+                let other = Iri::new(*other).unwrap();
+                if let Some(rel) = base.relativize(other) {
+                    assert_eq!(base.resolve(rel.as_ref()), other, "<{other}> → <{rel}>");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn relativize_examples() {
+        let base = BaseIri::new("https://example.org/people/alice").unwrap();
+        for (abs, rel) in [
+            ("https://example.org/people/alice", Some("")),
+            ("https://example.org/people/alice#me", Some("#me")),
+            ("https://example.org/people/alice?x=1", Some("?x=1")),
+            ("https://example.org/people/bob#me", Some("bob#me")),
+            ("https://example.org/people/", Some(".")),
+            ("https://example.org/groups/admin", Some("/groups/admin")),
+            ("https://example.org/people/../x", None),
+            ("https://example.com/people/bob", None),
+            ("http://example.org/people/bob", None),
+        ] {
+            let got = base.relativize(Iri::new(abs).unwrap());
+            assert_eq!(got.as_ref().map(IriRef::as_str), rel, "<{abs}>");
+        }
+    }
+
     #[test]
     fn resolve_bad_str() {
         let base1 = BaseIriRef::new("http://a/b/c/d;p?q").unwrap();
@@ -13,7 +13,10 @@ use std::cmp::{Ordering, PartialOrd};
 pub struct IsoTerm<T>(pub(crate) T);
 
 impl<T: Term> Term for IsoTerm<T> {
-    type BorrowTerm<'x> = IsoTerm<T::BorrowTerm<'x>> where T: 'x;
+    type BorrowTerm<'x>
+        = IsoTerm<T::BorrowTerm<'x>>
+    where
+        T: 'x;
 
     fn kind(&self) -> TermKind {
         self.0.kind()
@@ -88,12 +91,7 @@ where
     T2: Term,
 {
     fn eq(&self, other: &IsoTerm<T1>) -> bool {
-        use TermKind::BlankNode;
-        if self.kind() == BlankNode && other.kind() == BlankNode {
-            true
-        } else {
-            Term::eq(&self.0, other.0.borrow_term())
-        }
+        iso_cmp(&self.0, &other.0) == Ordering::Equal
     }
 }
 
@@ -105,23 +103,33 @@ where
     T2: Term,
 {
     fn partial_cmp(&self, other: &IsoTerm<T1>) -> Option<Ordering> {
-        use TermKind::BlankNode;
-        if self.kind() == BlankNode && other.kind() == BlankNode {
-            Some(Ordering::Equal)
-        } else {
-            Some(Term::cmp(&self.0, other.0.borrow_term()))
-        }
+        Some(iso_cmp(&self.0, &other.0))
     }
 }
 
 impl<T: Term> Ord for IsoTerm<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        use TermKind::BlankNode;
-        if self.kind() == BlankNode && other.kind() == BlankNode {
-            Ordering::Equal
-        } else {
-            Term::cmp(&self.0, other.0.borrow_term())
+        iso_cmp(&self.0, &other.0)
+    }
+}
+
+/// Compare two terms, considering all blank nodes equal,
+/// including those nested in triple terms.
+fn iso_cmp<T1: Term, T2: Term>(t1: &T1, t2: &T2) -> Ordering {
+    use TermKind::{BlankNode, Triple};
+    match (t1.kind(), t2.kind()) {
+        (BlankNode, BlankNode) => Ordering::Equal,
+        (Triple, Triple) => {
+            let (Some(tr1), Some(tr2)) = (t1.triple(), t2.triple()) else {
+                unreachable!()
+            };
+            tr1.iter()
+                .zip(tr2.iter())
+                .map(|(c1, c2)| iso_cmp(c1, c2))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
         }
+        _ => Term::cmp(t1, t2.borrow_term()),
     }
 }
 
@@ -143,6 +143,21 @@ fn quoted_triple() -> Result<(), Box<dyn Error>> {
     ];
     assert!(!isomorphic_datasets(&d1, &d4)?);
     assert!(!isomorphic_datasets(&d4, &d1)?);
+
+    // same as d1, with blank nodes renamed
+    let d5 = vec![
+        ([E, D, C], None),
+        ([MyTerm::Triple("e d c"), B, A], Some(E)),
+    ];
+    assert!(isomorphic_datasets(&d1, &d5)?);
+    assert!(isomorphic_datasets(&d5, &d1)?);
+
+    let d6 = vec![
+        ([E, D, C], None),
+        ([MyTerm::Triple("e c d"), B, A], Some(E)),
+    ];
+    assert!(!isomorphic_datasets(&d1, &d6)?);
+    assert!(!isomorphic_datasets(&d6, &d1)?);
     Ok(())
 }
 
@@ -330,7 +345,10 @@ enum MyTerm {
 }
 
 impl Term for MyTerm {
-    type BorrowTerm<'x> = Self where Self: 'x;
+    type BorrowTerm<'x>
+        = Self
+    where
+        Self: 'x;
 
     fn kind(&self) -> TermKind {
         match self {
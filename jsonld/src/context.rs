@@ -8,6 +8,7 @@ use json_ld::{
 };
 use json_syntax::Parse;
 use locspan::{Location, Span};
+use sophia_api::prefix::PrefixMap;
 use sophia_iri::Iri;
 
 use crate::vocabulary::ArcIri;
@@ -54,3 +55,20 @@ impl TryIntoContextRef for &str {
         Ok(RemoteDocumentReference::Loaded(rdoc))
     }
 }
+
+/// Build a context mapping each prefix of `prefix_map` to its namespace,
+/// e.g. to be used as a [compact context](crate::JsonLdOptions::with_compact_context).
+///
+/// NB: the empty prefix is ignored, as it is not a valid JSON-LD term.
+pub fn context_from_prefix_map<P: PrefixMap + ?Sized>(prefix_map: &P) -> ContextRef {
+    // neither prefixes nor IRIs can contain characters that need escaping in JSON
+    let entries: Vec<_> = prefix_map
+        .iter()
+        .filter(|(prefix, _)| !prefix.as_str().is_empty())
+        .map(|(prefix, ns)| format!("\"{}\": \"{}\"", prefix.as_str(), ns.as_str()))
+        .collect();
+    let json = format!("{{\"@context\": {{{}}}}}", entries.join(", "));
+    json.as_str()
+        .try_into_context_ref()
+        .expect("a context built from a prefix map should be valid")
+}
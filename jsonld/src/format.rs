@@ -0,0 +1,33 @@
+//! The JSON-LD [format](Format), for use in a [`FormatRegistry`].
+
+use crate::{JsonLdOptions, JsonLdParser, JsonLdSerializer};
+use sophia_api::format::{box_quads, serializer_error, Format, FormatRegistry};
+use sophia_api::parser::QuadParser;
+use sophia_api::serializer::QuadSerializer;
+use std::sync::Arc;
+
+/// The [JSON-LD](https://www.w3.org/TR/json-ld11/) format.
+///
+/// Its parser uses no document loader,
+/// so remote contexts are not supported.
+pub const JSON_LD: Format = Format {
+    name: "JSON-LD",
+    media_types: &["application/ld+json"],
+    extensions: &["jsonld"],
+    dataset: true,
+    parser: Some(|read, base| {
+        let mut options = JsonLdOptions::new();
+        if let Some(base) = base {
+            options = options.with_base(base.map_unchecked(Arc::from));
+        }
+        box_quads(JsonLdParser::new_with_options(options).parse(read))
+    }),
+    serializer: Some(|write, quads| {
+        serializer_error(JsonLdSerializer::new(write).serialize_quads(quads))
+    }),
+};
+
+/// Add all the formats of this crate to `registry`.
+pub fn register(registry: &mut FormatRegistry) {
+    registry.register(JSON_LD);
+}
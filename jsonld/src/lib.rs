@@ -17,6 +17,7 @@ pub mod options;
 pub use options::*;
 pub mod error;
 pub use error::*;
+pub mod format;
 pub mod loader;
 pub mod loader_factory;
 pub mod parser;
@@ -5,7 +5,7 @@ use std::{io::BufRead, sync::Arc};
 use json_ld::{JsonLdProcessor, RemoteDocument, ToRdfError};
 use json_syntax::{Parse, Value};
 use locspan::{Location, Span};
-use sophia_api::{prelude::QuadParser, quad::Spog};
+use sophia_api::prelude::QuadParser;
 use sophia_iri::Iri;
 
 use crate::{
@@ -19,6 +19,9 @@ mod adapter;
 use adapter::convert_quad;
 pub use adapter::RdfTerm;
 
+mod declarations;
+use declarations::declarations;
+
 mod source;
 pub use source::JsonLdQuadSource;
 
@@ -82,17 +85,18 @@ impl<LF> JsonLdParser<LF> {
         let mut loader = self.options.document_loader();
         let mut vocab = ArcVoc {};
         let options = self.options.inner().clone();
+        let base = self
+            .options
+            .base()
+            .map(|iri| iri.map_unchecked(String::from));
         match data
             .to_rdf_with_using(&mut vocab, &mut generator, &mut loader, options)
             .await
         {
             Err(ToRdfError::Expand(err)) => JsonLdQuadSource::from_err(err),
-            Ok(mut to_rdf) => JsonLdQuadSource::Quads(
-                to_rdf
-                    .cloned_quads()
-                    .map(convert_quad)
-                    .collect::<Vec<Spog<RdfTerm>>>()
-                    .into_iter(),
+            Ok(mut to_rdf) => JsonLdQuadSource::from_quads(
+                to_rdf.cloned_quads().map(convert_quad).collect(),
+                declarations(data.document().value(), base),
             ),
         }
     }
@@ -0,0 +1,119 @@
+//! Extraction of the prefixes and base IRI declared in the top-level context of a JSON-LD document.
+
+use json_syntax::Value;
+use sophia_api::prefix::{Prefix, PrefixMapPair};
+use sophia_iri::{resolve::BaseIri, Iri};
+
+/// Characters ending the IRI of a simple term definition that can be used as a prefix.
+///
+/// See <https://www.w3.org/TR/json-ld11/#dfn-prefix>.
+const GEN_DELIMS: [char; 7] = [':', '/', '?', '#', '[', ']', '@'];
+
+/// Compute the prefixes and base IRI declared in the top-level context(s) of `doc`,
+/// `base` being the base IRI of the document.
+///
+/// Terms are retained as prefixes if
+/// they are valid Turtle prefixes,
+/// their definition is an absolute IRI (or a compact IRI using a previously defined prefix), and
+/// either their definition is a simple string ending with a gen-delim character,
+/// or it is an expanded definition with `"@prefix": true`.
+/// Remote and scoped contexts are ignored.
+pub(crate) fn declarations<M>(
+    doc: &Value<M>,
+    mut base: Option<Iri<String>>,
+) -> (Vec<PrefixMapPair>, Option<Iri<String>>) {
+    let mut prefix_map = vec![];
+    let nodes = match doc.as_array() {
+        Some(items) => items.iter().map(|item| &**item).collect(),
+        None => vec![doc],
+    };
+    for node in nodes {
+        let Some(obj) = node.as_object() else {
+            continue;
+        };
+        for context in obj.get("@context") {
+            process_context(context, &mut prefix_map, &mut base);
+        }
+    }
+    (prefix_map, base)
+}
+
+fn process_context<M>(
+    context: &Value<M>,
+    prefix_map: &mut Vec<PrefixMapPair>,
+    base: &mut Option<Iri<String>>,
+) {
+    match context {
+        Value::Null => prefix_map.clear(),
+        Value::Array(items) => {
+            for item in items {
+                process_context(item, prefix_map, base);
+            }
+        }
+        Value::Object(definitions) => {
+            for entry in definitions.iter() {
+                let term = entry.key.as_str();
+                let definition: &Value<M> = &entry.value;
+                if term == "@base" {
+                    match definition {
+                        Value::Null => *base = None,
+                        Value::String(iri) => {
+                            if let Some(iri) = resolve(base.as_ref(), iri.as_str()) {
+                                *base = Some(iri);
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if !term.starts_with('@') {
+                    process_definition(term, definition, prefix_map);
+                }
+            }
+        }
+        // remote contexts are ignored
+        _ => {}
+    }
+}
+
+fn process_definition<M>(term: &str, definition: &Value<M>, prefix_map: &mut Vec<PrefixMapPair>) {
+    let ns = match definition {
+        Value::String(id) if id.ends_with(GEN_DELIMS) => Some(id.as_str()),
+        Value::Object(def) => def
+            .get("@prefix")
+            .next()
+            .and_then(|flag| flag.as_boolean())
+            .filter(|flag| *flag)
+            .and(def.get("@id").next())
+            .and_then(|id| id.as_str()),
+        _ => None,
+    }
+    .and_then(|ns| expand(ns, prefix_map));
+    let pos = prefix_map.iter().position(|(p, _)| p.as_str() == term);
+    match (ns, pos, Prefix::new(Box::from(term))) {
+        (Some(ns), Some(pos), _) => prefix_map[pos].1 = ns,
+        (Some(ns), None, Ok(prefix)) => prefix_map.push((prefix, ns)),
+        // the term is redefined as something else than a prefix
+        (None, Some(pos), _) => {
+            prefix_map.remove(pos);
+        }
+        _ => {}
+    }
+}
+
+/// Expand `iri` into an absolute IRI, if it is not one already, using the prefixes defined so far.
+fn expand(iri: &str, prefix_map: &[PrefixMapPair]) -> Option<Iri<Box<str>>> {
+    if let Some((prefix, suffix)) = iri.split_once(':') {
+        if let Some((_, ns)) = prefix_map.iter().find(|(p, _)| p.as_str() == prefix) {
+            return Iri::new(format!("{}{suffix}", ns.as_str()).into_boxed_str()).ok();
+        }
+    }
+    Iri::new(Box::from(iri)).ok()
+}
+
+fn resolve(base: Option<&Iri<String>>, iri: &str) -> Option<Iri<String>> {
+    match base {
+        Some(base) => BaseIri::new(base.as_str())
+            .and_then(|base| base.resolve(iri))
+            .ok(),
+        None => Iri::new(iri.to_string()).ok(),
+    }
+}
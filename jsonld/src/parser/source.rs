@@ -1,4 +1,5 @@
 use sophia_api::{
+    prefix::PrefixMapPair,
     quad::Spog,
     source::{
         Source,
@@ -6,6 +7,7 @@ use sophia_api::{
         StreamResult,
     },
 };
+use sophia_iri::Iri;
 
 use crate::JsonLdError;
 
@@ -13,16 +15,48 @@ use super::adapter::RdfTerm;
 
 /// The type of [`QuadSource`](sophia_api::source::QuadSource)
 /// returned by [`JsonLdParser`](super::JsonLdParser).
-pub enum JsonLdQuadSource {
-    /// Some quads were parsed
-    Quads(std::vec::IntoIter<Spog<RdfTerm>>),
-    /// An error was raised while parsing
-    Err(Option<JsonLdError>),
+pub struct JsonLdQuadSource {
+    quads: std::vec::IntoIter<Spog<RdfTerm>>,
+    error: Option<JsonLdError>,
+    prefix_map: Vec<PrefixMapPair>,
+    base: Option<Iri<String>>,
 }
 
 impl JsonLdQuadSource {
     pub(crate) fn from_err<E: Into<JsonLdError>>(err: E) -> Self {
-        JsonLdQuadSource::Err(Some(err.into()))
+        JsonLdQuadSource {
+            quads: vec![].into_iter(),
+            error: Some(err.into()),
+            prefix_map: vec![],
+            base: None,
+        }
+    }
+
+    pub(crate) fn from_quads(
+        quads: Vec<Spog<RdfTerm>>,
+        (prefix_map, base): (Vec<PrefixMapPair>, Option<Iri<String>>),
+    ) -> Self {
+        JsonLdQuadSource {
+            quads: quads.into_iter(),
+            error: None,
+            prefix_map,
+            base,
+        }
+    }
+
+    /// The prefixes declared in the top-level context of the parsed document,
+    /// suitable for `TurtleConfig::with_own_prefix_map` in `sophia_turtle`.
+    ///
+    /// Only the terms usable as prefixes in JSON-LD
+    /// (and whose name is a valid Turtle prefix) are retained.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        self.prefix_map.clone()
+    }
+
+    /// The base IRI declared in the top-level context of the parsed document,
+    /// or the one provided in the parser options.
+    pub fn base(&self) -> Option<Iri<String>> {
+        self.base.clone()
     }
 }
 
@@ -36,21 +70,12 @@ impl Source for JsonLdQuadSource {
         E: std::error::Error,
         F: FnMut(Self::Item<'_>) -> Result<(), E>,
     {
-        match self {
-            JsonLdQuadSource::Quads(quads) => {
-                if let Some(quad) = quads.next() {
-                    f(quad).map(|_| true).map_err(SinkError)
-                } else {
-                    Ok(false)
-                }
-            }
-            JsonLdQuadSource::Err(opt) => {
-                if let Some(err) = opt.take() {
-                    Err(SourceError(err))
-                } else {
-                    Ok(false)
-                }
-            }
+        if let Some(err) = self.error.take() {
+            return Err(SourceError(err));
+        }
+        match self.quads.next() {
+            Some(quad) => f(quad).map(|_| true).map_err(SinkError),
+            None => Ok(false),
         }
     }
 }
@@ -117,4 +117,38 @@ fn check_chain_loader() {
     assert_eq!(got, exp);
 }
 
+#[test]
+fn prefixes_and_base() {
+    let p = JsonLdParser::new();
+    let src = p.parse_str(
+        r#"{
+            "@context": {
+                "@base": "http://example.org/base/",
+                "ex": "http://example.org/ns#",
+                "foaf": {"@id": "http://xmlns.com/foaf/0.1/", "@prefix": true},
+                "name": "ex:name",
+                "sub": "ex:sub/"
+            },
+            "@id": "me",
+            "name": "me"
+        }"#,
+    );
+    let prefixes: Vec<_> = src
+        .prefix_map()
+        .into_iter()
+        .map(|(p, ns)| format!("{}={}", p.as_str(), ns.as_str()))
+        .collect();
+    assert_eq!(
+        prefixes,
+        [
+            "ex=http://example.org/ns#",
+            "foaf=http://xmlns.com/foaf/0.1/",
+            "sub=http://example.org/ns#sub/",
+        ]
+    );
+    assert_eq!(src.base().unwrap().as_str(), "http://example.org/base/");
+    let got: TestDataset = src.collect_quads().unwrap();
+    assert_eq!(got.len(), 1);
+}
+
 type TestDataset = Vec<Spog<ArcTerm>>;
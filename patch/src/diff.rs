@@ -0,0 +1,247 @@
+//! Computing the difference between two datasets, as a [`Patch`].
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+
+use sophia_api::dataset::patch::{Patch, PatchOp};
+use sophia_api::dataset::Dataset;
+use sophia_api::graph::Graph;
+use sophia_api::quad::{Quad, Spog};
+use sophia_api::term::{BnodeId, FromTerm, SimpleTerm, Term};
+use sophia_c14n::rdfc10::relabel;
+use sophia_c14n::C14nError;
+use sophia_isomorphism::isomorphic_datasets;
+
+type SimpleQuad = Spog<SimpleTerm<'static>>;
+
+/// An error raised by [`diff`] or [`diff_graphs`].
+#[derive(Debug, thiserror::Error)]
+pub enum DiffError<E, F>
+where
+    E: std::error::Error + 'static,
+    F: std::error::Error + 'static,
+{
+    /// An error was raised while processing the old dataset
+    #[error("error while processing the old dataset: {0}")]
+    Old(#[source] C14nError<E>),
+    /// An error was raised while processing the new dataset
+    #[error("error while processing the new dataset: {0}")]
+    New(#[source] C14nError<F>),
+}
+
+/// Compute a [`Patch`] transforming `old` into `new`.
+///
+/// The patch consists of a single transaction,
+/// deleting the quads of `old` that are not in `new`,
+/// then adding the quads of `new` that are not in `old`.
+///
+/// # Blank nodes
+///
+/// Blank node identifiers are not significant,
+/// so quads containing blank nodes are not compared by identifier.
+/// If the quads containing blank nodes in `old` and `new` are [isomorphic](isomorphic_datasets),
+/// none of them is included in the patch.
+/// Otherwise, both sets of quads are [canonicalized](sophia_c14n::rdfc10),
+/// and compared in their canonical form.
+/// The deleted quads use the blank node identifiers of `old`,
+/// while the added quads use the identifiers of `old` for blank nodes that are shared with `old`,
+/// and fresh identifiers for new blank nodes,
+/// so that applying the patch to `old` yields a dataset isomorphic to `new`.
+pub fn diff<D1: Dataset, D2: Dataset>(
+    old: &D1,
+    new: &D2,
+) -> Result<Patch, DiffError<D1::Error, D2::Error>> {
+    let (old_ground, old_bnodes) = split(old).map_err(DiffError::Old)?;
+    let (new_ground, new_bnodes) = split(new).map_err(DiffError::New)?;
+
+    let mut deleted: Vec<SimpleQuad> = old_ground.difference(&new_ground).cloned().collect();
+    let mut added: Vec<SimpleQuad> = new_ground.difference(&old_ground).cloned().collect();
+
+    let unchanged = old_bnodes.is_empty() && new_bnodes.is_empty()
+        || isomorphic_datasets(&old_bnodes, &new_bnodes).unwrap_or(false);
+    if !unchanged {
+        let (old_c14n, old_map) = canonicalize(&old_bnodes).map_err(DiffError::Old)?;
+        let (new_c14n, _) = canonicalize(&new_bnodes).map_err(DiffError::New)?;
+        // canonical label -> label in old
+        let old_labels: BTreeMap<_, _> = old_map
+            .iter()
+            .map(|(orig, c14n)| (c14n.as_str().to_string(), orig.to_string()))
+            .collect();
+        let used: BTreeSet<_> = old_map.keys().map(|k| k.to_string()).collect();
+        let mut fresh = BTreeMap::new();
+        let mut counter = 0..;
+        let mut rename = |label: &str| -> String {
+            if let Some(orig) = old_labels.get(label) {
+                return orig.clone();
+            }
+            fresh
+                .entry(label.to_string())
+                .or_insert_with(|| {
+                    counter
+                        .by_ref()
+                        .map(|i| format!("n{i}"))
+                        .find(|l| !used.contains(l))
+                        .unwrap()
+                })
+                .clone()
+        };
+        for q in old_c14n.difference(&new_c14n) {
+            deleted.push(map_bnodes(q, &mut rename));
+        }
+        for q in new_c14n.difference(&old_c14n) {
+            added.push(map_bnodes(q, &mut rename));
+        }
+    }
+
+    let mut patch = Patch::new();
+    if deleted.is_empty() && added.is_empty() {
+        return Ok(patch);
+    }
+    patch.push(PatchOp::TxBegin);
+    patch.extend(deleted.into_iter().map(PatchOp::Delete));
+    patch.extend(added.into_iter().map(PatchOp::Add));
+    patch.push(PatchOp::TxCommit);
+    Ok(patch)
+}
+
+/// Compute a [`Patch`] transforming graph `old` into graph `new`.
+///
+/// All quads in the patch belong to the default graph.
+/// See [`diff`] for more details.
+pub fn diff_graphs<G1: Graph, G2: Graph>(
+    old: &G1,
+    new: &G2,
+) -> Result<Patch, DiffError<G1::Error, G2::Error>> {
+    diff(&old.as_dataset(), &new.as_dataset())
+}
+
+/// Copy the quads of `d`, split into ground quads and quads containing blank nodes.
+#[allow(clippy::type_complexity)]
+fn split<D: Dataset>(
+    d: &D,
+) -> Result<(BTreeSet<SimpleQuad>, BTreeSet<SimpleQuad>), C14nError<D::Error>> {
+    let mut ground = BTreeSet::new();
+    let mut bnodes = BTreeSet::new();
+    for q in d.quads() {
+        let q = q?;
+        let has_bnode = q.s().atoms().any(|t| t.is_blank_node())
+            || q.o().atoms().any(|t| t.is_blank_node())
+            || q.g().is_some_and(|g| g.is_blank_node());
+        let (spo, g) = q.to_spog();
+        let q = (spo.map(SimpleTerm::from_term), g.map(SimpleTerm::from_term));
+        if has_bnode {
+            bnodes.insert(q);
+        } else {
+            ground.insert(q);
+        }
+    }
+    Ok((ground, bnodes))
+}
+
+/// Canonicalize `quads`, and return them together with the mapping from original to canonical labels.
+#[allow(clippy::type_complexity)]
+fn canonicalize<E: std::error::Error>(
+    quads: &BTreeSet<SimpleQuad>,
+) -> Result<(BTreeSet<SimpleQuad>, sophia_c14n::rdfc10::C14nIdMap), C14nError<E>> {
+    let (c14n, map) = relabel(quads).map_err(convert_error)?;
+    let c14n = c14n
+        .into_iter()
+        .map(|(spo, g)| (spo.map(SimpleTerm::from_term), g.map(SimpleTerm::from_term)))
+        .collect();
+    Ok((c14n, map))
+}
+
+fn convert_error<E: std::error::Error>(err: C14nError<Infallible>) -> C14nError<E> {
+    match err {
+        C14nError::Dataset(never) => match never {},
+        C14nError::Io(err) => C14nError::Io(err),
+        C14nError::ToxicGraph(msg) => C14nError::ToxicGraph(msg),
+        C14nError::Unsupported(msg) => C14nError::Unsupported(msg),
+    }
+}
+
+/// Rename the blank nodes of `q` with `rename`.
+fn map_bnodes(q: &SimpleQuad, rename: &mut impl FnMut(&str) -> String) -> SimpleQuad {
+    let (spo, g) = q;
+    (
+        spo.clone().map(|t| map_term(t, rename)),
+        g.clone().map(|t| map_term(t, rename)),
+    )
+}
+
+fn map_term(
+    t: SimpleTerm<'static>,
+    rename: &mut impl FnMut(&str) -> String,
+) -> SimpleTerm<'static> {
+    match t {
+        SimpleTerm::BlankNode(b) => {
+            SimpleTerm::BlankNode(BnodeId::new_unchecked(rename(b.as_str()).into()))
+        }
+        SimpleTerm::Triple(spo) => {
+            SimpleTerm::Triple(Box::new((*spo).map(|t| map_term(t, rename))))
+        }
+        t => t,
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::MutableDataset;
+    use sophia_api::source::QuadSource;
+    use sophia_inmem::dataset::LightDataset;
+    use sophia_turtle::parser::trig;
+
+    fn dataset(txt: &str) -> LightDataset {
+        trig::parse_str(&format!("PREFIX : <http://example.org/> {txt}"))
+            .collect_quads()
+            .unwrap()
+    }
+
+    /// Check that patching `old` with its diff with `new` yields `new`,
+    /// and that the diff has the `expected` number of changes (if any).
+    fn check(old: &str, new: &str, expected: Option<usize>) {
+        let mut old = dataset(old);
+        let new = dataset(new);
+        let patch = diff(&old, &new).unwrap();
+        if let Some(expected) = expected {
+            let changes = patch.added().count() + patch.deleted().count();
+            assert_eq!(changes, expected, "{patch:?}");
+        }
+        old.apply_patch(&patch).unwrap();
+        assert!(isomorphic_datasets(&old, &new).unwrap(), "{patch:?}");
+    }
+
+    #[test]
+    fn ground() {
+        check(
+            ":a :p :b. :g { :a :p :c }",
+            ":a :p :b. :g { :a :p :d }",
+            Some(2),
+        );
+        check(":a :p :b.", ":a :p :b.", Some(0));
+    }
+
+    #[test]
+    fn isomorphic_bnodes() {
+        check(
+            ":a :p _:x. _:x :q _:y. :a :r 1.",
+            ":a :p _:u. _:u :q _:v. :a :r 2.",
+            Some(2),
+        );
+    }
+
+    #[test]
+    fn changed_bnodes() {
+        check(
+            ":a :p _:x. _:x :name \"x\". _:y :name \"y\".",
+            ":a :p _:x. _:x :name \"x\". _:z :name \"z\". _:z :knows _:x.",
+            None,
+        );
+        // blank nodes in named graphs and graph names
+        check("_:g { :a :p _:x }", "_:g { :a :p _:x. :a :q _:x }", None);
+    }
+}
@@ -0,0 +1,53 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides support for tracking changes between datasets,
+//! using the [RDF Patch] format:
+//! * [`diff`] and [`diff_graphs`] compute the [`Patch`] transforming a dataset (resp. graph) into another one;
+//! * the [`parser`] and [`serializer`] modules read and write patches in the RDF Patch text format.
+//!
+//! Patches can then be applied to any [`MutableDataset`] with [`MutableDataset::apply_patch`].
+//!
+//! # Example
+//! ```
+//! # use sophia_api::dataset::MutableDataset;
+//! # use sophia_api::serializer::Stringifier;
+//! # use sophia_api::source::QuadSource;
+//! # use sophia_inmem::dataset::LightDataset;
+//! # use sophia_turtle::parser::nq;
+//! # use sophia_patch::{diff, parser, serializer::PatchSerializer};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut v1: LightDataset = nq::parse_str(r#"
+//!     <https://example.org/s> <https://example.org/p> "old" .
+//! "#).collect_quads()?;
+//! let v2: LightDataset = nq::parse_str(r#"
+//!     <https://example.org/s> <https://example.org/p> "new" .
+//! "#).collect_quads()?;
+//!
+//! let patch = diff(&v1, &v2)?;
+//! let txt = PatchSerializer::new_stringifier().serialize_patch(&patch)?.to_string();
+//! assert_eq!(txt, r#"TX .
+//! D <https://example.org/s> <https://example.org/p> "old" .
+//! A <https://example.org/s> <https://example.org/p> "new" .
+//! TC .
+//! "#);
+//!
+//! v1.apply_patch(&parser::parse_str(&txt)?)?;
+//! assert!(sophia_isomorphism::isomorphic_datasets(&v1, &v2)?);
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [`MutableDataset`]: sophia_api::dataset::MutableDataset
+//! [`MutableDataset::apply_patch`]: sophia_api::dataset::MutableDataset::apply_patch
+#![deny(missing_docs)]
+
+mod diff;
+pub use diff::*;
+pub mod parser;
+pub mod serializer;
+
+pub use sophia_api::dataset::patch::{Patch, PatchOp};
@@ -0,0 +1,458 @@
+//! Parser for the [RDF Patch] format.
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+use sophia_api::dataset::patch::{Patch, PatchOp};
+use sophia_api::ns::xsd;
+use sophia_api::prefix::{Prefix, PrefixMap, PrefixMapPair};
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, SimpleTerm, Term};
+use sophia_iri::Iri;
+use std::io::BufRead;
+
+/// An error raised while parsing an RDF Patch.
+#[derive(Debug, thiserror::Error)]
+pub enum PatchParseError {
+    /// An IO error occurred while reading the patch
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The patch is not syntactically valid
+    #[error("syntax error at line {line}: {message}")]
+    Syntax {
+        /// The line where the error occurred (starting at 1)
+        line: usize,
+        /// A description of the error
+        message: String,
+    },
+}
+
+/// Parse an RDF Patch from a string.
+///
+/// Prefixed names are resolved using the prefixes declared (`PA`) earlier in the patch.
+pub fn parse_str(txt: &str) -> std::result::Result<Patch, PatchParseError> {
+    Parser {
+        txt,
+        pos: 0,
+        prefixes: vec![],
+    }
+    .parse()
+}
+
+/// Parse an RDF Patch from a [`BufRead`].
+///
+/// See also [`parse_str`].
+pub fn parse_bufread<B: BufRead>(mut bufread: B) -> std::result::Result<Patch, PatchParseError> {
+    let mut txt = String::new();
+    bufread.read_to_string(&mut txt)?;
+    parse_str(&txt)
+}
+
+type Result<T> = std::result::Result<T, PatchParseError>;
+
+struct Parser<'a> {
+    txt: &'a str,
+    pos: usize,
+    prefixes: Vec<PrefixMapPair>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> Result<Patch> {
+        let mut patch = Patch::new();
+        loop {
+            self.skip_ws();
+            if self.rest().is_empty() {
+                return Ok(patch);
+            }
+            let keyword = self.word();
+            let op = match keyword {
+                "H" => {
+                    self.skip_ws();
+                    let name = self.word();
+                    if name.is_empty() {
+                        return Err(self.err("expected a header name"));
+                    }
+                    PatchOp::Header(name.into(), self.term()?)
+                }
+                "TX" => PatchOp::TxBegin,
+                "TC" => PatchOp::TxCommit,
+                "TA" => PatchOp::TxAbort,
+                "PA" => {
+                    let prefix = self.prefix()?;
+                    self.skip_ws();
+                    if !self.rest().starts_with('<') {
+                        return Err(self.err("expected an IRI"));
+                    }
+                    let iri = Iri::new(self.iriref()?.into_boxed_str())
+                        .map_err(|e| self.err(e.to_string()))?;
+                    self.prefixes.retain(|(p, _)| p != &prefix);
+                    self.prefixes.push((prefix.clone(), iri.clone()));
+                    PatchOp::AddPrefix(prefix, iri)
+                }
+                "PD" => {
+                    let prefix = self.prefix()?;
+                    self.prefixes.retain(|(p, _)| p != &prefix);
+                    PatchOp::DeletePrefix(prefix)
+                }
+                "A" | "D" => {
+                    let spo = [self.term()?, self.term()?, self.term()?];
+                    self.skip_ws();
+                    let g = if self.rest().starts_with('.') || self.at_eol_or_eof() {
+                        None
+                    } else {
+                        Some(self.term()?)
+                    };
+                    if keyword == "A" {
+                        PatchOp::Add((spo, g))
+                    } else {
+                        PatchOp::Delete((spo, g))
+                    }
+                }
+                "" => return Err(self.err("expected a keyword")),
+                other => return Err(self.err(format!("unknown keyword {other:?}"))),
+            };
+            patch.push(op);
+            self.end_of_row()?;
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.txt[self.pos..]
+    }
+
+    fn line(&self) -> usize {
+        self.txt[..self.pos].matches('\n').count() + 1
+    }
+
+    fn err(&self, message: impl Into<String>) -> PatchParseError {
+        PatchParseError::Syntax {
+            line: self.line(),
+            message: message.into(),
+        }
+    }
+
+    /// Skip whitespaces and comments.
+    fn skip_ws(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Whether only spaces and comments remain before the end of the line.
+    fn at_eol_or_eof(&self) -> bool {
+        let rest = self.rest();
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let line = line.trim_start();
+        line.is_empty() || line.starts_with('#')
+    }
+
+    /// Consume the optional `.` terminating a row, and check that nothing else follows on the line.
+    fn end_of_row(&mut self) -> Result<()> {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches([' ', '\t', '\r']);
+        self.pos += rest.len() - trimmed.len();
+        if trimmed.starts_with('.') {
+            self.pos += 1;
+        }
+        if !self.at_eol_or_eof() {
+            return Err(self.err("expected end of row"));
+        }
+        Ok(())
+    }
+
+    /// Consume a sequence of non-space characters.
+    ///
+    /// A trailing `.` is not considered part of the word.
+    fn word(&mut self) -> &'a str {
+        let rest = self.rest();
+        let mut len = rest
+            .find(|c: char| c.is_whitespace() || c == '<' || c == '"' || c == '\'')
+            .unwrap_or(rest.len());
+        if len > 1 && rest[..len].ends_with('.') {
+            len -= 1;
+        }
+        self.pos += len;
+        &self.txt[self.pos - len..self.pos]
+    }
+
+    fn prefix(&mut self) -> Result<Prefix<Box<str>>> {
+        self.skip_ws();
+        let word = self.word();
+        let Some(prefix) = word.strip_suffix(':') else {
+            return Err(self.err(format!("expected a prefix, found {word:?}")));
+        };
+        let prefix = Box::from(prefix);
+        Prefix::new(prefix).map_err(|e| self.err(e.to_string()))
+    }
+
+    fn term(&mut self) -> Result<SimpleTerm<'static>> {
+        self.skip_ws();
+        let rest = self.rest();
+        if rest.starts_with("<<") {
+            // RDF 1.2 triple terms are written <<( ... )>>, RDF-star quoted triples << ... >>
+            let close = if rest.starts_with("<<(") { ")>>" } else { ">>" };
+            self.pos += close.len();
+            let triple = [self.term()?, self.term()?, self.term()?];
+            self.skip_ws();
+            if !self.rest().starts_with(close) {
+                return Err(self.err(format!("expected '{close}'")));
+            }
+            self.pos += close.len();
+            Ok(SimpleTerm::Triple(Box::new(triple)))
+        } else if rest.starts_with('<') {
+            let iri = self.iriref()?;
+            self.iri_term(iri)
+        } else if let Some(label) = rest.strip_prefix("_:") {
+            let len = label
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '<')
+                .unwrap_or(label.len());
+            let label = label[..len].trim_end_matches('.');
+            self.pos += 2 + label.len();
+            BnodeId::new(label.to_string().into())
+                .map(SimpleTerm::BlankNode)
+                .map_err(|e| self.err(e.to_string()))
+        } else if rest.starts_with(['"', '\'']) {
+            self.literal()
+        } else {
+            let word = self.word();
+            if word.is_empty() {
+                return Err(self.err("expected a term"));
+            }
+            if word == "true" || word == "false" {
+                return Ok(typed(word.to_string(), xsd::boolean.iri().unwrap()));
+            }
+            if word.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) {
+                return self.number(word.to_string());
+            }
+            let word = word.to_string();
+            self.prefixed_name(&word)
+        }
+    }
+
+    fn iri_term(&self, iri: String) -> Result<SimpleTerm<'static>> {
+        IriRef::new(iri.into())
+            .map(SimpleTerm::Iri)
+            .map_err(|e| self.err(e.to_string()))
+    }
+
+    /// Consume an IRI between angle brackets, and return it unescaped.
+    fn iriref(&mut self) -> Result<String> {
+        debug_assert!(self.rest().starts_with('<'));
+        self.pos += 1;
+        let mut iri = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '>' => {
+                    self.pos += i + 1;
+                    return Ok(iri);
+                }
+                '\\' => {
+                    let (n, c) = unescape_unicode(&self.rest()[i + 1..])
+                        .ok_or_else(|| self.err("invalid escape sequence in IRI"))?;
+                    iri.push(c);
+                    for _ in 0..n {
+                        chars.next();
+                    }
+                }
+                c if c.is_whitespace() => break,
+                c => iri.push(c),
+            }
+        }
+        Err(self.err("unterminated IRI"))
+    }
+
+    fn literal(&mut self) -> Result<SimpleTerm<'static>> {
+        let rest = self.rest();
+        let quote = &rest[..1];
+        let quote = if rest[1..].starts_with(&quote.repeat(2)) {
+            quote.repeat(3)
+        } else {
+            quote.to_string()
+        };
+        self.pos += quote.len();
+        let mut lex = String::new();
+        let mut chars = self.rest().char_indices();
+        let end = loop {
+            let Some((i, c)) = chars.next() else {
+                return Err(self.err("unterminated literal"));
+            };
+            if self.rest()[i..].starts_with(&quote) {
+                break i;
+            }
+            match c {
+                '\\' => {
+                    let (n, c) = unescape(&self.rest()[i + 1..])
+                        .ok_or_else(|| self.err("invalid escape sequence in literal"))?;
+                    lex.push(c);
+                    for _ in 0..n {
+                        chars.next();
+                    }
+                }
+                '\n' | '\r' if quote.len() == 1 => {
+                    return Err(self.err("unterminated literal"));
+                }
+                c => lex.push(c),
+            }
+        };
+        self.pos += end + quote.len();
+        let rest = self.rest();
+        if let Some(tag) = rest.strip_prefix('@') {
+            let len = tag
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .unwrap_or(tag.len());
+            self.pos += 1 + len;
+            let tag = LanguageTag::new(tag[..len].to_string().into())
+                .map_err(|e| self.err(e.to_string()))?;
+            Ok(SimpleTerm::LiteralLanguage(lex.into(), tag))
+        } else if rest.starts_with("^^") {
+            self.pos += 2;
+            let dt = match self.term()? {
+                SimpleTerm::Iri(dt) => dt,
+                _ => return Err(self.err("expected a datatype IRI")),
+            };
+            Ok(SimpleTerm::LiteralDatatype(lex.into(), dt))
+        } else {
+            Ok(typed(lex, xsd::string.iri().unwrap()))
+        }
+    }
+
+    fn number(&self, word: String) -> Result<SimpleTerm<'static>> {
+        let digits = word.trim_start_matches(['+', '-']);
+        let valid = digits.contains(|c: char| c.is_ascii_digit())
+            && digits
+                .chars()
+                .all(|c| c.is_ascii_digit() || ".eE+-".contains(c))
+            && digits.starts_with(|c: char| c.is_ascii_digit() || c == '.');
+        if !valid {
+            return Err(self.err(format!("invalid number {word:?}")));
+        }
+        let dt = if digits.contains(['e', 'E']) {
+            xsd::double
+        } else if digits.contains('.') {
+            xsd::decimal
+        } else {
+            xsd::integer
+        };
+        Ok(typed(word, dt.iri().unwrap()))
+    }
+
+    fn prefixed_name(&self, word: &str) -> Result<SimpleTerm<'static>> {
+        let Some((prefix, suffix)) = word.split_once(':') else {
+            return Err(self.err(format!("unexpected token {word:?}")));
+        };
+        let Some(ns) = self.prefixes.as_slice().get_namespace(prefix) else {
+            return Err(self.err(format!("undeclared prefix {prefix:?}")));
+        };
+        self.iri_term(format!("{}{suffix}", ns.as_str()))
+    }
+}
+
+fn typed(lex: String, dt: IriRef<sophia_api::MownStr<'_>>) -> SimpleTerm<'static> {
+    SimpleTerm::LiteralDatatype(lex.into(), dt.map_unchecked(|m| m.to_string().into()))
+}
+
+/// Unescape the escape sequence at the start of `txt` (just after the backslash),
+/// and return the number of consumed characters together with the unescaped character.
+fn unescape(txt: &str) -> Option<(usize, char)> {
+    let c = match txt.chars().next()? {
+        't' => '\t',
+        'b' => '\u{8}',
+        'n' => '\n',
+        'r' => '\r',
+        'f' => '\u{c}',
+        '"' => '"',
+        '\'' => '\'',
+        '\\' => '\\',
+        _ => return unescape_unicode(txt),
+    };
+    Some((1, c))
+}
+
+/// Unescape a `uXXXX` or `UXXXXXXXX` escape sequence at the start of `txt`.
+fn unescape_unicode(txt: &str) -> Option<(usize, char)> {
+    let len = match txt.chars().next()? {
+        'u' => 4,
+        'U' => 8,
+        _ => return None,
+    };
+    let hex = txt.get(1..=len)?;
+    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    Some((len + 1, c))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_patch() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let patch = parse_str(
+            r#"
+            # a comment
+            H id <uuid:0686c69d-8f89-4496-acb5-744f0157a8db> .
+            TX .
+            PA ex: <http://example.org/> .
+            A ex:s ex:p "hello\nworld"@en .
+            A _:b0 <http://example.org/p> 42 <http://example.org/g> .
+            D ex:s ex:p """long "quoted" text""" .
+            A << ex:s ex:p ex:o >> ex:since "2020"^^<http://www.w3.org/2001/XMLSchema#gYear> .
+            PD ex: .
+            TC .
+            TA
+            "#,
+        )?;
+        let ops = patch.ops();
+        assert_eq!(ops.len(), 10);
+        assert!(matches!(&ops[0], PatchOp::Header(name, _) if &**name == "id"));
+        assert_eq!(ops[1], PatchOp::TxBegin);
+        let PatchOp::Add(([s, _, o], None)) = &ops[3] else {
+            panic!("unexpected {:?}", ops[3]);
+        };
+        assert!(Term::eq(s, IriRef::new_unchecked("http://example.org/s")));
+        assert_eq!(o.lexical_form().unwrap(), "hello\nworld");
+        assert_eq!(o.language_tag().unwrap().as_str(), "en");
+        let PatchOp::Add(([s, _, o], Some(g))) = &ops[4] else {
+            panic!("unexpected {:?}", ops[4]);
+        };
+        assert!(s.is_blank_node());
+        assert!(Term::eq(o, 42));
+        assert!(Term::eq(g, IriRef::new_unchecked("http://example.org/g")));
+        let PatchOp::Delete(([_, _, o], None)) = &ops[5] else {
+            panic!("unexpected {:?}", ops[5]);
+        };
+        assert_eq!(o.lexical_form().unwrap(), r#"long "quoted" text"#);
+        let PatchOp::Add(([s, _, _], None)) = &ops[6] else {
+            panic!("unexpected {:?}", ops[6]);
+        };
+        assert!(s.is_triple());
+        assert!(matches!(&ops[7], PatchOp::DeletePrefix(p) if p.as_str() == "ex"));
+        assert_eq!(ops[9], PatchOp::TxAbort);
+        Ok(())
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for (txt, line) in [
+            ("X .", 1),
+            ("TX .\nA <tag:s> <tag:p> .", 2),
+            ("A ex:s ex:p ex:o .", 1),
+            ("PA ex: <tag:> .\nPD ex: .\nA ex:s ex:p ex:o .", 3),
+            ("A <tag:s> <tag:p> \"unterminated .", 1),
+            ("TX TC", 1),
+        ] {
+            match parse_str(txt) {
+                Err(PatchParseError::Syntax { line: l, .. }) => assert_eq!(l, line, "{txt}"),
+                other => panic!("unexpected {other:?} for {txt}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Serializer for the [RDF Patch] format.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [RDF Patch]: https://afs.github.io/rdf-delta/rdf-patch.html
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+use sophia_api::dataset::patch::{Patch, PatchOp};
+use sophia_api::serializer::Stringifier;
+use sophia_turtle::serializer::nt::write_term;
+use std::io;
+
+/// RDF Patch serializer.
+///
+/// Terms are always written in full (prefixed names are never used),
+/// so that the output does not depend on the prefixes declared in the patch.
+pub struct PatchSerializer<W> {
+    write: W,
+}
+
+impl<W> PatchSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new RDF Patch serializer writing to `write`.
+    #[inline]
+    pub fn new(write: W) -> PatchSerializer<W> {
+        PatchSerializer { write }
+    }
+
+    /// Serialize the given patch.
+    pub fn serialize_patch(&mut self, patch: &Patch) -> io::Result<&mut Self> {
+        for op in patch {
+            self.serialize_op(op)?;
+        }
+        Ok(self)
+    }
+
+    /// Serialize a single patch operation.
+    pub fn serialize_op(&mut self, op: &PatchOp) -> io::Result<&mut Self> {
+        let w = &mut self.write;
+        match op {
+            PatchOp::Header(name, value) => {
+                write!(w, "H {name} ")?;
+                write_term(w, value)?;
+            }
+            PatchOp::TxBegin => w.write_all(b"TX")?,
+            PatchOp::TxCommit => w.write_all(b"TC")?,
+            PatchOp::TxAbort => w.write_all(b"TA")?,
+            PatchOp::AddPrefix(prefix, iri) => {
+                write!(w, "PA {}: <{}>", prefix.as_str(), iri.as_str())?;
+            }
+            PatchOp::DeletePrefix(prefix) => write!(w, "PD {}:", prefix.as_str())?,
+            PatchOp::Add(([s, p, o], g)) | PatchOp::Delete(([s, p, o], g)) => {
+                w.write_all(if matches!(op, PatchOp::Add(_)) {
+                    b"A "
+                } else {
+                    b"D "
+                })?;
+                for t in [s, p, o] {
+                    write_term(w, t)?;
+                    w.write_all(b" ")?;
+                }
+                if let Some(g) = g {
+                    write_term(w, g)?;
+                    w.write_all(b" ")?;
+                }
+                w.write_all(b".\n")?;
+                return Ok(self);
+            }
+        }
+        w.write_all(b" .\n")?;
+        Ok(self)
+    }
+}
+
+impl PatchSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        PatchSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for PatchSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_str;
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let src = r#"H id <uuid:0686c69d-8f89-4496-acb5-744f0157a8db> .
+TX .
+PA ex: <http://example.org/> .
+A <http://example.org/s> <http://example.org/p> "hello\nworld"@en .
+A _:b0 <http://example.org/p> "42"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example.org/g> .
+D <<( <http://example.org/s> <http://example.org/p> "o" )>> <http://example.org/p> _:b0 .
+PD ex: .
+TC .
+"#;
+        let patch = parse_str(src)?;
+        let out = PatchSerializer::new_stringifier()
+            .serialize_patch(&patch)?
+            .to_string();
+        assert_eq!(out, src);
+        assert_eq!(parse_str(&out)?, patch);
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! I define the errors and inconsistencies reported by the reasoner.
+use std::error::Error;
+use std::fmt;
+
+use sophia_api::term::SimpleTerm;
+
+/// A contradiction found in the input by the reasoner.
+///
+/// Each variant corresponds to one of the OWL 2 RL rules
+/// whose conclusion is `false`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Inconsistency {
+    /// `instance` is a member of `owl:Nothing` (rule `cls-nothing2`).
+    Nothing {
+        /// The offending instance
+        instance: SimpleTerm<'static>,
+    },
+    /// `instance` is a member of two disjoint classes (rule `cax-dw`).
+    DisjointClasses {
+        /// The offending instance
+        instance: SimpleTerm<'static>,
+        /// The two disjoint classes
+        classes: [SimpleTerm<'static>; 2],
+    },
+    /// Two terms are declared both `owl:sameAs` and `owl:differentFrom` (rule `eq-diff1`).
+    SameAndDifferent {
+        /// The two terms
+        terms: [SimpleTerm<'static>; 2],
+    },
+    /// A term is related to itself by an `owl:IrreflexiveProperty` (rule `prp-irp`).
+    IrreflexiveProperty {
+        /// The offending term
+        instance: SimpleTerm<'static>,
+        /// The irreflexive property
+        property: SimpleTerm<'static>,
+    },
+    /// Two terms are related in both directions by an `owl:AsymmetricProperty` (rule `prp-asyp`).
+    AsymmetricProperty {
+        /// The two terms
+        terms: [SimpleTerm<'static>; 2],
+        /// The asymmetric property
+        property: SimpleTerm<'static>,
+    },
+    /// Two terms are related by two disjoint properties (rule `prp-pdw`).
+    DisjointProperties {
+        /// The two terms
+        terms: [SimpleTerm<'static>; 2],
+        /// The two disjoint properties
+        properties: [SimpleTerm<'static>; 2],
+    },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Inconsistency::*;
+        match self {
+            Nothing { instance } => write!(f, "{instance:?} is a member of owl:Nothing"),
+            DisjointClasses {
+                instance,
+                classes: [c1, c2],
+            } => write!(
+                f,
+                "{instance:?} is a member of disjoint classes {c1:?} and {c2:?}"
+            ),
+            SameAndDifferent { terms: [t1, t2] } => {
+                write!(f, "{t1:?} and {t2:?} are both the same and different")
+            }
+            IrreflexiveProperty { instance, property } => write!(
+                f,
+                "{instance:?} is related to itself by irreflexive property {property:?}"
+            ),
+            AsymmetricProperty {
+                terms: [t1, t2],
+                property,
+            } => write!(
+                f,
+                "{t1:?} and {t2:?} are related both ways by asymmetric property {property:?}"
+            ),
+            DisjointProperties {
+                terms: [t1, t2],
+                properties: [p1, p2],
+            } => write!(
+                f,
+                "{t1:?} and {t2:?} are related by disjoint properties {p1:?} and {p2:?}"
+            ),
+        }
+    }
+}
+
+/// An error raised by [`materialize`](crate::materialize) or [`materialize_dataset`](crate::materialize_dataset).
+#[derive(Debug, thiserror::Error)]
+pub enum ReasonerError<E, F>
+where
+    E: Error + 'static,
+    F: Error + 'static,
+{
+    /// An error was raised while reading the input
+    #[error("error while reading the input: {0}")]
+    Source(#[source] E),
+    /// An error was raised while inserting the entailments
+    #[error("error while inserting entailments: {0}")]
+    Sink(#[source] F),
+    /// The input is inconsistent; nothing was inserted
+    #[error("the input is inconsistent: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Inconsistent(Vec<Inconsistency>),
+}
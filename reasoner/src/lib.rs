@@ -0,0 +1,205 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides a forward-chaining reasoner for (a large subset of) the [OWL 2 RL] profile,
+//! which materializes the entailments of any [`Graph`] or [`Dataset`]
+//! into any [`MutableGraph`] or [`MutableDataset`].
+//!
+//! The rules are evaluated semi-naively:
+//! each triple (asserted or inferred) is joined exactly once with the other triples known so far,
+//! until no new triple can be inferred.
+//!
+//! # Supported rules
+//!
+//! The following rules of the [OWL 2 RL rule set] are supported:
+//! * equality: `eq-sym`, `eq-trans`, `eq-rep-s`, `eq-rep-p`, `eq-rep-o`, `eq-diff1`;
+//! * properties: `prp-dom`, `prp-rng`, `prp-fp`, `prp-ifp`, `prp-irp`, `prp-symp`, `prp-asyp`, `prp-trp`,
+//!   `prp-spo1`, `prp-spo2`, `prp-eqp1`, `prp-eqp2`, `prp-pdw`, `prp-inv1`, `prp-inv2`;
+//! * classes: `cls-nothing2`, `cls-int1`, `cls-int2`, `cls-uni`, `cls-svf1`, `cls-svf2`, `cls-avf`,
+//!   `cls-hv1`, `cls-hv2`;
+//! * class axioms: `cax-sco`, `cax-eqc1`, `cax-eqc2`, `cax-dw`;
+//! * schema: `scm-sco`, `scm-eqc1`, `scm-eqc2`, `scm-spo`, `scm-eqp1`, `scm-eqp2`,
+//!   `scm-dom1`, `scm-dom2`, `scm-rng1`, `scm-rng2`, `scm-int`, `scm-uni`.
+//!
+//! Rules producing triples that hold in any graph (`eq-ref`, `scm-cls`, `scm-op`, `scm-dp`...)
+//! and rules about datatypes, cardinalities, keys and enumerations are not supported.
+//! Axioms involving RDF lists (`owl:propertyChainAxiom`, `owl:intersectionOf`, `owl:unionOf`)
+//! and property restrictions are read from the input only (not from inferred triples).
+//!
+//! Rules whose conclusion is `false` are reported as [`Inconsistency`]s.
+//!
+//! # Example
+//! ```
+//! # use sophia_api::graph::Graph;
+//! # use sophia_api::source::TripleSource;
+//! # use sophia_inmem::graph::FastGraph;
+//! # use sophia_turtle::parser::turtle;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut graph: FastGraph = turtle::parse_str(r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     PREFIX owl: <http://www.w3.org/2002/07/owl#>
+//!     PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+//!     :ancestor a owl:TransitiveProperty.
+//!     :parent owl:inverseOf :child; rdfs:subPropertyOf :ancestor.
+//!     :alice :parent :bob.
+//!     :bob :parent :charlie.
+//! "#).collect_triples()?;
+//!
+//! // :bob :child :alice, :charlie :child :bob,
+//! // :alice :ancestor :bob, :charlie, and :bob :ancestor :charlie
+//! assert_eq!(sophia_reasoner::materialize(&mut graph)?, 5);
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+//! [OWL 2 RL]: https://www.w3.org/TR/owl2-profiles/#OWL_2_RL
+//! [OWL 2 RL rule set]: https://www.w3.org/TR/owl2-profiles/#Reasoning_in_OWL_2_RL_and_RDF_Graphs_using_Rules
+#![deny(missing_docs)]
+
+use sophia_api::dataset::{Dataset, MutableDataset};
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::quad::Quad;
+use sophia_api::term::{GraphName, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+
+mod error;
+pub use error::*;
+mod rules;
+mod store;
+
+use rules::Engine;
+use store::Store;
+
+/// The result of reasoning over a graph or a dataset.
+///
+/// It holds the inferred triples in memory,
+/// and can insert them into any [`MutableGraph`] or [`MutableDataset`].
+#[derive(Clone, Debug)]
+pub struct Reasoner {
+    engine: Engine,
+    inconsistencies: Vec<Inconsistency>,
+}
+
+impl Reasoner {
+    /// Compute the entailments of the given graph.
+    pub fn from_graph<G: Graph>(graph: &G) -> Result<Self, G::Error> {
+        let mut store = Store::default();
+        for t in graph.triples() {
+            let fact = t?.to_spo().map(|t| store.intern(t));
+            store.insert(fact);
+        }
+        Ok(Self::run(store))
+    }
+
+    /// Compute the entailments of the union of all the graphs (default and named) of the given dataset.
+    pub fn from_dataset<D: Dataset>(dataset: &D) -> Result<Self, D::Error> {
+        let mut store = Store::default();
+        for q in dataset.quads() {
+            let fact = q?.to_spog().0.map(|t| store.intern(t));
+            store.insert(fact);
+        }
+        Ok(Self::run(store))
+    }
+
+    fn run(store: Store) -> Self {
+        let engine = Engine::run(store);
+        let inconsistencies = engine.inconsistencies();
+        Reasoner {
+            engine,
+            inconsistencies,
+        }
+    }
+
+    /// The inconsistencies found in the input.
+    pub fn inconsistencies(&self) -> &[Inconsistency] {
+        &self.inconsistencies
+    }
+
+    /// Whether no inconsistency was found in the input.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+
+    /// An iterator over the inferred triples (i.e. the entailed triples that are not in the input).
+    pub fn inferred_triples(&self) -> impl Iterator<Item = [&SimpleTerm<'static>; 3]> + '_ {
+        self.engine.inferred()
+    }
+
+    /// The number of inferred triples.
+    pub fn inferred_count(&self) -> usize {
+        self.engine.inferred_count()
+    }
+
+    /// Insert all inferred triples into `graph`.
+    ///
+    /// Return the number of triples actually inserted.
+    ///
+    /// NB: this inserts the inferred triples even if the input is inconsistent.
+    pub fn insert_into<G: MutableGraph>(&self, graph: &mut G) -> Result<usize, G::MutationError> {
+        let mut count = 0;
+        for [s, p, o] in self.inferred_triples() {
+            if graph.insert(s, p, o)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Insert all inferred triples into the given graph of `dataset`.
+    ///
+    /// Return the number of quads actually inserted.
+    ///
+    /// NB: this inserts the inferred triples even if the input is inconsistent.
+    pub fn insert_into_dataset<D: MutableDataset, T: Term>(
+        &self,
+        dataset: &mut D,
+        graph_name: GraphName<T>,
+    ) -> Result<usize, D::MutationError> {
+        let g = graph_name.as_ref().map(Term::borrow_term);
+        let mut count = 0;
+        for [s, p, o] in self.inferred_triples() {
+            if dataset.insert(s, p, o, g)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Materialize all the entailments of `graph` into itself.
+///
+/// Return the number of inserted triples.
+/// If the graph is inconsistent, it is left unchanged,
+/// and the inconsistencies are returned as a [`ReasonerError::Inconsistent`].
+pub fn materialize<G: MutableGraph>(
+    graph: &mut G,
+) -> Result<usize, ReasonerError<G::Error, G::MutationError>> {
+    let reasoner = Reasoner::from_graph(graph).map_err(ReasonerError::Source)?;
+    if !reasoner.is_consistent() {
+        return Err(ReasonerError::Inconsistent(reasoner.inconsistencies));
+    }
+    reasoner.insert_into(graph).map_err(ReasonerError::Sink)
+}
+
+/// Materialize all the entailments of `dataset` into its default graph.
+///
+/// Reasoning is performed on the union of all graphs (default and named) of `dataset`.
+/// Return the number of inserted quads.
+/// If the dataset is inconsistent, it is left unchanged,
+/// and the inconsistencies are returned as a [`ReasonerError::Inconsistent`].
+pub fn materialize_dataset<D: MutableDataset>(
+    dataset: &mut D,
+) -> Result<usize, ReasonerError<D::Error, D::MutationError>> {
+    let reasoner = Reasoner::from_dataset(dataset).map_err(ReasonerError::Source)?;
+    if !reasoner.is_consistent() {
+        return Err(ReasonerError::Inconsistent(reasoner.inconsistencies));
+    }
+    reasoner
+        .insert_into_dataset(dataset, None as GraphName<SimpleTerm>)
+        .map_err(ReasonerError::Sink)
+}
+
+#[cfg(test)]
+mod test;
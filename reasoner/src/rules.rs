@@ -0,0 +1,730 @@
+//! I implement the OWL 2 RL rules, and their semi-naive evaluation.
+//!
+//! Each fact (asserted or inferred) is passed exactly once to [`Engine::apply`],
+//! which fires every rule having this fact as one of its premises,
+//! looking up the other premises among all the facts known so far.
+//! Since every pair (or triple) of premises is thus considered when the last of them is applied,
+//! no entailment is missed, and no join is ever computed twice.
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::ns::{owl, rdf, rdfs};
+use sophia_api::term::{SimpleTerm, Term};
+
+use crate::error::Inconsistency;
+use crate::store::{Fact, Id, Store};
+
+/// The identifiers of the terms that the rules care about.
+#[derive(Clone, Copy, Debug)]
+struct Vocab {
+    type_: Id,
+    first: Id,
+    rest: Id,
+    nil: Id,
+    sub_class_of: Id,
+    sub_property_of: Id,
+    domain: Id,
+    range: Id,
+    thing: Id,
+    nothing: Id,
+    same_as: Id,
+    different_from: Id,
+    equivalent_class: Id,
+    equivalent_property: Id,
+    disjoint_with: Id,
+    property_disjoint_with: Id,
+    inverse_of: Id,
+    transitive_property: Id,
+    symmetric_property: Id,
+    asymmetric_property: Id,
+    irreflexive_property: Id,
+    functional_property: Id,
+    inverse_functional_property: Id,
+    property_chain_axiom: Id,
+    intersection_of: Id,
+    union_of: Id,
+    on_property: Id,
+    some_values_from: Id,
+    all_values_from: Id,
+    has_value: Id,
+}
+
+impl Vocab {
+    fn new(store: &mut Store) -> Self {
+        Vocab {
+            type_: store.intern(rdf::type_),
+            first: store.intern(rdf::first),
+            rest: store.intern(rdf::rest),
+            nil: store.intern(rdf::nil),
+            sub_class_of: store.intern(rdfs::subClassOf),
+            sub_property_of: store.intern(rdfs::subPropertyOf),
+            domain: store.intern(rdfs::domain),
+            range: store.intern(rdfs::range),
+            thing: store.intern(owl::Thing),
+            nothing: store.intern(owl::Nothing),
+            same_as: store.intern(owl::sameAs),
+            different_from: store.intern(owl::differentFrom),
+            equivalent_class: store.intern(owl::equivalentClass),
+            equivalent_property: store.intern(owl::equivalentProperty),
+            disjoint_with: store.intern(owl::disjointWith),
+            property_disjoint_with: store.intern(owl::propertyDisjointWith),
+            inverse_of: store.intern(owl::inverseOf),
+            transitive_property: store.intern(owl::TransitiveProperty),
+            symmetric_property: store.intern(owl::SymmetricProperty),
+            asymmetric_property: store.intern(owl::AsymmetricProperty),
+            irreflexive_property: store.intern(owl::IrreflexiveProperty),
+            functional_property: store.intern(owl::FunctionalProperty),
+            inverse_functional_property: store.intern(owl::InverseFunctionalProperty),
+            property_chain_axiom: store.intern(owl::propertyChainAxiom),
+            intersection_of: store.intern(owl::intersectionOf),
+            union_of: store.intern(owl::unionOf),
+            on_property: store.intern(owl::onProperty),
+            some_values_from: store.intern(owl::someValuesFrom),
+            all_values_from: store.intern(owl::allValuesFrom),
+            has_value: store.intern(owl::hasValue),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RestrictionKind {
+    SomeValuesFrom,
+    AllValuesFrom,
+    HasValue,
+}
+
+/// A property restriction `class owl:onProperty property; owl:xxx value`.
+#[derive(Clone, Copy, Debug)]
+struct Restriction {
+    class: Id,
+    property: Id,
+    kind: RestrictionKind,
+    value: Id,
+}
+
+/// The axioms that involve RDF lists or several triples about the same blank node.
+///
+/// They are collected from the input before reasoning starts.
+#[derive(Clone, Debug, Default)]
+struct Schema {
+    /// (property, chain of properties)
+    chains: Vec<(Id, Vec<Id>)>,
+    /// property -> (index in `chains`, position in the chain)
+    chains_by_step: HashMap<Id, Vec<(usize, usize)>>,
+    /// (class, members of the intersection)
+    intersections: Vec<(Id, Vec<Id>)>,
+    /// member class -> indexes in `intersections`
+    intersections_by_member: HashMap<Id, Vec<usize>>,
+    restrictions: Vec<Restriction>,
+    restrictions_by_property: HashMap<Id, Vec<usize>>,
+    restrictions_by_class: HashMap<Id, Vec<usize>>,
+    /// class -> indexes in `restrictions` of the someValuesFrom restrictions on this class
+    restrictions_by_filler: HashMap<Id, Vec<usize>>,
+}
+
+/// A contradiction, with terms represented by their identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Contradiction {
+    Nothing(Id),
+    DisjointClasses(Id, [Id; 2]),
+    SameAndDifferent([Id; 2]),
+    IrreflexiveProperty(Id, Id),
+    AsymmetricProperty([Id; 2], Id),
+    DisjointProperties([Id; 2], [Id; 2]),
+}
+
+/// Sort a pair of identifiers, so that symmetric contradictions are only reported once.
+fn sorted([a, b]: [Id; 2]) -> [Id; 2] {
+    if a <= b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Engine {
+    store: Store,
+    v: Vocab,
+    schema: Schema,
+    inferred: Vec<Fact>,
+    queue: Vec<Fact>,
+    contradictions: Vec<Contradiction>,
+    seen_contradictions: HashSet<Contradiction>,
+}
+
+impl Engine {
+    /// Compute all the entailments of the facts in `store`.
+    pub fn run(mut store: Store) -> Self {
+        let v = Vocab::new(&mut store);
+        let mut engine = Engine {
+            store,
+            v,
+            schema: Schema::default(),
+            inferred: vec![],
+            queue: vec![],
+            contradictions: vec![],
+            seen_contradictions: HashSet::new(),
+        };
+        engine.build_schema();
+        let mut delta: Vec<Fact> = engine.store.facts().copied().collect();
+        delta.sort_unstable(); // for reproducibility
+        engine.queue.clear();
+        while !delta.is_empty() {
+            for fact in delta {
+                engine.apply(fact);
+            }
+            delta = std::mem::take(&mut engine.queue);
+        }
+        engine
+    }
+
+    /// The inferred facts, in the order in which they were inferred.
+    pub fn inferred(&self) -> impl Iterator<Item = [&SimpleTerm<'static>; 3]> {
+        self.inferred
+            .iter()
+            .map(|fact| fact.map(|id| self.store.term(id)))
+    }
+
+    /// The number of inferred facts.
+    pub fn inferred_count(&self) -> usize {
+        self.inferred.len()
+    }
+
+    /// The contradictions found during reasoning.
+    pub fn inconsistencies(&self) -> Vec<Inconsistency> {
+        let t = |id: Id| self.store.term(id).clone();
+        self.contradictions
+            .iter()
+            .map(|c| match *c {
+                Contradiction::Nothing(x) => Inconsistency::Nothing { instance: t(x) },
+                Contradiction::DisjointClasses(x, cs) => Inconsistency::DisjointClasses {
+                    instance: t(x),
+                    classes: cs.map(t),
+                },
+                Contradiction::SameAndDifferent(xs) => {
+                    Inconsistency::SameAndDifferent { terms: xs.map(t) }
+                }
+                Contradiction::IrreflexiveProperty(x, p) => Inconsistency::IrreflexiveProperty {
+                    instance: t(x),
+                    property: t(p),
+                },
+                Contradiction::AsymmetricProperty(xs, p) => Inconsistency::AsymmetricProperty {
+                    terms: xs.map(t),
+                    property: t(p),
+                },
+                Contradiction::DisjointProperties(xs, ps) => Inconsistency::DisjointProperties {
+                    terms: xs.map(t),
+                    properties: ps.map(t),
+                },
+            })
+            .collect()
+    }
+
+    /// Add `fact` to the store, and schedule it for [`apply`](Engine::apply) if it is new.
+    ///
+    /// Facts with a literal subject or a non-IRI predicate are silently ignored,
+    /// as well as reflexive `owl:sameAs` facts (see [`apply_same_as`](Engine::apply_same_as)).
+    fn add(&mut self, fact: Fact) {
+        let [s, p, o] = fact;
+        if self.store.is_literal(s) || !self.store.term(p).is_iri() {
+            return;
+        }
+        if p == self.v.same_as && s == o {
+            return;
+        }
+        if self.store.insert(fact) {
+            self.inferred.push(fact);
+            self.queue.push(fact);
+        }
+    }
+
+    fn contradict(&mut self, c: Contradiction) {
+        if self.seen_contradictions.insert(c) {
+            self.contradictions.push(c);
+        }
+    }
+
+    fn has(&self, fact: Fact) -> bool {
+        self.store.contains(&fact)
+    }
+
+    fn is_a(&self, x: Id, class: Id) -> bool {
+        self.has([x, self.v.type_, class])
+    }
+
+    fn objects(&self, s: Id, p: Id) -> Vec<Id> {
+        self.store.objects(s, p).to_vec()
+    }
+
+    fn subjects(&self, p: Id, o: Id) -> Vec<Id> {
+        self.store.subjects(p, o).to_vec()
+    }
+
+    /// The objects of `x r _` and the subjects of `_ r x`, for a symmetric relation `r`.
+    fn both_ways(&self, x: Id, r: Id) -> Vec<Id> {
+        let mut ret = self.objects(x, r);
+        ret.extend_from_slice(self.store.subjects(r, x));
+        ret
+    }
+
+    /// The (subject, object) pairs of all the facts with predicate `p`.
+    fn pairs(&self, p: Id) -> Vec<[Id; 2]> {
+        self.store
+            .with_p(p)
+            .iter()
+            .map(|[s, _, o]| [*s, *o])
+            .collect()
+    }
+
+    /// The elements of the RDF list starting at `head`, if it is well-formed.
+    fn list(&self, mut head: Id) -> Option<Vec<Id>> {
+        let mut ret = vec![];
+        while head != self.v.nil {
+            if ret.len() > self.store.len() {
+                return None; // cyclic list
+            }
+            let ([first], [rest]) = (
+                self.store.objects(head, self.v.first),
+                self.store.objects(head, self.v.rest),
+            ) else {
+                return None;
+            };
+            ret.push(*first);
+            head = *rest;
+        }
+        Some(ret)
+    }
+
+    /// Collect the axioms using lists and restrictions (see [`Schema`]),
+    /// and add the entailments of rules `scm-int` and `scm-uni`.
+    fn build_schema(&mut self) {
+        let v = self.v;
+        for [p, l] in self.pairs(v.property_chain_axiom) {
+            if let Some(steps) = self.list(l).filter(|steps| !steps.is_empty()) {
+                let i = self.schema.chains.len();
+                for (pos, step) in steps.iter().enumerate() {
+                    let entry = self.schema.chains_by_step.entry(*step).or_default();
+                    entry.push((i, pos));
+                }
+                self.schema.chains.push((p, steps));
+            }
+        }
+        for [c, l] in self.pairs(v.intersection_of) {
+            if let Some(members) = self.list(l) {
+                let i = self.schema.intersections.len();
+                for m in &members {
+                    let entry = self.schema.intersections_by_member.entry(*m).or_default();
+                    entry.push(i);
+                    self.add([c, v.sub_class_of, *m]); // scm-int
+                }
+                self.schema.intersections.push((c, members));
+            }
+        }
+        for [c, l] in self.pairs(v.union_of) {
+            for m in self.list(l).unwrap_or_default() {
+                self.add([m, v.sub_class_of, c]); // scm-uni
+            }
+        }
+        for [class, property] in self.pairs(v.on_property) {
+            for (kind, p) in [
+                (RestrictionKind::SomeValuesFrom, v.some_values_from),
+                (RestrictionKind::AllValuesFrom, v.all_values_from),
+                (RestrictionKind::HasValue, v.has_value),
+            ] {
+                for value in self.objects(class, p) {
+                    let i = self.schema.restrictions.len();
+                    self.schema.restrictions.push(Restriction {
+                        class,
+                        property,
+                        kind,
+                        value,
+                    });
+                    let schema = &mut self.schema;
+                    schema
+                        .restrictions_by_property
+                        .entry(property)
+                        .or_default()
+                        .push(i);
+                    schema
+                        .restrictions_by_class
+                        .entry(class)
+                        .or_default()
+                        .push(i);
+                    if kind == RestrictionKind::SomeValuesFrom {
+                        schema
+                            .restrictions_by_filler
+                            .entry(value)
+                            .or_default()
+                            .push(i);
+                    }
+                }
+            }
+        }
+    }
+
+    fn restrictions(&self, index: &HashMap<Id, Vec<usize>>, key: Id) -> Vec<Restriction> {
+        index
+            .get(&key)
+            .map(|v| v.iter().map(|i| self.schema.restrictions[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Fire all the rules having `s p o` as one of their premises.
+    fn apply(&mut self, [s, p, o]: Fact) {
+        let v = self.v;
+        let o_is_literal = self.store.is_literal(o);
+
+        // rules about the property p
+        for q in self.objects(p, v.sub_property_of) {
+            self.add([s, q, o]); // prp-spo1
+        }
+        for c in self.objects(p, v.domain) {
+            self.add([s, v.type_, c]); // prp-dom
+        }
+        if !o_is_literal {
+            for c in self.objects(p, v.range) {
+                self.add([o, v.type_, c]); // prp-rng
+            }
+            for q in self.both_ways(p, v.inverse_of) {
+                self.add([o, q, s]); // prp-inv1, prp-inv2
+            }
+            if self.is_a(p, v.symmetric_property) {
+                self.add([o, p, s]); // prp-symp
+            }
+        }
+        if self.is_a(p, v.transitive_property) {
+            for z in self.objects(o, p) {
+                self.add([s, p, z]); // prp-trp
+            }
+            for x in self.subjects(p, s) {
+                self.add([x, p, o]); // prp-trp
+            }
+        }
+        if self.is_a(p, v.functional_property) {
+            for y in self.objects(s, p) {
+                if y != o {
+                    self.add([o, v.same_as, y]); // prp-fp
+                }
+            }
+        }
+        if self.is_a(p, v.inverse_functional_property) {
+            for x in self.subjects(p, o) {
+                if x != s {
+                    self.add([s, v.same_as, x]); // prp-ifp
+                }
+            }
+        }
+        if s == o && self.is_a(p, v.irreflexive_property) {
+            self.contradict(Contradiction::IrreflexiveProperty(s, p)); // prp-irp
+        }
+        if self.is_a(p, v.asymmetric_property) && self.has([o, p, s]) {
+            self.contradict(Contradiction::AsymmetricProperty(sorted([s, o]), p));
+            // prp-asyp
+        }
+        for q in self.both_ways(p, v.property_disjoint_with) {
+            if self.has([s, q, o]) {
+                let c = Contradiction::DisjointProperties([s, o], sorted([p, q]));
+                self.contradict(c); // prp-pdw
+            }
+        }
+
+        // equality
+        for s2 in self.objects(s, v.same_as) {
+            self.add([s2, p, o]); // eq-rep-s
+        }
+        for p2 in self.objects(p, v.same_as) {
+            self.add([s, p2, o]); // eq-rep-p
+        }
+        for o2 in self.objects(o, v.same_as) {
+            self.add([s, p, o2]); // eq-rep-o
+        }
+
+        // property chains
+        for (i, pos) in self
+            .schema
+            .chains_by_step
+            .get(&p)
+            .cloned()
+            .unwrap_or_default()
+        {
+            self.apply_chain(i, pos, s, o); // prp-spo2
+        }
+
+        // restrictions on p
+        for r in self.restrictions(&self.schema.restrictions_by_property, p) {
+            match r.kind {
+                RestrictionKind::HasValue if r.value == o => {
+                    self.add([s, v.type_, r.class]); // cls-hv2
+                }
+                RestrictionKind::SomeValuesFrom if r.value == v.thing || self.is_a(o, r.value) => {
+                    self.add([s, v.type_, r.class]); // cls-svf1, cls-svf2
+                }
+                RestrictionKind::AllValuesFrom if !o_is_literal && self.is_a(s, r.class) => {
+                    self.add([o, v.type_, r.value]); // cls-avf
+                }
+                _ => {}
+            }
+        }
+
+        // rules about the vocabulary
+        if p == v.type_ {
+            self.apply_type(s, o);
+        } else if p == v.sub_class_of {
+            self.apply_sub_class_of(s, o);
+        } else if p == v.sub_property_of {
+            self.apply_sub_property_of(s, o);
+        } else if p == v.equivalent_class {
+            self.add([s, v.sub_class_of, o]); // scm-eqc1
+            self.add([o, v.sub_class_of, s]); // scm-eqc1
+        } else if p == v.equivalent_property {
+            self.add([s, v.sub_property_of, o]); // scm-eqp1
+            self.add([o, v.sub_property_of, s]); // scm-eqp1
+        } else if p == v.domain {
+            for [x, _] in self.pairs(s) {
+                self.add([x, v.type_, o]); // prp-dom
+            }
+            for c in self.objects(o, v.sub_class_of) {
+                self.add([s, v.domain, c]); // scm-dom1
+            }
+            for q in self.subjects(v.sub_property_of, s) {
+                self.add([q, v.domain, o]); // scm-dom2
+            }
+        } else if p == v.range {
+            for [_, y] in self.pairs(s) {
+                if !self.store.is_literal(y) {
+                    self.add([y, v.type_, o]); // prp-rng
+                }
+            }
+            for c in self.objects(o, v.sub_class_of) {
+                self.add([s, v.range, c]); // scm-rng1
+            }
+            for q in self.subjects(v.sub_property_of, s) {
+                self.add([q, v.range, o]); // scm-rng2
+            }
+        } else if p == v.inverse_of {
+            for [x, y] in self.pairs(s) {
+                self.add([y, o, x]); // prp-inv1
+            }
+            for [x, y] in self.pairs(o) {
+                self.add([y, s, x]); // prp-inv2
+            }
+        } else if p == v.same_as {
+            self.apply_same_as(s, o);
+        } else if p == v.different_from {
+            if self.has([s, v.same_as, o]) {
+                self.contradict(Contradiction::SameAndDifferent(sorted([s, o])));
+                // eq-diff1
+            }
+        } else if p == v.disjoint_with {
+            for x in self.subjects(v.type_, s) {
+                if self.is_a(x, o) {
+                    self.contradict(Contradiction::DisjointClasses(x, sorted([s, o])));
+                    // cax-dw
+                }
+            }
+        } else if p == v.property_disjoint_with {
+            for [x, y] in self.pairs(s) {
+                if self.has([x, o, y]) {
+                    let c = Contradiction::DisjointProperties([x, y], sorted([s, o]));
+                    self.contradict(c); // prp-pdw
+                }
+            }
+        }
+    }
+
+    /// Fire the rules having `x rdf:type c` as one of their premises.
+    fn apply_type(&mut self, x: Id, c: Id) {
+        let v = self.v;
+        for d in self.objects(c, v.sub_class_of) {
+            self.add([x, v.type_, d]); // cax-sco
+        }
+        if c == v.nothing {
+            self.contradict(Contradiction::Nothing(x)); // cls-nothing2
+        }
+        for d in self.both_ways(c, v.disjoint_with) {
+            if self.is_a(x, d) {
+                self.contradict(Contradiction::DisjointClasses(x, sorted([c, d])));
+                // cax-dw
+            }
+        }
+        let intersections = self.schema.intersections_by_member.get(&c).cloned();
+        for i in intersections.unwrap_or_default() {
+            let (class, members) = &self.schema.intersections[i];
+            if members.iter().all(|m| self.is_a(x, *m)) {
+                self.add([x, v.type_, *class]); // cls-int1
+            }
+        }
+        for r in self.restrictions(&self.schema.restrictions_by_class, c) {
+            match r.kind {
+                RestrictionKind::HasValue => {
+                    self.add([x, r.property, r.value]); // cls-hv1
+                }
+                RestrictionKind::AllValuesFrom => {
+                    for y in self.objects(x, r.property) {
+                        if !self.store.is_literal(y) {
+                            self.add([y, v.type_, r.value]); // cls-avf
+                        }
+                    }
+                }
+                RestrictionKind::SomeValuesFrom => {}
+            }
+        }
+        for r in self.restrictions(&self.schema.restrictions_by_filler, c) {
+            for u in self.subjects(r.property, x) {
+                self.add([u, v.type_, r.class]); // cls-svf1
+            }
+        }
+
+        // x is a property with some characteristic, c
+        if c == v.transitive_property {
+            for [a, b] in self.pairs(x) {
+                for z in self.objects(b, x) {
+                    self.add([a, x, z]); // prp-trp
+                }
+            }
+        } else if c == v.symmetric_property {
+            for [a, b] in self.pairs(x) {
+                if !self.store.is_literal(b) {
+                    self.add([b, x, a]); // prp-symp
+                }
+            }
+        } else if c == v.functional_property {
+            for [a, b] in self.pairs(x) {
+                for b2 in self.objects(a, x) {
+                    if b2 != b {
+                        self.add([b, v.same_as, b2]); // prp-fp
+                    }
+                }
+            }
+        } else if c == v.inverse_functional_property {
+            for [a, b] in self.pairs(x) {
+                for a2 in self.subjects(x, b) {
+                    if a2 != a {
+                        self.add([a, v.same_as, a2]); // prp-ifp
+                    }
+                }
+            }
+        } else if c == v.irreflexive_property {
+            for [a, b] in self.pairs(x) {
+                if a == b {
+                    self.contradict(Contradiction::IrreflexiveProperty(a, x)); // prp-irp
+                }
+            }
+        } else if c == v.asymmetric_property {
+            for [a, b] in self.pairs(x) {
+                if self.has([b, x, a]) {
+                    self.contradict(Contradiction::AsymmetricProperty(sorted([a, b]), x));
+                    // prp-asyp
+                }
+            }
+        }
+    }
+
+    /// Fire the rules having `c1 rdfs:subClassOf c2` as one of their premises.
+    fn apply_sub_class_of(&mut self, c1: Id, c2: Id) {
+        let v = self.v;
+        for x in self.subjects(v.type_, c1) {
+            self.add([x, v.type_, c2]); // cax-sco
+        }
+        for c0 in self.subjects(v.sub_class_of, c1) {
+            self.add([c0, v.sub_class_of, c2]); // scm-sco
+        }
+        for c3 in self.objects(c2, v.sub_class_of) {
+            self.add([c1, v.sub_class_of, c3]); // scm-sco
+        }
+        if c1 != c2 && self.has([c2, v.sub_class_of, c1]) {
+            self.add([c1, v.equivalent_class, c2]); // scm-eqc2
+            self.add([c2, v.equivalent_class, c1]); // scm-eqc2
+        }
+        for p in self.subjects(v.domain, c1) {
+            self.add([p, v.domain, c2]); // scm-dom1
+        }
+        for p in self.subjects(v.range, c1) {
+            self.add([p, v.range, c2]); // scm-rng1
+        }
+    }
+
+    /// Fire the rules having `p1 rdfs:subPropertyOf p2` as one of their premises.
+    fn apply_sub_property_of(&mut self, p1: Id, p2: Id) {
+        let v = self.v;
+        for [x, y] in self.pairs(p1) {
+            self.add([x, p2, y]); // prp-spo1
+        }
+        for p0 in self.subjects(v.sub_property_of, p1) {
+            self.add([p0, v.sub_property_of, p2]); // scm-spo
+        }
+        for p3 in self.objects(p2, v.sub_property_of) {
+            self.add([p1, v.sub_property_of, p3]); // scm-spo
+        }
+        if p1 != p2 && self.has([p2, v.sub_property_of, p1]) {
+            self.add([p1, v.equivalent_property, p2]); // scm-eqp2
+            self.add([p2, v.equivalent_property, p1]); // scm-eqp2
+        }
+        for c in self.objects(p2, v.domain) {
+            self.add([p1, v.domain, c]); // scm-dom2
+        }
+        for c in self.objects(p2, v.range) {
+            self.add([p1, v.range, c]); // scm-rng2
+        }
+    }
+
+    /// Fire the rules having `x owl:sameAs y` as one of their premises.
+    ///
+    /// NB: rule `eq-ref` (every term is the same as itself) is not applied,
+    /// and the reflexive `owl:sameAs` triples that other rules would derive are not inferred either.
+    fn apply_same_as(&mut self, x: Id, y: Id) {
+        let v = self.v;
+        if x == y {
+            return;
+        }
+        self.add([y, v.same_as, x]); // eq-sym
+        for z in self.objects(y, v.same_as) {
+            if z != x {
+                self.add([x, v.same_as, z]); // eq-trans
+            }
+        }
+        for [_, p, o] in self.store.with_s(x).to_vec() {
+            self.add([y, p, o]); // eq-rep-s
+        }
+        for [s, o] in self.pairs(x) {
+            self.add([s, y, o]); // eq-rep-p
+        }
+        for [s, p, _] in self.store.with_o(x).to_vec() {
+            self.add([s, p, y]); // eq-rep-o
+        }
+        if self.has([x, v.different_from, y]) || self.has([y, v.different_from, x]) {
+            self.contradict(Contradiction::SameAndDifferent(sorted([x, y]))); // eq-diff1
+        }
+    }
+
+    /// Fire rule `prp-spo2` for the chain number `i`,
+    /// where `s o` is related by the property at position `pos` in the chain.
+    fn apply_chain(&mut self, i: usize, pos: usize, s: Id, o: Id) {
+        let (property, steps) = &self.schema.chains[i];
+        let mut starts = vec![s];
+        for step in steps[..pos].iter().rev() {
+            starts = self.step(&starts, |x| self.store.subjects(*step, x));
+        }
+        let mut ends = vec![o];
+        for step in &steps[pos + 1..] {
+            ends = self.step(&ends, |x| self.store.objects(x, *step));
+        }
+        let property = *property;
+        for a in &starts {
+            for b in &ends {
+                self.add([*a, property, *b]);
+            }
+        }
+    }
+
+    /// Follow one step of a property chain from all the nodes in `from`.
+    fn step<'s, F>(&self, from: &[Id], f: F) -> Vec<Id>
+    where
+        F: Fn(Id) -> &'s [Id],
+    {
+        let mut ret: Vec<Id> = from.iter().flat_map(|x| f(*x)).copied().collect();
+        ret.sort_unstable();
+        ret.dedup();
+        ret
+    }
+}
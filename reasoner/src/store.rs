@@ -0,0 +1,109 @@
+//! I define the in-memory store used by the reasoner.
+//!
+//! Terms are interned, so that facts are triples of small integers,
+//! indexed by subject, predicate and object, as well as by (subject, predicate) and (predicate, object).
+use std::collections::{HashMap, HashSet};
+
+use sophia_api::term::{FromTerm, SimpleTerm, Term};
+
+/// The identifier of an interned term.
+pub(crate) type Id = u32;
+
+/// A triple of interned terms.
+pub(crate) type Fact = [Id; 3];
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Store {
+    terms: Vec<SimpleTerm<'static>>,
+    ids: HashMap<SimpleTerm<'static>, Id>,
+    facts: HashSet<Fact>,
+    by_s: HashMap<Id, Vec<Fact>>,
+    by_p: HashMap<Id, Vec<Fact>>,
+    by_o: HashMap<Id, Vec<Fact>>,
+    by_sp: HashMap<[Id; 2], Vec<Id>>,
+    by_po: HashMap<[Id; 2], Vec<Id>>,
+}
+
+impl Store {
+    /// Get the identifier of the given term, interning it if necessary.
+    pub fn intern<T: Term>(&mut self, t: T) -> Id {
+        if let Some(id) = self.ids.get(&t.as_simple()) {
+            return *id;
+        }
+        let id = Id::try_from(self.terms.len()).expect("too many terms in reasoner store");
+        let t = SimpleTerm::from_term(t);
+        self.terms.push(t.clone());
+        self.ids.insert(t, id);
+        id
+    }
+
+    /// The term identified by `id`.
+    pub fn term(&self, id: Id) -> &SimpleTerm<'static> {
+        &self.terms[id as usize]
+    }
+
+    /// Whether the term identified by `id` is a literal.
+    pub fn is_literal(&self, id: Id) -> bool {
+        self.term(id).is_literal()
+    }
+
+    /// The number of facts in this store.
+    pub fn len(&self) -> usize {
+        self.facts.len()
+    }
+
+    /// An iterator over all the facts in this store.
+    pub fn facts(&self) -> impl Iterator<Item = &Fact> {
+        self.facts.iter()
+    }
+
+    /// Whether `fact` is in this store.
+    pub fn contains(&self, fact: &Fact) -> bool {
+        self.facts.contains(fact)
+    }
+
+    /// Add `fact` to this store, and return `true` if it was not already present.
+    pub fn insert(&mut self, fact: Fact) -> bool {
+        if !self.facts.insert(fact) {
+            return false;
+        }
+        let [s, p, o] = fact;
+        self.by_s.entry(s).or_default().push(fact);
+        self.by_p.entry(p).or_default().push(fact);
+        self.by_o.entry(o).or_default().push(fact);
+        self.by_sp.entry([s, p]).or_default().push(o);
+        self.by_po.entry([p, o]).or_default().push(s);
+        true
+    }
+
+    /// The facts having `s` as their subject.
+    pub fn with_s(&self, s: Id) -> &[Fact] {
+        self.by_s.get(&s).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The facts having `p` as their predicate.
+    pub fn with_p(&self, p: Id) -> &[Fact] {
+        self.by_p.get(&p).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The facts having `o` as their object.
+    pub fn with_o(&self, o: Id) -> &[Fact] {
+        self.by_o.get(&o).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The objects `o` such that `s p o` is in this store.
+    pub fn objects(&self, s: Id, p: Id) -> &[Id] {
+        self.by_sp
+            .get(&[s, p])
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The subjects `s` such that `s p o` is in this store.
+    pub fn subjects(&self, p: Id, o: Id) -> &[Id] {
+        self.by_po
+            .get(&[p, o])
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,282 @@
+use super::*;
+use sophia_api::source::{QuadSource, TripleSource};
+use sophia_api::term::IriRef;
+use sophia_inmem::dataset::LightDataset;
+use sophia_inmem::graph::LightGraph;
+use sophia_turtle::parser::{trig, turtle};
+
+const PREFIXES: &str = r#"
+    PREFIX : <http://example.org/>
+    PREFIX owl: <http://www.w3.org/2002/07/owl#>
+    PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+    PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+"#;
+
+fn graph(ttl: &str) -> LightGraph {
+    turtle::parse_str(&format!("{PREFIXES}{ttl}"))
+        .collect_triples()
+        .unwrap()
+}
+
+/// Materialize `ttl`, and check that it entails every triple in `expected` and none in `unexpected`
+fn check(ttl: &str, expected: &str, unexpected: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut g = graph(ttl);
+    materialize(&mut g)?;
+    for t in graph(expected).triples() {
+        let [s, p, o] = t?;
+        assert!(Graph::contains(&g, s, p, o)?, "missing {s:?} {p:?} {o:?}");
+    }
+    for t in graph(unexpected).triples() {
+        let [s, p, o] = t?;
+        assert!(
+            !Graph::contains(&g, s, p, o)?,
+            "unexpected {s:?} {p:?} {o:?}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn rdfs_rules() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :Student rdfs:subClassOf :Person.
+            :Person rdfs:subClassOf :Agent.
+            :enrolled rdfs:domain :Student; rdfs:range :Course; rdfs:subPropertyOf :related.
+            :alice :enrolled :math.
+            :bob :related "x".
+        "#,
+        r#"
+            :Student rdfs:subClassOf :Agent.
+            :alice a :Student, :Person, :Agent; :related :math.
+            :math a :Course.
+        "#,
+        r#"
+            :bob a :Student.
+        "#,
+    )
+}
+
+#[test]
+fn property_characteristics() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :partOf a owl:TransitiveProperty.
+            :sibling a owl:SymmetricProperty.
+            :hasPart owl:inverseOf :partOf.
+            :a :partOf :b. :b :partOf :c. :c :partOf :d.
+            :x :sibling :y.
+        "#,
+        r#"
+            :a :partOf :c, :d. :b :partOf :d.
+            :d :hasPart :a, :b, :c.
+            :y :sibling :x.
+        "#,
+        r#"
+            :d :partOf :a.
+        "#,
+    )
+}
+
+#[test]
+fn equivalences() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :Human owl:equivalentClass :Person.
+            :name owl:equivalentProperty :label.
+            :alice a :Human; :name "Alice".
+        "#,
+        r#"
+            :Person owl:equivalentClass :Human.
+            :Human rdfs:subClassOf :Person.
+            :alice a :Person; :label "Alice".
+        "#,
+        "",
+    )
+}
+
+#[test]
+fn same_as() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :ssn a owl:InverseFunctionalProperty.
+            :mother a owl:FunctionalProperty.
+            :alice :ssn "123"; :mother :carol.
+            :alice2 :ssn "123"; :mother :caroline.
+            :alice :knows :bob.
+        "#,
+        r#"
+            :alice owl:sameAs :alice2.
+            :alice2 owl:sameAs :alice; :knows :bob.
+            :carol owl:sameAs :caroline.
+            :caroline owl:sameAs :carol.
+        "#,
+        r#"
+            :alice owl:sameAs :alice.
+        "#,
+    )
+}
+
+#[test]
+fn property_chain() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :uncle owl:propertyChainAxiom (:parent :brother).
+            :alice :parent :bob, :betty.
+            :bob :brother :charles.
+            :betty :brother :dan.
+        "#,
+        r#"
+            :alice :uncle :charles, :dan.
+        "#,
+        r#"
+            :bob :uncle :charles.
+        "#,
+    )
+}
+
+#[test]
+fn class_expressions() -> Result<(), Box<dyn std::error::Error>> {
+    check(
+        r#"
+            :Mother owl:intersectionOf (:Woman :Parent).
+            :Adult owl:unionOf (:Man :Woman).
+            :Parent owl:equivalentClass [ owl:onProperty :child; owl:someValuesFrom owl:Thing ].
+            :French owl:equivalentClass [ owl:onProperty :nationality; owl:hasValue :france ].
+            :Vegan rdfs:subClassOf [ owl:onProperty :eats; owl:allValuesFrom :Plant ].
+            :alice a :Woman; :child :bob.
+            :bob :nationality :france.
+            :charles a :French, :Vegan; :eats :salad.
+        "#,
+        r#"
+            :alice a :Parent, :Mother, :Adult.
+            :Mother rdfs:subClassOf :Woman, :Parent.
+            :bob a :French.
+            :charles :nationality :france.
+            :salad a :Plant.
+        "#,
+        r#"
+            :bob a :Parent, :Adult.
+        "#,
+    )
+}
+
+#[test]
+fn inconsistencies() -> Result<(), Box<dyn std::error::Error>> {
+    let mut g = graph(
+        r#"
+            :Cat owl:disjointWith :Dog.
+            :felix a :Cat, :Dog.
+            :unicorn a owl:Nothing.
+            :a owl:sameAs :b. :b owl:differentFrom :a.
+            :knows a owl:IrreflexiveProperty.
+            :parentOf a owl:AsymmetricProperty.
+            :c :knows :c.
+            :d :parentOf :e. :e :parentOf :d.
+        "#,
+    );
+    let before = g.triples().count();
+    let Err(ReasonerError::Inconsistent(found)) = materialize(&mut g) else {
+        panic!("expected an inconsistency");
+    };
+    assert_eq!(g.triples().count(), before);
+    assert_eq!(found.len(), 5, "{found:?}");
+    let mut kinds: Vec<_> = found
+        .iter()
+        .map(|i| match i {
+            Inconsistency::Nothing { .. } => "nothing",
+            Inconsistency::DisjointClasses { .. } => "disjoint classes",
+            Inconsistency::SameAndDifferent { .. } => "same and different",
+            Inconsistency::IrreflexiveProperty { .. } => "irreflexive",
+            Inconsistency::AsymmetricProperty { .. } => "asymmetric",
+            Inconsistency::DisjointProperties { .. } => "disjoint properties",
+        })
+        .collect();
+    kinds.sort();
+    assert_eq!(
+        kinds,
+        [
+            "asymmetric",
+            "disjoint classes",
+            "irreflexive",
+            "nothing",
+            "same and different"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn inferred_nothing() -> Result<(), Box<dyn std::error::Error>> {
+    let g = graph(
+        r#"
+            :Impossible rdfs:subClassOf owl:Nothing.
+            :p rdfs:domain :Impossible.
+            :x :p :y.
+        "#,
+    );
+    let reasoner = Reasoner::from_graph(&g)?;
+    assert!(!reasoner.is_consistent());
+    assert!(matches!(
+        reasoner.inconsistencies(),
+        [Inconsistency::Nothing { instance }] if Term::eq(instance, g_iri("x"))
+    ));
+    Ok(())
+}
+
+#[test]
+fn reasoner_is_idempotent() -> Result<(), Box<dyn std::error::Error>> {
+    let mut g = graph(
+        r#"
+            :ancestor a owl:TransitiveProperty.
+            :parent rdfs:subPropertyOf :ancestor.
+            :a :parent :b. :b :parent :c. :c :parent :d.
+        "#,
+    );
+    let n = materialize(&mut g)?;
+    assert_eq!(n, 6);
+    assert_eq!(materialize(&mut g)?, 0);
+    Ok(())
+}
+
+#[test]
+fn dataset() -> Result<(), Box<dyn std::error::Error>> {
+    let mut d: LightDataset = trig::parse_str(&format!(
+        r#"{PREFIXES}
+            :ontology {{ :Student rdfs:subClassOf :Person. }}
+            :data {{ :alice a :Student. }}
+        "#
+    ))
+    .collect_quads()?;
+    assert_eq!(materialize_dataset(&mut d)?, 1);
+    let person = g_iri("Person");
+    let alice = g_iri("alice");
+    assert!(d.contains(&alice, rdf_type(), &person, None as GraphName<&SimpleTerm>)?);
+    Ok(())
+}
+
+#[test]
+fn insert_into_other_graph() -> Result<(), Box<dyn std::error::Error>> {
+    let g = graph(":p owl:inverseOf :q. :a :p :b.");
+    let reasoner = Reasoner::from_graph(&g)?;
+    let mut target = LightGraph::new();
+    assert_eq!(reasoner.insert_into(&mut target)?, 1);
+    assert!(Graph::contains(
+        &target,
+        g_iri("b"),
+        g_iri("q"),
+        g_iri("a")
+    )?);
+    assert_eq!(reasoner.inferred_count(), 1);
+    Ok(())
+}
+
+fn g_iri(suffix: &str) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(
+        format!("http://example.org/{suffix}").into(),
+    ))
+}
+
+fn rdf_type() -> SimpleTerm<'static> {
+    sophia_api::ns::rdf::type_.into_term()
+}
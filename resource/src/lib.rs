@@ -34,6 +34,8 @@ mod test {
     pub const F2R2: Iri<&str> = Iri::new_unchecked_const("http://example.org/file2.ttl#res2");
     pub const FAIL: Iri<&str> = Iri::new_unchecked_const("http://example.org/not_there");
     pub const F3: Iri<&str> = Iri::new_unchecked_const("http://example.org/file3.nt");
+    #[cfg(feature = "gzip")]
+    pub const F3GZ: Iri<&str> = Iri::new_unchecked_const("http://example.org/file3.nt.gz");
     #[cfg(feature = "jsonld")]
     pub const F4: Iri<&str> = Iri::new_unchecked_const("http://example.org/file4.jsonld");
     #[cfg(feature = "xml")]
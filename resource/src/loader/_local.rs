@@ -1,15 +1,19 @@
 use super::{util::*, *};
+use sophia_api::compression::decompress;
+use sophia_api::format::Format;
 use sophia_iri::Iri;
 use std::borrow::Borrow;
 use std::fmt::Debug;
 use std::fs::read;
-use std::io::ErrorKind as IoErrorKind;
+use std::io::{ErrorKind as IoErrorKind, Read};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// A resource loader using local versions of the resources.
 ///
 /// It guesses content-type from the file extensions,
+/// transparently decompresses compressed files
+/// (with the `gzip`, `bzip2` and `zstd` features, see [`sophia_api::compression`]),
 /// and emulates content-negotiation :
 /// if a resource is not found, it will try adding a few well-known extensions to it.
 #[derive(Clone, Debug, Default)]
@@ -58,17 +62,11 @@ impl LocalLoader {
     }
 
     fn ctype(&self, iri: &str) -> String {
-        if iri.ends_with(".ttl") {
-            "text/turtle".into()
-        } else if iri.ends_with(".nt") {
-            "application/n-triples".into()
-        } else if cfg!(feature = "jsonld") && iri.ends_with(".jsonld") {
-            "application/ld+json".into()
-        } else if cfg!(feature = "xml") && iri.ends_with(".rdf") {
-            "application/rdf+xml".into()
-        } else {
-            "application/octet-stream".into()
-        }
+        let name = &iri[iri.rfind('/').map_or(0, |i| i + 1)..];
+        registry()
+            .by_path(name)
+            .map_or("application/octet-stream", Format::media_type)
+            .into()
     }
 }
 
@@ -81,7 +79,13 @@ impl Loader for LocalLoader {
                 let subpath = Path::new(&iri[ns.len()..]);
                 let resource_path: PathBuf = path.join(subpath);
                 return match read(resource_path) {
-                    Ok(data) => Ok((data, self.ctype(iri))),
+                    Ok(data) => {
+                        let mut decompressed = vec![];
+                        decompress(&data[..])
+                            .read_to_end(&mut decompressed)
+                            .map_err(|e| LoaderError::IoError(iri_buf(iri), e))?;
+                        Ok((decompressed, self.ctype(iri)))
+                    }
                     Err(e) if e.kind() == IoErrorKind::NotFound => {
                         // emulate conneg if there is no extension
                         let no_ext = iri.as_bytes()[iri.rfind(['.', '/']).unwrap_or(0)] != b'.';
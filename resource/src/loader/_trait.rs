@@ -3,13 +3,12 @@ use crate::{Resource, ResourceError, TypedResource};
 #[cfg(feature = "jsonld")]
 use futures_util::FutureExt;
 use sophia_api::graph::CollectibleGraph;
-use sophia_api::parser::TripleParser;
-use sophia_api::source::TripleSource;
+use sophia_api::quad::Quad;
+use sophia_api::source::{QuadSource, TripleSource};
 use sophia_api::term::Term;
 use sophia_iri::Iri;
 #[cfg(feature = "jsonld")]
 use sophia_jsonld::loader_factory::ClosureLoaderFactory;
-use sophia_turtle::parser::{nt, turtle};
 use std::borrow::Borrow;
 use std::io;
 use std::sync::Arc;
@@ -25,6 +24,10 @@ pub trait Loader: Sync + Sized {
 
     /// Get the RDF representation of the resource identified by `iri`.
     ///
+    /// Formats supporting named graphs are not accepted
+    /// (they fail with [`LoaderError::CantGuessSyntax`]),
+    /// except for JSON-LD, of which only the default graph is kept.
+    ///
     /// # Precondition
     /// `iri` must contain no fragment identifier.
     fn get_graph<G, T>(&self, iri: Iri<T>) -> Result<G, LoaderError>
@@ -36,57 +39,46 @@ pub trait Loader: Sync + Sized {
         let iri_str = iri.as_str();
         let (data, ctype) = self.get(iri.as_ref())?;
         let bufread = io::BufReader::new(&data[..]);
-        match &ctype[..] {
-            "text/turtle" => turtle::TurtleParser {
-                base: Some(iri.as_ref().map_unchecked(|t| t.to_string())),
-            }
-            .parse(bufread)
-            .collect_triples()
-            .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err))),
-
-            "application/n-triples" => nt::NTriplesParser {}
+        #[cfg(feature = "jsonld")]
+        if ctype == "application/ld+json" {
+            use sophia_api::prelude::QuadParser;
+            use sophia_jsonld::{loader::ClosureLoader, JsonLdOptions, JsonLdParser};
+            let options = JsonLdOptions::new()
+                .with_base(iri.as_ref().map_unchecked(|t| t.into()))
+                .with_document_loader_factory(ClosureLoaderFactory::new(|| {
+                    ClosureLoader::new(|url| {
+                        async move {
+                            let (content, ctype) =
+                                self.get(url.as_ref()).map_err(|e| e.to_string())?;
+                            if ctype == "application/ld+json" {
+                                String::from_utf8(content).map_err(|e| e.to_string())
+                            } else {
+                                Err(format!("{url} is not JSON-LD: {ctype}"))
+                            }
+                        }
+                        .boxed()
+                    })
+                }));
+            return JsonLdParser::new_with_options(options)
                 .parse(bufread)
+                .filter_quads(|q| q.g().is_none())
+                .map_quads(Quad::into_triple)
                 .collect_triples()
-                .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err))),
-
-            #[cfg(feature = "jsonld")]
-            "application/ld+json" => {
-                use sophia_api::prelude::{Quad, QuadParser, QuadSource};
-                use sophia_jsonld::{loader::ClosureLoader, JsonLdOptions, JsonLdParser};
-                let options = JsonLdOptions::new()
-                    .with_base(iri.as_ref().map_unchecked(|t| t.into()))
-                    .with_document_loader_factory(ClosureLoaderFactory::new(|| {
-                        ClosureLoader::new(|url| {
-                            async move {
-                                let (content, ctype) =
-                                    self.get(url.as_ref()).map_err(|e| e.to_string())?;
-                                if ctype == "application/ld+json" {
-                                    String::from_utf8(content).map_err(|e| e.to_string())
-                                } else {
-                                    Err(format!("{url} is not JSON-LD: {ctype}"))
-                                }
-                            }
-                            .boxed()
-                        })
-                    }));
-                JsonLdParser::new_with_options(options)
-                    .parse(bufread)
-                    .filter_quads(|q| q.g().is_none())
-                    .map_quads(Quad::into_triple)
-                    .collect_triples()
-                    .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err)))
-            }
+                .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err)));
+        }
 
-            #[cfg(feature = "xml")]
-            "application/rdf+xml" => sophia_xml::parser::RdfXmlParser {
-                base: Some(iri.as_ref().map_unchecked(|t| t.to_string())),
-            }
-            .parse(bufread)
+        let format = registry()
+            .by_media_type(&ctype)
+            .filter(|format| !format.dataset)
+            .ok_or_else(|| LoaderError::CantGuessSyntax(iri_buf(iri_str)))?;
+        let base = Some(iri.as_ref().map_unchecked(|t| t.to_string()));
+        let graph = format
+            .parse(bufread, base)
+            .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err)))?
+            .map_quads(Quad::into_triple)
             .collect_triples()
-            .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err))),
-
-            _ => Err(LoaderError::CantGuessSyntax(iri_buf(iri_str))),
-        }
+            .map_err(|err| LoaderError::ParseError(iri_buf(iri_str), Box::new(err)))?;
+        Ok(graph)
     }
 
     /// Get the resource identified by `iri`
@@ -58,6 +58,17 @@ fn get_file3() -> TestResult {
     Ok(())
 }
 
+#[cfg(feature = "gzip")]
+#[test]
+fn get_file3_gz() -> TestResult {
+    let ldr = make_loader();
+    assert_eq!(
+        ldr.get(F3GZ)?,
+        (read("test/file3.nt")?, "application/n-triples".into()),
+    );
+    Ok(())
+}
+
 #[test]
 fn get_file3_no_ext() -> TestResult {
     let ldr = make_loader();
@@ -152,6 +163,25 @@ fn graph_from_nt() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn graph_from_nquads_fails() -> TestResult {
+    struct NQuadsLoader;
+    impl Loader for NQuadsLoader {
+        fn get<T: std::borrow::Borrow<str>>(
+            &self,
+            _: Iri<T>,
+        ) -> Result<(Vec<u8>, String), LoaderError> {
+            let data = "<tag:s> <tag:p> <tag:o> <tag:g> .\n";
+            Ok((data.into(), "application/n-quads".into()))
+        }
+    }
+    assert!(matches!(
+        NQuadsLoader.get_graph::<MyGraph, _>(F1),
+        Err(LoaderError::CantGuessSyntax(..)),
+    ));
+    Ok(())
+}
+
 #[cfg(feature = "jsonld")]
 #[test]
 fn graph_from_jsonld() -> TestResult {
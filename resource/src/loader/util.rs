@@ -1,8 +1,24 @@
+use sophia_api::format::FormatRegistry;
 use sophia_api::MownStr;
 use sophia_iri::Iri;
+use std::sync::OnceLock;
 
 pub type IriBuf = Iri<MownStr<'static>>;
 
 pub fn iri_buf(iri: &str) -> IriBuf {
     IriBuf::new_unchecked(iri.to_owned().into())
 }
+
+/// The formats supported by loaders (depending on the enabled features).
+pub fn registry() -> &'static FormatRegistry {
+    static REGISTRY: OnceLock<FormatRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = FormatRegistry::new();
+        sophia_turtle::format::register(&mut registry);
+        #[cfg(feature = "xml")]
+        sophia_xml::format::register(&mut registry);
+        #[cfg(feature = "jsonld")]
+        sophia_jsonld::format::register(&mut registry);
+        registry
+    })
+}
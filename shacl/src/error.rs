@@ -0,0 +1,38 @@
+//! I define the errors raised by the SHACL validator.
+use std::error::Error;
+
+use sophia_api::term::SimpleTerm;
+
+/// An error raised while loading a shapes graph.
+#[derive(Debug, thiserror::Error)]
+pub enum ShapesError<E>
+where
+    E: Error + 'static,
+{
+    /// An error was raised while reading the shapes graph
+    #[error("error while reading the shapes graph: {0}")]
+    Source(#[source] E),
+    /// The shapes graph is not well-formed
+    #[error("ill-formed shape {node:?}: {reason}")]
+    IllFormed {
+        /// The offending node in the shapes graph
+        node: SimpleTerm<'static>,
+        /// Why it is not well-formed
+        reason: String,
+    },
+}
+
+/// An error raised by [`validate`](crate::validate).
+#[derive(Debug, thiserror::Error)]
+pub enum ShaclError<E, F>
+where
+    E: Error + 'static,
+    F: Error + 'static,
+{
+    /// An error was raised while loading the shapes graph
+    #[error(transparent)]
+    Shapes(#[from] ShapesError<E>),
+    /// An error was raised while reading the data graph
+    #[error("error while reading the data graph: {0}")]
+    Data(#[source] F),
+}
@@ -0,0 +1,89 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides a validation engine for [SHACL Core] shapes,
+//! which validates any [`Graph`] against a shapes graph,
+//! and produces a [`ValidationReport`].
+//! The report can be inspected as a Rust struct,
+//! or converted to an RDF graph using the SHACL vocabulary
+//! (`sh:ValidationReport`, `sh:ValidationResult`).
+//!
+//! # Supported features
+//!
+//! * all kinds of targets (`sh:targetNode`, `sh:targetClass`, implicit class targets,
+//!   `sh:targetSubjectsOf`, `sh:targetObjectsOf`);
+//! * node shapes and property shapes, with all kinds of property paths;
+//! * value type constraints (`sh:class`, `sh:datatype`, `sh:nodeKind`);
+//! * cardinality constraints (`sh:minCount`, `sh:maxCount`);
+//! * value range constraints (`sh:minExclusive`, `sh:minInclusive`, `sh:maxExclusive`, `sh:maxInclusive`);
+//! * string-based constraints (`sh:minLength`, `sh:maxLength`, `sh:pattern`, `sh:languageIn`, `sh:uniqueLang`);
+//! * property pair constraints (`sh:equals`, `sh:disjoint`, `sh:lessThan`, `sh:lessThanOrEquals`);
+//! * logical constraints (`sh:not`, `sh:and`, `sh:or`, `sh:xone`);
+//! * shape-based constraints (`sh:node`, `sh:property`);
+//! * other constraints (`sh:closed`, `sh:ignoredProperties`, `sh:hasValue`, `sh:in`);
+//! * `sh:severity`, `sh:message` and `sh:deactivated`.
+//!
+//! Qualified value shapes, as well as SHACL-SPARQL, are not supported.
+//! Literals are compared by value only if they are numeric;
+//! other literals are compared by lexical form, provided that they have the same datatype.
+//!
+//! # Example
+//! ```
+//! # use sophia_api::source::TripleSource;
+//! # use sophia_inmem::graph::LightGraph;
+//! # use sophia_turtle::parser::turtle;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let shapes: LightGraph = turtle::parse_str(r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     PREFIX sh: <http://www.w3.org/ns/shacl#>
+//!     :PersonShape a sh:NodeShape;
+//!         sh:targetClass :Person;
+//!         sh:property [ sh:path :name; sh:minCount 1 ].
+//! "#).collect_triples()?;
+//! let data: LightGraph = turtle::parse_str(r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     :alice a :Person; :name "Alice".
+//!     :bob a :Person.
+//! "#).collect_triples()?;
+//!
+//! let report = sophia_shacl::validate(&shapes, &data)?;
+//! assert!(!report.conforms());
+//! assert_eq!(report.results().len(), 1); // :bob has no :name
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+//! [SHACL Core]: https://www.w3.org/TR/shacl/#core-components
+//! [`Graph`]: sophia_api::graph::Graph
+#![deny(missing_docs)]
+
+use sophia_api::graph::Graph;
+
+mod error;
+pub use error::*;
+pub mod ns;
+mod path;
+pub use path::Path;
+mod report;
+pub use report::*;
+mod shape;
+mod validator;
+pub use validator::Validator;
+
+/// Validate `data` against the shapes defined in `shapes`.
+///
+/// If several data graphs must be validated against the same shapes graph,
+/// it is more efficient to build a [`Validator`] once, and reuse it.
+pub fn validate<S: Graph, D: Graph>(
+    shapes: &S,
+    data: &D,
+) -> Result<ValidationReport, ShaclError<S::Error, D::Error>> {
+    Validator::new(shapes)?
+        .validate(data)
+        .map_err(ShaclError::Data)
+}
+
+#[cfg(test)]
+mod test;
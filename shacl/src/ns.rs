@@ -0,0 +1,110 @@
+//! The SHACL namespace.
+
+/// The standard `sh:` namespace.
+///
+/// NB: since `in` is a reserved keyword in Rust,
+/// the term `sh:in` spells `sh::in_` (with a trailing underscore).
+pub mod sh {
+    sophia_api::namespace!(
+        "http://www.w3.org/ns/shacl#",
+        // classes
+        NodeShape,
+        PropertyShape,
+        ValidationReport,
+        ValidationResult,
+        // severities
+        Info,
+        Warning,
+        Violation,
+        // node kinds
+        IRI,
+        BlankNode,
+        Literal,
+        BlankNodeOrIRI,
+        BlankNodeOrLiteral,
+        IRIOrLiteral,
+        // targets
+        targetNode,
+        targetClass,
+        targetSubjectsOf,
+        targetObjectsOf,
+        // paths
+        path,
+        inversePath,
+        alternativePath,
+        zeroOrMorePath,
+        oneOrMorePath,
+        zeroOrOnePath,
+        // shape properties
+        severity,
+        message,
+        deactivated,
+        // constraint parameters
+        class,
+        datatype,
+        nodeKind,
+        minCount,
+        maxCount,
+        minExclusive,
+        minInclusive,
+        maxExclusive,
+        maxInclusive,
+        minLength,
+        maxLength,
+        pattern,
+        flags,
+        languageIn,
+        uniqueLang,
+        equals,
+        disjoint,
+        lessThan,
+        lessThanOrEquals,
+        not,
+        and,
+        or,
+        xone,
+        node,
+        property,
+        closed,
+        ignoredProperties,
+        hasValue,
+        // validation report
+        conforms,
+        result,
+        focusNode,
+        resultPath,
+        value,
+        sourceShape,
+        sourceConstraintComponent,
+        resultSeverity,
+        resultMessage,
+        // constraint components
+        ClassConstraintComponent,
+        DatatypeConstraintComponent,
+        NodeKindConstraintComponent,
+        MinCountConstraintComponent,
+        MaxCountConstraintComponent,
+        MinExclusiveConstraintComponent,
+        MinInclusiveConstraintComponent,
+        MaxExclusiveConstraintComponent,
+        MaxInclusiveConstraintComponent,
+        MinLengthConstraintComponent,
+        MaxLengthConstraintComponent,
+        PatternConstraintComponent,
+        LanguageInConstraintComponent,
+        UniqueLangConstraintComponent,
+        EqualsConstraintComponent,
+        DisjointConstraintComponent,
+        LessThanConstraintComponent,
+        LessThanOrEqualsConstraintComponent,
+        NotConstraintComponent,
+        AndConstraintComponent,
+        OrConstraintComponent,
+        XoneConstraintComponent,
+        NodeConstraintComponent,
+        ClosedConstraintComponent,
+        HasValueConstraintComponent,
+        InConstraintComponent;
+        in_, "in"
+    );
+}
@@ -0,0 +1,156 @@
+//! I define SHACL property paths.
+use std::collections::BTreeSet;
+
+use sophia_api::graph::Graph;
+use sophia_api::ns::rdf;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{BnodeId, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+
+use crate::ns::sh;
+
+/// A set of nodes of the data graph.
+pub(crate) type Nodes = BTreeSet<SimpleTerm<'static>>;
+
+/// A [SHACL property path](https://www.w3.org/TR/shacl/#property-paths).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Path {
+    /// A predicate path, i.e. an IRI
+    Predicate(SimpleTerm<'static>),
+    /// An inverse path (`sh:inversePath`)
+    Inverse(Box<Path>),
+    /// A sequence path (an RDF list of paths)
+    Sequence(Vec<Path>),
+    /// An alternative path (`sh:alternativePath`)
+    Alternative(Vec<Path>),
+    /// A zero-or-more path (`sh:zeroOrMorePath`)
+    ZeroOrMore(Box<Path>),
+    /// A one-or-more path (`sh:oneOrMorePath`)
+    OneOrMore(Box<Path>),
+    /// A zero-or-one path (`sh:zeroOrOnePath`)
+    ZeroOrOne(Box<Path>),
+}
+
+impl Path {
+    /// The nodes reachable from `focus` in `data` through this path.
+    pub(crate) fn values<D: Graph>(
+        &self,
+        data: &D,
+        focus: &SimpleTerm<'static>,
+    ) -> Result<Nodes, D::Error> {
+        self.eval(data, &Nodes::from([focus.clone()]), true)
+    }
+
+    /// The nodes reachable from any of `nodes` through this path,
+    /// or through its inverse if `forward` is false.
+    fn eval<D: Graph>(&self, data: &D, nodes: &Nodes, forward: bool) -> Result<Nodes, D::Error> {
+        use Path::*;
+        match self {
+            Predicate(p) => {
+                let mut ret = Nodes::new();
+                for n in nodes {
+                    if forward {
+                        for t in data.triples_matching([n], [p], Any) {
+                            ret.insert(t?.o().into_term());
+                        }
+                    } else {
+                        for t in data.triples_matching(Any, [p], [n]) {
+                            ret.insert(t?.s().into_term());
+                        }
+                    }
+                }
+                Ok(ret)
+            }
+            Inverse(path) => path.eval(data, nodes, !forward),
+            Sequence(paths) => {
+                let mut ret = nodes.clone();
+                if forward {
+                    for path in paths {
+                        ret = path.eval(data, &ret, forward)?;
+                    }
+                } else {
+                    for path in paths.iter().rev() {
+                        ret = path.eval(data, &ret, forward)?;
+                    }
+                }
+                Ok(ret)
+            }
+            Alternative(paths) => {
+                let mut ret = Nodes::new();
+                for path in paths {
+                    ret.extend(path.eval(data, nodes, forward)?);
+                }
+                Ok(ret)
+            }
+            ZeroOrMore(path) => path.closure(data, nodes.clone(), forward),
+            OneOrMore(path) => {
+                let first = path.eval(data, nodes, forward)?;
+                path.closure(data, first, forward)
+            }
+            ZeroOrOne(path) => {
+                let mut ret = path.eval(data, nodes, forward)?;
+                ret.extend(nodes.iter().cloned());
+                Ok(ret)
+            }
+        }
+    }
+
+    /// `nodes`, plus all the nodes reachable from them through one or more steps of this path.
+    fn closure<D: Graph>(&self, data: &D, nodes: Nodes, forward: bool) -> Result<Nodes, D::Error> {
+        let mut ret = nodes.clone();
+        let mut frontier = nodes;
+        while !frontier.is_empty() {
+            frontier = self
+                .eval(data, &frontier, forward)?
+                .into_iter()
+                .filter(|n| ret.insert(n.clone()))
+                .collect();
+        }
+        Ok(ret)
+    }
+
+    /// Append to `triples` the RDF representation of this path,
+    /// and return the node representing it.
+    ///
+    /// `bnode_prefix` is used to generate fresh blank node identifiers.
+    pub(crate) fn to_triples(
+        &self,
+        triples: &mut Vec<[SimpleTerm<'static>; 3]>,
+        bnode_prefix: &str,
+    ) -> SimpleTerm<'static> {
+        use Path::*;
+        let fresh = |triples: &mut Vec<_>| {
+            SimpleTerm::BlankNode(BnodeId::new_unchecked(
+                format!("{bnode_prefix}_{}", triples.len()).into(),
+            ))
+        };
+        let (predicate, object) = match self {
+            Predicate(p) => return p.clone(),
+            Sequence(paths) => {
+                let items: Vec<_> = paths
+                    .iter()
+                    .map(|p| p.to_triples(triples, bnode_prefix))
+                    .collect();
+                let mut list: SimpleTerm<'static> = rdf::nil.into_term();
+                for item in items.into_iter().rev() {
+                    let cell = fresh(triples);
+                    triples.push([cell.clone(), rdf::first.into_term(), item]);
+                    triples.push([cell.clone(), rdf::rest.into_term(), list]);
+                    list = cell;
+                }
+                return list;
+            }
+            Alternative(paths) => {
+                let seq = Sequence(paths.clone()).to_triples(triples, bnode_prefix);
+                (sh::alternativePath, seq)
+            }
+            Inverse(path) => (sh::inversePath, path.to_triples(triples, bnode_prefix)),
+            ZeroOrMore(path) => (sh::zeroOrMorePath, path.to_triples(triples, bnode_prefix)),
+            OneOrMore(path) => (sh::oneOrMorePath, path.to_triples(triples, bnode_prefix)),
+            ZeroOrOne(path) => (sh::zeroOrOnePath, path.to_triples(triples, bnode_prefix)),
+        };
+        let node = fresh(triples);
+        triples.push([node.clone(), predicate.into_term(), object]);
+        node
+    }
+}
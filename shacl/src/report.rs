@@ -0,0 +1,141 @@
+//! I define the validation report produced by the validator.
+use sophia_api::graph::MutableGraph;
+use sophia_api::ns::{rdf, xsd, NsTerm};
+use sophia_api::term::{BnodeId, SimpleTerm, Term};
+
+use crate::ns::sh;
+use crate::Path;
+
+/// The severity of a [`ValidationResult`],
+/// as specified by the `sh:severity` of the shape that produced it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// `sh:Info`
+    Info,
+    /// `sh:Warning`
+    Warning,
+    /// `sh:Violation` (the default)
+    Violation,
+}
+
+impl Severity {
+    /// The IRI of this severity in the SHACL vocabulary.
+    pub fn iri(&self) -> NsTerm<'static> {
+        match self {
+            Severity::Info => sh::Info,
+            Severity::Warning => sh::Warning,
+            Severity::Violation => sh::Violation,
+        }
+    }
+}
+
+/// A single result of the validation (`sh:ValidationResult`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationResult {
+    /// The focus node that was validated (`sh:focusNode`)
+    pub focus_node: SimpleTerm<'static>,
+    /// The path of the property shape that produced this result, if any (`sh:resultPath`)
+    pub path: Option<Path>,
+    /// The value node that violated the constraint, if any (`sh:value`)
+    pub value: Option<SimpleTerm<'static>>,
+    /// The shape that produced this result (`sh:sourceShape`)
+    pub source_shape: SimpleTerm<'static>,
+    /// The constraint component that produced this result (`sh:sourceConstraintComponent`)
+    pub source_constraint_component: SimpleTerm<'static>,
+    /// The severity of this result (`sh:resultSeverity`)
+    pub severity: Severity,
+    /// The messages of the shape that produced this result (`sh:resultMessage`)
+    pub messages: Vec<SimpleTerm<'static>>,
+}
+
+/// The outcome of validating a data graph against a shapes graph (`sh:ValidationReport`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    results: Vec<ValidationResult>,
+}
+
+impl ValidationReport {
+    pub(crate) fn new(results: Vec<ValidationResult>) -> Self {
+        ValidationReport { results }
+    }
+
+    /// Whether the data graph conforms to the shapes graph,
+    /// i.e. whether this report contains no result (whatever their severity).
+    pub fn conforms(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The results of this report.
+    pub fn results(&self) -> &[ValidationResult] {
+        &self.results
+    }
+
+    /// Consume this report and return its results.
+    pub fn into_results(self) -> Vec<ValidationResult> {
+        self.results
+    }
+
+    /// The RDF representation of this report.
+    ///
+    /// The returned `Vec` implements [`Graph`](sophia_api::graph::Graph).
+    /// The report, its results, and the complex paths they contain are represented by blank nodes,
+    /// whose identifiers are prefixed with `shacl_`.
+    pub fn to_graph(&self) -> Vec<[SimpleTerm<'static>; 3]> {
+        let mut triples = vec![];
+        let report = bnode("shacl_report".into());
+        triples.push([
+            report.clone(),
+            rdf::type_.into_term(),
+            sh::ValidationReport.into_term(),
+        ]);
+        let conforms = if self.conforms() { "true" } else { "false" };
+        triples.push([
+            report.clone(),
+            sh::conforms.into_term(),
+            conforms * xsd::boolean,
+        ]);
+        for (i, res) in self.results.iter().enumerate() {
+            let node = bnode(format!("shacl_result{i}"));
+            triples.push([report.clone(), sh::result.into_term(), node.clone()]);
+            let mut add = |p: NsTerm, o: SimpleTerm<'static>| {
+                triples.push([node.clone(), p.into_term(), o]);
+            };
+            add(rdf::type_, sh::ValidationResult.into_term());
+            add(sh::focusNode, res.focus_node.clone());
+            if let Some(value) = &res.value {
+                add(sh::value, value.clone());
+            }
+            add(sh::sourceShape, res.source_shape.clone());
+            add(
+                sh::sourceConstraintComponent,
+                res.source_constraint_component.clone(),
+            );
+            add(sh::resultSeverity, res.severity.iri().into_term());
+            for msg in &res.messages {
+                add(sh::resultMessage, msg.clone());
+            }
+            if let Some(path) = &res.path {
+                let path = path.to_triples(&mut triples, &format!("shacl_path{i}"));
+                triples.push([node, sh::resultPath.into_term(), path]);
+            }
+        }
+        triples
+    }
+
+    /// Insert the RDF representation of this report (see [`ValidationReport::to_graph`]) into `graph`.
+    ///
+    /// Return the number of triples actually inserted.
+    pub fn insert_into<G: MutableGraph>(&self, graph: &mut G) -> Result<usize, G::MutationError> {
+        let mut count = 0;
+        for [s, p, o] in self.to_graph() {
+            if graph.insert(s, p, o)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+fn bnode(id: String) -> SimpleTerm<'static> {
+    SimpleTerm::BlankNode(BnodeId::new_unchecked(id.into()))
+}
@@ -0,0 +1,460 @@
+//! I define the internal representation of SHACL shapes,
+//! and how it is extracted from a shapes graph.
+use std::collections::{BTreeSet, HashMap};
+
+use regex::Regex;
+use sophia_api::graph::Graph;
+use sophia_api::ns::{rdf, rdfs, NsTerm};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+
+use crate::ns::sh;
+use crate::{Path, Severity, ShapesError};
+
+/// A node of the shapes graph or of the data graph.
+pub(crate) type Node = SimpleTerm<'static>;
+
+/// The index of a [`Shape`] in the list of shapes of a [`Validator`](crate::Validator).
+pub(crate) type ShapeId = usize;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Shape {
+    pub id: Node,
+    pub targets: Vec<Target>,
+    /// `Some` for property shapes, `None` for node shapes
+    pub path: Option<Path>,
+    pub constraints: Vec<Constraint>,
+    pub severity: Severity,
+    pub messages: Vec<Node>,
+    pub deactivated: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Target {
+    Node(Node),
+    Class(Node),
+    SubjectsOf(Node),
+    ObjectsOf(Node),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Constraint {
+    Class(Node),
+    Datatype(Node),
+    NodeKind(Node),
+    MinCount(usize),
+    MaxCount(usize),
+    MinExclusive(Node),
+    MinInclusive(Node),
+    MaxExclusive(Node),
+    MaxInclusive(Node),
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Regex),
+    LanguageIn(Vec<String>),
+    UniqueLang,
+    Equals(Node),
+    Disjoint(Node),
+    LessThan(Node),
+    LessThanOrEquals(Node),
+    Not(ShapeId),
+    And(Vec<ShapeId>),
+    Or(Vec<ShapeId>),
+    Xone(Vec<ShapeId>),
+    NodeShape(ShapeId),
+    PropertyShape(ShapeId),
+    Closed { allowed: BTreeSet<Node> },
+    HasValue(Node),
+    In(Vec<Node>),
+}
+
+impl Constraint {
+    /// The IRI of the constraint component of this constraint.
+    pub fn component(&self) -> NsTerm<'static> {
+        use Constraint::*;
+        match self {
+            Class(_) => sh::ClassConstraintComponent,
+            Datatype(_) => sh::DatatypeConstraintComponent,
+            NodeKind(_) => sh::NodeKindConstraintComponent,
+            MinCount(_) => sh::MinCountConstraintComponent,
+            MaxCount(_) => sh::MaxCountConstraintComponent,
+            MinExclusive(_) => sh::MinExclusiveConstraintComponent,
+            MinInclusive(_) => sh::MinInclusiveConstraintComponent,
+            MaxExclusive(_) => sh::MaxExclusiveConstraintComponent,
+            MaxInclusive(_) => sh::MaxInclusiveConstraintComponent,
+            MinLength(_) => sh::MinLengthConstraintComponent,
+            MaxLength(_) => sh::MaxLengthConstraintComponent,
+            Pattern(_) => sh::PatternConstraintComponent,
+            LanguageIn(_) => sh::LanguageInConstraintComponent,
+            UniqueLang => sh::UniqueLangConstraintComponent,
+            Equals(_) => sh::EqualsConstraintComponent,
+            Disjoint(_) => sh::DisjointConstraintComponent,
+            LessThan(_) => sh::LessThanConstraintComponent,
+            LessThanOrEquals(_) => sh::LessThanOrEqualsConstraintComponent,
+            Not(_) => sh::NotConstraintComponent,
+            And(_) => sh::AndConstraintComponent,
+            Or(_) => sh::OrConstraintComponent,
+            Xone(_) => sh::XoneConstraintComponent,
+            NodeShape(_) => sh::NodeConstraintComponent,
+            // results of property shapes are reported as is, so this is never actually used
+            PropertyShape(_) => sh::property,
+            Closed { .. } => sh::ClosedConstraintComponent,
+            HasValue(_) => sh::HasValueConstraintComponent,
+            In(_) => sh::InConstraintComponent,
+        }
+    }
+}
+
+type Result<T, E> = std::result::Result<T, ShapesError<E>>;
+
+/// Extract all the shapes of the given shapes graph.
+///
+/// Shapes with a target, as well as explicit instances of `sh:NodeShape` and `sh:PropertyShape`,
+/// are extracted, together with all the shapes they reference.
+pub(crate) fn parse_shapes<G: Graph>(graph: &G) -> Result<Vec<Shape>, G::Error> {
+    let mut parser = Parser {
+        graph,
+        shapes: vec![],
+        index: HashMap::new(),
+    };
+    let mut roots = BTreeSet::new();
+    for t in graph.triples_matching(
+        Any,
+        [
+            sh::targetNode,
+            sh::targetClass,
+            sh::targetSubjectsOf,
+            sh::targetObjectsOf,
+        ],
+        Any,
+    ) {
+        roots.insert(t.map_err(ShapesError::Source)?.s().into_term());
+    }
+    for t in graph.triples_matching(Any, [rdf::type_], [sh::NodeShape, sh::PropertyShape]) {
+        roots.insert(t.map_err(ShapesError::Source)?.s().into_term());
+    }
+    for id in roots {
+        parser.shape(id)?;
+    }
+    Ok(parser.shapes)
+}
+
+struct Parser<'a, G> {
+    graph: &'a G,
+    shapes: Vec<Shape>,
+    index: HashMap<Node, ShapeId>,
+}
+
+impl<G: Graph> Parser<'_, G> {
+    /// Parse the shape `id` (if not done already) and return its index.
+    fn shape(&mut self, id: Node) -> Result<ShapeId, G::Error> {
+        if let Some(i) = self.index.get(&id) {
+            return Ok(*i);
+        }
+        let i = self.shapes.len();
+        self.index.insert(id.clone(), i);
+        self.shapes.push(Shape {
+            id: id.clone(),
+            targets: vec![],
+            path: None,
+            constraints: vec![],
+            severity: Severity::Violation,
+            messages: vec![],
+            deactivated: false,
+        });
+
+        let targets = self.targets(&id)?;
+        let path = self
+            .object(&id, sh::path)?
+            .map(|p| self.path(&p))
+            .transpose()?;
+        let severity = match self.object(&id, sh::severity)? {
+            None => Severity::Violation,
+            Some(s) if sh::Violation == s => Severity::Violation,
+            Some(s) if sh::Warning == s => Severity::Warning,
+            Some(s) if sh::Info == s => Severity::Info,
+            Some(s) => return Err(ill_formed(&id, format!("unsupported severity {s:?}"))),
+        };
+        let messages = self.objects(&id, sh::message)?;
+        let deactivated = self.boolean(&id, sh::deactivated)?.unwrap_or(false);
+        let constraints = self.constraints(&id, path.is_some())?;
+
+        let shape = &mut self.shapes[i];
+        shape.targets = targets;
+        shape.path = path;
+        shape.severity = severity;
+        shape.messages = messages;
+        shape.deactivated = deactivated;
+        shape.constraints = constraints;
+        Ok(i)
+    }
+
+    fn targets(&self, id: &Node) -> Result<Vec<Target>, G::Error> {
+        let mut targets = vec![];
+        targets.extend(
+            self.objects(id, sh::targetNode)?
+                .into_iter()
+                .map(Target::Node),
+        );
+        targets.extend(
+            self.objects(id, sh::targetClass)?
+                .into_iter()
+                .map(Target::Class),
+        );
+        targets.extend(
+            self.objects(id, sh::targetSubjectsOf)?
+                .into_iter()
+                .map(Target::SubjectsOf),
+        );
+        targets.extend(
+            self.objects(id, sh::targetObjectsOf)?
+                .into_iter()
+                .map(Target::ObjectsOf),
+        );
+        // implicit class target
+        let types = self.objects(id, rdf::type_)?;
+        if types.iter().any(|t| rdfs::Class == t)
+            && types
+                .iter()
+                .any(|t| sh::NodeShape == t || sh::PropertyShape == t)
+        {
+            targets.push(Target::Class(id.clone()));
+        }
+        Ok(targets)
+    }
+
+    fn constraints(&mut self, id: &Node, is_property: bool) -> Result<Vec<Constraint>, G::Error> {
+        use Constraint::*;
+        let mut constraints = vec![];
+        for (param, make) in [
+            (sh::class, Class as fn(Node) -> Constraint),
+            (sh::datatype, Datatype),
+            (sh::nodeKind, NodeKind),
+            (sh::minExclusive, MinExclusive),
+            (sh::minInclusive, MinInclusive),
+            (sh::maxExclusive, MaxExclusive),
+            (sh::maxInclusive, MaxInclusive),
+            (sh::equals, Equals),
+            (sh::disjoint, Disjoint),
+            (sh::lessThan, LessThan),
+            (sh::lessThanOrEquals, LessThanOrEquals),
+            (sh::hasValue, HasValue),
+        ] {
+            constraints.extend(self.objects(id, param)?.into_iter().map(make));
+        }
+        if is_property {
+            if let Some(n) = self.integer(id, sh::minCount)? {
+                constraints.push(MinCount(n));
+            }
+            if let Some(n) = self.integer(id, sh::maxCount)? {
+                constraints.push(MaxCount(n));
+            }
+            if self.boolean(id, sh::uniqueLang)? == Some(true) {
+                constraints.push(UniqueLang);
+            }
+        }
+        if let Some(n) = self.integer(id, sh::minLength)? {
+            constraints.push(MinLength(n));
+        }
+        if let Some(n) = self.integer(id, sh::maxLength)? {
+            constraints.push(MaxLength(n));
+        }
+        if let Some(pattern) = self.object(id, sh::pattern)? {
+            constraints.push(Pattern(self.regex(id, &pattern)?));
+        }
+        for list in self.objects(id, sh::languageIn)? {
+            let ranges = self
+                .list(&list)?
+                .into_iter()
+                .map(|r| match r.lexical_form() {
+                    Some(lex) => Ok(lex.to_string()),
+                    None => Err(ill_formed(id, "sh:languageIn expects literals")),
+                })
+                .collect::<Result<_, _>>()?;
+            constraints.push(LanguageIn(ranges));
+        }
+        for list in self.objects(id, sh::in_)? {
+            constraints.push(In(self.list(&list)?));
+        }
+        for shape in self.objects(id, sh::not)? {
+            constraints.push(Not(self.shape(shape)?));
+        }
+        for shape in self.objects(id, sh::node)? {
+            constraints.push(NodeShape(self.shape(shape)?));
+        }
+        for (param, make) in [
+            (sh::and, And as fn(Vec<ShapeId>) -> Constraint),
+            (sh::or, Or),
+            (sh::xone, Xone),
+        ] {
+            for list in self.objects(id, param)? {
+                let shapes = self
+                    .list(&list)?
+                    .into_iter()
+                    .map(|s| self.shape(s))
+                    .collect::<Result<_, _>>()?;
+                constraints.push(make(shapes));
+            }
+        }
+        let properties = self.objects(id, sh::property)?;
+        if self.boolean(id, sh::closed)? == Some(true) {
+            let mut allowed = BTreeSet::new();
+            for p in &properties {
+                if let Some(Path::Predicate(iri)) = self
+                    .object(p, sh::path)?
+                    .map(|p| self.path(&p))
+                    .transpose()?
+                {
+                    allowed.insert(iri);
+                }
+            }
+            for list in self.objects(id, sh::ignoredProperties)? {
+                allowed.extend(self.list(&list)?);
+            }
+            constraints.push(Closed { allowed });
+        }
+        for shape in properties {
+            if self.object(&shape, sh::path)?.is_none() {
+                return Err(ill_formed(&shape, "a property shape must have a sh:path"));
+            }
+            constraints.push(PropertyShape(self.shape(shape)?));
+        }
+        Ok(constraints)
+    }
+
+    fn path(&self, node: &Node) -> Result<Path, G::Error> {
+        if node.is_iri() {
+            return Ok(Path::Predicate(node.clone()));
+        }
+        if !node.is_blank_node() {
+            return Err(ill_formed(node, "a path must be an IRI or a blank node"));
+        }
+        if self.object(node, rdf::first)?.is_some() {
+            let items = self.list(node)?;
+            if items.len() < 2 {
+                return Err(ill_formed(
+                    node,
+                    "a sequence path must have at least 2 items",
+                ));
+            }
+            return Ok(Path::Sequence(
+                items
+                    .iter()
+                    .map(|i| self.path(i))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        if let Some(list) = self.object(node, sh::alternativePath)? {
+            let items = self.list(&list)?;
+            if items.len() < 2 {
+                return Err(ill_formed(
+                    node,
+                    "an alternative path must have at least 2 items",
+                ));
+            }
+            return Ok(Path::Alternative(
+                items
+                    .iter()
+                    .map(|i| self.path(i))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        for (param, make) in [
+            (sh::inversePath, Path::Inverse as fn(Box<Path>) -> Path),
+            (sh::zeroOrMorePath, Path::ZeroOrMore),
+            (sh::oneOrMorePath, Path::OneOrMore),
+            (sh::zeroOrOnePath, Path::ZeroOrOne),
+        ] {
+            if let Some(inner) = self.object(node, param)? {
+                return Ok(make(Box::new(self.path(&inner)?)));
+            }
+        }
+        Err(ill_formed(node, "unrecognized path"))
+    }
+
+    /// All the values of `p` for `s`.
+    fn objects(&self, s: &Node, p: NsTerm) -> Result<Vec<Node>, G::Error> {
+        self.graph
+            .triples_matching([s], [p], Any)
+            .map(|t| t.map(|t| t.o().into_term()))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(ShapesError::Source)
+    }
+
+    /// The value of `p` for `s`, failing if there are more than one.
+    fn object(&self, s: &Node, p: NsTerm) -> Result<Option<Node>, G::Error> {
+        let mut objects = self.objects(s, p)?;
+        if objects.len() > 1 {
+            return Err(ill_formed(s, format!("more than one value for {p}")));
+        }
+        Ok(objects.pop())
+    }
+
+    fn integer(&self, s: &Node, p: NsTerm) -> Result<Option<usize>, G::Error> {
+        self.object(s, p)?
+            .map(|o| {
+                o.lexical_form()
+                    .and_then(|lex| lex.parse().ok())
+                    .ok_or_else(|| ill_formed(s, format!("{p} expects a non-negative integer")))
+            })
+            .transpose()
+    }
+
+    fn boolean(&self, s: &Node, p: NsTerm) -> Result<Option<bool>, G::Error> {
+        self.object(s, p)?
+            .map(|o| match o.lexical_form().as_deref() {
+                Some("true" | "1") => Ok(true),
+                Some("false" | "0") => Ok(false),
+                _ => Err(ill_formed(s, format!("{p} expects a boolean"))),
+            })
+            .transpose()
+    }
+
+    fn regex(&self, s: &Node, pattern: &Node) -> Result<Regex, G::Error> {
+        let Some(pattern) = pattern.lexical_form() else {
+            return Err(ill_formed(s, "sh:pattern expects a literal"));
+        };
+        let flags = self
+            .object(s, sh::flags)?
+            .and_then(|f| f.lexical_form().map(|f| f.to_string()))
+            .unwrap_or_default();
+        if let Some(f) = flags.chars().find(|c| !"imsx".contains(*c)) {
+            return Err(ill_formed(s, format!("unsupported regex flag {f:?}")));
+        }
+        let pattern = if flags.is_empty() {
+            pattern.to_string()
+        } else {
+            format!("(?{flags}){pattern}")
+        };
+        Regex::new(&pattern).map_err(|e| ill_formed(s, e.to_string()))
+    }
+
+    /// The items of the RDF list starting at `head`.
+    fn list(&self, head: &Node) -> Result<Vec<Node>, G::Error> {
+        let mut items = vec![];
+        let mut visited = BTreeSet::new();
+        let mut cell = head.clone();
+        while rdf::nil != cell {
+            if !visited.insert(cell.clone()) {
+                return Err(ill_formed(head, "cyclic list"));
+            }
+            let (Some(first), Some(rest)) = (
+                self.object(&cell, rdf::first)?,
+                self.object(&cell, rdf::rest)?,
+            ) else {
+                return Err(ill_formed(head, "ill-formed list"));
+            };
+            items.push(first);
+            cell = rest;
+        }
+        Ok(items)
+    }
+}
+
+fn ill_formed<E: std::error::Error>(node: &Node, reason: impl Into<String>) -> ShapesError<E> {
+    ShapesError::IllFormed {
+        node: node.clone(),
+        reason: reason.into(),
+    }
+}
@@ -0,0 +1,326 @@
+use super::*;
+use sophia_api::source::TripleSource;
+use sophia_api::term::{IriRef, SimpleTerm, Term};
+use sophia_inmem::graph::LightGraph;
+use sophia_isomorphism::isomorphic_graphs;
+use sophia_turtle::parser::turtle;
+
+const PREFIXES: &str = r#"
+    PREFIX : <http://example.org/>
+    PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+    PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+    PREFIX sh: <http://www.w3.org/ns/shacl#>
+    PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+"#;
+
+fn graph(ttl: &str) -> LightGraph {
+    turtle::parse_str(&format!("{PREFIXES}{ttl}"))
+        .collect_triples()
+        .unwrap()
+}
+
+/// Validate `data` against `shapes`,
+/// and return the (focus node, constraint component, value) of each result, sorted.
+fn check(shapes: &str, data: &str) -> Vec<(String, String, Option<String>)> {
+    let report = validate(&graph(shapes), &graph(data)).unwrap();
+    let mut found: Vec<_> = report
+        .results()
+        .iter()
+        .map(|r| {
+            (
+                short(&r.focus_node),
+                short(&r.source_constraint_component),
+                r.value.as_ref().map(short),
+            )
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// A compact representation of `t`, for readable assertions
+fn short(t: &SimpleTerm) -> String {
+    match t {
+        SimpleTerm::Iri(iri) => iri.as_str().rsplit(['/', '#']).next().unwrap().to_string(),
+        _ => t.lexical_form().map(|l| l.to_string()).unwrap_or_default(),
+    }
+}
+
+fn res(focus: &str, component: &str, value: Option<&str>) -> (String, String, Option<String>) {
+    (
+        focus.to_string(),
+        format!("{component}ConstraintComponent"),
+        value.map(str::to_string),
+    )
+}
+
+#[test]
+fn targets() {
+    let shapes = r#"
+        :S1 sh:targetNode :a; sh:nodeKind sh:Literal.
+        :S2 sh:targetClass :C; sh:nodeKind sh:Literal.
+        :S3 sh:targetSubjectsOf :p; sh:nodeKind sh:Literal.
+        :S4 sh:targetObjectsOf :p; sh:nodeKind sh:Literal.
+        :D a rdfs:Class, sh:NodeShape; sh:nodeKind sh:Literal.
+    "#;
+    let data = r#"
+        :D1 rdfs:subClassOf :C.
+        :b a :D1.
+        :c :p :d.
+        :e a :D.
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("a", "NodeKind", Some("a")),
+            res("b", "NodeKind", Some("b")),
+            res("c", "NodeKind", Some("c")),
+            res("d", "NodeKind", Some("d")),
+            res("e", "NodeKind", Some("e")),
+        ]
+    );
+}
+
+#[test]
+fn value_type() {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:property [ sh:path :knows; sh:class :Person ];
+            sh:property [ sh:path :age; sh:datatype xsd:integer ];
+            sh:property [ sh:path :label; sh:datatype rdf:langString ];
+            sh:property [ sh:path :home; sh:nodeKind sh:IRI ].
+    "#;
+    let data = r#"
+        :Student rdfs:subClassOf :Person.
+        :alice a :Student.
+        :x :knows :alice, :bob;
+           :age 42, "forty-two"^^xsd:integer, "42";
+           :label "x"@en, "x";
+           :home :here, "there".
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("x", "Class", Some("bob")),
+            res("x", "Datatype", Some("42")),
+            res("x", "Datatype", Some("forty-two")),
+            res("x", "Datatype", Some("x")),
+            res("x", "NodeKind", Some("there")),
+        ]
+    );
+}
+
+#[test]
+fn cardinality() {
+    let shapes = r#"
+        :S sh:targetClass :Person;
+            sh:property [ sh:path :name; sh:minCount 1; sh:maxCount 1 ].
+    "#;
+    let data = r#"
+        :alice a :Person; :name "Alice".
+        :bob a :Person.
+        :charlie a :Person; :name "Charlie", "Charles".
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("bob", "MinCount", None),
+            res("charlie", "MaxCount", None),
+        ]
+    );
+}
+
+#[test]
+fn ranges_and_strings() {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:property [ sh:path :age; sh:minInclusive 0; sh:maxExclusive 150 ];
+            sh:property [ sh:path :code; sh:pattern "^[a-z]+$"; sh:flags "i"; sh:maxLength 4 ];
+            sh:property [ sh:path :label; sh:languageIn ("en" "fr"); sh:uniqueLang true ].
+    "#;
+    let data = r#"
+        :x :age 12, -1, 150, 1.5e2, "old";
+           :code "abc", "ABCD", "ab1", "abcde";
+           :label "hello"@en-GB, "hi"@en, "hey"@en, "salut"@fr, "hallo"@de.
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("x", "LanguageIn", Some("hallo")),
+            res("x", "MaxExclusive", Some("1.5e2")),
+            res("x", "MaxExclusive", Some("150")),
+            res("x", "MaxExclusive", Some("old")),
+            res("x", "MaxLength", Some("abcde")),
+            res("x", "MinInclusive", Some("-1")),
+            res("x", "MinInclusive", Some("old")),
+            res("x", "Pattern", Some("ab1")),
+            res("x", "UniqueLang", None),
+        ]
+    );
+}
+
+#[test]
+fn property_pairs() {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:property [ sh:path :givenName; sh:equals :firstName ];
+            sh:property [ sh:path :name; sh:disjoint :nick ];
+            sh:property [ sh:path :start; sh:lessThan :end ].
+    "#;
+    let data = r#"
+        :x :givenName "A", "B"; :firstName "A";
+           :name "X"; :nick "X";
+           :start 1, 3; :end 2.
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("x", "Disjoint", Some("X")),
+            res("x", "Equals", Some("B")),
+            res("x", "LessThan", Some("3")),
+        ]
+    );
+}
+
+#[test]
+fn logical_and_shape_based() {
+    let shapes = r#"
+        :Named sh:property [ sh:path :name; sh:minCount 1 ].
+        :Aged sh:property [ sh:path :age; sh:minCount 1 ].
+        :S1 sh:targetNode :a, :b, :c; sh:or (:Named :Aged).
+        :S2 sh:targetNode :a, :b, :c; sh:xone (:Named :Aged).
+        :S3 sh:targetNode :a, :b, :c; sh:and (:Named :Aged).
+        :S4 sh:targetNode :a, :b, :c; sh:not :Aged.
+        :S5 sh:targetNode :a; sh:property [ sh:path :friend; sh:node :Named ].
+    "#;
+    let data = r#"
+        :a :name "A"; :friend :b, :c.
+        :b :age 1.
+        :c :name "C"; :age 3.
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("a", "And", Some("a")),
+            res("a", "Node", Some("b")),
+            res("b", "And", Some("b")),
+            res("b", "Not", Some("b")),
+            res("c", "Not", Some("c")),
+            res("c", "Xone", Some("c")),
+        ]
+    );
+}
+
+#[test]
+fn closed_in_and_has_value() {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:closed true;
+            sh:ignoredProperties (rdf:type);
+            sh:property [ sh:path :color; sh:in (:red :green) ];
+            sh:property [ sh:path :tag; sh:hasValue "important" ].
+    "#;
+    let data = r#"
+        :x a :Thing; :color :red, :blue; :tag "trivial"; :size 3.
+    "#;
+    assert_eq!(
+        check(shapes, data),
+        vec![
+            res("x", "Closed", Some("3")),
+            res("x", "HasValue", None),
+            res("x", "In", Some("blue")),
+        ]
+    );
+}
+
+#[test]
+fn complex_paths() {
+    let shapes = r#"
+        :S sh:targetNode :a;
+            sh:property [ sh:path ( :parent :parent ); sh:minCount 2 ];
+            sh:property [ sh:path [ sh:oneOrMorePath :parent ]; sh:maxCount 2 ];
+            sh:property [ sh:path [ sh:inversePath :parent ]; sh:hasValue :z ];
+            sh:property [ sh:path [ sh:alternativePath (:parent :friend) ]; sh:in (:b :f) ].
+    "#;
+    let data = r#"
+        :a :parent :b; :friend :f.
+        :b :parent :c.
+        :z :parent :a.
+    "#;
+    let report = validate(&graph(shapes), &graph(data)).unwrap();
+    let paths: Vec<_> = report.results().iter().map(|r| r.path.clone()).collect();
+    let parent = Path::Predicate(iri("parent"));
+    assert_eq!(
+        paths,
+        vec![Some(Path::Sequence(vec![parent.clone(), parent.clone()]))]
+    );
+}
+
+#[test]
+fn severity_and_messages() {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:severity sh:Warning;
+            sh:message "not a literal"@en;
+            sh:nodeKind sh:Literal.
+        :T sh:targetNode :x; sh:deactivated true; sh:nodeKind sh:Literal.
+    "#;
+    let report = validate(&graph(shapes), &graph("")).unwrap();
+    let [r] = report.results() else {
+        panic!("expected exactly one result: {report:?}");
+    };
+    assert_eq!(r.severity, Severity::Warning);
+    assert_eq!(r.messages.len(), 1);
+    assert!(Term::eq(&r.source_shape, iri("S")));
+}
+
+#[test]
+fn report_graph() -> Result<(), Box<dyn std::error::Error>> {
+    let shapes = r#"
+        :S sh:targetNode :x;
+            sh:property [ sh:path [ sh:inversePath :p ]; sh:minCount 1 ].
+    "#;
+    let report = validate(&graph(shapes), &graph(""))?;
+    let mut g = LightGraph::new();
+    report.insert_into(&mut g)?;
+    let expected = graph(
+        r#"
+        [] a sh:ValidationReport;
+            sh:conforms false;
+            sh:result [
+                a sh:ValidationResult;
+                sh:focusNode :x;
+                sh:resultPath [ sh:inversePath :p ];
+                sh:sourceShape [];
+                sh:sourceConstraintComponent sh:MinCountConstraintComponent;
+                sh:resultSeverity sh:Violation
+            ].
+        "#,
+    );
+    assert!(isomorphic_graphs(&expected, &g)?);
+
+    let conforming = validate(&graph(shapes), &graph(":y :p :x."))?;
+    assert!(conforming.conforms());
+    assert_eq!(conforming.to_graph().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn ill_formed_shapes() {
+    let shapes = graph(":S sh:targetNode :x; sh:property [ sh:minCount 1 ].");
+    assert!(matches!(
+        Validator::new(&shapes),
+        Err(ShapesError::IllFormed { .. })
+    ));
+    let shapes = graph(":S sh:targetNode :x; sh:pattern \"(\".");
+    assert!(matches!(
+        Validator::new(&shapes),
+        Err(ShapesError::IllFormed { .. })
+    ));
+}
+
+fn iri(suffix: &str) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(
+        format!("http://example.org/{suffix}").into(),
+    ))
+}
@@ -0,0 +1,486 @@
+//! I define the [`Validator`], which validates data graphs against a shapes graph.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use sophia_api::graph::Graph;
+use sophia_api::ns::{rdf, rdfs, xsd};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term, TermKind};
+use sophia_api::triple::Triple;
+
+use crate::ns::sh;
+use crate::path::Nodes;
+use crate::shape::{parse_shapes, Constraint, Node, Shape, ShapeId, Target};
+use crate::{Path, ShapesError, ValidationReport, ValidationResult};
+
+/// A SHACL validator, built from a shapes graph.
+///
+/// The same validator can be used to validate any number of data graphs.
+#[derive(Clone, Debug)]
+pub struct Validator {
+    shapes: Vec<Shape>,
+}
+
+impl Validator {
+    /// Build a validator from the given shapes graph.
+    pub fn new<G: Graph>(shapes: &G) -> Result<Self, ShapesError<G::Error>> {
+        Ok(Validator {
+            shapes: parse_shapes(shapes)?,
+        })
+    }
+
+    /// Validate `data` against the shapes of this validator.
+    pub fn validate<D: Graph>(&self, data: &D) -> Result<ValidationReport, D::Error> {
+        let mut validation = Validation {
+            shapes: &self.shapes,
+            data,
+            stack: vec![],
+        };
+        let mut results = vec![];
+        for (i, shape) in self.shapes.iter().enumerate() {
+            if shape.deactivated {
+                continue;
+            }
+            for focus in validation.focus_nodes(shape)? {
+                validation.validate(i, &focus, &mut results)?;
+            }
+        }
+        Ok(ValidationReport::new(results))
+    }
+}
+
+/// The state of the validation of one data graph.
+struct Validation<'a, D> {
+    shapes: &'a [Shape],
+    data: &'a D,
+    /// The (shape, focus node) pairs currently being validated, to cope with recursive shapes
+    stack: Vec<(ShapeId, Node)>,
+}
+
+impl<D: Graph> Validation<'_, D> {
+    /// The focus nodes selected by the targets of `shape`.
+    fn focus_nodes(&self, shape: &Shape) -> Result<Nodes, D::Error> {
+        let mut nodes = Nodes::new();
+        for target in &shape.targets {
+            match target {
+                Target::Node(n) => {
+                    nodes.insert(n.clone());
+                }
+                Target::Class(c) => {
+                    let classes: Vec<_> = self.subclasses(c)?.into_iter().collect();
+                    for t in self
+                        .data
+                        .triples_matching(Any, [rdf::type_], classes.as_slice())
+                    {
+                        nodes.insert(t?.s().into_term());
+                    }
+                }
+                Target::SubjectsOf(p) => {
+                    for t in self.data.triples_matching(Any, [p], Any) {
+                        nodes.insert(t?.s().into_term());
+                    }
+                }
+                Target::ObjectsOf(p) => {
+                    for t in self.data.triples_matching(Any, [p], Any) {
+                        nodes.insert(t?.o().into_term());
+                    }
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// `class` and all its (direct or indirect) subclasses in the data graph.
+    fn subclasses(&self, class: &Node) -> Result<Nodes, D::Error> {
+        Path::ZeroOrMore(Box::new(Path::Inverse(Box::new(Path::Predicate(
+            rdfs::subClassOf.into_term(),
+        )))))
+        .values(self.data, class)
+    }
+
+    /// Whether `node` is a SHACL instance of `class` in the data graph.
+    fn is_instance_of(&self, node: &Node, class: &Node) -> Result<bool, D::Error> {
+        let path = Path::Sequence(vec![
+            Path::Predicate(rdf::type_.into_term()),
+            Path::ZeroOrMore(Box::new(Path::Predicate(rdfs::subClassOf.into_term()))),
+        ]);
+        Ok(path.values(self.data, node)?.contains(class))
+    }
+
+    /// Whether `node` conforms to shape `shape`.
+    fn conforms(&mut self, shape: ShapeId, node: &Node) -> Result<bool, D::Error> {
+        let mut results = vec![];
+        self.validate(shape, node, &mut results)?;
+        Ok(results.is_empty())
+    }
+
+    /// Validate `focus` against shape `shape`, and append the results to `results`.
+    fn validate(
+        &mut self,
+        shape: ShapeId,
+        focus: &Node,
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), D::Error> {
+        let shapes = self.shapes;
+        let s = &shapes[shape];
+        if s.deactivated {
+            return Ok(());
+        }
+        let key = (shape, focus.clone());
+        if self.stack.contains(&key) {
+            // recursive validation of the same node against the same shape:
+            // assume it conforms
+            return Ok(());
+        }
+        self.stack.push(key);
+        let values = match &s.path {
+            None => Nodes::from([focus.clone()]),
+            Some(path) => path.values(self.data, focus)?,
+        };
+        for constraint in &s.constraints {
+            self.check(s, constraint, focus, &values, results)?;
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Check `constraint` of `shape` on the given focus node and value nodes.
+    fn check(
+        &mut self,
+        shape: &Shape,
+        constraint: &Constraint,
+        focus: &Node,
+        values: &Nodes,
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), D::Error> {
+        use Constraint::*;
+        let mut failed: Vec<Option<Node>> = vec![];
+        match constraint {
+            Class(c) => {
+                for v in values {
+                    if !self.is_instance_of(v, c)? {
+                        failed.push(Some(v.clone()));
+                    }
+                }
+            }
+            Datatype(dt) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| !has_datatype(v, dt))
+                    .map(|v| Some(v.clone())),
+            ),
+            NodeKind(kind) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| !has_node_kind(v, kind))
+                    .map(|v| Some(v.clone())),
+            ),
+            MinCount(n) => {
+                if values.len() < *n {
+                    failed.push(None);
+                }
+            }
+            MaxCount(n) => {
+                if values.len() > *n {
+                    failed.push(None);
+                }
+            }
+            MinExclusive(bound) => {
+                failed.extend(compare_all(values, bound, |o| o == Ordering::Greater))
+            }
+            MinInclusive(bound) => {
+                failed.extend(compare_all(values, bound, |o| o != Ordering::Less))
+            }
+            MaxExclusive(bound) => {
+                failed.extend(compare_all(values, bound, |o| o == Ordering::Less))
+            }
+            MaxInclusive(bound) => {
+                failed.extend(compare_all(values, bound, |o| o != Ordering::Greater))
+            }
+            MinLength(n) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| string_value(v).is_none_or(|s| s.chars().count() < *n))
+                    .map(|v| Some(v.clone())),
+            ),
+            MaxLength(n) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| string_value(v).is_none_or(|s| s.chars().count() > *n))
+                    .map(|v| Some(v.clone())),
+            ),
+            Pattern(re) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| string_value(v).is_none_or(|s| !re.is_match(&s)))
+                    .map(|v| Some(v.clone())),
+            ),
+            LanguageIn(ranges) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| {
+                        v.language_tag()
+                            .is_none_or(|tag| !ranges.iter().any(|r| lang_matches(&tag, r)))
+                    })
+                    .map(|v| Some(v.clone())),
+            ),
+            UniqueLang => {
+                let mut counts = HashMap::new();
+                for tag in values.iter().filter_map(|v| v.language_tag()) {
+                    *counts.entry(tag.to_ascii_lowercase()).or_insert(0) += 1;
+                }
+                let duplicates = counts.values().filter(|n| **n > 1).count();
+                failed.extend(std::iter::repeat_n(None, duplicates));
+            }
+            Equals(p) => {
+                let others = self.objects(focus, p)?;
+                failed.extend(
+                    values
+                        .symmetric_difference(&others)
+                        .map(|v| Some(v.clone())),
+                );
+            }
+            Disjoint(p) => {
+                let others = self.objects(focus, p)?;
+                failed.extend(values.intersection(&others).map(|v| Some(v.clone())));
+            }
+            LessThan(p) | LessThanOrEquals(p) => {
+                let strict = matches!(constraint, LessThan(_));
+                let others = self.objects(focus, p)?;
+                for v in values {
+                    if others.iter().any(|o| match compare(v, o) {
+                        Some(Ordering::Less) => false,
+                        Some(Ordering::Equal) => strict,
+                        _ => true,
+                    }) {
+                        failed.push(Some(v.clone()));
+                    }
+                }
+            }
+            Not(s) => {
+                for v in values {
+                    if self.conforms(*s, v)? {
+                        failed.push(Some(v.clone()));
+                    }
+                }
+            }
+            And(shapes) | Or(shapes) | Xone(shapes) => {
+                for v in values {
+                    let mut count = 0;
+                    for s in shapes {
+                        if self.conforms(*s, v)? {
+                            count += 1;
+                        }
+                    }
+                    let ok = match constraint {
+                        And(_) => count == shapes.len(),
+                        Or(_) => count > 0,
+                        _ => count == 1,
+                    };
+                    if !ok {
+                        failed.push(Some(v.clone()));
+                    }
+                }
+            }
+            NodeShape(s) => {
+                for v in values {
+                    if !self.conforms(*s, v)? {
+                        failed.push(Some(v.clone()));
+                    }
+                }
+            }
+            PropertyShape(s) => {
+                for v in values {
+                    self.validate(*s, v, results)?;
+                }
+            }
+            Closed { allowed } => {
+                for v in values {
+                    for t in self.data.triples_matching([v], Any, Any) {
+                        let t = t?;
+                        if !allowed.contains(&t.p().as_simple()) {
+                            results.push(ValidationResult {
+                                path: Some(Path::Predicate(t.p().into_term())),
+                                value: Some(t.o().into_term()),
+                                ..self.result(shape, constraint, v)
+                            });
+                        }
+                    }
+                }
+            }
+            HasValue(expected) => {
+                if !values.contains(expected) {
+                    failed.push(None);
+                }
+            }
+            In(allowed) => failed.extend(
+                values
+                    .iter()
+                    .filter(|v| !allowed.contains(v))
+                    .map(|v| Some(v.clone())),
+            ),
+        }
+        results.extend(failed.into_iter().map(|value| ValidationResult {
+            value,
+            ..self.result(shape, constraint, focus)
+        }));
+        Ok(())
+    }
+
+    /// The objects of `p` for `s` in the data graph.
+    fn objects(&self, s: &Node, p: &Node) -> Result<Nodes, D::Error> {
+        Path::Predicate(p.clone()).values(self.data, s)
+    }
+
+    /// A result produced by `constraint` of `shape` for `focus`, without value.
+    fn result(&self, shape: &Shape, constraint: &Constraint, focus: &Node) -> ValidationResult {
+        ValidationResult {
+            focus_node: focus.clone(),
+            path: shape.path.clone(),
+            value: None,
+            source_shape: shape.id.clone(),
+            source_constraint_component: constraint.component().into_term(),
+            severity: shape.severity,
+            messages: shape.messages.clone(),
+        }
+    }
+}
+
+/// Whether `node` is a literal with datatype `dt` and a valid lexical form.
+fn has_datatype(node: &Node, dt: &Node) -> bool {
+    let (Some(lex), Some(actual)) = (node.lexical_form(), node.datatype()) else {
+        return false;
+    };
+    if dt.iri().is_none_or(|dt| dt != actual) {
+        return false;
+    }
+    if rdf::langString == actual {
+        return node.language_tag().is_some();
+    }
+    let lex = lex.as_ref();
+    if is_integer_type(dt) {
+        let Ok(n) = lex.parse::<i128>() else {
+            return false;
+        };
+        if xsd::nonNegativeInteger == dt || xsd::unsignedLong == dt {
+            return n >= 0;
+        }
+        if xsd::positiveInteger == dt {
+            return n > 0;
+        }
+        if xsd::nonPositiveInteger == dt {
+            return n <= 0;
+        }
+        if xsd::negativeInteger == dt {
+            return n < 0;
+        }
+        return true;
+    }
+    if xsd::decimal == dt {
+        return lex.parse::<f64>().is_ok() && !lex.contains(['e', 'E', 'N', 'n', 'I', 'i']);
+    }
+    if xsd::double == dt || xsd::float == dt {
+        return matches!(lex, "INF" | "-INF" | "NaN")
+            || (lex.parse::<f64>().is_ok() && !lex.contains(['N', 'n', 'I', 'i']));
+    }
+    if xsd::boolean == dt {
+        return matches!(lex, "true" | "false" | "1" | "0");
+    }
+    true
+}
+
+fn is_integer_type(dt: &Node) -> bool {
+    [
+        xsd::integer,
+        xsd::nonPositiveInteger,
+        xsd::negativeInteger,
+        xsd::long,
+        xsd::int,
+        xsd::short,
+        xsd::byte,
+        xsd::nonNegativeInteger,
+        xsd::unsignedLong,
+        xsd::unsignedInt,
+        xsd::unsignedShort,
+        xsd::unsignedByte,
+        xsd::positiveInteger,
+    ]
+    .iter()
+    .any(|t| t == dt)
+}
+
+fn is_numeric_type(dt: &Node) -> bool {
+    is_integer_type(dt) || xsd::decimal == dt || xsd::double == dt || xsd::float == dt
+}
+
+/// Whether `node` has the given `sh:nodeKind`.
+fn has_node_kind(node: &Node, kind: &Node) -> bool {
+    let (iri, bnode, literal) = match node.kind() {
+        TermKind::Iri => (true, false, false),
+        TermKind::BlankNode => (false, true, false),
+        TermKind::Literal => (false, false, true),
+        _ => (false, false, false),
+    };
+    (sh::IRI == kind && iri)
+        || (sh::BlankNode == kind && bnode)
+        || (sh::Literal == kind && literal)
+        || (sh::BlankNodeOrIRI == kind && (bnode || iri))
+        || (sh::BlankNodeOrLiteral == kind && (bnode || literal))
+        || (sh::IRIOrLiteral == kind && (iri || literal))
+}
+
+/// The string value of `node`, as used by `sh:minLength`, `sh:maxLength` and `sh:pattern`
+/// (`None` for blank nodes).
+fn string_value(node: &Node) -> Option<String> {
+    match node {
+        SimpleTerm::Iri(iri) => Some(iri.as_str().to_string()),
+        _ => node.lexical_form().map(|lex| lex.to_string()),
+    }
+}
+
+/// Compare two literals.
+///
+/// Numeric literals are compared by value;
+/// other literals are compared by lexical form, provided they have the same datatype.
+/// Other pairs of terms are incomparable.
+fn compare(a: &Node, b: &Node) -> Option<Ordering> {
+    let (Some(lex_a), Some(dt_a)) = (a.lexical_form(), a.datatype()) else {
+        return None;
+    };
+    let (Some(lex_b), Some(dt_b)) = (b.lexical_form(), b.datatype()) else {
+        return None;
+    };
+    let num_a: Node = dt_a.clone().into_term();
+    let num_b: Node = dt_b.clone().into_term();
+    if is_numeric_type(&num_a) && is_numeric_type(&num_b) {
+        let x = lex_a.trim().parse::<f64>().ok()?;
+        let y = lex_b.trim().parse::<f64>().ok()?;
+        x.partial_cmp(&y)
+    } else if dt_a == dt_b && a.language_tag().is_none() && b.language_tag().is_none() {
+        Some(lex_a.cmp(&lex_b))
+    } else {
+        None
+    }
+}
+
+/// The values that do not compare to `bound` in a way satisfying `ok`.
+fn compare_all<'a>(
+    values: &'a Nodes,
+    bound: &'a Node,
+    ok: impl Fn(Ordering) -> bool + 'a,
+) -> impl Iterator<Item = Option<Node>> + 'a {
+    values
+        .iter()
+        .filter(move |v| !compare(v, bound).is_some_and(&ok))
+        .map(|v| Some(v.clone()))
+}
+
+/// Basic language range matching, as defined in [RFC 4647](https://www.rfc-editor.org/rfc/rfc4647#section-3.3.1).
+fn lang_matches(tag: &str, range: &str) -> bool {
+    if range == "*" {
+        return !tag.is_empty();
+    }
+    let tag = tag.to_ascii_lowercase();
+    let range = range.to_ascii_lowercase();
+    tag == range || (tag.starts_with(&range) && tag.as_bytes()[range.len()] == b'-')
+}
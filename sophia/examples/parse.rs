@@ -7,6 +7,9 @@
 //!
 //! The base IRI can be overridden via the environment variable SOPHIA_BASE.
 //!
+//! Compressed input is transparently decompressed
+//! (if compiled with the `gzip`, `bzip2` or `zstd` feature, respectively).
+//!
 //! Recognized formats are:
 //! - [`ntriples`](https://www.w3.org/TR/n-triples/) (alias `nt`)
 //! - [`turtle`](https://www.w3.org/TR/turtle/) (alias `ttl`)
@@ -23,6 +26,7 @@
 use std::fs::File;
 use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, Read, Stdin};
 
+use sophia::api::compression::{decompress, Decompress};
 use sophia::api::prelude::*;
 use sophia::api::source::StreamError::{SinkError, SourceError};
 #[cfg(feature = "jsonld")]
@@ -113,15 +117,17 @@ fn dump_quads<P: QuadParser<Input>>(input: Input, p: P) -> Result<(), String> {
 }
 
 enum Input {
-    Stdin(BufReader<Stdin>),
-    File(BufReader<File>),
+    Stdin(Decompress<BufReader<Stdin>>),
+    File(Decompress<BufReader<File>>),
 }
 
 impl Input {
     fn new(path: Option<String>) -> Self {
         match path {
-            None => Self::Stdin(BufReader::new(stdin())),
-            Some(path) => Self::File(BufReader::new(File::open(path).expect("Can not open file"))),
+            None => Self::Stdin(decompress(BufReader::new(stdin()))),
+            Some(path) => Self::File(decompress(BufReader::new(
+                File::open(path).expect("Can not open file"),
+            ))),
         }
     }
 }
@@ -9,6 +9,11 @@
 //! - [`jsonld`](https://www.w3.org/TR/json-ld11) (if compiled with the `jsonld` feature)
 //! - [`rdfxml`](https://www.w3.org/TR/rdf-syntax-grammar) (if compiled with the `xml` feature, alias `rdf`)
 //!
+//! Compressed input is transparently decompressed,
+//! and the output is compressed if the environment variable SOPHIA_COMPRESS
+//! is set to `gz`, `bz2` or `zst`
+//! (if compiled with the `gzip`, `bzip2` or `zstd` feature, respectively).
+//!
 //! NB: if the input is a dataset with named graphs,
 //! and the output format is a graph format,
 //! then only the default graph is serialized.
@@ -18,6 +23,7 @@
 
 use std::io::{stdin, stdout, BufReader, BufWriter};
 
+use sophia::api::compression::{compress, Compression};
 use sophia::api::prelude::*;
 use sophia::api::source::StreamError::{SinkError, SourceError};
 #[cfg(feature = "jsonld")]
@@ -34,8 +40,11 @@ use sophia::xml::serializer::{RdfXmlConfig, RdfXmlSerializer};
 
 fn main() {
     let input = BufReader::new(stdin());
-    let quad_source = gnq::parse_bufread(input);
-    let out = BufWriter::new(stdout());
+    let quad_source = gnq::parse_bufread_decompressed(input);
+    let compression = std::env::var("SOPHIA_COMPRESS").ok().map(|ext| {
+        Compression::from_extension(&ext).expect("Unrecognized compression in SOPHIA_COMPRESS")
+    });
+    let out = compress(BufWriter::new(stdout()), compression).expect("Can not compress output");
     let pretty: bool = std::env::var("SOPHIA_PRETTY")
         .unwrap_or_else(|_| "false".into())
         .parse()
@@ -0,0 +1,854 @@
+//! The `sophia` command-line tool.
+//!
+//! Run `sophia --help` for the list of commands and options.
+//!
+//! Input and output files are read from and written to the standard input/output
+//! when omitted (or given as `-`).
+//! Their format is guessed from their extension,
+//! unless specified with `--from` or `--to`.
+//! Compressed inputs are transparently decompressed,
+//! and outputs are compressed according to their extension
+//! (if Sophia is compiled with the `gzip`, `bzip2` or `zstd` feature).
+//!
+//! The exit code is 0 on success,
+//! 1 when the answer of the command is negative
+//! (non-isomorphic inputs for `iso`, invalid data for `validate`),
+//! and 2 when an error occurred.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use sophia::api::compression::{compress, decompress, Compress, Compression};
+use sophia::api::prefix::{Prefix, PrefixDiscovery, PrefixMapPair};
+use sophia::api::prelude::*;
+use sophia::api::quad::Spog;
+use sophia::api::sparql::results::{QueryResults, ResultsSerializer};
+use sophia::api::sparql::SparqlResult;
+use sophia::api::term::SimpleTerm;
+use sophia::c14n::hash::{Sha256, Sha384};
+use sophia::c14n::rdfc10::{self, DEFAULT_DEPTH_FACTOR, DEFAULT_PERMUTATION_LIMIT};
+use sophia::format::{
+    box_quads, box_triples, registry, serializer_error, BoxQuadSource, Format, FormatError,
+};
+use sophia::inmem::dataset::LightDataset;
+use sophia::iri::Iri;
+use sophia::isomorphism::isomorphic_datasets;
+#[cfg(feature = "jsonld")]
+use sophia::jsonld::{serializer::JsonLdSerializer, JsonLdOptions};
+use sophia::sparql::results::{
+    csv::CsvResultsSerializer, json::JsonResultsSerializer, tsv::TsvResultsSerializer,
+    xml::XmlResultsSerializer,
+};
+use sophia::sparql::SparqlWrapper;
+use sophia::turtle::format::{TRIG, TURTLE};
+use sophia::turtle::serializer::trig::TrigSerializer;
+use sophia::turtle::serializer::turtle::{TurtleConfig, TurtleSerializer};
+#[cfg(feature = "xml")]
+use sophia::xml::{
+    format::RDF_XML,
+    serializer::{RdfXmlConfig, RdfXmlSerializer},
+};
+
+const USAGE: &str = "\
+Usage: sophia <COMMAND> [OPTIONS] [ARGS]
+
+Commands:
+  convert [INPUT]            Convert INPUT to another format
+  c14n [INPUT]               Canonicalize INPUT with RDFC-1.0, in N-Quads
+  iso INPUT1 INPUT2          Check whether INPUT1 and INPUT2 are isomorphic
+  stats [INPUT]              Print statistics about INPUT
+  query QUERY [INPUT...]     Run a SPARQL query against the union of the INPUTs
+  validate [INPUT]           Check the syntax of INPUT (and its conformance, with --shapes)
+  formats                    List the supported formats
+
+Options:
+  -f, --from <FORMAT>        Format of the input(s) (name, extension or media type)
+  -t, --to <FORMAT>          Format of the output; for SELECT and ASK queries,
+                             one of json, xml, csv or tsv
+                             (default: tsv for SELECT, json for ASK)
+  -o, --output <FILE>        Write to FILE rather than the standard output
+  -b, --base <IRI>           Base IRI of the input(s)
+  -p, --prefix <PFX>=<IRI>   Declare a prefix in Turtle, TriG or RDF/XML output (repeatable)
+      --auto-prefixes        Declare prefixes for the most frequent namespaces
+                             (requires to load the whole input in memory)
+      --pretty               Pretty-print the output (Turtle, TriG, RDF/XML, JSON-LD)
+      --hash <HASH>          Hash function for c14n: sha256 (default) or sha384
+      --depth-factor <F>     Depth factor for c14n
+      --permutation-limit <N>
+                             Permutation limit for c14n
+  -s, --shapes <FILE>        SHACL shapes graph for validate
+  -q, --query-file <FILE>    Read the query from FILE rather than from the arguments
+  -h, --help                 Print this help
+  -V, --version              Print the version
+
+Exit status: 0 on success, 1 on a negative answer (iso, validate), 2 on error.
+";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let res = match args.next().as_deref() {
+        Some("convert") => convert(args),
+        Some("c14n") => c14n(args),
+        Some("iso") => iso(args),
+        Some("stats") => stats(args),
+        Some("query") => query(args),
+        Some("validate") => validate(args),
+        Some("formats") => formats(args),
+        Some("help" | "-h" | "--help") => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        Some("-V" | "--version") => {
+            println!("sophia {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        Some(other) => Err(Failure::Usage(format!("unknown command {other:?}"))),
+        None => Err(Failure::Usage("missing command".into())),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Negative) => ExitCode::from(1),
+        Err(Failure::Usage(msg)) => {
+            eprintln!("sophia: {msg}\nTry 'sophia --help' for more information.");
+            ExitCode::from(2)
+        }
+        Err(Failure::Error(msg)) => {
+            eprintln!("sophia: {msg}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      commands
+// ---------------------------------------------------------------------------------
+
+fn convert(args: impl Iterator<Item = String>) -> CliResult {
+    let mut opts = Options::parse(
+        args,
+        &[
+            "from",
+            "to",
+            "output",
+            "base",
+            "prefix",
+            "auto-prefixes",
+            "pretty",
+        ],
+    )?;
+    let input = Input::open(opts.single_input()?, opts.from.as_deref(), &opts)?;
+    let format = opts
+        .output_format()?
+        .ok_or_else(|| Failure::Usage("can not guess the output format, use --to".into()))?;
+    let name = input.name.clone();
+    let quads = if opts.auto_prefixes {
+        let data: Vec<_> = input.load()?;
+        opts.prefixes = PrefixDiscovery::new()
+            .with_known_prefixes(&opts.prefixes[..])
+            .dataset(&data)
+            .unwrap_or_else(|err| match err {});
+        box_quads(data.into_iter().map(Ok::<_, Infallible>))
+    } else {
+        input.quads()?
+    };
+    let mut output = Output::open(&opts)?;
+    serialize(format, &opts, &mut output.write, quads).map_err(|err| match err {
+        FormatError::Parse(_) => error(&name, err),
+        _ => error(&output.name, err),
+    })?;
+    output.finish()
+}
+
+fn c14n(args: impl Iterator<Item = String>) -> CliResult {
+    let opts = Options::parse(
+        args,
+        &[
+            "from",
+            "output",
+            "base",
+            "hash",
+            "depth-factor",
+            "permutation-limit",
+        ],
+    )?;
+    let input = Input::open(opts.single_input()?, opts.from.as_deref(), &opts)?;
+    let dataset: HashSet<_> = input.load()?;
+    let depth_factor = opts.depth_factor.unwrap_or(DEFAULT_DEPTH_FACTOR);
+    let permutation_limit = opts.permutation_limit.unwrap_or(DEFAULT_PERMUTATION_LIMIT);
+    let mut output = Output::open(&opts)?;
+    let w = &mut output.write;
+    let res = match squash(opts.hash.as_deref().unwrap_or("sha256")).as_str() {
+        "sha256" => {
+            rdfc10::normalize_with::<Sha256, _, _>(&dataset, w, depth_factor, permutation_limit)
+        }
+        "sha384" => {
+            rdfc10::normalize_with::<Sha384, _, _>(&dataset, w, depth_factor, permutation_limit)
+        }
+        _ => {
+            return Err(Failure::Usage(
+                "unknown hash function, expected sha256 or sha384".into(),
+            ))
+        }
+    };
+    res.map_err(|err| error(&output.name, err))?;
+    output.finish()
+}
+
+fn iso(args: impl Iterator<Item = String>) -> CliResult {
+    let opts = Options::parse(args, &["from", "base"])?;
+    let [path1, path2] = &opts.positional[..] else {
+        return Err(Failure::Usage("iso expects exactly two inputs".into()));
+    };
+    if path1 == "-" && path2 == "-" {
+        return Err(Failure::Usage(
+            "only one input can be read from stdin".into(),
+        ));
+    }
+    let d1: Vec<_> = Input::open(Some(path1), opts.from.as_deref(), &opts)?.load()?;
+    let d2: Vec<_> = Input::open(Some(path2), opts.from.as_deref(), &opts)?.load()?;
+    if isomorphic_datasets(&d1, &d2).map_err(|err| error("iso", err))? {
+        println!("{path1} and {path2} are isomorphic");
+        Ok(())
+    } else {
+        println!("{path1} and {path2} are not isomorphic");
+        Err(Failure::Negative)
+    }
+}
+
+fn stats(args: impl Iterator<Item = String>) -> CliResult {
+    let opts = Options::parse(args, &["from", "base"])?;
+    let input = Input::open(opts.single_input()?, opts.from.as_deref(), &opts)?;
+    let name = input.name.clone();
+    let format = input.format.name;
+    let mut quads = 0;
+    let mut default_graph = 0;
+    let [mut graphs, mut subjects, mut predicates, mut objects]: [HashSet<_>; 4] =
+        Default::default();
+    let [mut iris, mut bnodes, mut literals, mut triple_terms]: [HashSet<_>; 4] =
+        Default::default();
+    for quad in input.quads()? {
+        let ([s, p, o], g) = quad.map_err(|err| error(&name, err))?;
+        quads += 1;
+        match &g {
+            None => default_graph += 1,
+            Some(g) => {
+                graphs.insert(g.clone());
+            }
+        }
+        for t in [&s, &p, &o].into_iter().chain(g.as_ref()) {
+            let set: &mut HashSet<_> = match t.kind() {
+                TermKind::Iri => &mut iris,
+                TermKind::BlankNode => &mut bnodes,
+                TermKind::Literal => &mut literals,
+                TermKind::Triple => &mut triple_terms,
+                TermKind::Variable => continue,
+            };
+            set.insert(t.clone());
+        }
+        subjects.insert(s);
+        predicates.insert(p);
+        objects.insert(o);
+    }
+    println!("format: {format}");
+    println!("quads: {quads}");
+    println!("triples in default graph: {default_graph}");
+    println!("named graphs: {}", graphs.len());
+    println!("distinct subjects: {}", subjects.len());
+    println!("distinct predicates: {}", predicates.len());
+    println!("distinct objects: {}", objects.len());
+    println!("distinct IRIs: {}", iris.len());
+    println!("distinct blank nodes: {}", bnodes.len());
+    println!("distinct literals: {}", literals.len());
+    println!("distinct triple terms: {}", triple_terms.len());
+    Ok(())
+}
+
+fn query(args: impl Iterator<Item = String>) -> CliResult {
+    let opts = Options::parse(
+        args,
+        &[
+            "from",
+            "to",
+            "output",
+            "base",
+            "prefix",
+            "pretty",
+            "query-file",
+        ],
+    )?;
+    let (query, paths) = match &opts.query_file {
+        Some(path) => (
+            std::fs::read_to_string(path).map_err(|err| error(path, err))?,
+            &opts.positional[..],
+        ),
+        None => match opts.positional.split_first() {
+            Some((query, paths)) => (query.clone(), paths),
+            None => return Err(Failure::Usage("missing query".into())),
+        },
+    };
+    let mut dataset = LightDataset::new();
+    let paths: Vec<_> = match paths {
+        [] => vec![None],
+        paths => paths.iter().map(|p| Some(p.as_str())).collect(),
+    };
+    for path in paths {
+        let input = Input::open(path, opts.from.as_deref(), &opts)?;
+        let name = input.name.clone();
+        dataset
+            .insert_all(input.quads()?)
+            .map_err(|err| error(&name, err))?;
+    }
+    let result = SparqlWrapper(&dataset)
+        .query(query.as_str())
+        .map_err(|err| error("query", err))?;
+    let mut output = Output::open(&opts)?;
+    let w = &mut output.write;
+    let res = match result {
+        SparqlResult::Triples(triples) => {
+            let format = opts.output_format()?.unwrap_or(&TURTLE);
+            let triples: Vec<_> = triples
+                .collect::<Result<_, _>>()
+                .map_err(|err| error("query", err))?;
+            let triples = box_triples(triples.into_iter().map(Ok::<_, Infallible>));
+            serialize(format, &opts, w, triples).map_err(|err| err.to_string())
+        }
+        SparqlResult::Bindings(bindings) => write_results(&opts, w, &bindings.into())?,
+        SparqlResult::Boolean(b) => {
+            write_results(&opts, w, &QueryResults::<SimpleTerm>::Boolean(b))?
+        }
+    };
+    res.map_err(|err| error(&output.name, err))?;
+    output.finish()
+}
+
+fn validate(args: impl Iterator<Item = String>) -> CliResult {
+    let opts = Options::parse(
+        args,
+        &["from", "to", "output", "base", "prefix", "pretty", "shapes"],
+    )?;
+    let input = Input::open(opts.single_input()?, opts.from.as_deref(), &opts)?;
+    let name = input.name.clone();
+    let format = input.format.name;
+    let data: Vec<Spog<SimpleTerm<'static>>> = match input.load() {
+        Ok(data) => data,
+        Err(Failure::Error(msg)) => {
+            eprintln!("sophia: {msg}");
+            return Err(Failure::Negative);
+        }
+        Err(other) => return Err(other),
+    };
+    let Some(shapes) = &opts.shapes else {
+        println!("{name} is valid {format}");
+        return Ok(());
+    };
+    let shapes_graph: Vec<_> = Input::open(Some(shapes), None, &opts)?
+        .load::<Vec<_>>()?
+        .into_iter()
+        .filter_map(|(spo, g)| g.is_none().then_some(spo))
+        .collect();
+    let data_graph: Vec<_> = data
+        .into_iter()
+        .filter_map(|(spo, g)| g.is_none().then_some(spo))
+        .collect();
+    let report =
+        sophia::shacl::validate(&shapes_graph, &data_graph).map_err(|err| error(shapes, err))?;
+    if report.conforms() {
+        println!("{name} conforms to {shapes}");
+        return Ok(());
+    }
+    let format = opts.output_format()?.unwrap_or(&TURTLE);
+    let mut output = Output::open(&opts)?;
+    let triples = report.to_graph().into_iter().map(Ok::<_, Infallible>);
+    serialize(format, &opts, &mut output.write, box_triples(triples))
+        .map_err(|err| error(&output.name, err))?;
+    output.finish()?;
+    Err(Failure::Negative)
+}
+
+fn formats(args: impl Iterator<Item = String>) -> CliResult {
+    Options::parse(args, &[])?.single_input()?;
+    for format in registry().formats() {
+        let mut capabilities = vec![];
+        if format.parser.is_some() {
+            capabilities.push("read");
+        }
+        if format.serializer.is_some() {
+            capabilities.push("write");
+        }
+        if format.dataset {
+            capabilities.push("named graphs");
+        }
+        println!(
+            "{:<10} {:<24} {:<12} {}",
+            format.name,
+            format.media_type(),
+            format.extensions.join(","),
+            capabilities.join(", "),
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      helpers
+// ---------------------------------------------------------------------------------
+
+/// The ways a command can fail.
+#[derive(Debug)]
+enum Failure {
+    /// The command line is invalid
+    Usage(String),
+    /// The command could not complete
+    Error(String),
+    /// The command completed, with a negative answer
+    Negative,
+}
+
+type CliResult<T = ()> = Result<T, Failure>;
+
+/// Build a [`Failure::Error`] with the given context (typically a file name).
+fn error(context: impl Display, err: impl Display) -> Failure {
+    Failure::Error(format!("{context}: {err}"))
+}
+
+/// The options and positional arguments of a command.
+#[derive(Debug, Default)]
+struct Options {
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<String>,
+    base: Option<Iri<String>>,
+    prefixes: Vec<PrefixMapPair>,
+    auto_prefixes: bool,
+    pretty: bool,
+    hash: Option<String>,
+    depth_factor: Option<f32>,
+    permutation_limit: Option<usize>,
+    shapes: Option<String>,
+    query_file: Option<String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    /// Parse `args`, accepting only the (long names of) options in `allowed`.
+    fn parse(args: impl IntoIterator<Item = String>, allowed: &[&str]) -> CliResult<Self> {
+        let mut opts = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.strip_prefix("--") {
+                Some("") => {
+                    opts.positional.extend(args);
+                    break;
+                }
+                Some(long) => match long.split_once('=') {
+                    Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                    None => (long.to_string(), None),
+                },
+                None if arg.len() > 1 && arg.starts_with('-') => (short_option(&arg)?, None),
+                None => {
+                    opts.positional.push(arg);
+                    continue;
+                }
+            };
+            if name == "help" {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            if !allowed.contains(&name.as_str()) {
+                return Err(Failure::Usage(format!("unexpected option {arg:?}")));
+            }
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Failure::Usage(format!("option --{name} requires a value")))
+            };
+            match name.as_str() {
+                "from" => opts.from = Some(value()?),
+                "to" => opts.to = Some(value()?),
+                "output" => opts.output = Some(value()?),
+                "base" => {
+                    let iri = Iri::new(value()?);
+                    opts.base = Some(iri.map_err(|err| Failure::Usage(err.to_string()))?);
+                }
+                "prefix" => opts.prefixes.push(parse_prefix(&value()?)?),
+                "auto-prefixes" if inline.is_none() => opts.auto_prefixes = true,
+                "pretty" if inline.is_none() => opts.pretty = true,
+                "hash" => opts.hash = Some(value()?),
+                "depth-factor" => opts.depth_factor = Some(parse_number(&name, &value()?)?),
+                "permutation-limit" => {
+                    opts.permutation_limit = Some(parse_number(&name, &value()?)?)
+                }
+                "shapes" => opts.shapes = Some(value()?),
+                "query-file" => opts.query_file = Some(value()?),
+                _ => return Err(Failure::Usage(format!("unexpected option {arg:?}"))),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// The single (optional) positional argument of the command.
+    fn single_input(&self) -> CliResult<Option<&str>> {
+        match &self.positional[..] {
+            [] => Ok(None),
+            [input] => Ok(Some(input)),
+            [_, extra, ..] => Err(Failure::Usage(format!("unexpected argument {extra:?}"))),
+        }
+    }
+
+    /// The output format, as specified by `--to` or guessed from `--output`.
+    fn output_format(&self) -> CliResult<Option<&'static Format>> {
+        match (&self.to, &self.output) {
+            (Some(name), _) => lookup_format(name).map(Some),
+            (None, Some(path)) => registry()
+                .by_path(path)
+                .map(Some)
+                .ok_or_else(|| Failure::Usage(format!("can not guess the format of {path}"))),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Convert a short option to the corresponding long name.
+fn short_option(arg: &str) -> CliResult<String> {
+    let name = match arg {
+        "-f" => "from",
+        "-t" => "to",
+        "-o" => "output",
+        "-b" => "base",
+        "-p" => "prefix",
+        "-s" => "shapes",
+        "-q" => "query-file",
+        "-h" => "help",
+        _ => return Err(Failure::Usage(format!("unexpected option {arg:?}"))),
+    };
+    Ok(name.to_string())
+}
+
+/// Parse a prefix declaration of the form `PFX=IRI`.
+fn parse_prefix(txt: &str) -> CliResult<PrefixMapPair> {
+    let invalid = || Failure::Usage(format!("invalid prefix declaration {txt:?}"));
+    let (prefix, iri) = txt.split_once('=').ok_or_else(invalid)?;
+    let prefix = Prefix::new(Box::from(prefix)).map_err(|_| invalid())?;
+    let iri = Iri::new(Box::from(iri)).map_err(|_| invalid())?;
+    Ok((prefix, iri))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, txt: &str) -> CliResult<T> {
+    txt.parse()
+        .map_err(|_| Failure::Usage(format!("invalid value {txt:?} for --{name}")))
+}
+
+/// Lowercase `txt` and remove all its non-alphanumeric characters.
+fn squash(txt: &str) -> String {
+    txt.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Find a format by name (ignoring case and punctuation), extension or media type.
+fn lookup_format(name: &str) -> CliResult<&'static Format> {
+    let registry = registry();
+    registry
+        .formats()
+        .find(|format| squash(format.name) == squash(name))
+        .or_else(|| registry.by_extension(name))
+        .or_else(|| registry.by_media_type(name))
+        .ok_or_else(|| Failure::Usage(format!("unknown format {name:?}, see 'sophia formats'")))
+}
+
+/// An RDF input to a command.
+struct Input {
+    name: String,
+    format: &'static Format,
+    read: Box<dyn BufRead>,
+    base: Option<Iri<String>>,
+}
+
+impl Input {
+    /// Open the file at `path` (or the standard input if `None` or `-`),
+    /// in format `from` (or the format guessed from the extension of `path`).
+    fn open(path: Option<&str>, from: Option<&str>, opts: &Options) -> CliResult<Self> {
+        let path = path.filter(|p| *p != "-");
+        let name = path.unwrap_or("<stdin>").to_string();
+        let format = match (from, path) {
+            (Some(from), _) => lookup_format(from)?,
+            (None, Some(path)) => registry()
+                .by_path(path)
+                .ok_or_else(|| Failure::Usage(format!("can not guess the format of {path}")))?,
+            (None, None) => {
+                return Err(Failure::Usage(
+                    "can not guess the format of stdin, use --from".into(),
+                ))
+            }
+        };
+        let (read, base): (Box<dyn BufRead>, _) = match path {
+            Some(path) => {
+                let file = File::open(path).map_err(|err| error(path, err))?;
+                let base = opts.base.clone().or_else(|| file_iri(Path::new(path)));
+                (Box::new(decompress(BufReader::new(file))), base)
+            }
+            None => (Box::new(decompress(stdin().lock())), opts.base.clone()),
+        };
+        Ok(Input {
+            name,
+            format,
+            read,
+            base,
+        })
+    }
+
+    /// Parse this input.
+    fn quads(self) -> CliResult<BoxQuadSource<'static>> {
+        self.format
+            .parse(self.read, self.base)
+            .map_err(|err| error(&self.name, err))
+    }
+
+    /// Parse this input into a collection of quads.
+    fn load<C: FromIterator<Spog<SimpleTerm<'static>>>>(self) -> CliResult<C> {
+        let name = self.name.clone();
+        self.quads()?
+            .collect::<Result<C, _>>()
+            .map_err(|err| error(&name, err))
+    }
+}
+
+/// The `file:` IRI of `path`, used as the default base IRI of file inputs.
+fn file_iri(path: &Path) -> Option<Iri<String>> {
+    let path = path.canonicalize().ok()?;
+    Iri::new(format!("file://{}", path.to_str()?)).ok()
+}
+
+/// The output of a command.
+struct Output {
+    name: String,
+    write: Compress<Box<dyn Write>>,
+}
+
+impl Output {
+    /// Create the file specified by `--output` (or use the standard output),
+    /// compressed according to its extension.
+    fn open(opts: &Options) -> CliResult<Self> {
+        let path = opts.output.as_deref().filter(|p| *p != "-");
+        let name = path.unwrap_or("<stdout>").to_string();
+        let (write, compression): (Box<dyn Write>, _) = match path {
+            Some(path) => {
+                let file = File::create(path).map_err(|err| error(path, err))?;
+                let compression = Compression::from_path(Path::new(path)).0;
+                (Box::new(BufWriter::new(file)), compression)
+            }
+            None => (Box::new(BufWriter::new(stdout())), None),
+        };
+        let write = compress(write, compression).map_err(|err| error(&name, err))?;
+        Ok(Output { name, write })
+    }
+
+    /// Finish the compressed stream (if any) and flush the output.
+    fn finish(self) -> CliResult {
+        self.write
+            .finish()
+            .and_then(|mut write| write.flush())
+            .map_err(|err| error(&self.name, err))
+    }
+}
+
+/// Serialize `quads` in `format`,
+/// honouring the `--pretty`, `--prefix` and `--auto-prefixes` options.
+fn serialize(
+    format: &Format,
+    opts: &Options,
+    write: &mut dyn Write,
+    quads: BoxQuadSource<'_>,
+) -> Result<(), FormatError> {
+    let prefixes = || {
+        let mut prefixes = if opts.auto_prefixes {
+            vec![]
+        } else {
+            TurtleConfig::default_prefix_map()
+        };
+        prefixes.retain(|(p1, _)| opts.prefixes.iter().all(|(p2, _)| p1 != p2));
+        prefixes.extend(opts.prefixes.iter().cloned());
+        prefixes
+    };
+    let config = || {
+        TurtleConfig::new()
+            .with_pretty(opts.pretty)
+            .with_own_prefix_map(prefixes())
+    };
+    if format.name == TURTLE.name {
+        let triples = quads
+            .filter_quads(|q| q.g().is_none())
+            .map_quads(Quad::into_triple);
+        return serializer_error(
+            TurtleSerializer::new_with_config(write, config()).serialize_triples(triples),
+        );
+    }
+    if format.name == TRIG.name {
+        return serializer_error(
+            TrigSerializer::new_with_config(write, config()).serialize_quads(quads),
+        );
+    }
+    #[cfg(feature = "xml")]
+    if format.name == RDF_XML.name {
+        let triples = quads
+            .filter_quads(|q| q.g().is_none())
+            .map_quads(Quad::into_triple);
+        let config = RdfXmlConfig::new()
+            .with_pretty(opts.pretty)
+            .with_indentation(if opts.pretty { 4 } else { 0 })
+            .with_own_prefix_map(prefixes());
+        return serializer_error(
+            RdfXmlSerializer::new_with_config(write, config).serialize_triples(triples),
+        );
+    }
+    if !opts.prefixes.is_empty() {
+        eprintln!("sophia: warning: --prefix is ignored for {}", format.name);
+    }
+    if opts.pretty {
+        #[cfg(feature = "jsonld")]
+        if format.name == sophia::jsonld::format::JSON_LD.name {
+            let options = JsonLdOptions::new().with_spaces(2);
+            return serializer_error(
+                JsonLdSerializer::new_with_options(write, options).serialize_quads(quads),
+            );
+        }
+        eprintln!("sophia: warning: --pretty is ignored for {}", format.name);
+    }
+    // calling the factory directly, as `Format::serialize` would wrap parse errors twice
+    let serializer = format
+        .serializer
+        .ok_or(FormatError::NoSerializer(format.name))?;
+    serializer(write, quads)
+}
+
+/// Serialize `results` in the format specified by `--to`
+/// (or guessed from the extension of `--output`,
+/// defaulting to JSON for boolean results, which TSV can not represent, and TSV otherwise).
+fn write_results(
+    opts: &Options,
+    write: &mut dyn Write,
+    results: &QueryResults<impl Term>,
+) -> CliResult<Result<(), String>> {
+    let extension = opts.output.as_deref().and_then(|path| {
+        let path = Compression::from_path(Path::new(path)).1;
+        Some(path.extension()?.to_str()?.to_string())
+    });
+    let default = match results {
+        QueryResults::Boolean(_) => "json",
+        QueryResults::Bindings { .. } => "tsv",
+    };
+    let format = opts
+        .to
+        .as_deref()
+        .or(extension.as_deref())
+        .unwrap_or(default);
+    let res = match squash(format).as_str() {
+        "json" | "srj" | "applicationsparqlresultsjson" => JsonResultsSerializer::new(write)
+            .serialize_results(results)
+            .map(drop),
+        "xml" | "srx" | "applicationsparqlresultsxml" => XmlResultsSerializer::new(write)
+            .serialize_results(results)
+            .map(drop),
+        "csv" | "textcsv" => CsvResultsSerializer::new(write)
+            .serialize_results(results)
+            .map(drop),
+        "tsv" | "texttabseparatedvalues" => TsvResultsSerializer::new(write)
+            .serialize_results(results)
+            .map(drop),
+        _ => {
+            return Err(Failure::Usage(format!(
+                "unknown results format {format:?}, expected json, xml, csv or tsv"
+            )))
+        }
+    };
+    Ok(res.map_err(|err| err.to_string()))
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(txt: &str) -> Vec<String> {
+        txt.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options() {
+        let opts = Options::parse(
+            args("-f ttl --to=nq in.ttl --pretty -p ex=tag:ex/ -- -"),
+            &["from", "to", "pretty", "prefix"],
+        )
+        .unwrap();
+        assert_eq!(opts.from.as_deref(), Some("ttl"));
+        assert_eq!(opts.to.as_deref(), Some("nq"));
+        assert!(opts.pretty);
+        assert_eq!(opts.prefixes.len(), 1);
+        assert_eq!(opts.prefixes[0].0.as_str(), "ex");
+        assert_eq!(opts.positional, vec!["in.ttl", "-"]);
+        assert!(opts.single_input().is_err());
+    }
+
+    #[test]
+    fn options_errors() {
+        for (txt, allowed) in [
+            ("--shapes x", &["from"][..]),
+            ("-x", &["from"][..]),
+            ("--from", &["from"][..]),
+            ("--pretty=yes", &["pretty"][..]),
+            ("--prefix ex", &["prefix"][..]),
+            ("--base foo", &["base"][..]),
+            ("--depth-factor x", &["depth-factor"][..]),
+        ] {
+            let res = Options::parse(args(txt), allowed);
+            assert!(matches!(res, Err(Failure::Usage(_))), "{txt}");
+        }
+    }
+
+    #[test]
+    fn formats() {
+        for name in ["turtle", "TTL", "text/turtle", "Turtle"] {
+            assert_eq!(lookup_format(name).unwrap().name, "Turtle", "{name}");
+        }
+        for name in ["ntriples", "nt", "N-Triples", "application/n-triples"] {
+            assert_eq!(lookup_format(name).unwrap().name, "N-Triples", "{name}");
+        }
+        assert!(lookup_format("foo").is_err());
+    }
+
+    #[test]
+    fn boolean_results_default_to_json() -> Result<(), Box<dyn std::error::Error>> {
+        let opts = Options::parse(args(""), &["to", "output"]).unwrap();
+        let mut out = vec![];
+        let res = write_results(&opts, &mut out, &QueryResults::<SimpleTerm>::Boolean(true));
+        assert!(matches!(res, Ok(Ok(()))));
+        let out = String::from_utf8(out)?;
+        assert!(
+            out.contains(r#""boolean": true"#) || out.contains(r#""boolean":true"#),
+            "{out}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_with_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let opts = Options::parse(args("--pretty -p ex=tag:ex/"), &["prefix", "pretty"]).unwrap();
+        let quads = TURTLE.parse(&b"<tag:ex/a> <tag:ex/b> <tag:ex/c>."[..], None)?;
+        let mut out = vec![];
+        serialize(&TURTLE, &opts, &mut out, quads)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("PREFIX ex: <tag:ex/>"), "{out}");
+        assert!(out.contains("ex:c"), "{out}");
+        Ok(())
+    }
+}
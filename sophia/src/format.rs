@@ -0,0 +1,76 @@
+//! A [registry](FormatRegistry) of all the RDF concrete syntaxes supported by Sophia,
+//! depending on the enabled features.
+//!
+//! ```
+//! # use sophia::api::prelude::*;
+//! # use sophia::inmem::dataset::LightDataset;
+//! use sophia::format::{negotiate, parse_any};
+//!
+//! let data = "<tag:s> <tag:p> <tag:o> .";
+//! let dataset: LightDataset = parse_any(data.as_bytes(), "text/turtle", None)?.collect_quads()?;
+//! assert_eq!(dataset.quads().count(), 1);
+//!
+//! let format = negotiate("text/html;q=0.9, application/n-triples;q=0.8, */*;q=0.1");
+//! assert_eq!(format.unwrap().media_type(), "application/n-triples");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use sophia_iri::Iri;
+use std::io::{BufRead, Write};
+use std::sync::OnceLock;
+
+pub use sophia_api::format::*;
+use sophia_api::source::QuadSource;
+
+/// The registry of all the formats supported by Sophia.
+///
+/// It contains the Turtle family of formats,
+/// as well as RDF/XML and JSON-LD
+/// when the `xml` and `jsonld` features (respectively) are enabled.
+///
+/// To add your own formats, [clone](Clone) it and [register](FormatRegistry::register) them.
+pub fn registry() -> &'static FormatRegistry {
+    static REGISTRY: OnceLock<FormatRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = FormatRegistry::new();
+        sophia_turtle::format::register(&mut registry);
+        #[cfg(feature = "xml")]
+        sophia_xml::format::register(&mut registry);
+        #[cfg(feature = "jsonld")]
+        sophia_jsonld::format::register(&mut registry);
+        registry
+    })
+}
+
+/// Parse `read` with the format identified by `media_type`,
+/// using `base` to resolve relative IRIs.
+///
+/// See [`FormatRegistry::parse_any`].
+pub fn parse_any<'a, R: BufRead + 'a>(
+    read: R,
+    media_type: &str,
+    base: Option<Iri<String>>,
+) -> Result<BoxQuadSource<'a>, FormatError> {
+    registry().parse_any(read, media_type, base)
+}
+
+/// Serialize `source` into `write` with the format identified by `media_type`.
+///
+/// See [`FormatRegistry::serialize_any`].
+pub fn serialize_any<W: Write, S: QuadSource>(
+    write: W,
+    source: S,
+    media_type: &str,
+) -> Result<(), FormatError>
+where
+    S::Error: Send + Sync,
+{
+    registry().serialize_any(write, source, media_type)
+}
+
+/// Find the format best matching the given HTTP `Accept` header.
+///
+/// See [`FormatRegistry::negotiate`].
+pub fn negotiate(accept: &str) -> Option<&'static Format> {
+    registry().negotiate(accept)
+}
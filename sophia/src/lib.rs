@@ -6,15 +6,23 @@
 //!
 //! * [`api`]
 //! * [`c14n`]
+//! * [`disk`] (with the `disk` feature enabled)
+//! * [`hdt`] (with the `hdt` feature enabled)
 //! * [`inmem`]
 //! * [`iri`]
 //! * [`isomorphism`]
-//! * [`jsonld`]
+//! * [`jsonld`] (with the `jsonld` feature enabled)
+//! * [`patch`] (with the `patch` feature enabled)
+//! * [`reasoner`] (with the `reasoner` feature enabled)
 //! * [`resource`]
+//! * [`shacl`] (with the `shacl` feature enabled)
+//! * [`sparql`] (with the `sparql` feature enabled)
 //! * [`turtle`]
 //! * [`term`]
 //! * [`xml`] (with the `xml` feature enabled)
 //!
+//! It also provides a [registry](format) of all the supported RDF concrete syntaxes.
+//!
 //! # Getting Started
 //!
 //! See the [Sophia book](https://pchampin.github.io/sophia_rs/ch01_getting_started.html)
@@ -24,14 +32,28 @@
 //! [Linked Data]: http://linkeddata.org/
 #![deny(missing_docs)]
 
+pub mod format;
+
 pub use sophia_api as api;
 pub use sophia_c14n as c14n;
+#[cfg(feature = "disk")]
+pub use sophia_disk as disk;
+#[cfg(feature = "hdt")]
+pub use sophia_hdt as hdt;
 pub use sophia_inmem as inmem;
 pub use sophia_iri as iri;
 pub use sophia_isomorphism as isomorphism;
 #[cfg(feature = "jsonld")]
 pub use sophia_jsonld as jsonld;
+#[cfg(feature = "patch")]
+pub use sophia_patch as patch;
+#[cfg(feature = "reasoner")]
+pub use sophia_reasoner as reasoner;
 pub use sophia_resource as resource;
+#[cfg(feature = "shacl")]
+pub use sophia_shacl as shacl;
+#[cfg(feature = "sparql")]
+pub use sophia_sparql as sparql;
 pub use sophia_term as term;
 pub use sophia_turtle as turtle;
 #[cfg(feature = "xml")]
@@ -0,0 +1,506 @@
+//! I define the internal representation of SPARQL queries and update requests,
+//! closely following the [SPARQL algebra](https://www.w3.org/TR/sparql11-query/#sparqlAlgebra).
+//!
+//! Variables are not represented by their name,
+//! but by their index in the [variable table](Query::variables) of the query.
+//! This makes solution mappings cheap to build and to compare.
+use std::sync::Arc;
+
+use sophia_iri::resolve::BaseIri;
+use sophia_term::ArcTerm;
+
+/// A parsed SPARQL query.
+#[derive(Clone, Debug)]
+pub struct Query {
+    /// The form of this query (SELECT, CONSTRUCT, ASK or DESCRIBE)
+    pub form: QueryForm,
+    /// The dataset specified by `FROM` and `FROM NAMED` clauses, if any
+    pub dataset: Option<QueryDataset>,
+    /// The graph pattern of this query, including solution modifiers
+    pub pattern: GraphPattern,
+    /// The variable table of this query.
+    ///
+    /// Variables are identified by their index in this table.
+    /// Variables introduced by the parser (e.g. for blank nodes)
+    /// have a name starting with `_:`, which can not clash with SPARQL variable names.
+    pub variables: Vec<Arc<str>>,
+    /// The base IRI of this query, used by the `IRI` function
+    pub base: Option<BaseIri<String>>,
+}
+
+impl Query {
+    /// Return the index of the variable with the given name, if any.
+    pub fn variable_index(&self, name: &str) -> Option<usize> {
+        self.variables.iter().position(|v| &v[..] == name)
+    }
+}
+
+/// The different forms of SPARQL queries.
+#[derive(Clone, Debug)]
+pub enum QueryForm {
+    /// A SELECT query, projecting the given variables
+    Select(Vec<usize>),
+    /// A CONSTRUCT query, with the given template
+    Construct(Vec<[TermPattern; 3]>),
+    /// An ASK query
+    Ask,
+    /// A DESCRIBE query, with the given targets
+    Describe(Vec<TermPattern>),
+}
+
+/// The RDF dataset described by the `FROM` and `FROM NAMED` clauses.
+#[derive(Clone, Debug, Default)]
+pub struct QueryDataset {
+    /// The graphs whose merge constitutes the default graph
+    pub default: Vec<ArcTerm>,
+    /// The named graphs
+    pub named: Vec<ArcTerm>,
+}
+
+/// A parsed SPARQL update request.
+#[derive(Clone, Debug)]
+pub struct Update {
+    /// The operations of this request, in the order in which they must be applied
+    pub operations: Vec<UpdateOperation>,
+    /// The variable table of this request, shared by all its operations
+    /// (see [`Query::variables`]).
+    pub variables: Vec<Arc<str>>,
+    /// The base IRI of this request
+    pub base: Option<BaseIri<String>>,
+}
+
+/// A single operation of a SPARQL update request.
+#[derive(Clone, Debug)]
+pub enum UpdateOperation {
+    /// `INSERT DATA`, with the given ground quads
+    InsertData(Vec<QuadPattern>),
+    /// `DELETE DATA`, with the given ground quads
+    DeleteData(Vec<QuadPattern>),
+    /// `DELETE/INSERT ... WHERE` (including the `DELETE WHERE` short form)
+    DeleteInsert {
+        /// The quads to delete, instantiated with each solution of `query`
+        delete: Vec<QuadPattern>,
+        /// The quads to insert, instantiated with each solution of `query`
+        insert: Vec<QuadPattern>,
+        /// The graph specified by `WITH`, if any
+        with: Option<ArcTerm>,
+        /// The `WHERE` clause, as a query whose dataset is given by the `USING` clauses
+        query: Box<Query>,
+    },
+    /// `CLEAR`
+    ///
+    /// In this and the following operations, the boolean flag is `true` iff `SILENT` was specified.
+    Clear(GraphTarget, bool),
+    /// `DROP`
+    Drop(GraphTarget, bool),
+    /// `CREATE GRAPH`
+    Create(ArcTerm, bool),
+    /// `ADD`, from a graph into another one (`None` standing for the default graph)
+    Add(Option<ArcTerm>, Option<ArcTerm>, bool),
+    /// `MOVE`, from a graph to another one (`None` standing for the default graph)
+    Move(Option<ArcTerm>, Option<ArcTerm>, bool),
+    /// `COPY`, from a graph to another one (`None` standing for the default graph)
+    Copy(Option<ArcTerm>, Option<ArcTerm>, bool),
+}
+
+/// The graph(s) targeted by a `CLEAR` or `DROP` operation.
+#[derive(Clone, Debug)]
+pub enum GraphTarget {
+    /// `DEFAULT`
+    Default,
+    /// `GRAPH <iri>`
+    Named(ArcTerm),
+    /// `NAMED`
+    AllNamed,
+    /// `ALL`
+    All,
+}
+
+/// A triple pattern in a given graph, as used in update templates.
+#[derive(Clone, Debug)]
+pub struct QuadPattern {
+    /// The triple pattern
+    pub triple: [TermPattern; 3],
+    /// The graph in which the triple pattern applies (`None` for the default graph)
+    pub graph: Option<TermPattern>,
+}
+
+/// A term that may contain variables.
+#[derive(Clone, Debug)]
+pub enum TermPattern {
+    /// A constant RDF term
+    Const(ArcTerm),
+    /// A variable (identified by its index)
+    Var(usize),
+    /// A triple term containing at least one variable
+    Triple(Box<[TermPattern; 3]>),
+}
+
+impl TermPattern {
+    /// Iterate over all the variables occurring in this pattern.
+    pub fn for_each_var<F: FnMut(usize)>(&self, f: &mut F) {
+        match self {
+            TermPattern::Const(_) => (),
+            TermPattern::Var(i) => f(*i),
+            TermPattern::Triple(spo) => spo.iter().for_each(|tp| tp.for_each_var(f)),
+        }
+    }
+}
+
+/// A [property path](https://www.w3.org/TR/sparql11-query/#propertypaths) expression.
+#[derive(Clone, Debug)]
+pub enum PropertyPath {
+    /// A single predicate
+    Predicate(ArcTerm),
+    /// `^path`
+    Inverse(Box<PropertyPath>),
+    /// `path1 / path2`
+    Sequence(Box<PropertyPath>, Box<PropertyPath>),
+    /// `path1 | path2`
+    Alternative(Box<PropertyPath>, Box<PropertyPath>),
+    /// `path*`
+    ZeroOrMore(Box<PropertyPath>),
+    /// `path+`
+    OneOrMore(Box<PropertyPath>),
+    /// `path?`
+    ZeroOrOne(Box<PropertyPath>),
+    /// `!(iri1 | ... | ^iriN)`, split into forward and inverse IRIs
+    NegatedSet(Vec<ArcTerm>, Vec<ArcTerm>),
+}
+
+/// A graph pattern, as defined by the SPARQL algebra.
+#[derive(Clone, Debug)]
+pub enum GraphPattern {
+    /// A basic graph pattern
+    Bgp(Vec<[TermPattern; 3]>),
+    /// A property path pattern
+    Path(TermPattern, PropertyPath, TermPattern),
+    /// Join(P1, P2)
+    Join(Box<GraphPattern>, Box<GraphPattern>),
+    /// LeftJoin(P1, P2, F), as produced by `OPTIONAL`
+    LeftJoin(Box<GraphPattern>, Box<GraphPattern>, Option<Expression>),
+    /// Filter(F, P)
+    Filter(Expression, Box<GraphPattern>),
+    /// Union(P1, P2)
+    Union(Box<GraphPattern>, Box<GraphPattern>),
+    /// Minus(P1, P2)
+    Minus(Box<GraphPattern>, Box<GraphPattern>),
+    /// Graph(g, P), where g is an IRI or a variable
+    Graph(TermPattern, Box<GraphPattern>),
+    /// Extend(P, var, expr), as produced by `BIND` or `SELECT (expr AS ?var)`
+    Extend(Box<GraphPattern>, usize, Expression),
+    /// Inline data, as produced by `VALUES`
+    Values(Vec<usize>, Vec<Vec<Option<ArcTerm>>>),
+    /// ToList(P) + OrderBy
+    OrderBy(Box<GraphPattern>, Vec<OrderCondition>),
+    /// Project(P, vars)
+    Project(Box<GraphPattern>, Vec<usize>),
+    /// Distinct(P)
+    Distinct(Box<GraphPattern>),
+    /// Reduced(P)
+    Reduced(Box<GraphPattern>),
+    /// Slice(P, offset, limit)
+    Slice(Box<GraphPattern>, usize, Option<usize>),
+    /// Group(keys, P) followed by the computation of the given aggregates
+    Group(Box<GraphPattern>, Vec<usize>, Vec<(usize, Aggregate)>),
+}
+
+impl GraphPattern {
+    /// The empty group pattern `{}`, which has a single empty solution.
+    pub fn empty() -> Self {
+        GraphPattern::Bgp(vec![])
+    }
+
+    /// Iterate over the variables that are in-scope in this pattern
+    /// (as defined in [SPARQL 1.1 §18.2.1](https://www.w3.org/TR/sparql11-query/#variableScope)).
+    pub fn for_each_in_scope_var<F: FnMut(usize)>(&self, f: &mut F) {
+        use GraphPattern::*;
+        match self {
+            Bgp(tps) => tps
+                .iter()
+                .for_each(|tp| tp.iter().for_each(|t| t.for_each_var(f))),
+            Path(s, _, o) => {
+                s.for_each_var(f);
+                o.for_each_var(f);
+            }
+            Join(p1, p2) | LeftJoin(p1, p2, _) | Union(p1, p2) => {
+                p1.for_each_in_scope_var(f);
+                p2.for_each_in_scope_var(f);
+            }
+            Minus(p, _)
+            | Filter(_, p)
+            | OrderBy(p, _)
+            | Distinct(p)
+            | Reduced(p)
+            | Slice(p, ..) => p.for_each_in_scope_var(f),
+            Graph(g, p) => {
+                g.for_each_var(f);
+                p.for_each_in_scope_var(f);
+            }
+            Extend(p, v, _) => {
+                p.for_each_in_scope_var(f);
+                f(*v);
+            }
+            Values(vars, _) | Project(_, vars) => vars.iter().copied().for_each(f),
+            Group(_, keys, aggs) => {
+                keys.iter().copied().for_each(&mut *f);
+                aggs.iter().for_each(|(v, _)| f(*v));
+            }
+        }
+    }
+}
+
+/// An `ORDER BY` condition
+#[derive(Clone, Debug)]
+pub struct OrderCondition {
+    /// The expression to sort by
+    pub expression: Expression,
+    /// Whether the sort is ascending (default) or descending
+    pub ascending: bool,
+}
+
+/// An aggregate, as used in grouped queries.
+#[derive(Clone, Debug)]
+pub struct Aggregate {
+    /// The aggregate function
+    pub function: AggregateFunction,
+    /// The aggregated expression (`None` for `COUNT(*)`)
+    pub expression: Option<Expression>,
+    /// Whether `DISTINCT` was specified
+    pub distinct: bool,
+}
+
+/// The aggregate functions defined by SPARQL 1.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AggregateFunction {
+    /// `COUNT`
+    Count,
+    /// `SUM`
+    Sum,
+    /// `MIN`
+    Min,
+    /// `MAX`
+    Max,
+    /// `AVG`
+    Avg,
+    /// `SAMPLE`
+    Sample,
+    /// `GROUP_CONCAT`, with its separator
+    GroupConcat(String),
+}
+
+/// A SPARQL expression
+#[derive(Clone, Debug)]
+pub enum Expression {
+    /// A constant RDF term
+    Const(ArcTerm),
+    /// A variable
+    Var(usize),
+    /// `||`
+    Or(Box<Expression>, Box<Expression>),
+    /// `&&`
+    And(Box<Expression>, Box<Expression>),
+    /// `!`
+    Not(Box<Expression>),
+    /// Binary comparison operators (`=`, `!=`, `<`, `<=`, `>`, `>=`)
+    Compare(CompareOp, Box<Expression>, Box<Expression>),
+    /// `IN` (or `NOT IN` if the flag is false)
+    In(Box<Expression>, Vec<Expression>, bool),
+    /// Binary arithmetic operators (`+`, `-`, `*`, `/`)
+    Arith(ArithOp, Box<Expression>, Box<Expression>),
+    /// Unary `-`
+    Neg(Box<Expression>),
+    /// Unary `+`
+    Plus(Box<Expression>),
+    /// `BOUND(?var)`
+    Bound(usize),
+    /// `IF(cond, then, else)`
+    If(Box<Expression>, Box<Expression>, Box<Expression>),
+    /// `COALESCE(...)`
+    Coalesce(Vec<Expression>),
+    /// `EXISTS { ... }` (or `NOT EXISTS` if the flag is false)
+    Exists(Box<GraphPattern>, bool),
+    /// A call to a built-in function
+    Call(Function, Vec<Expression>),
+    /// A call to a function identified by an IRI (e.g. XSD casts)
+    Custom(ArcTerm, Vec<Expression>),
+}
+
+impl Expression {
+    /// Iterate over all the variables occurring in this expression
+    /// (excluding those in the pattern of `EXISTS`).
+    pub fn for_each_var<F: FnMut(usize)>(&self, f: &mut F) {
+        use Expression::*;
+        match self {
+            Const(_) | Exists(..) => (),
+            Var(i) | Bound(i) => f(*i),
+            Not(e) | Neg(e) | Plus(e) => e.for_each_var(f),
+            Or(e1, e2) | And(e1, e2) | Compare(_, e1, e2) | Arith(_, e1, e2) => {
+                e1.for_each_var(f);
+                e2.for_each_var(f);
+            }
+            If(e1, e2, e3) => {
+                e1.for_each_var(f);
+                e2.for_each_var(f);
+                e3.for_each_var(f);
+            }
+            In(e, es, _) => {
+                e.for_each_var(f);
+                es.iter().for_each(|e| e.for_each_var(f));
+            }
+            Coalesce(es) | Call(_, es) | Custom(_, es) => es.iter().for_each(|e| e.for_each_var(f)),
+        }
+    }
+}
+
+/// Comparison operators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+/// Arithmetic operators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+}
+
+/// The built-in functions of SPARQL 1.1 (and SPARQL 1.2)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Function {
+    Str,
+    Lang,
+    LangMatches,
+    Datatype,
+    Iri,
+    Bnode,
+    Rand,
+    Abs,
+    Ceil,
+    Floor,
+    Round,
+    Concat,
+    SubStr,
+    StrLen,
+    Replace,
+    UCase,
+    LCase,
+    EncodeForUri,
+    Contains,
+    StrStarts,
+    StrEnds,
+    StrBefore,
+    StrAfter,
+    Year,
+    Month,
+    Day,
+    Hours,
+    Minutes,
+    Seconds,
+    Timezone,
+    Tz,
+    Now,
+    Uuid,
+    StrUuid,
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+    StrLang,
+    StrDt,
+    SameTerm,
+    IsIri,
+    IsBlank,
+    IsLiteral,
+    IsNumeric,
+    Regex,
+    Triple,
+    Subject,
+    Predicate,
+    Object,
+    IsTriple,
+}
+
+impl Function {
+    /// Find the built-in function with the given (case-insensitive) name,
+    /// together with its minimum and maximum arity.
+    pub fn from_name(name: &str) -> Option<(Self, usize, usize)> {
+        use Function::*;
+        let ret = match &name.to_ascii_uppercase()[..] {
+            "STR" => (Str, 1, 1),
+            "LANG" => (Lang, 1, 1),
+            "LANGMATCHES" => (LangMatches, 2, 2),
+            "DATATYPE" => (Datatype, 1, 1),
+            "IRI" | "URI" => (Iri, 1, 1),
+            "BNODE" => (Bnode, 0, 1),
+            "RAND" => (Rand, 0, 0),
+            "ABS" => (Abs, 1, 1),
+            "CEIL" => (Ceil, 1, 1),
+            "FLOOR" => (Floor, 1, 1),
+            "ROUND" => (Round, 1, 1),
+            "CONCAT" => (Concat, 0, usize::MAX),
+            "SUBSTR" => (SubStr, 2, 3),
+            "STRLEN" => (StrLen, 1, 1),
+            "REPLACE" => (Replace, 3, 4),
+            "UCASE" => (UCase, 1, 1),
+            "LCASE" => (LCase, 1, 1),
+            "ENCODE_FOR_URI" => (EncodeForUri, 1, 1),
+            "CONTAINS" => (Contains, 2, 2),
+            "STRSTARTS" => (StrStarts, 2, 2),
+            "STRENDS" => (StrEnds, 2, 2),
+            "STRBEFORE" => (StrBefore, 2, 2),
+            "STRAFTER" => (StrAfter, 2, 2),
+            "YEAR" => (Year, 1, 1),
+            "MONTH" => (Month, 1, 1),
+            "DAY" => (Day, 1, 1),
+            "HOURS" => (Hours, 1, 1),
+            "MINUTES" => (Minutes, 1, 1),
+            "SECONDS" => (Seconds, 1, 1),
+            "TIMEZONE" => (Timezone, 1, 1),
+            "TZ" => (Tz, 1, 1),
+            "NOW" => (Now, 0, 0),
+            "UUID" => (Uuid, 0, 0),
+            "STRUUID" => (StrUuid, 0, 0),
+            "MD5" => (Md5, 1, 1),
+            "SHA1" => (Sha1, 1, 1),
+            "SHA256" => (Sha256, 1, 1),
+            "SHA384" => (Sha384, 1, 1),
+            "SHA512" => (Sha512, 1, 1),
+            "STRLANG" => (StrLang, 2, 2),
+            "STRDT" => (StrDt, 2, 2),
+            "SAMETERM" => (SameTerm, 2, 2),
+            "ISIRI" | "ISURI" => (IsIri, 1, 1),
+            "ISBLANK" => (IsBlank, 1, 1),
+            "ISLITERAL" => (IsLiteral, 1, 1),
+            "ISNUMERIC" => (IsNumeric, 1, 1),
+            "REGEX" => (Regex, 2, 3),
+            "TRIPLE" => (Triple, 3, 3),
+            "SUBJECT" => (Subject, 1, 1),
+            "PREDICATE" => (Predicate, 1, 1),
+            "OBJECT" => (Object, 1, 1),
+            "ISTRIPLE" => (IsTriple, 1, 1),
+            _ => return None,
+        };
+        Some(ret)
+    }
+}
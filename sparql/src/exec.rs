@@ -0,0 +1,1117 @@
+//! I implement the evaluation of the [SPARQL algebra](crate::algebra) against a [`Dataset`].
+//!
+//! Solution mappings are represented as [rows](Row) indexed by variable,
+//! and graph patterns are evaluated eagerly.
+//!
+//! Joins are evaluated by *substitution* whenever this does not change the semantics
+//! (i.e. when the right-hand pattern contains no filter, sub-query, etc.):
+//! the right-hand pattern is then evaluated once for each solution of the left-hand pattern,
+//! with the variables of that solution already bound.
+//! This allows the indexes of the underlying dataset to be used as much as possible.
+use std::cell::{Cell, OnceCell, RefCell};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use regex::Regex;
+use sophia_api::dataset::Dataset;
+use sophia_api::quad::Quad;
+use sophia_api::term::matcher::{Any, TermMatcher};
+use sophia_api::term::{FromTerm, GraphName, Term};
+use sophia_term::ArcTerm;
+
+use crate::algebra::*;
+use crate::expr::order_cmp;
+use crate::value::*;
+use crate::SparqlWrapperError;
+
+/// A solution mapping, represented as the list of values of each variable of the query.
+pub(crate) type Row = Vec<Option<ArcTerm>>;
+
+pub(crate) type EvalResult<T> = Result<T, SparqlWrapperError>;
+
+/// The result of executing a query.
+pub(crate) enum Outcome {
+    Bindings(Vec<Arc<str>>, Vec<Row>),
+    Boolean(bool),
+    Triples(Vec<[ArcTerm; 3]>),
+}
+
+/// Execute `query` against `dataset`.
+///
+/// `default_dataset` is used if `query` has no `FROM` or `FROM NAMED` clause,
+/// and `bindings` gives the values of pre-bound variables.
+pub(crate) fn execute<D: Dataset + ?Sized>(
+    dataset: &D,
+    query: &Query,
+    default_dataset: Option<&QueryDataset>,
+    bindings: &[(usize, ArcTerm)],
+) -> EvalResult<Outcome> {
+    let ev = Evaluator::with_dataset(dataset, query, query.dataset.as_ref().or(default_dataset));
+    let scope = Scope {
+        graph: &ev.default_graph,
+        substitute: false,
+        prebound: None,
+    };
+    let rows = if bindings.is_empty() {
+        ev.eval(&query.pattern, scope, &ev.empty_row())?
+    } else {
+        let mut seed = ev.empty_row();
+        for (var, val) in bindings {
+            seed[*var] = Some(val.clone());
+        }
+        ev.eval_prebound(&query.pattern, scope, &seed)?
+    };
+    let outcome = match &query.form {
+        QueryForm::Select(projection) => {
+            let names = projection
+                .iter()
+                .map(|i| query.variables[*i].clone())
+                .collect();
+            let rows = rows
+                .into_iter()
+                .map(|row| projection.iter().map(|i| row[*i].clone()).collect())
+                .collect();
+            Outcome::Bindings(names, rows)
+        }
+        QueryForm::Ask => Outcome::Boolean(!rows.is_empty()),
+        QueryForm::Construct(template) => Outcome::Triples(ev.construct(template, &rows)),
+        QueryForm::Describe(targets) => Outcome::Triples(ev.describe(targets, &rows)?),
+    };
+    ev.take_error()?;
+    Ok(outcome)
+}
+
+/// The number of evaluators created so far, used to seed their pseudo-random generator
+static EVALUATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The context in which a graph pattern is evaluated
+#[derive(Clone, Copy)]
+pub(crate) struct Scope<'g> {
+    /// The active graph (possibly the merge of several graphs)
+    pub(crate) graph: &'g [GraphName<ArcTerm>],
+    /// Whether the seed must be substituted in the whole pattern (as done by `EXISTS`)
+    pub(crate) substitute: bool,
+    /// The values of the pre-bound variables, pushed into every sub-pattern (except sub-queries)
+    pub(crate) prebound: Option<&'g Row>,
+}
+
+pub(crate) struct Evaluator<'a, D: Dataset + ?Sized> {
+    dataset: &'a D,
+    query: &'a Query,
+    pub(crate) default_graph: Vec<GraphName<ArcTerm>>,
+    named_graphs: OnceCell<Vec<ArcTerm>>,
+    pub(crate) now: ArcTerm,
+    rand: Cell<u64>,
+    bnode_prefix: String,
+    bnode_counter: Cell<usize>,
+    pub(crate) regexes: RefCell<HashMap<(String, String), Option<Regex>>>,
+    /// The first dataset error encountered while evaluating an expression
+    error: RefCell<Option<SparqlWrapperError>>,
+}
+
+impl<'a, D: Dataset + ?Sized> Evaluator<'a, D> {
+    pub(crate) fn new(dataset: &'a D, query: &'a Query) -> Self {
+        Self::with_dataset(dataset, query, query.dataset.as_ref())
+    }
+
+    /// Build an evaluator using `query_dataset` instead of the dataset specified by `query`.
+    pub(crate) fn with_dataset(
+        dataset: &'a D,
+        query: &'a Query,
+        query_dataset: Option<&QueryDataset>,
+    ) -> Self {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let now = typed_literal(
+            &DateTime::from_timestamp_millis(millis).to_lex(),
+            &XSD_DATETIME,
+        );
+        // the counter ensures that two evaluations never share the same seed,
+        // which matters for the blank nodes inserted by update requests
+        let count = EVALUATIONS.fetch_add(1, AtomicOrdering::Relaxed);
+        let seed = (millis as u64)
+            ^ 0x9E37_79B9_7F4A_7C15
+            ^ (query as *const Query as u64)
+            ^ count.wrapping_mul(0xD1B5_4A32_D192_ED03);
+        let default_graph = match query_dataset {
+            Some(qd) => qd.default.iter().cloned().map(Some).collect(),
+            None => vec![None],
+        };
+        let named_graphs = OnceCell::new();
+        if let Some(qd) = query_dataset {
+            let _ = named_graphs.set(qd.named.clone());
+        }
+        let ev = Evaluator {
+            dataset,
+            query,
+            default_graph,
+            named_graphs,
+            now,
+            rand: Cell::new(seed | 1),
+            bnode_prefix: String::new(),
+            bnode_counter: Cell::new(0),
+            regexes: RefCell::new(HashMap::new()),
+            error: RefCell::new(None),
+        };
+        let bnode_prefix = format!("q{:x}", ev.next_rand());
+        Evaluator { bnode_prefix, ..ev }
+    }
+
+    pub(crate) fn empty_row(&self) -> Row {
+        vec![None; self.query.variables.len()]
+    }
+
+    pub(crate) fn base(&self) -> Option<&sophia_iri::resolve::BaseIri<String>> {
+        self.query.base.as_ref()
+    }
+
+    /// A pseudo-random number (xorshift64*)
+    pub(crate) fn next_rand(&self) -> u64 {
+        let mut x = self.rand.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rand.set(x);
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// The prefix of all blank node labels produced by this query.
+    pub(crate) fn bnode_prefix(&self) -> &str {
+        &self.bnode_prefix
+    }
+
+    /// A fresh blank node, distinct from all other blank nodes produced by this query.
+    pub(crate) fn fresh_bnode(&self) -> ArcTerm {
+        let n = self.bnode_counter.get() + 1;
+        self.bnode_counter.set(n);
+        bnode_term(&format!("{}b{}", self.bnode_prefix, n))
+    }
+
+    /// Record a dataset error encountered while evaluating an expression.
+    pub(crate) fn record_error(&self, err: SparqlWrapperError) {
+        let mut error = self.error.borrow_mut();
+        if error.is_none() {
+            *error = Some(err);
+        }
+    }
+
+    /// Fail if a dataset error was recorded during the evaluation.
+    pub(crate) fn take_error(&self) -> EvalResult<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn named_graphs(&self) -> EvalResult<&[ArcTerm]> {
+        if let Some(names) = self.named_graphs.get() {
+            return Ok(names.as_slice());
+        }
+        let mut seen = HashSet::new();
+        let mut names = vec![];
+        for name in self.dataset.graph_names() {
+            let name = ArcTerm::from_term(name.map_err(dataset_error)?);
+            if seen.insert(name.clone()) {
+                names.push(name);
+            }
+        }
+        Ok(self.named_graphs.get_or_init(|| names).as_slice())
+    }
+
+    //
+    // Graph patterns
+    //
+
+    /// Evaluate pattern `p`, and return its solutions compatible with `seed`
+    /// (merged with `seed`).
+    pub(crate) fn eval(&self, p: &GraphPattern, scope: Scope, seed: &Row) -> EvalResult<Vec<Row>> {
+        if scope.substitute || correlatable(p) || seed.iter().all(Option::is_none) {
+            self.eval_inner(p, scope, seed)
+        } else {
+            let rows = self.eval_inner(p, scope, &self.base_row(scope))?;
+            Ok(rows.iter().filter_map(|row| merge(row, seed)).collect())
+        }
+    }
+
+    /// The seed of patterns evaluated independently of their context.
+    fn base_row(&self, scope: Scope) -> Row {
+        match scope.prebound {
+            Some(row) => row.clone(),
+            None => self.empty_row(),
+        }
+    }
+
+    /// Evaluate the top-level pattern `p` of the query, with pre-bound variables in `seed`.
+    ///
+    /// The bindings are pushed through the solution modifiers of the query,
+    /// and into every sub-pattern of its WHERE clause (except sub-queries).
+    fn eval_prebound(&self, p: &GraphPattern, scope: Scope, seed: &Row) -> EvalResult<Vec<Row>> {
+        use GraphPattern::*;
+        match p {
+            Slice(p, offset, limit) => {
+                Ok(slice(self.eval_prebound(p, scope, seed)?, *offset, *limit))
+            }
+            Distinct(p) | Reduced(p) => Ok(distinct(self.eval_prebound(p, scope, seed)?)),
+            Project(p, vars) => Ok(self.project(self.eval_prebound(p, scope, seed)?, vars, seed)),
+            p => {
+                let scope = Scope {
+                    prebound: Some(seed),
+                    ..scope
+                };
+                self.eval(p, scope, seed)
+            }
+        }
+    }
+
+    fn eval_inner(&self, p: &GraphPattern, scope: Scope, seed: &Row) -> EvalResult<Vec<Row>> {
+        use GraphPattern::*;
+        match p {
+            Bgp(tps) => self.eval_bgp(tps, scope, seed),
+            Path(s, path, o) => self.eval_path(s, path, o, scope, seed),
+            Join(p1, p2) => {
+                let left = self.eval(p1, scope, seed)?;
+                let mut ret = vec![];
+                if scope.substitute || correlatable(p2) {
+                    for row in &left {
+                        ret.extend(self.eval(p2, scope, row)?);
+                    }
+                } else if !left.is_empty() {
+                    let right = self.eval(p2, scope, &self.base_row(scope))?;
+                    for l in &left {
+                        ret.extend(right.iter().filter_map(|r| merge(l, r)));
+                    }
+                }
+                Ok(ret)
+            }
+            LeftJoin(p1, p2, cond) => {
+                let left = self.eval(p1, scope, seed)?;
+                let mut ret = vec![];
+                let right = if scope.substitute || correlatable(p2) || left.is_empty() {
+                    None
+                } else {
+                    Some(self.eval(p2, scope, &self.base_row(scope))?)
+                };
+                for l in left {
+                    let extensions = match &right {
+                        None => self.eval(p2, scope, &l)?,
+                        Some(right) => right.iter().filter_map(|r| merge(&l, r)).collect(),
+                    };
+                    let before = ret.len();
+                    for row in extensions {
+                        let keep = match cond {
+                            None => true,
+                            Some(cond) => self.eval_ebv(cond, &row, scope) == Some(true),
+                        };
+                        if keep {
+                            ret.push(row);
+                        }
+                    }
+                    if ret.len() == before {
+                        ret.push(l);
+                    }
+                }
+                Ok(ret)
+            }
+            Filter(cond, p) => {
+                let mut rows = self.eval(p, scope, seed)?;
+                rows.retain(|row| self.eval_ebv(cond, row, scope) == Some(true));
+                Ok(rows)
+            }
+            Union(p1, p2) => {
+                let mut rows = self.eval(p1, scope, seed)?;
+                rows.extend(self.eval(p2, scope, seed)?);
+                Ok(rows)
+            }
+            Minus(p1, p2) => {
+                let mut rows = self.eval(p1, scope, seed)?;
+                if !rows.is_empty() {
+                    let right = self.eval(p2, scope, &self.base_row(scope))?;
+                    rows.retain(|l| {
+                        !right.iter().any(|r| {
+                            merge(l, r).is_some()
+                                && l.iter()
+                                    .zip(r.iter())
+                                    .any(|(a, b)| a.is_some() && b.is_some())
+                        })
+                    });
+                }
+                Ok(rows)
+            }
+            Graph(name, p) => self.eval_graph(name, p, scope, seed),
+            Extend(p, var, expr) => {
+                let rows = self.eval(p, scope, seed)?;
+                let mut ret = Vec::with_capacity(rows.len());
+                for mut row in rows {
+                    match self.eval_expr(expr, &row, scope) {
+                        None => ret.push(row),
+                        Some(val) => match &row[*var] {
+                            None => {
+                                row[*var] = Some(val);
+                                ret.push(row);
+                            }
+                            Some(old) => {
+                                if Term::eq(old, &val) {
+                                    ret.push(row);
+                                }
+                            }
+                        },
+                    }
+                }
+                Ok(ret)
+            }
+            Values(vars, data) => {
+                let mut ret = vec![];
+                'rows: for values in data {
+                    let mut row = seed.clone();
+                    for (var, val) in vars.iter().zip(values) {
+                        match (val, &row[*var]) {
+                            (None, _) => (),
+                            (Some(val), None) => row[*var] = Some(val.clone()),
+                            (Some(val), Some(old)) => {
+                                if !Term::eq(old, val) {
+                                    continue 'rows;
+                                }
+                            }
+                        }
+                    }
+                    ret.push(row);
+                }
+                Ok(ret)
+            }
+            OrderBy(p, conditions) => {
+                let rows = self.eval(p, scope, seed)?;
+                let mut keyed: Vec<_> = rows
+                    .into_iter()
+                    .map(|row| {
+                        let keys: Vec<_> = conditions
+                            .iter()
+                            .map(|c| self.eval_expr(&c.expression, &row, scope))
+                            .collect();
+                        (keys, row)
+                    })
+                    .collect();
+                keyed.sort_by(|(k1, _), (k2, _)| {
+                    for (c, (v1, v2)) in conditions.iter().zip(k1.iter().zip(k2.iter())) {
+                        let ord = order_cmp(v1.as_ref(), v2.as_ref());
+                        let ord = if c.ascending { ord } else { ord.reverse() };
+                        if ord != Ordering::Equal {
+                            return ord;
+                        }
+                    }
+                    Ordering::Equal
+                });
+                Ok(keyed.into_iter().map(|(_, row)| row).collect())
+            }
+            Project(p, vars) => {
+                // sub-queries do not see the bindings of the enclosing query
+                let scope = Scope {
+                    prebound: None,
+                    ..scope
+                };
+                let rows = self.eval(p, scope, &self.empty_row())?;
+                Ok(self.project(rows, vars, seed))
+            }
+            Distinct(p) | Reduced(p) => Ok(distinct(self.eval(p, scope, seed)?)),
+            Slice(p, offset, limit) => Ok(slice(self.eval(p, scope, seed)?, *offset, *limit)),
+            Group(p, keys, aggregates) => self.eval_group(p, keys, aggregates, scope, seed),
+        }
+    }
+
+    /// Project `rows` on `vars`, and merge them with `seed`.
+    fn project(&self, rows: Vec<Row>, vars: &[usize], seed: &Row) -> Vec<Row> {
+        rows.into_iter()
+            .map(|row| {
+                let mut projected = self.empty_row();
+                for v in vars {
+                    projected[*v] = row[*v].clone();
+                }
+                projected
+            })
+            .filter_map(|row| merge(&row, seed))
+            .collect()
+    }
+
+    fn eval_bgp(&self, tps: &[[TermPattern; 3]], scope: Scope, seed: &Row) -> EvalResult<Vec<Row>> {
+        let mut rows = vec![seed.clone()];
+        let mut remaining: Vec<&[TermPattern; 3]> = tps.iter().collect();
+        while !remaining.is_empty() && !rows.is_empty() {
+            // greedily pick the most selective triple pattern, given the current bindings
+            let mut best = 0;
+            let mut best_score = 0;
+            for (i, tp) in remaining.iter().enumerate() {
+                let score = selectivity(tp, &rows[0]);
+                if score > best_score {
+                    best = i;
+                    best_score = score;
+                }
+            }
+            let tp = remaining.remove(best);
+            let mut next = vec![];
+            for row in &rows {
+                self.match_triple_pattern(tp, scope, row, &mut next)?;
+            }
+            rows = next;
+        }
+        Ok(rows)
+    }
+
+    fn match_triple_pattern(
+        &self,
+        tp: &[TermPattern; 3],
+        scope: Scope,
+        row: &Row,
+        out: &mut Vec<Row>,
+    ) -> EvalResult<()> {
+        let [s, p, o] = [0, 1, 2].map(|i| ground(&tp[i], row));
+        let before = out.len();
+        for quad in self.dataset.quads_matching(
+            TMatch(s.as_ref()),
+            TMatch(p.as_ref()),
+            TMatch(o.as_ref()),
+            scope.graph,
+        ) {
+            let quad = quad.map_err(dataset_error)?;
+            let mut new_row = row.clone();
+            if unify(&tp[0], quad.s(), &mut new_row)
+                && unify(&tp[1], quad.p(), &mut new_row)
+                && unify(&tp[2], quad.o(), &mut new_row)
+            {
+                out.push(new_row);
+            }
+        }
+        if scope.graph.len() > 1 {
+            // the active graph is a merge: remove duplicates coming from different graphs
+            dedup_from(out, before);
+        }
+        Ok(())
+    }
+
+    fn eval_graph(
+        &self,
+        name: &TermPattern,
+        p: &GraphPattern,
+        scope: Scope,
+        seed: &Row,
+    ) -> EvalResult<Vec<Row>> {
+        let named = self.named_graphs()?;
+        match ground(name, seed) {
+            Some(name) => {
+                if !named.iter().any(|n| Term::eq(n, &name)) {
+                    return Ok(vec![]);
+                }
+                let graph = [Some(name)];
+                let scope = Scope {
+                    graph: &graph,
+                    ..scope
+                };
+                self.eval(p, scope, seed)
+            }
+            None => {
+                let mut ret = vec![];
+                for n in named {
+                    let mut seed = seed.clone();
+                    if !unify(name, n, &mut seed) {
+                        continue;
+                    }
+                    let graph = [Some(n.clone())];
+                    let scope = Scope {
+                        graph: &graph,
+                        ..scope
+                    };
+                    ret.extend(self.eval(p, scope, &seed)?);
+                }
+                Ok(ret)
+            }
+        }
+    }
+
+    fn eval_group(
+        &self,
+        p: &GraphPattern,
+        keys: &[usize],
+        aggregates: &[(usize, Aggregate)],
+        scope: Scope,
+        seed: &Row,
+    ) -> EvalResult<Vec<Row>> {
+        let rows = self.eval(p, scope, seed)?;
+        let mut index: HashMap<Vec<Option<ArcTerm>>, usize> = HashMap::new();
+        let mut groups: Vec<(Vec<Option<ArcTerm>>, Vec<Row>)> = vec![];
+        for row in rows {
+            let key: Vec<_> = keys.iter().map(|k| row[*k].clone()).collect();
+            match index.get(&key) {
+                Some(i) => groups[*i].1.push(row),
+                None => {
+                    index.insert(key.clone(), groups.len());
+                    groups.push((key, vec![row]));
+                }
+            }
+        }
+        if groups.is_empty() && keys.is_empty() {
+            groups.push((vec![], vec![]));
+        }
+        Ok(groups
+            .into_iter()
+            .map(|(key, rows)| {
+                let mut ret = self.empty_row();
+                for (k, v) in keys.iter().zip(key) {
+                    ret[*k] = v;
+                }
+                for (var, agg) in aggregates {
+                    ret[*var] = self.eval_aggregate(agg, &rows, scope);
+                }
+                ret
+            })
+            .collect())
+    }
+
+    fn eval_aggregate(&self, agg: &Aggregate, rows: &[Row], scope: Scope) -> Option<ArcTerm> {
+        let Some(expr) = &agg.expression else {
+            // COUNT(*)
+            let count = if agg.distinct {
+                rows.iter().collect::<HashSet<_>>().len()
+            } else {
+                rows.len()
+            };
+            return Some(integer_literal(count as i64));
+        };
+        let mut values: Vec<Option<ArcTerm>> = rows
+            .iter()
+            .map(|row| self.eval_expr(expr, row, scope))
+            .collect();
+        if agg.distinct {
+            let mut seen = HashSet::new();
+            values.retain(|v| seen.insert(v.clone()));
+        }
+        match &agg.function {
+            AggregateFunction::Count => {
+                Some(integer_literal(values.iter().flatten().count() as i64))
+            }
+            AggregateFunction::Sum => {
+                let mut sum = Numeric::Integer(0);
+                for v in values {
+                    let n = as_numeric(&v?)??;
+                    sum = Numeric::apply(sum, n, i64::checked_add, |a, b| a + b)?;
+                }
+                Some(sum.to_term())
+            }
+            AggregateFunction::Avg => {
+                if values.is_empty() {
+                    return Some(integer_literal(0));
+                }
+                let count = values.len() as i64;
+                let mut sum = Numeric::Integer(0);
+                for v in values {
+                    let n = as_numeric(&v?)??;
+                    sum = Numeric::apply(sum, n, i64::checked_add, |a, b| a + b)?;
+                }
+                let sum = match sum {
+                    Numeric::Integer(i) => Numeric::Decimal(i as f64),
+                    n => n,
+                };
+                Numeric::apply(sum, Numeric::Integer(count), |_, _| None, |a, b| a / b)
+                    .map(Numeric::to_term)
+            }
+            AggregateFunction::Min => values
+                .into_iter()
+                .flatten()
+                .min_by(|a, b| order_cmp(Some(a), Some(b))),
+            AggregateFunction::Max => values
+                .into_iter()
+                .flatten()
+                .max_by(|a, b| order_cmp(Some(a), Some(b))),
+            AggregateFunction::Sample => values.into_iter().flatten().next(),
+            AggregateFunction::GroupConcat(separator) => {
+                let mut parts = vec![];
+                for v in values {
+                    let v = v?;
+                    parts.push(string_parts(&v)?.0.to_string());
+                }
+                Some(string_literal(&parts.join(separator)))
+            }
+        }
+    }
+
+    //
+    // Property paths
+    //
+
+    fn eval_path(
+        &self,
+        s: &TermPattern,
+        path: &PropertyPath,
+        o: &TermPattern,
+        scope: Scope,
+        seed: &Row,
+    ) -> EvalResult<Vec<Row>> {
+        let sv = ground(s, seed);
+        let ov = ground(o, seed);
+        let pairs = self.path_pairs(path, scope, sv.as_ref(), ov.as_ref())?;
+        let mut ret = vec![];
+        for (x, y) in pairs {
+            let mut row = seed.clone();
+            if unify(s, &x, &mut row) && unify(o, &y, &mut row) {
+                ret.push(row);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Return all the pairs of nodes connected by `path`,
+    /// with the given subject and/or object (if any).
+    fn path_pairs(
+        &self,
+        path: &PropertyPath,
+        scope: Scope,
+        s: Option<&ArcTerm>,
+        o: Option<&ArcTerm>,
+    ) -> EvalResult<Vec<(ArcTerm, ArcTerm)>> {
+        use PropertyPath::*;
+        match path {
+            Predicate(p) => {
+                let mut ret = vec![];
+                for quad in
+                    self.dataset
+                        .quads_matching(TMatch(s), TMatch(Some(p)), TMatch(o), scope.graph)
+                {
+                    let quad = quad.map_err(dataset_error)?;
+                    ret.push((ArcTerm::from_term(quad.s()), ArcTerm::from_term(quad.o())));
+                }
+                if scope.graph.len() > 1 {
+                    dedup_from(&mut ret, 0);
+                }
+                Ok(ret)
+            }
+            Inverse(p) => Ok(self
+                .path_pairs(p, scope, o, s)?
+                .into_iter()
+                .map(|(x, y)| (y, x))
+                .collect()),
+            Sequence(p1, p2) => {
+                let mut ret = vec![];
+                if s.is_none() && o.is_some() {
+                    for (m, y) in self.path_pairs(p2, scope, None, o)? {
+                        for (x, _) in self.path_pairs(p1, scope, None, Some(&m))? {
+                            ret.push((x, y.clone()));
+                        }
+                    }
+                } else {
+                    for (x, m) in self.path_pairs(p1, scope, s, None)? {
+                        for (_, y) in self.path_pairs(p2, scope, Some(&m), o)? {
+                            ret.push((x.clone(), y));
+                        }
+                    }
+                }
+                Ok(ret)
+            }
+            Alternative(p1, p2) => {
+                let mut ret = self.path_pairs(p1, scope, s, o)?;
+                ret.extend(self.path_pairs(p2, scope, s, o)?);
+                Ok(ret)
+            }
+            ZeroOrOne(p) => {
+                let mut ret = self.zero_length_pairs(scope, s, o)?;
+                ret.extend(self.path_pairs(p, scope, s, o)?);
+                dedup_from(&mut ret, 0);
+                Ok(ret)
+            }
+            ZeroOrMore(p) | OneOrMore(p) => {
+                let zero = matches!(path, ZeroOrMore(_));
+                let mut ret = vec![];
+                match (s, o) {
+                    (Some(s), _) => {
+                        for y in self.reachable(p, scope, s, zero, true)? {
+                            if o.map(|o| Term::eq(o, &y)).unwrap_or(true) {
+                                ret.push((s.clone(), y));
+                            }
+                        }
+                    }
+                    (None, Some(o)) => {
+                        for x in self.reachable(p, scope, o, zero, false)? {
+                            ret.push((x, o.clone()));
+                        }
+                    }
+                    (None, None) => {
+                        let starts = if zero {
+                            self.all_nodes(scope)?
+                        } else {
+                            let mut starts: Vec<_> = self
+                                .path_pairs(p, scope, None, None)?
+                                .into_iter()
+                                .map(|(x, _)| x)
+                                .collect();
+                            dedup_from(&mut starts, 0);
+                            starts
+                        };
+                        for x in starts {
+                            for y in self.reachable(p, scope, &x, zero, true)? {
+                                ret.push((x.clone(), y));
+                            }
+                        }
+                    }
+                }
+                Ok(ret)
+            }
+            NegatedSet(fwd, inv) => {
+                let mut ret = vec![];
+                if !fwd.is_empty() || inv.is_empty() {
+                    for quad in self
+                        .dataset
+                        .quads_matching(TMatch(s), Any, TMatch(o), scope.graph)
+                    {
+                        let quad = quad.map_err(dataset_error)?;
+                        if !fwd.iter().any(|p| Term::eq(p, quad.p())) {
+                            ret.push((ArcTerm::from_term(quad.s()), ArcTerm::from_term(quad.o())));
+                        }
+                    }
+                }
+                if !inv.is_empty() {
+                    for quad in self
+                        .dataset
+                        .quads_matching(TMatch(o), Any, TMatch(s), scope.graph)
+                    {
+                        let quad = quad.map_err(dataset_error)?;
+                        if !inv.iter().any(|p| Term::eq(p, quad.p())) {
+                            ret.push((ArcTerm::from_term(quad.o()), ArcTerm::from_term(quad.s())));
+                        }
+                    }
+                }
+                if scope.graph.len() > 1 {
+                    dedup_from(&mut ret, 0);
+                }
+                Ok(ret)
+            }
+        }
+    }
+
+    /// The nodes reachable from `start` through one or more steps of `path`
+    /// (or zero or more if `zero` is true), in the given direction.
+    fn reachable(
+        &self,
+        path: &PropertyPath,
+        scope: Scope,
+        start: &ArcTerm,
+        zero: bool,
+        forward: bool,
+    ) -> EvalResult<Vec<ArcTerm>> {
+        let mut seen = HashSet::new();
+        let mut ret = vec![];
+        let mut queue = VecDeque::new();
+        if zero {
+            seen.insert(start.clone());
+            ret.push(start.clone());
+        }
+        queue.push_back(start.clone());
+        while let Some(node) = queue.pop_front() {
+            let next = if forward {
+                self.path_pairs(path, scope, Some(&node), None)?
+                    .into_iter()
+                    .map(|(_, y)| y)
+                    .collect::<Vec<_>>()
+            } else {
+                self.path_pairs(path, scope, None, Some(&node))?
+                    .into_iter()
+                    .map(|(x, _)| x)
+                    .collect()
+            };
+            for n in next {
+                if seen.insert(n.clone()) {
+                    ret.push(n.clone());
+                    queue.push_back(n);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    fn zero_length_pairs(
+        &self,
+        scope: Scope,
+        s: Option<&ArcTerm>,
+        o: Option<&ArcTerm>,
+    ) -> EvalResult<Vec<(ArcTerm, ArcTerm)>> {
+        Ok(match (s, o) {
+            (Some(s), Some(o)) if !Term::eq(s, o) => vec![],
+            (Some(n), _) | (_, Some(n)) => vec![(n.clone(), n.clone())],
+            (None, None) => self
+                .all_nodes(scope)?
+                .into_iter()
+                .map(|n| (n.clone(), n))
+                .collect(),
+        })
+    }
+
+    /// All the subjects and objects of the active graph
+    fn all_nodes(&self, scope: Scope) -> EvalResult<Vec<ArcTerm>> {
+        let mut seen = HashSet::new();
+        let mut ret = vec![];
+        for quad in self.dataset.quads_matching(Any, Any, Any, scope.graph) {
+            let quad = quad.map_err(dataset_error)?;
+            for t in [ArcTerm::from_term(quad.s()), ArcTerm::from_term(quad.o())] {
+                if seen.insert(t.clone()) {
+                    ret.push(t);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    //
+    // Query forms
+    //
+
+    fn construct(&self, template: &[[TermPattern; 3]], rows: &[Row]) -> Vec<[ArcTerm; 3]> {
+        let mut seen = HashSet::new();
+        let mut ret = vec![];
+        for row in rows {
+            let mut bnodes = HashMap::new();
+            for tp in template {
+                let triple = tp
+                    .iter()
+                    .map(|t| self.instantiate(t, row, &mut bnodes))
+                    .collect::<Option<Vec<_>>>();
+                let Some(triple) = triple else { continue };
+                let [s, p, o]: [ArcTerm; 3] = triple.try_into().unwrap();
+                if s.is_literal() || !p.is_iri() {
+                    continue;
+                }
+                let triple = [s, p, o];
+                if seen.insert(triple.clone()) {
+                    ret.push(triple);
+                }
+            }
+        }
+        ret
+    }
+
+    /// Instantiate the quad templates of an update operation with each of the given rows,
+    /// skipping the instances that are not valid quads.
+    ///
+    /// Templates with no explicit graph are instantiated in `default`.
+    pub(crate) fn instantiate_quads(
+        &self,
+        templates: &[QuadPattern],
+        rows: &[Row],
+        default: Option<&ArcTerm>,
+    ) -> Vec<([ArcTerm; 3], GraphName<ArcTerm>)> {
+        let mut ret = vec![];
+        for row in rows {
+            let mut bnodes = HashMap::new();
+            for qp in templates {
+                let triple = qp
+                    .triple
+                    .iter()
+                    .map(|t| self.instantiate(t, row, &mut bnodes))
+                    .collect::<Option<Vec<_>>>();
+                let Some(triple) = triple else { continue };
+                let [s, p, o]: [ArcTerm; 3] = triple.try_into().unwrap();
+                if s.is_literal() || !p.is_iri() {
+                    continue;
+                }
+                let g = match &qp.graph {
+                    None => default.cloned(),
+                    Some(g) => match self.instantiate(g, row, &mut bnodes) {
+                        Some(g) if g.is_iri() => Some(g),
+                        _ => continue,
+                    },
+                };
+                ret.push(([s, p, o], g));
+            }
+        }
+        ret
+    }
+
+    fn instantiate(
+        &self,
+        tp: &TermPattern,
+        row: &Row,
+        bnodes: &mut HashMap<ArcTerm, ArcTerm>,
+    ) -> Option<ArcTerm> {
+        match tp {
+            TermPattern::Const(t) if t.is_blank_node() => Some(
+                bnodes
+                    .entry(t.clone())
+                    .or_insert_with(|| self.fresh_bnode())
+                    .clone(),
+            ),
+            TermPattern::Const(t) => Some(t.clone()),
+            TermPattern::Var(i) => row[*i].clone(),
+            TermPattern::Triple(spo) => {
+                let s = self.instantiate(&spo[0], row, bnodes)?;
+                let p = self.instantiate(&spo[1], row, bnodes)?;
+                let o = self.instantiate(&spo[2], row, bnodes)?;
+                (!s.is_literal() && p.is_iri()).then(|| ArcTerm::Triple(Arc::new([s, p, o])))
+            }
+        }
+    }
+
+    /// Return the triples describing the given targets,
+    /// i.e. their outgoing arcs in the default graph,
+    /// recursively including the description of blank nodes.
+    fn describe(&self, targets: &[TermPattern], rows: &[Row]) -> EvalResult<Vec<[ArcTerm; 3]>> {
+        let mut to_describe = vec![];
+        for t in targets {
+            match t {
+                TermPattern::Const(t) => to_describe.push(t.clone()),
+                _ => to_describe.extend(rows.iter().filter_map(|row| ground(t, row))),
+            }
+        }
+        let mut described = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut ret = vec![];
+        while let Some(node) = to_describe.pop() {
+            if !described.insert(node.clone()) {
+                continue;
+            }
+            for quad in self
+                .dataset
+                .quads_matching([&node], Any, Any, &self.default_graph[..])
+            {
+                let quad = quad.map_err(dataset_error)?;
+                let triple = [quad.s(), quad.p(), quad.o()].map(ArcTerm::from_term);
+                if triple[2].is_blank_node() {
+                    to_describe.push(triple[2].clone());
+                }
+                if seen.insert(triple.clone()) {
+                    ret.push(triple);
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// A [`TermMatcher`] matching a given term, or any term if `None`.
+struct TMatch<'a>(Option<&'a ArcTerm>);
+
+impl TermMatcher for TMatch<'_> {
+    type Term = ArcTerm;
+
+    fn matches<T2: Term + ?Sized>(&self, term: &T2) -> bool {
+        match self.0 {
+            Some(t) => Term::eq(t, term.borrow_term()),
+            None => true,
+        }
+    }
+
+    fn constant(&self) -> Option<&Self::Term> {
+        self.0
+    }
+}
+
+pub(crate) fn dataset_error<E: std::error::Error + 'static>(err: E) -> SparqlWrapperError {
+    SparqlWrapperError::Dataset(Box::new(err))
+}
+
+/// Whether pattern `p` can be evaluated by substituting the bindings of a previous solution.
+fn correlatable(p: &GraphPattern) -> bool {
+    use GraphPattern::*;
+    match p {
+        Bgp(_) | Path(..) | Values(..) => true,
+        Join(p1, p2) | Union(p1, p2) => correlatable(p1) && correlatable(p2),
+        Graph(_, p) => correlatable(p),
+        _ => false,
+    }
+}
+
+/// Return the value of `tp` in `row`, if it is fully bound.
+pub(crate) fn ground(tp: &TermPattern, row: &Row) -> Option<ArcTerm> {
+    match tp {
+        TermPattern::Const(t) => Some(t.clone()),
+        TermPattern::Var(i) => row[*i].clone(),
+        TermPattern::Triple(spo) => {
+            let s = ground(&spo[0], row)?;
+            let p = ground(&spo[1], row)?;
+            let o = ground(&spo[2], row)?;
+            Some(ArcTerm::Triple(Arc::new([s, p, o])))
+        }
+    }
+}
+
+/// Check that term `t` matches `tp`, binding variables in `row` as necessary.
+fn unify<T: Term>(tp: &TermPattern, t: T, row: &mut Row) -> bool {
+    match tp {
+        TermPattern::Const(c) => Term::eq(c, t),
+        TermPattern::Var(i) => match &row[*i] {
+            Some(val) => Term::eq(val, t),
+            None => {
+                row[*i] = Some(ArcTerm::from_term(t));
+                true
+            }
+        },
+        TermPattern::Triple(spo) => match t.triple() {
+            Some([s, p, o]) => {
+                unify(&spo[0], s, row) && unify(&spo[1], p, row) && unify(&spo[2], o, row)
+            }
+            None => false,
+        },
+    }
+}
+
+/// A score estimating how selective a triple pattern is, given the bindings in `row`.
+fn selectivity(tp: &[TermPattern; 3], row: &Row) -> u8 {
+    let bound = |t: &TermPattern| match t {
+        TermPattern::Const(_) => true,
+        TermPattern::Var(i) => row[*i].is_some(),
+        TermPattern::Triple(_) => false,
+    };
+    let mut score = 1;
+    if bound(&tp[0]) {
+        score += 4;
+    }
+    if bound(&tp[1]) {
+        score += 1;
+    }
+    if bound(&tp[2]) {
+        score += 3;
+    }
+    score
+}
+
+/// Merge two compatible rows, or return `None` if they are not compatible.
+pub(crate) fn merge(r1: &Row, r2: &Row) -> Option<Row> {
+    let mut ret = r1.clone();
+    for (a, b) in ret.iter_mut().zip(r2.iter()) {
+        if let Some(b) = b {
+            match a {
+                None => *a = Some(b.clone()),
+                Some(x) => {
+                    if !Term::eq(&*x, b) {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+    Some(ret)
+}
+
+/// Remove duplicate rows, preserving order.
+fn distinct(rows: Vec<Row>) -> Vec<Row> {
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| seen.insert(row.clone()))
+        .collect()
+}
+
+/// Skip the first `offset` rows, and keep at most `limit` of the remaining ones.
+fn slice(rows: Vec<Row>, offset: usize, limit: Option<usize>) -> Vec<Row> {
+    let rows = rows.into_iter().skip(offset);
+    match limit {
+        Some(limit) => rows.take(limit).collect(),
+        None => rows.collect(),
+    }
+}
+
+/// Remove duplicates in `v[start..]`, preserving order.
+fn dedup_from<T: Clone + Eq + std::hash::Hash>(v: &mut Vec<T>, start: usize) {
+    let mut seen = HashSet::new();
+    let mut i = 0;
+    v.retain(|x| {
+        i += 1;
+        i <= start || seen.insert(x.clone())
+    });
+}
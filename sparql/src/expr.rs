@@ -0,0 +1,811 @@
+//! I implement the evaluation of [SPARQL expressions](https://www.w3.org/TR/sparql11-query/#expressions).
+//!
+//! Evaluation errors are represented by `None`.
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use sha2::Digest;
+use sophia_api::dataset::Dataset;
+use sophia_api::term::{IriRef, LanguageTag, Term};
+use sophia_term::ArcTerm;
+
+use crate::algebra::*;
+use crate::exec::{Evaluator, Row, Scope};
+use crate::value::*;
+
+impl<'a, D: Dataset + ?Sized> Evaluator<'a, D> {
+    /// Evaluate expression `e` in the context of solution `row`.
+    pub(crate) fn eval_expr(&self, e: &Expression, row: &Row, scope: Scope) -> Option<ArcTerm> {
+        use Expression::*;
+        match e {
+            Const(t) => Some(t.clone()),
+            Var(v) => row[*v].clone(),
+            Or(e1, e2) => match (self.eval_ebv(e1, row, scope), self.eval_ebv(e2, row, scope)) {
+                (Some(true), _) | (_, Some(true)) => Some(bool_literal(true)),
+                (Some(false), Some(false)) => Some(bool_literal(false)),
+                _ => None,
+            },
+            And(e1, e2) => match (self.eval_ebv(e1, row, scope), self.eval_ebv(e2, row, scope)) {
+                (Some(false), _) | (_, Some(false)) => Some(bool_literal(false)),
+                (Some(true), Some(true)) => Some(bool_literal(true)),
+                _ => None,
+            },
+            Not(e) => self.eval_ebv(e, row, scope).map(|b| bool_literal(!b)),
+            Compare(op, e1, e2) => {
+                let v1 = self.eval_expr(e1, row, scope)?;
+                let v2 = self.eval_expr(e2, row, scope)?;
+                compare(*op, &v1, &v2).map(bool_literal)
+            }
+            In(e, list, positive) => {
+                let v = self.eval_expr(e, row, scope)?;
+                let mut error = false;
+                for e2 in list {
+                    match self
+                        .eval_expr(e2, row, scope)
+                        .and_then(|v2| equals(&v, &v2))
+                    {
+                        Some(true) => return Some(bool_literal(*positive)),
+                        Some(false) => (),
+                        None => error = true,
+                    }
+                }
+                (!error).then(|| bool_literal(!positive))
+            }
+            Arith(op, e1, e2) => {
+                let n1 = as_numeric(&self.eval_expr(e1, row, scope)?)??;
+                let n2 = as_numeric(&self.eval_expr(e2, row, scope)?)??;
+                arith(*op, n1, n2).map(Numeric::to_term)
+            }
+            Neg(e) => {
+                let n = match as_numeric(&self.eval_expr(e, row, scope)?)?? {
+                    Numeric::Integer(i) => Numeric::Integer(i.checked_neg()?),
+                    Numeric::Decimal(d) => Numeric::Decimal(-d),
+                    Numeric::Float(f) => Numeric::Float(-f),
+                    Numeric::Double(d) => Numeric::Double(-d),
+                };
+                Some(n.to_term())
+            }
+            Plus(e) => {
+                let v = self.eval_expr(e, row, scope)?;
+                as_numeric(&v)??;
+                Some(v)
+            }
+            Bound(v) => Some(bool_literal(row[*v].is_some())),
+            If(cond, e1, e2) => {
+                if self.eval_ebv(cond, row, scope)? {
+                    self.eval_expr(e1, row, scope)
+                } else {
+                    self.eval_expr(e2, row, scope)
+                }
+            }
+            Coalesce(list) => list.iter().find_map(|e| self.eval_expr(e, row, scope)),
+            Exists(p, positive) => {
+                let scope = Scope {
+                    substitute: true,
+                    ..scope
+                };
+                match self.eval(p, scope, row) {
+                    Ok(rows) => Some(bool_literal(rows.is_empty() != *positive)),
+                    Err(err) => {
+                        self.record_error(err);
+                        None
+                    }
+                }
+            }
+            Call(f, args) => self.call(*f, args, row, scope),
+            Custom(iri, args) => {
+                let args = args
+                    .iter()
+                    .map(|e| self.eval_expr(e, row, scope))
+                    .collect::<Option<Vec<_>>>()?;
+                cast(iri, &args)
+            }
+        }
+    }
+
+    /// Evaluate the [effective boolean value](https://www.w3.org/TR/sparql11-query/#ebv)
+    /// of expression `e` in the context of solution `row`.
+    pub(crate) fn eval_ebv(&self, e: &Expression, row: &Row, scope: Scope) -> Option<bool> {
+        ebv(&self.eval_expr(e, row, scope)?)
+    }
+
+    fn call(&self, f: Function, args: &[Expression], row: &Row, scope: Scope) -> Option<ArcTerm> {
+        use Function::*;
+        match f {
+            Rand => {
+                let r = (self.next_rand() >> 11) as f64 / (1_u64 << 53) as f64;
+                return Some(Numeric::Double(r).to_term());
+            }
+            Now => return Some(self.now.clone()),
+            Uuid => return Some(iri_term(&format!("urn:uuid:{}", self.uuid()))),
+            StrUuid => return Some(string_literal(&self.uuid())),
+            Bnode if args.is_empty() => return Some(self.fresh_bnode()),
+            _ => (),
+        }
+        let args = args
+            .iter()
+            .map(|e| self.eval_expr(e, row, scope))
+            .collect::<Option<Vec<_>>>()?;
+        match f {
+            Str => match &args[0] {
+                ArcTerm::Iri(iri) => Some(string_literal(iri.as_str())),
+                t => Some(string_literal(literal_parts(t)?.0)),
+            },
+            Lang => Some(string_literal(literal_parts(&args[0])?.2.unwrap_or(""))),
+            LangMatches => {
+                let tag = simple_str(&args[0])?.to_ascii_lowercase();
+                let range = simple_str(&args[1])?.to_ascii_lowercase();
+                Some(bool_literal(if range == "*" {
+                    !tag.is_empty()
+                } else {
+                    tag == range || tag.starts_with(&format!("{range}-"))
+                }))
+            }
+            Datatype => Some(iri_term(literal_parts(&args[0])?.1)),
+            Iri => match &args[0] {
+                ArcTerm::Iri(_) => Some(args[0].clone()),
+                t => {
+                    let txt = simple_str(t)?;
+                    let iri = match self.base() {
+                        Some(base) => base.resolve(txt).ok()?.unwrap(),
+                        None => txt.to_string(),
+                    };
+                    IriRef::new(Arc::<str>::from(iri)).ok().map(ArcTerm::Iri)
+                }
+            },
+            Bnode => {
+                let mut hasher = DefaultHasher::new();
+                Hash::hash(simple_str(&args[0])?, &mut hasher);
+                Some(bnode_term(&format!(
+                    "{}h{:x}",
+                    self.bnode_prefix(),
+                    hasher.finish()
+                )))
+            }
+            Abs | Ceil | Floor | Round => {
+                let op: fn(f64) -> f64 = match f {
+                    Abs => f64::abs,
+                    Ceil => f64::ceil,
+                    Floor => f64::floor,
+                    _ => |x| (x + 0.5).floor(),
+                };
+                let n = match as_numeric(&args[0])?? {
+                    Numeric::Integer(i) if f == Abs => Numeric::Integer(i.checked_abs()?),
+                    Numeric::Integer(i) => Numeric::Integer(i),
+                    Numeric::Decimal(d) => Numeric::Decimal(op(d)),
+                    Numeric::Float(x) => Numeric::Float(op(x as f64) as f32),
+                    Numeric::Double(d) => Numeric::Double(op(d)),
+                };
+                Some(n.to_term())
+            }
+            Concat => {
+                let mut lex = String::new();
+                let mut lang = None;
+                let mut same_lang = true;
+                for arg in &args {
+                    let (txt, tag) = string_parts(arg)?;
+                    lex.push_str(txt);
+                    match lang {
+                        None => lang = Some(tag),
+                        Some(prev) => same_lang &= same_tag(prev, tag),
+                    }
+                }
+                match lang {
+                    Some(Some(tag)) if same_lang => Some(lang_literal(&lex, tag)),
+                    _ => Some(string_literal(&lex)),
+                }
+            }
+            SubStr => {
+                let (txt, tag) = string_parts(&args[0])?;
+                let start = xpath_round(as_numeric(&args[1])??.as_f64());
+                let len = match args.get(2) {
+                    Some(arg) => Some(xpath_round(as_numeric(arg)??.as_f64())),
+                    None => None,
+                };
+                let sub: String = txt
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        let pos = (*i + 1) as f64;
+                        pos >= start && len.map(|len| pos < start + len).unwrap_or(true)
+                    })
+                    .map(|(_, c)| c)
+                    .collect();
+                Some(with_lang(&sub, tag))
+            }
+            StrLen => Some(integer_literal(
+                string_parts(&args[0])?.0.chars().count() as i64
+            )),
+            Replace => {
+                let (txt, tag) = string_parts(&args[0])?;
+                let pattern = simple_str(&args[1])?;
+                let replacement = xpath_replacement(simple_str(&args[2])?)?;
+                let flags = match args.get(3) {
+                    Some(arg) => simple_str(arg)?,
+                    None => "",
+                };
+                let re = self.regex(pattern, flags)?;
+                if re.is_match("") {
+                    return None;
+                }
+                Some(with_lang(&re.replace_all(txt, replacement.as_str()), tag))
+            }
+            UCase => {
+                let (txt, tag) = string_parts(&args[0])?;
+                Some(with_lang(&txt.to_uppercase(), tag))
+            }
+            LCase => {
+                let (txt, tag) = string_parts(&args[0])?;
+                Some(with_lang(&txt.to_lowercase(), tag))
+            }
+            EncodeForUri => {
+                let (txt, _) = string_parts(&args[0])?;
+                let mut encoded = String::with_capacity(txt.len());
+                for b in txt.bytes() {
+                    if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+                        encoded.push(b as char);
+                    } else {
+                        encoded.push_str(&format!("%{b:02X}"));
+                    }
+                }
+                Some(string_literal(&encoded))
+            }
+            Contains => {
+                let (s1, _, s2) = compatible_args(&args[0], &args[1])?;
+                Some(bool_literal(s1.contains(s2)))
+            }
+            StrStarts => {
+                let (s1, _, s2) = compatible_args(&args[0], &args[1])?;
+                Some(bool_literal(s1.starts_with(s2)))
+            }
+            StrEnds => {
+                let (s1, _, s2) = compatible_args(&args[0], &args[1])?;
+                Some(bool_literal(s1.ends_with(s2)))
+            }
+            StrBefore => {
+                let (s1, tag, s2) = compatible_args(&args[0], &args[1])?;
+                Some(match s1.find(s2) {
+                    Some(i) => with_lang(&s1[..i], tag),
+                    None => string_literal(""),
+                })
+            }
+            StrAfter => {
+                let (s1, tag, s2) = compatible_args(&args[0], &args[1])?;
+                Some(match s1.find(s2) {
+                    Some(i) => with_lang(&s1[i + s2.len()..], tag),
+                    None => string_literal(""),
+                })
+            }
+            Year => Some(integer_literal(as_datetime(&args[0])?.year)),
+            Month => Some(integer_literal(as_datetime(&args[0])?.month as i64)),
+            Day => Some(integer_literal(as_datetime(&args[0])?.day as i64)),
+            Hours => Some(integer_literal(as_datetime(&args[0])?.hour as i64)),
+            Minutes => Some(integer_literal(as_datetime(&args[0])?.minute as i64)),
+            Seconds => Some(Numeric::Decimal(as_datetime(&args[0])?.second).to_term()),
+            Timezone => {
+                let tz = as_datetime(&args[0])?.tz?;
+                Some(typed_literal(&format_duration(tz), &XSD_DAYTIMEDURATION))
+            }
+            Tz => {
+                let tz = as_datetime(&args[0])?.tz;
+                Some(string_literal(&tz.map(format_tz).unwrap_or_default()))
+            }
+            Md5 => Some(string_literal(&hex_digest::<md5::Md5>(simple_str(
+                &args[0],
+            )?))),
+            Sha1 => Some(string_literal(&hex_digest::<sha1::Sha1>(simple_str(
+                &args[0],
+            )?))),
+            Sha256 => Some(string_literal(&hex_digest::<sha2::Sha256>(simple_str(
+                &args[0],
+            )?))),
+            Sha384 => Some(string_literal(&hex_digest::<sha2::Sha384>(simple_str(
+                &args[0],
+            )?))),
+            Sha512 => Some(string_literal(&hex_digest::<sha2::Sha512>(simple_str(
+                &args[0],
+            )?))),
+            StrLang => {
+                let lex = simple_str(&args[0])?;
+                let tag = LanguageTag::new(Arc::<str>::from(simple_str(&args[1])?)).ok()?;
+                Some(lang_literal(lex, &tag))
+            }
+            StrDt => {
+                let lex = simple_str(&args[0])?;
+                match &args[1] {
+                    ArcTerm::Iri(dt) => Some(typed_literal(lex, dt)),
+                    _ => None,
+                }
+            }
+            SameTerm => Some(bool_literal(Term::eq(&args[0], &args[1]))),
+            IsIri => Some(bool_literal(args[0].is_iri())),
+            IsBlank => Some(bool_literal(args[0].is_blank_node())),
+            IsLiteral => Some(bool_literal(args[0].is_literal())),
+            IsNumeric => Some(bool_literal(matches!(as_numeric(&args[0]), Some(Some(_))))),
+            IsTriple => Some(bool_literal(args[0].is_triple())),
+            Regex => {
+                let (txt, _) = string_parts(&args[0])?;
+                let pattern = simple_str(&args[1])?;
+                let flags = match args.get(2) {
+                    Some(arg) => simple_str(arg)?,
+                    None => "",
+                };
+                Some(bool_literal(self.regex(pattern, flags)?.is_match(txt)))
+            }
+            Triple => {
+                let [s, p, o]: [ArcTerm; 3] = args.try_into().ok()?;
+                (!s.is_literal() && p.is_iri()).then(|| ArcTerm::Triple(Arc::new([s, p, o])))
+            }
+            Subject | Predicate | Object => match &args[0] {
+                ArcTerm::Triple(spo) => Some(
+                    spo[match f {
+                        Subject => 0,
+                        Predicate => 1,
+                        _ => 2,
+                    }]
+                    .clone(),
+                ),
+                _ => None,
+            },
+            Rand | Now | Uuid | StrUuid => unreachable!(),
+        }
+    }
+
+    /// Generate a random (version 4) UUID.
+    fn uuid(&self) -> String {
+        let a = (self.next_rand() & 0xFFFF_FFFF_FFFF_0FFF) | 0x4000;
+        let b = (self.next_rand() & 0x3FFF_FFFF_FFFF_FFFF) | 0x8000_0000_0000_0000;
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            a >> 32,
+            (a >> 16) & 0xFFFF,
+            a & 0xFFFF,
+            b >> 48,
+            b & 0xFFFF_FFFF_FFFF
+        )
+    }
+
+    /// Build (or retrieve from the cache) the regular expression with the given pattern and flags.
+    fn regex(&self, pattern: &str, flags: &str) -> Option<regex::Regex> {
+        let key = (pattern.to_string(), flags.to_string());
+        if let Some(re) = self.regexes.borrow().get(&key) {
+            return re.clone();
+        }
+        let re = build_regex(pattern, flags);
+        self.regexes.borrow_mut().insert(key, re.clone());
+        re
+    }
+}
+
+/// Compute the [effective boolean value](https://www.w3.org/TR/sparql11-query/#ebv) of `term`.
+pub(crate) fn ebv(term: &ArcTerm) -> Option<bool> {
+    if let Some(b) = as_boolean(term) {
+        return Some(b.unwrap_or(false));
+    }
+    if let Some(n) = as_numeric(term) {
+        return Some(n.map(|n| n.ebv()).unwrap_or(false));
+    }
+    simple_str(term).map(|txt| !txt.is_empty())
+}
+
+/// Apply a comparison operator.
+pub(crate) fn compare(op: CompareOp, t1: &ArcTerm, t2: &ArcTerm) -> Option<bool> {
+    match op {
+        CompareOp::Eq => equals(t1, t2),
+        CompareOp::Ne => equals(t1, t2).map(|b| !b),
+        _ => {
+            let ord = value_cmp(t1, t2)?;
+            Some(match op {
+                CompareOp::Lt => ord.is_lt(),
+                CompareOp::Le => ord.is_le(),
+                CompareOp::Gt => ord.is_gt(),
+                _ => ord.is_ge(),
+            })
+        }
+    }
+}
+
+/// The `=` operator, as defined by SPARQL (including [RDFterm-equal](https://www.w3.org/TR/sparql11-query/#func-RDFterm-equal)).
+pub(crate) fn equals(t1: &ArcTerm, t2: &ArcTerm) -> Option<bool> {
+    if let (Some(n1), Some(n2)) = (as_numeric(t1), as_numeric(t2)) {
+        return match (n1, n2) {
+            (Some(n1), Some(n2)) => Some(Numeric::partial_cmp(n1, n2) == Some(Ordering::Equal)),
+            _ => Term::eq(t1, t2).then_some(true),
+        };
+    }
+    if let (Some(b1), Some(b2)) = (as_boolean(t1), as_boolean(t2)) {
+        return match (b1, b2) {
+            (Some(b1), Some(b2)) => Some(b1 == b2),
+            _ => Term::eq(t1, t2).then_some(true),
+        };
+    }
+    if let (Some(d1), Some(d2)) = (as_datetime(t1), as_datetime(t2)) {
+        return Some(d1.timestamp() == d2.timestamp());
+    }
+    if let (ArcTerm::Triple(spo1), ArcTerm::Triple(spo2)) = (t1, t2) {
+        let mut ret = Some(true);
+        for (x1, x2) in spo1.iter().zip(spo2.iter()) {
+            match equals(x1, x2) {
+                Some(true) => (),
+                Some(false) => return Some(false),
+                None => ret = None,
+            }
+        }
+        return ret;
+    }
+    if Term::eq(t1, t2) {
+        Some(true)
+    } else if t1.is_literal() && t2.is_literal() {
+        // literals with different values are only known to be different
+        // if both their datatypes are supported
+        (has_known_datatype(t1) && has_known_datatype(t2)).then_some(false)
+    } else {
+        Some(false)
+    }
+}
+
+/// Compare the values of two literals, if they are comparable with `<`.
+fn value_cmp(t1: &ArcTerm, t2: &ArcTerm) -> Option<Ordering> {
+    if let (Some(n1), Some(n2)) = (as_numeric(t1), as_numeric(t2)) {
+        return Numeric::partial_cmp(n1?, n2?);
+    }
+    if let (Some(s1), Some(s2)) = (simple_str(t1), simple_str(t2)) {
+        return Some(Ord::cmp(s1, s2));
+    }
+    if let (Some(b1), Some(b2)) = (as_boolean(t1), as_boolean(t2)) {
+        return Some(Ord::cmp(&b1?, &b2?));
+    }
+    if let (Some(d1), Some(d2)) = (as_datetime(t1), as_datetime(t2)) {
+        return d1.timestamp().partial_cmp(&d2.timestamp());
+    }
+    None
+}
+
+/// The total order used by `ORDER BY` (and by `MIN` and `MAX`),
+/// extending the one defined in [SPARQL 1.1 §15.1](https://www.w3.org/TR/sparql11-query/#modOrderBy).
+pub(crate) fn order_cmp(t1: Option<&ArcTerm>, t2: Option<&ArcTerm>) -> Ordering {
+    let (t1, t2) = match (t1, t2) {
+        (None, None) => return Ordering::Equal,
+        (None, _) => return Ordering::Less,
+        (_, None) => return Ordering::Greater,
+        (Some(t1), Some(t2)) => (t1, t2),
+    };
+    let rank = |t: &ArcTerm| match t {
+        ArcTerm::BlankNode(_) => 0,
+        ArcTerm::Iri(_) => 1,
+        ArcTerm::Literal(_) => 2,
+        ArcTerm::Triple(_) => 3,
+        ArcTerm::Variable(_) => 4,
+    };
+    Ord::cmp(&rank(t1), &rank(t2)).then_with(|| match (t1, t2) {
+        (ArcTerm::Literal(_), ArcTerm::Literal(_)) => {
+            let (c1, c2) = (literal_category(t1), literal_category(t2));
+            c1.cmp(&c2)
+                .then_with(|| literal_value_cmp(c1, t1, t2))
+                .then_with(|| Term::cmp(t1, t2))
+        }
+        (ArcTerm::Triple(spo1), ArcTerm::Triple(spo2)) => spo1
+            .iter()
+            .zip(spo2.iter())
+            .map(|(x1, x2)| order_cmp(Some(x1), Some(x2)))
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal),
+        _ => Term::cmp(t1, t2),
+    })
+}
+
+/// Literals are sorted by category first: numerics, dates, booleans, strings, others.
+fn literal_category(t: &ArcTerm) -> u8 {
+    if matches!(as_numeric(t), Some(Some(_))) {
+        0
+    } else if as_datetime(t).is_some() {
+        1
+    } else if matches!(as_boolean(t), Some(Some(_))) {
+        2
+    } else if simple_str(t).is_some() {
+        3
+    } else {
+        4
+    }
+}
+
+/// Compare two literals of the same category (a total order).
+fn literal_value_cmp(category: u8, t1: &ArcTerm, t2: &ArcTerm) -> Ordering {
+    match category {
+        0 => match (as_numeric(t1), as_numeric(t2)) {
+            (Some(Some(Numeric::Integer(i1))), Some(Some(Numeric::Integer(i2)))) => i1.cmp(&i2),
+            (Some(Some(n1)), Some(Some(n2))) => n1.as_f64().total_cmp(&n2.as_f64()),
+            _ => Ordering::Equal,
+        },
+        1 => match (as_datetime(t1), as_datetime(t2)) {
+            (Some(d1), Some(d2)) => d1.timestamp().total_cmp(&d2.timestamp()),
+            _ => Ordering::Equal,
+        },
+        2 => match (as_boolean(t1), as_boolean(t2)) {
+            (Some(Some(b1)), Some(Some(b2))) => Ord::cmp(&b1, &b2),
+            _ => Ordering::Equal,
+        },
+        3 => simple_str(t1).cmp(&simple_str(t2)),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Apply an arithmetic operator.
+pub(crate) fn arith(op: ArithOp, n1: Numeric, n2: Numeric) -> Option<Numeric> {
+    match op {
+        ArithOp::Add => Numeric::apply(n1, n2, i64::checked_add, |a, b| a + b),
+        ArithOp::Sub => Numeric::apply(n1, n2, i64::checked_sub, |a, b| a - b),
+        ArithOp::Mul => Numeric::apply(n1, n2, i64::checked_mul, |a, b| a * b),
+        ArithOp::Div => {
+            // the division of two integers is a decimal
+            let n1 = match n1 {
+                Numeric::Integer(i) => Numeric::Decimal(i as f64),
+                n => n,
+            };
+            Numeric::apply(n1, n2, |_, _| None, |a, b| a / b)
+        }
+    }
+}
+
+/// Apply an XSD cast function (e.g. `xsd:integer(?x)`).
+fn cast(function: &ArcTerm, args: &[ArcTerm]) -> Option<ArcTerm> {
+    let ArcTerm::Iri(function) = function else {
+        return None;
+    };
+    let target = function.as_str().strip_prefix(XSD)?;
+    let [arg] = args else {
+        return None;
+    };
+    match target {
+        "string" => match arg {
+            ArcTerm::Iri(iri) => Some(string_literal(iri.as_str())),
+            t => Some(string_literal(literal_parts(t)?.0)),
+        },
+        "boolean" => {
+            if let Some(b) = as_boolean(arg) {
+                b.map(bool_literal)
+            } else if let Some(n) = as_numeric(arg) {
+                n.map(|n| bool_literal(n.ebv()))
+            } else {
+                match simple_str(arg)?.trim() {
+                    "true" | "1" => Some(bool_literal(true)),
+                    "false" | "0" => Some(bool_literal(false)),
+                    _ => None,
+                }
+            }
+        }
+        "integer" => {
+            if let Some(n) = as_numeric(arg) {
+                let d = match n? {
+                    Numeric::Integer(i) => return Some(integer_literal(i)),
+                    n => n.as_f64().trunc(),
+                };
+                (d.is_finite() && d.abs() < 9.2e18).then(|| integer_literal(d as i64))
+            } else if let Some(b) = as_boolean(arg) {
+                Some(integer_literal(b? as i64))
+            } else {
+                parse_integer(simple_str(arg)?.trim()).map(integer_literal)
+            }
+        }
+        "decimal" => {
+            let d = if let Some(n) = as_numeric(arg) {
+                n?.as_f64()
+            } else if let Some(b) = as_boolean(arg) {
+                b? as i64 as f64
+            } else {
+                parse_decimal(simple_str(arg)?.trim())?
+            };
+            d.is_finite().then(|| Numeric::Decimal(d).to_term())
+        }
+        "float" | "double" => {
+            let d = if let Some(n) = as_numeric(arg) {
+                n?.as_f64()
+            } else if let Some(b) = as_boolean(arg) {
+                b? as i64 as f64
+            } else {
+                parse_double(simple_str(arg)?.trim())?
+            };
+            Some(if target == "float" {
+                Numeric::Float(d as f32).to_term()
+            } else {
+                Numeric::Double(d).to_term()
+            })
+        }
+        "dateTime" => {
+            if let Some(dt) = as_datetime(arg) {
+                Some(typed_literal(&dt.to_lex(), &XSD_DATETIME))
+            } else {
+                let lex = simple_str(arg)?.trim();
+                DateTime::parse(lex, false).map(|_| typed_literal(lex, &XSD_DATETIME))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// If `term` is a simple literal (i.e. an `xsd:string`), return its lexical form.
+fn simple_str(term: &ArcTerm) -> Option<&str> {
+    match string_parts(term)? {
+        (txt, None) => Some(txt),
+        _ => None,
+    }
+}
+
+/// Check that two string literals are [argument-compatible](https://www.w3.org/TR/sparql11-query/#func-arg-compatibility),
+/// and return their lexical forms, and the language tag of the first one.
+#[allow(clippy::type_complexity)]
+fn compatible_args<'t>(
+    t1: &'t ArcTerm,
+    t2: &'t ArcTerm,
+) -> Option<(&'t str, Option<&'t LanguageTag<Arc<str>>>, &'t str)> {
+    let (s1, tag1) = string_parts(t1)?;
+    let (s2, tag2) = string_parts(t2)?;
+    match tag2 {
+        None => Some((s1, tag1, s2)),
+        Some(_) if same_tag(tag1, tag2) => Some((s1, tag1, s2)),
+        _ => None,
+    }
+}
+
+fn same_tag(tag1: Option<&LanguageTag<Arc<str>>>, tag2: Option<&LanguageTag<Arc<str>>>) -> bool {
+    match (tag1, tag2) {
+        (None, None) => true,
+        (Some(t1), Some(t2)) => t1.as_str().eq_ignore_ascii_case(t2.as_str()),
+        _ => false,
+    }
+}
+
+/// Build a string literal, with the given language tag if any.
+fn with_lang(txt: &str, tag: Option<&LanguageTag<Arc<str>>>) -> ArcTerm {
+    match tag {
+        Some(tag) => lang_literal(txt, tag),
+        None => string_literal(txt),
+    }
+}
+
+fn has_known_datatype(t: &ArcTerm) -> bool {
+    match literal_parts(t) {
+        Some((_, dt, _)) => {
+            dt == RDF_LANG_STRING
+                || is_numeric_datatype(dt)
+                || matches!(
+                    dt.strip_prefix(XSD),
+                    Some("string" | "boolean" | "dateTime" | "date")
+                )
+        }
+        None => false,
+    }
+}
+
+/// The rounding function of XPath (rounding half up).
+fn xpath_round(x: f64) -> f64 {
+    (x + 0.5).floor()
+}
+
+/// Convert an XPath replacement string into the syntax of the `regex` crate.
+fn xpath_replacement(replacement: &str) -> Option<String> {
+    let mut ret = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '$' => ret.push_str("$$"),
+                '\\' => ret.push('\\'),
+                _ => return None,
+            },
+            '$' => {
+                let mut group = String::new();
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    group.push(*d);
+                    chars.next();
+                }
+                if group.is_empty() {
+                    return None;
+                }
+                ret.push_str(&format!("${{{group}}}"));
+            }
+            c => ret.push(c),
+        }
+    }
+    Some(ret)
+}
+
+/// Build a regular expression from a pattern and [XPath flags](https://www.w3.org/TR/xpath-functions/#flags).
+fn build_regex(pattern: &str, flags: &str) -> Option<regex::Regex> {
+    let mut inline = String::new();
+    let mut quote = false;
+    for c in flags.chars() {
+        match c {
+            'i' | 's' | 'm' | 'x' => inline.push(c),
+            'q' => quote = true,
+            _ => return None,
+        }
+    }
+    let pattern = if quote {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    if inline.is_empty() {
+        regex::Regex::new(&pattern).ok()
+    } else {
+        regex::Regex::new(&format!("(?{inline}){pattern}")).ok()
+    }
+}
+
+/// Format a timezone offset (in minutes) as an `xsd:dayTimeDuration`.
+fn format_duration(tz: i32) -> String {
+    if tz == 0 {
+        return "PT0S".into();
+    }
+    let sign = if tz < 0 { "-" } else { "" };
+    let (h, m) = (tz.abs() / 60, tz.abs() % 60);
+    let mut txt = format!("{sign}PT");
+    if h > 0 {
+        txt.push_str(&format!("{h}H"));
+    }
+    if m > 0 {
+        txt.push_str(&format!("{m}M"));
+    }
+    txt
+}
+
+fn hex_digest<H: Digest>(txt: &str) -> String {
+    H::digest(txt.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("abc", "$1", None)]
+    #[test_case("a$1b", "a${1}b", Some(()))]
+    #[test_case("a\\$b", "a$$b", Some(()))]
+    #[test_case("a$b", "", None)]
+    fn replacement(xpath: &str, expected: &str, ok: Option<()>) {
+        match (xpath_replacement(xpath), ok) {
+            (Some(got), Some(())) => assert_eq!(got, expected),
+            (None, None) => (),
+            (Some(got), None) => assert_eq!(got, xpath),
+            (None, Some(())) => panic!("{xpath:?} should be valid"),
+        }
+    }
+
+    #[test_case(integer_literal(1), typed_literal("1.0", &XSD_DECIMAL), Some(true))]
+    #[test_case(integer_literal(1), integer_literal(2), Some(false))]
+    #[test_case(string_literal("a"), string_literal("a"), Some(true))]
+    #[test_case(string_literal("a"), integer_literal(1), Some(false))]
+    #[test_case(typed_literal("a", &xsd("foo")), typed_literal("b", &xsd("foo")), None)]
+    #[test_case(iri_term("tag:a"), iri_term("tag:b"), Some(false))]
+    fn equality(t1: ArcTerm, t2: ArcTerm, expected: Option<bool>) {
+        assert_eq!(equals(&t1, &t2), expected);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut terms = vec![
+            string_literal("b"),
+            integer_literal(10),
+            iri_term("tag:x"),
+            typed_literal("2.5", &XSD_DECIMAL),
+            bnode_term("b1"),
+            string_literal("a"),
+        ];
+        terms.sort_by(|t1, t2| order_cmp(Some(t1), Some(t2)));
+        let expected = vec![
+            bnode_term("b1"),
+            iri_term("tag:x"),
+            typed_literal("2.5", &XSD_DECIMAL),
+            integer_literal(10),
+            string_literal("a"),
+            string_literal("b"),
+        ];
+        assert_eq!(terms, expected);
+    }
+}
@@ -0,0 +1,413 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! It provides a native implementation of [SPARQL 1.1 Query](https://www.w3.org/TR/sparql11-query/)
+//! (with the [SPARQL 1.2](https://www.w3.org/TR/sparql12-query/) triple terms and reifiers),
+//! usable with any [`Dataset`].
+//!
+//! It supports the four query forms (SELECT, ASK, CONSTRUCT and DESCRIBE),
+//! `FILTER`, `OPTIONAL`, `UNION`, `MINUS`, `BIND`, `VALUES`, `GRAPH`,
+//! aggregates, sub-queries and property paths.
+//! `SERVICE` is not supported.
+//!
+//! It also implements [SPARQL 1.1 Update](https://www.w3.org/TR/sparql11-update/)
+//! for any [`MutableDataset`], through [`SparqlWrapperMut`]
+//! (except for the `LOAD` operation).
+//!
+//! The [`results`] module provides serializers and parsers
+//! for the standard formats of query results (JSON, XML, CSV and TSV).
+//!
+//! # Example
+//! ```
+//! # use sophia_api::sparql::{SparqlBindings, SparqlDataset};
+//! # use sophia_inmem::dataset::LightDataset;
+//! # use sophia_sparql::SparqlWrapper;
+//! # use sophia_turtle::parser::trig;
+//! # use sophia_api::source::QuadSource;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dataset: LightDataset = trig::parse_str(r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     :alice :knows :bob, :charlie.
+//!     :bob :knows :charlie.
+//! "#).collect_quads()?;
+//!
+//! let query = r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     SELECT ?x (COUNT(?y) AS ?n) { ?x :knows ?y } GROUP BY ?x ORDER BY ?x
+//! "#;
+//! let bindings = SparqlWrapper(&dataset).query(query)?.into_bindings();
+//! assert_eq!(bindings.variables(), vec!["x", "n"]);
+//! assert_eq!(bindings.into_iter().count(), 2);
+//! # Ok(()) }
+//! ```
+//!
+//! # Update example
+//! ```
+//! # use sophia_api::dataset::Dataset;
+//! # use sophia_api::sparql::SparqlUpdatable;
+//! # use sophia_inmem::dataset::LightDataset;
+//! # use sophia_sparql::SparqlWrapperMut;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut dataset = LightDataset::new();
+//! SparqlWrapperMut(&mut dataset).update(r#"
+//!     PREFIX : <https://example.org/ns/>
+//!     INSERT DATA { :alice :knows :bob. :bob :knows :charlie. } ;
+//!     DELETE { ?x :knows ?y } INSERT { ?y :isKnownBy ?x } WHERE { ?x :knows ?y }
+//! "#)?;
+//! assert_eq!(dataset.quads().count(), 2);
+//! # Ok(()) }
+//! ```
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+#![deny(missing_docs)]
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use sophia_api::dataset::{Dataset, MutableDataset};
+use sophia_api::prefix::PrefixMap;
+use sophia_api::sparql::results::QueryResults;
+use sophia_api::sparql::{
+    BindVariables, IntoQuery, ParseWithDefaults, Query, SetQueryDataset, SparqlBindings,
+    SparqlDataset, SparqlResult, SparqlUpdatable,
+};
+use sophia_api::term::{FromTerm, Term, TermKind};
+use sophia_iri::resolve::BaseIri;
+use sophia_iri::Iri;
+use sophia_term::ArcTerm;
+use thiserror::Error;
+
+pub mod algebra;
+pub mod parser;
+pub mod results;
+
+mod exec;
+mod expr;
+mod update;
+mod value;
+
+use exec::Outcome;
+use parser::SparqlParseError;
+
+/// A wrapper around any [`Dataset`], making it a [`SparqlDataset`].
+#[derive(Clone, Copy, Debug)]
+pub struct SparqlWrapper<'a, D: Dataset + ?Sized>(pub &'a D);
+
+impl<'a, D: Dataset + ?Sized> SparqlDataset for SparqlWrapper<'a, D> {
+    type BindingsTerm = ArcTerm;
+    type BindingsResult = Bindings;
+    type TriplesResult = Triples;
+    type SparqlError = SparqlWrapperError;
+    type Query = SparqlQuery;
+
+    fn query<Q>(&self, query: Q) -> Result<SparqlResult<Self>, Self::SparqlError>
+    where
+        Q: IntoQuery<Self::Query>,
+    {
+        run_query(self.0, query)
+    }
+}
+
+/// A wrapper around any [`MutableDataset`], making it a [`SparqlDataset`] and a [`SparqlUpdatable`].
+#[derive(Debug)]
+pub struct SparqlWrapperMut<'a, D: MutableDataset + ?Sized>(pub &'a mut D);
+
+impl<'a, D: MutableDataset + ?Sized> SparqlDataset for SparqlWrapperMut<'a, D> {
+    type BindingsTerm = ArcTerm;
+    type BindingsResult = Bindings;
+    type TriplesResult = Triples;
+    type SparqlError = SparqlWrapperError;
+    type Query = SparqlQuery;
+
+    fn query<Q>(&self, query: Q) -> Result<SparqlResult<Self>, Self::SparqlError>
+    where
+        Q: IntoQuery<Self::Query>,
+    {
+        run_query(&*self.0, query)
+    }
+}
+
+impl<'a, D: MutableDataset + ?Sized> SparqlUpdatable for SparqlWrapperMut<'a, D> {
+    type UpdateError = SparqlWrapperError;
+    type Update = SparqlUpdate;
+
+    fn update<U>(&mut self, update: U) -> Result<(), Self::UpdateError>
+    where
+        U: IntoQuery<Self::Update>,
+    {
+        let request = update.into_query()?;
+        let request: &SparqlUpdate = request.borrow();
+        update::update(self.0, &request.0)
+    }
+}
+
+/// Execute `query` against `dataset`, on behalf of the [`SparqlDataset`] `W`.
+fn run_query<W, D, Q>(dataset: &D, query: Q) -> Result<SparqlResult<W>, SparqlWrapperError>
+where
+    W: SparqlDataset<
+            BindingsResult = Bindings,
+            TriplesResult = Triples,
+            SparqlError = SparqlWrapperError,
+            Query = SparqlQuery,
+        > + ?Sized,
+    D: Dataset + ?Sized,
+    Q: IntoQuery<SparqlQuery>,
+{
+    let query = query.into_query()?;
+    let query: &SparqlQuery = query.borrow();
+    let outcome = exec::execute(
+        dataset,
+        &query.algebra,
+        query.dataset.as_ref(),
+        &query.bindings,
+    )?;
+    Ok(match outcome {
+        Outcome::Bindings(variables, rows) => SparqlResult::Bindings(Bindings { variables, rows }),
+        Outcome::Boolean(b) => SparqlResult::Boolean(b),
+        Outcome::Triples(triples) => {
+            SparqlResult::Triples(triples.into_iter().map(Ok).collect::<Vec<_>>().into_iter())
+        }
+    })
+}
+
+/// The result of a CONSTRUCT or DESCRIBE query executed by a [`SparqlWrapper`].
+pub type Triples = std::vec::IntoIter<Result<[ArcTerm; 3], SparqlWrapperError>>;
+
+/// A SPARQL query, parsed and ready to be executed by a [`SparqlWrapper`].
+///
+/// Cloning a query is cheap, as its internal representation is shared between the clones;
+/// so a query can be prepared once,
+/// then cloned and [parameterized](BindVariables) for each execution.
+#[derive(Clone, Debug)]
+pub struct SparqlQuery {
+    algebra: Arc<algebra::Query>,
+    /// The dataset to use if the query has no `FROM` or `FROM NAMED` clause
+    dataset: Option<algebra::QueryDataset>,
+    /// The values of pre-bound variables
+    bindings: Vec<(usize, ArcTerm)>,
+}
+
+impl SparqlQuery {
+    /// The internal representation of this query.
+    pub fn algebra(&self) -> &algebra::Query {
+        &self.algebra
+    }
+
+    /// The index of the variable with the given name,
+    /// ignoring the variables introduced by the parser.
+    fn variable(&self, name: &str) -> Option<usize> {
+        let name = name.trim_start_matches(['?', '$']);
+        if name.starts_with("_:") {
+            return None;
+        }
+        self.algebra.variable_index(name)
+    }
+}
+
+impl Query for SparqlQuery {
+    type Error = SparqlWrapperError;
+
+    fn parse(query_source: &str) -> Result<Self, Self::Error> {
+        Ok(parser::parse_query(query_source)?.into())
+    }
+}
+
+impl ParseWithDefaults for SparqlQuery {
+    fn parse_with_defaults<P>(
+        query_source: &str,
+        base: Option<Iri<&str>>,
+        prefixes: &P,
+    ) -> Result<Self, Self::Error>
+    where
+        P: PrefixMap + ?Sized,
+    {
+        let (base, prefixes) = defaults(base, prefixes);
+        let parser = parser::Parser::new(query_source)?.with_defaults(base, prefixes);
+        Ok(parser.query()?.into())
+    }
+}
+
+impl SetQueryDataset for SparqlQuery {
+    fn set_default_dataset<T: Term>(
+        &mut self,
+        default_graphs: &[T],
+        named_graphs: &[T],
+    ) -> Result<(), Self::Error> {
+        let graph_names = |graphs: &[T]| {
+            graphs
+                .iter()
+                .map(|g| match g.kind() {
+                    TermKind::Iri | TermKind::BlankNode => Ok(ArcTerm::from_term(g.borrow_term())),
+                    _ => Err(SparqlWrapperError::Preparation(
+                        "graph names must be IRIs or blank nodes".into(),
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        self.dataset = Some(algebra::QueryDataset {
+            default: graph_names(default_graphs)?,
+            named: graph_names(named_graphs)?,
+        });
+        Ok(())
+    }
+}
+
+impl BindVariables for SparqlQuery {
+    fn bind_variable<T: Term>(&mut self, name: &str, value: T) -> Result<(), Self::Error> {
+        let Some(var) = self.variable(name) else {
+            let msg = format!("no variable named {name:?} in query");
+            return Err(SparqlWrapperError::Preparation(msg));
+        };
+        if value.is_variable() {
+            let msg = format!("variable {name:?} can not be bound to a variable");
+            return Err(SparqlWrapperError::Preparation(msg));
+        }
+        let value = ArcTerm::from_term(value);
+        match self.bindings.iter_mut().find(|(v, _)| *v == var) {
+            Some((_, old)) => *old = value,
+            None => self.bindings.push((var, value)),
+        }
+        Ok(())
+    }
+
+    fn unbind_variable(&mut self, name: &str) {
+        if let Some(var) = self.variable(name) {
+            self.bindings.retain(|(v, _)| *v != var);
+        }
+    }
+}
+
+impl From<algebra::Query> for SparqlQuery {
+    fn from(value: algebra::Query) -> Self {
+        SparqlQuery {
+            algebra: Arc::new(value),
+            dataset: None,
+            bindings: vec![],
+        }
+    }
+}
+
+/// A SPARQL update request, parsed and ready to be applied by a [`SparqlWrapperMut`].
+#[derive(Clone, Debug)]
+pub struct SparqlUpdate(Arc<algebra::Update>);
+
+impl SparqlUpdate {
+    /// The internal representation of this update request.
+    pub fn algebra(&self) -> &algebra::Update {
+        &self.0
+    }
+}
+
+impl Query for SparqlUpdate {
+    type Error = SparqlWrapperError;
+
+    fn parse(query_source: &str) -> Result<Self, Self::Error> {
+        Ok(SparqlUpdate(Arc::new(parser::parse_update(query_source)?)))
+    }
+}
+
+impl ParseWithDefaults for SparqlUpdate {
+    fn parse_with_defaults<P>(
+        query_source: &str,
+        base: Option<Iri<&str>>,
+        prefixes: &P,
+    ) -> Result<Self, Self::Error>
+    where
+        P: PrefixMap + ?Sized,
+    {
+        let (base, prefixes) = defaults(base, prefixes);
+        let parser = parser::Parser::new(query_source)?.with_defaults(base, prefixes);
+        Ok(SparqlUpdate(Arc::new(parser.update()?)))
+    }
+}
+
+impl From<algebra::Update> for SparqlUpdate {
+    fn from(value: algebra::Update) -> Self {
+        SparqlUpdate(Arc::new(value))
+    }
+}
+
+/// Convert the arguments of [`ParseWithDefaults::parse_with_defaults`] for the parser.
+fn defaults<P: PrefixMap + ?Sized>(
+    base: Option<Iri<&str>>,
+    prefixes: &P,
+) -> (Option<BaseIri<String>>, HashMap<String, String>) {
+    let base = base.map(|iri| BaseIri::new(iri.as_str().to_string()).unwrap());
+    let prefixes = prefixes
+        .iter()
+        .map(|(prefix, ns)| (prefix.as_str().to_string(), ns.as_str().to_string()))
+        .collect();
+    (base, prefixes)
+}
+
+/// The result of a SELECT query executed by a [`SparqlWrapper`].
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    variables: Vec<Arc<str>>,
+    rows: Vec<Vec<Option<ArcTerm>>>,
+}
+
+impl Bindings {
+    /// Return the list of SELECTed variable names
+    pub fn variables(&self) -> Vec<&str> {
+        self.variables.iter().map(|v| &v[..]).collect()
+    }
+}
+
+impl From<Bindings> for QueryResults<ArcTerm> {
+    fn from(bindings: Bindings) -> Self {
+        let variables = bindings.variables.iter().map(|v| v.to_string()).collect();
+        QueryResults::Bindings {
+            variables,
+            rows: bindings.rows,
+        }
+    }
+}
+
+impl IntoIterator for Bindings {
+    type Item = Result<Vec<Option<ArcTerm>>, SparqlWrapperError>;
+    type IntoIter = std::iter::Map<
+        std::vec::IntoIter<Vec<Option<ArcTerm>>>,
+        fn(Vec<Option<ArcTerm>>) -> Self::Item,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter().map(Ok as fn(_) -> _)
+    }
+}
+
+impl<'a, D: Dataset + ?Sized> SparqlBindings<SparqlWrapper<'a, D>> for Bindings {
+    fn variables(&self) -> Vec<&str> {
+        self.variables.iter().map(|v| &v[..]).collect()
+    }
+}
+
+impl<'a, D: MutableDataset + ?Sized> SparqlBindings<SparqlWrapperMut<'a, D>> for Bindings {
+    fn variables(&self) -> Vec<&str> {
+        self.variables.iter().map(|v| &v[..]).collect()
+    }
+}
+
+/// The error type raised by [`SparqlWrapper`].
+#[derive(Debug, Error)]
+pub enum SparqlWrapperError {
+    /// The query could not be parsed
+    #[error("{0}")]
+    Parse(#[from] SparqlParseError),
+    /// The underlying dataset raised an error
+    #[error("dataset error: {0}")]
+    Dataset(Box<dyn Error>),
+    /// An update operation could not be applied
+    #[error("update error: {0}")]
+    Update(String),
+    /// A prepared query could not be parameterized
+    #[error("query preparation error: {0}")]
+    Preparation(String),
+}
+
+#[cfg(test)]
+mod test;
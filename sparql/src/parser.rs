@@ -0,0 +1,2017 @@
+//! A parser for [SPARQL 1.1](https://www.w3.org/TR/sparql11-query/) queries
+//! and [update requests](https://www.w3.org/TR/sparql11-update/),
+//! including the [SPARQL 1.2](https://www.w3.org/TR/sparql12-query/) triple terms (`<<( s p o )>>`),
+//! reified triples (`<< s p o >>`), reifiers (`~`) and annotations (`{| ... |}`),
+//! producing the [algebra](crate::algebra) used by the query engine.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, Term};
+use sophia_iri::resolve::BaseIri;
+use sophia_term::ArcTerm;
+use thiserror::Error;
+
+use crate::algebra::*;
+use crate::value::*;
+
+mod _lexer;
+use _lexer::{tokenize, Token};
+
+/// Parse a SPARQL query.
+pub fn parse_query(src: &str) -> Result<Query> {
+    Parser::new(src)?.query()
+}
+
+/// Parse a SPARQL update request.
+pub fn parse_update(src: &str) -> Result<Update> {
+    Parser::new(src)?.update()
+}
+
+/// An error raised when parsing an invalid SPARQL query or update request.
+#[derive(Clone, Debug, Error)]
+#[error("SPARQL syntax error at line {line}, column {column}: {message}")]
+pub struct SparqlParseError {
+    /// The error message
+    pub message: String,
+    /// The byte offset where the error occurred
+    pub offset: usize,
+    /// The line where the error occurred (starting at 1)
+    pub line: usize,
+    /// The column where the error occurred (starting at 1, counted in characters)
+    pub column: usize,
+}
+
+impl SparqlParseError {
+    pub(crate) fn new(src: &str, offset: usize, message: impl Into<String>) -> Self {
+        let offset = offset.min(src.len());
+        let before = &src[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        SparqlParseError {
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, SparqlParseError>;
+
+/// The clauses following the WHERE clause
+#[derive(Default)]
+struct Modifiers {
+    group: Option<Vec<(Option<Expression>, usize)>>,
+    having: Vec<Expression>,
+    order: Vec<OrderCondition>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+/// The keyword (if any) following `SELECT`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelectModifier {
+    /// Neither `DISTINCT` nor `REDUCED`
+    All,
+    /// `DISTINCT`
+    Distinct,
+    /// `REDUCED`
+    Reduced,
+}
+
+/// The content of a SELECT clause
+struct SelectClause {
+    modifier: SelectModifier,
+    /// `None` stands for `*`
+    items: Option<Vec<(Option<Expression>, usize)>>,
+}
+
+/// The predicate of a triple pattern
+enum Verb {
+    Simple(TermPattern),
+    Path(PropertyPath),
+}
+
+/// Triple patterns and path patterns accumulated while parsing a triples block
+#[derive(Default)]
+struct Triples {
+    triples: Vec<[TermPattern; 3]>,
+    paths: Vec<(TermPattern, PropertyPath, TermPattern)>,
+}
+
+impl Triples {
+    fn into_pattern(self) -> GraphPattern {
+        let mut p = GraphPattern::Bgp(self.triples);
+        for (s, path, o) in self.paths {
+            p = join(p, GraphPattern::Path(s, path, o));
+        }
+        p
+    }
+}
+
+pub(crate) struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    base: Option<BaseIri<String>>,
+    prefixes: HashMap<String, String>,
+    variables: Vec<Arc<str>>,
+    /// Blank nodes are parsed as variables in graph patterns, and as constants in templates
+    bnodes_as_vars: bool,
+    fresh: usize,
+    /// The aggregates encountered so far, if aggregates are allowed in the current context
+    aggregates: Option<Vec<(usize, Aggregate)>>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(src: &'a str) -> Result<Self> {
+        Ok(Parser {
+            src,
+            tokens: tokenize(src)?,
+            pos: 0,
+            base: None,
+            prefixes: HashMap::new(),
+            variables: vec![],
+            bnodes_as_vars: true,
+            fresh: 0,
+            aggregates: None,
+        })
+    }
+
+    /// Set the base IRI and prefix declarations to use, unless the source overrides them.
+    pub(crate) fn with_defaults(
+        mut self,
+        base: Option<BaseIri<String>>,
+        prefixes: HashMap<String, String>,
+    ) -> Self {
+        self.base = base;
+        self.prefixes = prefixes;
+        self
+    }
+
+    //
+    // Token helpers
+    //
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)].0
+    }
+
+    fn advance(&mut self) -> Token {
+        let tok = self.tokens[self.pos].0.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error(&self, msg: impl Into<String>) -> SparqlParseError {
+        SparqlParseError::new(self.src, self.tokens[self.pos].1, msg)
+    }
+
+    fn err<T>(&self, msg: impl Into<String>) -> Result<T> {
+        Err(self.error(msg))
+    }
+
+    fn is_word(&self, kw: &str) -> bool {
+        matches!(self.peek(), Token::Word(w) if w.eq_ignore_ascii_case(kw))
+    }
+
+    fn eat_word(&mut self, kw: &str) -> bool {
+        let ret = self.is_word(kw);
+        if ret {
+            self.advance();
+        }
+        ret
+    }
+
+    fn expect_word(&mut self, kw: &str) -> Result<()> {
+        if self.eat_word(kw) {
+            Ok(())
+        } else {
+            self.err(format!("expected {kw}"))
+        }
+    }
+
+    fn is_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Token::Punct(q) if *q == p)
+    }
+
+    fn eat_punct(&mut self, p: &str) -> bool {
+        let ret = self.is_punct(p);
+        if ret {
+            self.advance();
+        }
+        ret
+    }
+
+    fn expect_punct(&mut self, p: &str) -> Result<()> {
+        if self.eat_punct(p) {
+            Ok(())
+        } else {
+            self.err(format!("expected '{p}'"))
+        }
+    }
+
+    //
+    // Variables and terms
+    //
+
+    fn var(&mut self, name: &str) -> usize {
+        match self.variables.iter().position(|v| &v[..] == name) {
+            Some(i) => i,
+            None => {
+                self.variables.push(name.into());
+                self.variables.len() - 1
+            }
+        }
+    }
+
+    /// Create a new variable, that can not clash with any variable of the query.
+    fn fresh_var(&mut self, kind: &str) -> usize {
+        self.fresh += 1;
+        let name = format!("_:{kind}#{}", self.fresh);
+        self.var(&name)
+    }
+
+    fn fresh_bnode(&mut self) -> TermPattern {
+        if self.bnodes_as_vars {
+            TermPattern::Var(self.fresh_var("anon"))
+        } else {
+            self.fresh += 1;
+            TermPattern::Const(bnode_term(&format!("_anon{}", self.fresh)))
+        }
+    }
+
+    fn bnode(&mut self, label: &str) -> Result<TermPattern> {
+        if BnodeId::new(label).is_err() {
+            return self.err(format!("invalid blank node label {label:?}"));
+        }
+        if self.bnodes_as_vars {
+            Ok(TermPattern::Var(self.var(&format!("_:{label}"))))
+        } else {
+            Ok(TermPattern::Const(bnode_term(label)))
+        }
+    }
+
+    fn expect_var(&mut self) -> Result<usize> {
+        match self.peek().clone() {
+            Token::Var(name) => {
+                self.advance();
+                Ok(self.var(&name))
+            }
+            _ => self.err("expected variable"),
+        }
+    }
+
+    fn resolve_iri_str(&self, raw: &str) -> Result<String> {
+        match &self.base {
+            Some(base) => match base.resolve(raw) {
+                Ok(iri) => Ok(iri.unwrap()),
+                Err(e) => self.err(format!("invalid IRI <{raw}>: {e}")),
+            },
+            None => Ok(raw.to_string()),
+        }
+    }
+
+    fn make_iri(&self, iri: String) -> Result<ArcTerm> {
+        match IriRef::new(Arc::<str>::from(iri)) {
+            Ok(iri) => Ok(ArcTerm::Iri(iri)),
+            Err(e) => self.err(format!("invalid IRI: {e}")),
+        }
+    }
+
+    fn expand_pname(&self, prefix: &str, local: &str) -> Result<ArcTerm> {
+        match self.prefixes.get(prefix) {
+            Some(ns) => self.make_iri(format!("{ns}{local}")),
+            None => self.err(format!("undeclared prefix {prefix:?}")),
+        }
+    }
+
+    fn expect_iri(&mut self) -> Result<ArcTerm> {
+        match self.peek().clone() {
+            Token::Iri(raw) => {
+                let iri = self.resolve_iri_str(&raw)?;
+                let iri = self.make_iri(iri)?;
+                self.advance();
+                Ok(iri)
+            }
+            Token::PName(prefix, local) => {
+                let iri = self.expand_pname(&prefix, &local)?;
+                self.advance();
+                Ok(iri)
+            }
+            _ => self.err("expected IRI"),
+        }
+    }
+
+    fn iri_or_a(&mut self) -> Result<ArcTerm> {
+        if matches!(self.peek(), Token::Word(w) if w == "a") {
+            self.advance();
+            Ok(iri_term(RDF_TYPE))
+        } else {
+            self.expect_iri()
+        }
+    }
+
+    fn var_or_iri(&mut self) -> Result<TermPattern> {
+        if let Token::Var(_) = self.peek() {
+            Ok(TermPattern::Var(self.expect_var()?))
+        } else {
+            Ok(TermPattern::Const(self.expect_iri()?))
+        }
+    }
+
+    /// Parse an IRI, a literal, or a (possibly signed) number.
+    fn constant_term(&mut self) -> Result<ArcTerm> {
+        let sign = if self.eat_punct("-") {
+            "-"
+        } else if self.eat_punct("+") {
+            "+"
+        } else {
+            ""
+        };
+        match self.peek().clone() {
+            Token::Integer(n) => {
+                self.advance();
+                Ok(typed_literal(&format!("{sign}{n}"), &XSD_INTEGER))
+            }
+            Token::Decimal(n) => {
+                self.advance();
+                Ok(typed_literal(&format!("{sign}{n}"), &XSD_DECIMAL))
+            }
+            Token::Double(n) => {
+                self.advance();
+                Ok(typed_literal(&format!("{sign}{n}"), &XSD_DOUBLE))
+            }
+            _ if !sign.is_empty() => self.err("expected number"),
+            Token::String(lex) => {
+                self.advance();
+                match self.peek().clone() {
+                    Token::LangTag(tag) => match LanguageTag::new(Arc::<str>::from(tag)) {
+                        Ok(tag) => {
+                            self.advance();
+                            Ok(lang_literal(&lex, &tag))
+                        }
+                        Err(e) => self.err(format!("invalid language tag: {e}")),
+                    },
+                    Token::Punct("^^") => {
+                        self.advance();
+                        match self.expect_iri()? {
+                            ArcTerm::Iri(dt) => Ok(typed_literal(&lex, &dt)),
+                            _ => unreachable!(),
+                        }
+                    }
+                    _ => Ok(string_literal(&lex)),
+                }
+            }
+            Token::Word(w) if w == "true" || w == "false" => {
+                self.advance();
+                Ok(bool_literal(w == "true"))
+            }
+            Token::Iri(_) | Token::PName(..) => self.expect_iri(),
+            _ => self.err("expected RDF term"),
+        }
+    }
+
+    //
+    // Query
+    //
+
+    pub(crate) fn query(mut self) -> Result<Query> {
+        self.prologue()?;
+        let (form, dataset, pattern) = if self.is_word("SELECT") {
+            self.select_query()?
+        } else if self.is_word("CONSTRUCT") {
+            self.construct_query()?
+        } else if self.is_word("DESCRIBE") {
+            self.describe_query()?
+        } else if self.is_word("ASK") {
+            self.ask_query()?
+        } else {
+            return self.err("expected SELECT, CONSTRUCT, DESCRIBE or ASK");
+        };
+        if self.peek() != &Token::Eof {
+            return self.err("unexpected token after the end of the query");
+        }
+        Ok(Query {
+            form,
+            dataset,
+            pattern,
+            variables: self.variables,
+            base: self.base,
+        })
+    }
+
+    fn prologue(&mut self) -> Result<()> {
+        loop {
+            if self.eat_word("BASE") {
+                let Token::Iri(raw) = self.peek().clone() else {
+                    return self.err("expected IRI after BASE");
+                };
+                let resolved = self.resolve_iri_str(&raw)?;
+                match BaseIri::new(resolved) {
+                    Ok(base) => self.base = Some(base),
+                    Err(e) => return self.err(format!("invalid base IRI: {e}")),
+                }
+                self.advance();
+            } else if self.eat_word("PREFIX") {
+                let Token::PName(prefix, local) = self.peek().clone() else {
+                    return self.err("expected prefix after PREFIX");
+                };
+                if !local.is_empty() {
+                    return self.err("invalid prefix declaration");
+                }
+                self.advance();
+                let Token::Iri(raw) = self.peek().clone() else {
+                    return self.err("expected IRI after prefix");
+                };
+                let ns = self.resolve_iri_str(&raw)?;
+                self.advance();
+                self.prefixes.insert(prefix, ns);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn select_query(&mut self) -> Result<(QueryForm, Option<QueryDataset>, GraphPattern)> {
+        self.aggregates = Some(vec![]);
+        let clause = self.select_clause()?;
+        let dataset = self.dataset_clauses("FROM")?;
+        let pattern = self.where_clause()?;
+        let modifiers = self.solution_modifiers()?;
+        let values = self.values_clause()?;
+        let aggregates = self.aggregates.take().unwrap_or_default();
+        let (pattern, projection) =
+            self.build_select(pattern, clause, modifiers, values, aggregates)?;
+        Ok((QueryForm::Select(projection), dataset, pattern))
+    }
+
+    fn construct_query(&mut self) -> Result<(QueryForm, Option<QueryDataset>, GraphPattern)> {
+        self.expect_word("CONSTRUCT")?;
+        self.aggregates = Some(vec![]);
+        let (template, dataset, pattern);
+        if self.is_punct("{") {
+            self.advance();
+            self.bnodes_as_vars = false;
+            template = self.triples_template()?;
+            self.bnodes_as_vars = true;
+            self.expect_punct("}")?;
+            dataset = self.dataset_clauses("FROM")?;
+            pattern = self.where_clause()?;
+        } else {
+            dataset = self.dataset_clauses("FROM")?;
+            self.expect_word("WHERE")?;
+            self.expect_punct("{")?;
+            template = self.triples_template()?;
+            self.expect_punct("}")?;
+            pattern = GraphPattern::Bgp(template.clone());
+        }
+        let modifiers = self.solution_modifiers()?;
+        let values = self.values_clause()?;
+        let aggregates = self.aggregates.take().unwrap_or_default();
+        let pattern = self.build_pattern(
+            pattern,
+            modifiers,
+            values,
+            aggregates,
+            vec![],
+            None,
+            SelectModifier::All,
+        );
+        Ok((QueryForm::Construct(template), dataset, pattern))
+    }
+
+    fn describe_query(&mut self) -> Result<(QueryForm, Option<QueryDataset>, GraphPattern)> {
+        self.expect_word("DESCRIBE")?;
+        self.aggregates = Some(vec![]);
+        let mut targets = vec![];
+        let star = self.eat_punct("*");
+        if !star {
+            while let Token::Var(_) | Token::Iri(_) | Token::PName(..) = self.peek() {
+                targets.push(self.var_or_iri()?);
+            }
+            if targets.is_empty() {
+                return self.err("expected variable or IRI after DESCRIBE");
+            }
+        }
+        let dataset = self.dataset_clauses("FROM")?;
+        let pattern = if self.is_word("WHERE") || self.is_punct("{") {
+            self.where_clause()?
+        } else {
+            GraphPattern::empty()
+        };
+        if star {
+            targets = visible_vars(&pattern, &self.variables)
+                .into_iter()
+                .map(TermPattern::Var)
+                .collect();
+        }
+        let modifiers = self.solution_modifiers()?;
+        let values = self.values_clause()?;
+        let aggregates = self.aggregates.take().unwrap_or_default();
+        let pattern = self.build_pattern(
+            pattern,
+            modifiers,
+            values,
+            aggregates,
+            vec![],
+            None,
+            SelectModifier::All,
+        );
+        Ok((QueryForm::Describe(targets), dataset, pattern))
+    }
+
+    fn ask_query(&mut self) -> Result<(QueryForm, Option<QueryDataset>, GraphPattern)> {
+        self.expect_word("ASK")?;
+        self.aggregates = Some(vec![]);
+        let dataset = self.dataset_clauses("FROM")?;
+        let pattern = self.where_clause()?;
+        let modifiers = self.solution_modifiers()?;
+        let values = self.values_clause()?;
+        let aggregates = self.aggregates.take().unwrap_or_default();
+        let pattern = self.build_pattern(
+            pattern,
+            modifiers,
+            values,
+            aggregates,
+            vec![],
+            None,
+            SelectModifier::All,
+        );
+        Ok((QueryForm::Ask, dataset, pattern))
+    }
+
+    fn select_clause(&mut self) -> Result<SelectClause> {
+        self.expect_word("SELECT")?;
+        let modifier = if self.eat_word("DISTINCT") {
+            SelectModifier::Distinct
+        } else if self.eat_word("REDUCED") {
+            SelectModifier::Reduced
+        } else {
+            SelectModifier::All
+        };
+        if self.eat_punct("*") {
+            return Ok(SelectClause {
+                modifier,
+                items: None,
+            });
+        }
+        let mut items = vec![];
+        loop {
+            match self.peek() {
+                Token::Var(_) => items.push((None, self.expect_var()?)),
+                Token::Punct("(") => {
+                    self.advance();
+                    let e = self.expression()?;
+                    self.expect_word("AS")?;
+                    let v = self.expect_var()?;
+                    self.expect_punct(")")?;
+                    if items.iter().any(|(_, w)| *w == v) {
+                        return self.err("variable projected twice");
+                    }
+                    items.push((Some(e), v));
+                }
+                _ => break,
+            }
+        }
+        if items.is_empty() {
+            return self.err("expected variables or '*' after SELECT");
+        }
+        Ok(SelectClause {
+            modifier,
+            items: Some(items),
+        })
+    }
+
+    /// Parse `FROM` clauses (or `USING` clauses in update requests).
+    fn dataset_clauses(&mut self, keyword: &str) -> Result<Option<QueryDataset>> {
+        let mut dataset: Option<QueryDataset> = None;
+        while self.eat_word(keyword) {
+            let named = self.eat_word("NAMED");
+            let iri = self.expect_iri()?;
+            let dataset = dataset.get_or_insert_with(QueryDataset::default);
+            if named {
+                dataset.named.push(iri);
+            } else {
+                dataset.default.push(iri);
+            }
+        }
+        Ok(dataset)
+    }
+
+    fn where_clause(&mut self) -> Result<GraphPattern> {
+        self.eat_word("WHERE");
+        self.group_graph_pattern()
+    }
+
+    fn solution_modifiers(&mut self) -> Result<Modifiers> {
+        let mut m = Modifiers::default();
+        if self.eat_word("GROUP") {
+            self.expect_word("BY")?;
+            let saved = self.aggregates.take();
+            let mut keys = vec![];
+            loop {
+                match self.peek() {
+                    Token::Var(_) => keys.push((None, self.expect_var()?)),
+                    Token::Punct("(") => {
+                        self.advance();
+                        let e = self.expression()?;
+                        let v = if self.eat_word("AS") {
+                            self.expect_var()?
+                        } else {
+                            self.fresh_var("group")
+                        };
+                        self.expect_punct(")")?;
+                        keys.push((Some(e), v));
+                    }
+                    _ if self.starts_call() => {
+                        let e = self.primary()?;
+                        keys.push((Some(e), self.fresh_var("group")));
+                    }
+                    _ => break,
+                }
+            }
+            if keys.is_empty() {
+                return self.err("expected group condition");
+            }
+            self.aggregates = saved;
+            m.group = Some(keys);
+        }
+        if self.eat_word("HAVING") {
+            while self.is_punct("(") || self.starts_call() {
+                m.having.push(self.constraint()?);
+            }
+            if m.having.is_empty() {
+                return self.err("expected having condition");
+            }
+        }
+        if self.eat_word("ORDER") {
+            self.expect_word("BY")?;
+            loop {
+                let (expression, ascending) = if self.eat_word("ASC") {
+                    (self.bracketted_expression()?, true)
+                } else if self.eat_word("DESC") {
+                    (self.bracketted_expression()?, false)
+                } else if let Token::Var(_) = self.peek() {
+                    (Expression::Var(self.expect_var()?), true)
+                } else if self.is_punct("(") || self.starts_call() {
+                    (self.constraint()?, true)
+                } else {
+                    break;
+                };
+                m.order.push(OrderCondition {
+                    expression,
+                    ascending,
+                });
+            }
+            if m.order.is_empty() {
+                return self.err("expected order condition");
+            }
+        }
+        loop {
+            if self.eat_word("LIMIT") {
+                m.limit = Some(self.expect_usize()?);
+            } else if self.eat_word("OFFSET") {
+                m.offset = self.expect_usize()?;
+            } else {
+                break;
+            }
+        }
+        Ok(m)
+    }
+
+    fn expect_usize(&mut self) -> Result<usize> {
+        match self.peek().clone() {
+            Token::Integer(n) => match n.parse() {
+                Ok(n) => {
+                    self.advance();
+                    Ok(n)
+                }
+                Err(_) => self.err("integer too large"),
+            },
+            _ => self.err("expected integer"),
+        }
+    }
+
+    fn values_clause(&mut self) -> Result<Option<GraphPattern>> {
+        if self.eat_word("VALUES") {
+            Ok(Some(self.data_block()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Build the pattern of a SELECT query (or sub-query),
+    /// and return it together with its projection.
+    fn build_select(
+        &mut self,
+        pattern: GraphPattern,
+        clause: SelectClause,
+        modifiers: Modifiers,
+        values: Option<GraphPattern>,
+        aggregates: Vec<(usize, Aggregate)>,
+    ) -> Result<(GraphPattern, Vec<usize>)> {
+        let (projection, extends) = match clause.items {
+            None => {
+                if modifiers.group.is_some() {
+                    return self.err("SELECT * is not allowed with GROUP BY");
+                }
+                (visible_vars(&pattern, &self.variables), vec![])
+            }
+            Some(items) => {
+                if modifiers.group.is_some() || !aggregates.is_empty() {
+                    self.check_grouped_projection(&items, &modifiers, &aggregates)?;
+                }
+                let projection = items.iter().map(|(_, v)| *v).collect();
+                let extends = items
+                    .into_iter()
+                    .filter_map(|(e, v)| e.map(|e| (e, v)))
+                    .collect();
+                (projection, extends)
+            }
+        };
+        let pattern = self.build_pattern(
+            pattern,
+            modifiers,
+            values,
+            aggregates,
+            extends,
+            Some(projection.clone()),
+            clause.modifier,
+        );
+        Ok((pattern, projection))
+    }
+
+    /// Check that, in a grouped query,
+    /// the projection only uses group keys, aggregates or previously projected expressions,
+    /// as required by [SPARQL 1.1 §19.8](https://www.w3.org/TR/sparql11-query/#sparqlGrammar).
+    fn check_grouped_projection(
+        &self,
+        items: &[(Option<Expression>, usize)],
+        modifiers: &Modifiers,
+        aggregates: &[(usize, Aggregate)],
+    ) -> Result<()> {
+        let mut allowed: Vec<usize> = modifiers
+            .group
+            .iter()
+            .flatten()
+            .map(|(_, v)| *v)
+            .chain(aggregates.iter().map(|(v, _)| *v))
+            .collect();
+        for (e, v) in items {
+            let mut ungrouped = None;
+            match e {
+                None => {
+                    if !allowed.contains(v) {
+                        ungrouped = Some(*v);
+                    }
+                }
+                Some(e) => e.for_each_var(&mut |w| {
+                    if !allowed.contains(&w) {
+                        ungrouped.get_or_insert(w);
+                    }
+                }),
+            }
+            if let Some(w) = ungrouped {
+                let msg = format!("variable ?{} is not grouped", self.variables[w]);
+                return self.err(msg);
+            }
+            allowed.push(*v);
+        }
+        Ok(())
+    }
+
+    /// Apply the solution modifiers to the given pattern,
+    /// as described in [SPARQL 1.1 §18.2.4](https://www.w3.org/TR/sparql11-query/#convertSolMod).
+    #[allow(clippy::too_many_arguments)]
+    fn build_pattern(
+        &mut self,
+        mut p: GraphPattern,
+        m: Modifiers,
+        values: Option<GraphPattern>,
+        aggregates: Vec<(usize, Aggregate)>,
+        extends: Vec<(Expression, usize)>,
+        projection: Option<Vec<usize>>,
+        modifier: SelectModifier,
+    ) -> GraphPattern {
+        if let Some(values) = values {
+            p = join(p, values);
+        }
+        if m.group.is_some() || !aggregates.is_empty() {
+            let mut keys = vec![];
+            for (e, v) in m.group.unwrap_or_default() {
+                if let Some(e) = e {
+                    p = GraphPattern::Extend(Box::new(p), v, e);
+                }
+                keys.push(v);
+            }
+            p = GraphPattern::Group(Box::new(p), keys, aggregates);
+        }
+        if let Some(f) = conj(m.having) {
+            p = GraphPattern::Filter(f, Box::new(p));
+        }
+        for (e, v) in extends {
+            p = GraphPattern::Extend(Box::new(p), v, e);
+        }
+        if !m.order.is_empty() {
+            p = GraphPattern::OrderBy(Box::new(p), m.order);
+        }
+        if let Some(projection) = projection {
+            p = GraphPattern::Project(Box::new(p), projection);
+        }
+        match modifier {
+            SelectModifier::All => (),
+            SelectModifier::Distinct => p = GraphPattern::Distinct(Box::new(p)),
+            SelectModifier::Reduced => p = GraphPattern::Reduced(Box::new(p)),
+        }
+        if m.offset > 0 || m.limit.is_some() {
+            p = GraphPattern::Slice(Box::new(p), m.offset, m.limit);
+        }
+        p
+    }
+
+    //
+    // Graph patterns
+    //
+
+    fn group_graph_pattern(&mut self) -> Result<GraphPattern> {
+        self.expect_punct("{")?;
+        let saved = self.aggregates.take();
+        let p = if self.is_word("SELECT") {
+            self.sub_select()?
+        } else {
+            self.group_graph_pattern_sub()?
+        };
+        self.aggregates = saved;
+        self.expect_punct("}")?;
+        Ok(p)
+    }
+
+    fn sub_select(&mut self) -> Result<GraphPattern> {
+        self.aggregates = Some(vec![]);
+        let clause = self.select_clause()?;
+        let pattern = self.where_clause()?;
+        let modifiers = self.solution_modifiers()?;
+        let values = self.values_clause()?;
+        let aggregates = self.aggregates.take().unwrap_or_default();
+        let (pattern, _) = self.build_select(pattern, clause, modifiers, values, aggregates)?;
+        Ok(pattern)
+    }
+
+    /// Translate a group graph pattern,
+    /// as described in [SPARQL 1.1 §18.2.2.6](https://www.w3.org/TR/sparql11-query/#convertGraphPattern).
+    fn group_graph_pattern_sub(&mut self) -> Result<GraphPattern> {
+        let mut g = GraphPattern::empty();
+        let mut filters = vec![];
+        loop {
+            if self.starts_triple() {
+                let block = self.triples_block()?;
+                g = join(g, block);
+                continue;
+            }
+            if self.is_punct("}") {
+                break;
+            }
+            if self.eat_word("FILTER") {
+                filters.push(self.constraint()?);
+            } else if self.eat_word("OPTIONAL") {
+                g = match self.group_graph_pattern()? {
+                    GraphPattern::Filter(f, a) => GraphPattern::LeftJoin(Box::new(g), a, Some(f)),
+                    a => GraphPattern::LeftJoin(Box::new(g), Box::new(a), None),
+                };
+            } else if self.eat_word("MINUS") {
+                let a = self.group_graph_pattern()?;
+                g = GraphPattern::Minus(Box::new(g), Box::new(a));
+            } else if self.eat_word("GRAPH") {
+                let name = self.var_or_iri()?;
+                let a = self.group_graph_pattern()?;
+                g = join(g, GraphPattern::Graph(name, Box::new(a)));
+            } else if self.is_word("SERVICE") {
+                return self.err("SERVICE is not supported");
+            } else if self.eat_word("BIND") {
+                self.expect_punct("(")?;
+                let e = self.expression()?;
+                self.expect_word("AS")?;
+                let v = self.expect_var()?;
+                self.expect_punct(")")?;
+                let mut in_scope = false;
+                g.for_each_in_scope_var(&mut |w| in_scope |= w == v);
+                if in_scope {
+                    return self.err("BIND to a variable that is already in scope");
+                }
+                g = GraphPattern::Extend(Box::new(g), v, e);
+            } else if self.eat_word("VALUES") {
+                let a = self.data_block()?;
+                g = join(g, a);
+            } else if self.is_punct("{") {
+                let mut a = self.group_graph_pattern()?;
+                while self.eat_word("UNION") {
+                    let b = self.group_graph_pattern()?;
+                    a = GraphPattern::Union(Box::new(a), Box::new(b));
+                }
+                g = join(g, a);
+            } else {
+                return self.err("unexpected token in group graph pattern");
+            }
+            self.eat_punct(".");
+        }
+        if let Some(f) = conj(filters) {
+            g = GraphPattern::Filter(f, Box::new(g));
+        }
+        Ok(g)
+    }
+
+    fn data_block(&mut self) -> Result<GraphPattern> {
+        let mut vars = vec![];
+        let mut rows = vec![];
+        if let Token::Var(_) = self.peek() {
+            vars.push(self.expect_var()?);
+            self.expect_punct("{")?;
+            while !self.eat_punct("}") {
+                rows.push(vec![self.data_value()?]);
+            }
+        } else {
+            self.expect_punct("(")?;
+            while let Token::Var(_) = self.peek() {
+                vars.push(self.expect_var()?);
+            }
+            self.expect_punct(")")?;
+            self.expect_punct("{")?;
+            while !self.eat_punct("}") {
+                self.expect_punct("(")?;
+                let mut row = vec![];
+                while !self.eat_punct(")") {
+                    row.push(self.data_value()?);
+                }
+                if row.len() != vars.len() {
+                    return self.err("wrong number of values in VALUES row");
+                }
+                rows.push(row);
+            }
+        }
+        Ok(GraphPattern::Values(vars, rows))
+    }
+
+    fn data_value(&mut self) -> Result<Option<ArcTerm>> {
+        if self.eat_word("UNDEF") {
+            Ok(None)
+        } else if self.is_punct("<<(") {
+            match self.triple_term_pattern()? {
+                TermPattern::Const(t) => Ok(Some(t)),
+                _ => self.err("variables are not allowed in VALUES"),
+            }
+        } else {
+            Ok(Some(self.constant_term()?))
+        }
+    }
+
+    //
+    // Update
+    //
+
+    pub(crate) fn update(mut self) -> Result<Update> {
+        let mut operations = vec![];
+        loop {
+            self.prologue()?;
+            if self.peek() == &Token::Eof {
+                break;
+            }
+            operations.push(self.update_operation()?);
+            if !self.eat_punct(";") {
+                break;
+            }
+        }
+        if self.peek() != &Token::Eof {
+            return self.err("unexpected token after the end of the update request");
+        }
+        for op in &mut operations {
+            if let UpdateOperation::DeleteInsert { query, .. } = op {
+                query.variables = self.variables.clone();
+            }
+        }
+        Ok(Update {
+            operations,
+            variables: self.variables,
+            base: self.base,
+        })
+    }
+
+    fn update_operation(&mut self) -> Result<UpdateOperation> {
+        use UpdateOperation::*;
+        let data = matches!(self.peek_nth(1), Token::Word(w) if w.eq_ignore_ascii_case("DATA"));
+        let where_ = matches!(self.peek_nth(1), Token::Word(w) if w.eq_ignore_ascii_case("WHERE"));
+        if self.is_word("INSERT") && data {
+            self.advance();
+            self.advance();
+            Ok(InsertData(self.quads(false, true)?))
+        } else if self.is_word("DELETE") && data {
+            self.advance();
+            self.advance();
+            Ok(DeleteData(self.quads(false, false)?))
+        } else if self.is_word("DELETE") && where_ {
+            self.advance();
+            self.advance();
+            let delete = self.quads(true, false)?;
+            let query = self.update_query(quads_pattern(&delete));
+            Ok(DeleteInsert {
+                delete,
+                insert: vec![],
+                with: None,
+                query: Box::new(query),
+            })
+        } else if self.is_word("WITH") || self.is_word("DELETE") || self.is_word("INSERT") {
+            self.modify()
+        } else if self.eat_word("CLEAR") {
+            let silent = self.eat_word("SILENT");
+            Ok(Clear(self.graph_ref_all()?, silent))
+        } else if self.eat_word("DROP") {
+            let silent = self.eat_word("SILENT");
+            Ok(Drop(self.graph_ref_all()?, silent))
+        } else if self.eat_word("CREATE") {
+            let silent = self.eat_word("SILENT");
+            self.expect_word("GRAPH")?;
+            Ok(Create(self.expect_iri()?, silent))
+        } else if self.is_word("ADD") || self.is_word("MOVE") || self.is_word("COPY") {
+            let Token::Word(kw) = self.advance() else {
+                unreachable!()
+            };
+            let silent = self.eat_word("SILENT");
+            let from = self.graph_or_default()?;
+            self.expect_word("TO")?;
+            let to = self.graph_or_default()?;
+            Ok(match kw.to_ascii_uppercase().as_str() {
+                "ADD" => Add(from, to, silent),
+                "MOVE" => Move(from, to, silent),
+                _ => Copy(from, to, silent),
+            })
+        } else if self.is_word("LOAD") {
+            self.err("LOAD is not supported")
+        } else {
+            self.err("expected update operation")
+        }
+    }
+
+    /// Parse a `DELETE/INSERT` operation, with an optional `WITH` clause.
+    fn modify(&mut self) -> Result<UpdateOperation> {
+        let with = if self.eat_word("WITH") {
+            Some(self.expect_iri()?)
+        } else {
+            None
+        };
+        let mut delete = vec![];
+        let mut insert = vec![];
+        let has_delete = self.eat_word("DELETE");
+        if has_delete {
+            delete = self.quads(true, false)?;
+        }
+        if self.eat_word("INSERT") {
+            insert = self.quads(true, true)?;
+        } else if !has_delete {
+            return self.err("expected DELETE or INSERT");
+        }
+        let using = self.dataset_clauses("USING")?;
+        self.expect_word("WHERE")?;
+        let pattern = self.group_graph_pattern()?;
+        let mut query = self.update_query(pattern);
+        query.dataset = using;
+        Ok(UpdateOperation::DeleteInsert {
+            delete,
+            insert,
+            with,
+            query: Box::new(query),
+        })
+    }
+
+    /// Wrap the `WHERE` clause of an update operation into a [`Query`].
+    ///
+    /// Its variable table is filled once the whole request has been parsed.
+    fn update_query(&self, pattern: GraphPattern) -> Query {
+        Query {
+            form: QueryForm::Select(vec![]),
+            dataset: None,
+            pattern,
+            variables: vec![],
+            base: self.base.clone(),
+        }
+    }
+
+    /// Parse a quad template (`QuadPattern` or `QuadData` in the SPARQL grammar).
+    ///
+    /// Blank nodes are parsed as constants.
+    fn quads(&mut self, allow_vars: bool, allow_bnodes: bool) -> Result<Vec<QuadPattern>> {
+        self.expect_punct("{")?;
+        self.bnodes_as_vars = false;
+        let mut quads = vec![];
+        loop {
+            let graph = if self.eat_word("GRAPH") {
+                let g = self.var_or_iri()?;
+                self.expect_punct("{")?;
+                Some(g)
+            } else {
+                None
+            };
+            for triple in self.triples_template()? {
+                let quad = QuadPattern {
+                    triple,
+                    graph: graph.clone(),
+                };
+                let mut vars = false;
+                let mut bnodes = false;
+                quad.triple
+                    .iter()
+                    .chain(quad.graph.as_ref())
+                    .for_each(|tp| check_term_pattern(tp, &mut vars, &mut bnodes));
+                if vars && !allow_vars {
+                    return self.err("variables are not allowed in quad data");
+                }
+                if bnodes && !allow_bnodes {
+                    return self.err("blank nodes are not allowed in DELETE clauses");
+                }
+                quads.push(quad);
+            }
+            if graph.is_some() {
+                self.expect_punct("}")?;
+                self.eat_punct(".");
+            } else if !self.is_word("GRAPH") {
+                break;
+            }
+        }
+        self.bnodes_as_vars = true;
+        self.expect_punct("}")?;
+        Ok(quads)
+    }
+
+    fn graph_ref_all(&mut self) -> Result<GraphTarget> {
+        if self.eat_word("DEFAULT") {
+            Ok(GraphTarget::Default)
+        } else if self.eat_word("NAMED") {
+            Ok(GraphTarget::AllNamed)
+        } else if self.eat_word("ALL") {
+            Ok(GraphTarget::All)
+        } else {
+            self.expect_word("GRAPH")?;
+            Ok(GraphTarget::Named(self.expect_iri()?))
+        }
+    }
+
+    fn graph_or_default(&mut self) -> Result<Option<ArcTerm>> {
+        if self.eat_word("DEFAULT") {
+            Ok(None)
+        } else {
+            self.eat_word("GRAPH");
+            Ok(Some(self.expect_iri()?))
+        }
+    }
+
+    //
+    // Triples
+    //
+
+    fn starts_triple(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::Var(_)
+                | Token::Iri(_)
+                | Token::PName(..)
+                | Token::BNode(_)
+                | Token::String(_)
+                | Token::Integer(_)
+                | Token::Decimal(_)
+                | Token::Double(_)
+                | Token::Punct("(")
+                | Token::Punct("[")
+                | Token::Punct("<<" | "<<(")
+        )
+    }
+
+    fn starts_verb(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::Var(_) | Token::Iri(_) | Token::PName(..) | Token::Punct("^" | "(" | "!")
+        ) || matches!(self.peek(), Token::Word(w) if w == "a")
+    }
+
+    fn triples_block(&mut self) -> Result<GraphPattern> {
+        let mut acc = Triples::default();
+        loop {
+            self.triples_same_subject(&mut acc, true)?;
+            if !self.eat_punct(".") || !self.starts_triple() {
+                break;
+            }
+        }
+        Ok(acc.into_pattern())
+    }
+
+    fn triples_template(&mut self) -> Result<Vec<[TermPattern; 3]>> {
+        let mut acc = Triples::default();
+        while self.starts_triple() {
+            self.triples_same_subject(&mut acc, false)?;
+            if !self.eat_punct(".") {
+                break;
+            }
+        }
+        Ok(acc.triples)
+    }
+
+    fn triples_same_subject(&mut self, acc: &mut Triples, allow_paths: bool) -> Result<()> {
+        let anon = self.is_punct("[") && matches!(self.peek_nth(1), Token::Punct("]"));
+        let nil = self.is_punct("(") && matches!(self.peek_nth(1), Token::Punct(")"));
+        if !anon && !nil && (self.is_punct("[") || self.is_punct("(") || self.is_punct("<<")) {
+            let s = self.triples_node(acc, allow_paths)?;
+            if self.starts_verb() {
+                self.property_list(&s, acc, allow_paths)?;
+            }
+            Ok(())
+        } else {
+            let s = self.var_or_term()?;
+            self.property_list(&s, acc, allow_paths)
+        }
+    }
+
+    fn property_list(
+        &mut self,
+        s: &TermPattern,
+        acc: &mut Triples,
+        allow_paths: bool,
+    ) -> Result<()> {
+        loop {
+            let verb = self.verb(allow_paths)?;
+            self.object_list(s, &verb, acc, allow_paths)?;
+            let mut semicolon = false;
+            while self.eat_punct(";") {
+                semicolon = true;
+            }
+            if !semicolon || !self.starts_verb() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn verb(&mut self, allow_paths: bool) -> Result<Verb> {
+        if !allow_paths || matches!(self.peek(), Token::Var(_)) {
+            return Ok(Verb::Simple(self.simple_verb()?));
+        }
+        Ok(match self.path()? {
+            PropertyPath::Predicate(p) => Verb::Simple(TermPattern::Const(p)),
+            path => Verb::Path(path),
+        })
+    }
+
+    /// Parse a variable, an IRI or `a`.
+    fn simple_verb(&mut self) -> Result<TermPattern> {
+        if let Token::Var(_) = self.peek() {
+            Ok(TermPattern::Var(self.expect_var()?))
+        } else {
+            Ok(TermPattern::Const(self.iri_or_a()?))
+        }
+    }
+
+    fn object_list(
+        &mut self,
+        s: &TermPattern,
+        verb: &Verb,
+        acc: &mut Triples,
+        allow_paths: bool,
+    ) -> Result<()> {
+        loop {
+            let o = self.graph_node(acc, allow_paths)?;
+            match verb {
+                Verb::Simple(p) => {
+                    acc.triples.push([s.clone(), p.clone(), o.clone()]);
+                    self.annotation(s, p, o, acc, allow_paths)?;
+                }
+                Verb::Path(path) => {
+                    if self.is_punct("{|") || self.is_punct("~") {
+                        return self.err("annotations are not allowed on property paths");
+                    }
+                    self.add_path(s.clone(), path.clone(), o, acc);
+                }
+            }
+            if !self.eat_punct(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse the reifiers and annotation blocks following the object of triple `s p o`.
+    ///
+    /// Each reifier `r` adds the triple pattern `r rdf:reifies <<( s p o )>>`;
+    /// an annotation block describes the preceding reifier,
+    /// or a fresh blank node if it is not preceded by a reifier.
+    fn annotation(
+        &mut self,
+        s: &TermPattern,
+        p: &TermPattern,
+        o: TermPattern,
+        acc: &mut Triples,
+        allow_paths: bool,
+    ) -> Result<()> {
+        let tt = triple_term(s.clone(), p.clone(), o);
+        let mut reifier = None;
+        loop {
+            if self.eat_punct("~") {
+                let r = self.reifier()?;
+                acc.triples.push([r.clone(), reifies(), tt.clone()]);
+                reifier = Some(r);
+            } else if self.eat_punct("{|") {
+                let r = match reifier.take() {
+                    Some(r) => r,
+                    None => {
+                        let r = self.fresh_bnode();
+                        acc.triples.push([r.clone(), reifies(), tt.clone()]);
+                        r
+                    }
+                };
+                self.property_list(&r, acc, allow_paths)?;
+                self.expect_punct("|}")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse the optional identifier following `~` (a fresh blank node if omitted).
+    fn reifier(&mut self) -> Result<TermPattern> {
+        match self.peek() {
+            Token::Var(_)
+            | Token::Iri(_)
+            | Token::PName(..)
+            | Token::BNode(_)
+            | Token::Punct("[") => self.var_or_term(),
+            _ => Ok(self.fresh_bnode()),
+        }
+    }
+
+    /// Add a path pattern, decomposing it into triple patterns when possible
+    /// (as described in [SPARQL 1.1 §18.2.2.4](https://www.w3.org/TR/sparql11-query/#sparqlTranslatePathPatterns)).
+    fn add_path(&mut self, s: TermPattern, path: PropertyPath, o: TermPattern, acc: &mut Triples) {
+        match path {
+            PropertyPath::Predicate(p) => acc.triples.push([s, TermPattern::Const(p), o]),
+            PropertyPath::Inverse(inner) if matches!(*inner, PropertyPath::Predicate(_)) => {
+                self.add_path(o, *inner, s, acc)
+            }
+            PropertyPath::Sequence(p1, p2) => {
+                let mid = TermPattern::Var(self.fresh_var("path"));
+                self.add_path(s, *p1, mid.clone(), acc);
+                self.add_path(mid, *p2, o, acc);
+            }
+            path => acc.paths.push((s, path, o)),
+        }
+    }
+
+    fn graph_node(&mut self, acc: &mut Triples, allow_paths: bool) -> Result<TermPattern> {
+        let anon = self.is_punct("[") && matches!(self.peek_nth(1), Token::Punct("]"));
+        let nil = self.is_punct("(") && matches!(self.peek_nth(1), Token::Punct(")"));
+        if !anon && !nil && (self.is_punct("[") || self.is_punct("(") || self.is_punct("<<")) {
+            self.triples_node(acc, allow_paths)
+        } else {
+            self.var_or_term()
+        }
+    }
+
+    /// Parse a blank node property list, a collection or a reified triple
+    fn triples_node(&mut self, acc: &mut Triples, allow_paths: bool) -> Result<TermPattern> {
+        if self.is_punct("<<") {
+            self.reified_triple(acc)
+        } else if self.eat_punct("[") {
+            let node = self.fresh_bnode();
+            self.property_list(&node, acc, allow_paths)?;
+            self.expect_punct("]")?;
+            Ok(node)
+        } else {
+            self.expect_punct("(")?;
+            let mut items = vec![];
+            while !self.eat_punct(")") {
+                items.push(self.graph_node(acc, allow_paths)?);
+            }
+            let mut list = TermPattern::Const(iri_term(RDF_NIL));
+            for item in items.into_iter().rev() {
+                let node = self.fresh_bnode();
+                acc.triples
+                    .push([node.clone(), TermPattern::Const(iri_term(RDF_FIRST)), item]);
+                acc.triples
+                    .push([node.clone(), TermPattern::Const(iri_term(RDF_REST)), list]);
+                list = node;
+            }
+            Ok(list)
+        }
+    }
+
+    fn var_or_term(&mut self) -> Result<TermPattern> {
+        match self.peek().clone() {
+            Token::Var(_) => Ok(TermPattern::Var(self.expect_var()?)),
+            Token::BNode(label) => {
+                let ret = self.bnode(&label)?;
+                self.advance();
+                Ok(ret)
+            }
+            Token::Punct("[") => {
+                self.advance();
+                self.expect_punct("]")?;
+                Ok(self.fresh_bnode())
+            }
+            Token::Punct("(") => {
+                self.advance();
+                self.expect_punct(")")?;
+                Ok(TermPattern::Const(iri_term(RDF_NIL)))
+            }
+            Token::Punct("<<(") => self.triple_term_pattern(),
+            _ => Ok(TermPattern::Const(self.constant_term()?)),
+        }
+    }
+
+    /// Parse a triple term (`<<( s p o )>>`).
+    fn triple_term_pattern(&mut self) -> Result<TermPattern> {
+        self.expect_punct("<<(")?;
+        let s = self.var_or_term()?;
+        if matches!(&s, TermPattern::Const(t) if t.is_literal() || t.is_triple()) {
+            return self.err("the subject of a triple term must be an IRI or a blank node");
+        }
+        let p = self.simple_verb()?;
+        let o = self.var_or_term()?;
+        self.expect_punct(")>>")?;
+        Ok(triple_term(s, p, o))
+    }
+
+    /// Parse a reified triple (`<< s p o ~ r >>`),
+    /// adding the triple pattern `r rdf:reifies <<( s p o )>>` to `acc`,
+    /// and return its reifier `r` (a fresh blank node if omitted).
+    fn reified_triple(&mut self, acc: &mut Triples) -> Result<TermPattern> {
+        self.expect_punct("<<")?;
+        let s = self.reified_triple_node(acc)?;
+        if matches!(&s, TermPattern::Const(t) if t.is_literal() || t.is_triple()) {
+            return self.err("the subject of a reified triple must be an IRI or a blank node");
+        }
+        let p = self.simple_verb()?;
+        let o = self.reified_triple_node(acc)?;
+        let r = if self.eat_punct("~") {
+            self.reifier()?
+        } else {
+            self.fresh_bnode()
+        };
+        self.expect_punct(">>")?;
+        acc.triples
+            .push([r.clone(), reifies(), triple_term(s, p, o)]);
+        Ok(r)
+    }
+
+    fn reified_triple_node(&mut self, acc: &mut Triples) -> Result<TermPattern> {
+        if self.is_punct("<<") {
+            self.reified_triple(acc)
+        } else {
+            self.var_or_term()
+        }
+    }
+
+    //
+    // Property paths
+    //
+
+    fn path(&mut self) -> Result<PropertyPath> {
+        let mut p = self.path_sequence()?;
+        while self.eat_punct("|") {
+            let q = self.path_sequence()?;
+            p = PropertyPath::Alternative(Box::new(p), Box::new(q));
+        }
+        Ok(p)
+    }
+
+    fn path_sequence(&mut self) -> Result<PropertyPath> {
+        let mut p = self.path_elt_or_inverse()?;
+        while self.eat_punct("/") {
+            let q = self.path_elt_or_inverse()?;
+            p = PropertyPath::Sequence(Box::new(p), Box::new(q));
+        }
+        Ok(p)
+    }
+
+    fn path_elt_or_inverse(&mut self) -> Result<PropertyPath> {
+        if self.eat_punct("^") {
+            Ok(PropertyPath::Inverse(Box::new(self.path_elt()?)))
+        } else {
+            self.path_elt()
+        }
+    }
+
+    fn path_elt(&mut self) -> Result<PropertyPath> {
+        let p = self.path_primary()?;
+        Ok(if self.eat_punct("?") {
+            PropertyPath::ZeroOrOne(Box::new(p))
+        } else if self.eat_punct("*") {
+            PropertyPath::ZeroOrMore(Box::new(p))
+        } else if self.eat_punct("+") {
+            PropertyPath::OneOrMore(Box::new(p))
+        } else {
+            p
+        })
+    }
+
+    fn path_primary(&mut self) -> Result<PropertyPath> {
+        if self.eat_punct("!") {
+            let mut fwd = vec![];
+            let mut inv = vec![];
+            if self.eat_punct("(") {
+                if !self.eat_punct(")") {
+                    loop {
+                        self.path_one_in_set(&mut fwd, &mut inv)?;
+                        if self.eat_punct(")") {
+                            break;
+                        }
+                        self.expect_punct("|")?;
+                    }
+                }
+            } else {
+                self.path_one_in_set(&mut fwd, &mut inv)?;
+            }
+            Ok(PropertyPath::NegatedSet(fwd, inv))
+        } else if self.eat_punct("(") {
+            let p = self.path()?;
+            self.expect_punct(")")?;
+            Ok(p)
+        } else {
+            Ok(PropertyPath::Predicate(self.iri_or_a()?))
+        }
+    }
+
+    fn path_one_in_set(&mut self, fwd: &mut Vec<ArcTerm>, inv: &mut Vec<ArcTerm>) -> Result<()> {
+        if self.eat_punct("^") {
+            inv.push(self.iri_or_a()?);
+        } else {
+            fwd.push(self.iri_or_a()?);
+        }
+        Ok(())
+    }
+
+    //
+    // Expressions
+    //
+
+    /// Whether the next token starts a built-in call or a function call.
+    fn starts_call(&self) -> bool {
+        match self.peek() {
+            Token::Iri(_) | Token::PName(..) => true,
+            Token::Word(w) => {
+                let upper = w.to_ascii_uppercase();
+                Function::from_name(&upper).is_some()
+                    || matches!(
+                        &upper[..],
+                        "BOUND"
+                            | "IF"
+                            | "COALESCE"
+                            | "EXISTS"
+                            | "NOT"
+                            | "COUNT"
+                            | "SUM"
+                            | "MIN"
+                            | "MAX"
+                            | "AVG"
+                            | "SAMPLE"
+                            | "GROUP_CONCAT"
+                    )
+            }
+            _ => false,
+        }
+    }
+
+    fn constraint(&mut self) -> Result<Expression> {
+        if self.is_punct("(") {
+            self.bracketted_expression()
+        } else if self.starts_call() {
+            self.primary()
+        } else {
+            self.err("expected constraint")
+        }
+    }
+
+    fn bracketted_expression(&mut self) -> Result<Expression> {
+        self.expect_punct("(")?;
+        let e = self.expression()?;
+        self.expect_punct(")")?;
+        Ok(e)
+    }
+
+    pub(crate) fn expression(&mut self) -> Result<Expression> {
+        let mut e = self.and_expression()?;
+        while self.eat_punct("||") {
+            let f = self.and_expression()?;
+            e = Expression::Or(Box::new(e), Box::new(f));
+        }
+        Ok(e)
+    }
+
+    fn and_expression(&mut self) -> Result<Expression> {
+        let mut e = self.relational_expression()?;
+        while self.eat_punct("&&") {
+            let f = self.relational_expression()?;
+            e = Expression::And(Box::new(e), Box::new(f));
+        }
+        Ok(e)
+    }
+
+    fn relational_expression(&mut self) -> Result<Expression> {
+        let left = self.additive_expression()?;
+        let op = match self.peek() {
+            Token::Punct("=") => Some(CompareOp::Eq),
+            Token::Punct("!=") => Some(CompareOp::Ne),
+            Token::Punct("<") => Some(CompareOp::Lt),
+            Token::Punct("<=") => Some(CompareOp::Le),
+            Token::Punct(">") => Some(CompareOp::Gt),
+            Token::Punct(">=") => Some(CompareOp::Ge),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.advance();
+            let right = self.additive_expression()?;
+            Ok(Expression::Compare(op, Box::new(left), Box::new(right)))
+        } else if self.eat_word("IN") {
+            let list = self.expression_list()?;
+            Ok(Expression::In(Box::new(left), list, true))
+        } else if self.is_word("NOT")
+            && matches!(self.peek_nth(1), Token::Word(w) if w.eq_ignore_ascii_case("IN"))
+        {
+            self.advance();
+            self.advance();
+            let list = self.expression_list()?;
+            Ok(Expression::In(Box::new(left), list, false))
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn additive_expression(&mut self) -> Result<Expression> {
+        let mut e = self.multiplicative_expression()?;
+        loop {
+            let op = if self.eat_punct("+") {
+                ArithOp::Add
+            } else if self.eat_punct("-") {
+                ArithOp::Sub
+            } else {
+                return Ok(e);
+            };
+            let f = self.multiplicative_expression()?;
+            e = Expression::Arith(op, Box::new(e), Box::new(f));
+        }
+    }
+
+    fn multiplicative_expression(&mut self) -> Result<Expression> {
+        let mut e = self.unary_expression()?;
+        loop {
+            let op = if self.eat_punct("*") {
+                ArithOp::Mul
+            } else if self.eat_punct("/") {
+                ArithOp::Div
+            } else {
+                return Ok(e);
+            };
+            let f = self.unary_expression()?;
+            e = Expression::Arith(op, Box::new(e), Box::new(f));
+        }
+    }
+
+    fn unary_expression(&mut self) -> Result<Expression> {
+        if self.eat_punct("!") {
+            Ok(Expression::Not(Box::new(self.primary()?)))
+        } else if self.eat_punct("+") {
+            Ok(Expression::Plus(Box::new(self.primary()?)))
+        } else if self.eat_punct("-") {
+            Ok(Expression::Neg(Box::new(self.primary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression> {
+        match self.peek().clone() {
+            Token::Punct("(") => self.bracketted_expression(),
+            Token::Var(_) => Ok(Expression::Var(self.expect_var()?)),
+            Token::Iri(_) | Token::PName(..) => {
+                let iri = self.expect_iri()?;
+                if self.is_punct("(") {
+                    let args = self.arg_list()?;
+                    Ok(Expression::Custom(iri, args))
+                } else {
+                    Ok(Expression::Const(iri))
+                }
+            }
+            Token::Punct("<<(") => self.triple_term_expression(),
+            Token::Word(w) if w == "true" || w == "false" => {
+                Ok(Expression::Const(self.constant_term()?))
+            }
+            Token::Word(w) => self.builtin_call(&w),
+            Token::String(_) | Token::Integer(_) | Token::Decimal(_) | Token::Double(_) => {
+                Ok(Expression::Const(self.constant_term()?))
+            }
+            _ => self.err("expected expression"),
+        }
+    }
+
+    /// Parse a triple term (`<<( s p o )>>`) in an expression.
+    fn triple_term_expression(&mut self) -> Result<Expression> {
+        self.expect_punct("<<(")?;
+        let mut args = vec![];
+        for i in 0..3 {
+            args.push(match self.peek() {
+                Token::Var(_) => Expression::Var(self.expect_var()?),
+                Token::Punct("<<(") if i == 2 => self.triple_term_expression()?,
+                _ if i == 0 => Expression::Const(self.expect_iri()?),
+                _ if i == 1 => Expression::Const(self.iri_or_a()?),
+                _ => Expression::Const(self.constant_term()?),
+            });
+        }
+        self.expect_punct(")>>")?;
+        Ok(Expression::Call(Function::Triple, args))
+    }
+
+    fn arg_list(&mut self) -> Result<Vec<Expression>> {
+        self.expect_punct("(")?;
+        let mut args = vec![];
+        if self.eat_punct(")") {
+            return Ok(args);
+        }
+        self.eat_word("DISTINCT");
+        loop {
+            args.push(self.expression()?);
+            if self.eat_punct(")") {
+                return Ok(args);
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    fn expression_list(&mut self) -> Result<Vec<Expression>> {
+        self.expect_punct("(")?;
+        let mut args = vec![];
+        if self.eat_punct(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.eat_punct(")") {
+                return Ok(args);
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    fn builtin_call(&mut self, name: &str) -> Result<Expression> {
+        let upper = name.to_ascii_uppercase();
+        match &upper[..] {
+            "BOUND" => {
+                self.advance();
+                self.expect_punct("(")?;
+                let v = self.expect_var()?;
+                self.expect_punct(")")?;
+                Ok(Expression::Bound(v))
+            }
+            "IF" => {
+                self.advance();
+                let mut args = self.expression_list()?;
+                if args.len() != 3 {
+                    return self.err("IF expects 3 arguments");
+                }
+                let e3 = args.pop().unwrap();
+                let e2 = args.pop().unwrap();
+                let e1 = args.pop().unwrap();
+                Ok(Expression::If(Box::new(e1), Box::new(e2), Box::new(e3)))
+            }
+            "COALESCE" => {
+                self.advance();
+                Ok(Expression::Coalesce(self.expression_list()?))
+            }
+            "EXISTS" => {
+                self.advance();
+                let p = self.group_graph_pattern()?;
+                Ok(Expression::Exists(Box::new(p), true))
+            }
+            "NOT" => {
+                self.advance();
+                self.expect_word("EXISTS")?;
+                let p = self.group_graph_pattern()?;
+                Ok(Expression::Exists(Box::new(p), false))
+            }
+            "COUNT" | "SUM" | "MIN" | "MAX" | "AVG" | "SAMPLE" | "GROUP_CONCAT" => {
+                self.aggregate(&upper)
+            }
+            _ => match Function::from_name(&upper) {
+                Some((function, min, max)) => {
+                    self.advance();
+                    let args = self.expression_list()?;
+                    if args.len() < min || args.len() > max {
+                        return self.err(format!("wrong number of arguments for {upper}"));
+                    }
+                    Ok(Expression::Call(function, args))
+                }
+                None => self.err(format!("unknown function {name}")),
+            },
+        }
+    }
+
+    fn aggregate(&mut self, name: &str) -> Result<Expression> {
+        let Some(mut aggregates) = self.aggregates.take() else {
+            return self.err("aggregates are not allowed here");
+        };
+        self.advance();
+        self.expect_punct("(")?;
+        let distinct = self.eat_word("DISTINCT");
+        let expression = if name == "COUNT" && self.eat_punct("*") {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        let function = match name {
+            "COUNT" => AggregateFunction::Count,
+            "SUM" => AggregateFunction::Sum,
+            "MIN" => AggregateFunction::Min,
+            "MAX" => AggregateFunction::Max,
+            "AVG" => AggregateFunction::Avg,
+            "SAMPLE" => AggregateFunction::Sample,
+            _ => {
+                let mut separator = " ".to_string();
+                if self.eat_punct(";") {
+                    self.expect_word("SEPARATOR")?;
+                    self.expect_punct("=")?;
+                    match self.advance() {
+                        Token::String(sep) => separator = sep,
+                        _ => return self.err("expected separator string"),
+                    }
+                }
+                AggregateFunction::GroupConcat(separator)
+            }
+        };
+        self.expect_punct(")")?;
+        let v = self.fresh_var("agg");
+        aggregates.push((
+            v,
+            Aggregate {
+                function,
+                expression,
+                distinct,
+            },
+        ));
+        self.aggregates = Some(aggregates);
+        Ok(Expression::Var(v))
+    }
+}
+
+/// Join two patterns, merging basic graph patterns.
+pub(crate) fn join(g: GraphPattern, a: GraphPattern) -> GraphPattern {
+    match (g, a) {
+        (GraphPattern::Bgp(mut t1), GraphPattern::Bgp(t2)) => {
+            t1.extend(t2);
+            GraphPattern::Bgp(t1)
+        }
+        (GraphPattern::Bgp(t), a) if t.is_empty() => a,
+        (g, GraphPattern::Bgp(t)) if t.is_empty() => g,
+        (g, a) => GraphPattern::Join(Box::new(g), Box::new(a)),
+    }
+}
+
+/// Build the conjunction of the given expressions, if any.
+fn conj(exprs: Vec<Expression>) -> Option<Expression> {
+    exprs
+        .into_iter()
+        .reduce(|e1, e2| Expression::And(Box::new(e1), Box::new(e2)))
+}
+
+/// Build a triple term pattern.
+fn triple_term(s: TermPattern, p: TermPattern, o: TermPattern) -> TermPattern {
+    match (s, p, o) {
+        (TermPattern::Const(s), TermPattern::Const(p), TermPattern::Const(o)) => {
+            TermPattern::Const(ArcTerm::Triple(Arc::new([s, p, o])))
+        }
+        (s, p, o) => TermPattern::Triple(Box::new([s, p, o])),
+    }
+}
+
+fn reifies() -> TermPattern {
+    TermPattern::Const(iri_term(RDF_REIFIES))
+}
+
+/// Record whether `tp` contains variables and/or blank nodes.
+fn check_term_pattern(tp: &TermPattern, vars: &mut bool, bnodes: &mut bool) {
+    match tp {
+        TermPattern::Const(t) => *bnodes |= t.atoms().any(|a| a.is_blank_node()),
+        TermPattern::Var(_) => *vars = true,
+        TermPattern::Triple(spo) => spo
+            .iter()
+            .for_each(|tp| check_term_pattern(tp, vars, bnodes)),
+    }
+}
+
+/// Convert the quad template of a `DELETE WHERE` operation into a graph pattern.
+fn quads_pattern(quads: &[QuadPattern]) -> GraphPattern {
+    let mut default = vec![];
+    let mut p = GraphPattern::empty();
+    for quad in quads {
+        match &quad.graph {
+            None => default.push(quad.triple.clone()),
+            Some(g) => {
+                let bgp = GraphPattern::Bgp(vec![quad.triple.clone()]);
+                p = join(p, GraphPattern::Graph(g.clone(), Box::new(bgp)));
+            }
+        }
+    }
+    join(GraphPattern::Bgp(default), p)
+}
+
+/// The in-scope variables of `pattern` that are visible (i.e. not introduced by the parser),
+/// in order of appearance.
+pub(crate) fn visible_vars(pattern: &GraphPattern, variables: &[Arc<str>]) -> Vec<usize> {
+    let mut ret = vec![];
+    pattern.for_each_in_scope_var(&mut |v| {
+        if !ret.contains(&v) && !variables[v].starts_with("_:") {
+            ret.push(v);
+        }
+    });
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("SELECT * { ?s ?p ?o }")]
+    #[test_case(
+        "PREFIX ex: <http://example.org/> SELECT ?s WHERE { ?s a ex:C ; ex:p 1, -2.5, \"x\"@en }"
+    )]
+    #[test_case("BASE <http://example.org/> SELECT ?s { ?s <p>/<q>* [ <r> () ] }")]
+    #[test_case("SELECT (COUNT(*) AS ?c) { ?s ?p ?o } GROUP BY ?p HAVING (COUNT(?o) > 1) ORDER BY DESC(?c) LIMIT 5")]
+    #[test_case("SELECT ?x { { ?x ?p ?o } UNION { ?o ?p ?x } OPTIONAL { ?x ?q ?y FILTER(?y != 3) } MINUS { ?x a ?t } }")]
+    #[test_case("SELECT ?x { BIND(1+2*3 AS ?x) VALUES (?y ?z) { (1 UNDEF) (<tag:x> \"a\") } }")]
+    #[test_case("ASK { GRAPH ?g { ?s ?p ?o FILTER NOT EXISTS { ?o ?p ?s } } }")]
+    #[test_case("CONSTRUCT { ?s ?p [ ?q ?o ] } WHERE { ?s ?p ?o }")]
+    #[test_case("CONSTRUCT WHERE { ?s ?p ?o }")]
+    #[test_case("DESCRIBE <tag:x> ?y { ?y ?p ?o }")]
+    #[test_case(
+        "SELECT ?s { << ?s ?p ?o >> ?q ?r . ?s ?p ?o {| ?q ?r |} BIND(<<( ?s ?p ?o )>> AS ?t) }"
+    )]
+    #[test_case("SELECT ?s { << << ?s ?p ?o ~ ?r >> ?q <<( ?s ?p ?o )>> ~ >> . ?s ?p ?o ~ [] ~ _:r {| ?q ?r |} }")]
+    #[test_case("CONSTRUCT { ?s ?p ?o ~ ?r {| <tag:src> <tag:x> |} } WHERE { ?s ?p ?o ~ ?r }")]
+    #[test_case("SELECT ?x { SELECT (MAX(?y) AS ?x) { ?s ?p ?y } }")]
+    fn positive(query: &str) {
+        let res = parse_query(query);
+        assert!(res.is_ok(), "{:?}", res);
+    }
+
+    #[test_case("SELECT { ?s ?p ?o }")]
+    #[test_case("SELECT * { ?s ?p ?o ")]
+    #[test_case("SELECT * { ?s ex:p ?o }")]
+    #[test_case("SELECT * { ?s ?p ?o FILTER(COUNT(?o) > 1) }")]
+    #[test_case("SELECT * { ?s ?p ?o BIND(1 AS ?o) }")]
+    #[test_case("SELECT * { ?s ?p ?o } garbage")]
+    fn negative(query: &str) {
+        let res = parse_query(query);
+        assert!(res.is_err(), "{:?}", res);
+    }
+
+    #[test_case("")]
+    #[test_case("INSERT DATA { <tag:s> <tag:p> \"o\" . GRAPH <tag:g> { <tag:s> <tag:p> _:b } }")]
+    #[test_case(
+        "PREFIX : <tag:> DELETE { ?s :p ?o } INSERT { ?s :q [ :r ?o ] } WHERE { ?s :p ?o }"
+    )]
+    #[test_case("WITH <tag:g> DELETE { ?s ?p ?o } USING <tag:h> WHERE { ?s ?p ?o } ; CLEAR ALL")]
+    #[test_case("DELETE WHERE { ?s ?p ?o GRAPH ?g { ?s ?p ?o } }; DROP SILENT GRAPH <tag:g>; CREATE GRAPH <tag:h>")]
+    #[test_case(
+        "ADD DEFAULT TO <tag:g> ; MOVE GRAPH <tag:g> TO DEFAULT ; COPY <tag:g> TO <tag:h> ;"
+    )]
+    fn positive_update(update: &str) {
+        let res = parse_update(update);
+        assert!(res.is_ok(), "{:?}", res);
+    }
+
+    #[test_case("INSERT DATA { ?s <tag:p> <tag:o> }")]
+    #[test_case("DELETE DATA { _:b <tag:p> <tag:o> }")]
+    #[test_case("DELETE { ?s ?p [] } WHERE { ?s ?p ?o }")]
+    #[test_case("INSERT { ?s ?p ?o }")]
+    #[test_case("CLEAR <tag:g>")]
+    #[test_case("LOAD <tag:x>")]
+    #[test_case("INSERT DATA { <tag:s> <tag:p> <tag:o> } DROP ALL")]
+    fn negative_update(update: &str) {
+        let res = parse_update(update);
+        assert!(res.is_err(), "{:?}", res);
+    }
+
+    #[test]
+    fn error_position() {
+        let err = parse_query("SELECT *\nWHERE {\n  ?s ex:p ?o }").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 6);
+    }
+
+    #[test]
+    fn select_star_hides_bnodes() {
+        let q = parse_query("SELECT * { ?s ?p _:b . _:b ?q [] }").unwrap();
+        let QueryForm::Select(projection) = q.form else {
+            panic!()
+        };
+        let names: Vec<_> = projection.iter().map(|i| &q.variables[*i][..]).collect();
+        assert_eq!(names, vec!["s", "p", "q"]);
+    }
+}
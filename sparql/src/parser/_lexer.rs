@@ -0,0 +1,434 @@
+//! A tokenizer for SPARQL.
+use super::SparqlParseError;
+
+/// A SPARQL token.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Token {
+    /// An IRI reference, without the angle brackets, with escapes resolved
+    Iri(String),
+    /// A prefixed name (prefix without colon, local part with escapes resolved)
+    PName(String, String),
+    /// A blank node label (without `_:`)
+    BNode(String),
+    /// A variable name (without `?` or `$`)
+    Var(String),
+    /// A string literal, with escapes resolved
+    String(String),
+    /// A language tag (without `@`)
+    LangTag(String),
+    /// An integer literal
+    Integer(String),
+    /// A decimal literal
+    Decimal(String),
+    /// A double literal
+    Double(String),
+    /// A keyword or function name (case is preserved)
+    Word(String),
+    /// A punctuation or operator
+    Punct(&'static str),
+    /// End of input
+    Eof,
+}
+
+const PUNCTS: &[&str] = &[
+    "<<(", ")>>", "^^", "<<", ">>", "{|", "|}", "||", "&&", "!=", "<=", ">=", "{", "}", "(", ")",
+    "[", "]", ".", ",", ";", "*", "+", "-", "/", "!", "^", "|", "=", "<", ">", "?", "~",
+];
+
+/// Split `src` into a list of tokens, each associated with its byte offset.
+pub(crate) fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, SparqlParseError> {
+    Lexer { src, pos: 0 }.run()
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn run(mut self) -> Result<Vec<(Token, usize)>, SparqlParseError> {
+        let mut tokens = vec![];
+        loop {
+            self.skip_ws_and_comments();
+            let start = self.pos;
+            let Some(c) = self.peek() else {
+                tokens.push((Token::Eof, start));
+                return Ok(tokens);
+            };
+            let tok = match c {
+                '<' => self.iri_or_punct()?,
+                '"' | '\'' => self.string(c)?,
+                '?' | '$' => self.var(c),
+                '@' => self.langtag()?,
+                '_' if self.rest().starts_with("_:") => self.bnode()?,
+                '0'..='9' => self.number(),
+                '.' if self.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) => self.number(),
+                c if c == ':' || is_pn_chars_base(c) => self.name()?,
+                _ => self.punct()?,
+            };
+            tokens.push((tok, start));
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn err<T>(&self, msg: impl Into<String>) -> Result<T, SparqlParseError> {
+        Err(SparqlParseError::new(self.src, self.pos, msg))
+    }
+
+    fn skip_ws_and_comments(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '#' {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn iri_or_punct(&mut self) -> Result<Token, SparqlParseError> {
+        let rest = self.rest();
+        if !rest.starts_with("<<") && !rest.starts_with("<=") {
+            // try to read an IRIREF
+            let mut iri = String::new();
+            let mut chars = rest[1..].char_indices();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '>' => {
+                        self.pos += i + 2;
+                        return Ok(Token::Iri(iri));
+                    }
+                    '\\' => {
+                        let Some((_, u)) = chars.next() else { break };
+                        let len = match u {
+                            'u' => 4,
+                            'U' => 8,
+                            _ => break,
+                        };
+                        let hex: String = chars.by_ref().take(len).map(|(_, c)| c).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(c) if hex.len() == len => iri.push(c),
+                            _ => break,
+                        }
+                    }
+                    '<' | '"' | '{' | '}' | '|' | '^' | '`' => break,
+                    c if c <= ' ' => break,
+                    c => iri.push(c),
+                }
+            }
+        }
+        self.punct()
+    }
+
+    fn string(&mut self, quote: char) -> Result<Token, SparqlParseError> {
+        let long = quote.to_string().repeat(3);
+        let is_long = self.rest().starts_with(&long);
+        self.pos += if is_long { 3 } else { 1 };
+        let mut txt = String::new();
+        loop {
+            if is_long && self.rest().starts_with(&long) {
+                self.pos += 3;
+                return Ok(Token::String(txt));
+            }
+            match self.bump() {
+                None => return self.err("unterminated string"),
+                Some(c) if c == quote && !is_long => return Ok(Token::String(txt)),
+                Some('\n') | Some('\r') if !is_long => return self.err("newline in short string"),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('f') => '\u{c}',
+                        Some('"') => '"',
+                        Some('\'') => '\'',
+                        Some('\\') => '\\',
+                        Some('u') => self.hex_escape(4)?,
+                        Some('U') => self.hex_escape(8)?,
+                        _ => return self.err("invalid escape sequence in string"),
+                    };
+                    txt.push(c);
+                }
+                Some(c) => txt.push(c),
+            }
+        }
+    }
+
+    fn hex_escape(&mut self, len: usize) -> Result<char, SparqlParseError> {
+        let rest = self.rest();
+        match rest
+            .get(..len)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32)
+        {
+            Some(c) => {
+                self.pos += len;
+                Ok(c)
+            }
+            None => self.err("invalid unicode escape"),
+        }
+    }
+
+    fn var(&mut self, sigil: char) -> Token {
+        self.bump();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if is_pn_chars_u(c)
+                || c.is_ascii_digit()
+                || (self.pos > start
+                    && matches!(c, '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}'))
+            {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        if self.pos == start {
+            if sigil == '?' {
+                Token::Punct("?")
+            } else {
+                Token::Word("$".into())
+            }
+        } else {
+            Token::Var(self.src[start..self.pos].to_string())
+        }
+    }
+
+    fn langtag(&mut self) -> Result<Token, SparqlParseError> {
+        self.bump();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let tag = &self.src[start..self.pos];
+        if tag.is_empty() || !tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return self.err("invalid language tag");
+        }
+        Ok(Token::LangTag(tag.to_string()))
+    }
+
+    fn bnode(&mut self) -> Result<Token, SparqlParseError> {
+        self.pos += 2;
+        let label = self.pn_local()?;
+        if label.is_empty() {
+            return self.err("empty blank node label");
+        }
+        Ok(Token::BNode(label))
+    }
+
+    fn number(&mut self) -> Token {
+        let start = self.pos;
+        let mut kind = 0; // 0: integer, 1: decimal, 2: double
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.bump();
+        }
+        if self.peek() == Some('.') && matches!(self.peek_nth(1), Some('0'..='9')) {
+            kind = 1;
+            self.bump();
+            while matches!(self.peek(), Some('0'..='9')) {
+                self.bump();
+            }
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let sign = matches!(self.peek_nth(1), Some('+' | '-'));
+            let digit_at = if sign { 2 } else { 1 };
+            if matches!(self.peek_nth(digit_at), Some('0'..='9')) {
+                kind = 2;
+                self.pos += digit_at;
+                while matches!(self.peek(), Some('0'..='9')) {
+                    self.bump();
+                }
+            }
+        }
+        let txt = self.src[start..self.pos].to_string();
+        match kind {
+            0 => Token::Integer(txt),
+            1 => Token::Decimal(txt),
+            _ => Token::Double(txt),
+        }
+    }
+
+    /// Read a keyword or a prefixed name
+    fn name(&mut self) -> Result<Token, SparqlParseError> {
+        let start = self.pos;
+        // read PN_PREFIX
+        while let Some(c) = self.peek() {
+            if is_pn_chars(c) || c == '.' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        // a prefix can not end with a '.'
+        while self.src[start..self.pos].ends_with('.') {
+            self.pos -= 1;
+        }
+        let prefix = self.src[start..self.pos].to_string();
+        if self.peek() == Some(':') {
+            self.bump();
+            let local = self.pn_local()?;
+            Ok(Token::PName(prefix, local))
+        } else {
+            Ok(Token::Word(prefix))
+        }
+    }
+
+    /// Read a PN_LOCAL, resolving escapes
+    fn pn_local(&mut self) -> Result<String, SparqlParseError> {
+        let mut local = String::new();
+        let mut last_was_dot = 0;
+        loop {
+            match self.peek() {
+                Some(c) if is_pn_chars(c) || c == ':' || (c == '.' && !local.is_empty()) => {
+                    if c == '.' {
+                        last_was_dot += 1;
+                    } else {
+                        last_was_dot = 0;
+                    }
+                    local.push(c);
+                    self.bump();
+                }
+                Some('%') => {
+                    let hex = self.rest().get(1..3);
+                    match hex {
+                        Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                            local.push('%');
+                            local.push_str(hex);
+                            self.pos += 3;
+                            last_was_dot = 0;
+                        }
+                        _ => return self.err("invalid percent encoding in local name"),
+                    }
+                }
+                Some('\\') => match self.peek_nth(1) {
+                    Some(c) if "_~.-!$&'()*+,;=/?#@%".contains(c) => {
+                        local.push(c);
+                        self.pos += 2;
+                        last_was_dot = 0;
+                    }
+                    _ => return self.err("invalid escape in local name"),
+                },
+                _ => break,
+            }
+        }
+        // a local name can not end with a '.'
+        for _ in 0..last_was_dot {
+            local.pop();
+            self.pos -= 1;
+        }
+        Ok(local)
+    }
+
+    fn punct(&mut self) -> Result<Token, SparqlParseError> {
+        let rest = self.rest();
+        for p in PUNCTS {
+            if rest.starts_with(p) {
+                self.pos += p.len();
+                return Ok(Token::Punct(p));
+            }
+        }
+        self.err(format!("unexpected character {:?}", self.peek().unwrap()))
+    }
+}
+
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn is_pn_chars_u(c: char) -> bool {
+    c == '_' || is_pn_chars_base(c)
+}
+
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || matches!(c, '-' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let got: Vec<_> = tokenize("SELECT ?x { ?x a ex:Foo, <tag:x>. FILTER(?x<3.5) } # comment")
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        use Token::*;
+        assert_eq!(
+            got,
+            vec![
+                Word("SELECT".into()),
+                Var("x".into()),
+                Punct("{"),
+                Var("x".into()),
+                Word("a".into()),
+                PName("ex".into(), "Foo".into()),
+                Punct(","),
+                Iri("tag:x".into()),
+                Punct("."),
+                Word("FILTER".into()),
+                Punct("("),
+                Var("x".into()),
+                Punct("<"),
+                Decimal("3.5".into()),
+                Punct(")"),
+                Punct("}"),
+                Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn pname_ending_with_dot() {
+        let got: Vec<_> = tokenize("ex:a.b. 1.")
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        use Token::*;
+        assert_eq!(
+            got,
+            vec![
+                PName("ex".into(), "a.b".into()),
+                Punct("."),
+                Integer("1".into()),
+                Punct("."),
+                Eof
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! I implement [`ResultsSerializer`] and [`ResultsParser`]
+//! for the standard formats of SPARQL query results:
+//!
+//! * [`json`][]: [`application/sparql-results+json`](https://www.w3.org/TR/sparql11-results-json/),
+//! * [`xml`][]: [`application/sparql-results+xml`](https://www.w3.org/TR/rdf-sparql-XMLres/),
+//! * [`csv`][]: [`text/csv`](https://www.w3.org/TR/sparql11-results-csv-tsv/),
+//! * [`tsv`][]: [`text/tab-separated-values`](https://www.w3.org/TR/sparql11-results-csv-tsv/).
+//!
+//! Triple terms are encoded in JSON and XML as defined by the
+//! [SPARQL-star](https://w3c.github.io/rdf-star/cg-spec/editors_draft.html#query-result-formats)
+//! extensions of these formats, and in TSV with the `<< s p o >>` syntax.
+//!
+//! Note that CSV is a lossy format:
+//! see [`csv::CsvResultsParser`] for how it interprets the values it reads.
+//!
+//! [`ResultsSerializer`]: sophia_api::sparql::results::ResultsSerializer
+//! [`ResultsParser`]: sophia_api::sparql::results::ResultsParser
+use std::io;
+use std::sync::Arc;
+
+use sophia_api::term::{BnodeId, IriRef, LanguageTag};
+use sophia_term::{ArcTerm, GenericLiteral};
+use thiserror::Error;
+
+use crate::value::XSD_STRING;
+
+pub mod csv;
+pub mod json;
+pub mod tsv;
+pub mod xml;
+
+/// The error type raised by the results parsers of this module.
+#[derive(Debug, Error)]
+pub enum ResultsError {
+    /// The underlying reader raised an error
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data is not well-formed JSON
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The data is not well-formed XML
+    #[error("XML error: {0}")]
+    Xml(#[from] quick_xml::Error),
+    /// The data does not follow the structure of the results format
+    #[error("invalid query results: {0}")]
+    Syntax(String),
+}
+
+pub(crate) type ResultsResult<T> = Result<T, ResultsError>;
+
+/// Build a [`ResultsError::Syntax`] error.
+pub(crate) fn syntax_error<T>(msg: impl Into<String>) -> ResultsResult<T> {
+    Err(ResultsError::Syntax(msg.into()))
+}
+
+/// Build an IRI term, checking that `iri` is valid.
+pub(crate) fn make_iri(iri: &str) -> ResultsResult<ArcTerm> {
+    match IriRef::new(Arc::from(iri)) {
+        Ok(iri) => Ok(ArcTerm::Iri(iri)),
+        Err(_) => syntax_error(format!("invalid IRI <{iri}>")),
+    }
+}
+
+/// Build a blank node, checking that `label` is valid.
+pub(crate) fn make_bnode(label: &str) -> ResultsResult<ArcTerm> {
+    match BnodeId::new(Arc::from(label)) {
+        Ok(id) => Ok(ArcTerm::BlankNode(id)),
+        Err(_) => syntax_error(format!("invalid blank node label _:{label}")),
+    }
+}
+
+/// Build a literal, checking that its datatype or language tag is valid.
+pub(crate) fn make_literal(
+    lex: &str,
+    datatype: Option<&str>,
+    lang: Option<&str>,
+) -> ResultsResult<ArcTerm> {
+    let literal = match (lang, datatype) {
+        (Some(tag), _) => match LanguageTag::new(Arc::from(tag)) {
+            Ok(tag) => GenericLiteral::LanguageString(lex.into(), tag),
+            Err(_) => return syntax_error(format!("invalid language tag @{tag}")),
+        },
+        (None, Some(dt)) => match IriRef::new(Arc::from(dt)) {
+            Ok(dt) => GenericLiteral::Typed(lex.into(), dt),
+            Err(_) => return syntax_error(format!("invalid datatype <{dt}>")),
+        },
+        (None, None) => GenericLiteral::Typed(lex.into(), XSD_STRING.clone()),
+    };
+    Ok(ArcTerm::Literal(literal))
+}
+
+/// Build a triple term, checking that its subject and predicate are acceptable.
+pub(crate) fn make_triple(s: ArcTerm, p: ArcTerm, o: ArcTerm) -> ResultsResult<ArcTerm> {
+    if matches!(s, ArcTerm::Literal(_)) || !matches!(p, ArcTerm::Iri(_)) {
+        return syntax_error("invalid triple term");
+    }
+    Ok(ArcTerm::Triple(Arc::new([s, p, o])))
+}
+
+/// Build the error raised by serializers for a variable term, which can not appear in results.
+pub(crate) fn variable_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "variables can not be serialized as query results",
+    )
+}
+
+#[cfg(test)]
+mod test;
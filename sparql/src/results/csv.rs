@@ -0,0 +1,225 @@
+//! Serializer and parser for the
+//! [SPARQL 1.1 Query Results CSV Format](https://www.w3.org/TR/sparql11-results-csv-tsv/)
+//! (`text/csv`).
+//!
+//! This format is lossy: literals are reduced to their lexical form,
+//! so datatypes and language tags are lost.
+//! Triple terms, which are not covered by the specification,
+//! are encoded as in the [TSV format](super::tsv).
+use std::io;
+
+use sophia_api::serializer::Stringifier;
+use sophia_api::source::{SinkError, SourceError, StreamResult};
+use sophia_api::sparql::results::{QueryResults, ResultsParser, ResultsSerializer};
+use sophia_api::term::{Term, TermKind};
+use sophia_iri::Iri;
+use sophia_term::ArcTerm;
+
+use super::*;
+
+/// SPARQL results serializer for the CSV format.
+///
+/// NB: CSV can not represent the result of ASK queries,
+/// so [`serialize_boolean`](ResultsSerializer::serialize_boolean) always fails.
+pub struct CsvResultsSerializer<W> {
+    write: W,
+}
+
+impl<W> CsvResultsSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new CSV results serializer writing to `write`.
+    pub fn new(write: W) -> Self {
+        CsvResultsSerializer { write }
+    }
+}
+
+impl CsvResultsSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        CsvResultsSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for CsvResultsSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+impl<W> ResultsSerializer for CsvResultsSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_bindings<I, R, T, E>(
+        &mut self,
+        variables: &[&str],
+        rows: I,
+    ) -> StreamResult<&mut Self, E, Self::Error>
+    where
+        I: IntoIterator<Item = Result<R, E>>,
+        R: AsRef<[Option<T>]>,
+        T: Term,
+        E: 'static + std::error::Error,
+    {
+        let w = &mut self.write;
+        for (i, var) in variables.iter().enumerate() {
+            if i > 0 {
+                w.write_all(b",").map_err(SinkError)?;
+            }
+            write_field(w, var).map_err(SinkError)?;
+        }
+        w.write_all(b"\r\n").map_err(SinkError)?;
+        for row in rows {
+            let row = row.map_err(SourceError)?;
+            for (i, value) in row.as_ref().iter().enumerate() {
+                if i > 0 {
+                    w.write_all(b",").map_err(SinkError)?;
+                }
+                if let Some(value) = value {
+                    write_term(w, value.borrow_term()).map_err(SinkError)?;
+                }
+            }
+            w.write_all(b"\r\n").map_err(SinkError)?;
+        }
+        Ok(self)
+    }
+
+    fn serialize_boolean(&mut self, _: bool) -> Result<&mut Self, Self::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "CSV can not represent boolean results",
+        ))
+    }
+}
+
+/// Write the given term into the given write, as a CSV field.
+pub fn write_term<W, T>(w: &mut W, t: T) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    match t.kind() {
+        TermKind::Iri => write_field(w, t.iri().unwrap().as_str()),
+        TermKind::BlankNode => write_field(w, &format!("_:{}", t.bnode_id().unwrap().as_str())),
+        TermKind::Literal => write_field(w, &t.lexical_form().unwrap()),
+        TermKind::Triple => {
+            let mut buf = vec![];
+            tsv::write_term(&mut buf, t)?;
+            write_field(w, &String::from_utf8_lossy(&buf))
+        }
+        TermKind::Variable => Err(variable_error()),
+    }
+}
+
+fn write_field<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    if txt.contains(['"', ',', '\r', '\n']) {
+        write!(w, "\"{}\"", txt.replace('"', "\"\""))
+    } else {
+        w.write_all(txt.as_bytes())
+    }
+}
+
+/// SPARQL results parser for the CSV format.
+///
+/// Since CSV is a lossy format, values are interpreted as follows:
+/// * empty values are considered unbound,
+/// * values starting with `_:` are parsed as blank nodes,
+/// * values starting with `<<` are parsed as triple terms (as in [TSV](super::tsv)),
+/// * values that are valid absolute IRIs are parsed as IRIs,
+/// * any other value is parsed as a simple literal.
+#[derive(Clone, Debug, Default)]
+pub struct CsvResultsParser {}
+
+impl<B: io::BufRead> ResultsParser<B> for CsvResultsParser {
+    type Term = ArcTerm;
+    type Error = ResultsError;
+
+    fn parse_results(&self, mut data: B) -> ResultsResult<QueryResults<ArcTerm>> {
+        let mut txt = String::new();
+        data.read_to_string(&mut txt)?;
+        let mut records = records(&txt)?.into_iter();
+        let variables = match records.next() {
+            Some(header) if header.len() == 1 && header[0].is_empty() => vec![],
+            Some(header) => header,
+            None => return syntax_error("missing header line"),
+        };
+        let rows = records
+            .map(|record| {
+                if variables.is_empty() {
+                    if record.len() == 1 && record[0].is_empty() {
+                        return Ok(vec![]);
+                    }
+                    return syntax_error("unexpected value");
+                }
+                if record.len() != variables.len() {
+                    return syntax_error(format!(
+                        "expected {} values, found {}",
+                        variables.len(),
+                        record.len()
+                    ));
+                }
+                record.iter().map(String::as_str).map(parse_field).collect()
+            })
+            .collect::<ResultsResult<Vec<_>>>()?;
+        Ok(QueryResults::Bindings { variables, rows })
+    }
+}
+
+fn parse_field(field: &str) -> ResultsResult<Option<ArcTerm>> {
+    if field.is_empty() {
+        Ok(None)
+    } else if let Some(label) = field.strip_prefix("_:") {
+        make_bnode(label).map(Some)
+    } else if field.starts_with("<<") {
+        tsv::parse_term(field).map(Some)
+    } else if Iri::new(field).is_ok() {
+        make_iri(field).map(Some)
+    } else {
+        make_literal(field, None, None).map(Some)
+    }
+}
+
+/// Split `txt` into records, and records into fields, un-quoting them as required.
+fn records(txt: &str) -> ResultsResult<Vec<Vec<String>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = txt.chars().peekable();
+    while let Some(chr) = chars.next() {
+        if quoted {
+            if chr != '"' {
+                field.push(chr);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                quoted = false;
+            }
+            continue;
+        }
+        match chr {
+            '"' => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => (),
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            chr => field.push(chr),
+        }
+    }
+    if quoted {
+        return syntax_error("unterminated quoted value");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
@@ -0,0 +1,264 @@
+//! Serializer and parser for the
+//! [SPARQL 1.1 Query Results JSON Format](https://www.w3.org/TR/sparql11-results-json/)
+//! (`application/sparql-results+json`).
+//!
+//! Triple terms are encoded as objects with type `triple`,
+//! whose value has the members `subject`, `predicate` and `object`.
+use std::io;
+
+use serde_json::{Map, Value};
+use sophia_api::ns::xsd;
+use sophia_api::serializer::Stringifier;
+use sophia_api::source::{SinkError, SourceError, StreamResult};
+use sophia_api::sparql::results::{QueryResults, ResultsParser, ResultsSerializer};
+use sophia_api::term::{Term, TermKind};
+use sophia_term::ArcTerm;
+
+use super::*;
+
+/// SPARQL results serializer for the JSON format.
+pub struct JsonResultsSerializer<W> {
+    write: W,
+}
+
+impl<W> JsonResultsSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new JSON results serializer writing to `write`.
+    pub fn new(write: W) -> Self {
+        JsonResultsSerializer { write }
+    }
+}
+
+impl JsonResultsSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        JsonResultsSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for JsonResultsSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+impl<W> ResultsSerializer for JsonResultsSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_bindings<I, R, T, E>(
+        &mut self,
+        variables: &[&str],
+        rows: I,
+    ) -> StreamResult<&mut Self, E, Self::Error>
+    where
+        I: IntoIterator<Item = Result<R, E>>,
+        R: AsRef<[Option<T>]>,
+        T: Term,
+        E: 'static + std::error::Error,
+    {
+        let w = &mut self.write;
+        write_head(w, variables).map_err(SinkError)?;
+        w.write_all(b",\n\"results\":{\"bindings\":[")
+            .map_err(SinkError)?;
+        for (i, row) in rows.into_iter().enumerate() {
+            let row = row.map_err(SourceError)?;
+            if i > 0 {
+                w.write_all(b",").map_err(SinkError)?;
+            }
+            write_row(w, variables, row.as_ref()).map_err(SinkError)?;
+        }
+        w.write_all(b"\n]}}\n").map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn serialize_boolean(&mut self, value: bool) -> Result<&mut Self, Self::Error> {
+        let value = if value { "true" } else { "false" };
+        writeln!(self.write, "{{\"head\":{{}},\n\"boolean\":{value}}}")?;
+        Ok(self)
+    }
+}
+
+fn write_head<W: io::Write>(w: &mut W, variables: &[&str]) -> io::Result<()> {
+    w.write_all(b"{\"head\":{\"vars\":[")?;
+    for (i, var) in variables.iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        write_string(w, var)?;
+    }
+    w.write_all(b"]}")
+}
+
+fn write_row<W, T>(w: &mut W, variables: &[&str], row: &[Option<T>]) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    w.write_all(b"\n{")?;
+    let mut first = true;
+    for (var, value) in variables.iter().zip(row) {
+        if let Some(value) = value {
+            if !first {
+                w.write_all(b",")?;
+            }
+            first = false;
+            write_string(w, var)?;
+            w.write_all(b":")?;
+            write_term(w, value.borrow_term())?;
+        }
+    }
+    w.write_all(b"}")
+}
+
+/// Write the given term into the given write, as a JSON object.
+pub fn write_term<W, T>(w: &mut W, t: T) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    match t.kind() {
+        TermKind::Iri => {
+            w.write_all(b"{\"type\":\"uri\",\"value\":")?;
+            write_string(w, t.iri().unwrap().as_str())?;
+        }
+        TermKind::BlankNode => {
+            w.write_all(b"{\"type\":\"bnode\",\"value\":")?;
+            write_string(w, t.bnode_id().unwrap().as_str())?;
+        }
+        TermKind::Literal => {
+            w.write_all(b"{\"type\":\"literal\",\"value\":")?;
+            write_string(w, &t.lexical_form().unwrap())?;
+            match t.language_tag() {
+                Some(tag) => {
+                    w.write_all(b",\"xml:lang\":")?;
+                    write_string(w, tag.as_str())?;
+                }
+                None => {
+                    let dt = t.datatype().unwrap();
+                    if xsd::string != dt {
+                        w.write_all(b",\"datatype\":")?;
+                        write_string(w, dt.as_str())?;
+                    }
+                }
+            }
+        }
+        TermKind::Triple => {
+            let [s, p, o] = t.triple().unwrap();
+            w.write_all(b"{\"type\":\"triple\",\"value\":{\"subject\":")?;
+            write_term(w, s)?;
+            w.write_all(b",\"predicate\":")?;
+            write_term(w, p)?;
+            w.write_all(b",\"object\":")?;
+            write_term(w, o)?;
+            w.write_all(b"}")?;
+        }
+        TermKind::Variable => return Err(variable_error()),
+    }
+    w.write_all(b"}")
+}
+
+fn write_string<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    serde_json::to_writer(w, txt).map_err(io::Error::from)
+}
+
+/// SPARQL results parser for the JSON format.
+#[derive(Clone, Debug, Default)]
+pub struct JsonResultsParser {}
+
+impl<B: io::BufRead> ResultsParser<B> for JsonResultsParser {
+    type Term = ArcTerm;
+    type Error = ResultsError;
+
+    fn parse_results(&self, data: B) -> ResultsResult<QueryResults<ArcTerm>> {
+        let document: Value = serde_json::from_reader(data)?;
+        let Some(document) = document.as_object() else {
+            return syntax_error("expected a JSON object");
+        };
+        if let Some(boolean) = document.get("boolean") {
+            return match boolean.as_bool() {
+                Some(value) => Ok(QueryResults::Boolean(value)),
+                None => syntax_error("\"boolean\" must be true or false"),
+            };
+        }
+        let Some(vars) = document
+            .get("head")
+            .and_then(|head| head.get("vars"))
+            .and_then(Value::as_array)
+        else {
+            return syntax_error("missing \"head\".\"vars\"");
+        };
+        let variables = vars
+            .iter()
+            .map(|var| match var.as_str() {
+                Some(var) => Ok(var.to_string()),
+                None => syntax_error("variable names must be strings"),
+            })
+            .collect::<ResultsResult<Vec<_>>>()?;
+        let Some(bindings) = document
+            .get("results")
+            .and_then(|results| results.get("bindings"))
+            .and_then(Value::as_array)
+        else {
+            return syntax_error("missing \"results\".\"bindings\"");
+        };
+        let rows = bindings
+            .iter()
+            .map(|binding| parse_row(binding, &variables))
+            .collect::<ResultsResult<Vec<_>>>()?;
+        Ok(QueryResults::Bindings { variables, rows })
+    }
+}
+
+fn parse_row(binding: &Value, variables: &[String]) -> ResultsResult<Vec<Option<ArcTerm>>> {
+    let Some(binding) = binding.as_object() else {
+        return syntax_error("bindings must be JSON objects");
+    };
+    let mut row = vec![None; variables.len()];
+    for (var, value) in binding {
+        let Some(i) = variables.iter().position(|v| v == var) else {
+            return syntax_error(format!("unknown variable {var}"));
+        };
+        row[i] = Some(parse_term(value)?);
+    }
+    Ok(row)
+}
+
+fn parse_term(value: &Value) -> ResultsResult<ArcTerm> {
+    let Some(object) = value.as_object() else {
+        return syntax_error("RDF terms must be JSON objects");
+    };
+    match object.get("type").and_then(Value::as_str) {
+        Some("uri") => make_iri(string_member(object, "value")?),
+        Some("bnode") => make_bnode(string_member(object, "value")?),
+        Some("literal") | Some("typed-literal") => make_literal(
+            string_member(object, "value")?,
+            object.get("datatype").and_then(Value::as_str),
+            object.get("xml:lang").and_then(Value::as_str),
+        ),
+        Some("triple") => {
+            let Some(triple) = object.get("value").and_then(Value::as_object) else {
+                return syntax_error("the value of a triple term must be a JSON object");
+            };
+            let [s, p, o] = ["subject", "predicate", "object"].map(|key| match triple.get(key) {
+                Some(term) => parse_term(term),
+                None => syntax_error(format!("missing \"{key}\" in triple term")),
+            });
+            make_triple(s?, p?, o?)
+        }
+        Some(other) => syntax_error(format!("unknown term type {other}")),
+        None => syntax_error("missing term type"),
+    }
+}
+
+fn string_member<'a>(object: &'a Map<String, Value>, key: &str) -> ResultsResult<&'a str> {
+    match object.get(key).and_then(Value::as_str) {
+        Some(value) => Ok(value),
+        None => syntax_error(format!("missing or invalid \"{key}\"")),
+    }
+}
@@ -0,0 +1,230 @@
+use std::error::Error;
+
+use sophia_api::serializer::Stringifier;
+use sophia_api::sparql::results::{QueryResults, ResultsParser, ResultsSerializer};
+use sophia_api::sparql::SparqlDataset;
+use sophia_api::term::LanguageTag;
+use sophia_term::ArcTerm;
+use test_case::test_case;
+
+use super::csv::*;
+use super::json::*;
+use super::tsv::*;
+use super::xml::*;
+use crate::value::*;
+use crate::SparqlWrapper;
+
+fn sample() -> QueryResults<ArcTerm> {
+    let alice = iri_term("http://example.org/alice");
+    let knows = iri_term("http://example.org/knows");
+    let b1 = bnode_term("b1");
+    let en = LanguageTag::new_unchecked("en".into());
+    QueryResults::Bindings {
+        variables: vec!["x".into(), "y".into()],
+        rows: vec![
+            vec![
+                Some(alice.clone()),
+                Some(string_literal("a \"quoted\",\ttabbed\nstring")),
+            ],
+            vec![Some(b1.clone()), Some(lang_literal("hello", &en))],
+            vec![None, Some(integer_literal(42))],
+            vec![Some(typed_literal("1.5", &XSD_DOUBLE)), None],
+            vec![
+                Some(ArcTerm::Triple([alice, knows, b1].into())),
+                Some(bool_literal(true)),
+            ],
+        ],
+    }
+}
+
+#[test_case(JsonResultsSerializer::new_stringifier(), JsonResultsParser::default(); "json")]
+#[test_case(XmlResultsSerializer::new_stringifier(), XmlResultsParser::default(); "xml")]
+#[test_case(TsvResultsSerializer::new_stringifier(), TsvResultsParser::default(); "tsv")]
+fn round_trip<S, P>(mut serializer: S, parser: P) -> Result<(), Box<dyn Error>>
+where
+    S: ResultsSerializer + Stringifier,
+    P: for<'a> ResultsParser<&'a [u8], Term = ArcTerm>,
+{
+    let expected = sample();
+    let txt = serializer.serialize_results(&expected)?.to_string();
+    let got = parser.parse_results_str(&txt)?;
+    assert_eq!(got, expected, "{txt}");
+    Ok(())
+}
+
+#[test_case(JsonResultsSerializer::new_stringifier(), JsonResultsParser::default(); "json")]
+#[test_case(XmlResultsSerializer::new_stringifier(), XmlResultsParser::default(); "xml")]
+fn round_trip_boolean<S, P>(mut serializer: S, parser: P) -> Result<(), Box<dyn Error>>
+where
+    S: ResultsSerializer + Stringifier,
+    P: for<'a> ResultsParser<&'a [u8], Term = ArcTerm>,
+{
+    let txt = serializer.serialize_boolean(true)?.to_string();
+    assert!(parser.parse_results_str(&txt)?.into_boolean());
+    Ok(())
+}
+
+#[test]
+fn xml_escapes_carriage_returns() -> Result<(), Box<dyn Error>> {
+    let expected = QueryResults::Bindings {
+        variables: vec!["x".into()],
+        rows: vec![vec![Some(string_literal("a\r\nb\rc"))]],
+    };
+    let txt = XmlResultsSerializer::new_stringifier()
+        .serialize_results(&expected)?
+        .to_string();
+    assert!(txt.contains("a&#13;\nb&#13;c"), "{txt}");
+    assert!(!txt.contains('\r'), "{txt}");
+    assert_eq!(
+        XmlResultsParser::default().parse_results_str(&txt)?,
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn tsv_writes_triple_terms() -> Result<(), Box<dyn Error>> {
+    let txt = TsvResultsSerializer::new_stringifier()
+        .serialize_results(&sample())?
+        .to_string();
+    assert!(
+        txt.contains("<<( <http://example.org/alice> <http://example.org/knows> _:b1 )>>"),
+        "{txt}"
+    );
+    // the older SPARQL-star syntax is still accepted by the parser
+    let old = txt.replace("<<(", "<<").replace(")>>", ">>");
+    assert_eq!(
+        TsvResultsParser::default().parse_results_str(&old)?,
+        sample()
+    );
+    Ok(())
+}
+
+#[test]
+fn csv_is_lossy() -> Result<(), Box<dyn Error>> {
+    let txt = CsvResultsSerializer::new_stringifier()
+        .serialize_results(&sample())?
+        .to_string();
+    assert!(txt
+        .starts_with("x,y\r\nhttp://example.org/alice,\"a \"\"quoted\"\",\ttabbed\nstring\"\r\n"));
+    let (variables, rows) = CsvResultsParser::default()
+        .parse_results_str(&txt)?
+        .into_bindings();
+    assert_eq!(variables, vec!["x", "y"]);
+    let (_, expected) = sample().into_bindings();
+    assert_eq!(rows.len(), expected.len());
+    assert_eq!(
+        rows[0][1],
+        Some(string_literal("a \"quoted\",\ttabbed\nstring"))
+    );
+    assert_eq!(rows[1][0], expected[1][0]);
+    assert_eq!(rows[1][1], Some(string_literal("hello")));
+    assert_eq!(rows[2][0], None);
+    assert_eq!(rows[2][1], Some(string_literal("42")));
+    assert_eq!(rows[4][0], expected[4][0]);
+    Ok(())
+}
+
+#[test]
+fn parse_json() -> Result<(), Box<dyn Error>> {
+    let txt = r#"{
+        "head": { "vars": ["s", "o"], "link": ["http://example.org/meta"] },
+        "results": { "bindings": [
+            { "s": { "type": "bnode", "value": "b1" },
+              "o": { "type": "triple", "value": {
+                "subject": { "type": "uri", "value": "http://example.org/alice" },
+                "predicate": { "type": "uri", "value": "http://example.org/age" },
+                "object": { "type": "literal", "value": "42",
+                            "datatype": "http://www.w3.org/2001/XMLSchema#integer" }
+              } }
+            },
+            { "o": { "type": "literal", "value": "chat", "xml:lang": "fr" } }
+        ] }
+    }"#;
+    let (variables, rows) = JsonResultsParser::default()
+        .parse_results_str(txt)?
+        .into_bindings();
+    assert_eq!(variables, vec!["s", "o"]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][0], Some(bnode_term("b1")));
+    let alice_age = [
+        iri_term("http://example.org/alice"),
+        iri_term("http://example.org/age"),
+        integer_literal(42),
+    ];
+    assert_eq!(rows[0][1], Some(ArcTerm::Triple(alice_age.into())));
+    assert_eq!(rows[1][0], None);
+    let fr = LanguageTag::new_unchecked("fr".into());
+    assert_eq!(rows[1][1], Some(lang_literal("chat", &fr)));
+    Ok(())
+}
+
+#[test]
+fn parse_xml() -> Result<(), Box<dyn Error>> {
+    let txt = r#"<?xml version="1.0"?>
+        <sparql xmlns="http://www.w3.org/2005/sparql-results#">
+          <head><variable name="x"/><link href="meta"/></head>
+          <results>
+            <result>
+              <binding name="x"><literal datatype="http://www.w3.org/2001/XMLSchema#integer">42</literal></binding>
+            </result>
+            <result>
+              <binding name="x"><literal>a &lt;b&gt; <![CDATA[& c]]></literal></binding>
+            </result>
+          </results>
+        </sparql>"#;
+    let (variables, rows) = XmlResultsParser::default()
+        .parse_results_str(txt)?
+        .into_bindings();
+    assert_eq!(variables, vec!["x"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some(integer_literal(42))],
+            vec![Some(string_literal("a <b> & c"))]
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn parse_tsv() -> Result<(), Box<dyn Error>> {
+    let txt = "?x\t?y\n<http://example.org/a>\t1.5e0\n\t<<( _:b <http://example.org/p> true )>>\n";
+    let (variables, rows) = TsvResultsParser::default()
+        .parse_results_str(txt)?
+        .into_bindings();
+    assert_eq!(variables, vec!["x", "y"]);
+    assert_eq!(rows[0][0], Some(iri_term("http://example.org/a")));
+    assert_eq!(rows[0][1], Some(typed_literal("1.5e0", &XSD_DOUBLE)));
+    assert_eq!(rows[1][0], None);
+    let triple = [
+        bnode_term("b"),
+        iri_term("http://example.org/p"),
+        bool_literal(true),
+    ];
+    assert_eq!(rows[1][1], Some(ArcTerm::Triple(triple.into())));
+    Ok(())
+}
+
+#[test_case("{\"head\": {\"vars\": [\"x\"]}}"; "json without results")]
+#[test_case("{\"head\": {\"vars\": [\"x\"]}, \"results\": {\"bindings\": [{\"y\": {\"type\": \"uri\", \"value\": \"http://a.example/\"}}]}}"; "json unknown variable")]
+#[test_case("{\"head\": {\"vars\": [\"x\"]}, \"results\": {\"bindings\": [{\"x\": {\"type\": \"uri\", \"value\": \"a b\"}}]}}"; "json bad iri")]
+fn invalid_json(txt: &str) {
+    assert!(JsonResultsParser::default().parse_results_str(txt).is_err());
+}
+
+#[test]
+fn serialize_query_bindings() -> Result<(), Box<dyn Error>> {
+    let dataset: Vec<([ArcTerm; 3], Option<ArcTerm>)> = vec![];
+    let bindings = SparqlWrapper(&dataset)
+        .query("SELECT ?x ?y { VALUES (?x ?y) { (1 UNDEF) } }")?
+        .into_bindings();
+    let txt = TsvResultsSerializer::new_stringifier()
+        .serialize_sparql_bindings::<SparqlWrapper<&Vec<([ArcTerm; 3], Option<ArcTerm>)>>, _>(
+            bindings,
+        )?
+        .to_string();
+    let expected = "?x\t?y\n\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>\t\n";
+    assert_eq!(txt, expected);
+    Ok(())
+}
@@ -0,0 +1,337 @@
+//! Serializer and parser for the
+//! [SPARQL 1.1 Query Results TSV Format](https://www.w3.org/TR/sparql11-results-csv-tsv/)
+//! (`text/tab-separated-values`).
+//!
+//! RDF terms are encoded with the Turtle syntax,
+//! and triple terms with the `<<( s p o )>>` syntax of RDF 1.2.
+//! The parser also accepts the older `<< s p o >>` syntax of SPARQL-star.
+use std::io;
+
+use sophia_api::ns::xsd;
+use sophia_api::serializer::Stringifier;
+use sophia_api::source::{SinkError, SourceError, StreamResult};
+use sophia_api::sparql::results::{QueryResults, ResultsParser, ResultsSerializer};
+use sophia_api::term::{Term, TermKind};
+use sophia_term::ArcTerm;
+
+use super::*;
+use crate::value::{parse_decimal, parse_double, parse_integer, XSD};
+
+/// SPARQL results serializer for the TSV format.
+///
+/// NB: TSV can not represent the result of ASK queries,
+/// so [`serialize_boolean`](ResultsSerializer::serialize_boolean) always fails.
+pub struct TsvResultsSerializer<W> {
+    write: W,
+}
+
+impl<W> TsvResultsSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new TSV results serializer writing to `write`.
+    pub fn new(write: W) -> Self {
+        TsvResultsSerializer { write }
+    }
+}
+
+impl TsvResultsSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        TsvResultsSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for TsvResultsSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+impl<W> ResultsSerializer for TsvResultsSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_bindings<I, R, T, E>(
+        &mut self,
+        variables: &[&str],
+        rows: I,
+    ) -> StreamResult<&mut Self, E, Self::Error>
+    where
+        I: IntoIterator<Item = Result<R, E>>,
+        R: AsRef<[Option<T>]>,
+        T: Term,
+        E: 'static + std::error::Error,
+    {
+        let w = &mut self.write;
+        let header: Vec<_> = variables.iter().map(|var| format!("?{var}")).collect();
+        writeln!(w, "{}", header.join("\t")).map_err(SinkError)?;
+        for row in rows {
+            let row = row.map_err(SourceError)?;
+            for (i, value) in row.as_ref().iter().enumerate() {
+                if i > 0 {
+                    w.write_all(b"\t").map_err(SinkError)?;
+                }
+                if let Some(value) = value {
+                    write_term(w, value.borrow_term()).map_err(SinkError)?;
+                }
+            }
+            w.write_all(b"\n").map_err(SinkError)?;
+        }
+        Ok(self)
+    }
+
+    fn serialize_boolean(&mut self, _: bool) -> Result<&mut Self, Self::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TSV can not represent boolean results",
+        ))
+    }
+}
+
+/// Write the given term into the given write, in the TSV results format.
+pub fn write_term<W, T>(w: &mut W, t: T) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    match t.kind() {
+        TermKind::Iri => write!(w, "<{}>", t.iri().unwrap().as_str()),
+        TermKind::BlankNode => write!(w, "_:{}", t.bnode_id().unwrap().as_str()),
+        TermKind::Literal => {
+            w.write_all(b"\"")?;
+            for chr in t.lexical_form().unwrap().chars() {
+                match chr {
+                    '\t' => w.write_all(b"\\t")?,
+                    '\n' => w.write_all(b"\\n")?,
+                    '\r' => w.write_all(b"\\r")?,
+                    '"' => w.write_all(b"\\\"")?,
+                    '\\' => w.write_all(b"\\\\")?,
+                    chr => write!(w, "{chr}")?,
+                }
+            }
+            w.write_all(b"\"")?;
+            match t.language_tag() {
+                Some(tag) => write!(w, "@{}", tag.as_str()),
+                None => {
+                    let dt = t.datatype().unwrap();
+                    if xsd::string != dt {
+                        write!(w, "^^<{}>", dt.as_str())
+                    } else {
+                        Ok(())
+                    }
+                }
+            }
+        }
+        TermKind::Triple => {
+            let [s, p, o] = t.triple().unwrap();
+            w.write_all(b"<<( ")?;
+            write_term(w, s)?;
+            w.write_all(b" ")?;
+            write_term(w, p)?;
+            w.write_all(b" ")?;
+            write_term(w, o)?;
+            w.write_all(b" )>>")
+        }
+        TermKind::Variable => Err(variable_error()),
+    }
+}
+
+/// SPARQL results parser for the TSV format.
+#[derive(Clone, Debug, Default)]
+pub struct TsvResultsParser {}
+
+impl<B: io::BufRead> ResultsParser<B> for TsvResultsParser {
+    type Term = ArcTerm;
+    type Error = ResultsError;
+
+    fn parse_results(&self, data: B) -> ResultsResult<QueryResults<ArcTerm>> {
+        let mut lines = data.lines();
+        let header = match lines.next() {
+            Some(line) => line?,
+            None => return syntax_error("missing header line"),
+        };
+        let variables = if header.is_empty() {
+            vec![]
+        } else {
+            header
+                .split('\t')
+                .map(|var| match var.strip_prefix(['?', '$']) {
+                    Some(name) => Ok(name.to_string()),
+                    None => syntax_error(format!("invalid variable {var}")),
+                })
+                .collect::<ResultsResult<Vec<_>>>()?
+        };
+        let mut rows = vec![];
+        for line in lines {
+            let line = line?;
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if variables.is_empty() {
+                if !line.is_empty() {
+                    return syntax_error("unexpected value");
+                }
+                rows.push(vec![]);
+                continue;
+            }
+            let row = line
+                .split('\t')
+                .map(|field| match field.trim() {
+                    "" => Ok(None),
+                    field => parse_term(field).map(Some),
+                })
+                .collect::<ResultsResult<Vec<_>>>()?;
+            if row.len() != variables.len() {
+                return syntax_error(format!(
+                    "expected {} values, found {}",
+                    variables.len(),
+                    row.len()
+                ));
+            }
+            rows.push(row);
+        }
+        Ok(QueryResults::Bindings { variables, rows })
+    }
+}
+
+/// Parse an RDF term encoded with the Turtle syntax (without prefixed names).
+pub(crate) fn parse_term(txt: &str) -> ResultsResult<ArcTerm> {
+    let mut parser = TermParser { txt, pos: 0 };
+    let term = parser.term()?;
+    parser.skip_whitespace();
+    if parser.pos < txt.len() {
+        return syntax_error(format!("unexpected characters after term in {txt}"));
+    }
+    Ok(term)
+}
+
+struct TermParser<'a> {
+    txt: &'a str,
+    pos: usize,
+}
+
+impl<'a> TermParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.txt[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume `token` if the remaining text starts with it.
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> ResultsResult<()> {
+        self.skip_whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            syntax_error(format!("expected {token} in {}", self.txt))
+        }
+    }
+
+    /// Consume the longest prefix of the remaining text whose characters satisfy `pred`.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn term(&mut self) -> ResultsResult<ArcTerm> {
+        self.skip_whitespace();
+        if self.eat("<<") {
+            self.skip_whitespace();
+            let parenthesized = self.eat("(");
+            let s = self.term()?;
+            let p = self.term()?;
+            let o = self.term()?;
+            if parenthesized {
+                self.expect(")")?;
+            }
+            self.expect(">>")?;
+            make_triple(s, p, o)
+        } else if self.eat("<") {
+            let iri = self.take_while(|c| c != '>');
+            self.expect(">")?;
+            make_iri(iri)
+        } else if self.eat("_:") {
+            let label = self.take_while(|c| !c.is_whitespace() && c != '>' && c != ')');
+            make_bnode(label)
+        } else if self.eat("\"") {
+            let lex = self.string()?;
+            if self.eat("@") {
+                let tag = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-');
+                make_literal(&lex, None, Some(tag))
+            } else if self.eat("^^<") {
+                let datatype = self.take_while(|c| c != '>');
+                self.expect(">")?;
+                make_literal(&lex, Some(datatype), None)
+            } else {
+                make_literal(&lex, None, None)
+            }
+        } else {
+            let token = self.take_while(|c| !c.is_whitespace() && c != '>' && c != ')');
+            let datatype = if token == "true" || token == "false" {
+                "boolean"
+            } else if parse_integer(token).is_some() {
+                "integer"
+            } else if parse_decimal(token).is_some() && token.contains('.') {
+                "decimal"
+            } else if parse_double(token).is_some() && token.contains(['e', 'E']) {
+                "double"
+            } else {
+                return syntax_error(format!("invalid RDF term {token}"));
+            };
+            make_literal(token, Some(&format!("{XSD}{datatype}")), None)
+        }
+    }
+
+    /// Parse the rest of a quoted string, whose opening quote has already been consumed.
+    fn string(&mut self) -> ResultsResult<String> {
+        let mut ret = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, chr)) = chars.next() {
+            match chr {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(ret);
+                }
+                '\\' => {
+                    let unescaped = match chars.next() {
+                        Some((_, 't')) => '\t',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, '"')) => '"',
+                        Some((_, '\'')) => '\'',
+                        Some((_, '\\')) => '\\',
+                        Some((_, u @ ('u' | 'U'))) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex: String = chars.by_ref().take(len).map(|(_, c)| c).collect();
+                            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                Some(chr) if hex.len() == len => chr,
+                                _ => return syntax_error(format!("invalid escape \\{u}{hex}")),
+                            }
+                        }
+                        _ => return syntax_error("invalid escape sequence"),
+                    };
+                    ret.push(unescaped);
+                }
+                chr => ret.push(chr),
+            }
+        }
+        syntax_error("unterminated string")
+    }
+}
@@ -0,0 +1,409 @@
+//! Serializer and parser for the
+//! [SPARQL Query Results XML Format](https://www.w3.org/TR/rdf-sparql-XMLres/)
+//! (`application/sparql-results+xml`).
+//!
+//! Triple terms are encoded as `<triple>` elements,
+//! containing a `<subject>`, a `<predicate>` and an `<object>` element.
+use std::borrow::Cow;
+use std::io;
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sophia_api::ns::xsd;
+use sophia_api::serializer::Stringifier;
+use sophia_api::source::{SinkError, SourceError, StreamResult};
+use sophia_api::sparql::results::{QueryResults, ResultsParser, ResultsSerializer};
+use sophia_api::term::{Term, TermKind};
+use sophia_term::ArcTerm;
+
+use super::*;
+
+const HEADER: &str =
+    "<?xml version=\"1.0\"?>\n<sparql xmlns=\"http://www.w3.org/2005/sparql-results#\">\n";
+
+/// SPARQL results serializer for the XML format.
+pub struct XmlResultsSerializer<W> {
+    write: W,
+}
+
+impl<W> XmlResultsSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new XML results serializer writing to `write`.
+    pub fn new(write: W) -> Self {
+        XmlResultsSerializer { write }
+    }
+}
+
+impl XmlResultsSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        XmlResultsSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for XmlResultsSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+impl<W> ResultsSerializer for XmlResultsSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_bindings<I, R, T, E>(
+        &mut self,
+        variables: &[&str],
+        rows: I,
+    ) -> StreamResult<&mut Self, E, Self::Error>
+    where
+        I: IntoIterator<Item = Result<R, E>>,
+        R: AsRef<[Option<T>]>,
+        T: Term,
+        E: 'static + std::error::Error,
+    {
+        let w = &mut self.write;
+        write_head(w, variables).map_err(SinkError)?;
+        w.write_all(b"<results>\n").map_err(SinkError)?;
+        for row in rows {
+            let row = row.map_err(SourceError)?;
+            write_row(w, variables, row.as_ref()).map_err(SinkError)?;
+        }
+        w.write_all(b"</results>\n</sparql>\n").map_err(SinkError)?;
+        Ok(self)
+    }
+
+    fn serialize_boolean(&mut self, value: bool) -> Result<&mut Self, Self::Error> {
+        let value = if value { "true" } else { "false" };
+        writeln!(
+            self.write,
+            "{HEADER}<head/>\n<boolean>{value}</boolean>\n</sparql>"
+        )?;
+        Ok(self)
+    }
+}
+
+fn write_head<W: io::Write>(w: &mut W, variables: &[&str]) -> io::Result<()> {
+    w.write_all(HEADER.as_bytes())?;
+    w.write_all(b"<head>\n")?;
+    for var in variables {
+        writeln!(w, "  <variable name=\"{}\"/>", escape(var))?;
+    }
+    w.write_all(b"</head>\n")
+}
+
+fn write_row<W, T>(w: &mut W, variables: &[&str], row: &[Option<T>]) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    w.write_all(b"  <result>\n")?;
+    for (var, value) in variables.iter().zip(row) {
+        if let Some(value) = value {
+            write!(w, "    <binding name=\"{}\">", escape(var))?;
+            write_term(w, value.borrow_term())?;
+            w.write_all(b"</binding>\n")?;
+        }
+    }
+    w.write_all(b"  </result>\n")
+}
+
+/// Write the given term into the given write, as an XML element.
+pub fn write_term<W, T>(w: &mut W, t: T) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    match t.kind() {
+        TermKind::Iri => {
+            write!(w, "<uri>{}</uri>", escape(t.iri().unwrap().as_str()))?;
+        }
+        TermKind::BlankNode => {
+            write!(
+                w,
+                "<bnode>{}</bnode>",
+                escape(t.bnode_id().unwrap().as_str())
+            )?;
+        }
+        TermKind::Literal => {
+            w.write_all(b"<literal")?;
+            match t.language_tag() {
+                Some(tag) => {
+                    write!(w, " xml:lang=\"{}\"", escape(tag.as_str()))?;
+                }
+                None => {
+                    let dt = t.datatype().unwrap();
+                    if xsd::string != dt {
+                        write!(w, " datatype=\"{}\"", escape(dt.as_str()))?;
+                    }
+                }
+            }
+            let lex = t.lexical_form().unwrap();
+            write!(w, ">{}</literal>", escape_text(&lex))?;
+        }
+        TermKind::Triple => {
+            let [s, p, o] = t.triple().unwrap();
+            w.write_all(b"<triple><subject>")?;
+            write_term(w, s)?;
+            w.write_all(b"</subject><predicate>")?;
+            write_term(w, p)?;
+            w.write_all(b"</predicate><object>")?;
+            write_term(w, o)?;
+            w.write_all(b"</object></triple>")?;
+        }
+        TermKind::Variable => return Err(variable_error()),
+    }
+    Ok(())
+}
+
+/// Escape `txt` as XML character data,
+/// including carriage returns, which XML parsers would otherwise normalize into line feeds.
+fn escape_text(txt: &str) -> Cow<'_, str> {
+    let escaped = escape(txt);
+    if escaped.contains('\r') {
+        Cow::Owned(escaped.replace('\r', "&#13;"))
+    } else {
+        escaped
+    }
+}
+
+/// SPARQL results parser for the XML format.
+#[derive(Clone, Debug, Default)]
+pub struct XmlResultsParser {}
+
+impl<B: io::BufRead> ResultsParser<B> for XmlResultsParser {
+    type Term = ArcTerm;
+    type Error = ResultsError;
+
+    fn parse_results(&self, data: B) -> ResultsResult<QueryResults<ArcTerm>> {
+        let mut reader = XmlReader::new(data);
+        match reader.next_element()? {
+            Some((name, _)) if name == "sparql" => (),
+            _ => return syntax_error("expected a <sparql> element"),
+        }
+        let mut variables = vec![];
+        let mut rows = None;
+        let mut boolean = None;
+        while let Some((name, _)) = reader.next_element()? {
+            match name.as_str() {
+                "head" => {
+                    while let Some((name, attributes)) = reader.next_element()? {
+                        if name == "variable" {
+                            let Some(var) = attribute(&attributes, "name") else {
+                                return syntax_error("<variable> without a name");
+                            };
+                            variables.push(var.to_string());
+                        }
+                        reader.skip()?;
+                    }
+                }
+                "results" => rows = Some(parse_rows(&mut reader, &variables)?),
+                "boolean" => {
+                    boolean = match reader.text()?.trim() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        other => return syntax_error(format!("invalid boolean {other}")),
+                    }
+                }
+                _ => reader.skip()?,
+            }
+        }
+        match (rows, boolean) {
+            (Some(rows), None) => Ok(QueryResults::Bindings { variables, rows }),
+            (None, Some(value)) => Ok(QueryResults::Boolean(value)),
+            _ => syntax_error("expected exactly one of <results> or <boolean>"),
+        }
+    }
+}
+
+fn parse_rows<B: io::BufRead>(
+    reader: &mut XmlReader<B>,
+    variables: &[String],
+) -> ResultsResult<Vec<Vec<Option<ArcTerm>>>> {
+    let mut rows = vec![];
+    while let Some((name, _)) = reader.next_element()? {
+        if name != "result" {
+            reader.skip()?;
+            continue;
+        }
+        let mut row = vec![None; variables.len()];
+        while let Some((name, attributes)) = reader.next_element()? {
+            if name != "binding" {
+                reader.skip()?;
+                continue;
+            }
+            let Some(var) = attribute(&attributes, "name") else {
+                return syntax_error("<binding> without a name");
+            };
+            let Some(i) = variables.iter().position(|v| v == var) else {
+                return syntax_error(format!("unknown variable {var}"));
+            };
+            row[i] = Some(parse_term(reader)?);
+            if reader.next_element()?.is_some() {
+                return syntax_error("<binding> must contain exactly one RDF term");
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Parse the next element of `reader` as an RDF term.
+fn parse_term<B: io::BufRead>(reader: &mut XmlReader<B>) -> ResultsResult<ArcTerm> {
+    let Some((name, attributes)) = reader.next_element()? else {
+        return syntax_error("expected an RDF term");
+    };
+    match name.as_str() {
+        "uri" => make_iri(&reader.text()?),
+        "bnode" => make_bnode(&reader.text()?),
+        "literal" => make_literal(
+            &reader.text()?,
+            attribute(&attributes, "datatype"),
+            attribute(&attributes, "xml:lang"),
+        ),
+        "triple" => {
+            let mut spo = [None, None, None];
+            while let Some((name, _)) = reader.next_element()? {
+                let i = match name.as_str() {
+                    "subject" => 0,
+                    "predicate" => 1,
+                    "object" => 2,
+                    other => return syntax_error(format!("unexpected <{other}> in <triple>")),
+                };
+                spo[i] = Some(parse_term(reader)?);
+                if reader.next_element()?.is_some() {
+                    return syntax_error(format!("<{name}> must contain exactly one RDF term"));
+                }
+            }
+            let [Some(s), Some(p), Some(o)] = spo else {
+                return syntax_error("incomplete <triple>");
+            };
+            make_triple(s, p, o)
+        }
+        other => syntax_error(format!("unknown term element <{other}>")),
+    }
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// The attributes of an XML element, as (name, value) pairs.
+type Attributes = Vec<(String, String)>;
+
+/// An owned and simplified version of the XML events we care about.
+enum XmlEvent {
+    Start(String, Attributes),
+    End,
+    Text(String),
+    Eof,
+}
+
+/// A thin wrapper around [`quick_xml::Reader`], producing [`XmlEvent`]s.
+struct XmlReader<B> {
+    reader: Reader<B>,
+    buf: Vec<u8>,
+    /// Set when an empty element was read, whose end must be reported next
+    pending_end: bool,
+}
+
+impl<B: io::BufRead> XmlReader<B> {
+    fn new(data: B) -> Self {
+        XmlReader {
+            reader: Reader::from_reader(data),
+            buf: vec![],
+            pending_end: false,
+        }
+    }
+
+    fn next(&mut self) -> ResultsResult<XmlEvent> {
+        if self.pending_end {
+            self.pending_end = false;
+            return Ok(XmlEvent::End);
+        }
+        loop {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(e) => start_event(&e)?,
+                Event::Empty(e) => {
+                    self.pending_end = true;
+                    start_event(&e)?
+                }
+                Event::End(_) => XmlEvent::End,
+                Event::Text(e) => XmlEvent::Text(e.unescape()?.into_owned()),
+                Event::CData(e) => XmlEvent::Text(utf8(&e)?.to_string()),
+                Event::Eof => XmlEvent::Eof,
+                _ => continue,
+            };
+            return Ok(event);
+        }
+    }
+
+    /// Read the start of the next child element,
+    /// or `None` if the end of the current element is reached.
+    fn next_element(&mut self) -> ResultsResult<Option<(String, Attributes)>> {
+        loop {
+            match self.next()? {
+                XmlEvent::Start(name, attributes) => return Ok(Some((name, attributes))),
+                XmlEvent::End => return Ok(None),
+                XmlEvent::Text(txt) if txt.trim().is_empty() => continue,
+                XmlEvent::Text(_) => return syntax_error("unexpected text"),
+                XmlEvent::Eof => return syntax_error("unexpected end of document"),
+            }
+        }
+    }
+
+    /// Read the text content of the current element, up to its end.
+    fn text(&mut self) -> ResultsResult<String> {
+        let mut ret = String::new();
+        loop {
+            match self.next()? {
+                XmlEvent::Text(txt) => ret.push_str(&txt),
+                XmlEvent::End => return Ok(ret),
+                XmlEvent::Start(name, _) => {
+                    return syntax_error(format!("unexpected element <{name}>"))
+                }
+                XmlEvent::Eof => return syntax_error("unexpected end of document"),
+            }
+        }
+    }
+
+    /// Skip the rest of the current element, including its end.
+    fn skip(&mut self) -> ResultsResult<()> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                XmlEvent::Start(..) => depth += 1,
+                XmlEvent::End if depth == 0 => return Ok(()),
+                XmlEvent::End => depth -= 1,
+                XmlEvent::Text(_) => (),
+                XmlEvent::Eof => return syntax_error("unexpected end of document"),
+            }
+        }
+    }
+}
+
+fn start_event(e: &BytesStart) -> ResultsResult<XmlEvent> {
+    let name = utf8(e.local_name().as_ref())?.to_string();
+    let mut attributes = vec![];
+    for attr in e.attributes() {
+        let attr = attr.map_err(quick_xml::Error::from)?;
+        let key = utf8(attr.key.as_ref())?.to_string();
+        let value = attr.unescape_value()?.into_owned();
+        attributes.push((key, value));
+    }
+    Ok(XmlEvent::Start(name, attributes))
+}
+
+fn utf8(bytes: &[u8]) -> ResultsResult<&str> {
+    std::str::from_utf8(bytes).or_else(|_| syntax_error("invalid UTF-8"))
+}
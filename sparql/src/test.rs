@@ -0,0 +1,287 @@
+use super::*;
+use sophia_api::prefix::Prefix;
+use sophia_api::source::QuadSource;
+use sophia_api::term::{IriRef, Term};
+use sophia_inmem::dataset::LightDataset;
+use sophia_turtle::parser::trig;
+use test_case::test_case;
+
+const DATA: &str = r#"
+    PREFIX : <http://example.org/>
+    :alice a :Person ; :name "Alice" ; :age 30 ; :knows :bob, :charlie .
+    :bob a :Person ; :name "Bob"@en ; :age 25 ; :knows :charlie .
+    :charlie a :Person ; :name "Charlie" .
+    :list :items (1 2 3) .
+    :g1 { :alice :likes :bob . }
+    :g2 { :bob :likes :charlie . }
+"#;
+
+const PREFIXES: &str =
+    "PREFIX : <http://example.org/> PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>\n";
+
+fn dataset() -> LightDataset {
+    trig::parse_str(DATA).collect_quads().unwrap()
+}
+
+/// A compact representation of terms, to make expected results easier to write
+fn show(t: &Option<ArcTerm>) -> String {
+    match t {
+        None => "-".into(),
+        Some(t) if t.is_iri() => t.iri().unwrap().as_str().replace("http://example.org/", ""),
+        Some(t) if t.is_blank_node() => "_".into(),
+        Some(t) => match t.language_tag() {
+            Some(tag) => format!("{}@{}", t.lexical_form().unwrap(), tag.as_str()),
+            None => t.lexical_form().unwrap().to_string(),
+        },
+    }
+}
+
+#[test_case("SELECT ?x { ?x a :Person } ORDER BY ?x", &["alice", "bob", "charlie"]; "bgp")]
+#[test_case("SELECT ?x ?a { ?x a :Person OPTIONAL { ?x :age ?a } } ORDER BY ?x", &["alice 30", "bob 25", "charlie -"]; "optional")]
+#[test_case("SELECT ?x { ?x :age ?a FILTER(?a > 26) }", &["alice"]; "filter")]
+#[test_case("SELECT ?x { { ?x :age 30 } UNION { ?x :name \"Charlie\" } } ORDER BY ?x", &["alice", "charlie"]; "union")]
+#[test_case("SELECT ?x { ?x a :Person MINUS { ?x :knows ?y } }", &["charlie"]; "minus")]
+#[test_case("SELECT ?n { :alice :age ?a BIND(?a * 2 AS ?n) }", &["60"]; "bind")]
+#[test_case("SELECT ?x ?n { VALUES ?x { :alice :bob } ?x :name ?n } ORDER BY ?x", &["alice Alice", "bob Bob@en"]; "values")]
+#[test_case("SELECT ?x (COUNT(?y) AS ?c) { ?x :knows ?y } GROUP BY ?x ORDER BY DESC(?c)", &["alice 2", "bob 1"]; "group by")]
+#[test_case("SELECT ?k (COUNT(*) AS ?c) (?c * 2 AS ?d) { ?x :knows ?y } GROUP BY (?x AS ?k) ORDER BY ?k", &["alice 2 4", "bob 1 2"]; "group by expression")]
+#[test_case("SELECT (AVG(?a) AS ?m) (SUM(?a) AS ?s) { ?x :age ?a }", &["27.5 55"]; "aggregates")]
+#[test_case("SELECT ?x { { SELECT (MAX(?a) AS ?m) { ?y :age ?a } } ?x :age ?m }", &["alice"]; "subquery")]
+#[test_case("SELECT ?x { :alice :knows+ ?x } ORDER BY ?x", &["bob", "charlie"]; "one or more")]
+#[test_case("SELECT ?i { :list :items/rdf:rest*/rdf:first ?i } ORDER BY ?i", &["1", "2", "3"]; "list path")]
+#[test_case("SELECT ?x { ?x ^:knows :bob }", &["charlie"]; "inverse path")]
+#[test_case("SELECT ?g ?x { GRAPH ?g { ?x :likes ?y } } ORDER BY ?g", &["g1 alice", "g2 bob"]; "graph")]
+#[test_case("SELECT ?x FROM :g1 { ?x :likes ?y }", &["alice"]; "from")]
+#[test_case("SELECT (CONCAT(UCASE(?n), \"!\") AS ?s) { :alice :name ?n }", &["ALICE!"]; "string functions")]
+#[test_case("SELECT ?x { ?x :name ?n FILTER(LANG(?n) = \"en\") }", &["bob"]; "lang")]
+#[test_case("SELECT ?x { ?x a :Person FILTER NOT EXISTS { ?x :age ?a } }", &["charlie"]; "not exists")]
+#[test_case("SELECT DISTINCT ?x { ?x :knows [ a :Person ] } ORDER BY ?x", &["alice", "bob"]; "distinct")]
+#[test_case("SELECT ?x { ?x a :Person } ORDER BY DESC(?x) LIMIT 2 OFFSET 1", &["bob", "alice"]; "slice")]
+#[test_case("SELECT ?x { ?x :name ?n FILTER REGEX(?n, \"^c\", \"i\") }", &["charlie"]; "regex")]
+fn select(query: &str, expected: &[&str]) -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let query = format!("{PREFIXES}{query}");
+    let bindings = SparqlWrapper(&d).query(query.as_str())?.into_bindings();
+    let got = bindings
+        .into_iter()
+        .map(|row| {
+            let row = row?;
+            Ok(row.iter().map(show).collect::<Vec<_>>().join(" "))
+        })
+        .collect::<Result<Vec<_>, SparqlWrapperError>>()?;
+    assert_eq!(got, expected);
+    Ok(())
+}
+
+const RDF12_DATA: &str = r#"
+    PREFIX : <http://example.org/>
+    :alice :knows :bob ~ :r1 {| :src :wiki |} .
+    :bob :knows :charlie {| :src :news |} .
+"#;
+
+#[test_case("SELECT ?src { << :alice :knows :bob >> :src ?src }", &["wiki"]; "reified triple")]
+#[test_case("SELECT ?r { ?r rdf:reifies <<( :alice :knows :bob )>> }", &["r1"]; "triple term")]
+#[test_case("SELECT ?r ?src { :alice :knows :bob ~ ?r {| :src ?src |} }", &["r1 wiki"]; "reifier and annotation")]
+#[test_case("SELECT ?x ?src { ?x :knows ?y {| :src ?src |} } ORDER BY ?x", &["alice wiki", "bob news"]; "annotation")]
+#[test_case("SELECT ?r { << ?x :knows :charlie ~ ?r >> }", &["_"]; "reified triple with reifier variable")]
+#[test_case("SELECT ?r { ?r rdf:reifies ?t FILTER(?t = <<( :alice :knows :bob )>>) }", &["r1"]; "triple term expression")]
+#[test_case("SELECT ?src { VALUES ?t { <<( :bob :knows :charlie )>> } ?r rdf:reifies ?t ; :src ?src }", &["news"]; "triple term in values")]
+#[test_case("SELECT ?x { :alice :knows :bob . :bob :knows :charlie ~ ?x }", &["_"]; "reifier variable")]
+fn select_rdf12(query: &str, expected: &[&str]) -> Result<(), Box<dyn Error>> {
+    let d: LightDataset = trig::parse_str(RDF12_DATA).collect_quads()?;
+    let query = format!("{PREFIXES}{query}");
+    let bindings = SparqlWrapper(&d).query(query.as_str())?.into_bindings();
+    let got = bindings
+        .into_iter()
+        .map(|row| {
+            let row = row?;
+            Ok(row.iter().map(show).collect::<Vec<_>>().join(" "))
+        })
+        .collect::<Result<Vec<_>, SparqlWrapperError>>()?;
+    assert_eq!(got, expected);
+    Ok(())
+}
+
+#[test_case("ASK { :alice :knows :bob }", true)]
+#[test_case("ASK { :bob :knows :alice }", false)]
+#[test_case("ASK { FILTER(1/2 = 0.5) }", true)]
+fn ask(query: &str, expected: bool) -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let query = format!("{PREFIXES}{query}");
+    let got = SparqlWrapper(&d).query(query.as_str())?.into_boolean();
+    assert_eq!(got, expected);
+    Ok(())
+}
+
+#[test]
+fn construct() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let query =
+        format!("{PREFIXES}CONSTRUCT {{ ?y :knownBy [ :name ?x ] }} WHERE {{ ?x :knows ?y }}");
+    let triples: Vec<_> = SparqlWrapper(&d)
+        .query(query.as_str())?
+        .into_triples()
+        .collect::<Result<_, _>>()?;
+    assert_eq!(triples.len(), 6);
+    let bnodes: std::collections::HashSet<_> = triples
+        .iter()
+        .filter(|t| t[0].is_blank_node())
+        .map(|t| t[0].clone())
+        .collect();
+    assert_eq!(bnodes.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn describe() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let query = format!("{PREFIXES}DESCRIBE :alice");
+    let triples = SparqlWrapper(&d).query(query.as_str())?.into_triples();
+    assert_eq!(triples.count(), 5);
+    Ok(())
+}
+
+#[test_case("SELECT * WHERE { ?s ?p }"; "incomplete triple")]
+#[test_case("SELECT ?y { ?x :knows ?y } GROUP BY ?x"; "ungrouped variable")]
+#[test_case("SELECT ?x (COUNT(*) AS ?c) { ?x :knows ?y }"; "ungrouped variable with aggregate")]
+#[test_case("SELECT (?y AS ?z) { ?x :knows ?y } GROUP BY ?x"; "ungrouped variable in expression")]
+#[test_case("SELECT * { ?s ?p ?o FILTER(?o = << :a :b :c >>) }"; "reified triple in expression")]
+#[test_case("SELECT * { ?s ?p <<( :a :b :c ~ :r )>> }"; "reifier in triple term")]
+#[test_case("SELECT * { <<( \"a\" :b :c )>> ?p ?o }"; "literal subject in triple term")]
+#[test_case("SELECT * { ?s :knows/:knows ?o ~ :r }"; "reifier on property path")]
+fn parse_error(query: &str) {
+    let d = dataset();
+    let query = format!("{PREFIXES}{query}");
+    let res = SparqlWrapper(&d).query(query.as_str());
+    assert!(matches!(res, Err(SparqlWrapperError::Parse(_))));
+}
+
+#[test]
+fn prepared_query_with_bindings() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let wrapper = SparqlWrapper(&d);
+    let query = format!("{PREFIXES}SELECT ?x ?n {{ ?x :name ?n }} ORDER BY ?n LIMIT 1");
+    let prepared = wrapper.prepare_query(&query)?;
+    for (x, expected) in [("bob", "bob Bob@en"), ("charlie", "charlie Charlie")] {
+        let mut query = prepared.clone();
+        query.bind_variable(
+            "x",
+            IriRef::new_unchecked(format!("http://example.org/{x}")),
+        )?;
+        let got = wrapper
+            .query(&query)?
+            .into_bindings()
+            .into_iter()
+            .map(|row| Ok(row?.iter().map(show).collect::<Vec<_>>().join(" ")))
+            .collect::<Result<Vec<_>, SparqlWrapperError>>()?;
+        assert_eq!(got, vec![expected]);
+    }
+    Ok(())
+}
+
+#[test]
+fn bindings_are_visible_in_filters() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let query = format!("{PREFIXES}SELECT ?x {{ ?x :age ?a FILTER(?a > ?min) }}");
+    let mut query = SparqlQuery::parse(&query)?;
+    query.bind_variables([("min", 26)])?;
+    let got = SparqlWrapper(&d).query(&query)?.into_bindings();
+    assert_eq!(got.into_iter().count(), 1);
+    assert!(query.bind_variable("nope", 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn parse_with_defaults() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let prefixes = [(
+        Prefix::new_unchecked(Box::from("ex")),
+        Iri::new_unchecked(Box::from("http://example.org/")),
+    )];
+    let base = Iri::new_unchecked("http://example.org/");
+    let query = "ASK { <alice> ex:knows ex:bob }";
+    let query = SparqlQuery::parse_with_defaults(query, Some(base), &prefixes[..])?;
+    assert!(SparqlWrapper(&d).query(&query)?.into_boolean());
+    Ok(())
+}
+
+#[test]
+fn default_dataset() -> Result<(), Box<dyn Error>> {
+    let d = dataset();
+    let g1 = IriRef::new_unchecked("http://example.org/g1");
+    let mut query = SparqlQuery::parse(&format!("{PREFIXES}SELECT ?x {{ ?x :likes ?y }}"))?;
+    query.set_default_dataset(&[g1], &[])?;
+    let got = SparqlWrapper(&d).query(&query)?.into_bindings();
+    assert_eq!(got.into_iter().count(), 1);
+    let mut query =
+        SparqlQuery::parse(&format!("{PREFIXES}SELECT ?x FROM :g2 {{ ?x :likes ?y }}"))?;
+    query.set_default_dataset(&[g1], &[])?;
+    let got = SparqlWrapper(&d).query(&query)?.into_bindings();
+    assert_eq!(
+        got.into_iter().next().unwrap()?,
+        vec![Some(ArcTerm::from_term(IriRef::new_unchecked(
+            "http://example.org/bob"
+        )))]
+    );
+    Ok(())
+}
+
+#[test_case("INSERT DATA { :dave a :Person }", "ASK { :dave a :Person }"; "insert data")]
+#[test_case("DELETE DATA { :alice :age 30 }", "ASK { FILTER NOT EXISTS { :alice :age ?a } }"; "delete data")]
+#[test_case("DELETE { ?x :age ?a } INSERT { ?x :age ?b } WHERE { ?x :age ?a BIND(?a + 1 AS ?b) }", "ASK { :alice :age 31 . :bob :age 26 FILTER NOT EXISTS { :alice :age 30 } }"; "delete insert")]
+#[test_case("DELETE WHERE { :bob ?p ?o }", "ASK { FILTER NOT EXISTS { :bob ?p ?o } }"; "delete where")]
+#[test_case("WITH :g1 INSERT { ?x :likes :charlie } WHERE { ?x :likes :bob }", "ASK { GRAPH :g1 { :alice :likes :charlie } }"; "with")]
+#[test_case("CLEAR GRAPH :g1", "ASK { FILTER NOT EXISTS { GRAPH :g1 { ?s ?p ?o } } }"; "clear")]
+#[test_case("DROP NAMED", "ASK { :alice a :Person FILTER NOT EXISTS { GRAPH ?g { ?s ?p ?o } } }"; "drop named")]
+#[test_case("COPY :g1 TO :g2", "ASK { GRAPH :g2 { :alice :likes :bob } FILTER NOT EXISTS { GRAPH :g2 { :bob :likes :charlie } } }"; "copy")]
+#[test_case("MOVE :g1 TO DEFAULT", "ASK { :alice :likes :bob FILTER NOT EXISTS { GRAPH :g1 { ?s ?p ?o } } }"; "move")]
+#[test_case("ADD :g1 TO :g2", "ASK { GRAPH :g2 { :alice :likes :bob . :bob :likes :charlie } GRAPH :g1 { :alice :likes :bob } }"; "add")]
+fn update_request(update: &str, check: &str) -> Result<(), Box<dyn Error>> {
+    let mut d = dataset();
+    let update = format!("{PREFIXES}{update}");
+    SparqlWrapperMut(&mut d).update(update.as_str())?;
+    let check = format!("{PREFIXES}{check}");
+    assert!(SparqlWrapper(&d).query(check.as_str())?.into_boolean());
+    Ok(())
+}
+
+#[test]
+fn update_is_atomic() {
+    let mut d = dataset();
+    let before = d.quads().count();
+    let update =
+        format!("{PREFIXES}INSERT DATA {{ :dave a :Person }} ; CLEAR DEFAULT ; CREATE GRAPH :g1");
+    let res = SparqlWrapperMut(&mut d).update(update.as_str());
+    assert!(matches!(res, Err(SparqlWrapperError::Update(_))));
+    assert_eq!(d.quads().count(), before);
+}
+
+#[test_case("CLEAR GRAPH :nope"; "clear")]
+#[test_case("DROP GRAPH :nope"; "drop")]
+#[test_case("CREATE GRAPH :g1"; "create")]
+#[test_case("ADD :nope TO :g1"; "add")]
+#[test_case("COPY :nope TO :g1"; "copy")]
+#[test_case("MOVE :nope TO :g1"; "move")]
+fn update_fails_unless_silent(update: &str) -> Result<(), Box<dyn Error>> {
+    let mut d = dataset();
+    let before = d.quads().count();
+    let loud = format!("{PREFIXES}{update}");
+    let res = SparqlWrapperMut(&mut d).update(loud.as_str());
+    assert!(matches!(res, Err(SparqlWrapperError::Update(_))));
+    let (op, rest) = update.split_once(' ').unwrap();
+    let silent = format!("{PREFIXES}{op} SILENT {rest}");
+    SparqlWrapperMut(&mut d).update(silent.as_str())?;
+    assert_eq!(d.quads().count(), before);
+    Ok(())
+}
+
+#[test]
+fn insert_data_bnodes_are_fresh() -> Result<(), Box<dyn Error>> {
+    let mut d = LightDataset::new();
+    let mut wrapper = SparqlWrapperMut(&mut d);
+    let update = wrapper.prepare_update("INSERT DATA { _:b <tag:p> 1 }")?;
+    wrapper.update(&update)?;
+    wrapper.update(&update)?;
+    assert_eq!(d.quads().count(), 2);
+    Ok(())
+}
@@ -0,0 +1,247 @@
+//! I implement the application of [SPARQL update requests](crate::algebra::Update)
+//! to a [`MutableDataset`].
+//!
+//! Every change made by a request is recorded in a log,
+//! which is used to undo all of them if one of its operations fails.
+//! Since datasets do not record empty graphs,
+//! `CLEAR` and `DROP` are equivalent, and a named graph is deemed to exist iff it is not empty.
+//! As required by SPARQL 1.1 Update, operations on a non-existing graph
+//! (or `CREATE` on an existing one) fail unless `SILENT` is specified.
+use sophia_api::dataset::{Dataset, MutableDataset};
+use sophia_api::quad::Quad;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{FromTerm, GraphName, Term};
+use sophia_term::ArcTerm;
+
+use crate::algebra::*;
+use crate::exec::{dataset_error, EvalResult, Evaluator, Scope};
+use crate::SparqlWrapperError;
+
+type GQuad = ([ArcTerm; 3], GraphName<ArcTerm>);
+
+/// A change made to the dataset by an update request
+enum Change {
+    Inserted(GQuad),
+    Removed(GQuad),
+}
+
+/// A query with no variable, used to instantiate the templates of `INSERT DATA` and `DELETE DATA`.
+static DATA_QUERY: Query = Query {
+    form: QueryForm::Ask,
+    dataset: None,
+    pattern: GraphPattern::Bgp(Vec::new()),
+    variables: Vec::new(),
+    base: None,
+};
+
+/// Apply `update` to `dataset`.
+///
+/// If any operation fails, the changes made by the previous ones are undone.
+pub(crate) fn update<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    update: &Update,
+) -> EvalResult<()> {
+    let mut log = vec![];
+    for op in &update.operations {
+        if let Err(err) = apply(dataset, op, &mut log) {
+            rollback(dataset, log);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+fn apply<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    op: &UpdateOperation,
+    log: &mut Vec<Change>,
+) -> EvalResult<()> {
+    use UpdateOperation::*;
+    match op {
+        InsertData(quads) => {
+            let quads = instantiate_data(&*dataset, quads);
+            insert_all(dataset, quads, log)
+        }
+        DeleteData(quads) => {
+            let quads = instantiate_data(&*dataset, quads);
+            remove_all(dataset, quads, log)
+        }
+        DeleteInsert {
+            delete,
+            insert,
+            with,
+            query,
+        } => {
+            let (delete, insert) = {
+                let mut ev = Evaluator::new(&*dataset, query);
+                if let (None, Some(with)) = (&query.dataset, with) {
+                    ev.default_graph = vec![Some(with.clone())];
+                }
+                let scope = Scope {
+                    graph: &ev.default_graph,
+                    substitute: false,
+                    prebound: None,
+                };
+                let rows = ev.eval(&query.pattern, scope, &ev.empty_row())?;
+                ev.take_error()?;
+                (
+                    ev.instantiate_quads(delete, &rows, with.as_ref()),
+                    ev.instantiate_quads(insert, &rows, with.as_ref()),
+                )
+            };
+            remove_all(dataset, delete, log)?;
+            insert_all(dataset, insert, log)
+        }
+        Clear(target, silent) | Drop(target, silent) => {
+            let quads = match target {
+                GraphTarget::Default => graph_quads(&*dataset, None)?,
+                GraphTarget::Named(g) => {
+                    let quads = graph_quads(&*dataset, Some(g))?;
+                    if quads.is_empty() && !silent {
+                        return Err(no_such_graph(g));
+                    }
+                    quads
+                }
+                GraphTarget::AllNamed => {
+                    let mut quads = collect_quads(dataset.quads())?;
+                    quads.retain(|(_, g)| g.is_some());
+                    quads
+                }
+                GraphTarget::All => collect_quads(dataset.quads())?,
+            };
+            remove_all(dataset, quads, log)
+        }
+        Create(g, silent) => {
+            let exists = dataset
+                .quads_matching(Any, Any, Any, [Some(g)])
+                .next()
+                .is_some();
+            if exists && !silent {
+                let msg = format!("graph <{}> already exists", g.iri().unwrap().as_str());
+                return Err(SparqlWrapperError::Update(msg));
+            }
+            Ok(())
+        }
+        Add(from, to, silent) => transfer(dataset, from, to, *silent, false, false, log),
+        Copy(from, to, silent) => transfer(dataset, from, to, *silent, true, false, log),
+        Move(from, to, silent) => transfer(dataset, from, to, *silent, true, true, log),
+    }
+}
+
+/// The error raised when a named graph `g` does not exist.
+fn no_such_graph(g: &ArcTerm) -> SparqlWrapperError {
+    let msg = format!("graph <{}> does not exist", g.iri().unwrap().as_str());
+    SparqlWrapperError::Update(msg)
+}
+
+/// Insert the content of graph `from` into graph `to`,
+/// optionally clearing `to` beforehand and `from` afterwards.
+///
+/// If `from` is a non-existing named graph, this fails, or does nothing if `silent` is true.
+fn transfer<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    from: &Option<ArcTerm>,
+    to: &Option<ArcTerm>,
+    silent: bool,
+    clear_to: bool,
+    clear_from: bool,
+    log: &mut Vec<Change>,
+) -> EvalResult<()> {
+    if from == to {
+        return Ok(());
+    }
+    let source = graph_quads(&*dataset, from.as_ref())?;
+    if let (Some(g), true) = (from, source.is_empty()) {
+        return if silent {
+            Ok(())
+        } else {
+            Err(no_such_graph(g))
+        };
+    }
+    if clear_to {
+        let old = graph_quads(&*dataset, to.as_ref())?;
+        remove_all(dataset, old, log)?;
+    }
+    let copies = source
+        .iter()
+        .map(|(spo, _)| (spo.clone(), to.clone()))
+        .collect();
+    if clear_from {
+        remove_all(dataset, source, log)?;
+    }
+    insert_all(dataset, copies, log)
+}
+
+/// Insert the given quads, logging those that were not already present.
+fn insert_all<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    quads: Vec<GQuad>,
+    log: &mut Vec<Change>,
+) -> EvalResult<()> {
+    for quad in quads {
+        let ([s, p, o], g) = &quad;
+        if !dataset
+            .contains(s, p, o, g.as_ref())
+            .map_err(dataset_error)?
+        {
+            dataset.insert(s, p, o, g.as_ref()).map_err(dataset_error)?;
+            log.push(Change::Inserted(quad));
+        }
+    }
+    Ok(())
+}
+
+/// Remove the given quads, logging those that were actually present.
+fn remove_all<D: MutableDataset + ?Sized>(
+    dataset: &mut D,
+    quads: Vec<GQuad>,
+    log: &mut Vec<Change>,
+) -> EvalResult<()> {
+    for quad in quads {
+        let ([s, p, o], g) = &quad;
+        if dataset
+            .contains(s, p, o, g.as_ref())
+            .map_err(dataset_error)?
+        {
+            dataset.remove(s, p, o, g.as_ref()).map_err(dataset_error)?;
+            log.push(Change::Removed(quad));
+        }
+    }
+    Ok(())
+}
+
+/// Undo the changes recorded in `log`, in reverse order.
+///
+/// Errors are ignored: there is no better way to report them than the error that caused the rollback.
+fn rollback<D: MutableDataset + ?Sized>(dataset: &mut D, log: Vec<Change>) {
+    for change in log.into_iter().rev() {
+        let _ = match change {
+            Change::Inserted(([s, p, o], g)) => dataset.remove(s, p, o, g),
+            Change::Removed(([s, p, o], g)) => dataset.insert(s, p, o, g),
+        };
+    }
+}
+
+/// Instantiate the ground quads of `INSERT DATA` or `DELETE DATA`,
+/// replacing blank nodes with fresh ones.
+fn instantiate_data<D: Dataset + ?Sized>(dataset: &D, quads: &[QuadPattern]) -> Vec<GQuad> {
+    let ev = Evaluator::new(dataset, &DATA_QUERY);
+    ev.instantiate_quads(quads, &[ev.empty_row()], None)
+}
+
+fn graph_quads<D: Dataset + ?Sized>(dataset: &D, g: GraphName<&ArcTerm>) -> EvalResult<Vec<GQuad>> {
+    collect_quads(dataset.quads_matching(Any, Any, Any, [g]))
+}
+
+fn collect_quads<Q, E>(quads: impl Iterator<Item = Result<Q, E>>) -> EvalResult<Vec<GQuad>>
+where
+    Q: Quad,
+    E: std::error::Error + 'static,
+{
+    quads
+        .map(|quad| {
+            let (spo, g) = quad.map_err(dataset_error)?.to_spog();
+            Ok((spo.map(ArcTerm::from_term), g.map(ArcTerm::from_term)))
+        })
+        .collect()
+}
@@ -0,0 +1,506 @@
+//! Utility functions for building [`ArcTerm`]s,
+//! and for interpreting literals as values of the XSD datatypes supported by SPARQL.
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use sophia_api::term::{BnodeId, IriRef, LanguageTag};
+use sophia_term::{ArcTerm, GenericLiteral};
+
+pub(crate) const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+pub(crate) const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+pub(crate) const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub(crate) const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+pub(crate) const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+pub(crate) const RDF_NIL: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#nil";
+pub(crate) const RDF_REIFIES: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies";
+
+lazy_static! {
+    pub(crate) static ref XSD_STRING: IriRef<Arc<str>> = xsd("string");
+    pub(crate) static ref XSD_BOOLEAN: IriRef<Arc<str>> = xsd("boolean");
+    pub(crate) static ref XSD_INTEGER: IriRef<Arc<str>> = xsd("integer");
+    pub(crate) static ref XSD_DECIMAL: IriRef<Arc<str>> = xsd("decimal");
+    pub(crate) static ref XSD_FLOAT: IriRef<Arc<str>> = xsd("float");
+    pub(crate) static ref XSD_DOUBLE: IriRef<Arc<str>> = xsd("double");
+    pub(crate) static ref XSD_DATETIME: IriRef<Arc<str>> = xsd("dateTime");
+    pub(crate) static ref XSD_DAYTIMEDURATION: IriRef<Arc<str>> = xsd("dayTimeDuration");
+}
+
+/// Build the IRI of the XSD datatype with the given local name.
+pub(crate) fn xsd(local: &str) -> IriRef<Arc<str>> {
+    IriRef::new_unchecked(format!("{XSD}{local}").into())
+}
+
+/// Build an IRI term, without checking it.
+pub(crate) fn iri_term(iri: &str) -> ArcTerm {
+    ArcTerm::Iri(IriRef::new_unchecked(iri.into()))
+}
+
+/// Build a blank node, without checking its label.
+pub(crate) fn bnode_term(label: &str) -> ArcTerm {
+    ArcTerm::BlankNode(BnodeId::new_unchecked(label.into()))
+}
+
+/// Build a typed literal.
+pub(crate) fn typed_literal(lex: &str, datatype: &IriRef<Arc<str>>) -> ArcTerm {
+    ArcTerm::Literal(GenericLiteral::Typed(lex.into(), datatype.clone()))
+}
+
+/// Build a language-tagged literal.
+pub(crate) fn lang_literal(lex: &str, tag: &LanguageTag<Arc<str>>) -> ArcTerm {
+    ArcTerm::Literal(GenericLiteral::LanguageString(lex.into(), tag.clone()))
+}
+
+/// Build a simple literal (i.e. an `xsd:string`).
+pub(crate) fn string_literal(lex: &str) -> ArcTerm {
+    typed_literal(lex, &XSD_STRING)
+}
+
+/// Build an `xsd:boolean` literal.
+pub(crate) fn bool_literal(b: bool) -> ArcTerm {
+    typed_literal(if b { "true" } else { "false" }, &XSD_BOOLEAN)
+}
+
+/// Build an `xsd:integer` literal.
+pub(crate) fn integer_literal(i: i64) -> ArcTerm {
+    typed_literal(&i.to_string(), &XSD_INTEGER)
+}
+
+/// If `term` is a literal, return its lexical form, datatype IRI and language tag.
+pub(crate) fn literal_parts(term: &ArcTerm) -> Option<(&str, &str, Option<&str>)> {
+    match term {
+        ArcTerm::Literal(GenericLiteral::Typed(lex, dt)) => Some((&**lex, dt.as_str(), None)),
+        ArcTerm::Literal(GenericLiteral::LanguageString(lex, tag)) => {
+            Some((&**lex, RDF_LANG_STRING, Some(tag.as_str())))
+        }
+        _ => None,
+    }
+}
+
+/// If `term` is an `xsd:string` or a language-tagged string,
+/// return its lexical form and language tag.
+pub(crate) fn string_parts(term: &ArcTerm) -> Option<(&str, Option<&LanguageTag<Arc<str>>>)> {
+    match term {
+        ArcTerm::Literal(GenericLiteral::Typed(lex, dt)) if dt.as_str() == XSD_STRING.as_str() => {
+            Some((&**lex, None))
+        }
+        ArcTerm::Literal(GenericLiteral::LanguageString(lex, tag)) => Some((&**lex, Some(tag))),
+        _ => None,
+    }
+}
+
+/// If `term` is an `xsd:boolean`, return its value.
+///
+/// Returns `Some(None)` if the lexical form is invalid.
+pub(crate) fn as_boolean(term: &ArcTerm) -> Option<Option<bool>> {
+    match literal_parts(term)? {
+        (lex, dt, None) if dt == XSD_BOOLEAN.as_str() => Some(match lex {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// The numeric values supported by SPARQL.
+///
+/// NB: `xsd:decimal` values are approximated with `f64`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Numeric {
+    Integer(i64),
+    Decimal(f64),
+    Float(f32),
+    Double(f64),
+}
+
+impl Numeric {
+    fn rank(&self) -> u8 {
+        match self {
+            Numeric::Integer(_) => 0,
+            Numeric::Decimal(_) => 1,
+            Numeric::Float(_) => 2,
+            Numeric::Double(_) => 3,
+        }
+    }
+
+    /// Convert this numeric to `f64`.
+    pub(crate) fn as_f64(&self) -> f64 {
+        match *self {
+            Numeric::Integer(i) => i as f64,
+            Numeric::Decimal(d) => d,
+            Numeric::Float(f) => f as f64,
+            Numeric::Double(d) => d,
+        }
+    }
+
+    /// Convert this numeric to the given rank (see [type promotion](https://www.w3.org/TR/xpath20/#promotion)).
+    fn promote(self, rank: u8) -> Self {
+        match rank {
+            0 => self,
+            1 => Numeric::Decimal(self.as_f64()),
+            2 => Numeric::Float(self.as_f64() as f32),
+            _ => Numeric::Double(self.as_f64()),
+        }
+    }
+
+    /// Promote both operands to their common type.
+    pub(crate) fn unify(a: Self, b: Self) -> (Self, Self) {
+        let rank = a.rank().max(b.rank());
+        (a.promote(rank), b.promote(rank))
+    }
+
+    /// Apply a binary arithmetic operation.
+    ///
+    /// `None` is returned on overflow or division by zero (for integers and decimals).
+    pub(crate) fn apply(
+        a: Self,
+        b: Self,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Option<Self> {
+        match Self::unify(a, b) {
+            (Numeric::Integer(a), Numeric::Integer(b)) => int_op(a, b).map(Numeric::Integer),
+            (Numeric::Decimal(a), Numeric::Decimal(b)) => {
+                let r = float_op(a, b);
+                r.is_finite().then_some(Numeric::Decimal(r))
+            }
+            (Numeric::Float(a), Numeric::Float(b)) => {
+                Some(Numeric::Float(float_op(a as f64, b as f64) as f32))
+            }
+            (a, b) => Some(Numeric::Double(float_op(a.as_f64(), b.as_f64()))),
+        }
+    }
+
+    /// Compare two numerics (`None` if one of them is NaN).
+    pub(crate) fn partial_cmp(a: Self, b: Self) -> Option<Ordering> {
+        match Self::unify(a, b) {
+            (Numeric::Integer(a), Numeric::Integer(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+
+    /// Whether this numeric is considered as `true` (see [EBV](https://www.w3.org/TR/sparql11-query/#ebv)).
+    pub(crate) fn ebv(&self) -> bool {
+        let f = self.as_f64();
+        f != 0.0 && !f.is_nan()
+    }
+
+    /// Build the literal corresponding to this numeric value.
+    pub(crate) fn to_term(self) -> ArcTerm {
+        match self {
+            Numeric::Integer(i) => integer_literal(i),
+            Numeric::Decimal(d) => typed_literal(&format_decimal(d), &XSD_DECIMAL),
+            Numeric::Float(f) => typed_literal(&format_float(f), &XSD_FLOAT),
+            Numeric::Double(d) => typed_literal(&format_double(d), &XSD_DOUBLE),
+        }
+    }
+}
+
+/// Format a decimal value in its canonical form.
+pub(crate) fn format_decimal(d: f64) -> String {
+    let mut txt = format!("{d}");
+    if !txt.contains('.') {
+        txt.push_str(".0");
+    }
+    txt
+}
+
+/// Format a double value in its canonical form.
+pub(crate) fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        "NaN".into()
+    } else if d.is_infinite() {
+        (if d > 0.0 { "INF" } else { "-INF" }).into()
+    } else {
+        canonical_exp(format!("{d:E}"))
+    }
+}
+
+/// Format a float value in its canonical form.
+pub(crate) fn format_float(f: f32) -> String {
+    if f.is_finite() {
+        canonical_exp(format!("{f:E}"))
+    } else {
+        format_double(f as f64)
+    }
+}
+
+/// Rust writes 1E0, but the canonical form is 1.0E0
+fn canonical_exp(txt: String) -> String {
+    match txt.find('E') {
+        Some(i) if !txt[..i].contains('.') => format!("{}.0{}", &txt[..i], &txt[i..]),
+        _ => txt,
+    }
+}
+
+/// The local names of the XSD datatypes that are derived from `xsd:integer`.
+const INTEGER_TYPES: &[&str] = &[
+    "integer",
+    "nonPositiveInteger",
+    "negativeInteger",
+    "long",
+    "int",
+    "short",
+    "byte",
+    "nonNegativeInteger",
+    "unsignedLong",
+    "unsignedInt",
+    "unsignedShort",
+    "unsignedByte",
+    "positiveInteger",
+];
+
+/// Whether the given datatype is a numeric datatype.
+pub(crate) fn is_numeric_datatype(dt: &str) -> bool {
+    match dt.strip_prefix(XSD) {
+        Some(local) => {
+            matches!(local, "decimal" | "float" | "double") || INTEGER_TYPES.contains(&local)
+        }
+        None => false,
+    }
+}
+
+/// If `term` is a literal of a numeric datatype, return its value.
+///
+/// Returns `Some(None)` if the datatype is numeric but the lexical form is invalid.
+pub(crate) fn as_numeric(term: &ArcTerm) -> Option<Option<Numeric>> {
+    let (lex, dt, _) = literal_parts(term)?;
+    let local = dt.strip_prefix(XSD)?;
+    let lex = lex.trim();
+    Some(match local {
+        "decimal" => parse_decimal(lex).map(Numeric::Decimal),
+        "float" => parse_double(lex).map(|d| Numeric::Float(d as f32)),
+        "double" => parse_double(lex).map(Numeric::Double),
+        _ if INTEGER_TYPES.contains(&local) => parse_integer(lex).map(Numeric::Integer),
+        _ => return None,
+    })
+}
+
+pub(crate) fn parse_integer(lex: &str) -> Option<i64> {
+    let digits = lex.strip_prefix(['+', '-']).unwrap_or(lex);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    lex.strip_prefix('+').unwrap_or(lex).parse().ok()
+}
+
+pub(crate) fn parse_decimal(lex: &str) -> Option<f64> {
+    let digits = lex.strip_prefix(['+', '-']).unwrap_or(lex);
+    if digits.is_empty()
+        || digits == "."
+        || !digits.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+        || digits.bytes().filter(|b| *b == b'.').count() > 1
+    {
+        return None;
+    }
+    lex.parse().ok()
+}
+
+pub(crate) fn parse_double(lex: &str) -> Option<f64> {
+    match lex {
+        "INF" | "+INF" => Some(f64::INFINITY),
+        "-INF" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ if lex
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) =>
+        {
+            lex.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// A parsed `xsd:dateTime` (or `xsd:date`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: f64,
+    /// timezone offset, in minutes
+    pub tz: Option<i32>,
+}
+
+impl DateTime {
+    /// Parse the lexical form of an `xsd:dateTime` or an `xsd:date`.
+    pub(crate) fn parse(lex: &str, date_only: bool) -> Option<Self> {
+        let lex = lex.trim();
+        let (neg, lex) = match lex.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, lex),
+        };
+        let year_end = lex.find('-')?;
+        let year: i64 = lex[..year_end].parse().ok()?;
+        if year_end < 4 {
+            return None;
+        }
+        let rest = &lex[year_end + 1..];
+        let month: u32 = rest.get(0..2)?.parse().ok()?;
+        if rest.get(2..3)? != "-" {
+            return None;
+        }
+        let day: u32 = rest.get(3..5)?.parse().ok()?;
+        let mut rest = &rest[5..];
+        let (mut hour, mut minute, mut second) = (0, 0, 0.0);
+        if !date_only {
+            if rest.get(0..1)? != "T" || rest.get(3..4)? != ":" || rest.get(6..7)? != ":" {
+                return None;
+            }
+            hour = rest.get(1..3)?.parse().ok()?;
+            minute = rest.get(4..6)?.parse().ok()?;
+            let sec_end = rest[7..]
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .map(|i| i + 7)
+                .unwrap_or(rest.len());
+            second = rest[7..sec_end].parse().ok()?;
+            rest = &rest[sec_end..];
+        }
+        let tz = match rest {
+            "" => None,
+            "Z" => Some(0),
+            _ => {
+                let sign = match rest.get(0..1)? {
+                    "+" => 1,
+                    "-" => -1,
+                    _ => return None,
+                };
+                let h: i32 = rest.get(1..3)?.parse().ok()?;
+                let m: i32 = rest.get(4..6)?.parse().ok()?;
+                if rest.len() != 6 || rest.get(3..4)? != ":" {
+                    return None;
+                }
+                Some(sign * (h * 60 + m))
+            }
+        };
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 24 || minute > 59 {
+            return None;
+        }
+        Some(DateTime {
+            year: if neg { -year } else { year },
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            tz,
+        })
+    }
+
+    /// The number of seconds since 1970-01-01T00:00:00Z
+    /// (assuming UTC if no timezone is specified).
+    pub(crate) fn timestamp(&self) -> f64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let minutes =
+            (days * 24 + self.hour as i64) * 60 + self.minute as i64 - self.tz.unwrap_or(0) as i64;
+        minutes as f64 * 60.0 + self.second
+    }
+
+    /// Build a UTC date-time from a timestamp (in milliseconds).
+    pub(crate) fn from_timestamp_millis(millis: i64) -> Self {
+        let days = millis.div_euclid(86_400_000);
+        let ms_of_day = millis.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (ms_of_day / 3_600_000) as u32,
+            minute: (ms_of_day / 60_000 % 60) as u32,
+            second: (ms_of_day % 60_000) as f64 / 1000.0,
+            tz: Some(0),
+        }
+    }
+
+    /// Format this date-time as an `xsd:dateTime` lexical form.
+    pub(crate) fn to_lex(self) -> String {
+        let sec = if self.second.fract() == 0.0 {
+            format!("{:02}", self.second as u32)
+        } else {
+            let txt = format!("{:06.3}", self.second);
+            txt.trim_end_matches('0').to_string()
+        };
+        let mut txt = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{}",
+            self.year, self.month, self.day, self.hour, self.minute, sec
+        );
+        if let Some(tz) = self.tz {
+            txt.push_str(&format_tz(tz));
+        }
+        txt
+    }
+}
+
+/// Format a timezone offset (in minutes) as in `xsd:dateTime`.
+pub(crate) fn format_tz(tz: i32) -> String {
+    if tz == 0 {
+        "Z".into()
+    } else {
+        let sign = if tz < 0 { '-' } else { '+' };
+        format!("{}{:02}:{:02}", sign, tz.abs() / 60, tz.abs() % 60)
+    }
+}
+
+/// If `term` is an `xsd:dateTime` or `xsd:date`, return its value.
+pub(crate) fn as_datetime(term: &ArcTerm) -> Option<DateTime> {
+    let (lex, dt, _) = literal_parts(term)?;
+    match dt.strip_prefix(XSD)? {
+        "dateTime" | "dateTimeStamp" => DateTime::parse(lex, false),
+        "date" => DateTime::parse(lex, true),
+        _ => None,
+    }
+}
+
+// from http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let (m, d) = (m as i64, d as i64);
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// from http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(1.0, "1.0E0")]
+    #[test_case(1234.5, "1.2345E3")]
+    #[test_case(-0.01, "-1.0E-2")]
+    fn double_format(d: f64, exp: &str) {
+        assert_eq!(format_double(d), exp);
+    }
+
+    #[test_case("2024-02-29T12:34:56Z", 1709210096.0)]
+    #[test_case("2024-02-29T14:34:56+02:00", 1709210096.0)]
+    #[test_case("1970-01-01T00:00:00.5", 0.5)]
+    fn datetime_timestamp(lex: &str, exp: f64) {
+        assert_eq!(DateTime::parse(lex, false).unwrap().timestamp(), exp);
+    }
+
+    #[test]
+    fn datetime_roundtrip() {
+        let dt = DateTime::from_timestamp_millis(1_709_210_096_000);
+        assert_eq!(dt.to_lex(), "2024-02-29T12:34:56Z");
+    }
+}
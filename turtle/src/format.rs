@@ -0,0 +1,130 @@
+//! [Formats](Format) of the Turtle family, for use in a [`FormatRegistry`].
+
+use crate::parser::{nq::NQuadsParser, nt::NTriplesParser, trig::TriGParser, turtle::TurtleParser};
+use crate::serializer::{
+    nq::NqSerializer, nt::NtSerializer, trig::TrigSerializer, turtle::TurtleSerializer,
+};
+use sophia_api::format::{box_quads, box_triples, serializer_error, BoxQuadSource, Format};
+use sophia_api::format::{FormatError, FormatRegistry};
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{QuadSerializer, TripleSerializer};
+use sophia_api::source::{QuadSource, TripleSource};
+
+/// The [N-Triples](https://www.w3.org/TR/rdf12-n-triples/) format.
+pub const N_TRIPLES: Format = Format {
+    name: "N-Triples",
+    media_types: &["application/n-triples"],
+    extensions: &["nt"],
+    dataset: false,
+    parser: Some(|read, _| box_triples(NTriplesParser::default().parse(read))),
+    serializer: Some(|write, quads| {
+        serializer_error(NtSerializer::new(write).serialize_triples(default_graph(quads)))
+    }),
+};
+
+/// The [N-Quads](https://www.w3.org/TR/rdf12-n-quads/) format.
+pub const N_QUADS: Format = Format {
+    name: "N-Quads",
+    media_types: &["application/n-quads"],
+    extensions: &["nq"],
+    dataset: true,
+    parser: Some(|read, _| box_quads(NQuadsParser::default().parse(read))),
+    serializer: Some(|write, quads| {
+        serializer_error(NqSerializer::new(write).serialize_quads(quads))
+    }),
+};
+
+/// The [Turtle](https://www.w3.org/TR/rdf12-turtle/) format.
+pub const TURTLE: Format = Format {
+    name: "Turtle",
+    media_types: &["text/turtle", "application/x-turtle"],
+    extensions: &["ttl"],
+    dataset: false,
+    parser: Some(|read, base| box_triples(TurtleParser { base }.parse(read))),
+    serializer: Some(|write, quads| {
+        serializer_error(TurtleSerializer::new(write).serialize_triples(default_graph(quads)))
+    }),
+};
+
+/// The [TriG](https://www.w3.org/TR/rdf12-trig/) format.
+pub const TRIG: Format = Format {
+    name: "TriG",
+    media_types: &["application/trig"],
+    extensions: &["trig"],
+    dataset: true,
+    parser: Some(|read, base| box_quads(TriGParser { base }.parse(read))),
+    serializer: Some(|write, quads| {
+        serializer_error(TrigSerializer::new(write).serialize_quads(quads))
+    }),
+};
+
+/// Add all the formats of this crate to `registry`.
+pub fn register(registry: &mut FormatRegistry) {
+    registry
+        .register(TURTLE)
+        .register(N_TRIPLES)
+        .register(TRIG)
+        .register(N_QUADS);
+}
+
+/// The triples of the default graph of `quads`.
+fn default_graph(quads: BoxQuadSource<'_>) -> impl TripleSource<Error = FormatError> + '_ {
+    quads
+        .filter_quads(|q| q.g().is_none())
+        .map_quads(Quad::into_triple)
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::term::Term;
+    use sophia_isomorphism::isomorphic_datasets;
+
+    const TRIG_DATA: &str = r#"
+        PREFIX : <tag:>
+        :a :b "c"@en.
+        :g { :d :e ( 1 2 ) }
+    "#;
+
+    #[test]
+    fn roundtrips() -> Result<(), Box<dyn std::error::Error>> {
+        let mut registry = FormatRegistry::new();
+        register(&mut registry);
+        let expected: Vec<_> = registry
+            .parse_any(TRIG_DATA.as_bytes(), "application/trig", None)?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(expected.len(), 6);
+        for format in registry.formats() {
+            let mut data = vec![];
+            format.serialize(
+                &mut data,
+                expected.clone().into_iter().map(Ok::<_, FormatError>),
+            )?;
+            let got: Vec<_> = format.parse(&data[..], None)?.collect::<Result<_, _>>()?;
+            let expected: Vec<_> = expected
+                .iter()
+                .filter(|q| format.dataset || q.1.is_none())
+                .cloned()
+                .collect();
+            assert!(isomorphic_datasets(&expected, &got)?, "{}", format.name);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn base() -> Result<(), Box<dyn std::error::Error>> {
+        let mut registry = FormatRegistry::new();
+        register(&mut registry);
+        let base = Some(sophia_iri::Iri::new_unchecked("tag:x/".to_string()));
+        let got: Vec<_> = registry
+            .parse_any(&b"<a> <b> <c>."[..], "text/turtle; charset=utf-8", base)?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(got[0].0[0].iri().unwrap().as_str(), "tag:x/a");
+        Ok(())
+    }
+}
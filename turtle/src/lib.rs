@@ -2,12 +2,35 @@
 //! an [RDF] and [Linked Data] toolkit in Rust.
 //!
 //! Parsers and serializers for the Turtle-familt of RDF concrete syntaxes,
-//! based on [`rio_turtle`](https://docs.rs/rio_turtle/).
+//! including the [RDF 1.2] syntax for triple terms and reifiers.
+//!
+//! With the `async` feature enabled, the parsers and serializers
+//! also work with [tokio]'s `AsyncBufRead` and `AsyncWrite`
+//! (see [`AsyncTripleParser`](sophia_api::parser::AsyncTripleParser)
+//! and [`AsyncTripleSerializer`](sophia_api::serializer::AsyncTripleSerializer)).
+//!
+//! # Limitations
+//!
+//! Base directions of language-tagged strings (`"..."@en--ltr`), introduced by RDF 1.2,
+//! are not supported, as Sophia terms can not represent them.
+//! The parsers reject them with a syntax error rather than silently dropping the direction:
+//!
+//! ```
+//! # use sophia_api::{parser::TripleParser, source::{StreamError, TripleSource}, term::SimpleTerm};
+//! # use sophia_turtle::parser::turtle::TurtleParser;
+//! let ttl = r#"<tag:s> <tag:p> "hello"@en--ltr ."#;
+//! let res = TurtleParser::default().parse_str(ttl).collect_triples::<Vec<[SimpleTerm; 3]>>();
+//! let Err(StreamError::SourceError(err)) = res else { unreachable!() };
+//! assert_eq!(err.position().unwrap().column, 24);
+//! ```
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
 //! [Linked Data]: http://linkeddata.org/
+//! [RDF 1.2]: https://www.w3.org/TR/rdf12-turtle/
+//! [tokio]: https://docs.rs/tokio/
 #![deny(missing_docs)]
 
+pub mod format;
 pub mod parser;
 pub mod serializer;
@@ -1,5 +1,47 @@
-//! Parsers for the Turtle-familt of RDF concrete syntaxes,
-//! based on [`rio_turtle`].
+//! Parsers for the Turtle-familt of RDF concrete syntaxes.
+//!
+//! All parsers support the [RDF 1.2] syntax for triple terms (`<<( ... )>>`),
+//! and the Turtle and TriG parsers also support
+//! reified triples (`<< ... >>`), reifiers (`~`), annotation blocks (`{| ... |}`)
+//! and the `VERSION` directive.
+//! Base directions of language-tagged strings (`"..."@en--ltr`) are not supported
+//! (see [the crate documentation](crate#limitations)): they are reported as syntax errors.
+//! Relative IRI references are only accepted if the parser has a base IRI.
+//!
+//! Syntax errors are reported as [`TurtleError`]s,
+//! indicating the [`Position`] where the error occurred.
+//! By default, the first error interrupts the parsing;
+//! but each parser has a `with_lenient` method, returning a [`LenientParser`]
+//! which skips malformed statements and sends the corresponding errors to a channel:
+//!
+//! ```
+//! # use sophia_api::{parser::TripleParser, source::TripleSource};
+//! # use sophia_turtle::parser::nt::NTriplesParser;
+//! let (tx, rx) = std::sync::mpsc::channel();
+//! let parser = NTriplesParser {}.with_lenient(tx);
+//! let nt = "<tag:a> <tag:b> <tag:c> .\n<a> <tag:b> <tag:c> .\n<tag:a> <tag:b> <tag:d> .";
+//! let triples: Vec<[sophia_api::term::SimpleTerm; 3]> = parser.parse_str(nt).collect_triples()?;
+//! assert_eq!(triples.len(), 2);
+//! let errors: Vec<_> = rx.try_iter().collect();
+//! assert_eq!(errors.len(), 1);
+//! assert_eq!(errors[0].position().unwrap().line, 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [RDF 1.2]: https://www.w3.org/TR/rdf12-turtle/
+
+#[cfg(feature = "async")]
+mod _async;
+#[cfg(feature = "async")]
+pub use _async::*;
+mod _error;
+pub use _error::*;
+mod _lenient;
+pub use _lenient::*;
+mod _lexer;
+mod _parser;
+mod _source;
+pub use _source::*;
 
 pub mod gnq;
 pub mod gtrig;
@@ -7,3 +49,6 @@ pub mod nq;
 pub mod nt;
 pub mod trig;
 pub mod turtle;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,291 @@
+use super::_error::TurtleError;
+use super::_parser::Parser;
+use sophia_api::prefix::PrefixMapPair;
+use sophia_api::quad::Spog;
+use sophia_api::source::{
+    AsyncSource,
+    StreamError::{SinkError, SourceError},
+    StreamResult,
+};
+use sophia_api::term::SimpleTerm;
+use sophia_iri::Iri;
+use std::io::{self, BufRead, Read};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// A [`BufRead`] exposing the bytes received so far from an asynchronous reader.
+///
+/// When all received bytes have been consumed, but the end of the data is not reached yet,
+/// it fails with [`WouldBlock`](io::ErrorKind::WouldBlock);
+/// the parser is then [rolled back](Parser::rollback) to the start of the current statement,
+/// until more data is received.
+#[derive(Debug, Default)]
+pub(crate) struct Feed {
+    buf: Vec<u8>,
+    start: usize,
+    eof: bool,
+}
+
+impl Read for Feed {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let buf = self.fill_buf()?;
+            let n = buf.len().min(out.len());
+            out[..n].copy_from_slice(&buf[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Feed {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.start < self.buf.len() || self.eof {
+            Ok(&self.buf[self.start..])
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.start += amt;
+        if self.start >= self.buf.len() {
+            self.buf.clear();
+            self.start = 0;
+        }
+    }
+}
+
+/// Receive the next chunk of data from `read` into the feed of `parser`,
+/// and return its size (0 meaning that the end of the data is reached).
+async fn receive<R: AsyncBufRead + Unpin>(
+    read: &mut R,
+    parser: &mut Parser<Feed>,
+) -> Result<usize, TurtleError> {
+    let chunk = read.fill_buf().await?;
+    let len = chunk.len();
+    let feed = parser.get_mut();
+    if len == 0 {
+        feed.eof = true;
+    } else {
+        feed.buf.extend_from_slice(chunk);
+    }
+    read.consume(len);
+    Ok(len)
+}
+
+/// Parse the next statement, receiving more data as long as the statement is incomplete.
+///
+/// Every attempt re-lexes the statement from its start,
+/// so before each new attempt, at least as much data is received as for all previous attempts.
+/// This way, a statement spanning many chunks is only parsed a logarithmic number of times,
+/// and the overall parsing time remains linear in the size of the data.
+async fn parse_statement<R: AsyncBufRead + Unpin>(
+    read: &mut R,
+    parser: &mut Parser<Feed>,
+) -> Result<bool, TurtleError> {
+    let mut received = 0;
+    loop {
+        match parser.parse_statement() {
+            Err(TurtleError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => {
+                parser.rollback();
+                let mut more = 0;
+                loop {
+                    let len = receive(read, parser).await?;
+                    more += len;
+                    if len == 0 || more > received {
+                        break;
+                    }
+                }
+                received += more;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// The type of [`AsyncTripleSource`](sophia_api::source::AsyncTripleSource)
+/// returned by the Turtle and N-Triples parsers of this crate.
+pub struct AsyncTurtleTripleSource<R> {
+    pub(crate) read: R,
+    pub(crate) parser: Parser<Feed>,
+}
+
+impl<R> AsyncTurtleTripleSource<R> {
+    /// The prefixes declared so far in the parsed document,
+    /// suitable for [`TurtleConfig::with_own_prefix_map`](crate::serializer::turtle::TurtleConfig::with_own_prefix_map).
+    ///
+    /// Prefixes bound to a relative IRI reference (in a document without base IRI) are omitted.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        self.parser.prefix_map().to_vec()
+    }
+
+    /// The base IRI currently in effect in the parsed document,
+    /// i.e. the last one declared so far, or the one provided to the parser.
+    pub fn base(&self) -> Option<Iri<String>> {
+        self.parser
+            .base()
+            .map(|iri| iri.map_unchecked(String::from))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncSource for AsyncTurtleTripleSource<R> {
+    type Item<'x> = [SimpleTerm<'static>; 3];
+    type Error = TurtleError;
+
+    async fn try_for_some_item<E, F>(&mut self, mut f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: std::error::Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        let more = parse_statement(&mut self.read, &mut self.parser)
+            .await
+            .map_err(SourceError)?;
+        for (spo, _) in self.parser.quads.drain(..) {
+            f(spo).map_err(SinkError)?;
+        }
+        Ok(more)
+    }
+}
+
+/// The type of [`AsyncQuadSource`](sophia_api::source::AsyncQuadSource)
+/// returned by the TriG and N-Quads parsers of this crate.
+pub struct AsyncTurtleQuadSource<R> {
+    pub(crate) read: R,
+    pub(crate) parser: Parser<Feed>,
+}
+
+impl<R> AsyncTurtleQuadSource<R> {
+    /// The prefixes declared so far in the parsed document,
+    /// suitable for [`TurtleConfig::with_own_prefix_map`](crate::serializer::turtle::TurtleConfig::with_own_prefix_map).
+    ///
+    /// Prefixes bound to a relative IRI reference (in a document without base IRI) are omitted.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        self.parser.prefix_map().to_vec()
+    }
+
+    /// The base IRI currently in effect in the parsed document,
+    /// i.e. the last one declared so far, or the one provided to the parser.
+    pub fn base(&self) -> Option<Iri<String>> {
+        self.parser
+            .base()
+            .map(|iri| iri.map_unchecked(String::from))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncSource for AsyncTurtleQuadSource<R> {
+    type Item<'x> = Spog<SimpleTerm<'static>>;
+    type Error = TurtleError;
+
+    async fn try_for_some_item<E, F>(&mut self, mut f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: std::error::Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        let more = parse_statement(&mut self.read, &mut self.parser)
+            .await
+            .map_err(SourceError)?;
+        for quad in self.parser.quads.drain(..) {
+            f(quad).map_err(SinkError)?;
+        }
+        Ok(more)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::_parser::Syntax;
+    use sophia_api::source::{AsyncQuadSource, QuadSource};
+    use sophia_isomorphism::isomorphic_datasets;
+    use std::sync::mpsc::channel;
+
+    type Quads = Vec<Spog<SimpleTerm<'static>>>;
+    type Positions = Vec<(usize, usize)>;
+
+    fn parse_sync(syntax: Syntax, txt: &str) -> Result<(Quads, Positions), TurtleError> {
+        let (tx, rx) = channel();
+        let mut parser = Parser::new(txt.as_bytes(), syntax, false, None);
+        parser.set_lenient(tx);
+        let mut quads = vec![];
+        super::super::TurtleQuadSource(parser).for_each_quad(|q| quads.push(q))?;
+        Ok((quads, positions(rx.try_iter())))
+    }
+
+    fn parse_async(
+        syntax: Syntax,
+        txt: &str,
+        chunk_size: usize,
+    ) -> Result<(Quads, Positions), TurtleError> {
+        let (tx, rx) = channel();
+        let mut source = AsyncTurtleQuadSource {
+            read: tokio::io::BufReader::with_capacity(chunk_size, txt.as_bytes()),
+            parser: Parser::new(Feed::default(), syntax, false, None),
+        };
+        source.parser.set_lenient(tx);
+        let mut quads = vec![];
+        tokio::runtime::Builder::new_current_thread()
+            .build()?
+            .block_on(source.for_each_quad(|q| quads.push(q)))?;
+        Ok((quads, positions(rx.try_iter())))
+    }
+
+    fn positions(errors: impl Iterator<Item = TurtleError>) -> Positions {
+        errors
+            .map(|err| err.position().unwrap())
+            .map(|pos| (pos.line, pos.column))
+            .collect()
+    }
+
+    #[test]
+    fn chunked() -> Result<(), Box<dyn std::error::Error>> {
+        for (syntax, txt) in [
+            (
+                Syntax::NTriples,
+                "\u{feff}<tag:a> <tag:b> \"ça\\u00E7a\"@fr .\n<a> <tag:b> <tag:c> .\n_:x <tag:b> \"1\"^^<tag:d> .\n",
+            ),
+            (
+                Syntax::NQuads,
+                "<tag:a> <tag:b> <tag:c> <tag:g> .\n<tag:a> <tag:b> <tag: c> .\n_:x <tag:b> _:y _:g .",
+            ),
+            (
+                Syntax::Turtle,
+                r#"@prefix : <tag:> . @base <http://example.org/> .
+                   # a comment . with a dot
+                   :a :b "x . y", """long . "" string""", 1.5, 2. :c :d ( 1 [ :e 3 ] ) .
+                   <a> :b <<( :s :p :o )>> ~ :r {| :c :d |} .
+                   :a :b x:c . PREFIX x: <tag:x/> :a :b x:c, x:d\.e ."#,
+            ),
+            (
+                Syntax::TriG,
+                "PREFIX : <tag:> :g { :a :b :c . :a :b . :d :e :f } GRAPH :h { :a :b 'x' } { :a :b :c }",
+            ),
+        ] {
+            let expected = parse_sync(syntax, txt)?;
+            for chunk_size in [1, 2, 3, 5, 8, 64] {
+                let got = parse_async(syntax, txt, chunk_size)?;
+                assert!(
+                    isomorphic_datasets(&expected.0, &got.0)?,
+                    "{txt} by chunks of {chunk_size}: {:?}",
+                    got.0
+                );
+                assert_eq!(expected.1, got.1, "{txt} by chunks of {chunk_size}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn long_statement() -> Result<(), Box<dyn std::error::Error>> {
+        // would take a quadratic time if the statement was re-parsed after every chunk
+        let txt = format!("<tag:a> <tag:b> \"{}\" .\n", "x".repeat(200_000));
+        let (quads, errors) = parse_async(Syntax::NTriples, &txt, 1)?;
+        assert_eq!(quads.len(), 1);
+        assert!(errors.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use std::io;
+
+/// A position in a parsed document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// The number of bytes before this position
+    pub offset: usize,
+    /// The line of this position (starting at 1)
+    pub line: usize,
+    /// The column of this position, in characters (starting at 1)
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the start of a document.
+    pub const START: Position = Position {
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {})",
+            self.line, self.column, self.offset
+        )
+    }
+}
+
+/// An error raised by the parsers of this crate.
+#[derive(Debug, thiserror::Error)]
+pub enum TurtleError {
+    /// An IO error occurred while reading the document
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    /// The document is not syntactically valid
+    #[error("syntax error at {position}: {message}")]
+    Syntax {
+        /// A description of the error
+        message: String,
+        /// Where the error occurred
+        position: Position,
+    },
+}
+
+impl TurtleError {
+    /// The position where this error occurred, if it is a syntax error.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            TurtleError::Io(_) => None,
+            TurtleError::Syntax { position, .. } => Some(*position),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::_error::TurtleError;
+use super::TurtleQuadSource;
+use super::TurtleTripleSource;
+use sophia_api::parser::{QuadParser, TripleParser};
+use std::io::BufRead;
+use std::sync::mpsc::Sender;
+
+#[cfg(feature = "async")]
+use super::{AsyncTurtleQuadSource, AsyncTurtleTripleSource};
+#[cfg(feature = "async")]
+use sophia_api::parser::{AsyncQuadParser, AsyncTripleParser};
+
+/// A parser of this crate, made lenient with its `with_lenient` method.
+///
+/// Instead of aborting at the first syntax error,
+/// it skips the malformed statement and sends the error to a channel.
+#[derive(Clone, Debug)]
+pub struct LenientParser<P> {
+    parser: P,
+    errors: Sender<TurtleError>,
+}
+
+impl<P> LenientParser<P> {
+    pub(crate) fn new(parser: P, errors: Sender<TurtleError>) -> Self {
+        LenientParser { parser, errors }
+    }
+
+    /// The underlying (strict) parser.
+    pub fn inner(&self) -> &P {
+        &self.parser
+    }
+}
+
+impl<B, P> TripleParser<B> for LenientParser<P>
+where
+    B: BufRead,
+    P: TripleParser<B, Source = TurtleTripleSource<B>>,
+{
+    type Source = TurtleTripleSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        let mut source = self.parser.parse(data);
+        source.0.set_lenient(self.errors.clone());
+        source
+    }
+}
+
+impl<B, P> QuadParser<B> for LenientParser<P>
+where
+    B: BufRead,
+    P: QuadParser<B, Source = TurtleQuadSource<B>>,
+{
+    type Source = TurtleQuadSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        let mut source = self.parser.parse(data);
+        source.0.set_lenient(self.errors.clone());
+        source
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R, P> AsyncTripleParser<R> for LenientParser<P>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    P: AsyncTripleParser<R, Source = AsyncTurtleTripleSource<R>>,
+{
+    type Source = AsyncTurtleTripleSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        let mut source = self.parser.parse_async(data);
+        source.parser.set_lenient(self.errors.clone());
+        source
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R, P> AsyncQuadParser<R> for LenientParser<P>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    P: AsyncQuadParser<R, Source = AsyncTurtleQuadSource<R>>,
+{
+    type Source = AsyncTurtleQuadSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        let mut source = self.parser.parse_async(data);
+        source.parser.set_lenient(self.errors.clone());
+        source
+    }
+}
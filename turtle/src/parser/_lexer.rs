@@ -0,0 +1,806 @@
+//! Streaming tokenizer for the Turtle family of syntaxes.
+use super::_error::{Position, TurtleError};
+use std::fmt;
+use std::io::{self, BufRead};
+
+pub(crate) type Result<T> = std::result::Result<T, TurtleError>;
+
+/// A token of the Turtle family of syntaxes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// `<...>`, with escape sequences decoded
+    IriRef(String),
+    /// `prefix:local`, with escape sequences decoded in the local part
+    PName(String, String),
+    /// `_:label`
+    BNode(String),
+    /// `?name` or `$name`
+    Var(String),
+    /// Any form of quoted string, with escape sequences decoded
+    String(String),
+    /// `@tag`, also used for the `@prefix`, `@base` and `@version` directives
+    LangTag(String),
+    Integer(String),
+    Decimal(String),
+    Double(String),
+    /// A bare word, such as `a`, `true` or `PREFIX`
+    Word(String),
+    Punct(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::IriRef(iri) => write!(f, "<{iri}>"),
+            Token::PName(prefix, local) => write!(f, "{prefix}:{local}"),
+            Token::BNode(label) => write!(f, "_:{label}"),
+            Token::Var(name) => write!(f, "?{name}"),
+            Token::String(txt) => write!(f, "{txt:?}"),
+            Token::LangTag(tag) => write!(f, "@{tag}"),
+            Token::Integer(txt) | Token::Decimal(txt) | Token::Double(txt) => f.write_str(txt),
+            Token::Word(word) => f.write_str(word),
+            Token::Punct(punct) => write!(f, "'{punct}'"),
+            Token::Eof => f.write_str("end of file"),
+        }
+    }
+}
+
+/// Tokenizer reading from a [`BufRead`], keeping track of the current [`Position`].
+pub(crate) struct Lexer<B> {
+    read: B,
+    /// Bytes read from `read` but not consumed yet, starting at `start`
+    buf: Vec<u8>,
+    start: usize,
+    eof: bool,
+    pos: Position,
+    /// The value of `start` at the last [checkpoint](Lexer::checkpoint),
+    /// before which bytes may be discarded from `buf`
+    keep: usize,
+    kept_pos: Position,
+    /// Where the last token started, if reading it failed,
+    /// with the delimiter closing it if it is a string or an IRI
+    failed: Option<(Position, &'static [u8])>,
+    kept_failed: Option<(Position, &'static [u8])>,
+    /// Only accept the tokens of N-Triples and N-Quads
+    ntriples: bool,
+}
+
+impl<B: BufRead> Lexer<B> {
+    pub fn new(read: B, ntriples: bool) -> Self {
+        Lexer {
+            read,
+            buf: vec![],
+            start: 0,
+            eof: false,
+            pos: Position::START,
+            keep: 0,
+            kept_pos: Position::START,
+            failed: None,
+            kept_failed: None,
+            ntriples,
+        }
+    }
+
+    /// The underlying reader.
+    #[cfg(feature = "async")]
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.read
+    }
+
+    /// Record the current position, so that the lexer can [roll back](Lexer::rollback) to it.
+    pub fn checkpoint(&mut self) {
+        self.keep = self.start;
+        self.kept_pos = self.pos;
+        self.kept_failed = self.failed;
+    }
+
+    /// Go back to the last [checkpoint](Lexer::checkpoint),
+    /// so that the following tokens are read again.
+    #[cfg(feature = "async")]
+    pub fn rollback(&mut self) {
+        self.start = self.keep;
+        self.pos = self.kept_pos;
+        self.failed = self.kept_failed;
+    }
+
+    /// Build a syntax error at the current position.
+    pub fn err<T>(&self, message: impl Into<String>) -> Result<T> {
+        Err(TurtleError::Syntax {
+            message: message.into(),
+            position: self.pos,
+        })
+    }
+
+    /// Read the next token, and return it with its starting position.
+    pub fn next_token(&mut self) -> Result<(Token, Position)> {
+        self.failed = Some((self.pos, b""));
+        if self.pos.offset == 0 && self.peek_char()? == Some('\u{feff}') {
+            self.bump('\u{feff}');
+        }
+        self.skip_whitespace()?;
+        let pos = self.pos;
+        self.failed = Some((pos, b""));
+        let Some(c) = self.peek_char()? else {
+            self.failed = None;
+            return Ok((Token::Eof, pos));
+        };
+        let token = match c {
+            '<' => match (self.peek_byte(1)?, self.peek_byte(2)?) {
+                (Some(b'<'), Some(b'(')) => self.punct("<<("),
+                (Some(b'<'), _) => self.punct("<<"),
+                _ => self.iriref()?,
+            },
+            '>' if self.peek_byte(1)? == Some(b'>') => self.punct(">>"),
+            ')' if self.peek_byte(1)? == Some(b'>') && self.peek_byte(2)? == Some(b'>') => {
+                self.punct(")>>")
+            }
+            '{' if self.peek_byte(1)? == Some(b'|') => self.punct("{|"),
+            '|' if self.peek_byte(1)? == Some(b'}') => self.punct("|}"),
+            '^' if self.peek_byte(1)? == Some(b'^') => self.punct("^^"),
+            '.' if !self.ntriples && self.peek_byte(1)?.is_some_and(|b| b.is_ascii_digit()) => {
+                self.number()?
+            }
+            '.' => self.punct("."),
+            ',' => self.punct(","),
+            ';' => self.punct(";"),
+            '[' => self.punct("["),
+            ']' => self.punct("]"),
+            '(' => self.punct("("),
+            ')' => self.punct(")"),
+            '{' => self.punct("{"),
+            '}' => self.punct("}"),
+            '~' => self.punct("~"),
+            '"' | '\'' => self.string(c)?,
+            '_' if self.peek_byte(1)? == Some(b':') => self.bnode()?,
+            '?' | '$' => self.var(c)?,
+            '@' => self.lang_tag()?,
+            '0'..='9' | '+' | '-' => self.number()?,
+            ':' => self.name()?,
+            c if is_pn_chars_base(c) => self.name()?,
+            c => return self.err(format!("unexpected character {c:?}")),
+        };
+        self.failed = None;
+        if self.ntriples && !is_ntriples_token(&token) {
+            return Err(TurtleError::Syntax {
+                message: format!("unexpected {token} in N-Triples or N-Quads"),
+                position: pos,
+            });
+        }
+        Ok((token, pos))
+    }
+
+    /// Skip the rest of the current line (including the line feed), even if it is not valid UTF-8.
+    ///
+    /// This is used to resume after an error in the middle of a token.
+    pub fn skip_line(&mut self) -> Result<()> {
+        self.failed = None;
+        while let Some(b) = self.peek_byte(0)? {
+            self.skip_byte(b);
+            if b == b'\n' {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip what remains of the token whose reading failed, even if it is not valid UTF-8.
+    ///
+    /// A string or an IRI is skipped up to its closing delimiter,
+    /// or up to the end of the line if it can not span several lines.
+    /// Any other token is skipped up to where the error was detected,
+    /// or by one character if the error was detected at its start.
+    /// This is used to resume tokenizing after an error in the middle of a token.
+    pub fn skip_failed_token(&mut self) -> Result<()> {
+        let Some((start, closing)) = self.failed.take() else {
+            return Ok(());
+        };
+        if closing.is_empty() {
+            if self.pos == start {
+                if let Some(b) = self.peek_byte(0)? {
+                    self.skip_byte(b);
+                    while let Some(b) = self.peek_byte(0)?.filter(|b| b & 0xC0 == 0x80) {
+                        self.skip_byte(b);
+                    }
+                }
+            }
+            return Ok(());
+        }
+        while let Some(b) = self.peek_byte(0)? {
+            if b == b'\n' && closing.len() == 1 {
+                break;
+            }
+            if self.at(closing)? {
+                for &b in closing {
+                    self.skip_byte(b);
+                }
+                break;
+            }
+            self.skip_byte(b);
+            if b == b'\\' {
+                if let Some(b) = self.peek_byte(0)?.filter(|b| *b != b'\n') {
+                    self.skip_byte(b);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the next bytes are `bytes`.
+    fn at(&mut self, bytes: &[u8]) -> Result<bool> {
+        self.fill(bytes.len())?;
+        Ok(self.buf[self.start..].starts_with(bytes))
+    }
+
+    /// Consume byte `b`, which must be the next one, even if it is not valid UTF-8.
+    fn skip_byte(&mut self, b: u8) {
+        if b == b'\n' {
+            self.bump('\n');
+            return;
+        }
+        self.start += 1;
+        self.pos.offset += 1;
+        if b & 0xC0 != 0x80 {
+            self.pos.column += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<()> {
+        while let Some(c) = self.peek_char()? {
+            match c {
+                ' ' | '\t' | '\n' | '\r' => self.bump(c),
+                '#' => {
+                    while let Some(c) = self.peek_char()? {
+                        if c == '\n' || c == '\r' {
+                            break;
+                        }
+                        self.bump(c);
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn punct(&mut self, punct: &'static str) -> Token {
+        for c in punct.chars() {
+            self.bump(c);
+        }
+        Token::Punct(punct)
+    }
+
+    fn iriref(&mut self) -> Result<Token> {
+        self.failed = Some((self.pos, b">"));
+        self.bump('<');
+        let mut iri = String::new();
+        loop {
+            match self.peek_char()? {
+                None => return self.err("unterminated IRI"),
+                Some('>') => {
+                    self.bump('>');
+                    return Ok(Token::IriRef(iri));
+                }
+                Some('\\') => {
+                    self.bump('\\');
+                    match self.peek_char()? {
+                        Some(c @ ('u' | 'U')) => iri.push(self.uchar(c)?),
+                        _ => return self.err("invalid escape sequence in IRI"),
+                    }
+                }
+                Some(c) if c <= ' ' || "<\"{}|^`".contains(c) => {
+                    return self.err(format!("invalid character {c:?} in IRI"));
+                }
+                Some(c) => {
+                    self.bump(c);
+                    iri.push(c);
+                }
+            }
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Token> {
+        let q = quote as u8;
+        let long = self.peek_byte(1)? == Some(q) && self.peek_byte(2)? == Some(q);
+        if self.ntriples && (long || quote == '\'') {
+            return self.err("only double-quoted strings are allowed in N-Triples and N-Quads");
+        }
+        let closing: &'static [u8] = match (quote, long) {
+            ('"', true) => b"\"\"\"",
+            ('"', false) => b"\"",
+            (_, true) => b"'''",
+            (_, false) => b"'",
+        };
+        self.failed = Some((self.pos, closing));
+        let delimiter_len = closing.len();
+        for _ in 0..delimiter_len {
+            self.bump(quote);
+        }
+        let mut txt = String::new();
+        loop {
+            match self.peek_char()? {
+                None => return self.err("unterminated string"),
+                Some(c) if c == quote => {
+                    if !long {
+                        self.bump(c);
+                        break;
+                    }
+                    if self.peek_byte(1)? == Some(q) && self.peek_byte(2)? == Some(q) {
+                        for _ in 0..3 {
+                            self.bump(c);
+                        }
+                        break;
+                    }
+                    self.bump(c);
+                    txt.push(c);
+                }
+                Some('\\') => {
+                    self.bump('\\');
+                    let Some(c) = self.peek_char()? else {
+                        return self.err("unterminated string");
+                    };
+                    let unescaped = match c {
+                        'u' | 'U' => {
+                            txt.push(self.uchar(c)?);
+                            continue;
+                        }
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        'f' => '\u{c}',
+                        '"' | '\'' | '\\' => c,
+                        _ => return self.err(format!("invalid escape sequence \\{c}")),
+                    };
+                    self.bump(c);
+                    txt.push(unescaped);
+                }
+                Some(c @ ('\n' | '\r')) if !long => {
+                    return self.err(format!("invalid character {c:?} in string"));
+                }
+                Some(c) => {
+                    self.bump(c);
+                    txt.push(c);
+                }
+            }
+        }
+        Ok(Token::String(txt))
+    }
+
+    /// Decode a `\u` or `\U` escape sequence, the backslash being already consumed.
+    fn uchar(&mut self, u: char) -> Result<char> {
+        self.bump(u);
+        let len = if u == 'u' { 4 } else { 8 };
+        let mut code = 0;
+        for _ in 0..len {
+            match self
+                .peek_char()?
+                .and_then(|c| c.to_digit(16).map(|d| (c, d)))
+            {
+                Some((c, d)) => {
+                    self.bump(c);
+                    code = code * 16 + d;
+                }
+                None => return self.err("invalid hexadecimal digit in escape sequence"),
+            }
+        }
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.err(format!("invalid code point U+{code:X}")),
+        }
+    }
+
+    fn bnode(&mut self) -> Result<Token> {
+        self.bump('_');
+        self.bump(':');
+        let mut label = String::new();
+        match self.peek_char()? {
+            Some(c) if is_pn_chars_u(c) || c.is_ascii_digit() => {
+                self.bump(c);
+                label.push(c);
+            }
+            _ => return self.err("invalid blank node label"),
+        }
+        self.name_tail(&mut label, is_pn_chars)?;
+        Ok(Token::BNode(label))
+    }
+
+    fn var(&mut self, sigil: char) -> Result<Token> {
+        self.bump(sigil);
+        let mut name = String::new();
+        while let Some(c) = self.peek_char()? {
+            if !is_pn_chars(c) || c == '-' {
+                break;
+            }
+            self.bump(c);
+            name.push(c);
+        }
+        if name.is_empty() {
+            return self.err("invalid variable name");
+        }
+        Ok(Token::Var(name))
+    }
+
+    fn lang_tag(&mut self) -> Result<Token> {
+        self.bump('@');
+        let mut tag = String::new();
+        while let Some(c) = self.peek_char()? {
+            if !(c.is_ascii_alphanumeric() || c == '-') {
+                break;
+            }
+            self.bump(c);
+            tag.push(c);
+        }
+        if !tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return self.err("invalid language tag");
+        }
+        Ok(Token::LangTag(tag))
+    }
+
+    fn number(&mut self) -> Result<Token> {
+        let mut txt = String::new();
+        if let Some(c @ ('+' | '-')) = self.peek_char()? {
+            self.bump(c);
+            txt.push(c);
+        }
+        let mut digits = self.digits(&mut txt)?;
+        let mut decimal = false;
+        if self.peek_byte(0)? == Some(b'.') {
+            let next = self.peek_byte(1)?;
+            if next.is_some_and(|b| b.is_ascii_digit()) {
+                self.bump('.');
+                txt.push('.');
+                digits += self.digits(&mut txt)?;
+                decimal = true;
+            } else if digits > 0 && matches!(next, Some(b'e' | b'E')) && self.exponent_at(2)? {
+                self.bump('.');
+                txt.push('.');
+            }
+        }
+        if digits == 0 {
+            return self.err("invalid number");
+        }
+        if matches!(self.peek_byte(0)?, Some(b'e' | b'E')) && self.exponent_at(1)? {
+            for _ in 0..2 {
+                let c = self.peek_char()?.unwrap();
+                if c.is_ascii_digit() {
+                    break;
+                }
+                self.bump(c);
+                txt.push(c);
+            }
+            self.digits(&mut txt)?;
+            return Ok(Token::Double(txt));
+        }
+        Ok(if decimal {
+            Token::Decimal(txt)
+        } else {
+            Token::Integer(txt)
+        })
+    }
+
+    /// Whether the bytes at `offset` are the rest of an exponent (after the `e`).
+    fn exponent_at(&mut self, offset: usize) -> Result<bool> {
+        Ok(match self.peek_byte(offset)? {
+            Some(b'+' | b'-') => self
+                .peek_byte(offset + 1)?
+                .is_some_and(|b| b.is_ascii_digit()),
+            b => b.is_some_and(|b| b.is_ascii_digit()),
+        })
+    }
+
+    fn digits(&mut self, txt: &mut String) -> Result<usize> {
+        let mut count = 0;
+        while let Some(c) = self.peek_char()? {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            self.bump(c);
+            txt.push(c);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Read a bare word or a prefixed name.
+    fn name(&mut self) -> Result<Token> {
+        let mut prefix = String::new();
+        if let Some(c) = self.peek_char()?.filter(|c| is_pn_chars_base(*c)) {
+            self.bump(c);
+            prefix.push(c);
+            self.name_tail(&mut prefix, is_pn_chars)?;
+        }
+        if self.peek_char()? != Some(':') {
+            return Ok(Token::Word(prefix));
+        }
+        self.bump(':');
+        let mut local = String::new();
+        match self.peek_char()? {
+            Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => {
+                self.bump(c);
+                local.push(c);
+            }
+            Some('%' | '\\') => self.plx(&mut local)?,
+            _ => return Ok(Token::PName(prefix, local)),
+        }
+        self.name_tail(&mut local, |c| {
+            is_pn_chars(c) || c == ':' || c == '%' || c == '\\'
+        })?;
+        Ok(Token::PName(prefix, local))
+    }
+
+    /// Read the characters of a name accepted by `accept`,
+    /// or dots, provided that they are followed by a character accepted by `accept`.
+    fn name_tail(&mut self, txt: &mut String, accept: impl Fn(char) -> bool) -> Result<()> {
+        loop {
+            match self.peek_char()? {
+                Some('.') => {
+                    let mut dots = 1;
+                    while self.peek_byte(dots)? == Some(b'.') {
+                        dots += 1;
+                    }
+                    if !self.peek_char_at(dots)?.is_some_and(&accept) {
+                        return Ok(());
+                    }
+                    for _ in 0..dots {
+                        self.bump('.');
+                        txt.push('.');
+                    }
+                }
+                Some('%' | '\\') if accept('%') => self.plx(txt)?,
+                Some(c) if accept(c) => {
+                    self.bump(c);
+                    txt.push(c);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Read a percent-encoded character (kept as is) or an escaped character (unescaped).
+    fn plx(&mut self, txt: &mut String) -> Result<()> {
+        match self.peek_char()? {
+            Some('%') => {
+                self.bump('%');
+                txt.push('%');
+                for _ in 0..2 {
+                    match self.peek_char()? {
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            self.bump(c);
+                            txt.push(c);
+                        }
+                        _ => return self.err("invalid percent-encoding in prefixed name"),
+                    }
+                }
+            }
+            _ => {
+                self.bump('\\');
+                match self.peek_char()? {
+                    Some(c) if "_~.-!$&'()*+,;=/?#@%".contains(c) => {
+                        self.bump(c);
+                        txt.push(c);
+                    }
+                    _ => return self.err("invalid escape sequence in prefixed name"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Consume character `c`, which must be the next one.
+    fn bump(&mut self, c: char) {
+        let len = c.len_utf8();
+        self.start += len;
+        self.pos.offset += len;
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else {
+            self.pos.column += 1;
+        }
+    }
+
+    fn peek_byte(&mut self, offset: usize) -> Result<Option<u8>> {
+        self.fill(offset + 1)?;
+        Ok(self.buf.get(self.start + offset).copied())
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>> {
+        self.peek_char_at(0)
+    }
+
+    /// Decode the character starting `offset` bytes after the current position.
+    fn peek_char_at(&mut self, offset: usize) -> Result<Option<char>> {
+        let Some(b) = self.peek_byte(offset)? else {
+            return Ok(None);
+        };
+        let len = match b {
+            0x00..=0x7F => return Ok(Some(b as char)),
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return self.err("invalid UTF-8"),
+        };
+        self.fill(offset + len)?;
+        let from = self.start + offset;
+        match self.buf.get(from..from + len).map(std::str::from_utf8) {
+            Some(Ok(s)) => Ok(s.chars().next()),
+            _ => self.err("invalid UTF-8"),
+        }
+    }
+
+    /// Make sure that at least `n` unconsumed bytes are buffered, unless the end of file is reached.
+    fn fill(&mut self, n: usize) -> Result<()> {
+        while self.buf.len() - self.start < n && !self.eof {
+            if self.keep > 0 {
+                self.buf.drain(..self.keep);
+                self.start -= self.keep;
+                self.keep = 0;
+            }
+            let chunk = match self.read.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if chunk.is_empty() {
+                self.eof = true;
+            } else {
+                let len = chunk.len();
+                self.buf.extend_from_slice(chunk);
+                self.read.consume(len);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_ntriples_token(token: &Token) -> bool {
+    match token {
+        Token::IriRef(_)
+        | Token::BNode(_)
+        | Token::Var(_)
+        | Token::String(_)
+        | Token::LangTag(_)
+        | Token::Eof => true,
+        Token::Punct(punct) => ["<<(", ")>>", "^^", "."].contains(punct),
+        _ => false,
+    }
+}
+
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}'
+        | '\u{00D8}'..='\u{00F6}'
+        | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}'
+        | '\u{037F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn is_pn_chars_u(c: char) -> bool {
+    c == '_' || is_pn_chars_base(c)
+}
+
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || matches!(c,
+            '-'
+            | '0'..='9'
+            | '\u{00B7}'
+            | '\u{0300}'..='\u{036F}'
+            | '\u{203F}'..='\u{2040}'
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tokens(txt: &str) -> Result<Vec<Token>> {
+        let mut lexer = Lexer::new(txt.as_bytes(), false);
+        let mut tokens = vec![];
+        loop {
+            match lexer.next_token()?.0 {
+                Token::Eof => return Ok(tokens),
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_and_names() -> Result<()> {
+        use Token::*;
+        assert_eq!(
+            tokens("1 -2.5 .5 1.e3 4E-2 5. ex:a.b. :c\\.d%20 a true")?,
+            vec![
+                Integer("1".into()),
+                Decimal("-2.5".into()),
+                Decimal(".5".into()),
+                Double("1.e3".into()),
+                Double("4E-2".into()),
+                Integer("5".into()),
+                Punct("."),
+                PName("ex".into(), "a.b".into()),
+                Punct("."),
+                PName("".into(), "c.d%20".into()),
+                Word("a".into()),
+                Word("true".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn strings_and_iris() -> Result<()> {
+        use Token::*;
+        assert_eq!(
+            tokens(r#"<aé> "a\"b" 'c' """d""e""" '''f'''@en-GB ^^ _:b.1 ?x"#)?,
+            vec![
+                IriRef("aé".into()),
+                String("a\"b".into()),
+                String("c".into()),
+                String("d\"\"e".into()),
+                String("f".into()),
+                LangTag("en-GB".into()),
+                Punct("^^"),
+                BNode("b.1".into()),
+                Var("x".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn triple_terms() -> Result<()> {
+        use Token::*;
+        assert_eq!(
+            tokens("<<( <<:a>> )>> {| |} ~")?,
+            vec![
+                Punct("<<("),
+                Punct("<<"),
+                PName("".into(), "a".into()),
+                Punct(">>"),
+                Punct(")>>"),
+                Punct("{|"),
+                Punct("|}"),
+                Punct("~"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn positions() {
+        let mut lexer = Lexer::new("# comment\n  <a> 'é' .".as_bytes(), false);
+        let expected = [(2, 3, 12), (2, 7, 16), (2, 11, 21)];
+        for (line, column, offset) in expected {
+            let (_, pos) = lexer.next_token().unwrap();
+            assert_eq!(
+                pos,
+                Position {
+                    offset,
+                    line,
+                    column
+                }
+            );
+        }
+        let err = tokens("<a>\n <b c>").unwrap_err();
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                offset: 7,
+                line: 2,
+                column: 4
+            })
+        );
+    }
+}
@@ -0,0 +1,1016 @@
+//! Parser for the Turtle family of syntaxes, producing quads of [`SimpleTerm`]s.
+use super::_error::{Position, TurtleError};
+use super::_lexer::{Lexer, Result, Token};
+use sophia_api::prefix::{Prefix, PrefixMapPair};
+use sophia_api::quad::Spog;
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, SimpleTerm, Term, TermKind, VarName};
+use sophia_api::MownStr;
+use sophia_iri::resolve::BaseIri;
+use sophia_iri::Iri;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+use std::sync::mpsc::Sender;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+const RDF_NIL: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#nil";
+const RDF_REIFIES: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+
+/// The prefix of the labels of the blank nodes generated by the parser.
+///
+/// Labels starting with this prefix in the document are replaced by generated ones,
+/// to avoid any clash.
+const GENERATED: &str = "_g";
+
+/// The concrete syntaxes supported by [`Parser`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Syntax {
+    NTriples,
+    NQuads,
+    Turtle,
+    TriG,
+}
+
+/// How a term was written, which constrains where it can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    /// An IRI, a labelled blank node, a literal, a variable or a triple term
+    Simple,
+    /// `[]`
+    Anon,
+    /// `[ ... ]`
+    PropertyList,
+    /// `( ... )`
+    Collection,
+    /// `<< ... >>`
+    Reified,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Shape::Simple => "term",
+            Shape::Anon => "anonymous blank node",
+            Shape::PropertyList => "blank node property list",
+            Shape::Collection => "collection",
+            Shape::Reified => "reified triple",
+        })
+    }
+}
+
+/// The positions where a term can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Subject,
+    Predicate,
+    Object,
+    GraphName,
+    Reifier,
+    /// The subject of a triple term
+    TtSubject,
+    /// The object of a triple term
+    TtObject,
+    /// The subject of a reified triple
+    RtSubject,
+    /// The object of a reified triple
+    RtObject,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::Subject => "subject",
+            Role::Predicate => "predicate",
+            Role::Object => "object",
+            Role::GraphName => "graph name",
+            Role::Reifier => "reifier",
+            Role::TtSubject => "subject of a triple term",
+            Role::TtObject => "object of a triple term",
+            Role::RtSubject => "subject of a reified triple",
+            Role::RtObject => "object of a reified triple",
+        })
+    }
+}
+
+/// A streaming parser for the Turtle family of syntaxes.
+///
+/// Each call to [`parse_statement`](Parser::parse_statement)
+/// parses one statement and stores the quads it produces in [`quads`](Parser::quads).
+pub(crate) struct Parser<B> {
+    lexer: Lexer<B>,
+    peeked: Option<(Token, Position)>,
+    syntax: Syntax,
+    /// Whether terms of any kind are allowed in any position (including variables)
+    generalized: bool,
+    base: Option<BaseIri<String>>,
+    prefixes: HashMap<String, String>,
+    /// The prefixes declared so far with an absolute namespace, in declaration order
+    prefix_map: Vec<PrefixMapPair>,
+    /// Generated labels replacing the labels of the document starting with [`GENERATED`]
+    bnode_labels: HashMap<String, String>,
+    bnode_count: usize,
+    graph: Option<SimpleTerm<'static>>,
+    /// Whether the parser is inside a `{ ... }` block (in TriG)
+    in_block: bool,
+    /// Where syntax errors are sent in lenient mode
+    lenient: Option<Sender<TurtleError>>,
+    /// The position of the first token of the current statement
+    statement_start: Position,
+    /// Whether the lexer failed in the middle of a token
+    mid_token: bool,
+    /// Whether the parser is skipping a malformed statement, in lenient mode
+    recovering: bool,
+    /// The values of `peeked` and `mid_token` at the last checkpoint
+    saved: (Option<(Token, Position)>, bool),
+    /// The quads produced by the last parsed statement
+    pub quads: Vec<Spog<SimpleTerm<'static>>>,
+}
+
+impl<B: BufRead> Parser<B> {
+    pub fn new(read: B, syntax: Syntax, generalized: bool, base: Option<Iri<String>>) -> Self {
+        let ntriples = matches!(syntax, Syntax::NTriples | Syntax::NQuads);
+        Parser {
+            lexer: Lexer::new(read, ntriples),
+            peeked: None,
+            syntax,
+            generalized,
+            base: base.map(|iri| BaseIri::new(iri.unwrap()).unwrap()),
+            prefixes: HashMap::new(),
+            prefix_map: vec![],
+            bnode_labels: HashMap::new(),
+            bnode_count: 0,
+            graph: None,
+            in_block: false,
+            lenient: None,
+            statement_start: Position::START,
+            mid_token: false,
+            recovering: false,
+            saved: (None, false),
+            quads: vec![],
+        }
+    }
+
+    /// Make this parser lenient, sending syntax errors to `errors` instead of returning them.
+    pub fn set_lenient(&mut self, errors: Sender<TurtleError>) {
+        self.lenient = Some(errors);
+    }
+
+    /// Parse the next statement, and store the quads it produces in [`quads`](Parser::quads).
+    ///
+    /// Return `false` if the end of the document was reached.
+    ///
+    /// In lenient mode, a statement containing a syntax error produces no quad:
+    /// the error is sent to the [`lenient`](Parser::lenient) channel,
+    /// and the parser skips to the next statement.
+    ///
+    /// If an IO error occurs, the parser can be [rolled back](Parser::rollback)
+    /// to the state it had before this call, so that the statement can be parsed again.
+    pub fn parse_statement(&mut self) -> Result<bool> {
+        self.checkpoint();
+        if self.recovering {
+            self.recover()?;
+            self.recovering = false;
+            return Ok(true);
+        }
+        let res = match self.syntax {
+            Syntax::NTriples | Syntax::NQuads => self.nt_statement(),
+            Syntax::Turtle | Syntax::TriG => self.turtle_statement(),
+        };
+        match res {
+            Err(err @ TurtleError::Syntax { .. }) if self.lenient.is_some() => {
+                self.quads.clear();
+                // a closed receiver just means that nobody cares about the errors
+                let _ = self.lenient.as_ref().unwrap().send(err);
+                self.recovering = true;
+                self.checkpoint();
+                self.recover()?;
+                self.recovering = false;
+                Ok(true)
+            }
+            res => res,
+        }
+    }
+
+    /// Go back to the state of the parser at the start of the last call to
+    /// [`parse_statement`](Parser::parse_statement)
+    /// (or at the start of the recovery, if it was interrupted while skipping a malformed statement).
+    ///
+    /// This is used to parse a statement again after the reader failed with [`WouldBlock`],
+    /// i.e. when the whole statement was not available yet.
+    ///
+    /// [`WouldBlock`]: std::io::ErrorKind::WouldBlock
+    #[cfg(feature = "async")]
+    pub fn rollback(&mut self) {
+        self.lexer.rollback();
+        (self.peeked, self.mid_token) = self.saved.clone();
+        self.quads.clear();
+    }
+
+    fn checkpoint(&mut self) {
+        self.lexer.checkpoint();
+        self.saved = (self.peeked.clone(), self.mid_token);
+    }
+
+    /// The underlying reader.
+    #[cfg(feature = "async")]
+    pub fn get_mut(&mut self) -> &mut B {
+        self.lexer.get_mut()
+    }
+
+    /// Skip tokens up to the start of the next statement, after a syntax error.
+    ///
+    /// In N-Triples and N-Quads, the next statement starts on the next line;
+    /// in Turtle and TriG, it starts after the next `.`
+    /// (or after the next `}` if the error occurred in a TriG block),
+    /// tokenizing being resumed right after a token that could not be read.
+    fn recover(&mut self) -> Result<()> {
+        let ntriples = self.is_ntriples();
+        loop {
+            if self.mid_token {
+                self.mid_token = false;
+                if ntriples {
+                    return self.lexer.skip_line();
+                }
+                self.lexer.skip_failed_token()?;
+            }
+            let (token, pos) = match self.next() {
+                Ok(next) => next,
+                Err(TurtleError::Syntax { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+            match token {
+                Token::Eof => {}
+                _ if ntriples && pos.line > self.statement_start.line => {}
+                Token::Punct(".") if !ntriples => return Ok(()),
+                Token::Punct("}") if self.in_block => {
+                    self.close_block();
+                    return Ok(());
+                }
+                _ => continue,
+            }
+            self.peeked = Some((token, pos));
+            return Ok(());
+        }
+    }
+
+    fn nt_statement(&mut self) -> Result<bool> {
+        self.statement_start = self.peek_pos()?;
+        if self.peek()? == &Token::Eof {
+            return Ok(false);
+        }
+        let s = self.checked_term(Role::Subject)?;
+        let p = self.checked_term(Role::Predicate)?;
+        let o = self.checked_term(Role::Object)?;
+        let g = if self.syntax == Syntax::NQuads && self.peek()? != &Token::Punct(".") {
+            Some(self.checked_term(Role::GraphName)?)
+        } else {
+            None
+        };
+        self.expect(".")?;
+        self.quads.push(([s, p, o], g));
+        Ok(true)
+    }
+
+    fn turtle_statement(&mut self) -> Result<bool> {
+        if self.in_block {
+            return self.block_statement();
+        }
+        let (token, pos) = self.next()?;
+        self.statement_start = pos;
+        match token {
+            Token::Eof => return Ok(false),
+            Token::LangTag(directive) if is_directive(&directive) => {
+                self.directive(&directive, true)?;
+            }
+            Token::Word(directive) if is_directive(&directive.to_ascii_lowercase()) => {
+                self.directive(&directive.to_ascii_lowercase(), false)?;
+            }
+            Token::Word(word)
+                if self.syntax == Syntax::TriG && word.eq_ignore_ascii_case("graph") =>
+            {
+                let g = self.checked_term(Role::GraphName)?;
+                self.open_block(Some(g))?;
+            }
+            Token::Punct("{") if self.syntax == Syntax::TriG => {
+                self.peeked = Some((token, pos));
+                self.open_block(None)?;
+            }
+            _ => {
+                self.peeked = Some((token, pos));
+                let (s, shape) = self.term()?;
+                if self.syntax == Syntax::TriG && self.peek()? == &Token::Punct("{") {
+                    self.check(&s, shape, Role::GraphName, pos)?;
+                    self.open_block(Some(s))?;
+                } else {
+                    self.check(&s, shape, Role::Subject, pos)?;
+                    self.triples_tail(&s, shape)?;
+                    self.expect(".")?;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Parse the directive following its keyword, and its final `.` if `dotted`.
+    ///
+    /// The declaration only takes effect once the whole directive is parsed,
+    /// so that it is not applied twice if the parser is [rolled back](Parser::rollback).
+    fn directive(&mut self, directive: &str, dotted: bool) -> Result<()> {
+        let (token, pos) = self.next()?;
+        let mut prefix_decl = None;
+        let mut base_decl = None;
+        match (directive, token) {
+            ("prefix", Token::PName(prefix, local)) if local.is_empty() => {
+                let (token, pos) = self.next()?;
+                let Token::IriRef(iri) = token else {
+                    return self.unexpected(token, pos);
+                };
+                let SimpleTerm::Iri(ns) = self.iri_ref(iri, pos)? else {
+                    unreachable!()
+                };
+                prefix_decl = Some((prefix, ns.unwrap().to_string()));
+            }
+            ("base", Token::IriRef(iri)) => {
+                let SimpleTerm::Iri(iri) = self.iri_ref(iri, pos)? else {
+                    unreachable!()
+                };
+                match BaseIri::new(iri.unwrap().to_string()) {
+                    Ok(base) => base_decl = Some(base),
+                    Err(err) => return syntax_error(format!("invalid base IRI: {err}"), pos),
+                }
+            }
+            ("version", Token::String(_)) => {}
+            (_, token) => return self.unexpected(token, pos),
+        }
+        if dotted {
+            self.expect(".")?;
+        }
+        if let Some((prefix, ns)) = prefix_decl {
+            self.declare_prefix(&prefix, &ns);
+            self.prefixes.insert(prefix, ns);
+        }
+        if base_decl.is_some() {
+            self.base = base_decl;
+        }
+        Ok(())
+    }
+
+    /// Record the declaration of `prefix` in [`prefix_map`](Parser::prefix_map),
+    /// unless `ns` is a relative IRI reference.
+    fn declare_prefix(&mut self, prefix: &str, ns: &str) {
+        let Ok(ns) = sophia_iri::Iri::new(Box::from(ns)) else {
+            return;
+        };
+        match self
+            .prefix_map
+            .iter_mut()
+            .find(|(p, _)| p.as_str() == prefix)
+        {
+            Some(pair) => pair.1 = ns,
+            None => self
+                .prefix_map
+                .push((Prefix::new_unchecked(Box::from(prefix)), ns)),
+        }
+    }
+
+    /// The prefixes declared so far in the document (with an absolute namespace).
+    pub fn prefix_map(&self) -> &[PrefixMapPair] {
+        &self.prefix_map
+    }
+
+    /// The current base IRI, if any.
+    pub fn base(&self) -> Option<Iri<&str>> {
+        self.base
+            .as_ref()
+            .map(|base| Iri::new_unchecked(base.as_str()))
+    }
+
+    /// Parse `{`, opening the block containing the triples of graph `g`.
+    ///
+    /// The content of the block is then parsed one statement at a time
+    /// by [`block_statement`](Parser::block_statement).
+    fn open_block(&mut self, g: Option<SimpleTerm<'static>>) -> Result<()> {
+        self.expect("{")?;
+        self.graph = g;
+        self.in_block = true;
+        Ok(())
+    }
+
+    /// Parse the next statement inside a `{ ... }` block, or its closing `}`.
+    fn block_statement(&mut self) -> Result<bool> {
+        self.statement_start = self.peek_pos()?;
+        if self.eat("}")? {
+            self.close_block();
+            return Ok(true);
+        }
+        let s = self.checked_term_with_shape(Role::Subject)?;
+        self.triples_tail(&s.0, s.1)?;
+        if !self.eat(".")? {
+            self.expect("}")?;
+            self.close_block();
+        }
+        Ok(true)
+    }
+
+    fn close_block(&mut self) {
+        self.graph = None;
+        self.in_block = false;
+    }
+
+    /// Parse the predicate-object list following subject `s`
+    /// (optional if `s` is a blank node property list or a reified triple).
+    fn triples_tail(&mut self, s: &SimpleTerm<'static>, shape: Shape) -> Result<()> {
+        if matches!(shape, Shape::PropertyList | Shape::Reified)
+            && matches!(self.peek()?, Token::Punct("." | "}") | Token::Eof)
+        {
+            return Ok(());
+        }
+        self.predicate_object_list(s)
+    }
+
+    fn predicate_object_list(&mut self, s: &SimpleTerm<'static>) -> Result<()> {
+        loop {
+            let p = self.verb()?;
+            self.object_list(s, &p)?;
+            if !self.eat(";")? {
+                return Ok(());
+            }
+            while self.eat(";")? {}
+            if matches!(
+                self.peek()?,
+                Token::Punct("." | "]" | "}" | "|}") | Token::Eof
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn object_list(&mut self, s: &SimpleTerm<'static>, p: &SimpleTerm<'static>) -> Result<()> {
+        loop {
+            let o = self.checked_term(Role::Object)?;
+            self.emit(s.clone(), p.clone(), o.clone());
+            self.annotation(s, p, o)?;
+            if !self.eat(",")? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse the reifiers and annotation blocks following the object of triple `s p o`.
+    fn annotation(
+        &mut self,
+        s: &SimpleTerm<'static>,
+        p: &SimpleTerm<'static>,
+        o: SimpleTerm<'static>,
+    ) -> Result<()> {
+        let triple = SimpleTerm::Triple(Box::new([s.clone(), p.clone(), o]));
+        let mut reifier = None;
+        loop {
+            if self.eat("~")? {
+                let r = self.reifier()?;
+                self.emit(r.clone(), iri(RDF_REIFIES), triple.clone());
+                reifier = Some(r);
+            } else if self.eat("{|")? {
+                let r = match reifier.take() {
+                    Some(r) => r,
+                    None => {
+                        let r = self.fresh_bnode();
+                        self.emit(r.clone(), iri(RDF_REIFIES), triple.clone());
+                        r
+                    }
+                };
+                self.predicate_object_list(&r)?;
+                self.expect("|}")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse the optional identifier following `~`.
+    fn reifier(&mut self) -> Result<SimpleTerm<'static>> {
+        match self.peek()? {
+            Token::IriRef(_)
+            | Token::PName(..)
+            | Token::BNode(_)
+            | Token::Var(_)
+            | Token::Punct("[") => self.checked_term(Role::Reifier),
+            _ => Ok(self.fresh_bnode()),
+        }
+    }
+
+    fn verb(&mut self) -> Result<SimpleTerm<'static>> {
+        if let Token::Word(word) = self.peek()? {
+            if word == "a" {
+                self.next()?;
+                return Ok(iri(RDF_TYPE));
+            }
+        }
+        self.checked_term(Role::Predicate)
+    }
+
+    fn checked_term(&mut self, role: Role) -> Result<SimpleTerm<'static>> {
+        self.checked_term_with_shape(role).map(|(t, _)| t)
+    }
+
+    fn checked_term_with_shape(&mut self, role: Role) -> Result<(SimpleTerm<'static>, Shape)> {
+        let pos = self.peek_pos()?;
+        let (t, shape) = self.term()?;
+        self.check(&t, shape, role, pos)?;
+        Ok((t, shape))
+    }
+
+    /// Check that term `t`, written with the given shape, can be used in the given role.
+    fn check(&self, t: &SimpleTerm, shape: Shape, role: Role, pos: Position) -> Result<()> {
+        let shape_ok = match role {
+            Role::Subject | Role::Object => true,
+            Role::RtSubject | Role::RtObject => {
+                matches!(shape, Shape::Simple | Shape::Anon | Shape::Reified)
+            }
+            _ => matches!(shape, Shape::Simple | Shape::Anon),
+        };
+        if !shape_ok {
+            return syntax_error(format!("unexpected {shape} as {role}"), pos);
+        }
+        let kind = t.kind();
+        let kind_ok = self.generalized
+            || match kind {
+                TermKind::Iri => true,
+                TermKind::BlankNode => role != Role::Predicate,
+                TermKind::Literal | TermKind::Triple => {
+                    matches!(role, Role::Object | Role::TtObject | Role::RtObject)
+                }
+                TermKind::Variable => false,
+            };
+        if !kind_ok {
+            let kind = match kind {
+                TermKind::Iri => "IRI",
+                TermKind::BlankNode => "blank node",
+                TermKind::Literal => "literal",
+                TermKind::Triple => "triple term",
+                TermKind::Variable => "variable",
+            };
+            return syntax_error(format!("unexpected {kind} as {role}"), pos);
+        }
+        Ok(())
+    }
+
+    fn term(&mut self) -> Result<(SimpleTerm<'static>, Shape)> {
+        let (token, pos) = self.next()?;
+        let t = match token {
+            Token::IriRef(iri) => self.iri_ref(iri, pos)?,
+            Token::PName(prefix, local) => self.pname(&prefix, &local, pos)?,
+            Token::BNode(label) => self.labelled_bnode(label),
+            Token::Var(name) => SimpleTerm::Variable(VarName::new_unchecked(name.into())),
+            Token::String(lex) => self.literal(lex)?,
+            Token::Integer(lex) => typed(lex, XSD_INTEGER),
+            Token::Decimal(lex) => typed(lex, XSD_DECIMAL),
+            Token::Double(lex) => typed(lex, XSD_DOUBLE),
+            Token::Word(word) if word == "true" || word == "false" => typed(word, XSD_BOOLEAN),
+            Token::Punct("[") => {
+                let b = self.fresh_bnode();
+                if self.eat("]")? {
+                    return Ok((b, Shape::Anon));
+                }
+                self.predicate_object_list(&b)?;
+                self.expect("]")?;
+                return Ok((b, Shape::PropertyList));
+            }
+            Token::Punct("(") => return Ok((self.collection()?, Shape::Collection)),
+            Token::Punct("<<(") => {
+                let s = self.checked_term(Role::TtSubject)?;
+                let p = self.verb()?;
+                let o = self.checked_term(Role::TtObject)?;
+                self.expect(")>>")?;
+                SimpleTerm::Triple(Box::new([s, p, o]))
+            }
+            Token::Punct("<<") => {
+                let s = self.checked_term(Role::RtSubject)?;
+                let p = self.verb()?;
+                let o = self.checked_term(Role::RtObject)?;
+                let r = if self.eat("~")? {
+                    self.reifier()?
+                } else {
+                    self.fresh_bnode()
+                };
+                self.expect(">>")?;
+                let triple = SimpleTerm::Triple(Box::new([s, p, o]));
+                self.emit(r.clone(), iri(RDF_REIFIES), triple);
+                return Ok((r, Shape::Reified));
+            }
+            token => return self.unexpected(token, pos),
+        };
+        Ok((t, Shape::Simple))
+    }
+
+    /// Parse the items of a collection, the opening parenthesis being already consumed.
+    fn collection(&mut self) -> Result<SimpleTerm<'static>> {
+        let mut items = vec![];
+        while !self.eat(")")? {
+            items.push(self.checked_term(Role::Object)?);
+        }
+        let mut list = iri(RDF_NIL);
+        for item in items.into_iter().rev() {
+            let node = self.fresh_bnode();
+            self.emit(node.clone(), iri(RDF_REST), list);
+            self.emit(node.clone(), iri(RDF_FIRST), item);
+            list = node;
+        }
+        Ok(list)
+    }
+
+    /// Parse the language tag or datatype (if any) following the lexical form `lex`.
+    fn literal(&mut self, lex: String) -> Result<SimpleTerm<'static>> {
+        match self.peek()? {
+            Token::LangTag(_) => {
+                let (Token::LangTag(tag), pos) = self.next()? else {
+                    unreachable!()
+                };
+                if tag.contains("--") {
+                    return syntax_error("base direction is not supported", pos);
+                }
+                match LanguageTag::new(MownStr::from(tag)) {
+                    Ok(tag) => Ok(SimpleTerm::LiteralLanguage(lex.into(), tag)),
+                    Err(err) => syntax_error(err.to_string(), pos),
+                }
+            }
+            Token::Punct("^^") => {
+                self.next()?;
+                let (token, pos) = self.next()?;
+                let datatype = match token {
+                    Token::IriRef(iri) => self.iri_ref(iri, pos)?,
+                    Token::PName(prefix, local) => self.pname(&prefix, &local, pos)?,
+                    token => return self.unexpected(token, pos),
+                };
+                let SimpleTerm::Iri(datatype) = datatype else {
+                    unreachable!()
+                };
+                Ok(SimpleTerm::LiteralDatatype(lex.into(), datatype))
+            }
+            _ => Ok(typed(lex, XSD_STRING)),
+        }
+    }
+
+    fn iri_ref(&self, iri: String, pos: Position) -> Result<SimpleTerm<'static>> {
+        let iri = match &self.base {
+            Some(base) => base
+                .resolve(iri.as_str())
+                .map(Iri::unwrap)
+                .map_err(|e| e.to_string()),
+            None => Iri::new(iri).map(Iri::unwrap).map_err(|e| e.to_string()),
+        };
+        match iri {
+            Ok(iri) => Ok(SimpleTerm::Iri(IriRef::new_unchecked(iri.into()))),
+            Err(err) => syntax_error(format!("invalid IRI: {err}"), pos),
+        }
+    }
+
+    fn pname(&self, prefix: &str, local: &str, pos: Position) -> Result<SimpleTerm<'static>> {
+        let Some(ns) = self.prefixes.get(prefix) else {
+            return syntax_error(format!("undefined prefix {prefix:?}"), pos);
+        };
+        match Iri::new(format!("{ns}{local}")) {
+            Ok(iri) => Ok(SimpleTerm::Iri(IriRef::new_unchecked(iri.unwrap().into()))),
+            Err(err) => syntax_error(format!("invalid IRI: {err}"), pos),
+        }
+    }
+
+    fn is_ntriples(&self) -> bool {
+        matches!(self.syntax, Syntax::NTriples | Syntax::NQuads)
+    }
+
+    fn labelled_bnode(&mut self, label: String) -> SimpleTerm<'static> {
+        // N-Triples and N-Quads have no anonymous blank nodes, so labels need not be replaced,
+        // which makes them independent of the rest of the document (e.g. when parsing it by chunks)
+        if self.is_ntriples() || !label.starts_with(GENERATED) {
+            return bnode(label);
+        }
+        if let Some(label) = self.bnode_labels.get(&label) {
+            return bnode(label.clone());
+        }
+        let b = self.fresh_bnode();
+        let generated = b.bnode_id().unwrap().to_string();
+        self.bnode_labels.insert(label, generated);
+        b
+    }
+
+    fn fresh_bnode(&mut self) -> SimpleTerm<'static> {
+        self.bnode_count += 1;
+        bnode(format!("{GENERATED}{}", self.bnode_count))
+    }
+
+    fn emit(&mut self, s: SimpleTerm<'static>, p: SimpleTerm<'static>, o: SimpleTerm<'static>) {
+        self.quads.push(([s, p, o], self.graph.clone()));
+    }
+
+    fn next(&mut self) -> Result<(Token, Position)> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.lex(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+
+    fn peek_pos(&mut self) -> Result<Position> {
+        self.peek()?;
+        Ok(self.peeked.as_ref().unwrap().1)
+    }
+
+    fn lex(&mut self) -> Result<(Token, Position)> {
+        let res = self.lexer.next_token();
+        self.mid_token = matches!(res, Err(TurtleError::Syntax { .. }));
+        res
+    }
+
+    /// Consume the next token if it is `punct`.
+    fn eat(&mut self, punct: &str) -> Result<bool> {
+        if matches!(self.peek()?, Token::Punct(p) if *p == punct) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        let (token, pos) = self.next()?;
+        match token {
+            Token::Punct(p) if p == punct => Ok(()),
+            token => {
+                let message = format!("expected '{punct}', found {token}");
+                self.peeked = Some((token, pos));
+                syntax_error(message, pos)
+            }
+        }
+    }
+
+    /// Build an error about `token`, which is put back so that recovery can start from it.
+    fn unexpected<T>(&mut self, token: Token, pos: Position) -> Result<T> {
+        let message = format!("unexpected {token}");
+        self.peeked = Some((token, pos));
+        syntax_error(message, pos)
+    }
+}
+
+fn syntax_error<T>(message: impl Into<String>, position: Position) -> Result<T> {
+    Err(TurtleError::Syntax {
+        message: message.into(),
+        position,
+    })
+}
+
+fn is_directive(name: &str) -> bool {
+    matches!(name, "prefix" | "base" | "version")
+}
+
+fn iri(iri: &'static str) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(iri)))
+}
+
+fn bnode(label: String) -> SimpleTerm<'static> {
+    SimpleTerm::BlankNode(BnodeId::new_unchecked(label.into()))
+}
+
+fn typed(lex: String, datatype: &'static str) -> SimpleTerm<'static> {
+    SimpleTerm::LiteralDatatype(lex.into(), IriRef::new_unchecked(MownStr::from(datatype)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::Dataset;
+    use sophia_api::ns::rdf;
+    use sophia_api::quad::Quad;
+    use sophia_api::term::matcher::Any;
+
+    fn parse(syntax: Syntax, txt: &str) -> Result<Vec<Spog<SimpleTerm<'static>>>> {
+        let mut parser = Parser::new(txt.as_bytes(), syntax, false, None);
+        let mut quads = vec![];
+        while parser.parse_statement()? {
+            quads.append(&mut parser.quads);
+        }
+        Ok(quads)
+    }
+
+    #[test]
+    fn rdf12() -> Result<()> {
+        let d = parse(
+            Syntax::Turtle,
+            r#"
+                VERSION "1.2"
+                @version '1.2' .
+                PREFIX : <tag:>
+                :a :b :c ~ :r {| :d :e |}.
+                << :a :b <<( :c :d :e )>> ~ _:r >> :f ( 1 2.0 3e0 ) .
+            "#,
+        )?;
+        assert_eq!(d.len(), 11);
+        let r = IriRef::new_unchecked("tag:r");
+        assert_eq!(d.quads_matching([r], [rdf::reifies], Any, Any).count(), 1);
+        let tt = d
+            .quads_matching(Any, [rdf::reifies], Any, Any)
+            .filter(|q| q.as_ref().unwrap().s().is_blank_node())
+            .count();
+        assert_eq!(tt, 1);
+        assert_eq!(d.quads_matching(Any, [rdf::first], Any, Any).count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn trig_graphs() -> Result<()> {
+        let d = parse(
+            Syntax::TriG,
+            r#"
+                BASE <http://example.org/>
+                <a> <b> <c> .
+                { <a> <b> <d> }
+                <g1> { <a> <b> <e> . <a> <b> <f> . }
+                GRAPH _:g2 { [ <b> <g> ] }
+                [] { <a> <b> <h> }
+            "#,
+        )?;
+        assert_eq!(d.len(), 6);
+        let defaults = d.iter().filter(|(_, g)| g.is_none()).count();
+        assert_eq!(defaults, 2);
+        let g1 = IriRef::new_unchecked("http://example.org/g1");
+        assert_eq!(d.quads_matching(Any, Any, Any, [Some(g1)]).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn generated_labels() -> Result<()> {
+        let d = parse(
+            Syntax::NTriples,
+            "_:_g1 <tag:p> _:_g1 .\n_:_g1 <tag:p> _:b .",
+        )?;
+        let labels: Vec<_> = d
+            .iter()
+            .map(|([s, _, _], _)| s.bnode_id().unwrap())
+            .collect();
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[0].as_str(), "_g1");
+        let d = parse(Syntax::Turtle, "_:_g1 <tag:p> [] .")?;
+        assert_ne!(d[0].0[0], d[0].0[2]);
+        Ok(())
+    }
+
+    fn parse_lenient(syntax: Syntax, txt: &str) -> Result<(usize, Vec<(usize, usize)>)> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut parser = Parser::new(txt.as_bytes(), syntax, false, None);
+        parser.set_lenient(tx);
+        let mut count = 0;
+        while parser.parse_statement()? {
+            count += parser.quads.drain(..).count();
+        }
+        drop(parser);
+        let positions = rx
+            .iter()
+            .map(|err| err.position().unwrap())
+            .map(|pos| (pos.line, pos.column))
+            .collect();
+        Ok((count, positions))
+    }
+
+    #[test]
+    fn lenient() -> Result<()> {
+        for (syntax, txt, count, positions) in [
+            (
+                Syntax::NTriples,
+                "<tag:a> <tag:b> <tag:c> .\n<a> <tag:b> <tag:c> .\n<tag:a> <tag:b> <tag:d> .",
+                2,
+                vec![(2, 1)],
+            ),
+            (
+                Syntax::NTriples,
+                "<tag:a> <tag:b> <tag:c>\n<tag:a> <tag:b> <tag:d> .\n<tag:a> <tag:b> \"e .",
+                1,
+                vec![(2, 1), (3, 21)],
+            ),
+            (
+                Syntax::NQuads,
+                "<tag:a> <tag:b> <tag:c> <tag:g> <tag:h> .\n<tag:a> <tag:b> <tag: c> .\n<tag:a> <tag:b> <tag:c> <tag:g> .",
+                1,
+                vec![(1, 33), (2, 22)],
+            ),
+            (
+                Syntax::Turtle,
+                "<tag:a> <tag:b> <tag:c>, x:d, <tag:e> . <tag:a> <tag:b> [ <tag:f> 1 ] .",
+                2,
+                vec![(1, 26)],
+            ),
+            (
+                Syntax::Turtle,
+                "<tag:a> <tag:b> \"unterminated\n<tag:a> <tag:b> <tag:c> . <tag:a> <tag:b> <tag:d> .",
+                1,
+                vec![(1, 30)],
+            ),
+            (
+                Syntax::Turtle,
+                "@prefix : <tag:> .\n:c :p :d, @@ , :e .\n:f :p :x .\n:z :p :z .",
+                2,
+                vec![(2, 12)],
+            ),
+            (
+                Syntax::Turtle,
+                "@prefix : <tag:> .\n:c :p :d , :e ; :q @@ .\n:a :b :c .",
+                1,
+                vec![(2, 21)],
+            ),
+            (
+                Syntax::Turtle,
+                "<tag:a> <tag:b> \"\\q . x\" . <tag:a> <tag:b> <tag:c> .",
+                1,
+                vec![(1, 19)],
+            ),
+            (
+                Syntax::Turtle,
+                "<tag:a> <tag:b> <tag:c d> . <tag:a> <tag:b> <tag:c> . <tag:a> <tag:b> ! .",
+                1,
+                vec![(1, 23), (1, 71)],
+            ),
+            (
+                Syntax::TriG,
+                "<tag:g> { <tag:a> <tag:b> 'c' . <tag:a> <tag:b> . <tag:a> <tag:b> 'd' }\n<tag:a> <tag:b> 'e' .",
+                3,
+                vec![(1, 49)],
+            ),
+            (
+                Syntax::TriG,
+                "<tag:g> { <tag:a> <tag:b> _:c:d }\n<tag:a> <tag:b> 'e' .",
+                1,
+                vec![(1, 30)],
+            ),
+        ] {
+            let res = parse_lenient(syntax, txt)?;
+            assert_eq!(res, (count, positions), "{txt}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for (syntax, txt, line, column) in [
+            (
+                Syntax::NTriples,
+                "<tag:a> <tag:b> <tag:c> .\n  <a> <tag:b> <tag:c> .",
+                2,
+                3,
+            ),
+            (
+                Syntax::NTriples,
+                "<<( <tag:a> <tag:b> <tag:c> )>> <tag:b> <tag:c> .",
+                1,
+                1,
+            ),
+            (Syntax::NTriples, "<tag:a> <tag:b> 'c' .", 1, 17),
+            (Syntax::NQuads, "<tag:a> <tag:b> <tag:c> \"g\" .", 1, 25),
+            (Syntax::Turtle, "<tag:a> <tag:b> x:c .", 1, 17),
+            (Syntax::Turtle, "\"a\" <tag:b> <tag:c> .", 1, 1),
+            (Syntax::Turtle, "<tag:a> _:b <tag:c> .", 1, 9),
+            (Syntax::Turtle, "<tag:a> <tag:b> <tag:c> ", 1, 25),
+            (Syntax::Turtle, "<tag:a> <tag:b> \"c\"@en--ltr .", 1, 20),
+            (Syntax::NTriples, "<tag:a> <tag:b> \"c\"@ar--rtl .", 1, 20),
+            (
+                Syntax::NQuads,
+                "<tag:a> <tag:b> \"c\"@en--ltr <tag:g> .",
+                1,
+                20,
+            ),
+            (
+                Syntax::TriG,
+                "<tag:g> { <tag:a> <tag:b> \"c\"@en-US--ltr }",
+                1,
+                30,
+            ),
+            (
+                Syntax::Turtle,
+                "<tag:a> <tag:b> <<( [ <tag:p> 1 ] <tag:b> 2 )>> .",
+                1,
+                21,
+            ),
+            (Syntax::Turtle, "<tag:a> <tag:b> ?c .", 1, 17),
+            (Syntax::Turtle, "<tag:a> <b> <tag:c> .", 1, 9),
+            (Syntax::Turtle, "@prefix x: <a/> . x:b x:c x:d .", 1, 12),
+            (Syntax::TriG, "<g> { <tag:a> <tag:b> <tag:c> }", 1, 1),
+            (Syntax::Turtle, "{ <tag:a> <tag:b> <tag:c> }", 1, 1),
+            (Syntax::TriG, "<tag:g> { <tag:g2> { } }", 1, 20),
+        ] {
+            let err = parse(syntax, txt).unwrap_err();
+            let pos = err.position().unwrap();
+            assert_eq!((pos.line, pos.column), (line, column), "{txt} → {err}");
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use super::_error::TurtleError;
+use super::_parser::Parser;
+use sophia_api::prefix::PrefixMapPair;
+use sophia_api::quad::Spog;
+use sophia_api::source::{
+    Source,
+    StreamError::{SinkError, SourceError},
+    StreamResult,
+};
+use sophia_api::term::SimpleTerm;
+use sophia_iri::Iri;
+use std::io::BufRead;
+
+/// The type of [`TripleSource`](sophia_api::source::TripleSource)
+/// returned by the Turtle and N-Triples parsers of this crate.
+pub struct TurtleTripleSource<B>(pub(crate) Parser<B>);
+
+impl<B: BufRead> TurtleTripleSource<B> {
+    /// The prefixes declared so far in the parsed document,
+    /// suitable for [`TurtleConfig::with_own_prefix_map`](crate::serializer::turtle::TurtleConfig::with_own_prefix_map).
+    ///
+    /// Prefixes bound to a relative IRI reference (in a document without base IRI) are omitted.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        self.0.prefix_map().to_vec()
+    }
+
+    /// The base IRI currently in effect in the parsed document,
+    /// i.e. the last one declared so far, or the one provided to the parser.
+    pub fn base(&self) -> Option<Iri<String>> {
+        self.0.base().map(|iri| iri.map_unchecked(String::from))
+    }
+}
+
+impl<B: BufRead> Source for TurtleTripleSource<B> {
+    type Item<'x> = [SimpleTerm<'static>; 3];
+    type Error = TurtleError;
+
+    fn try_for_some_item<E, F>(&mut self, mut f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: std::error::Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        let more = self.0.parse_statement().map_err(SourceError)?;
+        for (spo, _) in self.0.quads.drain(..) {
+            f(spo).map_err(SinkError)?;
+        }
+        Ok(more)
+    }
+}
+
+/// The type of [`QuadSource`](sophia_api::source::QuadSource)
+/// returned by the TriG and N-Quads parsers of this crate.
+pub struct TurtleQuadSource<B>(pub(crate) Parser<B>);
+
+impl<B: BufRead> TurtleQuadSource<B> {
+    /// The prefixes declared so far in the parsed document,
+    /// suitable for [`TurtleConfig::with_own_prefix_map`](crate::serializer::turtle::TurtleConfig::with_own_prefix_map).
+    ///
+    /// Prefixes bound to a relative IRI reference (in a document without base IRI) are omitted.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        self.0.prefix_map().to_vec()
+    }
+
+    /// The base IRI currently in effect in the parsed document,
+    /// i.e. the last one declared so far, or the one provided to the parser.
+    pub fn base(&self) -> Option<Iri<String>> {
+        self.0.base().map(|iri| iri.map_unchecked(String::from))
+    }
+}
+
+impl<B: BufRead> Source for TurtleQuadSource<B> {
+    type Item<'x> = Spog<SimpleTerm<'static>>;
+    type Error = TurtleError;
+
+    fn try_for_some_item<E, F>(&mut self, mut f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: std::error::Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        let more = self.0.parse_statement().map_err(SourceError)?;
+        for quad in self.0.quads.drain(..) {
+            f(quad).map_err(SinkError)?;
+        }
+        Ok(more)
+    }
+}
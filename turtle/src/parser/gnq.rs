@@ -1,19 +1,45 @@
-//! Adapter for the Generalized [N-Quads] parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/gnquads.rs)
+//! Parser for Generalized [N-Quads],
+//! where terms of any kind (including variables) can be used in any position.
 //!
-//! [N-Quads]: https://www.w3.org/TR/n-quads/
-use rio_turtle::GeneralizedNQuadsParser as RioGNQParser;
+//! [N-Quads]: https://www.w3.org/TR/rdf12-n-quads/
+use super::_parser::{Parser, Syntax};
+use super::TurtleQuadSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::QuadParser;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
 
-/// N-Quads parser based on RIO.
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncQuadParser;
+
+/// Generalized N-Quads parser.
 #[derive(Clone, Debug, Default)]
 pub struct GNQuadsParser {}
 
+impl GNQuadsParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the end of its line,
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> QuadParser<B> for GNQuadsParser {
-    type Source = GeneralizedRioSource<RioGNQParser<B>>;
+    type Source = TurtleQuadSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        GeneralizedRioSource(RioGNQParser::new(data))
+        TurtleQuadSource(Parser::new(data, Syntax::NQuads, true, None))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncQuadParser<R> for GNQuadsParser {
+    type Source = super::AsyncTurtleQuadSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleQuadSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::NQuads, true, None),
+        }
     }
 }
 
@@ -42,11 +68,11 @@ mod test {
             <http://localhost/ex#me> <http://example.org/ns/knows> _:b1.
             _:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/Person> <tag:g1>.
             _:b1 <http://example.org/ns/name> "Alice" <tag:g1>.
-            << <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 >> <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            <<( <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 )>> <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
         "#;
 
         let mut d = MyDataset::new();
-        let p = GNQuadsParser {};
+        let p = GNQuadsParser::default();
         let c = p.parse_str(nq).add_to_dataset(&mut d)?;
         assert_eq!(c, 4);
         assert_eq!(
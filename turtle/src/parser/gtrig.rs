@@ -1,28 +1,49 @@
-//! Adapter for the Generalized TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/gtrig.rs)
-
-use rio_turtle::GTriGParser as RioGTriGParser;
+//! Parser for Generalized [TriG],
+//! where terms of any kind (including variables) can be used in any position.
+//!
+//! [TriG]: https://www.w3.org/TR/rdf12-trig/
+use super::_parser::{Parser, Syntax};
+use super::TurtleQuadSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::QuadParser;
 use sophia_iri::Iri;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
+
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncQuadParser;
 
-/// TriG parser based on RIO.
+/// Generalized TriG parser.
 #[derive(Clone, Debug, Default)]
 pub struct GTriGParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<Iri<String>>,
 }
 
+impl GTriGParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the next `.` (or `}` at the end of a block),
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> QuadParser<B> for GTriGParser {
-    type Source = GeneralizedRioSource<RioGTriGParser<B>>;
+    type Source = TurtleQuadSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        let base = self
-            .base
-            .clone()
-            .map(Iri::unwrap)
-            .map(oxiri::Iri::parse)
-            .map(Result::unwrap);
-        GeneralizedRioSource(RioGTriGParser::new(data, base))
+        TurtleQuadSource(Parser::new(data, Syntax::TriG, true, self.base.clone()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncQuadParser<R> for GTriGParser {
+    type Source = super::AsyncTurtleQuadSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleQuadSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::TriG, true, self.base.clone()),
+        }
     }
 }
 
@@ -63,7 +84,7 @@ mod test {
             base: Some(Iri::new_unchecked("http://localhost/ex".to_string())),
         };
         let c = p.parse_str(gtrig).add_to_dataset(&mut d)?;
-        assert_eq!(c, 4);
+        assert_eq!(c, 5);
         assert_eq!(
             d.quads_matching(
                 [Iri::new_unchecked("http://localhost/ex#me")],
@@ -96,11 +117,21 @@ mod test {
         );
         assert_eq!(
             d.quads_matching(
+                TermKind::BlankNode,
+                [&rdf::reifies],
                 (
                     [Iri::new_unchecked("http://localhost/ex#me")],
                     [Iri::new_unchecked("http://example.org/ns/knows")],
                     TermKind::BlankNode,
                 ),
+                [None as Option<&SimpleTerm>],
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            d.quads_matching(
+                TermKind::BlankNode,
                 [Iri::new_unchecked("http://example.org/ns/since")],
                 [2002],
                 [None as Option<&SimpleTerm>],
@@ -108,7 +139,7 @@ mod test {
             .count(),
             1
         );
-        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 1);
+        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 2);
         Ok(())
     }
 }
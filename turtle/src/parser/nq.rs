@@ -1,19 +1,44 @@
-//! Adapter for the [N-Quads] parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/nquads.rs)
+//! Parser for the [N-Quads] concrete syntax of RDF.
 //!
-//! [N-Quads]: https://www.w3.org/TR/n-quads/
-use rio_turtle::NQuadsParser as RioNQParser;
+//! [N-Quads]: https://www.w3.org/TR/rdf12-n-quads/
+use super::_parser::{Parser, Syntax};
+use super::TurtleQuadSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::QuadParser;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
 
-/// N-Quads parser based on RIO.
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncQuadParser;
+
+/// N-Quads parser.
 #[derive(Clone, Debug, Default)]
 pub struct NQuadsParser {}
 
+impl NQuadsParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the end of its line,
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> QuadParser<B> for NQuadsParser {
-    type Source = StrictRioQuadSource<RioNQParser<B>>;
+    type Source = TurtleQuadSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        StrictRioQuadSource(RioNQParser::new(data))
+        TurtleQuadSource(Parser::new(data, Syntax::NQuads, false, None))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncQuadParser<R> for NQuadsParser {
+    type Source = super::AsyncTurtleQuadSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleQuadSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::NQuads, false, None),
+        }
     }
 }
 
@@ -42,13 +67,14 @@ mod test {
             <http://localhost/ex#me> <http://example.org/ns/knows> _:b1.
             _:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/Person> <tag:g1>.
             _:b1 <http://example.org/ns/name> "Alice" <tag:g1>.
-            << <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 >> <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            _:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 )>>.
+            _:r <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
         "#;
 
         let mut d = MyDataset::new();
-        let p = NQuadsParser {};
+        let p = NQuadsParser::default();
         let c = p.parse_str(nq).add_to_dataset(&mut d)?;
-        assert_eq!(c, 4);
+        assert_eq!(c, 5);
         assert_eq!(
             d.quads_matching(
                 [Iri::new_unchecked("http://localhost/ex#me")],
@@ -81,11 +107,21 @@ mod test {
         );
         assert_eq!(
             d.quads_matching(
+                TermKind::BlankNode,
+                [&rdf::reifies],
                 (
                     [Iri::new_unchecked("http://localhost/ex#me")],
                     [Iri::new_unchecked("http://example.org/ns/knows")],
                     TermKind::BlankNode,
                 ),
+                [None as Option<&SimpleTerm>],
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            d.quads_matching(
+                TermKind::BlankNode,
                 [Iri::new_unchecked("http://example.org/ns/since")],
                 [2002],
                 [None as Option<&SimpleTerm>],
@@ -93,7 +129,7 @@ mod test {
             .count(),
             1
         );
-        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 1);
+        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 2);
         Ok(())
     }
 }
@@ -1,19 +1,44 @@
-//! Adapter for the [N-Triples] parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/ntriples.rs)
+//! Parser for the [N-Triples] concrete syntax of RDF.
 //!
-//! [N-Triples]: https://www.w3.org/TR/n-triples/
-use rio_turtle::NTriplesParser as RioNTParser;
+//! [N-Triples]: https://www.w3.org/TR/rdf12-n-triples/
+use super::_parser::{Parser, Syntax};
+use super::TurtleTripleSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::TripleParser;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
 
-/// N-Triples parser based on RIO.
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncTripleParser;
+
+/// N-Triples parser.
 #[derive(Clone, Debug, Default)]
 pub struct NTriplesParser {}
 
+impl NTriplesParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the end of its line,
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> TripleParser<B> for NTriplesParser {
-    type Source = StrictRioTripleSource<RioNTParser<B>>;
+    type Source = TurtleTripleSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        StrictRioTripleSource(RioNTParser::new(data))
+        TurtleTripleSource(Parser::new(data, Syntax::NTriples, false, None))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncTripleParser<R> for NTriplesParser {
+    type Source = super::AsyncTurtleTripleSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleTripleSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::NTriples, false, None),
+        }
     }
 }
 
@@ -41,13 +66,14 @@ mod test {
             <http://localhost/ex#me> <http://example.org/ns/knows> _:b1.
             _:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/Person>.
             _:b1 <http://example.org/ns/name> "Alice".
-            << <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 >> <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            _:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://localhost/ex#me> <http://example.org/ns/knows> _:b1 )>>.
+            _:r <http://example.org/ns/since> "2002"^^<http://www.w3.org/2001/XMLSchema#integer>.
         "#;
 
         let mut g = MyGraph::new();
-        let p = NTriplesParser {};
+        let p = NTriplesParser::default();
         let c = p.parse_str(nt).add_to_graph(&mut g)?;
-        assert_eq!(c, 4);
+        assert_eq!(c, 5);
         assert_eq!(
             g.triples_matching(
                 [Iri::new_unchecked("http://localhost/ex#me")],
@@ -77,18 +103,37 @@ mod test {
         );
         assert_eq!(
             g.triples_matching(
+                TermKind::BlankNode,
+                [&rdf::reifies],
                 (
                     [Iri::new_unchecked("http://localhost/ex#me")],
                     [Iri::new_unchecked("http://example.org/ns/knows")],
                     TermKind::BlankNode,
                 ),
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            g.triples_matching(
+                TermKind::BlankNode,
                 [Iri::new_unchecked("http://example.org/ns/since")],
                 [2002],
             )
             .count(),
             1
         );
-        assert_eq!(g.blank_nodes().collect::<HashSet<_>>().len(), 1);
+        assert_eq!(g.blank_nodes().collect::<HashSet<_>>().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mod_functions() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nt = "<tag:a> <tag:b> <tag:c> .\n";
+        let source: TurtleTripleSource<&[u8]> = parse_bufread(nt.as_bytes());
+        assert_eq!(source.collect_triples::<MyGraph>()?.len(), 1);
+        let g: MyGraph = parse_bufread_decompressed(nt.as_bytes()).collect_triples()?;
+        assert_eq!(g.len(), 1);
         Ok(())
     }
 }
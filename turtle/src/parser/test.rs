@@ -0,0 +1,302 @@
+//! A corpus of positive and negative tests for the Turtle-family parsers,
+//! modelled after the [RDF 1.2 test suites] of the W3C.
+//!
+//! * evaluation tests check that a document parses to the dataset described in N-Quads
+//!   (up to blank node renaming);
+//! * negative syntax tests check that a document is rejected.
+//!
+//! [RDF 1.2 test suites]: https://w3c.github.io/rdf-tests/
+use super::_parser::{Parser, Syntax};
+use super::TurtleError;
+use sophia_api::quad::Spog;
+use sophia_api::term::SimpleTerm;
+use sophia_iri::Iri;
+use sophia_isomorphism::isomorphic_datasets;
+use test_case::test_case;
+use Syntax::*;
+
+/// The base IRI of the Turtle and TriG documents
+const BASE: &str = "http://example/base/";
+
+fn parse(syntax: Syntax, txt: &str) -> Result<Vec<Spog<SimpleTerm<'static>>>, TurtleError> {
+    let base = match syntax {
+        Turtle | TriG => Some(Iri::new_unchecked(BASE.to_string())),
+        NTriples | NQuads => None,
+    };
+    let mut parser = Parser::new(txt.as_bytes(), syntax, false, base);
+    let mut quads = vec![];
+    while parser.parse_statement()? {
+        quads.append(&mut parser.quads);
+    }
+    Ok(quads)
+}
+
+// Turtle
+
+#[test_case(Turtle, "<http://a.example/s> <http://a.example/p> <http://a.example/o> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle iris")]
+#[test_case(Turtle, "<s> <p> <#o> .", "<http://example/base/s> <http://example/base/p> <http://example/base/#o> ."; "turtle relative iris")]
+#[test_case(Turtle, "@base <http://a.example/> . <s> <p> <o> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle base")]
+#[test_case(Turtle, "BASE <http://a.example/> <s> <p> <o> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle sparql base")]
+#[test_case(Turtle, "@base <http://a.example/x/> . @base <../y/> . <s> <p> <o> .", "<http://a.example/y/s> <http://a.example/y/p> <http://a.example/y/o> ."; "turtle relative base")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:s p:p p:o .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle prefix")]
+#[test_case(Turtle, "pReFiX p: <http://a.example/>\np:s p:p p:o .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle sparql prefix")]
+#[test_case(Turtle, "@prefix : <http://a.example/> . :s :p : .", "<http://a.example/s> <http://a.example/p> <http://a.example/> ."; "turtle empty prefix")]
+#[test_case(Turtle, "@prefix p: <x/> . p:s p:p p:o .", "<http://example/base/x/s> <http://example/base/x/p> <http://example/base/x/o> ."; "turtle relative prefix")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . @prefix p: <http://b.example/> . p:s p:p p:o .", "<http://b.example/s> <http://b.example/p> <http://b.example/o> ."; "turtle prefix redefinition")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:a.b-c p:p p:o.", "<http://a.example/a.b-c> <http://a.example/p> <http://a.example/o> ."; "turtle local name with dots")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:0 p:p p:1a .", "<http://a.example/0> <http://a.example/p> <http://a.example/1a> ."; "turtle local name with leading digit")]
+#[test_case(Turtle, r"@prefix p: <http://a.example/> . p:\~a\.b p:p p:%20 .", "<http://a.example/~a.b> <http://a.example/p> <http://a.example/%20> ."; "turtle local name escapes")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:a:b p:p p:_ .", "<http://a.example/a:b> <http://a.example/p> <http://a.example/_> ."; "turtle local name with colon")]
+#[test_case(Turtle, "@prefix é: <http://a.example/> . é:s é:p é:ö .", "<http://a.example/s> <http://a.example/p> <http://a.example/ö> ."; "turtle non ascii names")]
+#[test_case(Turtle, r"<http://a.example/\u0073> <http://a.example/p> <http://a.example/\U0000006F> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle uchar in iri")]
+#[test_case(Turtle, "<http://a.example/s> a <http://a.example/C> .", "<http://a.example/s> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://a.example/C> ."; "turtle a keyword")]
+#[test_case(Turtle, "<s> <p1> <o1> ; <p2> <o2>, <o3> ;; .", "<http://example/base/s> <http://example/base/p1> <http://example/base/o1> .\n<http://example/base/s> <http://example/base/p2> <http://example/base/o2> .\n<http://example/base/s> <http://example/base/p2> <http://example/base/o3> ."; "turtle predicate object lists")]
+#[test_case(Turtle, "[] <p> <o> .", "_:b <http://example/base/p> <http://example/base/o> ."; "turtle anonymous blank node")]
+#[test_case(Turtle, "[ <p> <o> ] .", "_:b <http://example/base/p> <http://example/base/o> ."; "turtle blank node property list alone")]
+#[test_case(Turtle, "[ <p> <o> ] <q> <r> .", "_:b <http://example/base/p> <http://example/base/o> .\n_:b <http://example/base/q> <http://example/base/r> ."; "turtle blank node property list as subject")]
+#[test_case(Turtle, "<s> <p> [ <q> [ <r> <o> ] ; ] .", "<http://example/base/s> <http://example/base/p> _:b1 .\n_:b1 <http://example/base/q> _:b2 .\n_:b2 <http://example/base/r> <http://example/base/o> ."; "turtle nested blank node property lists")]
+#[test_case(Turtle, "_:a <p> _:b . _:b <p> _:a .", "_:x <http://example/base/p> _:y .\n_:y <http://example/base/p> _:x ."; "turtle labelled blank nodes")]
+#[test_case(Turtle, "_:1a.b <p> _:c.", "_:x <http://example/base/p> _:y ."; "turtle blank node labels with digits and dots")]
+#[test_case(Turtle, "_:a <p> [] . [] <p> _:a .", "_:a <http://example/base/p> _:b1 .\n_:b2 <http://example/base/p> _:a ."; "turtle anonymous blank nodes are distinct")]
+#[test_case(Turtle, "<s> <p> () .", "<http://example/base/s> <http://example/base/p> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> ."; "turtle empty collection")]
+#[test_case(Turtle, "<s> <p> (1 <o>) .", "<http://example/base/s> <http://example/base/p> _:l1 .\n_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> _:l2 .\n_:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://example/base/o> .\n_:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> ."; "turtle collection")]
+#[test_case(Turtle, "<s> <p> (()) .", "<http://example/base/s> <http://example/base/p> _:l1 .\n_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .\n_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> ."; "turtle nested collection")]
+#[test_case(Turtle, "(<o>) <p> <o> .", "_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://example/base/o> .\n_:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> .\n_:l1 <http://example/base/p> <http://example/base/o> ."; "turtle collection as subject")]
+#[test_case(Turtle, r#"<s> <p> "a", 'b', """c "d" ""e""", '''f
+g''' ."#, r#"<http://example/base/s> <http://example/base/p> "a" .
+<http://example/base/s> <http://example/base/p> "b" .
+<http://example/base/s> <http://example/base/p> "c \"d\" \"\"e" .
+<http://example/base/s> <http://example/base/p> "f\ng" ."#; "turtle strings")]
+#[test_case(Turtle, r#"<s> <p> "\t\b\n\r\f\"\'\\" ."#, r#"<http://example/base/s> <http://example/base/p> "\t\b\n\r\f\"'\\" ."#; "turtle string escapes")]
+#[test_case(Turtle, r#"<s> <p> "\u00E9\U0001F600" ."#, r#"<http://example/base/s> <http://example/base/p> "é😀" ."#; "turtle uchar in string")]
+#[test_case(Turtle, r#"<s> <p> "chat"@fr, "color"@en-US ."#, r#"<http://example/base/s> <http://example/base/p> "chat"@fr .
+<http://example/base/s> <http://example/base/p> "color"@en-US ."#; "turtle language tags")]
+#[test_case(Turtle, r#"@prefix p: <http://a.example/> . <s> <p> "1"^^p:t, "2"^^<t> ."#, r#"<http://example/base/s> <http://example/base/p> "1"^^<http://a.example/t> .
+<http://example/base/s> <http://example/base/p> "2"^^<http://example/base/t> ."#; "turtle datatypes")]
+#[test_case(Turtle, "<s> <p> 1, -2, +3 .", r#"<http://example/base/s> <http://example/base/p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> .
+<http://example/base/s> <http://example/base/p> "-2"^^<http://www.w3.org/2001/XMLSchema#integer> .
+<http://example/base/s> <http://example/base/p> "+3"^^<http://www.w3.org/2001/XMLSchema#integer> ."#; "turtle integers")]
+#[test_case(Turtle, "<s> <p> 4.5, .5, -0.5 .", r#"<http://example/base/s> <http://example/base/p> "4.5"^^<http://www.w3.org/2001/XMLSchema#decimal> .
+<http://example/base/s> <http://example/base/p> ".5"^^<http://www.w3.org/2001/XMLSchema#decimal> .
+<http://example/base/s> <http://example/base/p> "-0.5"^^<http://www.w3.org/2001/XMLSchema#decimal> ."#; "turtle decimals")]
+#[test_case(Turtle, "<s> <p> 1e3, 1.5E-3, -.5e+2 .", r#"<http://example/base/s> <http://example/base/p> "1e3"^^<http://www.w3.org/2001/XMLSchema#double> .
+<http://example/base/s> <http://example/base/p> "1.5E-3"^^<http://www.w3.org/2001/XMLSchema#double> .
+<http://example/base/s> <http://example/base/p> "-.5e+2"^^<http://www.w3.org/2001/XMLSchema#double> ."#; "turtle doubles")]
+#[test_case(Turtle, "<s> <p> 1.", r#"<http://example/base/s> <http://example/base/p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> ."#; "turtle integer followed by a dot")]
+#[test_case(Turtle, "<s> <p> true, false .", r#"<http://example/base/s> <http://example/base/p> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+<http://example/base/s> <http://example/base/p> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> ."#; "turtle booleans")]
+#[test_case(Turtle, "# comment\n<s> <p> <o> . # comment\n# <s> <p> <x> .", "<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "turtle comments")]
+#[test_case(Turtle, "<http://a.example/s><http://a.example/p><http://a.example/o>.", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "turtle no whitespace")]
+#[test_case(Turtle, "", ""; "turtle empty document")]
+// Turtle 1.2
+#[test_case(Turtle, "VERSION \"1.2\"\n<s> <p> <o> .", "<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "turtle version")]
+#[test_case(Turtle, "@version '1.2-basic' . <s> <p> <o> .", "<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "turtle at version")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <c> )>> .", "<http://example/base/s> <http://example/base/p> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> ."; "turtle triple term")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <<( <c> <d> <e> )>> )>> .", "<http://example/base/s> <http://example/base/p> <<( <http://example/base/a> <http://example/base/b> <<( <http://example/base/c> <http://example/base/d> <http://example/base/e> )>> )>> ."; "turtle nested triple term")]
+#[test_case(Turtle, "<s> <p> <<( _:a <b> \"c\"@en )>>, <<( [] a 1 )>> .", "<http://example/base/s> <http://example/base/p> <<( _:a <http://example/base/b> \"c\"@en )>> .\n<http://example/base/s> <http://example/base/p> <<( _:b <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> )>> ."; "turtle triple term with blank nodes and literals")]
+#[test_case(Turtle, "<< <a> <b> <c> >> <p> <o> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle reified triple as subject")]
+#[test_case(Turtle, "<s> <p> << <a> <b> <c> >> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n<http://example/base/s> <http://example/base/p> _:r ."; "turtle reified triple as object")]
+#[test_case(Turtle, "<< <a> <b> <c> >> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> ."; "turtle reified triple alone")]
+#[test_case(Turtle, "<< <a> <b> <c> ~ <r> >> <p> <o> .", "<http://example/base/r> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n<http://example/base/r> <http://example/base/p> <http://example/base/o> ."; "turtle reified triple with reifier")]
+#[test_case(Turtle, "<< <a> <b> <c> ~ _:r >> <p> <o> . _:r <q> <z> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> .\n_:r <http://example/base/q> <http://example/base/z> ."; "turtle reified triple with blank reifier")]
+#[test_case(Turtle, "<< <a> <b> <c> ~ >> <p> <o> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle reified triple with empty reifier")]
+#[test_case(Turtle, "<< << <a> <b> <c> >> <q> [] >> <p> <o> .", "_:r1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( _:r1 <http://example/base/q> _:x )>> .\n_:r2 <http://example/base/p> <http://example/base/o> ."; "turtle nested reified triples")]
+#[test_case(Turtle, "<< <a> <b> <<( <c> <d> <e> )>> >> <p> <o> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <<( <http://example/base/c> <http://example/base/d> <http://example/base/e> )>> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle reified triple with triple term")]
+#[test_case(Turtle, "<a> <b> <c> ~ <r> .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n<http://example/base/r> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> ."; "turtle reifier")]
+#[test_case(Turtle, "<a> <b> <c> ~ .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> ."; "turtle empty reifier")]
+#[test_case(Turtle, "<a> <b> <c> {| <p> <o> |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle annotation")]
+#[test_case(Turtle, "<a> <b> <c> ~ <r> {| <p> <o> |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n<http://example/base/r> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n<http://example/base/r> <http://example/base/p> <http://example/base/o> ."; "turtle reifier and annotation")]
+#[test_case(Turtle, "<a> <b> <c> ~ [] {| <p> <o> |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle anonymous reifier and annotation")]
+#[test_case(Turtle, "<a> <b> <c> ~ <r1> ~ <r2> {| <p> <o> |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n<http://example/base/r1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n<http://example/base/r2> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n<http://example/base/r2> <http://example/base/p> <http://example/base/o> ."; "turtle several reifiers")]
+#[test_case(Turtle, "<a> <b> <c> {| <p> <o1> |} {| <p> <o2> |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r1 <http://example/base/p> <http://example/base/o1> .\n_:r2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r2 <http://example/base/p> <http://example/base/o2> ."; "turtle several annotations")]
+#[test_case(Turtle, "<a> <b> <c> {| <p> <o> {| <q> <z> |} |} .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r1 <http://example/base/p> <http://example/base/o> .\n_:r2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( _:r1 <http://example/base/p> <http://example/base/o> )>> .\n_:r2 <http://example/base/q> <http://example/base/z> ."; "turtle nested annotations")]
+#[test_case(Turtle, "<a> <b> <c> {| <p> 1 |}, <d> ; <e> <f> .", "<http://example/base/a> <http://example/base/b> <http://example/base/c> .\n_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n<http://example/base/a> <http://example/base/b> <http://example/base/d> .\n<http://example/base/a> <http://example/base/e> <http://example/base/f> ."; "turtle annotation in object list")]
+#[test_case(Turtle, "[ <b> <c> {| <p> <o> |} ] .", "_:a <http://example/base/b> <http://example/base/c> .\n_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( _:a <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "turtle annotation in blank node property list")]
+// TriG
+#[test_case(TriG, "<s> <p> <o> .", "<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "trig default graph")]
+#[test_case(TriG, "{ <s> <p> <o> }", "<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "trig wrapped default graph")]
+#[test_case(TriG, "<g> { <s> <p> <o> . }", "<http://example/base/s> <http://example/base/p> <http://example/base/o> <http://example/base/g> ."; "trig named graph")]
+#[test_case(TriG, "GRAPH <g> { <s> <p> <o> } graph <h> { <s> <p> <o> }", "<http://example/base/s> <http://example/base/p> <http://example/base/o> <http://example/base/g> .\n<http://example/base/s> <http://example/base/p> <http://example/base/o> <http://example/base/h> ."; "trig graph keyword")]
+#[test_case(TriG, "@prefix p: <http://a.example/> . p:g { p:s p:p p:o }", "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> ."; "trig prefixed graph name")]
+#[test_case(TriG, "_:g { <s> <p> _:g } [] { <s> <p> <o> }", "<http://example/base/s> <http://example/base/p> _:g _:g .\n<http://example/base/s> <http://example/base/p> <http://example/base/o> _:h ."; "trig blank graph names")]
+#[test_case(TriG, "<g> { } { } GRAPH <h> {}", ""; "trig empty graphs")]
+#[test_case(TriG, "<g> { <s> <p> <o1> . <s> <p> <o2> }", "<http://example/base/s> <http://example/base/p> <http://example/base/o1> <http://example/base/g> .\n<http://example/base/s> <http://example/base/p> <http://example/base/o2> <http://example/base/g> ."; "trig several triples in a graph")]
+#[test_case(TriG, "<g> { [ <p> <o> ] . (1) <p> <o> }", "_:b <http://example/base/p> <http://example/base/o> <http://example/base/g> .\n_:l <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example/base/g> .\n_:l <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> <http://example/base/g> .\n_:l <http://example/base/p> <http://example/base/o> <http://example/base/g> ."; "trig blank nodes and collections in a graph")]
+#[test_case(TriG, "<g> { <s> <p> <o> } @prefix p: <http://a.example/> . p:g { p:s p:p p:o } <s> <p> <o> .", "<http://example/base/s> <http://example/base/p> <http://example/base/o> <http://example/base/g> .\n<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> .\n<http://example/base/s> <http://example/base/p> <http://example/base/o> ."; "trig directives between graphs")]
+#[test_case(TriG, "<g> { <a> <b> <c> ~ <r> {| <p> <o> |} }", "<http://example/base/a> <http://example/base/b> <http://example/base/c> <http://example/base/g> .\n<http://example/base/r> <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> <http://example/base/g> .\n<http://example/base/r> <http://example/base/p> <http://example/base/o> <http://example/base/g> ."; "trig annotation in a graph")]
+#[test_case(TriG, "<g> { << <a> <b> <c> >> . <s> <p> <<( <a> <b> <c> )>> }", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> <http://example/base/g> .\n<http://example/base/s> <http://example/base/p> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> <http://example/base/g> ."; "trig triple terms and reified triples in a graph")]
+#[test_case(TriG, "<< <a> <b> <c> >> <p> <o> .", "_:r <http://www.w3.org/1999/02/22-rdf-syntax-ns#reifies> <<( <http://example/base/a> <http://example/base/b> <http://example/base/c> )>> .\n_:r <http://example/base/p> <http://example/base/o> ."; "trig reified triple in default graph")]
+// N-Triples
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "ntriples iris")]
+#[test_case(NTriples, "_:a <http://a.example/p> _:b .\n_:b <http://a.example/p> _:a .", "_:x <http://a.example/p> _:y .\n_:y <http://a.example/p> _:x ."; "ntriples blank nodes")]
+#[test_case(NTriples, "_:1a.b <http://a.example/p> _:c-d .", "_:x <http://a.example/p> _:y ."; "ntriples blank node labels")]
+#[test_case(NTriples, r#"<http://a.example/s> <http://a.example/p> "a" .
+<http://a.example/s> <http://a.example/p> "b"@en-US .
+<http://a.example/s> <http://a.example/p> "c"^^<http://a.example/t> ."#, r#"<http://a.example/s> <http://a.example/p> "a" .
+<http://a.example/s> <http://a.example/p> "b"@en-US .
+<http://a.example/s> <http://a.example/p> "c"^^<http://a.example/t> ."#; "ntriples literals")]
+#[test_case(NTriples, r#"<http://a.example/s> <http://a.example/p> "\t\b\n\r\f\"\'\\\u00E9\U0001F600" ."#, r#"<http://a.example/s> <http://a.example/p> "\t\b\n\r\f\"'\\é😀" ."#; "ntriples string escapes")]
+#[test_case(NTriples, r"<http://a.example/\u0073> <http://a.example/p> <http://a.example/\U0000006F> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "ntriples uchar in iris")]
+#[test_case(NTriples, "# comment\n\n  <http://a.example/s>\t<http://a.example/p>  <http://a.example/o>\t. # comment\n\n", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "ntriples comments and whitespace")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> .\r\n<http://a.example/s> <http://a.example/p> <http://a.example/o2> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> .\n<http://a.example/s> <http://a.example/p> <http://a.example/o2> ."; "ntriples crlf")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o>.", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "ntriples no final eol")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> .", "<http://a.example/s> <http://a.example/p> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> ."; "ntriples triple term")]
+#[test_case(NTriples, "_:r <http://a.example/p> <<(_:a <http://a.example/b> <<( <http://a.example/c> <http://a.example/d> \"e\" )>>)>> .", "_:r <http://a.example/p> <<( _:a <http://a.example/b> <<( <http://a.example/c> <http://a.example/d> \"e\" )>> )>> ."; "ntriples nested triple term")]
+// N-Quads
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> ."; "nquads default graph")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> .", "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> ."; "nquads named graph")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> \"o\"@en _:g .\n_:g <http://a.example/p> <http://a.example/o> _:g .", "<http://a.example/s> <http://a.example/p> \"o\"@en _:x .\n_:x <http://a.example/p> <http://a.example/o> _:x ."; "nquads blank graph name")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <<( <http://a.example/a> <http://a.example/b> \"c\" )>> <http://a.example/g> .", "<http://a.example/s> <http://a.example/p> <<( <http://a.example/a> <http://a.example/b> \"c\" )>> <http://a.example/g> ."; "nquads triple term")]
+fn eval(syntax: Syntax, txt: &str, expected: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let got = parse(syntax, txt)?;
+    let expected = parse(NQuads, expected)?;
+    assert!(
+        isomorphic_datasets(&got, &expected)?,
+        "{txt}\n\ngot:\n{got:#?}\n\nexpected:\n{expected:#?}"
+    );
+    Ok(())
+}
+
+// Turtle
+#[test_case(Turtle, "<s> <p> <o>"; "turtle missing dot")]
+#[test_case(Turtle, "<s> <p> <o> .."; "turtle extra dot")]
+#[test_case(Turtle, "p:s <p> <o> ."; "turtle undefined prefix")]
+#[test_case(Turtle, "\"s\" <p> <o> ."; "turtle literal subject")]
+#[test_case(Turtle, "1 <p> <o> ."; "turtle number subject")]
+#[test_case(Turtle, "<s> \"p\" <o> ."; "turtle literal predicate")]
+#[test_case(Turtle, "<s> _:p <o> ."; "turtle blank node predicate")]
+#[test_case(Turtle, "<s> [] <o> ."; "turtle anonymous predicate")]
+#[test_case(Turtle, "<s> (<p>) <o> ."; "turtle collection predicate")]
+#[test_case(Turtle, "a <p> <o> ."; "turtle a as subject")]
+#[test_case(Turtle, "<s> <p> a ."; "turtle a as object")]
+#[test_case(Turtle, "?s <p> <o> ."; "turtle variable")]
+#[test_case(Turtle, "<s> <p> ."; "turtle missing object")]
+#[test_case(Turtle, "<s> <p> <o>, ."; "turtle missing object after comma")]
+#[test_case(Turtle, "<s> ; <p> <o> ."; "turtle missing predicate before semicolon")]
+#[test_case(Turtle, "() ."; "turtle collection alone")]
+#[test_case(Turtle, "[ <p> <o> ."; "turtle unclosed blank node property list")]
+#[test_case(Turtle, "<s> <p> [ ] ] ."; "turtle extra bracket")]
+#[test_case(Turtle, "<s> <p> ( 1 2 ."; "turtle unclosed collection")]
+#[test_case(Turtle, "[ ] ."; "turtle anonymous blank node alone")]
+#[test_case(Turtle, r#"<s> <p> "\a" ."#; "turtle bad string escape")]
+#[test_case(Turtle, r#"<s> <p> "\u00Z0" ."#; "turtle bad uchar")]
+#[test_case(Turtle, "<s> <p> \"a\nb\" ."; "turtle newline in short string")]
+#[test_case(Turtle, "<s> <p> 'a\rb' ."; "turtle carriage return in short string")]
+#[test_case(Turtle, r#"<s> <p> """a ."#; "turtle unterminated long string")]
+#[test_case(Turtle, r#"<s> <p> "a"@ ."#; "turtle empty language tag")]
+#[test_case(Turtle, r#"<s> <p> "a"@en@fr ."#; "turtle two language tags")]
+#[test_case(Turtle, r#"<s> <p> "a"@en^^<t> ."#; "turtle language tag and datatype")]
+#[test_case(Turtle, r#"<s> <p> "a"^^"t" ."#; "turtle literal datatype")]
+#[test_case(Turtle, "<s> <p> <a b> ."; "turtle space in iri")]
+#[test_case(Turtle, "<s> <p> <a{b> ."; "turtle brace in iri")]
+#[test_case(Turtle, "<s> <p> <a|b> ."; "turtle pipe in iri")]
+#[test_case(Turtle, "<s> <p> <a^b> ."; "turtle caret in iri")]
+#[test_case(Turtle, "<s> <p> <a\"b> ."; "turtle quote in iri")]
+#[test_case(Turtle, r"<s> <p> <a\nb> ."; "turtle echar in iri")]
+#[test_case(Turtle, r"<s> <p> <a\u0020b> ."; "turtle escaped space in iri")]
+#[test_case(Turtle, r"@prefix p: <http://a.example/> . p:s p:p p:\a ."; "turtle bad local name escape")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:s p:p p:%2 ."; "turtle bad percent escape")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> . p:s p:p p:a~b ."; "turtle unescaped tilde in local name")]
+#[test_case(Turtle, "@prefix p <http://a.example/> ."; "turtle prefix without colon")]
+#[test_case(Turtle, "@prefix p: <http://a.example/> p:s p:p p:o ."; "turtle prefix without dot")]
+#[test_case(Turtle, "@prefix p: \"http://a.example/\" ."; "turtle prefix with literal")]
+#[test_case(Turtle, "PREFIX p: <http://a.example/> . p:s p:p p:o ."; "turtle sparql prefix with dot")]
+#[test_case(Turtle, "@PREFIX p: <http://a.example/> ."; "turtle uppercase at prefix")]
+#[test_case(Turtle, "@base <http://a.example/>"; "turtle base without dot")]
+#[test_case(Turtle, "BASE <http://a.example/> . <s> <p> <o> ."; "turtle sparql base with dot")]
+#[test_case(Turtle, "{ <s> <p> <o> }"; "turtle wrapped graph")]
+#[test_case(Turtle, "<g> { <s> <p> <o> }"; "turtle named graph")]
+#[test_case(Turtle, "<s> <p> <o> <g> ."; "turtle quad")]
+// Turtle 1.2
+#[test_case(Turtle, "<<( <a> <b> <c> )>> <p> <o> ."; "turtle triple term as subject")]
+#[test_case(Turtle, "<s> <<( <a> <b> <c> )>> <o> ."; "turtle triple term as predicate")]
+#[test_case(Turtle, "<s> <p> <<( \"a\" <b> <c> )>> ."; "turtle literal in triple term subject")]
+#[test_case(Turtle, "<s> <p> <<( <<( <a> <b> <c> )>> <b> <c> )>> ."; "turtle triple term in triple term subject")]
+#[test_case(Turtle, "<s> <p> <<( <a> _:b <c> )>> ."; "turtle blank node in triple term predicate")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> (1) )>> ."; "turtle collection in triple term")]
+#[test_case(Turtle, "<s> <p> <<( [ <q> <z> ] <b> <c> )>> ."; "turtle blank node property list in triple term")]
+#[test_case(Turtle, "<s> <p> <<( << <a> <b> <c> >> <q> <z> )>> ."; "turtle reified triple in triple term")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <c> ~ <r> )>> ."; "turtle reifier in triple term")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <c> {| <q> <z> |} )>> ."; "turtle annotation in triple term")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> )>> ."; "turtle triple term with two terms")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <c> <d> )>> ."; "turtle triple term with four terms")]
+#[test_case(Turtle, "<s> <p> <<( <a> <b> <c> >> ."; "turtle unclosed triple term")]
+#[test_case(Turtle, "<s> <p> << <a> <b> <c> )>> ."; "turtle mismatched reified triple")]
+#[test_case(Turtle, "<< \"a\" <b> <c> >> <p> <o> ."; "turtle literal in reified triple subject")]
+#[test_case(Turtle, "<< <<( <a> <b> <c> )>> <b> <c> >> <p> <o> ."; "turtle triple term in reified triple subject")]
+#[test_case(Turtle, "<< <a> <b> (1) >> <p> <o> ."; "turtle collection in reified triple")]
+#[test_case(Turtle, "<< [ <q> <z> ] <b> <c> >> <p> <o> ."; "turtle blank node property list in reified triple")]
+#[test_case(Turtle, "<< <a> <b> <c> ~ <r1> ~ <r2> >> <p> <o> ."; "turtle two reifiers in reified triple")]
+#[test_case(Turtle, "<< <a> <b> <c> {| <q> <z> |} >> <p> <o> ."; "turtle annotation in reified triple")]
+#[test_case(Turtle, "<< <a> <b> >> <p> <o> ."; "turtle reified triple with two terms")]
+#[test_case(Turtle, "<s> << <a> <b> <c> >> <o> ."; "turtle reified triple as predicate")]
+#[test_case(Turtle, "<a> <b> <c> ~ \"r\" ."; "turtle literal reifier")]
+#[test_case(Turtle, "<a> <b> <c> ~ [ <q> <z> ] ."; "turtle blank node property list as reifier")]
+#[test_case(Turtle, "<a> <b> <c> ~ <<( <a> <b> <c> )>> ."; "turtle triple term as reifier")]
+#[test_case(Turtle, "<s> <p> {| <q> <z> |} ."; "turtle annotation without object")]
+#[test_case(Turtle, "<a> {| <q> <z> |} <b> <c> ."; "turtle annotation after subject")]
+#[test_case(Turtle, "<a> <b> <c> {| |} ."; "turtle empty annotation")]
+#[test_case(Turtle, "<a> <b> <c> {| <q> <z> ."; "turtle unclosed annotation")]
+#[test_case(Turtle, "VERSION <1.2>"; "turtle version with iri")]
+#[test_case(Turtle, "VERSION 1.2"; "turtle version with number")]
+#[test_case(Turtle, "@version \"1.2\""; "turtle at version without dot")]
+#[test_case(Turtle, "VERSION \"1.2\" . <s> <p> <o> ."; "turtle version with dot")]
+#[test_case(Turtle, r#"<s> <p> "a"@en--ltr ."#; "turtle base direction is not supported")]
+// TriG
+#[test_case(TriG, "<g> { <s> <p> <o> "; "trig unclosed graph")]
+#[test_case(TriG, "<g> { <s> <p> <o> } ."; "trig dot after graph")]
+#[test_case(TriG, "<g> { <h> { <s> <p> <o> } }"; "trig nested graphs")]
+#[test_case(TriG, "{ <s> <p> <o> . . }"; "trig extra dot in graph")]
+#[test_case(TriG, "\"g\" { <s> <p> <o> }"; "trig literal graph name")]
+#[test_case(TriG, "<<( <a> <b> <c> )>> { <s> <p> <o> }"; "trig triple term graph name")]
+#[test_case(TriG, "<< <a> <b> <c> >> { <s> <p> <o> }"; "trig reified triple graph name")]
+#[test_case(TriG, "(<g>) { <s> <p> <o> }"; "trig collection graph name")]
+#[test_case(TriG, "[ <p> <o> ] { <s> <p> <o> }"; "trig blank node property list graph name")]
+#[test_case(TriG, "GRAPH { <s> <p> <o> }"; "trig graph keyword without name")]
+#[test_case(TriG, "GRAPH <g> <s> <p> <o> ."; "trig graph keyword without braces")]
+#[test_case(TriG, "GRAPH <g> <h> { <s> <p> <o> }"; "trig graph keyword with two names")]
+#[test_case(TriG, "<g> { @prefix p: <http://a.example/> . }"; "trig directive in graph")]
+#[test_case(TriG, "<g> { PREFIX p: <http://a.example/> }"; "trig sparql directive in graph")]
+#[test_case(TriG, "<g> { <s> <p> }"; "trig incomplete triple in graph")]
+#[test_case(TriG, "<g> <s> { <p> <o> }"; "trig graph name and subject")]
+#[test_case(TriG, "<s> <p> <o> <g> ."; "trig quad")]
+// N-Triples
+#[test_case(NTriples, "<s> <http://a.example/p> <http://a.example/o> ."; "ntriples relative iri")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o>"; "ntriples missing dot")]
+#[test_case(NTriples, "@prefix p: <http://a.example/> ."; "ntriples prefix")]
+#[test_case(NTriples, "p:s <http://a.example/p> <http://a.example/o> ."; "ntriples prefixed name")]
+#[test_case(NTriples, "<http://a.example/s> a <http://a.example/o> ."; "ntriples a keyword")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> 'o' ."; "ntriples single quoted string")]
+#[test_case(NTriples, r#"<http://a.example/s> <http://a.example/p> """o""" ."#; "ntriples long string")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> 1 ."; "ntriples number")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> true ."; "ntriples boolean")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> [] ."; "ntriples anonymous blank node")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> (<http://a.example/o>) ."; "ntriples collection")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> \"o\"^^p:t ."; "ntriples prefixed datatype")]
+#[test_case(NTriples, "\"s\" <http://a.example/p> <http://a.example/o> ."; "ntriples literal subject")]
+#[test_case(NTriples, "<http://a.example/s> _:p <http://a.example/o> ."; "ntriples blank node predicate")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> ."; "ntriples quad")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> ; <http://a.example/q> <http://a.example/z> ."; "ntriples predicate object list")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> \"a\nb\" ."; "ntriples newline in string")]
+#[test_case(NTriples, r"<http://a.example/s> <http://a.example/p> <http://a.example/a\nb> ."; "ntriples echar in iri")]
+#[test_case(NTriples, "<<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> <http://a.example/p> <http://a.example/o> ."; "ntriples triple term as subject")]
+#[test_case(NTriples, "<http://a.example/s> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> <http://a.example/o> ."; "ntriples triple term as predicate")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <<( \"a\" <http://a.example/b> <http://a.example/c> )>> ."; "ntriples literal in triple term subject")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> << <http://a.example/a> <http://a.example/b> <http://a.example/c> >> ."; "ntriples reified triple")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> ~ <http://a.example/r> ."; "ntriples reifier")]
+#[test_case(NTriples, "<http://a.example/s> <http://a.example/p> <http://a.example/o> {| <http://a.example/q> <http://a.example/z> |} ."; "ntriples annotation")]
+#[test_case(NTriples, r#"<http://a.example/s> <http://a.example/p> "a"@en--ltr ."#; "ntriples base direction is not supported")]
+// N-Quads
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> \"g\" ."; "nquads literal graph name")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> ."; "nquads triple term graph name")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <g> ."; "nquads relative graph name")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g> <http://a.example/h> ."; "nquads five terms")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <http://a.example/o> <http://a.example/g>"; "nquads missing dot")]
+#[test_case(NQuads, "<http://a.example/g> { <http://a.example/s> <http://a.example/p> <http://a.example/o> }"; "nquads trig graph")]
+#[test_case(NQuads, "<http://a.example/s> <http://a.example/p> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> <<( <http://a.example/a> <http://a.example/b> <http://a.example/c> )>> ."; "nquads triple terms as object and graph name")]
+fn negative_syntax(syntax: Syntax, txt: &str) {
+    match parse(syntax, txt) {
+        Err(TurtleError::Syntax { .. }) => {}
+        res => panic!("{txt}\n\nexpected a syntax error, got {res:?}"),
+    }
+}
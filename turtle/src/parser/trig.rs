@@ -1,28 +1,48 @@
-//! Adapter for the TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
-
-use rio_turtle::TriGParser as RioTriGParser;
+//! Parser for the [TriG] concrete syntax of RDF.
+//!
+//! [TriG]: https://www.w3.org/TR/rdf12-trig/
+use super::_parser::{Parser, Syntax};
+use super::TurtleQuadSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::QuadParser;
 use sophia_iri::Iri;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
+
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncQuadParser;
 
-/// TriG parser based on RIO.
+/// TriG parser.
 #[derive(Clone, Debug, Default)]
 pub struct TriGParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<Iri<String>>,
 }
 
+impl TriGParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the next `.` (or `}` at the end of a block),
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> QuadParser<B> for TriGParser {
-    type Source = StrictRioQuadSource<RioTriGParser<B>>;
+    type Source = TurtleQuadSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        let base = self
-            .base
-            .clone()
-            .map(Iri::unwrap)
-            .map(oxiri::Iri::parse)
-            .map(Result::unwrap);
-        StrictRioQuadSource(RioTriGParser::new(data, base))
+        TurtleQuadSource(Parser::new(data, Syntax::TriG, false, self.base.clone()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncQuadParser<R> for TriGParser {
+    type Source = super::AsyncTurtleQuadSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleQuadSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::TriG, false, self.base.clone()),
+        }
     }
 }
 
@@ -63,7 +83,7 @@ mod test {
             base: Some(Iri::new_unchecked("http://localhost/ex".to_string())),
         };
         let c = p.parse_str(trig).add_to_dataset(&mut d)?;
-        assert_eq!(c, 4);
+        assert_eq!(c, 5);
         assert_eq!(
             d.quads_matching(
                 [Iri::new_unchecked("http://localhost/ex#me")],
@@ -96,11 +116,21 @@ mod test {
         );
         assert_eq!(
             d.quads_matching(
+                TermKind::BlankNode,
+                [&rdf::reifies],
                 (
                     [Iri::new_unchecked("http://localhost/ex#me")],
                     [Iri::new_unchecked("http://example.org/ns/knows")],
                     TermKind::BlankNode,
                 ),
+                [None as Option<&SimpleTerm>],
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            d.quads_matching(
+                TermKind::BlankNode,
                 [Iri::new_unchecked("http://example.org/ns/since")],
                 [2002],
                 [None as Option<&SimpleTerm>],
@@ -108,7 +138,7 @@ mod test {
             .count(),
             1
         );
-        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 1);
+        assert_eq!(d.blank_nodes().collect::<HashSet<_>>().len(), 2);
         Ok(())
     }
 }
@@ -1,27 +1,48 @@
-//! Adapter for the Turtle parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
-use rio_turtle::TurtleParser as RioTurtleParser;
+//! Parser for the [Turtle] concrete syntax of RDF.
+//!
+//! [Turtle]: https://www.w3.org/TR/rdf12-turtle/
+use super::_parser::{Parser, Syntax};
+use super::TurtleTripleSource;
+use super::{LenientParser, TurtleError};
 use sophia_api::parser::TripleParser;
 use sophia_iri::Iri;
-use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::mpsc::Sender;
 
-/// Turtle parser based on RIO.
+#[cfg(feature = "async")]
+use sophia_api::parser::AsyncTripleParser;
+
+/// Turtle parser.
 #[derive(Clone, Debug, Default)]
 pub struct TurtleParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<Iri<String>>,
 }
 
+impl TurtleParser {
+    /// Make this parser lenient:
+    /// a malformed statement is skipped up to the next `.`,
+    /// and the corresponding error is sent to `errors` instead of aborting the parsing.
+    pub fn with_lenient(self, errors: Sender<TurtleError>) -> LenientParser<Self> {
+        LenientParser::new(self, errors)
+    }
+}
+
 impl<B: BufRead> TripleParser<B> for TurtleParser {
-    type Source = StrictRioTripleSource<RioTurtleParser<B>>;
+    type Source = TurtleTripleSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        let base = self
-            .base
-            .clone()
-            .map(Iri::unwrap)
-            .map(oxiri::Iri::parse)
-            .map(Result::unwrap);
-        StrictRioTripleSource(RioTurtleParser::new(data, base))
+        TurtleTripleSource(Parser::new(data, Syntax::Turtle, false, self.base.clone()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncTripleParser<R> for TurtleParser {
+    type Source = super::AsyncTurtleTripleSource<R>;
+    fn parse_async(&self, data: R) -> Self::Source {
+        super::AsyncTurtleTripleSource {
+            read: data,
+            parser: Parser::new(Default::default(), Syntax::Turtle, false, self.base.clone()),
+        }
     }
 }
 
@@ -57,7 +78,7 @@ mod test {
             base: Some(Iri::new_unchecked("http://localhost/ex".to_string())),
         };
         let c = p.parse_str(turtle).add_to_graph(&mut g)?;
-        assert_eq!(c, 4);
+        assert_eq!(c, 5);
         assert_eq!(
             g.triples_matching(
                 [Iri::new_unchecked("http://localhost/ex#me")],
@@ -87,18 +108,66 @@ mod test {
         );
         assert_eq!(
             g.triples_matching(
+                TermKind::BlankNode,
+                [&rdf::reifies],
                 (
                     [Iri::new_unchecked("http://localhost/ex#me")],
                     [Iri::new_unchecked("http://example.org/ns/knows")],
                     TermKind::BlankNode,
                 ),
+            )
+            .count(),
+            1
+        );
+        assert_eq!(
+            g.triples_matching(
+                TermKind::BlankNode,
                 [Iri::new_unchecked("http://example.org/ns/since")],
                 [2002],
             )
             .count(),
             1
         );
-        assert_eq!(g.blank_nodes().collect::<HashSet<_>>().len(), 1);
+        assert_eq!(g.blank_nodes().collect::<HashSet<_>>().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn prefixes_and_base() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let turtle = r#"
+            @prefix : <http://example.org/ns/> .
+            <#me> :knows [ :name "Alice" ] .
+            BASE <http://example.org/other/>
+            PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+            PREFIX rel: <rel/>
+            PREFIX : <http://example.org/ns2/>
+            <#me> foaf:name "me" .
+        "#;
+
+        let p = TurtleParser {
+            base: Some(Iri::new_unchecked("http://localhost/ex".to_string())),
+        };
+        let mut source = p.parse_str(turtle);
+        assert_eq!(source.base().unwrap().as_str(), "http://localhost/ex");
+        source.for_some_triple(&mut |_| {})?;
+        assert_eq!(source.prefix_map().len(), 1);
+        let mut g = MyGraph::new();
+        source.for_each_triple(|t| g.push(t))?;
+        assert_eq!(g.len(), 3);
+        assert_eq!(source.base().unwrap().as_str(), "http://example.org/other/");
+        let prefixes: Vec<_> = source
+            .prefix_map()
+            .into_iter()
+            .map(|(p, ns)| format!("{}={}", p.as_str(), ns.as_str()))
+            .collect();
+        assert_eq!(
+            prefixes,
+            [
+                "=http://example.org/ns2/",
+                "foaf=http://xmlns.com/foaf/0.1/",
+                "rel=http://example.org/other/rel/",
+            ]
+        );
         Ok(())
     }
 }
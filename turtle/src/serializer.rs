@@ -1,7 +1,10 @@
-//! Serializers for the Turtle-familt of RDF concrete syntaxes,
-//! based on [`rio_turtle`].
+//! Serializers for the Turtle-familt of RDF concrete syntaxes.
 
+#[cfg(feature = "async")]
+mod _async;
+mod _indexed;
 mod _pretty;
+mod _streaming;
 pub mod nq;
 pub mod nt;
 pub mod trig;
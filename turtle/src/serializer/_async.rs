@@ -0,0 +1,24 @@
+//! Utility code for the async serializers.
+
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// The size above which the output buffered by async serializers is written to their target.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Write the content of `buf` to `write`, and clear it,
+/// if it exceeds [`CHUNK_SIZE`] or if `all` is true.
+///
+/// Async serializers produce their output synchronously into `buf`
+/// (reusing the code of the synchronous serializers),
+/// and regularly call this function to write it out.
+pub(super) async fn write_chunk<W>(write: &mut W, buf: &mut Vec<u8>, all: bool) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    if all || buf.len() >= CHUNK_SIZE {
+        write.write_all(buf).await?;
+        buf.clear();
+    }
+    Ok(())
+}
@@ -0,0 +1,427 @@
+//! Utility code for pretty-printing Turtle and TriG directly from an indexed [`Dataset`].
+//!
+//! Contrarily to [`prettify`](super::_pretty::prettify),
+//! which requires a copy of all the quads in a [`PrettifiableDataset`](super::_pretty::PrettifiableDataset),
+//! and builds auxiliary structures covering all its subjects,
+//! the code below keeps a bounded amount of state
+//! (apart from the blank nodes occurring in quoted triples, which are usually few).
+//! Instead, it relies on [`Dataset::quads_matching`] to decide,
+//! each time it encounters a blank node, how that blank node should be written.
+//! It is therefore only efficient for datasets that index their quads.
+//!
+//! Blank nodes are nested and lists are written with the collection syntax
+//! under the same conditions as in [`prettify`](super::_pretty::prettify).
+//! In each cycle of nestable blank nodes, the smallest one is labelled,
+//! and used as the root of the others.
+//!
+//! The output is prettier when [`Dataset::quads`] yields quads grouped by graph name and subject
+//! (which is typically the case of indexed datasets),
+//! but it is correct in any case.
+
+use super::_pretty::{write_compact_iri, write_compact_literal, write_header};
+use super::turtle::TurtleConfig;
+use sophia_api::dataset::Dataset;
+use sophia_api::ns::rdf;
+use sophia_api::quad::{iter_spog, Quad};
+use sophia_api::source::{SinkError, SourceError, StreamResult};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{GraphName, SimpleTerm, Term, TermKind};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+type PResult<T, D> = StreamResult<T, <D as Dataset>::Error, io::Error>;
+
+/// Serialize `dataset` in pretty TriG on `write`, using the given `config`.
+///
+/// NB: `dataset` must not contain duplicate quads.
+/// If it only contains a default graph, the resulting TriG will be valid Turtle.
+pub fn prettify_indexed<D, W>(dataset: &D, write: W, config: &TurtleConfig) -> PResult<(), D>
+where
+    D: Dataset,
+    W: io::Write,
+{
+    let mut p = IndexedPrettifier::new(dataset, write, config)?;
+    p.write_all()
+}
+
+struct IndexedPrettifier<'a, D, W> {
+    dataset: &'a D,
+    write: W,
+    indent: String,
+    config: &'a TurtleConfig,
+    /// The identifiers of the blank nodes occurring in quoted triples
+    quoted: BTreeSet<String>,
+    /// The named graph whose block is currently open, if any
+    graph: Option<SimpleTerm<'static>>,
+    /// The root of the tree being written
+    root: Option<SimpleTerm<'static>>,
+}
+
+/// How a blank node must be written.
+enum BnodeUse {
+    /// With its label, because it is used several times as object, or in several graphs,
+    /// or as predicate or graph name, or in a quoted triple.
+    Labelled,
+    /// As `[]`, because it is never used as object (and only in one graph).
+    Anonymous,
+    /// Nested in the description of its only referrer.
+    Nested(SimpleTerm<'static>),
+}
+
+impl<'a, D: Dataset, W: Write> IndexedPrettifier<'a, D, W> {
+    fn new(dataset: &'a D, write: W, config: &'a TurtleConfig) -> PResult<Self, D> {
+        let mut quoted = BTreeSet::new();
+        for q in dataset.quads() {
+            for t in iter_spog(q.map_err(SourceError)?).filter(Term::is_triple) {
+                quoted.extend(
+                    t.atoms()
+                        .filter_map(|a| a.bnode_id().map(|id| id.as_str().to_string())),
+                );
+            }
+        }
+        Ok(Self {
+            dataset,
+            write,
+            indent: String::new(),
+            config,
+            quoted,
+            graph: None,
+            root: None,
+        })
+    }
+
+    fn write_all(&mut self) -> PResult<(), D> {
+        write_header(&mut self.write, self.config).map_err(SinkError)?;
+        let dataset = self.dataset;
+        // the graph name and subject of the previous quad,
+        // and the predicate and object of the first quad returned by quads_matching for them
+        let mut current: Option<(GraphName<SimpleTerm>, SimpleTerm, [SimpleTerm; 2])> = None;
+        for q in dataset.quads() {
+            let ([qs, qp, qo], qg) = q.map_err(SourceError)?.to_spog();
+            let s = qs.as_simple();
+            let g = qg.as_ref().map(Term::as_simple);
+            let same = current
+                .as_ref()
+                .is_some_and(|(cg, cs, _)| cg.as_ref() == g.as_ref() && cs == &s);
+            if !same {
+                let first = dataset
+                    .quads_matching([&s], Any, Any, [g.as_ref()])
+                    .next()
+                    .expect("a dataset should contain the quads it yields")
+                    .map_err(SourceError)?;
+                let po = [first.p().into_term(), first.o().into_term()];
+                current = Some((
+                    g.as_ref().map(|g| g.clone().into_term()),
+                    s.clone().into_term(),
+                    po,
+                ));
+            }
+            // The quads of a subject may not all be consecutive,
+            // so the subject is only described when its "first" quad is met.
+            // This ensures that it is described exactly once.
+            let [p0, o0] = &current.as_ref().unwrap().2;
+            if qp.as_simple() == *p0 && qo.as_simple() == *o0 {
+                self.write_subject(&s, g.as_ref())?;
+            }
+        }
+        if self.graph.take().is_some() {
+            self.unindent();
+            self.write_bytes(b"}\n")?;
+        }
+        self.write.flush().map_err(SinkError)?;
+        Ok(())
+    }
+
+    /// Describe `subject` in graph `g`, unless it is nested in the description of another node.
+    fn write_subject(&mut self, subject: &SimpleTerm, g: GraphName<&SimpleTerm>) -> PResult<(), D> {
+        if subject.is_blank_node() {
+            let nested = matches!(
+                self.bnode_use(subject).map_err(SourceError)?,
+                BnodeUse::Nested(_)
+            );
+            if nested && !self.is_cycle_root(subject).map_err(SourceError)? {
+                return Ok(());
+            }
+        }
+        if self.graph.as_ref() != g {
+            if self.graph.is_some() {
+                self.unindent();
+                self.write_bytes(b"}\n")?;
+            }
+            if let Some(name) = g {
+                self.write_newline()?;
+                self.write_bytes(b"GRAPH ")?;
+                self.write_term(name)?;
+                self.write_bytes(b" {")?;
+                self.indent();
+            }
+            self.graph = g.map(|name| name.clone().into_term());
+        }
+        self.write_tree(subject, g)
+    }
+
+    fn write_tree(&mut self, root: &SimpleTerm, g: GraphName<&SimpleTerm>) -> PResult<(), D> {
+        self.write_newline()?;
+        if root.is_blank_node()
+            && matches!(
+                self.bnode_use(root).map_err(SourceError)?,
+                BnodeUse::Anonymous
+            )
+        {
+            self.write_bytes(b"[]")?;
+        } else {
+            self.write_term(root)?;
+        }
+        self.root = Some(root.clone().into_term());
+        self.write_properties(root, g)?;
+        self.write_bytes(b".\n")
+    }
+
+    fn write_properties(
+        &mut self,
+        subject: &SimpleTerm,
+        g: GraphName<&SimpleTerm>,
+    ) -> PResult<(), D> {
+        let dataset = self.dataset;
+        let mut predicate: Option<SimpleTerm> = None;
+        self.indent(); // to predicate-level
+        for q in dataset.quads_matching([subject], [rdf::type_], Any, [g]) {
+            let q = q.map_err(SourceError)?;
+            if predicate.is_none() {
+                predicate = Some(rdf::type_.into_term());
+                self.write_bytes(b" a ")?;
+                self.indent(); // to object-level
+            } else {
+                self.write_bytes(b",")?;
+                self.write_newline()?;
+            }
+            self.write_object(&q.o().as_simple(), g)?;
+        }
+        for q in dataset.quads_matching([subject], Any, Any, [g]) {
+            let q = q.map_err(SourceError)?;
+            let qp = q.p();
+            let p = qp.as_simple();
+            if rdf::type_ == p {
+                continue;
+            }
+            if predicate.as_ref() != Some(&p) {
+                if predicate.is_some() {
+                    self.write_bytes(b";")?;
+                    self.unindent(); // back to predicate-level
+                }
+                self.write_newline()?;
+                self.write_term(&p)?;
+                self.write_bytes(b" ")?;
+                self.indent(); // to object-level
+                predicate = Some(p.into_term());
+            } else {
+                self.write_bytes(b",")?;
+                self.write_newline()?;
+            }
+            self.write_object(&q.o().as_simple(), g)?;
+        }
+        if predicate.is_some() {
+            self.unindent(); // back to predicate-level
+        }
+        self.unindent(); // back to original level
+        Ok(())
+    }
+
+    fn write_object(&mut self, object: &SimpleTerm, g: GraphName<&SimpleTerm>) -> PResult<(), D> {
+        if object.is_blank_node()
+            && self.root.as_ref() != Some(object)
+            && matches!(
+                self.bnode_use(object).map_err(SourceError)?,
+                BnodeUse::Nested(_)
+            )
+        {
+            if self.is_list(object, g)? {
+                self.write_list(object, g)
+            } else {
+                self.write_bytes(b"[")?;
+                self.write_properties(object, g)?;
+                self.write_bytes(b"]")
+            }
+        } else {
+            self.write_term(object)
+        }
+    }
+
+    /// Pre-condition: `head` is the head of a well-formed list (see [`Self::is_list`]).
+    fn write_list(&mut self, head: &SimpleTerm, g: GraphName<&SimpleTerm>) -> PResult<(), D> {
+        self.write_bytes(b"(")?;
+        self.indent();
+        let mut node = head.clone().into_term();
+        while let Some([item, rest]) = self.list_node(&node, g).map_err(SourceError)? {
+            self.write_newline()?;
+            self.write_object(&item, g)?;
+            node = rest;
+        }
+        self.unindent();
+        self.write_newline()?;
+        self.write_bytes(b")")
+    }
+
+    fn write_term(&mut self, term: &SimpleTerm) -> PResult<(), D> {
+        use TermKind::*;
+        match term.kind() {
+            Iri => write_compact_iri(&mut self.write, &term.iri().unwrap(), self.config),
+            BlankNode => write!(self.write, "_:{}", term.bnode_id().unwrap().as_str()),
+            Literal => write_compact_literal(&mut self.write, term, self.config),
+            Variable => write!(self.write, "?{}", term.variable().unwrap().as_str()),
+            Triple => {
+                self.write_bytes(b"<<( ")?;
+                for t in term.triple().unwrap() {
+                    self.write_term(t)?;
+                    self.write_bytes(b" ")?;
+                }
+                return self.write_bytes(b")>>");
+            }
+        }
+        .map_err(SinkError)
+    }
+
+    fn write_newline(&mut self) -> PResult<(), D> {
+        self.write_bytes(b"\n")?;
+        self.write
+            .write_all(self.indent.as_bytes())
+            .map_err(SinkError)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> PResult<(), D> {
+        self.write.write_all(bytes).map_err(SinkError)
+    }
+
+    fn indent(&mut self) {
+        self.indent.push_str(self.config.indentation());
+    }
+
+    fn unindent(&mut self) {
+        let ilen = self.config.indentation().len();
+        self.indent.truncate(self.indent.len() - ilen);
+    }
+
+    /// Determine how the blank node `bnode` must be written (see [`BnodeUse`]).
+    fn bnode_use(&self, bnode: &SimpleTerm) -> Result<BnodeUse, D::Error> {
+        let d = self.dataset;
+        if self.quoted.contains(bnode.bnode_id().unwrap().as_str())
+            || d.quads_matching(Any, [bnode], Any, Any)
+                .next()
+                .transpose()?
+                .is_some()
+            || d.quads_matching(Any, Any, Any, [Some(bnode)])
+                .next()
+                .transpose()?
+                .is_some()
+        {
+            return Ok(BnodeUse::Labelled);
+        }
+        let mut referrers = d.quads_matching(Any, Any, [bnode], Any);
+        let (referrer, mut graph) = match (referrers.next().transpose()?, referrers.next()) {
+            (None, _) => (None, None),
+            (Some(q), None) => (
+                Some(q.s().into_term()),
+                Some(q.g().map(Term::into_term::<SimpleTerm<'static>>)),
+            ),
+            (Some(_), Some(_)) => return Ok(BnodeUse::Labelled),
+        };
+        // all the quads describing bnode must be in the same graph as the one referring to it
+        for q in d.quads_matching([bnode], Any, Any, Any) {
+            let g = q?.g().map(|g| g.into_term());
+            match &graph {
+                None => graph = Some(g),
+                Some(g0) if *g0 == g => {}
+                Some(_) => return Ok(BnodeUse::Labelled),
+            }
+        }
+        Ok(match referrer {
+            None => BnodeUse::Anonymous,
+            Some(s) => BnodeUse::Nested(s),
+        })
+    }
+
+    /// Whether `bnode` is part of a cycle of nestable blank nodes,
+    /// in which it is the smallest (so it must be the root of the others).
+    ///
+    /// This walks up the chain of referrers in constant memory,
+    /// using Brent's algorithm to detect cycles that do not contain `bnode`.
+    fn is_cycle_root(&self, bnode: &SimpleTerm) -> Result<bool, D::Error> {
+        let BnodeUse::Nested(mut current) = self.bnode_use(bnode)? else {
+            return Ok(false);
+        };
+        let mut tortoise: Option<SimpleTerm> = None;
+        let (mut power, mut lambda) = (1, 0);
+        loop {
+            if current == *bnode {
+                return Ok(true);
+            }
+            // if bnode is in a cycle, so are all its successive referrers,
+            // so meeting a smaller one means that bnode is not the smallest of its cycle
+            if !current.is_blank_node()
+                || Term::cmp(&current, bnode).is_lt()
+                || tortoise.as_ref() == Some(&current)
+            {
+                return Ok(false);
+            }
+            let BnodeUse::Nested(next) = self.bnode_use(&current)? else {
+                return Ok(false);
+            };
+            lambda += 1;
+            if lambda == power {
+                tortoise = Some(current);
+                power *= 2;
+                lambda = 0;
+            }
+            current = next;
+        }
+    }
+
+    /// Whether `head` (a nestable blank node) is the head of a well-formed list,
+    /// i.e. a chain of list nodes (see [`Self::list_node`]) ending with `rdf:nil`.
+    fn is_list(&self, head: &SimpleTerm, g: GraphName<&SimpleTerm>) -> PResult<bool, D> {
+        let mut node = head.clone().into_term();
+        loop {
+            let Some([_, rest]) = self.list_node(&node, g).map_err(SourceError)? else {
+                return Ok(false);
+            };
+            if rdf::nil == rest {
+                return Ok(true);
+            }
+            if !rest.is_blank_node()
+                || self.root.as_ref() == Some(&rest)
+                || !matches!(
+                    self.bnode_use(&rest).map_err(SourceError)?,
+                    BnodeUse::Nested(_)
+                )
+            {
+                return Ok(false);
+            }
+            node = rest;
+        }
+    }
+
+    /// If `node` is described in `g` by exactly one `rdf:first` and one `rdf:rest`, return their objects.
+    fn list_node(
+        &self,
+        node: &SimpleTerm,
+        g: GraphName<&SimpleTerm>,
+    ) -> Result<Option<[SimpleTerm<'static>; 2]>, D::Error> {
+        let mut first = None;
+        let mut rest = None;
+        for q in self.dataset.quads_matching([node], Any, Any, [g]) {
+            let q = q?;
+            let slot = if rdf::first == q.p() {
+                &mut first
+            } else if rdf::rest == q.p() {
+                &mut rest
+            } else {
+                return Ok(None);
+            };
+            if slot.is_some() {
+                return Ok(None);
+            }
+            *slot = Some(q.o().into_term());
+        }
+        Ok(first.zip(rest).map(|(first, rest)| [first, rest]))
+    }
+}
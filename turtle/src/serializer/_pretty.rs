@@ -1,5 +1,9 @@
 //! Utility code for pretty-printing Turtle and TriG.
 //!
+//! This requires a copy of the whole dataset in memory;
+//! see [`_indexed`](super::_indexed) for an alternative working in bounded memory
+//! on datasets providing efficient index access.
+//!
 //! Possible improvements:
 //! 1. PrettifiableDataset should encapsulate some of the "indexes" built by Prettifier
 //! (labelled, subject_types, named_graphs)
@@ -18,7 +22,6 @@ use sophia_api::prefix::PrefixMap;
 use sophia_api::quad::{iter_spog, Gspo, Quad, Spog};
 use sophia_api::term::matcher::Any;
 use sophia_api::term::{GraphName, SimpleTerm, Term, TermKind};
-use sophia_api::triple::Triple;
 use sophia_api::MownStr;
 use sophia_iri::{Iri, IriRef};
 use std::cmp::Ordering;
@@ -43,7 +46,7 @@ where
     W: io::Write,
 {
     assert!(base_indent.chars().all(char::is_whitespace));
-    write_prefixes(&mut write, &config.prefix_map[..])?;
+    write_header(&mut write, config)?;
 
     let mut p = Prettifier::new(&dataset, &mut write, base_indent.into(), config);
     p.write_all()?;
@@ -51,13 +54,15 @@ where
     Ok(())
 }
 
-/// write the prefix declarations of the given prefix_map, using SPARQL style.
-fn write_prefixes<W, P>(mut write: W, prefix_map: &P) -> io::Result<()>
+/// write the base and prefix declarations of the given config, using SPARQL style.
+pub(super) fn write_header<W>(mut write: W, config: &TurtleConfig) -> io::Result<()>
 where
     W: io::Write,
-    P: PrefixMap + ?Sized,
 {
-    for (pre, iri) in prefix_map.iter() {
+    if let Some(base) = config.base() {
+        writeln!(&mut write, "BASE <{}>", base.as_str())?;
+    }
+    for (pre, iri) in config.prefix_map.iter() {
         writeln!(&mut write, "PREFIX {}: <{}>", pre.as_str(), iri.as_str())?;
     }
     Ok(())
@@ -195,7 +200,7 @@ impl<'a, W: Write> Prettifier<'a, W> {
         if !types.is_empty() {
             self.write_bytes(b" a ")?;
             self.indent(); // to object-level
-            self.write_objects(subject, predicate.unwrap(), &types)?;
+            self.write_objects(&types)?;
         }
         // NB: we know that PrettifiableDataset<'_> iterates triples grouped by predicate
         // (it uses a GSPO index)
@@ -222,7 +227,7 @@ impl<'a, W: Write> Prettifier<'a, W> {
                 self.write_bytes(b",")?;
                 self.write_newline()?;
             }
-            self.write_object(subject, predicate.unwrap(), t.o())?;
+            self.write_term(t.o())?;
         }
         if predicate.is_some() {
             self.unindent(); // back to predicate-level
@@ -231,41 +236,12 @@ impl<'a, W: Write> Prettifier<'a, W> {
         Ok(())
     }
 
-    fn write_objects(
-        &mut self,
-        subject: &'a SimpleTerm<'a>,
-        predicate: &'a SimpleTerm<'a>,
-        objects: &[&'a SimpleTerm<'a>],
-    ) -> io::Result<()> {
-        self.write_object(subject, predicate, objects[0])?;
+    fn write_objects(&mut self, objects: &[&'a SimpleTerm<'a>]) -> io::Result<()> {
+        self.write_term(objects[0])?;
         for obj in &objects[1..] {
             self.write_bytes(b",")?;
             self.write_newline()?;
-            self.write_object(subject, predicate, obj)?;
-        }
-        Ok(())
-    }
-
-    fn write_object(
-        &mut self,
-        subject: &'a SimpleTerm<'a>,
-        predicate: &'a SimpleTerm<'a>,
-        object: &'a SimpleTerm<'a>,
-    ) -> io::Result<()> {
-        self.write_term(object)?;
-        let tr = SimpleTerm::Triple(Box::new([
-            subject.clone(),
-            predicate.clone(),
-            object.clone(),
-        ]));
-        if let Some(i) = self.find_st_index(tr) {
-            let (_, s, st) = self.subject_types[i];
-            if st == SubjectType::Annotation {
-                self.write_bytes(b" {|")?;
-                self.write_properties(s)?;
-                self.write_bytes(b" |}")?;
-                self.subject_types[i].2 = SubjectType::Done;
-            }
+            self.write_term(obj)?;
         }
         Ok(())
     }
@@ -280,35 +256,18 @@ impl<'a, W: Write> Prettifier<'a, W> {
                 write!(&mut self.write, "?{}", term.variable().unwrap().as_str())
             }
             Triple => {
-                self.write_bytes(b"<< ")?;
+                self.write_bytes(b"<<( ")?;
                 for t in term.triple().unwrap() {
                     self.write_term(t)?;
                     self.write_bytes(b" ")?;
                 }
-                self.write_bytes(b">>")
+                self.write_bytes(b")>>")
             }
         }
     }
 
     fn write_iri(&mut self, iri: &IriRef<MownStr>) -> io::Result<()> {
-        if rdf::nil == iri {
-            return self.write_bytes(b"()");
-        }
-        let Some(iri) = Iri::new(iri.as_str()).ok() else {
-            return write!(self.write, "<{}>", iri.as_str());
-        };
-        match self
-            .config
-            .prefix_map
-            .get_checked_prefixed_pair(iri, |txt| PN_LOCAL.is_match(txt))
-        {
-            Some((pre, suf)) => {
-                write!(self.write, "{}:{}", pre.as_str(), suf)
-            }
-            None => {
-                write!(self.write, "<{}>", iri.as_str())
-            }
-        }
+        write_compact_iri(&mut self.write, iri, self.config)
     }
 
     fn write_bnode(&mut self, bn: &'a SimpleTerm<'a>) -> io::Result<()> {
@@ -345,27 +304,7 @@ impl<'a, W: Write> Prettifier<'a, W> {
     }
 
     fn write_literal(&mut self, lit: &'a SimpleTerm<'a>) -> io::Result<()> {
-        debug_assert!(lit.kind() == TermKind::Literal);
-        let datatype = lit.datatype().unwrap();
-        let value = lit.lexical_form().unwrap();
-        if xsd::integer == datatype && INTEGER.is_match(&value)
-            || xsd::decimal == datatype && DECIMAL.is_match(&value)
-            || xsd::double == datatype && DOUBLE.is_match(&value)
-            || xsd::boolean == datatype && BOOLEAN.is_match(&value)
-        {
-            self.write_bytes(value.as_bytes())?;
-        } else {
-            self.write_bytes(b"\"")?;
-            super::nt::quoted_string(&mut self.write, value.as_bytes())?;
-            self.write_bytes(b"\"")?;
-            if let Some(tag) = lit.language_tag() {
-                write!(self.write, "@{}", tag.as_str())?;
-            } else if xsd::string != datatype {
-                self.write_bytes(b"^^")?;
-                self.write_iri(&datatype)?;
-            }
-        }
-        Ok(())
+        write_compact_literal(&mut self.write, lit, self.config)
     }
 
     fn write_newline(&mut self) -> io::Result<()> {
@@ -449,13 +388,6 @@ fn build_labelled<'a>(d: &'a PrettifiableDataset) -> BTreeSet<&'a SimpleTerm<'a>
                         });
                 }
                 TermKind::Triple => {
-                    let mut atoms = t.atoms();
-                    let [s, p, o] = t.triple().unwrap().spo();
-                    if s.is_blank_node() && Dataset::contains(d, s, p, o, q.g()).unwrap() {
-                        atoms.next(); // skip the subject blank nodes in atoms
-                                      // and leave it to the "asserted" blank node to determine
-                                      // if it must be labelled or not
-                    }
                     for a in t.atoms().filter(Term::is_blank_node) {
                         profiles
                             .entry(a)
@@ -555,17 +487,6 @@ fn build_subject_types<'a>(
                         SubjectType::Root
                     }
                 }
-                Triple => {
-                    let tr = s.triple().unwrap();
-                    if rdf::first != tr.p()
-                        && rdf::rest != tr.p()
-                        && Dataset::contains(d, tr.s(), tr.p(), tr.o(), g).unwrap()
-                    {
-                        SubjectType::Annotation
-                    } else {
-                        SubjectType::Root
-                    }
-                }
                 _ => SubjectType::Root,
             };
             ((g, s), st)
@@ -580,8 +501,6 @@ enum SubjectType {
     Root,
     /// A node that can be used as a subtree (square brackets with property list)
     SubTree,
-    /// A quoted triple that is also asserted
-    Annotation,
     /// A dummy subject type, to indicate that this subject has been serialized already
     Done,
 }
@@ -663,6 +582,70 @@ fn find_subject<T: Term>(s: T, swt: &SubjectsWithType) -> Option<usize> {
     }
 }
 
+/// Write `iri` as a prefixed name if the prefix map of `config` allows it
+/// (or as `()` if it is `rdf:nil`),
+/// as an IRI reference relative to the base of `config` if possible,
+/// as an absolute IRI reference otherwise.
+pub(super) fn write_compact_iri<W>(
+    mut write: W,
+    iri: &IriRef<MownStr>,
+    config: &TurtleConfig,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    if rdf::nil == iri {
+        return write.write_all(b"()");
+    }
+    let Some(iri) = Iri::new(iri.as_str()).ok() else {
+        return write!(write, "<{}>", iri.as_str());
+    };
+    let prefix_map = &config.prefix_map[..];
+    if let Some((pre, suf)) =
+        prefix_map.get_checked_prefixed_pair(iri, |txt| PN_LOCAL.is_match(txt))
+    {
+        return write!(write, "{}:{}", pre.as_str(), suf);
+    }
+    match config.base.as_ref().and_then(|base| base.relativize(iri)) {
+        Some(rel) => write!(write, "<{}>", rel.as_str()),
+        None => write!(write, "<{}>", iri.as_str()),
+    }
+}
+
+/// Write literal `lit`, using the shorthand syntax of numbers and booleans when possible,
+/// and writing its datatype with [`write_compact_iri`].
+pub(super) fn write_compact_literal<W, T>(
+    mut write: W,
+    lit: T,
+    config: &TurtleConfig,
+) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    debug_assert!(lit.kind() == TermKind::Literal);
+    let datatype = lit.datatype().unwrap();
+    let value = lit.lexical_form().unwrap();
+    if xsd::integer == datatype && INTEGER.is_match(&value)
+        || xsd::decimal == datatype && DECIMAL.is_match(&value)
+        || xsd::double == datatype && DOUBLE.is_match(&value)
+        || xsd::boolean == datatype && BOOLEAN.is_match(&value)
+    {
+        write.write_all(value.as_bytes())?;
+    } else {
+        write.write_all(b"\"")?;
+        super::nt::quoted_string(&mut write, value.as_bytes())?;
+        write.write_all(b"\"")?;
+        if let Some(tag) = lit.language_tag() {
+            write!(write, "@{}", tag.as_str())?;
+        } else if xsd::string != datatype {
+            write.write_all(b"^^")?;
+            write_compact_iri(&mut write, &datatype, config)?;
+        }
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------------
 //                                      inners
 // ---------------------------------------------------------------------------------
@@ -0,0 +1,177 @@
+//! Utility code for serializing Turtle and TriG in streaming mode.
+
+use super::_pretty::{write_compact_iri, write_compact_literal, write_header};
+use super::nt;
+use super::turtle::TurtleConfig;
+use sophia_api::ns::rdf;
+use sophia_api::term::{GraphName, SimpleTerm, Term, TermKind};
+use sophia_api::triple::Triple;
+use std::io;
+
+/// Write quads in TriG (or triples in Turtle, if all graph names are `None`),
+/// factorizing the subject and predicate shared with the previous quad.
+///
+/// The base and prefixes of the [config](TurtleConfig) are declared first,
+/// and used to abbreviate IRIs;
+/// `rdf:type` is abbreviated as `a`, and numbers and booleans are written in their short form.
+///
+/// NB: non-standard (generalized) RDF quads are silently ignored.
+pub(super) struct StreamingWriter<'a, W> {
+    write: W,
+    config: &'a TurtleConfig,
+    /// Whether the base and prefix declarations have been written
+    started: bool,
+    /// The graph name of the previous quad, if any
+    graph: Option<GraphName<SimpleTerm<'static>>>,
+    /// The subject and predicate of the previous quad, if its statement is not terminated yet
+    subject_predicate: Option<[SimpleTerm<'static>; 2]>,
+}
+
+impl<'a, W: io::Write> StreamingWriter<'a, W> {
+    pub fn new(write: W, config: &'a TurtleConfig) -> Self {
+        StreamingWriter {
+            write,
+            config,
+            started: false,
+            graph: None,
+            subject_predicate: None,
+        }
+    }
+
+    /// The underlying writer.
+    #[cfg(feature = "async")]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.write
+    }
+
+    pub fn write_triple<T: Triple>(&mut self, t: T) -> io::Result<()> {
+        let [s, p, o] = t.to_spo();
+        self.write_quad([s, p, o], None)
+    }
+
+    pub fn write_quad<T: Term>(&mut self, [s, p, o]: [T; 3], g: GraphName<T>) -> io::Result<()> {
+        if !is_standard_triple(&s.as_simple(), &p.as_simple(), &o.as_simple())
+            || !g.as_ref().is_none_or(|g| is_resource(g.kind()))
+        {
+            return Ok(());
+        }
+        self.start()?;
+        let same_graph = self.graph.as_ref().is_some_and(|cg| match (cg, &g) {
+            (None, None) => true,
+            (Some(cg), Some(g)) => Term::eq(cg, g.borrow_term()),
+            _ => false,
+        });
+        if !same_graph {
+            self.finish()?;
+            if let Some(g) = &g {
+                self.write_term(g.borrow_term())?;
+                self.write.write_all(b" {\n")?;
+            }
+            self.graph = Some(g.map(Term::into_term));
+        }
+        let indentation = self.config.indentation().as_bytes();
+        let indent = match self.graph {
+            Some(Some(_)) => 1,
+            _ => 0,
+        };
+        match &self.subject_predicate {
+            Some([cs, cp]) if Term::eq(cs, s.borrow_term()) => {
+                if Term::eq(cp, p.borrow_term()) {
+                    self.write.write_all(b" ,\n")?;
+                    self.write.write_all(&indentation.repeat(indent + 2))?;
+                } else {
+                    self.write.write_all(b" ;\n")?;
+                    self.write.write_all(&indentation.repeat(indent + 1))?;
+                    self.write_predicate(p.borrow_term())?;
+                    self.write.write_all(b" ")?;
+                }
+            }
+            _ => {
+                self.end_statement()?;
+                self.write.write_all(&indentation.repeat(indent))?;
+                self.write_term(s.borrow_term())?;
+                self.write.write_all(b" ")?;
+                self.write_predicate(p.borrow_term())?;
+                self.write.write_all(b" ")?;
+            }
+        }
+        self.write_term(o.borrow_term())?;
+        self.subject_predicate = Some([s.into_term(), p.into_term()]);
+        Ok(())
+    }
+
+    /// Terminate the current statement and graph, if any.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.end_statement()?;
+        if let Some(Some(_)) = self.graph.take() {
+            self.write.write_all(b"}\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write the base and prefix declarations, if not done yet.
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            write_header(&mut self.write, self.config)?;
+            if self.config.base().is_some() || !self.config.prefix_map.is_empty() {
+                self.write.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end_statement(&mut self) -> io::Result<()> {
+        if self.subject_predicate.take().is_some() {
+            self.write.write_all(b" .\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_predicate<T: Term>(&mut self, p: T) -> io::Result<()> {
+        if rdf::type_ == p {
+            self.write.write_all(b"a")
+        } else {
+            self.write_term(p)
+        }
+    }
+
+    fn write_term<T: Term>(&mut self, t: T) -> io::Result<()> {
+        write_term(&mut self.write, t, self.config)
+    }
+}
+
+/// Write `t` in Turtle, using the base and prefixes of `config` to abbreviate IRIs.
+fn write_term<W, T>(write: &mut W, t: T, config: &TurtleConfig) -> io::Result<()>
+where
+    W: io::Write,
+    T: Term,
+{
+    match t.kind() {
+        TermKind::Iri => write_compact_iri(write, &t.iri().unwrap(), config),
+        TermKind::Literal => write_compact_literal(write, t, config),
+        TermKind::Triple => {
+            write.write_all(b"<<( ")?;
+            for t in t.to_triple().unwrap() {
+                write_term(write, t, config)?;
+                write.write_all(b" ")?;
+            }
+            write.write_all(b")>>")
+        }
+        _ => nt::write_term(write, t),
+    }
+}
+
+fn is_resource(kind: TermKind) -> bool {
+    matches!(kind, TermKind::Iri | TermKind::BlankNode)
+}
+
+fn is_standard_triple(s: &SimpleTerm, p: &SimpleTerm, o: &SimpleTerm) -> bool {
+    is_resource(s.kind())
+        && p.kind() == TermKind::Iri
+        && match o {
+            SimpleTerm::Triple(spo) => is_standard_triple(&spo[0], &spo[1], &spo[2]),
+            _ => o.kind() != TermKind::Variable,
+        }
+}
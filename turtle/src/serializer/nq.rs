@@ -15,6 +15,13 @@ use sophia_api::serializer::*;
 use sophia_api::source::{QuadSource, StreamResult};
 use std::io;
 
+#[cfg(feature = "async")]
+use super::_async::write_chunk;
+#[cfg(feature = "async")]
+use sophia_api::source::{AsyncQuadSource, SinkError};
+#[cfg(feature = "async")]
+use std::io::Write;
+
 /// N-Quads serializer configuration.
 pub type NqConfig = super::nt::NtConfig;
 
@@ -24,10 +31,7 @@ pub struct NqSerializer<W> {
     write: W,
 }
 
-impl<W> NqSerializer<W>
-where
-    W: io::Write,
-{
+impl<W> NqSerializer<W> {
     /// Build a new N-Quads serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> NqSerializer<W> {
@@ -82,6 +86,47 @@ where
     }
 }
 
+#[cfg(feature = "async")]
+impl<W> AsyncQuadSerializer for NqSerializer<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    async fn serialize_quads_async<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: AsyncQuadSource,
+    {
+        if self.config.ascii {
+            todo!("Pure-ASCII N-Quads is not implemented yet")
+        }
+        let mut buf = vec![];
+        while source
+            .try_for_some_quad(|q| {
+                let (tr, gn) = q.spog();
+                write_triple(&mut buf, tr)?;
+                if let Some(t) = gn {
+                    buf.write_all(b" ")?;
+                    write_term(&mut buf, t)?;
+                }
+                buf.write_all(b".\n")
+            })
+            .await?
+        {
+            write_chunk(&mut self.write, &mut buf, false)
+                .await
+                .map_err(SinkError)?;
+        }
+        write_chunk(&mut self.write, &mut buf, true)
+            .await
+            .map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
 impl NqSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -168,9 +213,18 @@ pub(crate) mod test {
 _:me <http://schema.org/name> "Pierre-Antoine" _:me.
 _:me <http://example.org/value> "42"^^<http://www.w3.org/2001/XMLSchema#integer> _:me.
 _:me <http://example.org/message> "hello\nworld"@en <tag:g1>.
-<<_:me <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>>> <http://schema.org/creator> ?x.
+<<( _:me <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> )>> <http://schema.org/creator> ?x.
 "#
         );
+
+        #[cfg(feature = "async")]
+        {
+            let mut serializer = NqSerializer::new(vec![]);
+            tokio::runtime::Builder::new_current_thread()
+                .build()?
+                .block_on(serializer.serialize_dataset_async(&d))?;
+            assert_eq!(serializer.as_str(), s);
+        }
         Ok(())
     }
 }
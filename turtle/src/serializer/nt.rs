@@ -16,6 +16,13 @@ use sophia_api::term::{Term, TermKind};
 use sophia_api::triple::Triple;
 use std::io;
 
+#[cfg(feature = "async")]
+use super::_async::write_chunk;
+#[cfg(feature = "async")]
+use sophia_api::source::{AsyncTripleSource, SinkError};
+#[cfg(feature = "async")]
+use std::io::Write;
+
 /// N-Triples serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct NtConfig {
@@ -36,10 +43,7 @@ pub struct NtSerializer<W> {
     write: W,
 }
 
-impl<W> NtSerializer<W>
-where
-    W: io::Write,
-{
+impl<W> NtSerializer<W> {
     /// Build a new N-Triples serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> NtSerializer<W> {
@@ -86,6 +90,42 @@ where
     }
 }
 
+#[cfg(feature = "async")]
+impl<W> AsyncTripleSerializer for NtSerializer<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    async fn serialize_triples_async<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: AsyncTripleSource,
+    {
+        if self.config.ascii {
+            todo!("Pure-ASCII N-Triples is not implemented yet")
+        }
+        let mut buf = vec![];
+        while source
+            .try_for_some_triple(|t| {
+                write_triple(&mut buf, t)?;
+                buf.write_all(b".\n")
+            })
+            .await?
+        {
+            write_chunk(&mut self.write, &mut buf, false)
+                .await
+                .map_err(SinkError)?;
+        }
+        write_chunk(&mut self.write, &mut buf, true)
+            .await
+            .map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
 impl NtSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -157,9 +197,9 @@ where
             }
         }
         Triple => {
-            w.write_all(b"<<")?;
+            w.write_all(b"<<( ")?;
             write_triple(w, t.to_triple().unwrap())?;
-            w.write_all(b">>")?;
+            w.write_all(b" )>>")?;
         }
         Variable => {
             w.write_all(b"?")?;
@@ -266,9 +306,18 @@ pub(crate) mod test {
 _:me <http://schema.org/name> "Pierre-Antoine".
 _:me <http://example.org/value> "42"^^<http://www.w3.org/2001/XMLSchema#integer>.
 _:me <http://example.org/message> "hello\nworld"@en.
-<<_:me <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>>> <http://schema.org/creator> ?x.
+<<( _:me <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> )>> <http://schema.org/creator> ?x.
 "#
         );
+
+        #[cfg(feature = "async")]
+        {
+            let mut serializer = NtSerializer::new(vec![]);
+            tokio::runtime::Builder::new_current_thread()
+                .build()?
+                .block_on(serializer.serialize_graph_async(&g))?;
+            assert_eq!(serializer.as_str(), s);
+        }
         Ok(())
     }
 }
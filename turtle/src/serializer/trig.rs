@@ -9,15 +9,21 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use rio_turtle::TriGFormatter;
+use sophia_api::dataset::SetDataset;
 use sophia_api::quad::Quad;
 use sophia_api::serializer::{QuadSerializer, Stringifier};
 use sophia_api::source::{QuadSource, SinkError, SourceError, StreamResult};
 use sophia_api::term::Term;
-use sophia_rio::serializer::rio_format_quads;
 use std::io;
 
+use super::_indexed::prettify_indexed;
 pub(super) use super::_pretty::*;
+use super::_streaming::StreamingWriter;
+
+#[cfg(feature = "async")]
+use super::_async::write_chunk;
+#[cfg(feature = "async")]
+use sophia_api::{serializer::AsyncQuadSerializer, source::AsyncQuadSource};
 
 /// Trig serializer configuration.
 pub type TrigConfig = super::turtle::TurtleConfig;
@@ -28,10 +34,7 @@ pub struct TrigSerializer<W> {
     pub(super) write: W,
 }
 
-impl<W> TrigSerializer<W>
-where
-    W: io::Write,
-{
+impl<W> TrigSerializer<W> {
     /// Build a new Trig serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> TrigSerializer<W> {
@@ -49,6 +52,32 @@ where
     }
 }
 
+impl<W> TrigSerializer<W>
+where
+    W: io::Write,
+{
+    /// Serialize a whole [`Dataset`](sophia_api::dataset::Dataset) in pretty TriG,
+    /// whatever the [`pretty`](TrigConfig::pretty) flag of the config.
+    ///
+    /// Contrarily to [`serialize_dataset`](QuadSerializer::serialize_dataset) in pretty mode,
+    /// this does not copy `dataset` in memory, but explores it with
+    /// [`quads_matching`](sophia_api::dataset::Dataset::quads_matching),
+    /// so that memory usage remains bounded.
+    /// This is intended for big datasets providing efficient index access
+    /// (e.g. those of `sophia_inmem` or `sophia_disk`);
+    /// with other datasets, it may be much slower than the other serialization methods.
+    pub fn serialize_indexed_dataset<D>(
+        &mut self,
+        dataset: &D,
+    ) -> StreamResult<&mut Self, D::Error, io::Error>
+    where
+        D: SetDataset,
+    {
+        prettify_indexed(dataset, &mut self.write, &self.config)?;
+        Ok(self)
+    }
+}
+
 impl<W> QuadSerializer for TrigSerializer<W>
 where
     W: io::Write,
@@ -74,10 +103,62 @@ where
                 .map_err(SourceError)?;
             prettify(dataset, &mut self.write, &self.config, "").map_err(SinkError)?;
         } else {
-            let mut tf = TriGFormatter::new(&mut self.write);
-            rio_format_quads(&mut tf, source)?;
-            tf.finish().map_err(SinkError)?;
+            let mut writer = StreamingWriter::new(&mut self.write, &self.config);
+            source.try_for_each_quad(|q| {
+                let (spo, g) = q.spog();
+                writer.write_quad(spo, g)
+            })?;
+            writer.finish().map_err(SinkError)?;
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<W> AsyncQuadSerializer for TrigSerializer<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    async fn serialize_quads_async<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: AsyncQuadSource,
+    {
+        let mut buf = vec![];
+        if self.config.pretty {
+            let mut dataset = PrettifiableDataset::new();
+            source
+                .for_each_quad(|t| {
+                    let (spo, g) = t.spog();
+                    let spo = spo.map(|t| t.into_term());
+                    let g = g.map(|t| t.into_term());
+                    dataset.insert((g, spo));
+                })
+                .await
+                .map_err(SourceError)?;
+            prettify(dataset, &mut buf, &self.config, "").map_err(SinkError)?;
+        } else {
+            let mut writer = StreamingWriter::new(&mut buf, &self.config);
+            while source
+                .try_for_some_quad(|q| {
+                    let (spo, g) = q.spog();
+                    writer.write_quad(spo, g)
+                })
+                .await?
+            {
+                write_chunk(&mut self.write, writer.get_mut(), false)
+                    .await
+                    .map_err(SinkError)?;
+            }
+            writer.finish().map_err(SinkError)?;
         }
+        write_chunk(&mut self.write, &mut buf, true)
+            .await
+            .map_err(SinkError)?;
         Ok(self)
     }
 }
@@ -110,7 +191,9 @@ pub(crate) mod test {
     use super::*;
     use sophia_api::term::SimpleTerm;
     use sophia_api::{dataset::Dataset, quad::Spog};
+    use sophia_inmem::dataset::FastDataset;
     use sophia_isomorphism::isomorphic_datasets;
+    use std::collections::HashSet;
     use std::error::Error;
 
     const TESTS: &[&str] = &[
@@ -144,11 +227,14 @@ pub(crate) mod test {
                 _:c :b "c"; :t _:c.
             }
         "#,
-        r#"# quoted triples
+        r#"# triple terms and reifiers
             PREFIX : <http://example.org/ns/>
             GRAPH <tag:g> {
+                :s :p <<( :s :p :o0 )>>.
                 << :s :p :o1 >> :a :b.
+                << :s :p :o1 ~ :r1 >> :a :b.
                 :s :p :o2 {| :c :d |}.
+                :s :p :o3 ~ :r3 {| :c :d |}, :o4 ~ ~:r4.
             }
         "#,
         r#"# blank node graph name
@@ -194,6 +280,32 @@ pub(crate) mod test {
         Ok(())
     }
 
+    #[test]
+    fn streaming_compact() -> Result<(), Box<dyn Error>> {
+        let trig = r#"
+            PREFIX : <http://example.org/ns/>
+            :alice a :Person.
+            GRAPH :g { :bob a :Person; :age 42, 43. }
+        "#;
+        let d: Vec<Spog<SimpleTerm>> = crate::parser::trig::parse_str(trig).collect_quads()?;
+        let out = TrigSerializer::new_stringifier()
+            .serialize_quads(d.quads())?
+            .to_string();
+        let expected = r#"PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+
+<http://example.org/ns/alice> a <http://example.org/ns/Person> .
+<http://example.org/ns/g> {
+  <http://example.org/ns/bob> a <http://example.org/ns/Person> ;
+    <http://example.org/ns/age> 42 ,
+      43 .
+}
+"#;
+        assert_eq!(out, expected);
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_pretty() -> Result<(), Box<dyn Error>> {
         for ttl in TESTS {
@@ -212,4 +324,69 @@ pub(crate) mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_indexed() -> Result<(), Box<dyn Error>> {
+        for ttl in TESTS {
+            println!("==========\n{}\n----------", ttl);
+            let d1: FastDataset = crate::parser::trig::parse_str(ttl).collect_quads()?;
+            let out = TrigSerializer::new_stringifier()
+                .serialize_indexed_dataset(&d1)?
+                .to_string();
+            println!("{}", &out);
+
+            let d2: Vec<Spog<SimpleTerm>> = crate::parser::trig::parse_str(&out).collect_quads()?;
+            assert!(isomorphic_datasets(&d1, &d2)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_indexed_unsorted() -> Result<(), Box<dyn Error>> {
+        // quads of a HashSet are not grouped by graph name and subject
+        for ttl in TESTS {
+            println!("==========\n{}\n----------", ttl);
+            let d1: HashSet<Spog<SimpleTerm>> =
+                crate::parser::trig::parse_str(ttl).collect_quads()?;
+            let out = TrigSerializer::new_stringifier()
+                .serialize_indexed_dataset(&d1)?
+                .to_string();
+            println!("{}", &out);
+
+            let d2: Vec<Spog<SimpleTerm>> = crate::parser::trig::parse_str(&out).collect_quads()?;
+            assert!(isomorphic_datasets(&d1, &d2)?);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn roundtrip_async() -> Result<(), Box<dyn Error>> {
+        use crate::parser::trig::TriGParser;
+        use sophia_api::parser::AsyncQuadParser;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        for trig in TESTS {
+            for pretty in [false, true] {
+                println!("==========\n{} (pretty: {})\n----------", trig, pretty);
+                let d1: Vec<Spog<SimpleTerm>> =
+                    crate::parser::trig::parse_str(trig).collect_quads()?;
+
+                // read through a tiny buffer, so that statements span several chunks
+                let read = tokio::io::BufReader::with_capacity(7, trig.as_bytes());
+                let source = TriGParser::default().parse_async(read);
+                let config = TrigConfig::new().with_pretty(pretty);
+                let mut serializer = TrigSerializer::new_with_config(vec![], config);
+                runtime.block_on(serializer.serialize_quads_async(source))?;
+                let out = serializer.to_string();
+                println!("{}", &out);
+
+                let d2: Vec<Spog<SimpleTerm>> =
+                    crate::parser::trig::parse_str(&out).collect_quads()?;
+
+                assert!(isomorphic_datasets(&d1, &d2)?);
+            }
+        }
+        Ok(())
+    }
 }
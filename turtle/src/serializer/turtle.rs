@@ -9,17 +9,25 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use rio_turtle::TurtleFormatter;
+use sophia_api::graph::SetGraph;
 use sophia_api::prefix::{Prefix, PrefixMap, PrefixMapPair};
 use sophia_api::serializer::{Stringifier, TripleSerializer};
 use sophia_api::source::{SinkError, SourceError, StreamResult, TripleSource};
 use sophia_api::term::{SimpleTerm, Term};
 use sophia_api::triple::Triple;
+use sophia_iri::resolve::BaseIri;
 use sophia_iri::Iri;
-use sophia_rio::serializer::rio_format_triples;
+use std::borrow::Borrow;
 use std::io;
 
+use super::_indexed::prettify_indexed;
 pub(super) use super::_pretty::*;
+use super::_streaming::StreamingWriter;
+
+#[cfg(feature = "async")]
+use super::_async::write_chunk;
+#[cfg(feature = "async")]
+use sophia_api::{serializer::AsyncTripleSerializer, source::AsyncTripleSource};
 
 /// Turtle serializer configuration.
 #[derive(Clone, Debug)]
@@ -27,6 +35,7 @@ pub struct TurtleConfig {
     pub(super) pretty: bool,
     pub(super) prefix_map: Vec<PrefixMapPair>,
     pub(super) indentation: String,
+    pub(super) base: Option<BaseIri<String>>,
 }
 
 impl TurtleConfig {
@@ -34,11 +43,13 @@ impl TurtleConfig {
     ///
     /// If false (default), the triples will be serialized in streaming mode.
     /// Subject and predicate "factorization" will only occur based on the previous triple(s)
-    /// in the stream. The collection syntax for `rdf:List`s will not be used.
+    /// in the stream. The collection syntax for `rdf:List`s will not be used,
+    /// nor will blank nodes be nested.
     ///
     /// If true, extra effort will be made to group related triples together,
     /// and to use the collection syntax whenever possible.
-    /// This requires storing the whole graph in memory.
+    /// This requires storing the whole graph in memory
+    /// (see [`TurtleSerializer::serialize_indexed_graph`] for an alternative).
     pub fn pretty(&self) -> bool {
         self.pretty
     }
@@ -46,19 +57,29 @@ impl TurtleConfig {
     /// [`PrefixMap`] to use in serialization.
     /// (defaults to a map containing rdf:, rdfs: and xsd:)
     ///
-    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
+    /// All its prefixes are declared at the start of the output,
+    /// and used to abbreviate IRIs whenever possible.
     pub fn prefix_map(&self) -> &[PrefixMapPair] {
         &self.prefix_map
     }
 
     /// Indentation to use in serialization.
     /// (defaults to `"  "`, can only contain ASCII whitespaces)
-    ///
-    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
     pub fn indentation(&self) -> &str {
         &self.indentation
     }
 
+    /// Base IRI to use in serialization.
+    /// (defaults to `None`)
+    ///
+    /// If set, it is declared at the start of the output,
+    /// and IRIs that can not be abbreviated with the [`prefix_map`](TurtleConfig::prefix_map)
+    /// are written relative to it, whenever this round-trips exactly
+    /// (see [`BaseIri::relativize`]).
+    pub fn base(&self) -> Option<&BaseIri<String>> {
+        self.base.as_ref()
+    }
+
     /// Build a new default [`TurtleConfig`].
     pub fn new() -> Self {
         let pretty = false;
@@ -68,6 +89,7 @@ impl TurtleConfig {
             pretty,
             prefix_map,
             indentation,
+            base: None,
         }
     }
 
@@ -100,6 +122,12 @@ impl TurtleConfig {
         self
     }
 
+    /// Transform a [`TurtleConfig`] by setting the [`base`][`TurtleConfig::base`] IRI.
+    pub fn with_base<T: Borrow<str>>(mut self, base: Iri<T>) -> Self {
+        self.base = Some(Iri::new_unchecked(base.as_str().to_string()).to_base());
+        self
+    }
+
     /// Return the prefix map that is used when none is provided
     pub fn default_prefix_map() -> Vec<PrefixMapPair> {
         vec![
@@ -131,10 +159,7 @@ pub struct TurtleSerializer<W> {
     pub(super) write: W,
 }
 
-impl<W> TurtleSerializer<W>
-where
-    W: io::Write,
-{
+impl<W> TurtleSerializer<W> {
     /// Build a new Turtle serializer writing to `write`, with the default config.
     #[inline]
     pub fn new(write: W) -> TurtleSerializer<W> {
@@ -152,6 +177,32 @@ where
     }
 }
 
+impl<W> TurtleSerializer<W>
+where
+    W: io::Write,
+{
+    /// Serialize a whole [`Graph`](sophia_api::graph::Graph) in pretty Turtle,
+    /// whatever the [`pretty`](TurtleConfig::pretty) flag of the config.
+    ///
+    /// Contrarily to [`serialize_graph`](TripleSerializer::serialize_graph) in pretty mode,
+    /// this does not copy `graph` in memory, but explores it with
+    /// [`triples_matching`](sophia_api::graph::Graph::triples_matching),
+    /// so that memory usage remains bounded.
+    /// This is intended for big graphs providing efficient index access
+    /// (e.g. those of `sophia_inmem` or `sophia_disk`);
+    /// with other graphs, it may be much slower than the other serialization methods.
+    pub fn serialize_indexed_graph<G>(
+        &mut self,
+        graph: &G,
+    ) -> StreamResult<&mut Self, G::Error, io::Error>
+    where
+        G: SetGraph,
+    {
+        prettify_indexed(&graph.as_dataset(), &mut self.write, &self.config)?;
+        Ok(self)
+    }
+}
+
 impl<W> TripleSerializer for TurtleSerializer<W>
 where
     W: io::Write,
@@ -176,14 +227,59 @@ where
                 .map_err(SourceError)?;
             prettify(dataset, &mut self.write, &self.config, "").map_err(SinkError)?;
         } else {
-            let mut tf = TurtleFormatter::new(&mut self.write);
-            rio_format_triples(&mut tf, source)?;
-            tf.finish().map_err(SinkError)?;
+            let mut writer = StreamingWriter::new(&mut self.write, &self.config);
+            source.try_for_each_triple(|t| writer.write_triple(t))?;
+            writer.finish().map_err(SinkError)?;
         }
         Ok(self)
     }
 }
 
+#[cfg(feature = "async")]
+impl<W> AsyncTripleSerializer for TurtleSerializer<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    async fn serialize_triples_async<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: AsyncTripleSource,
+    {
+        let mut buf = vec![];
+        if self.config.pretty {
+            let mut dataset = PrettifiableDataset::new();
+            let default = None as Option<SimpleTerm>;
+            source
+                .for_each_triple(|t| {
+                    let spo = t.spo().map(Term::into_term);
+                    dataset.insert((default.clone(), spo));
+                })
+                .await
+                .map_err(SourceError)?;
+            prettify(dataset, &mut buf, &self.config, "").map_err(SinkError)?;
+        } else {
+            let mut writer = StreamingWriter::new(&mut buf, &self.config);
+            while source
+                .try_for_some_triple(|t| writer.write_triple(t))
+                .await?
+            {
+                write_chunk(&mut self.write, writer.get_mut(), false)
+                    .await
+                    .map_err(SinkError)?;
+            }
+            writer.finish().map_err(SinkError)?;
+        }
+        write_chunk(&mut self.write, &mut buf, true)
+            .await
+            .map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
 impl TurtleSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -211,6 +307,7 @@ impl Stringifier for TurtleSerializer<Vec<u8>> {
 pub(crate) mod test {
     use super::*;
     use sophia_api::graph::Graph;
+    use sophia_inmem::graph::FastGraph;
     use sophia_isomorphism::isomorphic_graphs;
     use std::error::Error;
 
@@ -238,10 +335,13 @@ pub(crate) mod test {
             _:b :n "b"; :s [ :s _:b ].
             _:c :b "c"; :t _:c.
         "#,
-        r#"# quoted triples
+        r#"# triple terms and reifiers
             PREFIX : <http://example.org/ns/>
+            :s :p <<( :s :p :o0 )>>.
             << :s :p :o1 >> :a :b.
+            << :s :p :o1 ~ :r1 >> :a :b.
             :s :p :o2 {| :c :d |}.
+            :s :p :o3 ~ :r3 {| :c :d |}, :o4 ~ ~:r4.
         "#,
     ];
 
@@ -265,6 +365,109 @@ pub(crate) mod test {
         Ok(())
     }
 
+    #[test]
+    fn roundtrip_not_pretty_with_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut prefix_map = TurtleConfig::default_prefix_map();
+        prefix_map.push((
+            Prefix::new_unchecked("".into()),
+            Iri::new_unchecked("http://example.org/ns/".into()),
+        ));
+        let config = TurtleConfig::new().with_own_prefix_map(prefix_map);
+        for ttl in TESTS {
+            println!("==========\n{}\n----------", ttl);
+            let g1: Vec<[SimpleTerm; 3]> =
+                crate::parser::turtle::parse_str(ttl).collect_triples()?;
+
+            let out = TurtleSerializer::new_stringifier_with_config(config.clone())
+                .serialize_triples(g1.triples())?
+                .to_string();
+            println!("{}", &out);
+
+            let g2: Vec<[SimpleTerm; 3]> =
+                crate::parser::turtle::parse_str(&out).collect_triples()?;
+
+            assert!(isomorphic_graphs(&g1, &g2)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_compact() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX : <http://example.org/ns/>
+            PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+            :alice rdf:type :Person; :age 42, 4.2, 4.2e1, "42"; :admin true; :x:y <tag:z>.
+            :bob :name "Bob"^^:name; :weird <http://example.org/ns/a.>.
+        "#;
+        let g: Vec<[SimpleTerm; 3]> = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let prefix_map = vec![(
+            Prefix::new_unchecked("".into()),
+            Iri::new_unchecked("http://example.org/ns/".into()),
+        )];
+        let config = TurtleConfig::new()
+            .with_own_prefix_map(prefix_map)
+            .with_indentation("\t");
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        let expected = r#"PREFIX : <http://example.org/ns/>
+
+:alice a :Person ;
+	:age 42 ,
+		4.2 ,
+		4.2e1 ,
+		"42" ;
+	:admin true ;
+	:x:y <tag:z> .
+:bob :name "Bob"^^:name ;
+	:weird <http://example.org/ns/a.> .
+"#;
+        assert_eq!(out, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn with_base() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+            <https://example.org/people/alice#me> foaf:knows
+                <https://example.org/people/bob#me>,
+                <https://example.org/people/../x>,
+                <https://example.com/people/carol#me>;
+              foaf:page <https://example.org/people/alice>.
+        "#;
+        let g1: Vec<[SimpleTerm; 3]> = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let prefix_map = vec![(
+            Prefix::new_unchecked("foaf".into()),
+            Iri::new_unchecked("http://xmlns.com/foaf/0.1/".into()),
+        )];
+        let base = Iri::new_unchecked("https://example.org/people/alice");
+        for pretty in [false, true] {
+            let config = TurtleConfig::new()
+                .with_pretty(pretty)
+                .with_own_prefix_map(prefix_map.clone())
+                .with_base(base);
+            let out = TurtleSerializer::new_stringifier_with_config(config)
+                .serialize_triples(g1.triples())?
+                .to_string();
+            println!("{}", &out);
+            assert!(out.starts_with("BASE <https://example.org/people/alice>\n"));
+            for expected in [
+                "<#me>",
+                "<bob#me>",
+                "<https://example.org/people/../x>",
+                "<https://example.com/people/carol#me>",
+                "<>",
+            ] {
+                assert!(out.contains(expected), "{expected}");
+            }
+            let g2: Vec<[SimpleTerm; 3]> =
+                crate::parser::turtle::parse_str(&out).collect_triples()?;
+            assert!(isomorphic_graphs(&g1, &g2)?);
+        }
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_pretty() -> Result<(), Box<dyn Error>> {
         for ttl in TESTS {
@@ -300,4 +503,92 @@ pub(crate) mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_indexed() -> Result<(), Box<dyn Error>> {
+        for ttl in TESTS {
+            println!("==========\n{}\n----------", ttl);
+            let g1: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+            let out = TurtleSerializer::new_stringifier()
+                .serialize_indexed_graph(&g1)?
+                .to_string();
+            println!("{}", &out);
+
+            let g2: Vec<[SimpleTerm; 3]> =
+                crate::parser::turtle::parse_str(&out).collect_triples()?;
+            assert!(isomorphic_graphs(&g1, &g2)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn indexed() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX : <http://example.org/ns/>
+            :alice a :Person; :knows [ a :Person; :name "Bob" ]; :likes (1 [ :n 2 ] _:c).
+            _:c :name "Carol".
+            :dan :knows _:c.
+        "#;
+        let g: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let prefix_map = vec![(
+            Prefix::new_unchecked("".into()),
+            Iri::new_unchecked("http://example.org/ns/".into()),
+        )];
+        let config = TurtleConfig::new().with_own_prefix_map(prefix_map);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_indexed_graph(&g)?
+            .to_string();
+        // same layout as in pretty mode, but subjects are in the order of the index
+        let expected = r#"PREFIX : <http://example.org/ns/>
+
+:alice a :Person;
+  :knows [ a :Person;
+      :name "Bob"];
+  :likes (
+      1
+      [
+        :n 2]
+      _:c
+    ).
+
+_:c
+  :name "Carol".
+
+:dan
+  :knows _:c.
+"#;
+        assert_eq!(out, expected);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn roundtrip_async() -> Result<(), Box<dyn Error>> {
+        use crate::parser::turtle::TurtleParser;
+        use sophia_api::parser::AsyncTripleParser;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        for ttl in TESTS {
+            for pretty in [false, true] {
+                println!("==========\n{} (pretty: {})\n----------", ttl, pretty);
+                let g1: Vec<[SimpleTerm; 3]> =
+                    crate::parser::turtle::parse_str(ttl).collect_triples()?;
+
+                // read through a tiny buffer, so that statements span several chunks
+                let read = tokio::io::BufReader::with_capacity(7, ttl.as_bytes());
+                let source = TurtleParser::default().parse_async(read);
+                let config = TurtleConfig::new().with_pretty(pretty);
+                let mut serializer = TurtleSerializer::new_with_config(vec![], config);
+                runtime.block_on(serializer.serialize_triples_async(source))?;
+                let out = serializer.to_string();
+                println!("{}", &out);
+
+                let g2: Vec<[SimpleTerm; 3]> =
+                    crate::parser::turtle::parse_str(&out).collect_triples()?;
+
+                assert!(isomorphic_graphs(&g1, &g2)?);
+            }
+        }
+        Ok(())
+    }
 }
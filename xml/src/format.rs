@@ -0,0 +1,61 @@
+//! The RDF/XML [format](Format), for use in a [`FormatRegistry`].
+
+use crate::parser::RdfXmlParser;
+use crate::serializer::RdfXmlSerializer;
+use sophia_api::format::{box_triples, serializer_error, Format, FormatRegistry};
+use sophia_api::parser::TripleParser;
+use sophia_api::quad::Quad;
+use sophia_api::serializer::TripleSerializer;
+use sophia_api::source::QuadSource;
+
+/// The [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/) format.
+pub const RDF_XML: Format = Format {
+    name: "RDF/XML",
+    media_types: &["application/rdf+xml"],
+    extensions: &["rdf", "owl"],
+    dataset: false,
+    parser: Some(|read, base| box_triples(RdfXmlParser { base }.parse(read))),
+    serializer: Some(|write, quads| {
+        let triples = quads
+            .filter_quads(|q| q.g().is_none())
+            .map_quads(Quad::into_triple);
+        serializer_error(RdfXmlSerializer::new(write).serialize_triples(triples))
+    }),
+};
+
+/// Add all the formats of this crate to `registry`.
+pub fn register(registry: &mut FormatRegistry) {
+    registry.register(RDF_XML);
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::format::FormatError;
+    use sophia_isomorphism::isomorphic_datasets;
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let xml = r#"<?xml version="1.0"?>
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="tag:">
+              <rdf:Description rdf:about="a"><ex:p>hello</ex:p></rdf:Description>
+            </rdf:RDF>"#;
+        let base = Some(sophia_iri::Iri::new_unchecked("tag:x/".to_string()));
+        let expected: Vec<_> = RDF_XML
+            .parse(xml.as_bytes(), base)?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(expected.len(), 1);
+        let mut data = vec![];
+        RDF_XML.serialize(
+            &mut data,
+            expected.clone().into_iter().map(Ok::<_, FormatError>),
+        )?;
+        let got: Vec<_> = RDF_XML.parse(&data[..], None)?.collect::<Result<_, _>>()?;
+        assert!(isomorphic_datasets(&expected, &got)?);
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 //! This crate is part of [Sophia],
 //! an [RDF] and [Linked Data] toolkit in Rust.
 //!
-//! Parser and serializer for the [RDF/XML] concrete syntax,
-//! based on [`rio_xml`].
+//! Parser and serializer for the [RDF/XML] concrete syntax
+//! (the parser being based on [`rio_xml`]).
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
@@ -10,5 +10,6 @@
 //! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
 #![deny(missing_docs)]
 
+pub mod format;
 pub mod parser;
 pub mod serializer;
@@ -5,9 +5,16 @@
 
 use rio_xml::RdfXmlParser as RioRdfXmlParser;
 use sophia_api::parser::TripleParser;
+use sophia_api::prefix::PrefixMapPair;
+use sophia_api::source::{Source, StreamResult};
 use sophia_iri::Iri;
+use sophia_rio::model::Trusted;
 use sophia_rio::parser::*;
 use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+
+mod _declarations;
+use _declarations::{lock, DeclarationScanner, Declarations};
 
 /// N-Triples parser based on RIO.
 #[derive(Clone, Debug, Default)]
@@ -17,15 +24,56 @@ pub struct RdfXmlParser {
 }
 
 impl<B: BufRead> TripleParser<B> for RdfXmlParser {
-    type Source = StrictRioTripleSource<RioRdfXmlParser<B>>;
+    type Source = RdfXmlTripleSource<B>;
     fn parse(&self, data: B) -> Self::Source {
+        let scanner = DeclarationScanner::new(data, self.base.clone());
+        let declarations = scanner.declarations();
         let base = self
             .base
             .clone()
             .map(Iri::unwrap)
             .map(oxiri::Iri::parse)
             .map(Result::unwrap);
-        StrictRioTripleSource(RioRdfXmlParser::new(data, base))
+        RdfXmlTripleSource {
+            source: StrictRioTripleSource(RioRdfXmlParser::new(scanner, base)),
+            declarations,
+        }
+    }
+}
+
+/// The type of [`TripleSource`](sophia_api::source::TripleSource)
+/// returned by [`RdfXmlParser`].
+pub struct RdfXmlTripleSource<B: BufRead> {
+    source: StrictRioTripleSource<RioRdfXmlParser<DeclarationScanner<B>>>,
+    declarations: Arc<Mutex<Declarations>>,
+}
+
+impl<B: BufRead> RdfXmlTripleSource<B> {
+    /// The namespaces declared (with `xmlns` attributes) so far in the parsed document,
+    /// suitable for `TurtleConfig::with_own_prefix_map` in `sophia_turtle`.
+    ///
+    /// Namespace prefixes that are not valid Turtle prefixes are omitted.
+    pub fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        lock(&self.declarations).prefix_map.clone()
+    }
+
+    /// The base IRI declared by the last `xml:base` attribute read so far,
+    /// or the one provided to the parser.
+    pub fn base(&self) -> Option<Iri<String>> {
+        lock(&self.declarations).base.clone()
+    }
+}
+
+impl<B: BufRead> Source for RdfXmlTripleSource<B> {
+    type Item<'x> = Trusted<rio_api::model::Triple<'x>>;
+    type Error = rio_xml::RdfXmlError;
+
+    fn try_for_some_item<E, F>(&mut self, f: F) -> StreamResult<bool, Self::Error, E>
+    where
+        E: std::error::Error,
+        F: FnMut(Self::Item<'_>) -> Result<(), E>,
+    {
+        self.source.try_for_some_item(f)
     }
 }
 
@@ -41,10 +89,45 @@ mod test {
     use sophia_api::graph::Graph;
     use sophia_api::ns::rdf;
     use sophia_api::source::TripleSource;
-    use sophia_api::term::{SimpleTerm, TermKind};
+    use sophia_api::term::{SimpleTerm, Term, TermKind};
+    use sophia_api::triple::Triple;
 
     type MyGraph = Vec<[SimpleTerm<'static>; 3]>;
 
+    #[test]
+    fn prefixes_and_base() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let xml = r##"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns="http://example.org/ns/"
+                 xml:base="http://example.org/base/">
+          <rdf:Description rdf:about="#me" xmlns:foaf="http://xmlns.com/foaf/0.1/">
+            <foaf:name>me</foaf:name>
+          </rdf:Description>
+        </rdf:RDF>
+        "##;
+
+        let mut source = RdfXmlParser::default().parse_str(xml);
+        assert!(source.base().is_none());
+        let mut g = MyGraph::new();
+        source.for_each_triple(|t| g.push(t.to_spo().map(|t| t.into_term())))?;
+        assert_eq!(g.len(), 1);
+        assert_eq!(source.base().unwrap().as_str(), "http://example.org/base/");
+        let prefixes: Vec<_> = source
+            .prefix_map()
+            .into_iter()
+            .map(|(p, ns)| format!("{}={}", p.as_str(), ns.as_str()))
+            .collect();
+        assert_eq!(
+            prefixes,
+            [
+                "rdf=http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+                "=http://example.org/ns/",
+                "foaf=http://xmlns.com/foaf/0.1/",
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_simple_xml_string() -> std::result::Result<(), Box<dyn std::error::Error>> {
         let xml = r#"<?xml version="1.0" encoding="utf-8"?>
@@ -0,0 +1,416 @@
+//! A pass-through reader, recording the namespace and base declarations
+//! of the XML document read through it.
+
+use sophia_api::prefix::{Prefix, PrefixMapPair};
+use sophia_iri::resolve::BaseIri;
+use sophia_iri::Iri;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The declarations found so far in an XML document.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Declarations {
+    /// The `xmlns` declarations, in document order
+    pub prefix_map: Vec<PrefixMapPair>,
+    /// The base IRI, initially provided to the parser,
+    /// then declared by the last `xml:base` attribute
+    pub base: Option<Iri<String>>,
+}
+
+/// A [`BufRead`] wrapper scanning the bytes consumed from the underlying reader,
+/// and recording the declarations of the document in a shared [`Declarations`].
+///
+/// This is not a full XML parser:
+/// it only recognizes tags, comments, CDATA sections, processing instructions
+/// and internal entities declared in the DOCTYPE,
+/// which is enough to extract the attributes of all start tags.
+pub(crate) struct DeclarationScanner<B> {
+    inner: B,
+    shared: Arc<Mutex<Declarations>>,
+    state: State,
+    /// The markup being scanned (without the leading `<`)
+    markup: Vec<u8>,
+    entities: HashMap<String, String>,
+    /// The base IRI in scope in each open element (the first one being the document's)
+    bases: Vec<Option<Iri<String>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Text,
+    /// In a start tag, end tag, or unidentified markup
+    Tag {
+        quote: Option<u8>,
+    },
+    Comment {
+        dashes: u8,
+    },
+    CData {
+        brackets: u8,
+    },
+    Pi {
+        question: bool,
+    },
+    Doctype {
+        quote: Option<u8>,
+        depth: usize,
+    },
+}
+
+impl<B: BufRead> DeclarationScanner<B> {
+    pub fn new(inner: B, base: Option<Iri<String>>) -> Self {
+        DeclarationScanner {
+            inner,
+            shared: Arc::new(Mutex::new(Declarations {
+                prefix_map: vec![],
+                base: base.clone(),
+            })),
+            state: State::Text,
+            markup: vec![],
+            entities: HashMap::new(),
+            bases: vec![base],
+        }
+    }
+
+    /// A handle on the declarations recorded by this scanner.
+    pub fn declarations(&self) -> Arc<Mutex<Declarations>> {
+        self.shared.clone()
+    }
+
+    fn scan(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state = match self.state {
+                State::Text if b == b'<' => {
+                    self.markup.clear();
+                    State::Tag { quote: None }
+                }
+                State::Text => State::Text,
+                State::Tag { quote: None } if b == b'>' => {
+                    self.end_tag();
+                    State::Text
+                }
+                State::Tag { quote } => {
+                    self.markup.push(b);
+                    self.identify_markup(match quote {
+                        None if b == b'"' || b == b'\'' => Some(b),
+                        Some(q) if q == b => None,
+                        _ => quote,
+                    })
+                }
+                State::Comment { dashes } => match b {
+                    b'-' => State::Comment { dashes: dashes + 1 },
+                    b'>' if dashes >= 2 => State::Text,
+                    _ => State::Comment { dashes: 0 },
+                },
+                State::CData { brackets } => match b {
+                    b']' => State::CData {
+                        brackets: brackets + 1,
+                    },
+                    b'>' if brackets >= 2 => State::Text,
+                    _ => State::CData { brackets: 0 },
+                },
+                State::Pi { question } => match b {
+                    b'>' if question => State::Text,
+                    _ => State::Pi {
+                        question: b == b'?',
+                    },
+                },
+                State::Doctype {
+                    quote: None,
+                    depth: 0,
+                } if b == b'>' => {
+                    self.end_doctype();
+                    State::Text
+                }
+                State::Doctype { quote, depth } => {
+                    self.markup.push(b);
+                    match (quote, b) {
+                        (None, b'"' | b'\'') => State::Doctype {
+                            quote: Some(b),
+                            depth,
+                        },
+                        (Some(q), _) if q == b => State::Doctype { quote: None, depth },
+                        (None, b'[') => State::Doctype {
+                            quote,
+                            depth: depth + 1,
+                        },
+                        (None, b']') => State::Doctype {
+                            quote,
+                            depth: depth.saturating_sub(1),
+                        },
+                        _ => State::Doctype { quote, depth },
+                    }
+                }
+            };
+        }
+    }
+
+    /// Switch to a more specific state once the start of the markup identifies it.
+    fn identify_markup(&mut self, quote: Option<u8>) -> State {
+        match &self.markup[..] {
+            b"!--" => State::Comment { dashes: 0 },
+            b"![CDATA[" => State::CData { brackets: 0 },
+            [b'?', ..] => State::Pi { question: false },
+            [b'!', ..]
+                if !b"!--".starts_with(&self.markup) && !b"![CDATA[".starts_with(&self.markup) =>
+            {
+                State::Doctype { quote, depth: 0 }
+            }
+            _ => State::Tag { quote },
+        }
+    }
+
+    /// Record the declarations of the start tag that was just scanned,
+    /// and keep track of the base IRI in scope.
+    fn end_tag(&mut self) {
+        match self.markup.first() {
+            None => return,
+            Some(b'/') => {
+                if self.bases.len() > 1 {
+                    self.bases.pop();
+                }
+                return;
+            }
+            _ => {}
+        }
+        let markup = String::from_utf8_lossy(&self.markup).into_owned();
+        let mut shared = lock(&self.shared);
+        let mut base = self.bases.last().cloned().flatten();
+        for (name, value) in attributes(&markup) {
+            let value = self.decode(value);
+            if name == "xml:base" {
+                let declared = match &base {
+                    Some(base) => BaseIri::new(base.as_str())
+                        .and_then(|base| base.resolve(value.as_str()))
+                        .ok(),
+                    None => Iri::new(value).ok(),
+                };
+                if let Some(declared) = declared {
+                    shared.base = Some(declared.clone());
+                    base = Some(declared);
+                }
+                continue;
+            }
+            let prefix = if name == "xmlns" {
+                ""
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                prefix
+            } else {
+                continue;
+            };
+            let (Ok(prefix), Ok(ns)) = (
+                Prefix::new(Box::from(prefix)),
+                Iri::new(value.into_boxed_str()),
+            ) else {
+                continue;
+            };
+            match shared
+                .prefix_map
+                .iter_mut()
+                .find(|(p, _)| p.as_str() == prefix.as_str())
+            {
+                Some(pair) => pair.1 = ns,
+                None => shared.prefix_map.push((prefix, ns)),
+            }
+        }
+        if self.markup.last() != Some(&b'/') {
+            self.bases.push(base);
+        }
+    }
+
+    /// Record the internal entities declared in the DOCTYPE that was just scanned.
+    fn end_doctype(&mut self) {
+        let markup = String::from_utf8_lossy(&self.markup).into_owned();
+        for decl in markup.split("<!ENTITY").skip(1) {
+            let decl = decl.trim_start();
+            // parameter entities are ignored
+            if decl.starts_with('%') {
+                continue;
+            }
+            let Some((name, rest)) = decl.split_once(|c: char| c.is_ascii_whitespace()) else {
+                continue;
+            };
+            let rest = rest.trim_start();
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            if let Some((value, _)) = rest[1..].split_once(quote) {
+                let value = self.decode(value);
+                self.entities.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    /// Replace entity and character references in an attribute value.
+    fn decode(&self, value: &str) -> String {
+        let mut decoded = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(amp) = rest.find('&') {
+            decoded.push_str(&rest[..amp]);
+            rest = &rest[amp..];
+            let Some(semicolon) = rest.find(';') else {
+                break;
+            };
+            let name = &rest[1..semicolon];
+            let replacement = match name {
+                "lt" => Some("<".to_string()),
+                "gt" => Some(">".to_string()),
+                "amp" => Some("&".to_string()),
+                "apos" => Some("'".to_string()),
+                "quot" => Some("\"".to_string()),
+                _ if name.starts_with("#x") => u32::from_str_radix(&name[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(String::from),
+                _ if name.starts_with('#') => name[1..]
+                    .parse()
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(String::from),
+                _ => self.entities.get(name).cloned(),
+            };
+            match replacement {
+                Some(replacement) => {
+                    decoded.push_str(&replacement);
+                    rest = &rest[semicolon + 1..];
+                }
+                None => {
+                    decoded.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        decoded.push_str(rest);
+        decoded
+    }
+}
+
+impl<B: BufRead> Read for DeclarationScanner<B> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let buf = self.inner.fill_buf()?;
+        let n = buf.len().min(out.len());
+        out[..n].copy_from_slice(&buf[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<B: BufRead> BufRead for DeclarationScanner<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // per the contract of BufRead, this returns the same bytes as the previous call,
+        // without reading anything
+        if let Ok(buf) = self.inner.fill_buf() {
+            let bytes = buf[..amt.min(buf.len())].to_vec();
+            self.scan(&bytes);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Lock the shared declarations, ignoring poisoning (they are always in a consistent state).
+pub(crate) fn lock(shared: &Mutex<Declarations>) -> MutexGuard<'_, Declarations> {
+    shared.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Iterate over the (name, raw value) pairs of the attributes in a start tag
+/// (without its `<` and `>`).
+fn attributes(markup: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = markup
+        .trim_end_matches('/')
+        .trim_start_matches(|c: char| !c.is_ascii_whitespace());
+    std::iter::from_fn(move || {
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, after) = after[1..].split_once(quote)?;
+        rest = after;
+        Some((name.trim(), value))
+    })
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scan() -> io::Result<()> {
+        let xml = r##"<?xml version="1.0" encoding="utf-8"?>
+        <!DOCTYPE rdf:RDF [
+          <!ENTITY ex "http://example.org/ns/">
+        ]>
+        <!-- <fake xmlns:fake="tag:fake"/> -->
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="&ex;"
+                 xml:base="http://example.org/base/">
+          <rdf:Description rdf:about="#me" xmlns:ex2 = 'http://example.org/a&amp;b#'>
+            <name xml:base="sub/"><![CDATA[ <x xmlns:cdata="tag:cdata"> ]]></name>
+          </rdf:Description>
+        </rdf:RDF>
+        "##;
+        // read through a tiny buffer, to check that markup spanning several chunks is supported
+        let mut scanner =
+            DeclarationScanner::new(io::BufReader::with_capacity(7, xml.as_bytes()), None);
+        let declarations = scanner.declarations();
+        io::copy(&mut scanner, &mut io::sink())?;
+        let declarations = lock(&declarations);
+        let prefixes: Vec<_> = declarations
+            .prefix_map
+            .iter()
+            .map(|(p, ns)| format!("{}={}", p.as_str(), ns.as_str()))
+            .collect();
+        assert_eq!(
+            prefixes,
+            [
+                "rdf=http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+                "=http://example.org/ns/",
+                "ex2=http://example.org/a&b#",
+            ]
+        );
+        assert_eq!(
+            declarations.base.as_ref().unwrap().as_str(),
+            "http://example.org/base/sub/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_bases() -> io::Result<()> {
+        let xml = r##"<a xml:base="http://example.org/a/">
+          <b xml:base="b/"><c xml:base="c/"/></b>
+          <d xml:base="d/"/>
+          <e><f xml:base="f/"></f></e>
+        </a>
+        <g xml:base="g/"/>"##;
+        let expected = [
+            "tag:root/",
+            "http://example.org/a/",
+            "http://example.org/a/b/",
+            "http://example.org/a/b/c/",
+            "http://example.org/a/d/",
+            "http://example.org/a/f/",
+            "tag:root/g/",
+        ];
+        let mut scanner =
+            DeclarationScanner::new(xml.as_bytes(), Some(Iri::new_unchecked("tag:root/".into())));
+        let declarations = scanner.declarations();
+        let mut buf = [0; 1];
+        let mut bases = vec![];
+        while scanner.read(&mut buf)? > 0 {
+            let base = lock(&declarations).base.clone().unwrap();
+            if bases.last() != Some(&base) {
+                bases.push(base);
+            }
+        }
+        let bases: Vec<_> = bases.iter().map(|b| b.as_str()).collect();
+        assert_eq!(bases, expected);
+        Ok(())
+    }
+}
@@ -1,5 +1,4 @@
 //! Serializer for the [RDF/XML] concrete syntax of RDF.
-//! based on [`rio_xml`].
 //!
 //! **Important**:
 //! the methods in this module accepting a [`Write`]
@@ -10,16 +9,28 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use rio_xml::RdfXmlFormatter;
+use sophia_api::prefix::{Prefix, PrefixMap, PrefixMapPair};
 use sophia_api::serializer::{Stringifier, TripleSerializer};
-use sophia_api::source::{SinkError, StreamResult, TripleSource};
-use sophia_rio::serializer::rio_format_triples;
+use sophia_api::source::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_iri::resolve::BaseIri;
+use sophia_iri::Iri;
+use std::borrow::Borrow;
 use std::io;
 
+mod _pretty;
+use _pretty::{prettify, PrettifiableGraph};
+mod _streaming;
+use _streaming::StreamingWriter;
+
 /// RDF/XML serializer configuration.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct RdfXmlConfig {
     indentation: usize,
+    base: Option<BaseIri<String>>,
+    pretty: bool,
+    prefix_map: Vec<PrefixMapPair>,
 }
 
 impl RdfXmlConfig {
@@ -29,9 +40,56 @@ impl RdfXmlConfig {
         self.indentation
     }
 
+    /// Should the serializer make extra effort to produce pretty RDF/XML.
+    ///
+    /// If false (default), the triples will be serialized in streaming mode.
+    /// Consecutive triples with the same subject are grouped
+    /// in a single `rdf:Description` element.
+    ///
+    /// If true, all the triples of a subject are grouped in a single node element,
+    /// named after its type when possible (typed node element);
+    /// blank nodes used only once as object are nested in the property element,
+    /// and `rdf:List`s of resources are written with `rdf:parseType="Collection"`.
+    /// This requires storing the whole graph in memory.
+    pub fn pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// [`PrefixMap`] to use in serialization.
+    /// (defaults to a map containing rdf:, rdfs: and xsd:)
+    ///
+    /// Its namespaces are declared on the root element,
+    /// and used to name node and property elements whenever possible.
+    /// Other namespaces are declared on the element using them.
+    ///
+    /// In [`pretty`](RdfXmlConfig::pretty) mode, only the namespaces actually used are declared,
+    /// all of them on the root element
+    /// (with a generated `ns{i}` prefix for those missing from the prefix map).
+    ///
+    /// NB: the `rdf` prefix is always bound to the RDF namespace,
+    /// and prefixes starting with `xml` are ignored, as they are reserved by XML.
+    pub fn prefix_map(&self) -> &[PrefixMapPair] {
+        &self.prefix_map
+    }
+
+    /// Base IRI to use in serialization.
+    /// (defaults to `None`)
+    ///
+    /// If set, it is declared with `xml:base` on the root element,
+    /// and IRIs are written relative to it, whenever this round-trips exactly
+    /// (see [`BaseIri::relativize`]).
+    pub fn base(&self) -> Option<&BaseIri<String>> {
+        self.base.as_ref()
+    }
+
     /// Build a new default [`RdfXmlConfig`]
     pub fn new() -> Self {
-        Default::default()
+        RdfXmlConfig {
+            indentation: 0,
+            base: None,
+            pretty: false,
+            prefix_map: Self::default_prefix_map(),
+        }
     }
 
     /// Transform an [`RdfXmlConfig`] by setting the [`indentation`](RdfXmlConfig::indentation).
@@ -39,6 +97,54 @@ impl RdfXmlConfig {
         self.indentation = i;
         self
     }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`base`](RdfXmlConfig::base) IRI.
+    pub fn with_base<T: Borrow<str>>(mut self, base: Iri<T>) -> Self {
+        self.base = Some(Iri::new_unchecked(base.as_str().to_string()).to_base());
+        self
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`pretty`](RdfXmlConfig::pretty) flag.
+    pub fn with_pretty(mut self, b: bool) -> Self {
+        self.pretty = b;
+        self
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`prefix_map`](RdfXmlConfig::prefix_map)
+    /// (copying `pm` using [`PrefixMap::to_vec`]).
+    pub fn with_prefix_map<P: PrefixMap + ?Sized>(self, pm: &P) -> Self {
+        self.with_own_prefix_map(pm.to_vec())
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`prefix_map`](RdfXmlConfig::prefix_map).
+    pub fn with_own_prefix_map(mut self, pm: Vec<PrefixMapPair>) -> Self {
+        self.prefix_map = pm;
+        self
+    }
+
+    /// Return the prefix map that is used when none is provided
+    pub fn default_prefix_map() -> Vec<PrefixMapPair> {
+        vec![
+            (
+                Prefix::new_unchecked("rdf".into()),
+                Iri::new_unchecked("http://www.w3.org/1999/02/22-rdf-syntax-ns#".into()),
+            ),
+            (
+                Prefix::new_unchecked("rdfs".into()),
+                Iri::new_unchecked("http://www.w3.org/2000/01/rdf-schema#".into()),
+            ),
+            (
+                Prefix::new_unchecked("xsd".into()),
+                Iri::new_unchecked("http://www.w3.org/2001/XMLSchema#".into()),
+            ),
+        ]
+    }
+}
+
+impl Default for RdfXmlConfig {
+    fn default() -> Self {
+        RdfXmlConfig::new()
+    }
 }
 
 /// RDF/XML serializer.
@@ -76,20 +182,24 @@ where
 
     fn serialize_triples<TS>(
         &mut self,
-        source: TS,
+        mut source: TS,
     ) -> StreamResult<&mut Self, TS::Error, Self::Error>
     where
         TS: TripleSource,
     {
-        // temporarily move out self.write
-        let res = if self.config.indentation > 0 {
-            RdfXmlFormatter::with_indentation(&mut self.write, self.config.indentation)
+        if self.config.pretty {
+            let mut graph = PrettifiableGraph::new();
+            source
+                .for_each_triple(|t| {
+                    graph.insert(t.spo().map(Term::into_term::<SimpleTerm>));
+                })
+                .map_err(SourceError)?;
+            prettify(graph, &mut self.write, &self.config).map_err(SinkError)?;
         } else {
-            RdfXmlFormatter::new(&mut self.write)
-        };
-        let mut tf = res.map_err(SinkError)?;
-        rio_format_triples(&mut tf, source)?;
-        tf.finish().map_err(SinkError)?;
+            let mut writer = StreamingWriter::new(&mut self.write, &self.config);
+            source.try_for_each_triple(|t| writer.write_triple(t))?;
+            writer.finish().map_err(SinkError)?;
+        }
         Ok(self)
     }
 }
@@ -121,10 +231,10 @@ impl Stringifier for RdfXmlSerializer<Vec<u8>> {
 pub(crate) mod test {
     use super::*;
     use sophia_api::graph::Graph;
-    use sophia_api::term::SimpleTerm;
     use sophia_isomorphism::isomorphic_graphs;
 
-    const TESTS: &[&str] = &[r#"<?xml version="1.0" encoding="utf-8"?>
+    const TESTS: &[&str] = &[
+        r#"<?xml version="1.0" encoding="utf-8"?>
         <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
                  xmlns="http://example.org/ns/">
           <rdf:Description rdf:about="http://localhost/ex#me">
@@ -135,7 +245,56 @@ pub(crate) mod test {
             </knows>
           </rdf:Description>
         </rdf:RDF>
-        "#];
+        "#,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:ex="http://example.org/ns/">
+          <ex:Person rdf:about="http://localhost/ex#me">
+            <ex:name xml:lang="en">Alice &amp; "Bob" &lt;3</ex:name>
+            <ex:age rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</ex:age>
+            <ex:note>line 1
+line 2</ex:note>
+            <ex:list rdf:parseType="Collection">
+              <rdf:Description rdf:about="http://localhost/ex#a"/>
+              <rdf:Description rdf:about="http://localhost/ex#b"/>
+            </ex:list>
+          </ex:Person>
+        </rdf:RDF>
+        "#,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:ex="http://example.org/ns/">
+          <rdf:Description rdf:nodeID="a">
+            <ex:next rdf:nodeID="b"/>
+          </rdf:Description>
+          <rdf:Description rdf:nodeID="b">
+            <ex:next rdf:nodeID="a"/>
+          </rdf:Description>
+          <rdf:Description rdf:nodeID="d">
+            <ex:self rdf:nodeID="d"/>
+          </rdf:Description>
+          <rdf:Description rdf:about="http://localhost/ex#x">
+            <rdf:type rdf:resource="http://example.org/42"/>
+            <ex:p rdf:nodeID="c"/>
+            <ex:q rdf:nodeID="c"/>
+            <ex:r rdf:parseType="Resource"/>
+            <ex:list rdf:parseType="Collection">
+              <rdf:Description rdf:about="http://localhost/ex#a"/>
+              <ex:Thing><ex:name>b</ex:name></ex:Thing>
+              <rdf:Description rdf:nodeID="c"/>
+            </ex:list>
+            <ex:badlist rdf:parseType="Resource">
+              <rdf:first>literal</rdf:first>
+              <rdf:rest rdf:resource="http://www.w3.org/1999/02/22-rdf-syntax-ns#nil"/>
+            </ex:badlist>
+            <http:other xmlns:http="http://example.org/other/">42</http:other>
+          </rdf:Description>
+          <rdf:Description rdf:nodeID="c">
+            <ex:name>c</ex:name>
+          </rdf:Description>
+        </rdf:RDF>
+        "#,
+    ];
 
     #[test]
     fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
@@ -173,4 +332,169 @@ pub(crate) mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_pretty() -> Result<(), Box<dyn std::error::Error>> {
+        for indentation in [0, 2] {
+            let config = RdfXmlConfig::new()
+                .with_pretty(true)
+                .with_indentation(indentation);
+            for rdfxml in TESTS {
+                println!("==========\n{}\n----------", rdfxml);
+                let g1: Vec<[SimpleTerm; 3]> =
+                    crate::parser::parse_str(rdfxml).collect_triples()?;
+
+                let out = RdfXmlSerializer::new_stringifier_with_config(config.clone())
+                    .serialize_triples(g1.triples())?
+                    .to_string();
+                println!("{}", &out);
+
+                let g2: Vec<[SimpleTerm; 3]> = crate::parser::parse_str(&out).collect_triples()?;
+
+                assert!(isomorphic_graphs(&g1, &g2)?);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pretty() -> Result<(), Box<dyn std::error::Error>> {
+        let rdfxml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:foaf="http://xmlns.com/foaf/0.1/">
+          <rdf:Description rdf:about="http://example.org/alice">
+            <rdf:type rdf:resource="http://xmlns.com/foaf/0.1/Person"/>
+            <foaf:name xml:lang="en">Alice</foaf:name>
+            <foaf:knows>
+              <foaf:Person>
+                <foaf:name>Bob</foaf:name>
+              </foaf:Person>
+            </foaf:knows>
+            <foaf:based_near rdf:parseType="Resource">
+              <foaf:name>Paris</foaf:name>
+            </foaf:based_near>
+            <foaf:made rdf:parseType="Collection">
+              <rdf:Description rdf:about="http://example.org/doc1"/>
+              <rdf:Description rdf:about="http://example.org/doc2"/>
+            </foaf:made>
+            <foaf:age rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</foaf:age>
+          </rdf:Description>
+        </rdf:RDF>
+        "#;
+        let g: Vec<[SimpleTerm; 3]> = crate::parser::parse_str(rdfxml).collect_triples()?;
+        let prefix_map = vec![(
+            Prefix::new_unchecked("foaf".into()),
+            Iri::new_unchecked("http://xmlns.com/foaf/0.1/".into()),
+        )];
+        let config = RdfXmlConfig::new()
+            .with_pretty(true)
+            .with_indentation(2)
+            .with_own_prefix_map(prefix_map);
+        let out = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:foaf="http://xmlns.com/foaf/0.1/">
+  <foaf:Person rdf:about="http://example.org/alice">
+    <foaf:age rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</foaf:age>
+    <foaf:based_near rdf:parseType="Resource">
+      <foaf:name>Paris</foaf:name>
+    </foaf:based_near>
+    <foaf:knows>
+      <foaf:Person>
+        <foaf:name>Bob</foaf:name>
+      </foaf:Person>
+    </foaf:knows>
+    <foaf:made rdf:parseType="Collection">
+      <rdf:Description rdf:about="http://example.org/doc1"/>
+      <rdf:Description rdf:about="http://example.org/doc2"/>
+    </foaf:made>
+    <foaf:name xml:lang="en">Alice</foaf:name>
+  </foaf:Person>
+</rdf:RDF>
+"#;
+        assert_eq!(out, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_namespaces() -> Result<(), Box<dyn std::error::Error>> {
+        let rdfxml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:ex="http://example.org/ns/">
+          <ex:Thing rdf:about="http://example.org/a">
+            <ex:p>1</ex:p>
+            <ex:q>2</ex:q>
+            <ex:r rdf:resource="http://example.org/b"/>
+          </ex:Thing>
+        </rdf:RDF>
+        "#;
+        let g: Vec<[SimpleTerm; 3]> = crate::parser::parse_str(rdfxml).collect_triples()?;
+        let config = RdfXmlConfig::new().with_pretty(true).with_indentation(2);
+        let out = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ns0="http://example.org/ns/">
+  <ns0:Thing rdf:about="http://example.org/a">
+    <ns0:p>1</ns0:p>
+    <ns0:q>2</ns0:q>
+    <ns0:r rdf:resource="http://example.org/b"/>
+  </ns0:Thing>
+</rdf:RDF>
+"#;
+        assert_eq!(out, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn with_base() -> Result<(), Box<dyn std::error::Error>> {
+        let rdfxml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns:foaf="http://xmlns.com/foaf/0.1/">
+          <foaf:Person rdf:about="https://example.org/people/alice#me">
+            <foaf:knows rdf:resource="https://example.org/people/bob#me"/>
+            <foaf:knows rdf:resource="https://example.com/people/carol#me"/>
+            <foaf:page rdf:resource="https://example.org/people/alice"/>
+          </foaf:Person>
+        </rdf:RDF>
+        "#;
+        let g1: Vec<[SimpleTerm; 3]> = crate::parser::parse_str(rdfxml).collect_triples()?;
+        let config = RdfXmlConfig::new()
+            .with_indentation(2)
+            .with_base(Iri::new_unchecked("https://example.org/people/alice"));
+        let out = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g1.triples())?
+            .to_string();
+        println!("{}", &out);
+        for expected in [
+            r#"xml:base="https://example.org/people/alice""#,
+            r##"rdf:about="#me""##,
+            r##"rdf:resource="bob#me""##,
+            r##"rdf:resource="https://example.com/people/carol#me""##,
+            r#"rdf:resource="""#,
+        ] {
+            assert!(out.contains(expected), "{expected}");
+        }
+        let g2: Vec<[SimpleTerm; 3]> = crate::parser::parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g1, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_property() {
+        let g = [[
+            SimpleTerm::Iri(sophia_iri::IriRef::new_unchecked(
+                "http://example.org/s".into(),
+            )),
+            SimpleTerm::Iri(sophia_iri::IriRef::new_unchecked(
+                "http://example.org/42".into(),
+            )),
+            SimpleTerm::Iri(sophia_iri::IriRef::new_unchecked(
+                "http://example.org/o".into(),
+            )),
+        ]];
+        let mut ser = RdfXmlSerializer::new_stringifier();
+        assert!(ser.serialize_triples(g.triples()).is_err());
+    }
 }
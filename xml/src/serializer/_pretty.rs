@@ -0,0 +1,337 @@
+//! Utility code for pretty-printing RDF/XML.
+//!
+//! Triples are grouped by subject, and each group is described by a node element,
+//! named after the subject's type when possible (typed node element).
+//! Blank nodes that are used exactly once as object are nested in the corresponding
+//! property element, unless they are involved in a cycle,
+//! and well-formed `rdf:List`s of resources are written with `rdf:parseType="Collection"`.
+
+use super::_streaming::*;
+use super::RdfXmlConfig;
+use sophia_api::ns::rdf;
+use sophia_api::prefix::Prefix;
+use sophia_api::term::{SimpleTerm, Term, TermKind};
+use sophia_iri::Iri;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+pub type PrettifiableGraph<'a> = BTreeSet<[SimpleTerm<'a>; 3]>;
+
+/// Serialize `graph` in pretty RDF/XML on `write`, using the given `config`.
+pub fn prettify<W>(graph: PrettifiableGraph<'_>, write: W, config: &RdfXmlConfig) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let mut triples = Vec::with_capacity(graph.len());
+    for t in graph {
+        let [s, p, o] = &t;
+        if !is_standard_triple(s, p, o)? {
+            continue;
+        }
+        if let Some(piri) = p.iri().filter(|piri| qname(piri, config).is_none()) {
+            return Err(not_a_property(&piri));
+        }
+        triples.push(t);
+    }
+    let config = with_used_namespaces(&triples, config);
+    let mut p = Prettifier::new(&triples, write, &config);
+    p.write_all()
+}
+
+/// Return a copy of `config` whose prefix map contains exactly the namespaces
+/// used by the node and property elements describing `triples`,
+/// so that they are all declared once on the root element.
+///
+/// Unused prefixes are dropped,
+/// and namespaces missing from the prefix map of `config` get a fresh `ns{i}` prefix.
+fn with_used_namespaces(triples: &[[SimpleTerm; 3]], config: &RdfXmlConfig) -> RdfXmlConfig {
+    let mut declared = BTreeSet::new();
+    let mut undeclared = Vec::new();
+    for chunk in triples.chunk_by(|t1, t2| t1[0] == t2[0]) {
+        let type_index = chunk.iter().position(|t| is_typed_node(t, config));
+        for (i, [_, p, o]) in chunk.iter().enumerate() {
+            let iri = if Some(i) == type_index { o } else { p }.iri().unwrap();
+            match qname(&iri, config).unwrap() {
+                QName::Declared(pre, _) => {
+                    declared.insert(pre.to_string());
+                }
+                QName::Undeclared(ns, _) => {
+                    if !undeclared.iter().any(|u| u == ns) {
+                        undeclared.push(ns.to_string());
+                    }
+                }
+            }
+        }
+    }
+    let mut prefix_map: Vec<_> = config
+        .prefix_map
+        .iter()
+        .filter(|(pre, _)| declared.contains(pre.as_str()))
+        .cloned()
+        .collect();
+    let mut fresh = (0..)
+        .map(|i| format!("ns{i}"))
+        .filter(|pre| config.prefix_map.iter().all(|(p, _)| p.as_str() != pre));
+    for ns in undeclared {
+        prefix_map.push((
+            Prefix::new_unchecked(fresh.next().unwrap().into()),
+            Iri::new_unchecked(ns.into()),
+        ));
+    }
+    config.clone().with_own_prefix_map(prefix_map)
+}
+
+/// Whether `triple` is an `rdf:type` arc whose object can be used as a node element name.
+fn is_typed_node([_, p, o]: &[SimpleTerm; 3], config: &RdfXmlConfig) -> bool {
+    rdf::type_ == p && o.is_iri() && qname(o.iri().unwrap().as_str(), config).is_some()
+}
+
+struct Prettifier<'a, W> {
+    write: W,
+    config: &'a RdfXmlConfig,
+    fresh_prefix: String,
+    /// The triples of each subject, sorted by predicate
+    descriptions: BTreeMap<&'a SimpleTerm<'a>, &'a [[SimpleTerm<'a>; 3]]>,
+    /// The blank nodes to be nested in the property element referring to them
+    nested: BTreeSet<&'a SimpleTerm<'a>>,
+    /// The nested blank nodes having exactly one `rdf:first` and one `rdf:rest`,
+    /// associated with their item and rest
+    list_nodes: BTreeMap<&'a SimpleTerm<'a>, [&'a SimpleTerm<'a>; 2]>,
+    /// The list nodes from which `rdf:nil` can be reached through list nodes only
+    collections: BTreeSet<&'a SimpleTerm<'a>>,
+}
+
+impl<'a, W: Write> Prettifier<'a, W> {
+    /// Pre-condition: `triples` is sorted, and only contains standard RDF triples
+    /// whose predicate can be written as a property element.
+    fn new(triples: &'a [[SimpleTerm<'a>; 3]], write: W, config: &'a RdfXmlConfig) -> Self {
+        let descriptions = triples
+            .chunk_by(|t1, t2| t1[0] == t2[0])
+            .map(|chunk| (&chunk[0][0], chunk))
+            .collect();
+        let nested = build_nested(&descriptions);
+        let list_nodes = build_list_nodes(&descriptions, &nested);
+        let collections = build_collections(&list_nodes);
+        Self {
+            write,
+            config,
+            fresh_prefix: fresh_prefix(config),
+            descriptions,
+            nested,
+            list_nodes,
+            collections,
+        }
+    }
+
+    fn write_all(&mut self) -> io::Result<()> {
+        write_root_start(&mut self.write, self.config)?;
+        let roots: Vec<_> = self
+            .descriptions
+            .keys()
+            .copied()
+            .filter(|s| !self.nested.contains(s))
+            .collect();
+        for s in roots {
+            self.write_node(s, 1)?;
+        }
+        write_root_end(&mut self.write, self.config)
+    }
+
+    /// Write the node element describing `subject`, at the given indentation `level`.
+    fn write_node(&mut self, subject: &'a SimpleTerm<'a>, level: usize) -> io::Result<()> {
+        let triples = self.triples_of(subject);
+        let type_index = triples.iter().position(|t| self.is_typed_node(t));
+        newline(&mut self.write, self.config, level)?;
+        let name = match type_index {
+            Some(i) => {
+                let tiri = triples[i][2].iri().unwrap();
+                let name = qname(&tiri, self.config).unwrap();
+                write_element_start(&mut self.write, &name, &self.fresh_prefix)?
+            }
+            None => {
+                self.write.write_all(b"<rdf:Description")?;
+                "rdf:Description".to_string()
+            }
+        };
+        if !self.nested.contains(subject) {
+            write_node_attribute(&mut self.write, "rdf:about", subject, self.config)?;
+        }
+        if triples.len() == usize::from(type_index.is_some()) {
+            return self.write.write_all(b"/>");
+        }
+        self.write.write_all(b">")?;
+        for (i, t) in triples.iter().enumerate() {
+            if Some(i) != type_index {
+                self.write_property(t, level + 1)?;
+            }
+        }
+        newline(&mut self.write, self.config, level)?;
+        write!(self.write, "</{name}>")
+    }
+
+    /// Write the property element representing `triple`, at the given indentation `level`.
+    fn write_property(
+        &mut self,
+        [_, p, o]: &'a [SimpleTerm<'a>; 3],
+        level: usize,
+    ) -> io::Result<()> {
+        let piri = p.iri().unwrap();
+        let pname = qname(&piri, self.config).unwrap();
+        newline(&mut self.write, self.config, level)?;
+        let pname = write_element_start(&mut self.write, &pname, &self.fresh_prefix)?;
+        match o.kind() {
+            TermKind::Literal => write_literal_content(&mut self.write, o, &pname, self.config),
+            TermKind::BlankNode if self.collections.contains(o) => {
+                self.write.write_all(br#" rdf:parseType="Collection">"#)?;
+                let mut node = o;
+                while let Some([item, rest]) = self.list_nodes.get(node).copied() {
+                    self.write_item(item, level + 1)?;
+                    node = rest;
+                }
+                newline(&mut self.write, self.config, level)?;
+                write!(self.write, "</{pname}>")
+            }
+            TermKind::BlankNode if self.nested.contains(o) => {
+                let triples = self.triples_of(o);
+                if triples.iter().any(|t| self.is_typed_node(t)) {
+                    self.write.write_all(b">")?;
+                    self.write_node(o, level + 1)?;
+                    newline(&mut self.write, self.config, level)?;
+                    write!(self.write, "</{pname}>")
+                } else if triples.is_empty() {
+                    self.write.write_all(br#" rdf:parseType="Resource"/>"#)
+                } else {
+                    self.write.write_all(br#" rdf:parseType="Resource">"#)?;
+                    for t in triples {
+                        self.write_property(t, level + 1)?;
+                    }
+                    newline(&mut self.write, self.config, level)?;
+                    write!(self.write, "</{pname}>")
+                }
+            }
+            _ => {
+                write_node_attribute(&mut self.write, "rdf:resource", o, self.config)?;
+                self.write.write_all(b"/>")
+            }
+        }
+    }
+
+    /// Write `item` as a member of an `rdf:parseType="Collection"`.
+    fn write_item(&mut self, item: &'a SimpleTerm<'a>, level: usize) -> io::Result<()> {
+        if self.nested.contains(item) {
+            self.write_node(item, level)
+        } else {
+            newline(&mut self.write, self.config, level)?;
+            self.write.write_all(b"<rdf:Description")?;
+            write_node_attribute(&mut self.write, "rdf:about", item, self.config)?;
+            self.write.write_all(b"/>")
+        }
+    }
+
+    fn triples_of(&self, subject: &SimpleTerm) -> &'a [[SimpleTerm<'a>; 3]] {
+        self.descriptions.get(subject).copied().unwrap_or(&[])
+    }
+
+    fn is_typed_node(&self, triple: &[SimpleTerm; 3]) -> bool {
+        is_typed_node(triple, self.config)
+    }
+}
+
+/// Blank nodes can be nested if
+/// - they are used exactly once as object, and
+/// - they are not involved in a cycle of such blank nodes.
+///
+/// In each cycle, one blank node is arbitrarily excluded,
+/// so that it becomes the root of the others.
+fn build_nested<'a>(
+    descriptions: &BTreeMap<&'a SimpleTerm<'a>, &'a [[SimpleTerm<'a>; 3]]>,
+) -> BTreeSet<&'a SimpleTerm<'a>> {
+    let mut referrers = BTreeMap::new();
+    for triples in descriptions.values() {
+        for [s, _, o] in triples.iter() {
+            if o.is_blank_node() {
+                referrers
+                    .entry(o)
+                    .and_modify(|r: &mut Option<&SimpleTerm>| *r = None)
+                    .or_insert(Some(s));
+            }
+        }
+    }
+    let referrers: BTreeMap<_, _> = referrers
+        .into_iter()
+        .filter_map(|(o, s)| s.map(|s| (o, s)))
+        .collect();
+    let mut nested: BTreeSet<_> = referrers.keys().copied().collect();
+    // nodes known to be reachable from a root
+    let mut settled = BTreeSet::new();
+    for &key in referrers.keys() {
+        let mut path = BTreeSet::from([key]);
+        let mut current = referrers[key];
+        loop {
+            if !nested.contains(current) || settled.contains(current) {
+                break;
+            }
+            if path.contains(current) {
+                // break the cycle
+                nested.remove(current);
+                break;
+            }
+            path.insert(current);
+            current = referrers[current];
+        }
+        settled.extend(path);
+    }
+    nested
+}
+
+/// List nodes are nested blank nodes with exactly two triples:
+/// one `rdf:first` whose object is an IRI or a blank node, and one `rdf:rest`.
+fn build_list_nodes<'a>(
+    descriptions: &BTreeMap<&'a SimpleTerm<'a>, &'a [[SimpleTerm<'a>; 3]]>,
+    nested: &BTreeSet<&'a SimpleTerm<'a>>,
+) -> BTreeMap<&'a SimpleTerm<'a>, [&'a SimpleTerm<'a>; 2]> {
+    nested
+        .iter()
+        .filter_map(|&node| match descriptions.get(node)? {
+            [[_, p1, item], [_, p2, rest]]
+                if rdf::first == p1
+                    && rdf::rest == p2
+                    && matches!(item.kind(), TermKind::Iri | TermKind::BlankNode) =>
+            {
+                Some((node, [item, rest]))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collections are list nodes from which `rdf:nil` can be reached through list nodes only.
+fn build_collections<'a>(
+    list_nodes: &BTreeMap<&'a SimpleTerm<'a>, [&'a SimpleTerm<'a>; 2]>,
+) -> BTreeSet<&'a SimpleTerm<'a>> {
+    let mut collections = BTreeSet::new();
+    let mut invalid = BTreeSet::new();
+    for &head in list_nodes.keys() {
+        let mut path = BTreeSet::new();
+        let mut current = head;
+        let valid = loop {
+            if rdf::nil == current || collections.contains(current) {
+                break true;
+            }
+            if invalid.contains(current) || path.contains(current) {
+                break false;
+            }
+            let Some([_, rest]) = list_nodes.get(current) else {
+                break false;
+            };
+            path.insert(current);
+            current = rest;
+        };
+        if valid {
+            collections.extend(path);
+        } else {
+            invalid.extend(path);
+        }
+    }
+    collections
+}
@@ -0,0 +1,472 @@
+//! Utility code for serializing RDF/XML in streaming mode.
+
+use super::RdfXmlConfig;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::term::{SimpleTerm, Term, TermKind};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_iri::{Iri, IriRef};
+use std::io;
+
+/// Names of the RDF namespace that can not be used as node or property elements.
+const FORBIDDEN_RDF: &[&str] = &[
+    "RDF",
+    "Description",
+    "ID",
+    "about",
+    "parseType",
+    "resource",
+    "nodeID",
+    "datatype",
+    "li",
+    "aboutEach",
+    "aboutEachPrefix",
+    "bagID",
+];
+
+/// Write triples in RDF/XML,
+/// grouping consecutive triples sharing the same subject in a single `rdf:Description`.
+///
+/// The namespaces of the [config](RdfXmlConfig)'s prefix map are declared on the root element,
+/// and its base IRI (if any) is declared with `xml:base` and used to write IRIs relative to it.
+///
+/// NB: non-standard (generalized) RDF triples are silently ignored.
+pub(super) struct StreamingWriter<'a, W> {
+    write: W,
+    config: &'a RdfXmlConfig,
+    /// Prefix to use for namespaces that are not in the prefix map
+    fresh_prefix: String,
+    /// Whether the root element has been opened
+    started: bool,
+    /// The subject of the currently open `rdf:Description`, if any
+    subject: Option<SimpleTerm<'static>>,
+}
+
+impl<'a, W: io::Write> StreamingWriter<'a, W> {
+    pub fn new(write: W, config: &'a RdfXmlConfig) -> Self {
+        StreamingWriter {
+            write,
+            config,
+            fresh_prefix: fresh_prefix(config),
+            started: false,
+            subject: None,
+        }
+    }
+
+    pub fn write_triple<T: Triple>(&mut self, t: T) -> io::Result<()> {
+        let [s, p, o] = t.spo();
+        if !is_standard_triple(&s, &p, &o)? {
+            return Ok(());
+        }
+        let piri = p.iri().unwrap();
+        let Some(pname) = qname(piri.as_str(), self.config) else {
+            return Err(not_a_property(piri.as_str()));
+        };
+        self.start()?;
+        if !self
+            .subject
+            .as_ref()
+            .is_some_and(|cs| Term::eq(cs, s.borrow_term()))
+        {
+            self.end_description()?;
+            newline(&mut self.write, self.config, 1)?;
+            self.write.write_all(b"<rdf:Description")?;
+            write_node_attribute(&mut self.write, "rdf:about", s.borrow_term(), self.config)?;
+            self.write.write_all(b">")?;
+            self.subject = Some(s.into_term());
+        }
+        newline(&mut self.write, self.config, 2)?;
+        let pname = write_element_start(&mut self.write, &pname, &self.fresh_prefix)?;
+        if o.kind() == TermKind::Literal {
+            write_literal_content(&mut self.write, o, &pname, self.config)
+        } else {
+            write_node_attribute(&mut self.write, "rdf:resource", o, self.config)?;
+            self.write.write_all(b"/>")
+        }
+    }
+
+    /// Close the current `rdf:Description` and the root element.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.end_description()?;
+        write_root_end(&mut self.write, self.config)
+    }
+
+    /// Write the XML declaration and open the root element, if not done yet.
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            write_root_start(&mut self.write, self.config)?;
+        }
+        Ok(())
+    }
+
+    fn end_description(&mut self) -> io::Result<()> {
+        if self.subject.take().is_some() {
+            newline(&mut self.write, self.config, 1)?;
+            self.write.write_all(b"</rdf:Description>")?;
+        }
+        Ok(())
+    }
+}
+
+/// Check whether `[s, p, o]` is a standard RDF triple,
+/// and fail if its object is a triple term (which RDF/XML does not support).
+pub(super) fn is_standard_triple<T: Term>(s: &T, p: &T, o: &T) -> io::Result<bool> {
+    if o.kind() == TermKind::Triple {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "RDF/XML only supports named, blank or literal object",
+        ));
+    }
+    Ok(matches!(s.kind(), TermKind::Iri | TermKind::BlankNode)
+        && p.kind() == TermKind::Iri
+        && o.kind() != TermKind::Variable)
+}
+
+/// The error raised for predicates that can not be written as a property element.
+pub(super) fn not_a_property(iri: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("<{iri}> can not be used as an RDF/XML property"),
+    )
+}
+
+/// The name of an XML element representing an IRI.
+pub(super) enum QName<'a> {
+    /// A prefix declared on the root element, and a local name
+    Declared(&'a str, &'a str),
+    /// A namespace that must be declared on the element itself, and a local name
+    Undeclared(&'a str, &'a str),
+}
+
+/// Compute the [`QName`] representing `iri` as an XML element, if any,
+/// using the prefix map of `config` when possible.
+pub(super) fn qname<'a>(iri: &'a str, config: &'a RdfXmlConfig) -> Option<QName<'a>> {
+    if let Some(local) = iri.strip_prefix(rdf::PREFIX.as_str()) {
+        return (is_ncname(local) && !FORBIDDEN_RDF.contains(&local))
+            .then_some(QName::Declared("rdf", local));
+    }
+    let declared = declared_namespaces(config)
+        .filter_map(|(pre, ns)| {
+            let local = iri.strip_prefix(ns)?;
+            is_ncname(local).then_some((pre, ns, local))
+        })
+        .max_by_key(|(_, ns, _)| ns.len());
+    if let Some((pre, _, local)) = declared {
+        return Some(QName::Declared(pre, local));
+    }
+    split_iri(iri).map(|(ns, local)| QName::Undeclared(ns, local))
+}
+
+/// Iterate over the prefix/namespace pairs of the prefix map of `config`
+/// that can be declared on the root element
+/// (excluding `rdf`, which is always declared, and the reserved `xml` prefixes).
+pub(super) fn declared_namespaces(config: &RdfXmlConfig) -> impl Iterator<Item = (&str, &str)> {
+    config
+        .prefix_map
+        .iter()
+        .map(|(pre, ns)| (pre.as_str(), ns.as_str()))
+        .filter(|(pre, _)| *pre != "rdf" && !pre.to_ascii_lowercase().starts_with("xml"))
+}
+
+/// Return a prefix that is not declared in the prefix map of `config`,
+/// to be used for undeclared namespaces.
+pub(super) fn fresh_prefix(config: &RdfXmlConfig) -> String {
+    (0..)
+        .map(|i| format!("ns{i}"))
+        .find(|pre| config.prefix_map.iter().all(|(p, _)| p.as_str() != pre))
+        .unwrap()
+}
+
+/// Write the opening tag of an element named `name` (without closing it),
+/// and return its qualified name (to be used in the closing tag).
+pub(super) fn write_element_start<W: io::Write>(
+    mut write: W,
+    name: &QName,
+    fresh_prefix: &str,
+) -> io::Result<String> {
+    match name {
+        QName::Declared("", local) => {
+            write!(write, "<{local}")?;
+            Ok(local.to_string())
+        }
+        QName::Declared(pre, local) => {
+            write!(write, "<{pre}:{local}")?;
+            Ok(format!("{pre}:{local}"))
+        }
+        QName::Undeclared(ns, local) => {
+            write!(write, "<{fresh_prefix}:{local} xmlns:{fresh_prefix}=\"")?;
+            write_escaped(&mut write, ns, true)?;
+            write.write_all(b"\"")?;
+            Ok(format!("{fresh_prefix}:{local}"))
+        }
+    }
+}
+
+/// Write the XML declaration and the opening tag of the root element.
+pub(super) fn write_root_start<W: io::Write>(
+    mut write: W,
+    config: &RdfXmlConfig,
+) -> io::Result<()> {
+    write.write_all(br#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    newline(&mut write, config, 0)?;
+    write!(write, "<rdf:RDF xmlns:rdf=\"{}\"", rdf::PREFIX.as_str())?;
+    for (pre, ns) in declared_namespaces(config) {
+        if pre.is_empty() {
+            write.write_all(b" xmlns=\"")?;
+        } else {
+            write!(write, " xmlns:{pre}=\"")?;
+        }
+        write_escaped(&mut write, ns, true)?;
+        write.write_all(b"\"")?;
+    }
+    if let Some(base) = &config.base {
+        write.write_all(b" xml:base=\"")?;
+        write_escaped(&mut write, base.as_str(), true)?;
+        write.write_all(b"\"")?;
+    }
+    write.write_all(b">")
+}
+
+/// Write the closing tag of the root element.
+pub(super) fn write_root_end<W: io::Write>(mut write: W, config: &RdfXmlConfig) -> io::Result<()> {
+    newline(&mut write, config, 0)?;
+    write.write_all(b"</rdf:RDF>")?;
+    if config.indentation > 0 {
+        write.write_all(b"\n")?;
+    }
+    write.flush()
+}
+
+/// Write a line break followed by `level` indentations,
+/// unless indentation is disabled in `config`.
+pub(super) fn newline<W: io::Write>(
+    mut write: W,
+    config: &RdfXmlConfig,
+    level: usize,
+) -> io::Result<()> {
+    if config.indentation > 0 {
+        write.write_all(b"\n")?;
+        write.write_all(" ".repeat(level * config.indentation).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write the attributes and content of a property element whose object is literal `lit`,
+/// then close the element (named `name`).
+pub(super) fn write_literal_content<W: io::Write, T: Term>(
+    mut write: W,
+    lit: T,
+    name: &str,
+    config: &RdfXmlConfig,
+) -> io::Result<()> {
+    if let Some(tag) = lit.language_tag() {
+        write.write_all(b" xml:lang=\"")?;
+        write_escaped(&mut write, tag.as_str(), true)?;
+        write.write_all(b"\"")?;
+    } else {
+        let datatype = lit.datatype().unwrap();
+        if xsd::string != datatype {
+            write_iri_attribute(&mut write, "rdf:datatype", &datatype, config)?;
+        }
+    }
+    write.write_all(b">")?;
+    write_escaped(&mut write, &lit.lexical_form().unwrap(), false)?;
+    write!(write, "</{name}>")
+}
+
+/// Write `term` (an IRI or a blank node) as an attribute
+/// (`iri_attr` or `rdf:nodeID`, respectively).
+pub(super) fn write_node_attribute<W: io::Write, T: Term>(
+    mut write: W,
+    iri_attr: &str,
+    term: T,
+    config: &RdfXmlConfig,
+) -> io::Result<()> {
+    match term.iri() {
+        Some(iri) => write_iri_attribute(write, iri_attr, &iri, config),
+        None => {
+            write.write_all(b" rdf:nodeID=\"")?;
+            write_escaped(&mut write, term.bnode_id().unwrap().as_str(), true)?;
+            write.write_all(b"\"")
+        }
+    }
+}
+
+/// Write `iri` as the value of attribute `attr`,
+/// relative to the base of `config` if possible.
+pub(super) fn write_iri_attribute<W: io::Write>(
+    mut write: W,
+    attr: &str,
+    iri: &IriRef<MownStr>,
+    config: &RdfXmlConfig,
+) -> io::Result<()> {
+    write!(write, " {attr}=\"")?;
+    let rel = config
+        .base
+        .as_ref()
+        .zip(Iri::new(iri.as_str()).ok())
+        .and_then(|(base, iri)| base.relativize(iri));
+    match rel {
+        Some(rel) => write_escaped(&mut write, rel.as_str(), true)?,
+        None => write_escaped(&mut write, iri.as_str(), true)?,
+    }
+    write.write_all(b"\"")
+}
+
+/// Split `iri` into a namespace and a local name,
+/// such that the local name is a valid [NCName](https://www.w3.org/TR/xml-names/#NT-NCName).
+///
+/// Return `None` if no such split exists.
+fn split_iri(iri: &str) -> Option<(&str, &str)> {
+    let mut start = iri.len();
+    for (i, c) in iri.char_indices().rev() {
+        if !is_name_char(c) {
+            break;
+        }
+        if is_name_start_char(c) {
+            start = i;
+        }
+    }
+    (start > 0 && start < iri.len()).then(|| iri.split_at(start))
+}
+
+/// Whether `txt` is a valid [NCName](https://www.w3.org/TR/xml-names/#NT-NCName).
+fn is_ncname(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+/// Whether `c` is a valid [NameStartChar](https://www.w3.org/TR/xml/#NT-NameStartChar),
+/// excluding `:`
+fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | '_'
+        | 'a'..='z'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}')
+}
+
+/// Whether `c` is a valid [NameChar](https://www.w3.org/TR/xml/#NT-NameChar),
+/// excluding `:`
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || matches!(c,
+            '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// Write `txt` escaping XML special characters
+/// (including quotes and whitespaces other than space, if `attribute` is true).
+fn write_escaped<W: io::Write>(mut write: W, txt: &str, attribute: bool) -> io::Result<()> {
+    let mut last = 0;
+    for (i, c) in txt.char_indices() {
+        let escaped: &[u8] = match c {
+            '&' => b"&amp;",
+            '<' => b"&lt;",
+            '>' => b"&gt;",
+            '\r' => b"&#xD;",
+            '"' if attribute => b"&quot;",
+            '\n' if attribute => b"&#xA;",
+            '\t' if attribute => b"&#x9;",
+            _ => continue,
+        };
+        write.write_all(&txt.as_bytes()[last..i])?;
+        write.write_all(escaped)?;
+        last = i + c.len_utf8();
+    }
+    write.write_all(&txt.as_bytes()[last..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_iri() {
+        for (iri, expected) in [
+            (
+                "http://schema.org/Person",
+                Some(("http://schema.org/", "Person")),
+            ),
+            (
+                "http://example.org/ns#a.b-c",
+                Some(("http://example.org/ns#", "a.b-c")),
+            ),
+            (
+                "http://example.org/ns/42abc",
+                Some(("http://example.org/ns/42", "abc")),
+            ),
+            (
+                "http://example.org/ns/a:b",
+                Some(("http://example.org/ns/a:", "b")),
+            ),
+            ("http://schema.org/", None),
+            ("http://example.org/42", None),
+            ("tag:x", Some(("tag:", "x"))),
+        ] {
+            assert_eq!(split_iri(iri), expected, "{iri}");
+        }
+    }
+
+    #[test]
+    fn test_qname() {
+        use sophia_api::prefix::Prefix;
+        let config = RdfXmlConfig::new().with_own_prefix_map(vec![
+            (
+                Prefix::new_unchecked("ex".into()),
+                Iri::new_unchecked("http://example.org/".into()),
+            ),
+            (
+                Prefix::new_unchecked("ns".into()),
+                Iri::new_unchecked("http://example.org/ns/".into()),
+            ),
+        ]);
+        for (iri, expected) in [
+            (
+                "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+                Some((true, "rdf", "type")),
+            ),
+            ("http://www.w3.org/1999/02/22-rdf-syntax-ns#li", None),
+            ("http://example.org/a", Some((true, "ex", "a"))),
+            ("http://example.org/ns/a", Some((true, "ns", "a"))),
+            ("http://example.org/ns/42", None),
+            (
+                "http://example.org/x/a",
+                Some((false, "http://example.org/x/", "a")),
+            ),
+        ] {
+            let got = qname(iri, &config).map(|qn| match qn {
+                QName::Declared(pre, local) => (true, pre, local),
+                QName::Undeclared(ns, local) => (false, ns, local),
+            });
+            assert_eq!(got, expected, "{iri}");
+        }
+        assert_eq!(fresh_prefix(&config), "ns0");
+    }
+
+    #[test]
+    fn test_write_escaped() -> io::Result<()> {
+        for (txt, attribute, expected) in [
+            ("a<b>&c", false, "a&lt;b&gt;&amp;c"),
+            ("\"é\"\n", false, "\"é\"\n"),
+            ("\"é\"\n", true, "&quot;é&quot;&#xA;"),
+        ] {
+            let mut buf = vec![];
+            write_escaped(&mut buf, txt, attribute)?;
+            assert_eq!(std::str::from_utf8(&buf).unwrap(), expected);
+        }
+        Ok(())
+    }
+}